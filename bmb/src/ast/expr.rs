@@ -51,16 +51,43 @@ pub enum Expr {
         body: Box<Spanned<Expr>>,
     },
 
+    /// Statement-form let inside a block: `{ let [mut] name = value; ... }`
+    /// The binding is in scope for the rest of the enclosing block
+    LetStmt {
+        name: String,
+        mutable: bool,
+        ty: Option<Spanned<Type>>,
+        value: Box<Spanned<Expr>>,
+    },
+
     /// Assignment: name = value (v0.5 Phase 2)
     Assign {
         name: String,
         value: Box<Spanned<Expr>>,
     },
 
+    /// Element assignment: `arr[i] = value`, also `grid[i][j] = value`.
+    /// `array` is a variable or an index or field chain rooted at one.
+    IndexAssign {
+        array: Box<Spanned<Expr>>,
+        index: Box<Spanned<Expr>>,
+        value: Box<Spanned<Expr>>,
+    },
+
+    /// Field assignment: `p.x = value`, also `a.b.c = value`.
+    /// `object` is a variable or an index or field chain rooted at one.
+    FieldAssign {
+        object: Box<Spanned<Expr>>,
+        field: Spanned<String>,
+        value: Box<Spanned<Expr>>,
+    },
+
     /// While loop: while cond { body } (v0.5 Phase 2)
     /// v0.37: Optional invariant for verification
     /// Syntax: while cond invariant inv { body }
     While {
+        /// Optional loop label: `'outer: while cond { ... }`
+        label: Option<String>,
         cond: Box<Spanned<Expr>>,
         /// v0.37: Optional loop invariant for SMT verification
         /// The invariant must hold before the loop and be preserved by each iteration
@@ -70,6 +97,8 @@ pub enum Expr {
 
     /// For loop: for var in iter { body } (v0.5 Phase 3)
     For {
+        /// Optional loop label: `'outer: for i in 0..n { ... }`
+        label: Option<String>,
         var: String,
         iter: Box<Spanned<Expr>>,
         body: Box<Spanned<Expr>>,
@@ -80,17 +109,23 @@ pub enum Expr {
     /// Infinite loop: loop { body }
     /// Exit with break, can return a value with `break value`
     Loop {
+        /// Optional loop label: `'outer: loop { ... }`
+        label: Option<String>,
         body: Box<Spanned<Expr>>,
     },
 
-    /// Break from loop: break or break value
-    /// Returns unit or the specified value from the enclosing loop
+    /// Break from loop: break, break value or break 'label
+    /// Returns unit or the specified value from the targeted loop;
+    /// without a label it targets the innermost loop
     Break {
+        label: Option<String>,
         value: Option<Box<Spanned<Expr>>>,
     },
 
-    /// Continue to next iteration: continue
-    Continue,
+    /// Continue to next iteration: continue or continue 'label
+    Continue {
+        label: Option<String>,
+    },
 
     /// Early return: return or return value
     Return {
@@ -126,6 +161,9 @@ pub enum Expr {
     StructInit {
         name: String,
         fields: Vec<(Spanned<String>, Spanned<Expr>)>,
+        /// `..base` at the end: a value of the same struct supplying the
+        /// fields not listed
+        base: Option<Box<Spanned<Expr>>>,
     },
 
     /// Field access: expr.field
@@ -259,6 +297,127 @@ pub enum Expr {
     },
 }
 
+impl Expr {
+    /// The variable an assignment target is rooted at: `a` for `a`,
+    /// `a[i][j]` and `a.b[i].c`
+    pub fn place_root(&self) -> Option<&str> {
+        match self {
+            Expr::Var(name) => Some(name),
+            Expr::Index { expr, .. } | Expr::FieldAccess { expr, .. } => expr.node.place_root(),
+            _ => None,
+        }
+    }
+
+    /// Call `f` on every direct subexpression
+    pub fn for_each_child(&self, f: &mut impl FnMut(&Spanned<Expr>)) {
+        match self {
+            Expr::Assign { value, .. } => f(value),
+            Expr::IndexAssign { array, index, value } => {
+                f(array);
+                f(index);
+                f(value);
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                f(object);
+                f(value);
+            }
+            Expr::If { cond, then_branch, else_branch } => {
+                f(cond);
+                f(then_branch);
+                f(else_branch);
+            }
+            Expr::Let { value, body, .. } => {
+                f(value);
+                f(body);
+            }
+            Expr::While { cond, invariant, body, .. } => {
+                f(cond);
+                if let Some(inv) = invariant {
+                    f(inv);
+                }
+                f(body);
+            }
+            Expr::For { iter, body, .. } => {
+                f(iter);
+                f(body);
+            }
+            Expr::Match { expr: scrutinee, arms } => {
+                f(scrutinee);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        f(guard);
+                    }
+                    f(&arm.body);
+                }
+            }
+            Expr::Binary { left, right, .. } | Expr::Index { expr: left, index: right } => {
+                f(left);
+                f(right);
+            }
+            Expr::Range { start, end, .. } => {
+                f(start);
+                f(end);
+            }
+            Expr::Block(exprs) | Expr::ArrayLit(exprs) | Expr::Tuple(exprs)
+            | Expr::Call { args: exprs, .. } | Expr::EnumVariant { args: exprs, .. } => {
+                exprs.iter().for_each(f);
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                f(receiver);
+                args.iter().for_each(f);
+            }
+            Expr::StructInit { fields, base, .. } => {
+                for (_, value) in fields {
+                    f(value);
+                }
+                if let Some(base) = base {
+                    f(base);
+                }
+            }
+            Expr::Break { value: Some(inner), .. } | Expr::Return { value: Some(inner) }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::FieldAccess { expr: inner, .. }
+            | Expr::TupleField { expr: inner, .. }
+            | Expr::Deref(inner)
+            | Expr::Ref(inner)
+            | Expr::RefMut(inner)
+            | Expr::Cast { expr: inner, .. }
+            | Expr::LetStmt { value: inner, .. }
+            | Expr::Loop { body: inner, .. }
+            | Expr::Closure { body: inner, .. }
+            | Expr::StateRef { expr: inner, .. }
+            | Expr::Forall { body: inner, .. }
+            | Expr::Exists { body: inner, .. } => f(inner),
+            Expr::Break { value: None, .. } | Expr::Return { value: None }
+            | Expr::IntLit(_) | Expr::FloatLit(_) | Expr::BoolLit(_) | Expr::StringLit(_)
+            | Expr::CharLit(_) | Expr::Var(_) | Expr::Ret | Expr::Unit | Expr::It
+            | Expr::Continue { .. } | Expr::Todo { .. } => {}
+        }
+    }
+
+    /// Names of the variables this expression reads, assigns or calls, in
+    /// order of first mention. Names bound inside it are included, so for a
+    /// closure body this is a superset of what it captures.
+    pub fn mentioned_names(&self) -> Vec<String> {
+        fn visit(expr: &Expr, out: &mut Vec<String>) {
+            let name = match expr {
+                Expr::Var(name) | Expr::Assign { name, .. } | Expr::Call { func: name, .. } => Some(name),
+                _ => None,
+            };
+            if let Some(name) = name
+                && !out.contains(name)
+            {
+                out.push(name.clone());
+            }
+            expr.for_each_child(&mut |child| visit(&child.node, out));
+        }
+
+        let mut out = Vec::new();
+        visit(self, &mut out);
+        out
+    }
+}
+
 /// A single arm in a match expression
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchArm {
@@ -88,6 +88,39 @@ pub enum Item {
     TraitDef(TraitDef),
     /// Impl block (v0.20.1): impl Trait for Type { ... }
     ImplBlock(ImplBlock),
+    /// Compile-time constant: const NAME: Type = expr;
+    ConstDef(ConstDef),
+}
+
+impl Item {
+    /// Span of the whole item, including its attributes
+    pub fn span(&self) -> Span {
+        match self {
+            Item::FnDef(f) => f.span,
+            Item::StructDef(s) => s.span,
+            Item::EnumDef(e) => e.span,
+            Item::TypeAlias(t) => t.span,
+            Item::Use(u) => u.span,
+            Item::ExternFn(e) => e.span,
+            Item::TraitDef(t) => t.span,
+            Item::ImplBlock(i) => i.span,
+            Item::ConstDef(c) => c.span,
+        }
+    }
+
+    /// Name the item declares; `None` for `use` and `impl`
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Item::FnDef(f) => Some(&f.name.node),
+            Item::StructDef(s) => Some(&s.name.node),
+            Item::EnumDef(e) => Some(&e.name.node),
+            Item::ExternFn(e) => Some(&e.name.node),
+            Item::TraitDef(t) => Some(&t.name.node),
+            Item::TypeAlias(t) => Some(&t.name.node),
+            Item::ConstDef(c) => Some(&c.name.node),
+            Item::Use(_) | Item::ImplBlock(_) => None,
+        }
+    }
 }
 
 /// Use statement (v0.5 Phase 4)
@@ -95,10 +128,31 @@ pub enum Item {
 pub struct UseStmt {
     /// Path segments (e.g., ["lexer", "Token"] for use lexer::Token)
     pub path: Vec<Spanned<String>>,
+    /// `use math::*`: import every public item of the module
+    #[serde(default)]
+    pub glob: bool,
+    /// `use math::gcd as g`: the name the item is imported under
+    #[serde(default)]
+    pub alias: Option<Spanned<String>>,
     /// Span of the entire use statement
     pub span: Span,
 }
 
+impl UseStmt {
+    /// Source form of the imported path, e.g. `math::*` or `math::gcd as g`
+    pub fn path_text(&self) -> String {
+        let mut text = self.path.iter().map(|s| s.node.as_str()).collect::<Vec<_>>().join("::");
+        if self.glob {
+            text.push_str("::*");
+        }
+        if let Some(alias) = &self.alias {
+            text.push_str(" as ");
+            text.push_str(&alias.node);
+        }
+        text
+    }
+}
+
 /// ABI (Application Binary Interface) specification (v0.20.2)
 /// Used to specify calling conventions for FFI
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -159,14 +213,14 @@ pub struct TraitDef {
     pub name: Spanned<String>,
     /// Type parameters (if any): `trait Container<T> { ... }`
     pub type_params: Vec<TypeParam>,
-    /// Trait method signatures (without bodies)
+    /// Trait method signatures (with optional default bodies)
     pub methods: Vec<TraitMethod>,
     /// Span
     pub span: Span,
 }
 
 /// Trait method signature (v0.20.1)
-/// Method declaration in a trait (without body)
+/// Method declaration in a trait, optionally with a default body
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraitMethod {
     /// Method name
@@ -175,21 +229,25 @@ pub struct TraitMethod {
     pub params: Vec<Param>,
     /// Return type
     pub ret_ty: Spanned<Type>,
+    /// Default body used when an impl block omits the method
+    #[serde(default)]
+    pub body: Option<Spanned<Expr>>,
     /// Span
     pub span: Span,
 }
 
 /// Impl block (v0.20.1)
 /// Syntax: impl Trait for Type { fn method(self) -> Type = body; }
+/// Inherent impl: impl Type { fn method(self) -> Type = body; }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImplBlock {
     /// Attributes
     pub attributes: Vec<Attribute>,
     /// Type parameters (if any): `impl<T> Trait for Container<T>`
     pub type_params: Vec<TypeParam>,
-    /// Trait being implemented
-    pub trait_name: Spanned<String>,
-    /// Target type (the type implementing the trait)
+    /// Trait being implemented; `None` for an inherent impl (`impl Point { ... }`)
+    pub trait_name: Option<Spanned<String>>,
+    /// Target type (the type implementing the trait or owning the methods)
     pub target_type: Spanned<Type>,
     /// Method implementations
     pub methods: Vec<FnDef>,
@@ -215,6 +273,9 @@ pub struct StructDef {
 pub struct StructField {
     pub name: Spanned<String>,
     pub ty: Spanned<Type>,
+    /// `x: i64 = 0`: value used when a struct literal omits the field
+    #[serde(default)]
+    pub default: Option<Spanned<Expr>>,
 }
 
 /// Enum definition
@@ -261,6 +322,25 @@ pub struct TypeAliasDef {
     pub span: Span,
 }
 
+/// Compile-time constant
+/// Syntax: `const MAX_SIZE: i64 = 1024;`
+/// The initializer may only use literals, other constants and operators on them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstDef {
+    /// Attributes (e.g., `@deprecated`)
+    pub attributes: Vec<Attribute>,
+    /// Visibility (pub or private)
+    pub visibility: Visibility,
+    /// Name of the constant
+    pub name: Spanned<String>,
+    /// Declared type
+    pub ty: Spanned<Type>,
+    /// Initializer expression
+    pub value: Spanned<Expr>,
+    /// Span of the entire definition
+    pub span: Span,
+}
+
 /// Named contract (v0.2)
 /// A contract with an optional name for better error messages
 /// e.g., `sorted_input: forall(i in 0..<len(arr)-1): arr[i] <= arr[i+1]`
@@ -362,4 +442,9 @@ impl Attribute {
     pub fn is_trust(&self) -> bool {
         self.name() == "trust"
     }
+
+    /// Check if this is an @inline attribute
+    pub fn is_inline(&self) -> bool {
+        self.name() == "inline"
+    }
 }
@@ -5,7 +5,7 @@
 use super::expr::{BinOp, Expr, LiteralPattern, Pattern, RangeKind, StateKind, UnOp};
 use super::types::Type;
 use super::{
-    EnumDef, ExternFn, FnDef, ImplBlock, Item, Program, StructDef, TraitDef, TypeAliasDef, UseStmt, Visibility,
+    ConstDef, EnumDef, ExternFn, FnDef, ImplBlock, Item, Program, StructDef, TraitDef, TypeAliasDef, UseStmt, Visibility,
 };
 
 /// Format AST as S-expression (Lisp-like notation)
@@ -34,6 +34,7 @@ fn format_item(item: &Item, level: usize) -> String {
         Item::ImplBlock(i) => format_impl_block(i, level),
         // v0.50.6: Type alias
         Item::TypeAlias(t) => format_type_alias(t, level),
+        Item::ConstDef(c) => format_const_def(c, level),
     }
 }
 
@@ -114,7 +115,10 @@ fn format_struct_def(s: &StructDef, level: usize) -> String {
     out.push_str(
         &s.fields
             .iter()
-            .map(|f| format!("({} {})", f.name.node, format_type(&f.ty.node)))
+            .map(|f| match &f.default {
+                Some(default) => format!("({} {} {})", f.name.node, format_type(&f.ty.node), format_expr(&default.node)),
+                None => format!("({} {})", f.name.node, format_type(&f.ty.node)),
+            })
             .collect::<Vec<_>>()
             .join(" "),
     );
@@ -187,13 +191,7 @@ fn format_extern_fn(e: &ExternFn, level: usize) -> String {
 
 fn format_use_stmt(u: &UseStmt, level: usize) -> String {
     let ind = indent(level);
-    let path_str = u
-        .path
-        .iter()
-        .map(|s| s.node.as_str())
-        .collect::<Vec<_>>()
-        .join("::");
-    format!("{}(use {})\n", ind, path_str)
+    format!("{}(use {})\n", ind, u.path_text())
 }
 
 // v0.20.1: Trait definition formatting
@@ -209,7 +207,13 @@ fn format_trait_def(t: &TraitDef, level: usize) -> String {
                 .map(|p| format!("({} {})", p.name.node, format_type(&p.ty.node)))
                 .collect::<Vec<_>>()
                 .join(" ");
-            format!("{}  (fn {} ({}) -> {})", ind, m.name.node, params, format_type(&m.ret_ty.node))
+            match &m.body {
+                Some(body) => format!(
+                    "{}  (fn {} ({}) -> {} {})",
+                    ind, m.name.node, params, format_type(&m.ret_ty.node), format_expr(&body.node)
+                ),
+                None => format!("{}  (fn {} ({}) -> {})", ind, m.name.node, params, format_type(&m.ret_ty.node)),
+            }
         })
         .collect::<Vec<_>>()
         .join("\n");
@@ -228,17 +232,25 @@ fn format_impl_block(i: &ImplBlock, level: usize) -> String {
         .map(|m| format_fn_def(m, level + 1))
         .collect::<Vec<_>>()
         .join("");
+    let head = match &i.trait_name {
+        Some(trait_name) => format!("{} for {}", trait_name.node, format_type(&i.target_type.node)),
+        None => format_type(&i.target_type.node),
+    };
+    format!("{}(impl {}\n{}{})\n", ind, head, methods, ind)
+}
+
+// v0.50.6: Type alias formatting
+fn format_const_def(c: &ConstDef, level: usize) -> String {
     format!(
-        "{}(impl {} for {}\n{}{})\n",
-        ind,
-        i.trait_name.node,
-        format_type(&i.target_type.node),
-        methods,
-        ind
+        "{}(const {} :{} {} {})\n",
+        indent(level),
+        c.name.node,
+        format_visibility(&c.visibility),
+        format_type(&c.ty.node),
+        format_expr(&c.value.node)
     )
 }
 
-// v0.50.6: Type alias formatting
 fn format_type_alias(t: &TypeAliasDef, level: usize) -> String {
     let ind = indent(level);
     let mut out = format!("{}(type {} ", ind, t.name.node);
@@ -297,6 +309,7 @@ pub fn format_type(ty: &Type) -> String {
         Type::Struct { name, .. } => name.clone(),
         Type::Enum { name, .. } => name.clone(),
         Type::Array(inner, size) => format!("[{} {}]", format_type(inner), size),
+        Type::ArrayConst(inner, name) => format!("[{} {}]", format_type(inner), name),
         Type::Ref(inner) => format!("(&{})", format_type(inner)),
         Type::RefMut(inner) => format!("(&mut {})", format_type(inner)),
         Type::Refined { base, constraints } => {
@@ -328,6 +341,11 @@ pub fn format_type(ty: &Type) -> String {
     }
 }
 
+/// Loop label as an extra S-expression atom: ` 'outer`, or nothing
+fn format_label(label: &Option<String>) -> String {
+    label.as_ref().map(|l| format!(" '{l}")).unwrap_or_default()
+}
+
 /// v0.84: Format expression as S-expression (span-agnostic)
 /// Used for semantic duplication detection
 pub fn format_expr(expr: &Expr) -> String {
@@ -391,30 +409,60 @@ pub fn format_expr(expr: &Expr) -> String {
             )
         }
 
+        Expr::LetStmt {
+            name,
+            mutable,
+            ty,
+            value,
+        } => {
+            let mut_str = if *mutable { "mut " } else { "" };
+            let ty_str = ty
+                .as_ref()
+                .map(|t| format!(" : {}", format_type(&t.node)))
+                .unwrap_or_default();
+            format!("(let {}{}{} {})", mut_str, name, ty_str, format_expr(&value.node))
+        }
+
         Expr::Assign { name, value } => {
             format!("(set! {} {})", name, format_expr(&value.node))
         }
 
+        Expr::IndexAssign { array, index, value } => {
+            format!(
+                "(index-set! {} {} {})",
+                format_expr(&array.node),
+                format_expr(&index.node),
+                format_expr(&value.node)
+            )
+        }
+
+        Expr::FieldAssign { object, field, value } => {
+            format!("(field-set! {} {} {})", format_expr(&object.node), field.node, format_expr(&value.node))
+        }
+
         // v0.37: Include invariant if present
-        Expr::While { cond, invariant, body } => {
+        Expr::While { label, cond, invariant, body } => {
             match invariant {
                 Some(inv) => format!(
-                    "(while {} :invariant {} {})",
+                    "(while{} {} :invariant {} {})",
+                    format_label(label),
                     format_expr(&cond.node),
                     format_expr(&inv.node),
                     format_expr(&body.node)
                 ),
                 None => format!(
-                    "(while {} {})",
+                    "(while{} {} {})",
+                    format_label(label),
                     format_expr(&cond.node),
                     format_expr(&body.node)
                 ),
             }
         }
 
-        Expr::For { var, iter, body } => {
+        Expr::For { label, var, iter, body } => {
             format!(
-                "(for {} {} {})",
+                "(for{} {} {} {})",
+                format_label(label),
                 var,
                 format_expr(&iter.node),
                 format_expr(&body.node)
@@ -460,13 +508,15 @@ pub fn format_expr(expr: &Expr) -> String {
             }
         }
 
-        Expr::StructInit { name, fields } => {
-            let fs = fields
+        Expr::StructInit { name, fields, base } => {
+            let mut fs = fields
                 .iter()
                 .map(|(n, v)| format!("({} {})", n.node, format_expr(&v.node)))
-                .collect::<Vec<_>>()
-                .join(" ");
-            format!("(new {} {})", name, fs)
+                .collect::<Vec<_>>();
+            if let Some(base) = base {
+                fs.push(format!("(.. {})", format_expr(&base.node)));
+            }
+            format!("(new {} {})", name, fs.join(" "))
         }
 
         Expr::FieldAccess { expr, field } => {
@@ -595,12 +645,12 @@ pub fn format_expr(expr: &Expr) -> String {
         }
 
         // v0.36: Additional control flow
-        Expr::Loop { body } => format!("(loop {})", format_expr(&body.node)),
-        Expr::Break { value } => match value {
-            Some(v) => format!("(break {})", format_expr(&v.node)),
-            None => "(break)".to_string(),
+        Expr::Loop { label, body } => format!("(loop{} {})", format_label(label), format_expr(&body.node)),
+        Expr::Break { label, value } => match value {
+            Some(v) => format!("(break{} {})", format_label(label), format_expr(&v.node)),
+            None => format!("(break{})", format_label(label)),
         },
-        Expr::Continue => "(continue)".to_string(),
+        Expr::Continue { label } => format!("(continue{})", format_label(label)),
         Expr::Return { value } => match value {
             Some(v) => format!("(return {})", format_expr(&v.node)),
             None => "(return)".to_string(),
@@ -3,7 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 /// A span in the source code
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -20,6 +20,99 @@ impl Span {
             end: self.end.max(other.end),
         }
     }
+
+    /// Line and column of the start and (exclusive) end
+    pub fn resolve(&self, index: &LineIndex) -> (LineCol, LineCol) {
+        (index.line_col(self.start), index.line_col(self.end))
+    }
+}
+
+/// 1-based line and column. Columns count characters, so a tab or a
+/// multi-byte character is one column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Line starts and multi-byte characters of one file, built once so that
+/// each offset resolves to a [`LineCol`] in O(log n)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineIndex {
+    len: usize,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// Start offset and UTF-8 length of each non-ASCII character
+    wide_chars: Vec<(usize, usize)>,
+    /// `extra_bytes[i]`: bytes beyond one per character in `wide_chars[..i]`
+    extra_bytes: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = Vec::new();
+        let mut extra_bytes = vec![0];
+        for (offset, c) in source.char_indices() {
+            if c == '\n' {
+                line_starts.push(offset + 1);
+            } else if !c.is_ascii() {
+                wide_chars.push((offset, c.len_utf8()));
+                extra_bytes.push(extra_bytes.last().unwrap() + c.len_utf8() - 1);
+            }
+        }
+        Self { len: source.len(), line_starts, wide_chars, extra_bytes }
+    }
+
+    /// Number of lines; a trailing newline starts an empty last line
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte range of a 1-based line, without its line break
+    pub fn line_span(&self, line: usize) -> Span {
+        let Some(&start) = self.line_starts.get(line.saturating_sub(1)) else {
+            return Span::new(self.len, self.len);
+        };
+        let end = self.line_starts.get(line).map_or(self.len, |&next| next - 1);
+        Span::new(start, end)
+    }
+
+    /// Line and column of a byte offset. An offset inside a character
+    /// resolves to that character, one past the end to the end.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let mut offset = offset.min(self.len);
+        let wide = self.wide_chars.partition_point(|&(start, _)| start < offset);
+        if let Some(&(start, len)) = wide.checked_sub(1).map(|i| &self.wide_chars[i])
+            && offset < start + len
+        {
+            offset = start;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let wide = self.wide_chars.partition_point(|&(start, _)| start < offset);
+        let wide_before_line = self.wide_chars.partition_point(|&(start, _)| start < line_start);
+        let extra = self.extra_bytes[wide] - self.extra_bytes[wide_before_line];
+        LineCol { line, col: offset - line_start - extra + 1 }
+    }
+
+    /// Byte offset of a line and column; a column past the end of its line
+    /// is the end of that line, a line past the end is the end of the file
+    pub fn offset(&self, position: LineCol) -> usize {
+        if position.line > self.line_count() {
+            return self.len;
+        }
+        let Span { start: line_start, end: line_end } = self.line_span(position.line);
+        let mut offset = line_start + position.col.saturating_sub(1);
+        let first = self.wide_chars.partition_point(|&(start, _)| start < line_start);
+        for &(start, len) in &self.wide_chars[first..] {
+            if start >= offset || start >= line_end {
+                break;
+            }
+            offset += len - 1;
+        }
+        offset.min(line_end)
+    }
 }
 
 impl From<Span> for std::ops::Range<usize> {
@@ -53,3 +146,34 @@ impl<T> Spanned<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index_counts_characters() {
+        let source = "let s = \"東京\";\n\tlet t = s;\r\nend";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 3);
+
+        let semi = source.find(';').unwrap();
+        assert_eq!(index.line_col(semi), LineCol { line: 1, col: 13 });
+        // Inside the second CJK character
+        let kyo = source.find('京').unwrap();
+        assert_eq!(index.line_col(kyo + 1), LineCol { line: 1, col: 11 });
+        let t = source.find('t').unwrap();
+        assert_eq!(index.line_col(source.find("t =").unwrap()), LineCol { line: 2, col: 6 });
+        assert_eq!(index.line_col(t), LineCol { line: 1, col: 3 });
+        assert_eq!(index.line_col(source.len() + 5), LineCol { line: 3, col: 4 });
+
+        for offset in [0, kyo, semi, source.find("end").unwrap(), source.len()] {
+            assert_eq!(index.offset(index.line_col(offset)), offset);
+        }
+        assert_eq!(index.offset(LineCol { line: 1, col: 99 }), source.find('\n').unwrap());
+        assert_eq!(index.offset(LineCol { line: 9, col: 1 }), source.len());
+
+        let span = Span::new(source.find('"').unwrap(), semi);
+        assert_eq!(span.resolve(&index), (LineCol { line: 1, col: 9 }, LineCol { line: 1, col: 13 }));
+    }
+}
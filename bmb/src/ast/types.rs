@@ -82,6 +82,9 @@ pub enum Type {
     RefMut(Box<Type>),
     /// Fixed-size array type (v0.5 Phase 6): [T; N]
     Array(Box<Type>, usize),
+    /// Fixed-size array whose length is a named constant: [T; MAX_SIZE].
+    /// The type checker resolves it to `Array` with the constant's value.
+    ArrayConst(Box<Type>, String),
     /// Inline refinement type (v0.2): T{constraints}
     /// e.g., i64{!= 0}, i64{>= lo, <= hi}
     /// The constraints are expressions relative to the refined value
@@ -141,6 +144,7 @@ impl PartialEq for Type {
             (Type::Ref(a), Type::Ref(b)) => a == b,
             (Type::RefMut(a), Type::RefMut(b)) => a == b,
             (Type::Array(t1, s1), Type::Array(t2, s2)) => t1 == t2 && s1 == s2,
+            (Type::ArrayConst(t1, n1), Type::ArrayConst(t2, n2)) => t1 == t2 && n1 == n2,
             // Refined types are equal if base types are equal
             // (constraints are semantic, not structural)
             (Type::Refined { base: b1, .. }, Type::Refined { base: b2, .. }) => b1 == b2,
@@ -233,6 +237,7 @@ impl std::fmt::Display for Type {
             Type::Ref(inner) => write!(f, "&{inner}"),
             Type::RefMut(inner) => write!(f, "&mut {inner}"),
             Type::Array(elem, size) => write!(f, "[{elem}; {size}]"),
+            Type::ArrayConst(elem, name) => write!(f, "[{elem}; {name}]"),
             Type::Refined { base, constraints } => {
                 write!(f, "{}{{", base)?;
                 for (i, _) in constraints.iter().enumerate() {
@@ -16,9 +16,11 @@ use crate::cfg::{CfgEvaluator, Target};
 use crate::codegen::CodeGenError;
 #[cfg(feature = "llvm")]
 use crate::codegen::CodeGen;
-use crate::mir::lower_program;
+use crate::mir::{lower_program_with_contracts, lower_program_with_debug_info, ContractMode};
 use crate::parser::parse;
 use crate::lexer::tokenize;
+use crate::project::ProjectError;
+use crate::resolver::Resolver;
 use crate::types::TypeChecker;
 
 /// Build configuration
@@ -41,6 +43,10 @@ pub struct BuildConfig {
     /// Target triple for cross-compilation (v0.50.23)
     /// e.g., "x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "aarch64-apple-darwin"
     pub target_triple: Option<String>,
+    /// Runtime checking of pre/post conditions (off by default)
+    pub contracts: ContractMode,
+    /// Emit DWARF debug info mapping machine code back to source lines
+    pub debug_info: bool,
 }
 
 impl BuildConfig {
@@ -56,6 +62,8 @@ impl BuildConfig {
             verbose: false,
             target: Target::Native,
             target_triple: None,
+            contracts: ContractMode::Off,
+            debug_info: false,
         }
     }
 
@@ -71,6 +79,18 @@ impl BuildConfig {
         self
     }
 
+    /// Set how contracts are treated in the generated code
+    pub fn contracts(mut self, mode: ContractMode) -> Self {
+        self.contracts = mode;
+        self
+    }
+
+    /// Set whether debug info is emitted
+    pub fn debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
     /// Set output path
     pub fn output(mut self, path: PathBuf) -> Self {
         self.output = path;
@@ -134,11 +154,22 @@ pub enum BuildError {
     #[error("Type error: {0}")]
     Type(String),
 
+    /// Lexer, parser or type error, kept intact for structured reporting
+    #[error("{0}")]
+    Compile(#[from] crate::error::CompileError),
+
     #[error("Code generation error: {0}")]
     CodeGen(#[from] CodeGenError),
 
+    #[error("Project error: {0}")]
+    Project(#[from] ProjectError),
+
     #[error("Linker error: {0}")]
     Linker(String),
+
+    /// `--target` names a triple LLVM would not accept
+    #[error("{0}")]
+    InvalidTarget(String),
 }
 
 /// Build result
@@ -146,6 +177,10 @@ pub type BuildResult<T> = Result<T, BuildError>;
 
 /// Build a BMB program
 pub fn build(config: &BuildConfig) -> BuildResult<()> {
+    if let Some(triple) = &config.target_triple {
+        crate::codegen::check_target_triple(triple).map_err(|e| BuildError::InvalidTarget(e.to_string()))?;
+    }
+
     // Read source
     let source = std::fs::read_to_string(&config.input)?;
     let filename = config.input.display().to_string();
@@ -155,11 +190,10 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
     }
 
     // Tokenize
-    let tokens = tokenize(&source).map_err(|e| BuildError::Parse(e.message().to_string()))?;
+    let tokens = tokenize(&source)?;
 
     // Parse
-    let program = parse(&filename, &source, tokens)
-        .map_err(|e| BuildError::Parse(e.message().to_string()))?;
+    let program = parse(&filename, &source, tokens)?;
 
     if config.verbose {
         println!("  Parsed {} items", program.items.len());
@@ -174,18 +208,35 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
                  program.items.len(), config.target.as_str());
     }
 
+    // Resolve `use` statements against the input's directory and its project
+    let mut resolver = Resolver::for_file(&config.input)?;
+    let mut imports = resolver.resolve_uses(&program)?;
+
     // Type check
     let mut type_checker = TypeChecker::new();
-    type_checker
-        .check_program(&program)
-        .map_err(|e| BuildError::Type(format!("{:?}", e)))?;
+    type_checker.register_imports(&resolver, &imports);
+    type_checker.check_program_with_imports(&program, &mut imports)?;
 
     if config.verbose {
         println!("  Type check passed");
     }
 
+    // Compile imported modules into the same unit
+    let program = if resolver.module_count() > 0 {
+        if config.verbose {
+            println!("  Linked {} imported module(s)", resolver.module_count());
+        }
+        cfg_eval.filter_program(&resolver.link(&program))
+    } else {
+        program
+    };
+
     // Lower to MIR
-    let mut mir = lower_program(&program);
+    let mut mir = if config.debug_info {
+        lower_program_with_debug_info(&program, config.contracts)
+    } else {
+        lower_program_with_contracts(&program, config.contracts)
+    };
 
     if config.verbose {
         println!("  Generated MIR for {} functions", mir.functions.len());
@@ -222,7 +273,10 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
             OptLevel::Aggressive => CodeGenOptLevel::Aggressive,
         };
 
-        let codegen = CodeGen::with_opt_level(codegen_opt);
+        let mut codegen = CodeGen::with_opt_level(codegen_opt);
+        if let Some(triple) = &config.target_triple {
+            codegen = codegen.with_target_triple(triple);
+        }
 
         if config.emit_ir {
             // Emit LLVM IR
@@ -245,7 +299,7 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
 
         // Link if building executable
         if matches!(config.output_type, OutputType::Executable) {
-            link_executable(&obj_path, &config.output, config.verbose)?;
+            link_executable(&obj_path, &config.output, config.target_triple.as_deref(), config.verbose)?;
         }
 
         Ok(())
@@ -258,11 +312,14 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
 
         // Use text-based LLVM IR generation + clang
         // v0.50.23: Support cross-compilation target triple
-        let codegen = if let Some(ref triple) = config.target_triple {
+        let mut codegen = if let Some(ref triple) = config.target_triple {
             TextCodeGen::with_target(triple)
         } else {
             TextCodeGen::new()
         };
+        if config.debug_info {
+            codegen = codegen.with_debug_info(&config.input, &source);
+        }
         let ir = codegen.generate(&mir).map_err(|_| BuildError::CodeGen(
             CodeGenError::LlvmNotAvailable, // Use existing error type
         ))?;
@@ -302,6 +359,12 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
         };
 
         cmd.args([opt_flag, "-c", ir_path.to_str().unwrap(), "-o", obj_path.to_str().unwrap()]);
+        if config.debug_info {
+            cmd.arg("-g");
+        }
+        // Cross builds compile and link every piece for the target
+        let target_flag = config.target_triple.as_ref().map(|triple| format!("--target={}", triple));
+        cmd.args(&target_flag);
 
         let output_result = cmd.output()?;
         if !output_result.status.success() {
@@ -317,6 +380,7 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
         let runtime_obj = config.output.with_file_name("runtime").with_extension(if cfg!(windows) { "obj" } else { "o" });
         let mut cmd = Command::new(&clang);
         cmd.args(["-c", runtime_path.to_str().unwrap(), "-o", runtime_obj.to_str().unwrap()]);
+        cmd.args(&target_flag);
 
         // Add Windows SDK include paths if on Windows
         #[cfg(target_os = "windows")]
@@ -382,6 +446,7 @@ pub fn build(config: &BuildConfig) -> BuildResult<()> {
                 "-o",
                 config.output.to_str().unwrap(),
             ]);
+            cmd.args(&target_flag);
 
             let output_result = cmd.output()?;
             if !output_result.status.success() {
@@ -471,7 +536,7 @@ fn find_runtime_c() -> Result<std::path::PathBuf, String> {
 
 /// Link object file to executable
 #[cfg(feature = "llvm")]
-fn link_executable(obj_path: &Path, output: &Path, verbose: bool) -> BuildResult<()> {
+fn link_executable(obj_path: &Path, output: &Path, target_triple: Option<&str>, verbose: bool) -> BuildResult<()> {
     // Find the appropriate linker
     let linker = find_linker()?;
 
@@ -498,6 +563,11 @@ fn link_executable(obj_path: &Path, output: &Path, verbose: bool) -> BuildResult
     // Output file
     cmd.args(["-o", output.to_str().unwrap()]);
 
+    // clang links for the target of a cross build
+    if let Some(triple) = target_triple {
+        cmd.arg(format!("--target={}", triple));
+    }
+
     // Platform-specific linker flags
     #[cfg(target_os = "windows")]
     {
@@ -82,6 +82,7 @@ impl CfgEvaluator {
             Item::TraitDef(t) => self.evaluate_attrs(&t.attributes), // v0.20.1
             Item::ImplBlock(i) => self.evaluate_attrs(&i.attributes), // v0.20.1
             Item::TypeAlias(t) => self.evaluate_attrs(&t.attributes), // v0.50.6
+            Item::ConstDef(c) => self.evaluate_attrs(&c.attributes),
         }
     }
 
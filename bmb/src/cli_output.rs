@@ -0,0 +1,199 @@
+//! Command outcomes and exit codes
+//!
+//! Every `bmb` subcommand finishes with a [`CliOutcome`]. In machine mode it
+//! is printed as the last line of stdout, one record with the same shape for
+//! every command:
+//!
+//! ```json
+//! {"type":"summary","command":"test","ok":false,"exit_code":1,"counts":{"tests":3,"passed":2,"failed":1}}
+//! ```
+//!
+//! and its [`ExitStatus`] is the process exit code:
+//!
+//! | Code | Status | Meaning |
+//! |------|--------|---------|
+//! | 0 | `Success` | Everything passed |
+//! | 1 | `Diagnostics` | Compile errors, failed tests or proofs, strict or denied lint warnings, unformatted files |
+//! | 2 | `Usage` | Bad arguments, missing input files or project |
+//! | 3 | `Internal` | I/O failures, missing tools, crashes |
+
+use std::fmt;
+
+use serde_json::json;
+
+/// Exit status of a subcommand, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExitStatus {
+    Success,
+    Diagnostics,
+    Usage,
+    Internal,
+}
+
+impl ExitStatus {
+    /// Process exit code
+    pub fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Diagnostics => 1,
+            ExitStatus::Usage => 2,
+            ExitStatus::Internal => 3,
+        }
+    }
+
+    /// Status for a command that failed with `error`
+    pub fn of_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        if error.is::<Reported>() || error.is::<crate::CompileError>() {
+            ExitStatus::Diagnostics
+        } else if error.is::<UsageError>()
+            || matches!(error.downcast_ref::<crate::project::ProjectError>(),
+                Some(crate::project::ProjectError::InvalidName(_)))
+            || error.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+        {
+            ExitStatus::Usage
+        } else {
+            ExitStatus::Internal
+        }
+    }
+}
+
+/// How a subcommand ended, with the counts its summary reports
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOutcome {
+    pub command: &'static str,
+    pub status: ExitStatus,
+    pub counts: Vec<(&'static str, u64)>,
+    /// Whether a summary record is printed; off for commands whose stdout
+    /// belongs to a program or protocol (`run`, `repl`, `lsp`)
+    pub report: bool,
+}
+
+impl CliOutcome {
+    /// A successful outcome with no counts
+    pub fn new(command: &'static str) -> Self {
+        CliOutcome { command, status: ExitStatus::Success, counts: Vec::new(), report: true }
+    }
+
+    /// Outcome of a command that failed with `error`
+    pub fn from_error(command: &'static str, error: &(dyn std::error::Error + 'static)) -> Self {
+        CliOutcome::new(command).with_status(ExitStatus::of_error(error))
+    }
+
+    /// Raise the status to `status`, keeping a worse one already set
+    pub fn with_status(mut self, status: ExitStatus) -> Self {
+        self.status = self.status.max(status);
+        self
+    }
+
+    /// Mark the outcome as failed with diagnostics when `failed` holds
+    pub fn failed_if(self, failed: bool) -> Self {
+        if failed {
+            self.with_status(ExitStatus::Diagnostics)
+        } else {
+            self
+        }
+    }
+
+    /// Add a count to the summary
+    pub fn count(mut self, name: &'static str, value: usize) -> Self {
+        self.counts.push((name, value as u64));
+        self
+    }
+
+    /// Suppress the summary record
+    pub fn quiet(mut self) -> Self {
+        self.report = false;
+        self
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == ExitStatus::Success
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.status.code()
+    }
+
+    /// The summary record
+    pub fn to_json(&self) -> serde_json::Value {
+        let counts: serde_json::Map<_, _> = self.counts.iter().map(|(name, n)| (name.to_string(), json!(n))).collect();
+        json!({
+            "type": "summary",
+            "command": self.command,
+            "ok": self.is_ok(),
+            "exit_code": self.exit_code(),
+            "counts": counts,
+        })
+    }
+
+    /// Print the summary record. Human output already ends with each
+    /// command's own summary, so only machine mode prints anything.
+    pub fn emit(&self, human: bool) {
+        if self.report && !human {
+            println!("{}", self.to_json());
+        }
+    }
+}
+
+/// Marker error: the failure was already reported as diagnostics, so only
+/// the outcome is left to emit
+#[derive(Debug)]
+pub struct Reported;
+
+impl fmt::Display for Reported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error already reported")
+    }
+}
+
+impl std::error::Error for Reported {}
+
+/// A command was invoked wrongly: bad arguments or missing inputs
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_record_shape() {
+        let outcome = CliOutcome::new("test").count("tests", 3).count("passed", 2).count("failed", 1).failed_if(true);
+        assert_eq!(
+            outcome.to_json(),
+            json!({
+                "type": "summary",
+                "command": "test",
+                "ok": false,
+                "exit_code": 1,
+                "counts": { "tests": 3, "passed": 2, "failed": 1 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_status_keeps_the_worst() {
+        let outcome = CliOutcome::new("check").with_status(ExitStatus::Usage).failed_if(true);
+        assert_eq!(outcome.status, ExitStatus::Usage);
+        assert_eq!(CliOutcome::new("fmt").failed_if(false).exit_code(), 0);
+    }
+
+    #[test]
+    fn test_error_statuses() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(ExitStatus::of_error(&missing), ExitStatus::Usage);
+        assert_eq!(ExitStatus::of_error(&UsageError("no index".into())), ExitStatus::Usage);
+        assert_eq!(ExitStatus::of_error(&Reported), ExitStatus::Diagnostics);
+        assert_eq!(ExitStatus::of_error(&crate::CompileError::type_error("mismatch", crate::Span::new(0, 1))), ExitStatus::Diagnostics);
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "no");
+        assert_eq!(ExitStatus::of_error(&denied), ExitStatus::Internal);
+    }
+}
@@ -2,14 +2,15 @@
 //!
 //! This module generates LLVM IR from MIR and compiles to object files.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use inkwell::builder::Builder;
 use inkwell::context::Context;
+use inkwell::intrinsics::Intrinsic;
 use inkwell::module::Module;
 use inkwell::targets::{
-    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine,
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
 };
 use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
 use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue};
@@ -75,6 +76,8 @@ impl From<OptLevel> for OptimizationLevel {
 /// LLVM Code Generator
 pub struct CodeGen {
     opt_level: OptLevel,
+    /// Target triple for cross-compilation; the host's when `None`
+    target_triple: Option<String>,
 }
 
 impl CodeGen {
@@ -82,12 +85,19 @@ impl CodeGen {
     pub fn new() -> Self {
         Self {
             opt_level: OptLevel::default(),
+            target_triple: None,
         }
     }
 
     /// Create a new code generator with optimization level
     pub fn with_opt_level(opt_level: OptLevel) -> Self {
-        Self { opt_level }
+        Self { opt_level, target_triple: None }
+    }
+
+    /// Generate code for `triple` instead of the host
+    pub fn with_target_triple(mut self, triple: impl Into<String>) -> Self {
+        self.target_triple = Some(triple.into());
+        self
     }
 
     /// Compile MIR to object file
@@ -132,6 +142,9 @@ impl CodeGen {
             ctx.gen_function_body(func)?;
         }
 
+        if let Some(triple) = &self.target_triple {
+            ctx.module.set_triple(&TargetTriple::create(triple));
+        }
         Ok(ctx.module.print_to_string().to_string())
     }
 
@@ -140,19 +153,30 @@ impl CodeGen {
         // Initialize all targets
         Target::initialize_all(&InitializationConfig::default());
 
-        let target_triple = TargetMachine::get_default_triple();
+        let target_triple = match &self.target_triple {
+            Some(triple) => TargetTriple::create(triple),
+            None => TargetMachine::get_default_triple(),
+        };
         let target = Target::from_triple(&target_triple)
             .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
+        module.set_triple(&target_triple);
 
-        // Use native CPU for best performance (like Rust's -C target-cpu=native)
-        let cpu = TargetMachine::get_host_cpu_name();
-        let features = TargetMachine::get_host_cpu_features();
+        // Use native CPU for best performance (like Rust's -C target-cpu=native);
+        // a cross build cannot assume anything beyond the generic CPU
+        let (cpu, features) = if self.target_triple.is_some() {
+            ("generic".to_string(), String::new())
+        } else {
+            (
+                TargetMachine::get_host_cpu_name().to_string(),
+                TargetMachine::get_host_cpu_features().to_string(),
+            )
+        };
 
         let target_machine = target
             .create_target_machine(
                 &target_triple,
-                cpu.to_str().unwrap_or("x86-64"),
-                features.to_str().unwrap_or(""),
+                &cpu,
+                &features,
                 self.opt_level.into(),
                 RelocMode::Default,
                 CodeModel::Default,
@@ -207,6 +231,10 @@ struct LlvmContext<'ctx> {
 
     /// Block lookup table (local to current function)
     blocks: HashMap<String, inkwell::basic_block::BasicBlock<'ctx>>,
+
+    /// Places of unsigned integer or char type (local to current function),
+    /// which casts widen with zero extension
+    unsigned: HashSet<String>,
 }
 
 impl<'ctx> LlvmContext<'ctx> {
@@ -220,6 +248,7 @@ impl<'ctx> LlvmContext<'ctx> {
             functions: HashMap::new(),
             variables: HashMap::new(),
             blocks: HashMap::new(),
+            unsigned: HashSet::new(),
         }
     }
 
@@ -272,6 +301,25 @@ impl<'ctx> LlvmContext<'ctx> {
         let sqrt_fn = self.module.add_function("llvm.sqrt.f64", sqrt_type, None);
         self.functions.insert("sqrt".to_string(), sqrt_fn);
 
+        // sin/cos/floor/ceil/round/ln/exp(f64) -> f64 (LLVM intrinsics)
+        for (name, intrinsic) in [
+            ("sin", "llvm.sin.f64"),
+            ("cos", "llvm.cos.f64"),
+            ("floor", "llvm.floor.f64"),
+            ("ceil", "llvm.ceil.f64"),
+            ("round", "llvm.round.f64"),
+            ("ln", "llvm.log.f64"),
+            ("exp", "llvm.exp.f64"),
+        ] {
+            let math_fn = self.module.add_function(intrinsic, sqrt_type, None);
+            self.functions.insert(name.to_string(), math_fn);
+        }
+
+        // pow(f64, f64) -> f64 (LLVM intrinsic)
+        let pow_type = f64_type.fn_type(&[f64_type.into(), f64_type.into()], false);
+        let pow_fn = self.module.add_function("llvm.pow.f64", pow_type, None);
+        self.functions.insert("pow".to_string(), pow_fn);
+
         // i64_to_f64(i64) -> f64
         // This is handled by sitofp instruction, but we declare it as a placeholder
         // The actual implementation is in gen_call
@@ -309,6 +357,11 @@ impl<'ctx> LlvmContext<'ctx> {
         let println_str_fn = self.module.add_function("bmb_println_str", println_str_type, None);
         self.functions.insert("println_str".to_string(), println_str_fn);
 
+        // bmb_contract_fail(ptr) -> void, called by checked contracts
+        let contract_fail_type = void_type.fn_type(&[ptr_type.into()], false);
+        let contract_fail_fn = self.module.add_function("bmb_contract_fail", contract_fail_type, None);
+        self.functions.insert("bmb_contract_fail".to_string(), contract_fail_fn);
+
         // len(ptr) -> i64
         let len_type = i64_type.fn_type(&[ptr_type.into()], false);
         let len_fn = self.module.add_function("bmb_string_len", len_type, None);
@@ -507,6 +560,8 @@ impl<'ctx> LlvmContext<'ctx> {
             // v0.95: Char represented as i32 (Unicode code point)
             MirType::Char => self.context.i32_type().into(),
             MirType::Unit => self.context.i8_type().into(), // Unit represented as i8
+            // Closures and tuples are i64 handles to blocks of words
+            MirType::Fn { .. } | MirType::Tuple(_) => self.context.i64_type().into(),
             // v0.35: String represented as i8 pointer
             MirType::String => self
                 .context
@@ -556,6 +611,13 @@ impl<'ctx> LlvmContext<'ctx> {
         // Clear per-function state
         self.variables.clear();
         self.blocks.clear();
+        self.unsigned = func
+            .params
+            .iter()
+            .chain(&func.locals)
+            .filter(|(_, ty)| is_unsigned(ty))
+            .map(|(name, _)| name.clone())
+            .collect();
 
         // Get the already-declared function
         let function = *self.functions.get(&func.name)
@@ -719,6 +781,21 @@ impl<'ctx> LlvmContext<'ctx> {
                 self.store_to_place(dest, value)?;
             }
 
+            MirInst::BinOp {
+                dest,
+                op: op @ (MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows),
+                lhs,
+                rhs,
+            } => {
+                let unsigned = [lhs, rhs]
+                    .into_iter()
+                    .any(|operand| matches!(operand, Operand::Place(p) if self.unsigned.contains(&p.name)));
+                let lhs_val = self.gen_operand(lhs)?;
+                let rhs_val = self.gen_operand(rhs)?;
+                let result = self.gen_overflow_test(*op, unsigned, lhs_val, rhs_val)?;
+                self.store_to_place(dest, result)?;
+            }
+
             MirInst::BinOp { dest, op, lhs, rhs } => {
                 let lhs_val = self.gen_operand(lhs)?;
                 let rhs_val = self.gen_operand(rhs)?;
@@ -732,6 +809,16 @@ impl<'ctx> LlvmContext<'ctx> {
                 self.store_to_place(dest, result)?;
             }
 
+            MirInst::Cast { dest, src, ty } => {
+                let src_val = self.gen_operand(src)?;
+                let unsigned = match src {
+                    Operand::Place(p) => self.unsigned.contains(&p.name),
+                    Operand::Constant(c) => matches!(c, Constant::Char(_) | Constant::Bool(_)),
+                };
+                let result = self.gen_cast(src_val, unsigned, ty)?;
+                self.store_to_place(dest, result)?;
+            }
+
             MirInst::Call { dest, func, args } => {
                 // v0.35.4: Handle type conversion intrinsics specially
                 if func == "i64_to_f64" && args.len() == 1 {
@@ -779,6 +866,8 @@ impl<'ctx> LlvmContext<'ctx> {
                 // PHI nodes are transformed into stores in predecessor blocks
                 // If we reach here, it's a bug
             }
+            // Debug info is only emitted by the text backend
+            MirInst::DebugLoc { .. } => {}
             MirInst::StructInit { .. }
             | MirInst::FieldAccess { .. }
             | MirInst::FieldStore { .. }
@@ -790,6 +879,11 @@ impl<'ctx> LlvmContext<'ctx> {
                     "Struct/Enum/Array instructions not yet supported in LLVM codegen".to_string(),
                 ));
             }
+            MirInst::CallIndirect { .. } => {
+                return Err(CodeGenError::LlvmError(
+                    "closure calls not yet supported in LLVM codegen".to_string(),
+                ));
+            }
         }
 
         Ok(())
@@ -865,6 +959,7 @@ impl<'ctx> LlvmContext<'ctx> {
             Constant::String(_) => self.context.ptr_type(inkwell::AddressSpace::default()).into(),
             Constant::Unit => self.context.i8_type().into(),
             Constant::Char(_) => self.context.i32_type().into(),
+            Constant::FnAddr(_) => self.context.i64_type().into(),
         }
     }
 
@@ -899,6 +994,13 @@ impl<'ctx> LlvmContext<'ctx> {
             Constant::Unit => self.context.i8_type().const_int(0, false).into(),
             // v0.95: Char as i32 Unicode code point
             Constant::Char(c) => self.context.i32_type().const_int(*c as u64, false).into(),
+            Constant::FnAddr(name) => {
+                let function = self.functions.get(name)
+                    .expect("address taken of an undeclared function");
+                function.as_global_value().as_pointer_value()
+                    .const_to_int(self.context.i64_type())
+                    .into()
+            }
         }
     }
 
@@ -1024,6 +1126,18 @@ impl<'ctx> LlvmContext<'ctx> {
                     .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
                 Ok(result.into())
             }
+            MirBinOp::UDiv => {
+                let result = self.builder
+                    .build_int_unsigned_div(lhs.into_int_value(), rhs.into_int_value(), "udiv")
+                    .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
+                Ok(result.into())
+            }
+            MirBinOp::UMod => {
+                let result = self.builder
+                    .build_int_unsigned_rem(lhs.into_int_value(), rhs.into_int_value(), "umod")
+                    .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
+                Ok(result.into())
+            }
 
             // Float arithmetic
             MirBinOp::FAdd => {
@@ -1120,6 +1234,18 @@ impl<'ctx> LlvmContext<'ctx> {
                     .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
                 Ok(result.into())
             }
+            MirBinOp::ULt | MirBinOp::UGt | MirBinOp::ULe | MirBinOp::UGe => {
+                let predicate = match op {
+                    MirBinOp::ULt => IntPredicate::ULT,
+                    MirBinOp::UGt => IntPredicate::UGT,
+                    MirBinOp::ULe => IntPredicate::ULE,
+                    _ => IntPredicate::UGE,
+                };
+                let result = self.builder
+                    .build_int_compare(predicate, lhs.into_int_value(), rhs.into_int_value(), "ucmp")
+                    .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
+                Ok(result.into())
+            }
 
             // Float comparison
             MirBinOp::FEq => {
@@ -1187,6 +1313,13 @@ impl<'ctx> LlvmContext<'ctx> {
                     .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
                 Ok(result.into())
             }
+            MirBinOp::UShr => {
+                // Logical right shift for u32/u64
+                let result = self.builder
+                    .build_right_shift(lhs.into_int_value(), rhs.into_int_value(), false, "ushr")
+                    .map_err(|e| CodeGenError::LlvmError(e.to_string()))?;
+                Ok(result.into())
+            }
 
             // v0.95: Wrapping arithmetic (same as regular ops in LLVM, wraps on overflow)
             MirBinOp::AddWrap => {
@@ -1220,6 +1353,11 @@ impl<'ctx> LlvmContext<'ctx> {
                 Ok(result.into())
             }
 
+            // Overflow tests need the operands' signedness, see `gen_overflow_test`
+            MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows => {
+                self.gen_overflow_test(op, false, lhs, rhs)
+            }
+
             // v0.95: Saturating arithmetic
             MirBinOp::AddSat | MirBinOp::SubSat | MirBinOp::MulSat => {
                 // For now, treat as regular ops (full implementation needs saturation logic)
@@ -1265,6 +1403,88 @@ impl<'ctx> LlvmContext<'ctx> {
         }
     }
 
+    /// The overflow bit of `llvm.{s,u}{add,sub,mul}.with.overflow` on the operands
+    fn gen_overflow_test(
+        &self,
+        op: MirBinOp,
+        unsigned: bool,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> CodeGenResult<BasicValueEnum<'ctx>> {
+        let name = format!(
+            "llvm.{}{}.with.overflow",
+            if unsigned { "u" } else { "s" },
+            match op {
+                MirBinOp::AddOverflows => "add",
+                MirBinOp::SubOverflows => "sub",
+                _ => "mul",
+            }
+        );
+        let (lhs, rhs) = (lhs.into_int_value(), rhs.into_int_value());
+        let intrinsic = Intrinsic::find(&name)
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[lhs.get_type().into()]))
+            .ok_or_else(|| CodeGenError::UnknownFunction(name.clone()))?;
+        let err = |e: inkwell::builder::BuilderError| CodeGenError::LlvmError(e.to_string());
+        let pair = self.builder
+            .build_call(intrinsic, &[lhs.into(), rhs.into()], "ovf")
+            .map_err(err)?
+            .try_as_basic_value()
+            .basic()
+            .ok_or_else(|| CodeGenError::LlvmError(format!("{name} returned no value")))?;
+        let bit = self.builder
+            .build_extract_value(pair.into_struct_value(), 1, "ovf_bit")
+            .map_err(err)?;
+        Ok(bit)
+    }
+
+    /// Generate an `as` conversion of `src` to `ty`
+    fn gen_cast(
+        &self,
+        src: BasicValueEnum<'ctx>,
+        unsigned: bool,
+        ty: &MirType,
+    ) -> CodeGenResult<BasicValueEnum<'ctx>> {
+        let target = self.mir_type_to_llvm(ty);
+        let err = |e: inkwell::builder::BuilderError| CodeGenError::LlvmError(e.to_string());
+        let result: BasicValueEnum<'ctx> = match (src, target) {
+            (BasicValueEnum::FloatValue(f), BasicTypeEnum::FloatType(_)) => f.into(),
+            (BasicValueEnum::FloatValue(f), BasicTypeEnum::IntType(_)) if *ty == MirType::Bool => {
+                let zero = f.get_type().const_zero();
+                self.builder.build_float_compare(FloatPredicate::ONE, f, zero, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::FloatValue(f), BasicTypeEnum::IntType(t)) if is_unsigned(ty) => {
+                self.builder.build_float_to_unsigned_int(f, t, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::FloatValue(f), BasicTypeEnum::IntType(t)) => {
+                self.builder.build_float_to_signed_int(f, t, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::IntValue(i), BasicTypeEnum::FloatType(t)) if unsigned => {
+                self.builder.build_unsigned_int_to_float(i, t, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::IntValue(i), BasicTypeEnum::FloatType(t)) => {
+                self.builder.build_signed_int_to_float(i, t, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::IntValue(i), BasicTypeEnum::IntType(_)) if *ty == MirType::Bool => {
+                let zero = i.get_type().const_zero();
+                self.builder.build_int_compare(IntPredicate::NE, i, zero, "cast").map_err(err)?.into()
+            }
+            (BasicValueEnum::IntValue(i), BasicTypeEnum::IntType(t)) => {
+                let (from, to) = (i.get_type().get_bit_width(), t.get_bit_width());
+                if from == to {
+                    i.into()
+                } else if from > to {
+                    self.builder.build_int_truncate(i, t, "cast").map_err(err)?.into()
+                } else if unsigned {
+                    self.builder.build_int_z_extend(i, t, "cast").map_err(err)?.into()
+                } else {
+                    self.builder.build_int_s_extend(i, t, "cast").map_err(err)?.into()
+                }
+            }
+            (value, _) => value,
+        };
+        Ok(result)
+    }
+
     /// Generate a unary operation
     fn gen_unaryop(
         &self,
@@ -1300,3 +1520,8 @@ impl<'ctx> LlvmContext<'ctx> {
         }
     }
 }
+
+/// Whether values of `ty` widen with zero extension
+fn is_unsigned(ty: &MirType) -> bool {
+    matches!(ty, MirType::U32 | MirType::U64 | MirType::Char | MirType::Bool)
+}
@@ -5,14 +5,29 @@
 //! portable and easier to debug.
 //!
 //! The generated IR is compatible with the bootstrap compiler output.
+//!
+//! # Struct ABI
+//!
+//! Each struct becomes a named type, `%struct.Name = type { ... }`, with
+//! fields in declaration order and nested structs stored inline. A struct
+//! value is a `ptr` to its memory, which lives in a stack slot of the
+//! function that created it:
+//!
+//! - Struct arguments are passed as that `ptr`. Callees only read through it;
+//!   `let` bindings and assignments copy the whole struct into a slot of
+//!   their own, so field stores never reach the caller's value.
+//! - A function returning a struct returns `void` and takes a leading
+//!   `ptr sret(%struct.Name)` parameter. The caller passes a slot for the
+//!   result, and `return` copies the value into it.
 
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::path::Path;
 use thiserror::Error;
 
 use crate::mir::{
-    BasicBlock, Constant, MirBinOp, MirFunction, MirInst, MirProgram, MirType, MirUnaryOp,
-    Operand, Place, Terminator,
+    enum_field_word, inst_dest, variant_discriminant, BasicBlock, Constant, MirBinOp, MirFunction, MirInst,
+    MirProgram, MirType, MirUnaryOp, Operand, Place, Terminator,
 };
 
 /// Text-based code generation error
@@ -24,8 +39,14 @@ pub enum TextCodeGenError {
     #[error("Unknown variable: {0}")]
     UnknownVariable(String),
 
+    #[error("Unknown struct: {0}")]
+    UnknownStruct(String),
+
     #[error("Formatting error: {0}")]
     FormatError(#[from] std::fmt::Error),
+
+    #[error("invalid target triple `{0}`: {1}")]
+    InvalidTargetTriple(String, String),
 }
 
 /// Result type for text code generation
@@ -35,13 +56,45 @@ pub type TextCodeGenResult<T> = Result<T, TextCodeGenError>;
 pub struct TextCodeGen {
     /// Target triple (default: x86_64-pc-windows-msvc for Windows)
     target_triple: String,
+    /// Source file that `DebugLoc` spans refer to, when emitting debug info
+    debug_source: Option<DebugSource>,
 }
 
+/// Architectures a target triple may start with; families such as `armv7`
+/// or `riscv64` are matched by prefix
+const TRIPLE_ARCHES: &[&str] = &[
+    "x86_64", "i386", "i486", "i586", "i686", "aarch64", "arm64", "arm", "thumb", "riscv", "wasm32",
+    "wasm64", "powerpc", "ppc", "mips", "s390x", "sparc", "loongarch", "avr", "msp430", "hexagon",
+];
+
+/// Check that `triple` looks like `arch-vendor-os[-env]` (the vendor may be
+/// left out, as in `aarch64-linux-gnu`) with an architecture LLVM knows
+pub fn check_target_triple(triple: &str) -> TextCodeGenResult<()> {
+    let invalid = |reason: String| Err(TextCodeGenError::InvalidTargetTriple(triple.to_string(), reason));
+    let parts: Vec<&str> = triple.split('-').collect();
+    if !(2..=4).contains(&parts.len()) {
+        return invalid("expected `arch-vendor-os[-env]`, e.g. `aarch64-unknown-linux-gnu`".to_string());
+    }
+    if let Some(part) = parts.iter().find(|part| {
+        part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    }) {
+        return invalid(format!("`{}` is not a valid component", part));
+    }
+    if !TRIPLE_ARCHES.iter().any(|arch| parts[0].starts_with(arch)) {
+        return invalid(format!("unknown architecture `{}`", parts[0]));
+    }
+    Ok(())
+}
+
+/// Marker line standing in for a `DebugLoc` until the function is finished
+const DEBUG_LOC_MARKER: &str = "  ;!loc ";
+
 impl TextCodeGen {
     /// Create a new text code generator
     pub fn new() -> Self {
         Self {
             target_triple: Self::default_target_triple(),
+            debug_source: None,
         }
     }
 
@@ -49,9 +102,17 @@ impl TextCodeGen {
     pub fn with_target(target: impl Into<String>) -> Self {
         Self {
             target_triple: target.into(),
+            debug_source: None,
         }
     }
 
+    /// Emit DWARF debug info, resolving `DebugLoc` spans against `source`,
+    /// the contents of the file at `path`
+    pub fn with_debug_info(mut self, path: &Path, source: &str) -> Self {
+        self.debug_source = Some(DebugSource::new(path, source));
+        self
+    }
+
     /// Get default target triple based on platform
     fn default_target_triple() -> String {
         #[cfg(target_os = "windows")]
@@ -74,6 +135,7 @@ impl TextCodeGen {
 
     /// Generate complete LLVM IR module as text
     pub fn generate(&self, program: &MirProgram) -> TextCodeGenResult<String> {
+        check_target_triple(&self.target_triple)?;
         let mut output = String::new();
 
         // Module header
@@ -91,6 +153,9 @@ impl TextCodeGen {
             .map(|f| (f.name.clone(), self.mir_type_to_llvm(&f.ret_ty)))
             .collect();
 
+        // Named struct types
+        self.emit_struct_types(&mut output, program)?;
+
         // Emit string globals
         self.emit_string_globals(&mut output, &string_table)?;
 
@@ -98,13 +163,57 @@ impl TextCodeGen {
         self.emit_runtime_declarations(&mut output)?;
 
         // Generate functions with string table and function type map
+        let mut debug = self.debug_source.as_ref().map(DebugMetadata::new);
         for func in &program.functions {
-            self.emit_function_with_strings(&mut output, func, &string_table, &fn_return_types)?;
+            match (&mut debug, &self.debug_source) {
+                (Some(debug), Some(source)) => {
+                    let mut text = String::new();
+                    self.emit_function_with_strings(&mut text, func, &string_table, &fn_return_types)?;
+                    debug.attach(&mut output, &text, func, source)?;
+                }
+                _ => self.emit_function_with_strings(&mut output, func, &string_table, &fn_return_types)?,
+            }
+        }
+
+        if let Some(debug) = debug {
+            debug.emit(&mut output)?;
         }
 
         Ok(output)
     }
 
+    /// Emit a `%struct.Name = type { ... }` definition for every struct
+    /// appearing in a function signature or local
+    fn emit_struct_types(&self, out: &mut String, program: &MirProgram) -> TextCodeGenResult<()> {
+        fn collect<'a>(ty: &'a MirType, structs: &mut std::collections::BTreeMap<&'a str, &'a [(String, Box<MirType>)]>) {
+            if let MirType::Struct { name, fields } = ty
+                && structs.insert(name, fields).is_none()
+            {
+                for (_, field_ty) in fields {
+                    collect(field_ty, structs);
+                }
+            }
+        }
+
+        let mut structs = std::collections::BTreeMap::new();
+        for func in &program.functions {
+            collect(&func.ret_ty, &mut structs);
+            for (_, ty) in func.params.iter().chain(&func.locals) {
+                collect(ty, &mut structs);
+            }
+        }
+        if structs.is_empty() {
+            return Ok(());
+        }
+
+        for (name, fields) in structs {
+            let field_types: Vec<String> = fields.iter().map(|(_, ty)| self.llvm_field_type(ty)).collect();
+            writeln!(out, "%struct.{} = type {{ {} }}", name, field_types.join(", "))?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
     /// Collect all string constants from the program
     fn collect_string_constants(&self, program: &MirProgram) -> HashMap<String, String> {
         let mut table = HashMap::new();
@@ -148,6 +257,19 @@ impl TextCodeGen {
                                 }
                         }
                     }
+                    // Check for string constants stored into struct fields
+                    let field_values: Vec<&Operand> = match inst {
+                        MirInst::StructInit { fields, .. } => fields.iter().map(|(_, v)| v).collect(),
+                        MirInst::FieldStore { value, .. } => vec![value],
+                        _ => Vec::new(),
+                    };
+                    for value in field_values {
+                        if let Operand::Constant(Constant::String(s)) = value
+                            && !table.contains_key(s) {
+                                table.insert(s.clone(), format!(".str.{}", counter));
+                                counter += 1;
+                            }
+                    }
                 }
                 // Check for string constants in Return terminator
                 if let Terminator::Return(Some(Operand::Constant(Constant::String(s)))) = &block.terminator
@@ -216,6 +338,7 @@ impl TextCodeGen {
         writeln!(out, "declare i64 @bmb_abs(i64)")?;  // bmb_ prefix to avoid stdlib conflict
         writeln!(out, "declare i64 @min(i64, i64)")?;
         writeln!(out, "declare i64 @max(i64, i64)")?;
+        writeln!(out, "declare void @bmb_contract_fail(ptr)")?;
         writeln!(out)?;
 
         // Phase 32.3: String runtime functions
@@ -278,6 +401,7 @@ impl TextCodeGen {
         writeln!(out, "declare ptr @char_to_string(i32)")?;
         writeln!(out, "declare void @print_str(ptr)")?;
         writeln!(out, "declare void @println_str(ptr)")?;
+        writeln!(out, "declare void @flush()")?;
         writeln!(out)?;
 
         // File I/O wrappers
@@ -296,9 +420,57 @@ impl TextCodeGen {
         writeln!(out, "declare i64 @sb_clear(i64)")?;
         writeln!(out)?;
 
+        // String search and transformation
+        writeln!(out, "declare i1 @str_contains(ptr, ptr)")?;
+        writeln!(out, "declare i1 @str_starts_with(ptr, ptr)")?;
+        writeln!(out, "declare i1 @str_ends_with(ptr, ptr)")?;
+        writeln!(out, "declare i64 @str_index_of(ptr, ptr)")?;
+        writeln!(out, "declare ptr @str_replace(ptr, ptr, ptr)")?;
+        writeln!(out, "declare ptr @str_trim(ptr)")?;
+        writeln!(out, "declare i64 @parse_int(ptr)")?;
+        writeln!(out, "declare i64 @str_split(ptr, ptr)")?;
+        writeln!(out, "declare ptr @str_from_id(i64)")?;
+        writeln!(out)?;
+
+        // HashMap and String-valued vec builtins
+        writeln!(out, "declare i64 @hashmap_new()")?;
+        writeln!(out, "declare i64 @hashmap_insert(i64, i64, i64)")?;
+        writeln!(out, "declare i64 @hashmap_get(i64, i64)")?;
+        writeln!(out, "declare i64 @hashmap_contains(i64, i64)")?;
+        writeln!(out, "declare i64 @hashmap_remove(i64, i64)")?;
+        writeln!(out, "declare i64 @hashmap_len(i64)")?;
+        writeln!(out, "declare void @hashmap_free(i64)")?;
+        writeln!(out, "declare void @hashmap_insert_str(i64, i64, ptr)")?;
+        writeln!(out, "declare ptr @hashmap_get_str(i64, i64)")?;
+        writeln!(out, "declare i64 @hashmap_insert_str_key(i64, ptr, i64)")?;
+        writeln!(out, "declare i64 @hashmap_get_str_key(i64, ptr)")?;
+        writeln!(out, "declare i64 @hashmap_contains_str_key(i64, ptr)")?;
+        writeln!(out, "declare void @vec_push_str(i64, ptr)")?;
+        writeln!(out, "declare ptr @vec_get_str(i64, i64)")?;
+        writeln!(out)?;
+
         // v0.34: Math intrinsics for Phase 34.4 Benchmark Gate
         writeln!(out, "; Runtime declarations - Math intrinsics")?;
         writeln!(out, "declare double @llvm.sqrt.f64(double)")?;
+        writeln!(out, "declare double @llvm.sin.f64(double)")?;
+        writeln!(out, "declare double @llvm.cos.f64(double)")?;
+        writeln!(out, "declare double @llvm.floor.f64(double)")?;
+        writeln!(out, "declare double @llvm.ceil.f64(double)")?;
+        writeln!(out, "declare double @llvm.round.f64(double)")?;
+        writeln!(out, "declare double @llvm.log.f64(double)")?;
+        writeln!(out, "declare double @llvm.exp.f64(double)")?;
+        writeln!(out, "declare double @llvm.pow.f64(double, double)")?;
+        writeln!(out)?;
+
+        // Overflow-aware integer arithmetic for `+?`/`+|`, checked `+` and friends
+        writeln!(out, "; Runtime declarations - Overflow intrinsics")?;
+        for ty in ["i32", "i64"] {
+            for op in ["sadd", "ssub", "smul", "uadd", "usub", "umul"] {
+                writeln!(out, "declare {{{ty}, i1}} @llvm.{op}.with.overflow.{ty}({ty}, {ty})")?;
+            }
+            writeln!(out, "declare {ty} @llvm.sadd.sat.{ty}({ty}, {ty})")?;
+            writeln!(out, "declare {ty} @llvm.ssub.sat.{ty}({ty}, {ty})")?;
+        }
         writeln!(out)?;
 
         // v0.34.2: Memory allocation for Phase 34.2 Dynamic Collections
@@ -363,8 +535,15 @@ impl TextCodeGen {
                             // Comparison operators return i1
                             MirBinOp::Eq | MirBinOp::Ne | MirBinOp::Lt | MirBinOp::Le
                             | MirBinOp::Gt | MirBinOp::Ge => "i1",
+                            MirBinOp::ULt | MirBinOp::ULe | MirBinOp::UGt | MirBinOp::UGe => "i1",
+                            MirBinOp::FEq | MirBinOp::FNe | MirBinOp::FLt | MirBinOp::FLe
+                            | MirBinOp::FGt | MirBinOp::FGe => "i1",
                             // String concat returns ptr
                             MirBinOp::Add if lhs_ty == "ptr" => "ptr",
+                            // Checked ops return an `Option` handle
+                            MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked => "i64",
+                            // Overflow tests are the intrinsic's overflow bit
+                            MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows => "i1",
                             // Logical ops preserve operand type
                             MirBinOp::And | MirBinOp::Or => lhs_ty,
                             // Arithmetic ops preserve operand type
@@ -409,12 +588,34 @@ impl TextCodeGen {
         // Track defined names to handle SSA violations from MIR
         let mut name_counts: HashMap<String, u32> = HashMap::new();
 
-        // Function signature
-        let ret_type = self.mir_type_to_llvm(&func.ret_ty);
-        let params: Vec<String> = func
+        // Function signature; a struct result is written through an sret pointer
+        let ret_type = match func.ret_ty {
+            MirType::Struct { .. } => "void",
+            _ => self.mir_type_to_llvm(&func.ret_ty),
+        };
+        // Parameters the body reassigns live in an alloca like locals, seeded
+        // from the incoming `%name.arg` value
+        let assigned_params: std::collections::HashSet<&str> = func
             .params
             .iter()
-            .map(|(name, ty)| format!("{} %{}", self.mir_type_to_llvm(ty), name))
+            .map(|(name, _)| name.as_str())
+            .filter(|name| {
+                func.blocks
+                    .iter()
+                    .flat_map(|b| b.instructions.iter())
+                    .any(|inst| inst_dest(inst).is_some_and(|d| d.name == *name))
+            })
+            .collect();
+        let sret = match &func.ret_ty {
+            MirType::Struct { name, .. } => Some(format!("ptr sret(%struct.{}) %_sret", name)),
+            _ => None,
+        };
+        let params: Vec<String> = sret
+            .into_iter()
+            .chain(func.params.iter().map(|(name, ty)| {
+                let suffix = if assigned_params.contains(name.as_str()) { ".arg" } else { "" };
+                format!("{} %{}{}", self.mir_type_to_llvm(ty), name, suffix)
+            }))
             .collect();
 
         // Mark parameters as defined
@@ -468,7 +669,8 @@ impl TextCodeGen {
                         if let Operand::Place(p) = val {
                             // Check if this place is a local variable (not a phi destination)
                             // Phi destinations are SSA values, not memory locations
-                            if func.locals.iter().any(|(n, _)| n == &p.name)
+                            if (func.locals.iter().any(|(n, _)| n == &p.name)
+                                || assigned_params.contains(p.name.as_str()))
                                && !phi_dests.contains(&p.name) {
                                 let key = (block.label.clone(), p.name.clone(), pred_label.clone());
                                 let load_temp = format!("{}.phi.{}", p.name, pred_label);
@@ -511,12 +713,32 @@ impl TextCodeGen {
             .filter(|(_, ty)| self.mir_type_to_llvm(ty) != "void")
             .filter(|(name, _)| !phi_dests.contains(name))
             .map(|(name, _)| name.clone())
+            .chain(assigned_params.iter().map(|name| name.to_string()))
             .collect();
 
+        // Struct values created here (literals, copies and sret call results)
+        // each get a slot, allocated once up front so loops don't grow the stack
+        let mut struct_slots: Vec<(&str, &str)> = Vec::new();
+        for inst in func.blocks.iter().flat_map(|b| b.instructions.iter()) {
+            let dest = match inst {
+                MirInst::StructInit { dest, .. } | MirInst::Copy { dest, .. } => dest,
+                MirInst::Call { dest: Some(dest), .. } => dest,
+                _ => continue,
+            };
+            if let Some(MirType::Struct { name, .. }) = self.struct_type(dest, func)
+                && !struct_slots.iter().any(|(slot, _)| *slot == dest.name)
+            {
+                struct_slots.push((&dest.name, name));
+            }
+        }
+
         // Emit entry block with allocas for local variables (excluding phi-referenced ones)
         // Use "alloca_entry" to avoid conflicts with user variables named "entry"
-        if !local_names.is_empty() {
+        if !local_names.is_empty() || !struct_slots.is_empty() {
             writeln!(out, "alloca_entry:")?;
+            for (name, struct_name) in &struct_slots {
+                writeln!(out, "  %{}.slot = alloca %struct.{}", name, struct_name)?;
+            }
             for (name, ty) in &func.locals {
                 if local_names.contains(name) {
                     let llvm_ty = self.mir_type_to_llvm(ty);
@@ -526,6 +748,13 @@ impl TextCodeGen {
                     }
                 }
             }
+            for (name, ty) in &func.params {
+                if assigned_params.contains(name.as_str()) {
+                    let llvm_ty = self.mir_type_to_llvm(ty);
+                    writeln!(out, "  %{}.addr = alloca {}", name, llvm_ty)?;
+                    writeln!(out, "  store {} %{}.arg, ptr %{}.addr", llvm_ty, name, name)?;
+                }
+            }
             // Jump to the actual first block
             if let Some(first_block) = func.blocks.first() {
                 writeln!(out, "  br label %bb_{}", first_block.label)?;
@@ -645,6 +874,13 @@ impl TextCodeGen {
         current_block_label: &str,
     ) -> TextCodeGenResult<()> {
         match inst {
+            MirInst::DebugLoc { span } => {
+                // Resolved into `!dbg` attachments once the function is complete
+                if self.debug_source.is_some() {
+                    writeln!(out, "{}{}", DEBUG_LOC_MARKER, span.start)?;
+                }
+            }
+
             MirInst::Const { dest, value } => {
                 let ty = self.constant_type(value);
                 // Check if destination is a local (uses alloca)
@@ -678,6 +914,9 @@ impl TextCodeGen {
                         Constant::Char(c) => {
                             writeln!(out, "  %{} = add {} 0, {}", temp_name, ty, *c as u32)?;
                         }
+                        Constant::FnAddr(_) => {
+                            writeln!(out, "  %{} = add {} 0, {}", temp_name, ty, self.format_constant(value))?;
+                        }
                     }
                     writeln!(out, "  store {} %{}, ptr %{}.addr", ty, temp_name, dest.name)?;
                 } else {
@@ -712,6 +951,9 @@ impl TextCodeGen {
                         Constant::Char(c) => {
                             writeln!(out, "  %{} = add {} 0, {}", dest_name, ty, *c as u32)?;
                         }
+                        Constant::FnAddr(_) => {
+                            writeln!(out, "  %{} = add {} 0, {}", dest_name, ty, self.format_constant(value))?;
+                        }
                     }
                 }
             }
@@ -721,6 +963,21 @@ impl TextCodeGen {
                 let ty = place_types.get(&src.name).copied()
                     .unwrap_or_else(|| self.infer_place_type(src, func));
 
+                // Structs have value semantics: copy the whole value into the
+                // destination's own slot
+                if let Some(MirType::Struct { name, .. }) = self.struct_type(dest, func)
+                    && ty == "ptr"
+                {
+                    let tmp = self.unique_name(&format!("{}.copy", dest.name), name_counts);
+                    let src_ptr = self.operand_value(
+                        out, &Operand::Place(src.clone()), "ptr", &format!("{}.src", tmp), local_names, string_table,
+                    )?;
+                    writeln!(out, "  %{} = load %struct.{}, ptr {}", tmp, name, src_ptr)?;
+                    writeln!(out, "  store %struct.{} %{}, ptr %{}.slot", name, tmp, dest.name)?;
+                    self.define_struct_place(out, dest, &format!("%{}.slot", dest.name), local_names, name_counts)?;
+                    return Ok(());
+                }
+
                 // v0.31.23: Skip void type copies (result of void-returning function calls)
                 if ty == "void" {
                     // No-op: void values cannot be copied or stored
@@ -872,6 +1129,80 @@ impl TextCodeGen {
                     if local_names.contains(&dest.name) {
                         writeln!(out, "  store i1 %{}, ptr %{}.addr", dest_name, dest.name)?;
                     }
+                } else if matches!(op, MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows) {
+                    // The overflow bit alone, for the branch to the overflow trap
+                    let sign = if self.is_unsigned_binop(lhs, rhs, func) { "u" } else { "s" };
+                    let intrinsic = match op {
+                        MirBinOp::AddOverflows => "add",
+                        MirBinOp::SubOverflows => "sub",
+                        _ => "mul",
+                    };
+                    writeln!(out, "  %{}.ovf = call {{{}, i1}} @llvm.{}{}.with.overflow.{}({} {}, {} {})",
+                             dest_name, lhs_ty, sign, intrinsic, lhs_ty, lhs_ty, lhs_str, lhs_ty, rhs_str)?;
+                    writeln!(out, "  %{} = extractvalue {{{}, i1}} %{}.ovf, 1", dest_name, lhs_ty, dest_name)?;
+                    if local_names.contains(&dest.name) {
+                        writeln!(out, "  store i1 %{}, ptr %{}.addr", dest_name, dest.name)?;
+                    }
+                } else if matches!(op, MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked) {
+                    // v0.38: Checked arithmetic builds `Some(value)` or `None` on the heap
+                    let unsigned = self.is_unsigned_binop(lhs, rhs, func);
+                    let sign = if unsigned { "u" } else { "s" };
+                    let intrinsic = match op {
+                        MirBinOp::AddChecked => "add",
+                        MirBinOp::SubChecked => "sub",
+                        _ => "mul",
+                    };
+                    writeln!(out, "  %{}.ovf = call {{{}, i1}} @llvm.{}{}.with.overflow.{}({} {}, {} {})",
+                             dest_name, lhs_ty, sign, intrinsic, lhs_ty, lhs_ty, lhs_str, lhs_ty, rhs_str)?;
+                    writeln!(out, "  %{}.val = extractvalue {{{}, i1}} %{}.ovf, 0", dest_name, lhs_ty, dest_name)?;
+                    writeln!(out, "  %{}.bit = extractvalue {{{}, i1}} %{}.ovf, 1", dest_name, lhs_ty, dest_name)?;
+                    writeln!(out, "  %{}.tag = select i1 %{}.bit, i64 {}, i64 {}",
+                             dest_name, dest_name, variant_discriminant("None"), variant_discriminant("Some"))?;
+                    let payload = if lhs_ty == "i64" {
+                        format!("%{}.val", dest_name)
+                    } else {
+                        let ext = if unsigned { "zext" } else { "sext" };
+                        writeln!(out, "  %{}.wide = {} {} %{}.val to i64", dest_name, ext, lhs_ty, dest_name)?;
+                        format!("%{}.wide", dest_name)
+                    };
+                    writeln!(out, "  %{}.mem = call ptr @malloc(i64 16)", dest_name)?;
+                    writeln!(out, "  store i64 %{}.tag, ptr %{}.mem", dest_name, dest_name)?;
+                    writeln!(out, "  %{}.slot = getelementptr i64, ptr %{}.mem, i32 1", dest_name, dest_name)?;
+                    writeln!(out, "  store i64 {}, ptr %{}.slot", payload, dest_name)?;
+                    writeln!(out, "  %{} = ptrtoint ptr %{}.mem to i64", dest_name, dest_name)?;
+                    if local_names.contains(&dest.name) {
+                        writeln!(out, "  store i64 %{}, ptr %{}.addr", dest_name, dest.name)?;
+                    }
+                } else if matches!(op, MirBinOp::AddSat | MirBinOp::SubSat | MirBinOp::MulSat) {
+                    // v0.38: Saturating arithmetic clamps to the type's min/max
+                    let (min, max) = if lhs_ty == "i32" {
+                        (i32::MIN as i64, i32::MAX as i64)
+                    } else {
+                        (i64::MIN, i64::MAX)
+                    };
+                    match op {
+                        MirBinOp::AddSat | MirBinOp::SubSat => {
+                            let intrinsic = if *op == MirBinOp::AddSat { "sadd" } else { "ssub" };
+                            writeln!(out, "  %{} = call {} @llvm.{}.sat.{}({} {}, {} {})",
+                                     dest_name, lhs_ty, intrinsic, lhs_ty, lhs_ty, lhs_str, lhs_ty, rhs_str)?;
+                        }
+                        _ => {
+                            // No multiply intrinsic: clamp towards the sign of the exact product
+                            writeln!(out, "  %{}.ovf = call {{{}, i1}} @llvm.smul.with.overflow.{}({} {}, {} {})",
+                                     dest_name, lhs_ty, lhs_ty, lhs_ty, lhs_str, lhs_ty, rhs_str)?;
+                            writeln!(out, "  %{}.val = extractvalue {{{}, i1}} %{}.ovf, 0", dest_name, lhs_ty, dest_name)?;
+                            writeln!(out, "  %{}.bit = extractvalue {{{}, i1}} %{}.ovf, 1", dest_name, lhs_ty, dest_name)?;
+                            writeln!(out, "  %{}.sign = xor {} {}, {}", dest_name, lhs_ty, lhs_str, rhs_str)?;
+                            writeln!(out, "  %{}.neg = icmp slt {} %{}.sign, 0", dest_name, lhs_ty, dest_name)?;
+                            writeln!(out, "  %{}.clamp = select i1 %{}.neg, {} {}, {} {}",
+                                     dest_name, dest_name, lhs_ty, min, lhs_ty, max)?;
+                            writeln!(out, "  %{} = select i1 %{}.bit, {} %{}.clamp, {} %{}.val",
+                                     dest_name, dest_name, lhs_ty, dest_name, lhs_ty, dest_name)?;
+                        }
+                    }
+                    if local_names.contains(&dest.name) {
+                        writeln!(out, "  store {} %{}, ptr %{}.addr", lhs_ty, dest_name, dest.name)?;
+                    }
                 } else {
                     // v0.34: Fix float operations - MIR may use Add/Sub/etc. for f64 due to type inference issues
                     // Override to float operations when operand type is double/f64
@@ -886,6 +1217,7 @@ impl TextCodeGen {
                             MirBinOp::AddWrap | MirBinOp::SubWrap | MirBinOp::MulWrap |
                             // v0.38: Checked arithmetic (not applicable to floats)
                             MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked |
+                            MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows |
                             // v0.38: Saturating arithmetic (not applicable to floats)
                             MirBinOp::AddSat | MirBinOp::SubSat | MirBinOp::MulSat => {
                                 let (s, _) = self.binop_to_llvm(*op);
@@ -898,7 +1230,10 @@ impl TextCodeGen {
                             MirBinOp::Le | MirBinOp::FLe => "fcmp ole",
                             MirBinOp::Ge | MirBinOp::FGe => "fcmp oge",
                             MirBinOp::And | MirBinOp::Or | MirBinOp::Shl | MirBinOp::Shr |
-                            MirBinOp::Band | MirBinOp::Bor | MirBinOp::Bxor | MirBinOp::Implies => {
+                            MirBinOp::Band | MirBinOp::Bor | MirBinOp::Bxor | MirBinOp::Implies |
+                            // Unsigned operations only apply to integers
+                            MirBinOp::UDiv | MirBinOp::UMod | MirBinOp::ULt | MirBinOp::UGt |
+                            MirBinOp::ULe | MirBinOp::UGe | MirBinOp::UShr => {
                                 // These operations don't apply to floats, use integer path
                                 let (s, _) = self.binop_to_llvm(*op);
                                 s
@@ -959,39 +1294,105 @@ impl TextCodeGen {
                 }
             }
 
+            MirInst::Cast { dest, src, ty } => {
+                let dest_name = self.unique_name(&dest.name, name_counts);
+                let src_ty = match src {
+                    Operand::Constant(c) => self.constant_type(c),
+                    Operand::Place(p) => place_types.get(&p.name).copied()
+                        .unwrap_or_else(|| self.infer_place_type(p, func)),
+                };
+                let dest_ty = self.mir_type_to_llvm(ty);
+                let src_str = match src {
+                    Operand::Place(p) if local_names.contains(&p.name) => {
+                        let load_name = format!("{}.{}.cast", dest_name, p.name);
+                        writeln!(out, "  %{} = load {}, ptr %{}.addr", load_name, src_ty, p.name)?;
+                        format!("%{}", load_name)
+                    }
+                    _ => self.format_operand_with_strings(src, string_table),
+                };
+
+                // Unsigned integers, chars and bools widen with zero bits
+                let src_mir_ty = match src {
+                    Operand::Place(p) => func.params.iter().chain(&func.locals)
+                        .find(|(name, _)| name == &p.name)
+                        .map(|(_, ty)| ty.clone()),
+                    Operand::Constant(Constant::Char(_)) => Some(MirType::Char),
+                    Operand::Constant(Constant::Bool(_)) => Some(MirType::Bool),
+                    Operand::Constant(_) => None,
+                };
+                let unsigned_src = matches!(src_mir_ty, Some(MirType::U32 | MirType::U64 | MirType::Char | MirType::Bool));
+                let unsigned_dest = matches!(ty, MirType::U32 | MirType::U64);
+                let bits = |t: &str| match t {
+                    "i1" => 1,
+                    "i8" => 8,
+                    "i32" => 32,
+                    _ => 64,
+                };
+                // `as char` keeps the low 32 bits; only the interpreter checks
+                // that the result is a Unicode scalar value
+                let conversion = match (src_ty, dest_ty) {
+                    (s, d) if s == d => format!("bitcast {} {} to {}", s, src_str, d),
+                    ("double", "i1") => format!("fcmp one double {}, 0.0", src_str),
+                    ("double", d) => {
+                        let op = if unsigned_dest { "fptoui" } else { "fptosi" };
+                        format!("{} double {} to {}", op, src_str, d)
+                    }
+                    (s, "double") => {
+                        let op = if unsigned_src { "uitofp" } else { "sitofp" };
+                        format!("{} {} {} to double", op, s, src_str)
+                    }
+                    (s, "i1") => format!("icmp ne {} {}, 0", s, src_str),
+                    (s, d) if bits(s) < bits(d) => {
+                        let op = if unsigned_src { "zext" } else { "sext" };
+                        format!("{} {} {} to {}", op, s, src_str, d)
+                    }
+                    (s, d) => format!("trunc {} {} to {}", s, src_str, d),
+                };
+                writeln!(out, "  %{} = {}", dest_name, conversion)?;
+                if local_names.contains(&dest.name) {
+                    writeln!(out, "  store {} %{}, ptr %{}.addr", dest_ty, dest_name, dest.name)?;
+                }
+            }
+
             MirInst::Call { dest, func: fn_name, args } => {
                 // v0.34: Handle math intrinsics and type conversions
-                if fn_name == "sqrt" && args.len() == 1 {
-                    // sqrt(x: f64) -> f64 via LLVM intrinsic
-                    let arg_ty = match &args[0] {
-                        Operand::Constant(c) => self.constant_type(c),
-                        Operand::Place(p) => place_types.get(&p.name).copied()
-                            .unwrap_or_else(|| self.infer_place_type(p, func)),
-                    };
-                    let arg_val = match &args[0] {
-                        Operand::Place(p) if local_names.contains(&p.name) => {
-                            let load_name = format!("{}.sqrt.arg", p.name);
-                            writeln!(out, "  %{} = load {}, ptr %{}.addr", load_name, arg_ty, p.name)?;
-                            format!("%{}", load_name)
-                        }
-                        _ => self.format_operand_with_strings(&args[0], string_table),
-                    };
-                    // Convert i64 to f64 if needed
-                    let f64_val = if arg_ty == "i64" {
-                        let conv_name = format!("{}.sqrt.conv", dest.as_ref().map(|d| d.name.as_str()).unwrap_or("tmp"));
-                        writeln!(out, "  %{} = sitofp i64 {} to double", conv_name, arg_val)?;
-                        format!("%{}", conv_name)
-                    } else {
-                        arg_val
-                    };
+                if let Some(intrinsic) = f64_math_intrinsic(fn_name, args.len()) {
+                    // sqrt/sin/cos/.../pow(x: f64, ...) -> f64 via LLVM intrinsic
+                    let mut f64_args = Vec::with_capacity(args.len());
+                    for arg in args {
+                        let arg_ty = match arg {
+                            Operand::Constant(c) => self.constant_type(c),
+                            Operand::Place(p) => place_types.get(&p.name).copied()
+                                .unwrap_or_else(|| self.infer_place_type(p, func)),
+                        };
+                        let arg_val = match arg {
+                            Operand::Place(p) if local_names.contains(&p.name) => {
+                                let load_name = self.unique_name(&format!("{}.{}.arg", p.name, fn_name), name_counts);
+                                writeln!(out, "  %{} = load {}, ptr %{}.addr", load_name, arg_ty, p.name)?;
+                                format!("%{}", load_name)
+                            }
+                            _ => self.format_operand_with_strings(arg, string_table),
+                        };
+                        // Convert i64 to f64 if needed
+                        let f64_val = if arg_ty == "i64" {
+                            let base = format!("{}.{}.conv", dest.as_ref().map(|d| d.name.as_str()).unwrap_or("tmp"), fn_name);
+                            let conv_name = self.unique_name(&base, name_counts);
+                            writeln!(out, "  %{} = sitofp i64 {} to double", conv_name, arg_val)?;
+                            format!("%{}", conv_name)
+                        } else {
+                            arg_val
+                        };
+                        f64_args.push(format!("double {}", f64_val));
+                    }
+                    let call_args = f64_args.join(", ");
                     if let Some(d) = dest {
                         if local_names.contains(&d.name) {
-                            let temp_name = format!("{}.sqrt", d.name);
-                            writeln!(out, "  %{} = call double @llvm.sqrt.f64(double {})", temp_name, f64_val)?;
+                            let temp_name = format!("{}.{}", d.name, fn_name);
+                            writeln!(out, "  %{} = call double @{}({})", temp_name, intrinsic, call_args)?;
                             writeln!(out, "  store double %{}, ptr %{}.addr", temp_name, d.name)?;
                         } else {
                             let dest_name = self.unique_name(&d.name, name_counts);
-                            writeln!(out, "  %{} = call double @llvm.sqrt.f64(double {})", dest_name, f64_val)?;
+                            writeln!(out, "  %{} = call double @{}({})", dest_name, intrinsic, call_args)?;
                         }
                     }
                     return Ok(());
@@ -1600,44 +2001,25 @@ impl TextCodeGen {
                     .unwrap_or_else(|| self.infer_call_return_type(fn_name, func));
 
                 // Generate unique base name for this call instruction
-                let call_base = dest.as_ref().map(|d| d.name.clone()).unwrap_or_else(|| format!("call_{}", fn_name));
-
-                // Emit loads for local variables used as arguments
-                let mut arg_vals: Vec<(String, String)> = Vec::new(); // (type, value)
-                for (i, arg) in args.iter().enumerate() {
-                    let ty = match arg {
-                        Operand::Constant(c) => self.constant_type(c),
-                        Operand::Place(p) => place_types.get(&p.name).copied()
-                            .unwrap_or_else(|| self.infer_place_type(p, func)),
-                    };
-
-                    let val = match arg {
-                        Operand::Place(p) if local_names.contains(&p.name) => {
-                            // Emit load from alloca (use call_base for uniqueness)
-                            let load_name = format!("{}.{}.arg{}", call_base, p.name, i);
-                            writeln!(out, "  %{} = load {}, ptr %{}.addr", load_name, ty, p.name)?;
-                            format!("%{}", load_name)
-                        }
-                        Operand::Constant(Constant::String(s)) => {
-                            // String constants need to be wrapped with bmb_string_from_cstr
-                            if let Some(global_name) = string_table.get(s) {
-                                let wrapper_name = format!("{}.strarg{}", call_base, i);
-                                writeln!(out, "  %{} = call ptr @bmb_string_from_cstr(ptr @{})", wrapper_name, global_name)?;
-                                format!("%{}", wrapper_name)
-                            } else {
-                                self.format_operand_with_strings(arg, string_table)
-                            }
-                        }
-                        _ => self.format_operand_with_strings(arg, string_table),
-                    };
-                    arg_vals.push((ty.to_string(), val));
+                // (calls without a destination, e.g. repeated print_str("..."), need a counter)
+                let call_base = dest.as_ref().map(|d| d.name.clone())
+                    .unwrap_or_else(|| self.unique_name(&format!("call_{}", fn_name), name_counts));
+
+                let args_str = self.call_args(out, args, &call_base, func, place_types, local_names, string_table)?;
+
+                // A struct result is written to the destination's slot
+                if let Some(d) = dest
+                    && let Some(MirType::Struct { name, .. }) = self.struct_type(d, func)
+                {
+                    let slot = format!("%{}.slot", d.name);
+                    let all_args: Vec<String> = std::iter::once(format!("ptr sret(%struct.{}) {}", name, slot))
+                        .chain(args_str)
+                        .collect();
+                    writeln!(out, "  call void @{}({})", fn_name, all_args.join(", "))?;
+                    self.define_struct_place(out, d, &slot, local_names, name_counts)?;
+                    return Ok(());
                 }
 
-                let args_str: Vec<String> = arg_vals
-                    .iter()
-                    .map(|(ty, val)| format!("{} {}", ty, val))
-                    .collect();
-
                 if ret_ty == "void" {
                     writeln!(
                         out,
@@ -1681,6 +2063,29 @@ impl TextCodeGen {
                 }
             }
 
+            MirInst::CallIndirect { dest, callee, args } => {
+                let ret_ty = dest.as_ref()
+                    .map_or("void", |d| place_types.get(&d.name).copied().unwrap_or_else(|| self.infer_place_type(d, func)));
+                let call_base = dest.as_ref().map(|d| d.name.clone())
+                    .unwrap_or_else(|| self.unique_name("call_indirect", name_counts));
+                let addr = self.operand_value(out, callee, "i64", &format!("{}.fn", call_base), local_names, string_table)?;
+                writeln!(out, "  %{}.fnptr = inttoptr i64 {} to ptr", call_base, addr)?;
+                let args_str = self.call_args(out, args, &call_base, func, place_types, local_names, string_table)?;
+                match dest {
+                    Some(d) if local_names.contains(&d.name) => {
+                        writeln!(out, "  %{}.call = call {} %{}.fnptr({})", d.name, ret_ty, call_base, args_str.join(", "))?;
+                        writeln!(out, "  store {} %{}.call, ptr %{}.addr", ret_ty, d.name, d.name)?;
+                    }
+                    Some(d) => {
+                        let dest_name = self.unique_name(&d.name, name_counts);
+                        writeln!(out, "  %{} = call {} %{}.fnptr({})", dest_name, ret_ty, call_base, args_str.join(", "))?;
+                    }
+                    None => {
+                        writeln!(out, "  call void %{}.fnptr({})", call_base, args_str.join(", "))?;
+                    }
+                }
+            }
+
             MirInst::Phi { dest, values } => {
                 let dest_name = self.unique_name(&dest.name, name_counts);
                 // PHI nodes must come at the start of a basic block
@@ -1738,56 +2143,177 @@ impl TextCodeGen {
 
             // v0.19.0: Struct operations
             MirInst::StructInit { dest, struct_name, fields } => {
-                // In LLVM, we allocate space for the struct and store each field
-                // For now, treat struct as a pointer (i64) and use insertvalue
+                let Some(MirType::Struct { fields: layout, .. }) = self.struct_type(dest, func) else {
+                    return Err(TextCodeGenError::UnknownStruct(struct_name.clone()));
+                };
                 writeln!(out, "  ; struct {} init with {} fields", struct_name, fields.len())?;
-                // Create zeroinitializer and insertvalue for each field
-                writeln!(out, "  %{} = alloca i64, i32 {}", dest.name, fields.len().max(1))?;
-                for (i, (field_name, value)) in fields.iter().enumerate() {
-                    let val_str = self.format_operand(value);
-                    writeln!(out, "  ; field {} = {}", field_name, val_str)?;
-                    let ty = self.infer_operand_type(value, func);
-                    writeln!(out, "  %{}_f{} = getelementptr i64, ptr %{}, i32 {}",
-                             dest.name, i, dest.name, i)?;
-                    writeln!(out, "  store {} {}, ptr %{}_f{}", ty, val_str, dest.name, i)?;
+                let struct_ty = format!("%struct.{}", struct_name);
+                let slot = format!("%{}.slot", dest.name);
+
+                // (field index, field type, value), in layout order
+                let mut values: Vec<(usize, &MirType, &Operand)> = fields
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        let index = layout.iter().position(|(field, _)| field == name)?;
+                        Some((index, &*layout[index].1, value))
+                    })
+                    .collect();
+                values.sort_by_key(|(index, _, _)| *index);
+
+                let all_constant = values.len() == layout.len()
+                    && values.iter().all(|(_, _, value)| {
+                        matches!(value, Operand::Constant(c) if !matches!(c, Constant::String(_)))
+                    });
+                if all_constant {
+                    // Store the whole value as one aggregate constant
+                    let elems: Vec<String> = values
+                        .iter()
+                        .map(|(_, ty, value)| format!("{} {}", self.llvm_field_type(ty), self.format_operand(value)))
+                        .collect();
+                    writeln!(out, "  store {} {{ {} }}, ptr {}", struct_ty, elems.join(", "), slot)?;
+                } else {
+                    for (index, ty, value) in values {
+                        let field_ptr = format!("%{}.f{}", dest.name, index);
+                        writeln!(out, "  {} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}",
+                                 field_ptr, struct_ty, slot, index)?;
+                        let tmp = format!("{}.f{}", dest.name, index);
+                        self.emit_field_store(out, ty, value, &field_ptr, &tmp, local_names, string_table)?;
+                    }
+                }
+                self.define_struct_place(out, dest, &slot, local_names, name_counts)?;
+            }
+
+            MirInst::FieldAccess { dest, base, field } if enum_field_word(field).is_some()
+                && place_types.get(&base.name).copied().unwrap_or_else(|| self.infer_place_type(base, func)) == "i64" =>
+            {
+                // Enum values are i64 handles to a heap block of words (tag, payload...)
+                let word = enum_field_word(field).unwrap_or(0);
+                writeln!(out, "  ; enum field .{} from %{}", field, base.name)?;
+                let handle = if local_names.contains(&base.name) {
+                    writeln!(out, "  %{}.handle = load i64, ptr %{}.addr", dest.name, base.name)?;
+                    format!("%{}.handle", dest.name)
+                } else {
+                    format!("%{}", base.name)
+                };
+                writeln!(out, "  %{}.ptr = inttoptr i64 {} to ptr", dest.name, handle)?;
+                writeln!(out, "  %{}.slot = getelementptr i64, ptr %{}.ptr, i32 {}", dest.name, dest.name, word)?;
+                // Undo the widening `EnumVariant` applied to the payload
+                let ty = place_types.get(&dest.name).copied().unwrap_or("i64");
+                let cast = match ty {
+                    "i1" | "i32" => Some("trunc"),
+                    "ptr" => Some("inttoptr"),
+                    "double" => Some("bitcast"),
+                    _ => None,
+                };
+                let (value, ty) = match cast {
+                    Some(cast) => {
+                        writeln!(out, "  %{}.word = load i64, ptr %{}.slot", dest.name, dest.name)?;
+                        writeln!(out, "  %{} = {} i64 %{}.word to {}", dest.name, cast, dest.name, ty)?;
+                        (format!("%{}", dest.name), ty)
+                    }
+                    None => {
+                        writeln!(out, "  %{} = load i64, ptr %{}.slot", dest.name, dest.name)?;
+                        (format!("%{}", dest.name), "i64")
+                    }
+                };
+                if local_names.contains(&dest.name) {
+                    writeln!(out, "  store {} {}, ptr %{}.addr", ty, value, dest.name)?;
                 }
             }
 
             MirInst::FieldAccess { dest, base, field } => {
-                // Load field from struct pointer
+                let Some((index, struct_name, field_ty)) = self.struct_field(base, field, func) else {
+                    // Not a known struct: load from the base itself
+                    writeln!(out, "  ; field access .{} from %{}", field, base.name)?;
+                    writeln!(out, "  %{} = load i64, ptr %{}", dest.name, base.name)?;
+                    return Ok(());
+                };
                 writeln!(out, "  ; field access .{} from %{}", field, base.name)?;
-                // For now, just load from base (simplified - needs field offset calculation)
-                writeln!(out, "  %{} = load i64, ptr %{}", dest.name, base.name)?;
+                let base_ptr = self.operand_value(
+                    out, &Operand::Place(base.clone()), "ptr", &format!("{}.base", dest.name), local_names, string_table,
+                )?;
+                let field_ptr = format!("%{}.fptr", dest.name);
+                writeln!(out, "  {} = getelementptr inbounds %struct.{}, ptr {}, i32 0, i32 {}",
+                         field_ptr, struct_name, base_ptr, index)?;
+                if let MirType::Struct { .. } = field_ty {
+                    // A nested struct is used in place, through its address
+                    self.define_struct_place(out, dest, &field_ptr, local_names, name_counts)?;
+                } else {
+                    let ty = self.llvm_field_type(field_ty);
+                    if local_names.contains(&dest.name) {
+                        writeln!(out, "  %{}.fval = load {}, ptr {}", dest.name, ty, field_ptr)?;
+                        writeln!(out, "  store {} %{}.fval, ptr %{}.addr", ty, dest.name, dest.name)?;
+                    } else {
+                        let dest_name = self.unique_name(&dest.name, name_counts);
+                        writeln!(out, "  %{} = load {}, ptr {}", dest_name, ty, field_ptr)?;
+                    }
+                }
             }
 
             MirInst::FieldStore { base, field, value } => {
-                // Store value to field in struct pointer
-                let val_str = self.format_operand(value);
-                let ty = self.infer_operand_type(value, func);
-                writeln!(out, "  ; field store .{} = {}", field, val_str)?;
-                writeln!(out, "  store {} {}, ptr %{}", ty, val_str, base.name)?;
+                let Some((index, struct_name, field_ty)) = self.struct_field(base, field, func) else {
+                    // Not a known struct: store to the base itself
+                    let val_str = self.format_operand(value);
+                    let ty = self.infer_operand_type(value, func);
+                    writeln!(out, "  ; field store .{} = {}", field, val_str)?;
+                    writeln!(out, "  store {} {}, ptr %{}", ty, val_str, base.name)?;
+                    return Ok(());
+                };
+                writeln!(out, "  ; field store .{} of %{}", field, base.name)?;
+                let tmp = self.unique_name(&format!("{}.{}.set", base.name, field), name_counts);
+                let base_ptr = self.operand_value(
+                    out, &Operand::Place(base.clone()), "ptr", &format!("{}.base", tmp), local_names, string_table,
+                )?;
+                writeln!(out, "  %{}.fptr = getelementptr inbounds %struct.{}, ptr {}, i32 0, i32 {}",
+                         tmp, struct_name, base_ptr, index)?;
+                self.emit_field_store(out, field_ty, value, &format!("%{}.fptr", tmp), &tmp, local_names, string_table)?;
             }
 
             // v0.19.1: Enum variant
             MirInst::EnumVariant { dest, enum_name, variant, args } => {
-                // Enums are represented as tagged unions:
-                // - First word: discriminant (variant index)
+                // Enums are represented as tagged unions on the heap, passed as i64 handles:
+                // - First word: discriminant (see `variant_discriminant`)
                 // - Following words: variant data
                 writeln!(out, "  ; enum {}::{} with {} args", enum_name, variant, args.len())?;
-                // Allocate space for enum (discriminant + max variant size)
-                let size = 1 + args.len().max(1);
-                writeln!(out, "  %{} = alloca i64, i32 {}", dest.name, size)?;
-                // Store discriminant (simplified: hash of variant name)
-                let discriminant: i64 = variant.bytes().fold(0i64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as i64));
-                writeln!(out, "  %{}_disc = getelementptr i64, ptr %{}, i32 0", dest.name, dest.name)?;
-                writeln!(out, "  store i64 {}, ptr %{}_disc", discriminant, dest.name)?;
+                let size = 1 + args.len();
+                writeln!(out, "  %{}.mem = call ptr @malloc(i64 {})", dest.name, size * 8)?;
+                writeln!(out, "  store i64 {}, ptr %{}.mem", variant_discriminant(variant), dest.name)?;
                 // Store variant arguments
                 for (i, arg) in args.iter().enumerate() {
-                    let arg_str = self.format_operand(arg);
-                    let ty = self.infer_operand_type(arg, func);
-                    writeln!(out, "  %{}_a{} = getelementptr i64, ptr %{}, i32 {}",
+                    let ty = match arg {
+                        Operand::Place(p) => place_types.get(&p.name).copied()
+                            .unwrap_or_else(|| self.infer_place_type(p, func)),
+                        Operand::Constant(c) => self.constant_type(c),
+                    };
+                    let arg_str = match arg {
+                        Operand::Place(p) if local_names.contains(&p.name) => {
+                            writeln!(out, "  %{}.a{}.load = load {}, ptr %{}.addr", dest.name, i, ty, p.name)?;
+                            format!("%{}.a{}.load", dest.name, i)
+                        }
+                        _ => self.format_operand_with_strings(arg, string_table),
+                    };
+                    // Every payload slot is one i64 word
+                    let cast = match ty {
+                        "i1" => Some("zext"),
+                        "i32" => Some("sext"),
+                        "ptr" => Some("ptrtoint"),
+                        "double" => Some("bitcast"),
+                        _ => None,
+                    };
+                    let word = match cast {
+                        Some(cast) => {
+                            writeln!(out, "  %{}.a{}.word = {} {} {} to i64", dest.name, i, cast, ty, arg_str)?;
+                            format!("%{}.a{}.word", dest.name, i)
+                        }
+                        None => arg_str,
+                    };
+                    writeln!(out, "  %{}_a{} = getelementptr i64, ptr %{}.mem, i32 {}",
                              dest.name, i, dest.name, i + 1)?;
-                    writeln!(out, "  store {} {}, ptr %{}_a{}", ty, arg_str, dest.name, i)?;
+                    writeln!(out, "  store i64 {}, ptr %{}_a{}", word, dest.name, i)?;
+                }
+                writeln!(out, "  %{} = ptrtoint ptr %{}.mem to i64", dest.name, dest.name)?;
+                if local_names.contains(&dest.name) {
+                    writeln!(out, "  store i64 %{}, ptr %{}.addr", dest.name, dest.name)?;
                 }
             }
 
@@ -1838,7 +2364,7 @@ impl TextCodeGen {
     ) -> TextCodeGenResult<()> {
         match term {
             Terminator::Return(None) => {
-                if func.ret_ty == MirType::Unit {
+                if matches!(func.ret_ty, MirType::Unit | MirType::Struct { .. }) {
                     writeln!(out, "  ret void")?;
                 } else {
                     // Should not happen - return with no value for non-unit type
@@ -1846,6 +2372,21 @@ impl TextCodeGen {
                 }
             }
 
+            Terminator::Return(Some(val)) if matches!(func.ret_ty, MirType::Struct { .. }) => {
+                // Copy the struct into the caller's sret slot
+                let struct_ty = self.llvm_field_type(&func.ret_ty);
+                let src = match val {
+                    Operand::Place(p) if local_names.contains(&p.name) => {
+                        writeln!(out, "  %_ret_ptr = load ptr, ptr %{}.addr", p.name)?;
+                        "%_ret_ptr".to_string()
+                    }
+                    _ => self.format_operand(val),
+                };
+                writeln!(out, "  %_ret_val = load {}, ptr {}", struct_ty, src)?;
+                writeln!(out, "  store {} %_ret_val, ptr %_sret", struct_ty)?;
+                writeln!(out, "  ret void")?;
+            }
+
             Terminator::Return(Some(val)) => {
                 let ty = self.mir_type_to_llvm(&func.ret_ty);
                 // Special handling for string constant returns
@@ -1924,6 +2465,51 @@ impl TextCodeGen {
         Ok(())
     }
 
+    /// `type value` for each call argument, loading locals from their slots
+    /// and wrapping string constants as BMB strings
+    #[allow(clippy::too_many_arguments)]
+    fn call_args(
+        &self,
+        out: &mut String,
+        args: &[Operand],
+        call_base: &str,
+        func: &MirFunction,
+        place_types: &HashMap<String, &'static str>,
+        local_names: &std::collections::HashSet<String>,
+        string_table: &HashMap<String, String>,
+    ) -> TextCodeGenResult<Vec<String>> {
+        let mut args_str = Vec::with_capacity(args.len());
+        for (i, arg) in args.iter().enumerate() {
+            let ty = match arg {
+                Operand::Constant(c) => self.constant_type(c),
+                Operand::Place(p) => place_types.get(&p.name).copied()
+                    .unwrap_or_else(|| self.infer_place_type(p, func)),
+            };
+
+            let val = match arg {
+                Operand::Place(p) if local_names.contains(&p.name) => {
+                    // Emit load from alloca (use call_base for uniqueness)
+                    let load_name = format!("{}.{}.arg{}", call_base, p.name, i);
+                    writeln!(out, "  %{} = load {}, ptr %{}.addr", load_name, ty, p.name)?;
+                    format!("%{}", load_name)
+                }
+                Operand::Constant(Constant::String(s)) => {
+                    // String constants need to be wrapped with bmb_string_from_cstr
+                    if let Some(global_name) = string_table.get(s) {
+                        let wrapper_name = format!("{}.strarg{}", call_base, i);
+                        writeln!(out, "  %{} = call ptr @bmb_string_from_cstr(ptr @{})", wrapper_name, global_name)?;
+                        format!("%{}", wrapper_name)
+                    } else {
+                        self.format_operand_with_strings(arg, string_table)
+                    }
+                }
+                _ => self.format_operand_with_strings(arg, string_table),
+            };
+            args_str.push(format!("{} {}", ty, val));
+        }
+        Ok(args_str)
+    }
+
     /// Convert MIR type to LLVM type string
     fn mir_type_to_llvm(&self, ty: &MirType) -> &'static str {
         match ty {
@@ -1945,7 +2531,105 @@ impl TextCodeGen {
             MirType::Array { .. } => "ptr",
             // v0.64: Character type (32-bit Unicode codepoint)
             MirType::Char => "i32",
+            // Closures and tuples are i64 handles to blocks of words
+            MirType::Fn { .. } | MirType::Tuple(_) => "i64",
+        }
+    }
+
+    /// LLVM type of a struct field: nested structs are stored inline
+    fn llvm_field_type(&self, ty: &MirType) -> String {
+        match ty {
+            MirType::Struct { name, .. } => format!("%struct.{}", name),
+            // Unit values are an `i8 0` (see `constant_type`)
+            MirType::Unit => "i8".to_string(),
+            _ => self.mir_type_to_llvm(ty).to_string(),
+        }
+    }
+
+    /// Index, struct name and type of `base.field` when `base` holds a struct
+    fn struct_field<'f>(&self, base: &Place, field: &str, func: &'f MirFunction) -> Option<(usize, &'f str, &'f MirType)> {
+        let Some(MirType::Struct { name, fields }) = self.struct_type(base, func) else {
+            return None;
+        };
+        let index = fields.iter().position(|(f, _)| f == field)?;
+        Some((index, name, &fields[index].1))
+    }
+
+    /// `op` as an LLVM value of type `ty`: a local is loaded from its slot
+    /// into `%{tmp}`, a string constant becomes a BMB string
+    fn operand_value(
+        &self,
+        out: &mut String,
+        op: &Operand,
+        ty: &str,
+        tmp: &str,
+        local_names: &std::collections::HashSet<String>,
+        string_table: &HashMap<String, String>,
+    ) -> TextCodeGenResult<String> {
+        match op {
+            Operand::Place(p) if local_names.contains(&p.name) => {
+                writeln!(out, "  %{} = load {}, ptr %{}.addr", tmp, ty, p.name)?;
+                Ok(format!("%{}", tmp))
+            }
+            Operand::Constant(Constant::String(s)) if string_table.contains_key(s) => {
+                writeln!(out, "  %{} = call ptr @bmb_string_from_cstr(ptr @{})", tmp, string_table[s])?;
+                Ok(format!("%{}", tmp))
+            }
+            _ => Ok(self.format_operand(op)),
+        }
+    }
+
+    /// Store `value` into the struct field of type `ty` at `field_ptr`; a
+    /// nested struct is copied in
+    #[allow(clippy::too_many_arguments)]
+    fn emit_field_store(
+        &self,
+        out: &mut String,
+        ty: &MirType,
+        value: &Operand,
+        field_ptr: &str,
+        tmp: &str,
+        local_names: &std::collections::HashSet<String>,
+        string_table: &HashMap<String, String>,
+    ) -> TextCodeGenResult<()> {
+        let field_ty = self.llvm_field_type(ty);
+        if let MirType::Struct { .. } = ty {
+            let src = self.operand_value(out, value, "ptr", &format!("{}.src", tmp), local_names, string_table)?;
+            writeln!(out, "  %{}.agg = load {}, ptr {}", tmp, field_ty, src)?;
+            writeln!(out, "  store {} %{}.agg, ptr {}", field_ty, tmp, field_ptr)?;
+        } else {
+            let val = self.operand_value(out, value, &field_ty, &format!("{}.val", tmp), local_names, string_table)?;
+            writeln!(out, "  store {} {}, ptr {}", field_ty, val, field_ptr)?;
+        }
+        Ok(())
+    }
+
+    /// Make `dest` hold the struct at `ptr`
+    fn define_struct_place(
+        &self,
+        out: &mut String,
+        dest: &Place,
+        ptr: &str,
+        local_names: &std::collections::HashSet<String>,
+        name_counts: &mut HashMap<String, u32>,
+    ) -> TextCodeGenResult<()> {
+        if local_names.contains(&dest.name) {
+            writeln!(out, "  store ptr {}, ptr %{}.addr", ptr, dest.name)?;
+        } else {
+            let dest_name = self.unique_name(&dest.name, name_counts);
+            writeln!(out, "  %{} = select i1 true, ptr {}, ptr null", dest_name, ptr)?;
         }
+        Ok(())
+    }
+
+    /// Struct type of a parameter or local holding a struct
+    fn struct_type<'f>(&self, place: &Place, func: &'f MirFunction) -> Option<&'f MirType> {
+        func.params
+            .iter()
+            .chain(&func.locals)
+            .find(|(name, _)| *name == place.name)
+            .map(|(_, ty)| ty)
+            .filter(|ty| matches!(ty, MirType::Struct { .. }))
     }
 
     /// Get LLVM type for a constant
@@ -1958,6 +2642,7 @@ impl TextCodeGen {
             // v0.64: Character constant (32-bit Unicode codepoint)
             Constant::Char(_) => "i32",
             Constant::Unit => "i8",
+            Constant::FnAddr(_) => "i64",
         }
     }
 
@@ -1985,6 +2670,7 @@ impl TextCodeGen {
             // v0.64: Character constant (Unicode codepoint)
             Constant::Char(c) => (*c as u32).to_string(),
             Constant::Unit => "0".to_string(),
+            Constant::FnAddr(name) => format!("ptrtoint (ptr @{} to i64)", name),
         }
     }
 
@@ -2032,6 +2718,16 @@ impl TextCodeGen {
         "i64"
     }
 
+    /// Whether `lhs op rhs` is on u32/u64 values; LLVM integers carry no
+    /// sign, so this reads the MIR type of whichever operand is a place
+    fn is_unsigned_binop(&self, lhs: &Operand, rhs: &Operand, func: &MirFunction) -> bool {
+        [lhs, rhs].into_iter().any(|op| match op {
+            Operand::Place(p) => func.params.iter().chain(&func.locals)
+                .any(|(name, ty)| name == &p.name && ty.is_unsigned()),
+            Operand::Constant(_) => false,
+        })
+    }
+
     /// Infer type of an operand
     fn infer_operand_type(&self, op: &Operand, func: &MirFunction) -> &'static str {
         match op {
@@ -2045,13 +2741,15 @@ impl TextCodeGen {
         // Built-in functions
         match fn_name {
             // Void return
-            "println" | "print" | "assert" | "bmb_print_str" | "print_str" => "void",
+            "println" | "print" | "assert" | "bmb_print_str" | "print_str"
+            | "bmb_contract_fail" | "flush" => "void",
 
             // i64 return - Basic
             "read_int" | "abs" | "bmb_abs" | "min" | "max" | "f64_to_i64" => "i64",
 
             // f64 return - Math intrinsics (v0.34)
             "sqrt" | "i64_to_f64" => "double",
+            "sin" | "cos" | "pow" | "floor" | "ceil" | "round" | "ln" | "exp" => "double",
 
             // i64 return - String operations (both full and wrapper names)
             // v0.46: byte_at added as preferred name (same as interpreter)
@@ -2074,6 +2772,18 @@ impl TextCodeGen {
             | "bmb_string_concat" | "bmb_chr"
             | "slice" | "chr" => "ptr",
 
+            // String search and transformation
+            "str_contains" | "str_starts_with" | "str_ends_with" => "i1",
+            "str_index_of" | "parse_int" | "str_split" => "i64",
+            "str_replace" | "str_trim" | "str_from_id" => "ptr",
+
+            // HashMap and String-valued vec builtins
+            "hashmap_new" | "hashmap_insert" | "hashmap_get" | "hashmap_contains" | "hashmap_remove"
+            | "hashmap_len" | "hashmap_insert_str_key" | "hashmap_get_str_key"
+            | "hashmap_contains_str_key" => "i64",
+            "hashmap_get_str" | "vec_get_str" => "ptr",
+            "hashmap_free" | "hashmap_insert_str" | "vec_push_str" => "void",
+
             // ptr return - File I/O (both full and wrapper names)
             "bmb_read_file" | "read_file" => "ptr",
 
@@ -2115,14 +2825,20 @@ impl TextCodeGen {
             MirBinOp::SubWrap => ("sub", true),
             MirBinOp::MulWrap => ("mul", true),
 
-            // v0.38: Checked arithmetic - use intrinsics for overflow detection
-            // For now, same as wrapping (full checked impl needs Option handling)
+            // v0.38: Checked arithmetic - integer operands use `llvm.{s,u}*.with.overflow`
+            // in `emit_instruction_with_strings`; this is the wrapped value
             MirBinOp::AddChecked => ("add", true),
             MirBinOp::SubChecked => ("sub", true),
             MirBinOp::MulChecked => ("mul", true),
 
-            // v0.38: Saturating arithmetic - clamps to min/max on overflow
-            // LLVM doesn't have native saturating ops; use sadd.sat intrinsics in future
+            // Overflow tests - the overflow bit of `llvm.{s,u}*.with.overflow`,
+            // emitted in `emit_instruction_with_strings`
+            MirBinOp::AddOverflows => ("add", false),
+            MirBinOp::SubOverflows => ("sub", false),
+            MirBinOp::MulOverflows => ("mul", false),
+
+            // v0.38: Saturating arithmetic - integer operands use `llvm.s*.sat` or an
+            // overflow clamp in `emit_instruction_with_strings`
             MirBinOp::AddSat => ("add", true),
             MirBinOp::SubSat => ("sub", true),
             MirBinOp::MulSat => ("mul", true),
@@ -2141,6 +2857,14 @@ impl TextCodeGen {
             MirBinOp::Le => ("icmp sle", false),
             MirBinOp::Ge => ("icmp sge", false),
 
+            // Unsigned integer division and comparison (u32, u64)
+            MirBinOp::UDiv => ("udiv", true),
+            MirBinOp::UMod => ("urem", true),
+            MirBinOp::ULt => ("icmp ult", false),
+            MirBinOp::UGt => ("icmp ugt", false),
+            MirBinOp::ULe => ("icmp ule", false),
+            MirBinOp::UGe => ("icmp uge", false),
+
             // Floating-point comparison - result is i1
             MirBinOp::FEq => ("fcmp oeq", false),
             MirBinOp::FNe => ("fcmp one", false),
@@ -2156,6 +2880,7 @@ impl TextCodeGen {
             // v0.32: Shift operators - result type same as operand
             MirBinOp::Shl => ("shl", true),
             MirBinOp::Shr => ("ashr", true),  // arithmetic shift right (preserves sign)
+            MirBinOp::UShr => ("lshr", true),  // logical shift right (u32, u64)
 
             // v0.36: Bitwise operators - result type same as operand
             MirBinOp::Band => ("and", true),
@@ -2175,6 +2900,174 @@ impl Default for TextCodeGen {
     }
 }
 
+/// Source file that debug locations are resolved against
+struct DebugSource {
+    filename: String,
+    directory: String,
+    /// Byte offset at which each line starts
+    line_starts: Vec<usize>,
+}
+
+impl DebugSource {
+    fn new(path: &Path, source: &str) -> Self {
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let filename = absolute
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let directory = absolute
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { filename, directory, line_starts }
+    }
+
+    /// 1-based line and column of a byte offset
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset).max(1);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+/// Metadata nodes of a module emitted with debug info
+struct DebugMetadata {
+    nodes: Vec<String>,
+    locations: HashMap<(usize, usize, usize), usize>,
+}
+
+impl DebugMetadata {
+    const COMPILE_UNIT: usize = 0;
+    const FILE: usize = 1;
+    const SUBROUTINE_TYPE: usize = 4;
+
+    fn new(source: &DebugSource) -> Self {
+        let nodes = vec![
+            format!(
+                "distinct !DICompileUnit(language: DW_LANG_C, file: !{}, producer: \"bmb\", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)",
+                Self::FILE
+            ),
+            format!(
+                "!DIFile(filename: \"{}\", directory: \"{}\")",
+                escape_metadata_string(&source.filename),
+                escape_metadata_string(&source.directory)
+            ),
+            "!{i32 7, !\"Dwarf Version\", i32 4}".to_string(),
+            "!{i32 2, !\"Debug Info Version\", i32 3}".to_string(),
+            "!DISubroutineType(types: !{})".to_string(),
+        ];
+        Self { nodes, locations: HashMap::new() }
+    }
+
+    fn add(&mut self, node: String) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn location(&mut self, line: usize, col: usize, scope: usize) -> usize {
+        if let Some(&id) = self.locations.get(&(line, col, scope)) {
+            return id;
+        }
+        let id = self.add(format!("!DILocation(line: {}, column: {}, scope: !{})", line, col, scope));
+        self.locations.insert((line, col, scope), id);
+        id
+    }
+
+    /// Copy the emitted `text` of `func` to `out`, giving it a subprogram and
+    /// replacing location markers with `!dbg` attachments on the instructions
+    /// that follow them
+    fn attach(
+        &mut self,
+        out: &mut String,
+        text: &str,
+        func: &MirFunction,
+        source: &DebugSource,
+    ) -> TextCodeGenResult<()> {
+        // The first location of a function is its name
+        let start = func
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .find_map(|inst| match inst {
+                MirInst::DebugLoc { span } => Some(span.start),
+                _ => None,
+            })
+            .unwrap_or(0);
+        let (line, _) = source.line_col(start);
+        let linkage = if func.name == "main" {
+            ", linkageName: \"bmb_user_main\"".to_string()
+        } else {
+            String::new()
+        };
+        let subprogram = self.add(format!(
+            "distinct !DISubprogram(name: \"{}\"{}, scope: !{file}, file: !{file}, line: {line}, type: !{}, scopeLine: {line}, spFlags: DISPFlagDefinition, unit: !{})",
+            escape_metadata_string(&func.name),
+            linkage,
+            Self::SUBROUTINE_TYPE,
+            Self::COMPILE_UNIT,
+            file = Self::FILE,
+            line = line,
+        ));
+
+        let mut loc = self.location(line, 1, subprogram);
+        for text_line in text.lines() {
+            if let Some(offset) = text_line.strip_prefix(DEBUG_LOC_MARKER) {
+                let (line, col) = source.line_col(offset.parse().unwrap_or(0));
+                loc = self.location(line, col, subprogram);
+            } else if let Some(define) = text_line.strip_suffix(" {").filter(|l| l.starts_with("define ")) {
+                writeln!(out, "{} !dbg !{} {{", define, subprogram)?;
+            } else if text_line == "  ]" || (text_line.starts_with("  ")
+                && !text_line.starts_with("    ")
+                && !text_line.starts_with("  ;")
+                && !text_line.starts_with("  switch "))
+            {
+                // Switch cases share the attachment placed after their `]`
+                writeln!(out, "{}, !dbg !{}", text_line, loc)?;
+            } else {
+                writeln!(out, "{}", text_line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&self, out: &mut String) -> TextCodeGenResult<()> {
+        writeln!(out, "!llvm.dbg.cu = !{{!{}}}", Self::COMPILE_UNIT)?;
+        writeln!(out, "!llvm.module.flags = !{{!2, !3}}")?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            writeln!(out, "!{} = {}", id, node)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escape a string for use in a metadata string literal
+fn escape_metadata_string(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'"' && b != b'\\' => (b as char).to_string(),
+            _ => format!("\\{:02X}", b),
+        })
+        .collect()
+}
+
+/// LLVM intrinsic implementing a BMB f64 math builtin with the given arity
+fn f64_math_intrinsic(fn_name: &str, arity: usize) -> Option<&'static str> {
+    match (fn_name, arity) {
+        ("sqrt", 1) => Some("llvm.sqrt.f64"),
+        ("sin", 1) => Some("llvm.sin.f64"),
+        ("cos", 1) => Some("llvm.cos.f64"),
+        ("floor", 1) => Some("llvm.floor.f64"),
+        ("ceil", 1) => Some("llvm.ceil.f64"),
+        ("round", 1) => Some("llvm.round.f64"),
+        ("ln", 1) => Some("llvm.log.f64"),
+        ("exp", 1) => Some("llvm.exp.f64"),
+        ("pow", 2) => Some("llvm.pow.f64"),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2204,6 +3097,8 @@ mod tests {
                 postconditions: vec![],
                 is_pure: false,
                 is_const: false,
+                is_inline: false,
+                is_public: false,
             }],
             extern_fns: vec![],
         };
@@ -2215,4 +3110,305 @@ mod tests {
         assert!(ir.contains("%_t0 = add nsw i64 %a, %b"));  // nsw for optimization
         assert!(ir.contains("ret i64 %_t0"));
     }
+
+    #[test]
+    fn test_target_triple_is_emitted_and_checked() {
+        let program = MirProgram { functions: vec![], extern_fns: vec![] };
+        let ir = TextCodeGen::with_target("aarch64-unknown-linux-gnu").generate(&program).unwrap();
+        assert!(ir.contains("target triple = \"aarch64-unknown-linux-gnu\""));
+
+        for ok in ["x86_64-pc-windows-msvc", "armv7-unknown-linux-gnueabihf", "aarch64-linux-gnu", "riscv64gc-unknown-none-elf"] {
+            assert!(check_target_triple(ok).is_ok(), "{}", ok);
+        }
+        let reason = |triple: &str| check_target_triple(triple).unwrap_err().to_string();
+        assert_eq!(reason("sparkle-unknown-linux"), "invalid target triple `sparkle-unknown-linux`: unknown architecture `sparkle`");
+        assert!(reason("x86_64").contains("expected `arch-vendor-os[-env]`"));
+        assert!(reason("x86_64--linux").contains("`` is not a valid component"));
+        assert!(reason("x86_64-unknown-linux gnu").contains("`linux gnu` is not a valid component"));
+        assert!(TextCodeGen::with_target("nope").generate(&program).is_err());
+    }
+
+    #[test]
+    fn test_math_intrinsic_calls() {
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "power".to_string(),
+                params: vec![
+                    ("x".to_string(), MirType::F64),
+                    ("y".to_string(), MirType::F64),
+                ],
+                ret_ty: MirType::F64,
+                locals: vec![],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![
+                        MirInst::Call {
+                            dest: Some(Place::new("_t0")),
+                            func: "pow".to_string(),
+                            args: vec![
+                                Operand::Place(Place::new("x")),
+                                Operand::Place(Place::new("y")),
+                            ],
+                        },
+                        MirInst::Call {
+                            dest: Some(Place::new("_t1")),
+                            func: "ln".to_string(),
+                            args: vec![Operand::Place(Place::new("_t0"))],
+                        },
+                    ],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("_t1")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let codegen = TextCodeGen::new();
+        let ir = codegen.generate(&program).unwrap();
+
+        assert!(ir.contains("declare double @llvm.pow.f64(double, double)"));
+        assert!(ir.contains("%_t0 = call double @llvm.pow.f64(double %x, double %y)"));
+        assert!(ir.contains("%_t1 = call double @llvm.log.f64(double %_t0)"));
+    }
+
+    #[test]
+    fn test_overflow_operator_intrinsics() {
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "ops".to_string(),
+                params: vec![
+                    ("a".to_string(), MirType::I64),
+                    ("b".to_string(), MirType::I64),
+                ],
+                ret_ty: MirType::I64,
+                locals: vec![],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![
+                        MirInst::BinOp {
+                            dest: Place::new("_t0"),
+                            op: MirBinOp::AddChecked,
+                            lhs: Operand::Place(Place::new("a")),
+                            rhs: Operand::Place(Place::new("b")),
+                        },
+                        MirInst::BinOp {
+                            dest: Place::new("_t1"),
+                            op: MirBinOp::MulSat,
+                            lhs: Operand::Place(Place::new("a")),
+                            rhs: Operand::Place(Place::new("b")),
+                        },
+                    ],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("_t1")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let codegen = TextCodeGen::new();
+        let ir = codegen.generate(&program).unwrap();
+
+        // Checked ops box `Some`/`None` with the overflow bit selecting the tag
+        assert!(ir.contains("%_t0.ovf = call {i64, i1} @llvm.sadd.with.overflow.i64(i64 %a, i64 %b)"));
+        assert!(ir.contains("%_t0.tag = select i1 %_t0.bit, i64 1034, i64 1036"));
+        assert!(ir.contains("%_t0 = ptrtoint ptr %_t0.mem to i64"));
+        // Saturating multiply clamps on overflow
+        assert!(ir.contains("%_t1 = select i1 %_t1.bit, i64 %_t1.clamp, i64 %_t1.val"));
+    }
+
+    #[test]
+    fn test_contract_fail_calls_get_unique_names() {
+        let fail_call = |msg: &str| MirInst::Call {
+            dest: None,
+            func: "bmb_contract_fail".to_string(),
+            args: vec![Operand::Constant(Constant::String(msg.to_string()))],
+        };
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "f".to_string(),
+                params: vec![],
+                ret_ty: MirType::Unit,
+                locals: vec![],
+                blocks: vec![
+                    BasicBlock {
+                        label: "entry".to_string(),
+                        instructions: vec![fail_call("first")],
+                        terminator: Terminator::Goto("next".to_string()),
+                    },
+                    BasicBlock {
+                        label: "next".to_string(),
+                        instructions: vec![fail_call("second")],
+                        terminator: Terminator::Unreachable,
+                    },
+                ],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let ir = TextCodeGen::new().generate(&program).unwrap();
+
+        assert!(ir.contains("declare void @bmb_contract_fail(ptr)"));
+        assert!(ir.contains("call void @bmb_contract_fail(ptr %call_bmb_contract_fail.strarg0)"));
+        assert!(ir.contains("call void @bmb_contract_fail(ptr %call_bmb_contract_fail_1.strarg0)"));
+    }
+
+    #[test]
+    fn test_debug_info_locations() {
+        use crate::ast::Span;
+
+        let source = "fn main() -> i64 = {\n    let x = 1;\n    x\n};\n";
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "main".to_string(),
+                params: vec![],
+                ret_ty: MirType::I64,
+                locals: vec![],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![
+                        MirInst::DebugLoc { span: Span::new(3, 7) },
+                        MirInst::DebugLoc { span: Span::new(25, 35) },
+                        MirInst::Const {
+                            dest: Place::new("x"),
+                            value: Constant::Int(1),
+                        },
+                    ],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("x")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let plain = TextCodeGen::new().generate(&program).unwrap();
+        assert!(!plain.contains("!dbg"));
+
+        let ir = TextCodeGen::new()
+            .with_debug_info(Path::new("/src/main.bmb"), source)
+            .generate(&program)
+            .unwrap();
+
+        assert!(ir.contains("!llvm.dbg.cu = !{!0}"));
+        assert!(ir.contains("!DIFile(filename: \"main.bmb\", directory: \"/src\")"));
+        assert!(ir.contains("define i64 @bmb_user_main() !dbg !5 {"));
+        assert!(ir.contains(
+            "!5 = distinct !DISubprogram(name: \"main\", linkageName: \"bmb_user_main\""
+        ));
+        assert!(ir.contains("%x = add i64 0, 1, !dbg !8"));
+        assert!(ir.contains("!8 = !DILocation(line: 2, column: 5, scope: !5)"));
+        assert!(ir.contains("ret i64 %x, !dbg !8"));
+        assert!(!ir.contains(DEBUG_LOC_MARKER));
+    }
+
+    #[test]
+    fn test_struct_layout_and_abi() {
+        let source = "struct Point { x: i64, y: i64 }
+struct Line { from: Point, to: Point, weight: f64 }
+fn origin() -> Point = new Point { x: 0, y: 0 };
+fn end_y(l: Line) -> i64 = l.to.y;
+fn main() -> i64 = end_y(new Line { from: origin(), to: new Point { x: 1, y: 2 }, weight: 0.5 });
+";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let ir = TextCodeGen::new().generate(&crate::mir::lower_program(&ast)).unwrap();
+
+        assert!(ir.contains("%struct.Line = type { %struct.Point, %struct.Point, double }"));
+        assert!(ir.contains("%struct.Point = type { i64, i64 }"));
+        // Struct results go through an sret pointer, arguments by pointer
+        assert!(ir.contains("define void @origin(ptr sret(%struct.Point) %_sret)"));
+        assert!(ir.contains("define i64 @end_y(ptr %l)"));
+        assert!(ir.contains("store ptr %_t0.slot, ptr %_t0.addr"));
+        assert!(ir.contains("store %struct.Point { i64 0, i64 0 }, ptr %_t0.slot"));
+        assert!(ir.contains("store %struct.Point %_ret_val, ptr %_sret"));
+        assert!(ir.contains("call void @origin(ptr sret(%struct.Point) %_t0.slot)"));
+        // Nested fields: the inner struct is addressed in place, then loaded from
+        assert!(ir.contains("getelementptr inbounds %struct.Line, ptr %l, i32 0, i32 1"));
+        assert!(ir.contains("getelementptr inbounds %struct.Point, ptr %_t1.base, i32 0, i32 1"));
+        assert!(ir.contains("load %struct.Point, ptr"));
+    }
+
+    #[test]
+    fn test_unsigned_and_narrow_integers() {
+        let source = "fn half(x: u64) -> u64 = x / 2;
+fn rem(a: u32, b: u32) -> u32 = a % b;
+fn below(a: u32, b: u32) -> bool = a < b;
+fn signed(a: i64, b: i64) -> bool = a < b;
+fn widen(x: u32) -> i64 = x as i64;
+fn extend(x: i32) -> i64 = x as i64;
+fn narrow(x: i64) -> i32 = x as i32;
+fn dec(x: i32) -> i32 = x - 1;
+fn flip(x: i32) -> i32 = (1 as i32) - x;
+";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let ir = TextCodeGen::new().generate(&crate::mir::lower_program(&ast)).unwrap();
+
+        assert!(ir.contains("udiv i64 %x, 2"));
+        assert!(ir.contains("urem i32 %a, %b"));
+        assert!(ir.contains("icmp ult i32 %a, %b"));
+        assert!(ir.contains("icmp slt i64 %a, %b"));
+        assert!(ir.contains("zext i32 %x to i64"));
+        assert!(ir.contains("sext i32 %x to i64"));
+        assert!(ir.contains("trunc i64 %x to i32"));
+        // A literal has the width the checker gave it: the i32 operand it is
+        // combined with, or whatever it was cast to
+        assert!(ir.contains("sub nsw i32 %x, 1"));
+        assert!(ir.contains("trunc i64 1 to i32"));
+    }
+
+    #[test]
+    fn test_checked_operators_follow_signedness() {
+        let source = "fn sum(a: u32, b: u32) -> u32? = a +? b;
+fn diff(a: i32, b: i32) -> i32? = a -? b;
+";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let ir = TextCodeGen::new().generate(&crate::mir::lower_program(&ast)).unwrap();
+
+        // An unsigned payload is zero-extended into the `Some` slot
+        assert!(ir.contains("@llvm.uadd.with.overflow.i32(i32 %a, i32 %b)"));
+        assert!(ir.contains("zext i32 %_t0.val to i64"));
+        assert!(ir.contains("@llvm.ssub.with.overflow.i32(i32 %a, i32 %b)"));
+        assert!(ir.contains("sext i32 %_t0.val to i64"));
+    }
+
+    #[test]
+    fn test_checked_contracts_branch_on_overflow_bit() {
+        let source = "fn add(a: u64, b: u64) -> u64 = a + b;
+fn mul(a: i32, b: i32) -> i32 = a * b;
+";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = crate::mir::lower_program_with_contracts(&ast, crate::mir::ContractMode::Check);
+        let ir = TextCodeGen::new().generate(&mir).unwrap();
+
+        // Unsigned operands use the unsigned intrinsic; the bit is the branch
+        // condition and nothing is boxed
+        assert!(ir.contains("%_t1.ovf = call {i64, i1} @llvm.uadd.with.overflow.i64(i64 %a, i64 %b)"));
+        assert!(ir.contains("%_t1 = extractvalue {i64, i1} %_t1.ovf, 1"));
+        assert!(ir.contains("@llvm.smul.with.overflow.i32(i32 %a, i32 %b)"));
+        assert!(ir.contains("label %bb_overflow_fail_1, label %bb_overflow_ok_0"));
+        assert!(!ir.contains("call ptr @malloc(i64 16)"));
+    }
 }
@@ -15,7 +15,7 @@
 mod llvm_text;
 mod wasm_text;
 
-pub use llvm_text::{TextCodeGen, TextCodeGenError, TextCodeGenResult};
+pub use llvm_text::{check_target_triple, TextCodeGen, TextCodeGenError, TextCodeGenResult};
 pub use wasm_text::{WasmCodeGen, WasmCodeGenError, WasmCodeGenResult, WasmTarget};
 
 #[cfg(feature = "llvm")]
@@ -12,13 +12,34 @@
 //!     ↓
 //! .wat file → wat2wasm → .wasm
 //! ```
+//!
+//! Strings mirror the runtime's `BmbString` with 32-bit fields: a string value
+//! is an i32 pointer to a `{ data: i32, len: i32, cap: i32 }` header. String
+//! constants live in data segments after the reserved I/O area, and the heap
+//! used by `$alloc` starts right after them.
+//!
+//! On the WASI target the file builtins (`read_file`, `write_file`,
+//! `append_file`, `file_exists`, `file_size`) are glue functions over
+//! `wasi_snapshot_preview1`. Paths resolve against the first preopened
+//! directory (fd 3), e.g. `wasmtime run --dir . prog.wat`.
+//!
+//! Math builtins with no f64 instruction (`sin`, `cos`, `round`, `ln`, `exp`,
+//! `pow`) are imported from the host's `env` module, so the standalone target
+//! rejects them.
+//!
+//! Tuples are i32 pointers to one i64 word per element. On targets whose
+//! engines accept several results (see [`WasmTarget::supports_multi_value`])
+//! a tuple-returning function returns its elements as WASM values, and the
+//! caller packs them back into a block; on the standalone target it returns
+//! the pointer.
 
+use std::collections::HashMap;
 use std::fmt::Write;
 use thiserror::Error;
 
 use crate::mir::{
-    BasicBlock, Constant, MirBinOp, MirExternFn, MirFunction, MirInst, MirProgram, MirType,
-    MirUnaryOp, Operand, Terminator,
+    enum_field_word, variant_discriminant, BasicBlock, Constant, MirBinOp, MirExternFn,
+    MirFunction, MirInst, MirProgram, MirType, MirUnaryOp, Operand, Place, Terminator,
 };
 
 /// WASM text code generation error
@@ -55,12 +76,48 @@ pub enum WasmTarget {
     Standalone,
 }
 
+impl WasmTarget {
+    /// Whether functions may return several values (the multi-value
+    /// proposal); engines for standalone modules are not assumed to
+    pub fn supports_multi_value(self) -> bool {
+        match self {
+            WasmTarget::Wasi | WasmTarget::Browser => true,
+            WasmTarget::Standalone => false,
+        }
+    }
+}
+
+/// Start of the string constant area (below it: I/O scratch buffers)
+const DATA_START: u32 = 1024;
+
+/// Size of a string header: `{ data: i32, len: i32, cap: i32 }`
+const STRING_HEADER_SIZE: u32 = 12;
+
+/// Bytes per WASM memory page
+const PAGE_SIZE: u32 = 65536;
+
+/// `wasi_snapshot_preview1` functions imported by the WASI runtime
+const WASI_RUNTIME_IMPORTS: [&str; 6] = ["fd_write", "proc_exit", "path_open", "fd_read", "fd_close", "fd_filestat_get"];
+
+/// File builtins lowered to WASI glue functions
+const FILE_BUILTINS: [&str; 5] = ["read_file", "write_file", "append_file", "file_exists", "file_size"];
+
+/// Math builtins with no f64 instruction, imported from the host's `env`
+/// module as `(name, arity)`
+const MATH_IMPORTS: [(&str, usize); 6] = [("sin", 1), ("cos", 1), ("round", 1), ("ln", 1), ("exp", 1), ("pow", 2)];
+
 /// Text-based WASM Code Generator
 pub struct WasmCodeGen {
     /// Target environment
     target: WasmTarget,
     /// Memory pages (64KB each)
     memory_pages: u32,
+    /// Address of each string constant's header, filled in by `generate`
+    string_offsets: HashMap<String, u32>,
+    /// Functions whose address is taken, in table order, filled in by `generate`
+    fn_table: Vec<String>,
+    /// Return type of each function in the program, filled in by `generate`
+    fn_returns: HashMap<String, MirType>,
 }
 
 impl WasmCodeGen {
@@ -69,6 +126,9 @@ impl WasmCodeGen {
         Self {
             target: WasmTarget::default(),
             memory_pages: 1,
+            string_offsets: HashMap::new(),
+            fn_table: Vec::new(),
+            fn_returns: HashMap::new(),
         }
     }
 
@@ -77,6 +137,9 @@ impl WasmCodeGen {
         Self {
             target,
             memory_pages: 1,
+            string_offsets: HashMap::new(),
+            fn_table: Vec::new(),
+            fn_returns: HashMap::new(),
         }
     }
 
@@ -88,6 +151,20 @@ impl WasmCodeGen {
 
     /// Generate complete WASM module as text (.wat format)
     pub fn generate(&self, program: &MirProgram) -> WasmCodeGenResult<String> {
+        let (string_offsets, data_end) = Self::layout_strings(program);
+        // The heap starts 8-byte aligned after the string constants
+        let heap_start = (data_end + 7) & !7;
+        let codegen = WasmCodeGen {
+            target: self.target,
+            memory_pages: self.memory_pages.max(heap_start.div_ceil(PAGE_SIZE)),
+            string_offsets,
+            fn_table: Self::collect_fn_table(program),
+            fn_returns: program.functions.iter().map(|f| (f.name.clone(), f.ret_ty.clone())).collect(),
+        };
+        codegen.generate_module(program, heap_start)
+    }
+
+    fn generate_module(&self, program: &MirProgram, heap_start: u32) -> WasmCodeGenResult<String> {
         let mut output = String::new();
 
         // Module header
@@ -95,14 +172,18 @@ impl WasmCodeGen {
         writeln!(output, "  ;; Generated by BMB compiler (v0.12.1)")?;
         writeln!(output)?;
 
+        // Runtime imports based on target (v0.13.0: includes extern fns)
+        // Imports must precede all memory, global and function definitions
+        self.emit_imports(&mut output, program)?;
+
         // Memory declaration
         self.emit_memory(&mut output)?;
 
-        // Global variables for runtime
-        self.emit_globals(&mut output)?;
+        // String constants in linear memory
+        self.emit_data(&mut output)?;
 
-        // Runtime imports based on target (v0.13.0: includes extern fns)
-        self.emit_imports(&mut output, program)?;
+        // Global variables for runtime
+        self.emit_globals(&mut output, heap_start)?;
 
         // Generate function type signatures
         self.emit_types(&mut output, program)?;
@@ -115,6 +196,9 @@ impl WasmCodeGen {
             self.emit_function(&mut output, func)?;
         }
 
+        // Table of functions called indirectly (lifted closures)
+        self.emit_table(&mut output)?;
+
         // Export main function if exists
         self.emit_exports(&mut output, program)?;
 
@@ -133,11 +217,134 @@ impl WasmCodeGen {
         Ok(())
     }
 
+    /// Assign every string constant a header address in the data area.
+    /// Returns the offsets and the end of the data area.
+    fn layout_strings(program: &MirProgram) -> (HashMap<String, u32>, u32) {
+        let mut offsets = HashMap::new();
+        let mut next = DATA_START;
+        let mut add = |op: &Operand| {
+            if let Operand::Constant(Constant::String(s)) = op
+                && !offsets.contains_key(s)
+            {
+                offsets.insert(s.clone(), next);
+                // Keep headers 4-byte aligned
+                next = (next + STRING_HEADER_SIZE + s.len() as u32 + 3) & !3;
+            }
+        };
+
+        for func in &program.functions {
+            for block in &func.blocks {
+                for inst in &block.instructions {
+                    match inst {
+                        MirInst::Const { value, .. } => add(&Operand::Constant(value.clone())),
+                        MirInst::BinOp { lhs, rhs, .. } => {
+                            add(lhs);
+                            add(rhs);
+                        }
+                        MirInst::UnaryOp { src, .. } | MirInst::Cast { src, .. } => add(src),
+                        MirInst::Call { args, .. }
+                        | MirInst::CallIndirect { args, .. }
+                        | MirInst::EnumVariant { args, .. }
+                        | MirInst::ArrayInit { elements: args, .. } => args.iter().for_each(&mut add),
+                        MirInst::Phi { values, .. } => values.iter().for_each(|(v, _)| add(v)),
+                        MirInst::StructInit { fields, .. } => fields.iter().for_each(|(_, v)| add(v)),
+                        MirInst::FieldStore { value, .. } => add(value),
+                        MirInst::IndexLoad { index, .. } => add(index),
+                        MirInst::IndexStore { index, value, .. } => {
+                            add(index);
+                            add(value);
+                        }
+                        MirInst::Copy { .. } | MirInst::FieldAccess { .. } | MirInst::DebugLoc { .. } => {}
+                    }
+                }
+                match &block.terminator {
+                    Terminator::Return(Some(op)) => add(op),
+                    Terminator::Branch { cond, .. } => add(cond),
+                    Terminator::Switch { discriminant, .. } => add(discriminant),
+                    _ => {}
+                }
+            }
+        }
+
+        (offsets, next)
+    }
+
+    /// Functions named by `Constant::FnAddr` anywhere in the program, in
+    /// order of first use; a function's address is its table index
+    fn collect_fn_table(program: &MirProgram) -> Vec<String> {
+        let mut table: Vec<String> = Vec::new();
+        for inst in program.functions.iter().flat_map(|f| f.blocks.iter()).flat_map(|b| b.instructions.iter()) {
+            let operands: Vec<&Operand> = match inst {
+                MirInst::Call { args, .. } | MirInst::EnumVariant { args, .. } => args.iter().collect(),
+                MirInst::CallIndirect { callee, args, .. } => std::iter::once(callee).chain(args).collect(),
+                _ => Vec::new(),
+            };
+            let named = operands.into_iter().filter_map(|op| match op {
+                Operand::Constant(Constant::FnAddr(name)) => Some(name),
+                _ => None,
+            });
+            let constant = match inst {
+                MirInst::Const { value: Constant::FnAddr(name), .. } => Some(name),
+                _ => None,
+            };
+            for name in named.chain(constant) {
+                if !table.contains(name) {
+                    table.push(name.clone());
+                }
+            }
+        }
+        table
+    }
+
+    /// Emit the function table backing `call_indirect`
+    fn emit_table(&self, out: &mut String) -> WasmCodeGenResult<()> {
+        if self.fn_table.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "  (table {} funcref)", self.fn_table.len())?;
+        let funcs: Vec<String> = self.fn_table.iter().map(|name| format!("${}", name)).collect();
+        writeln!(out, "  (elem (i32.const 0) func {})", funcs.join(" "))?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    /// Emit data segments for string constants: header followed by the bytes
+    fn emit_data(&self, out: &mut String) -> WasmCodeGenResult<()> {
+        if self.string_offsets.is_empty() {
+            return Ok(());
+        }
+
+        let mut strings: Vec<_> = self.string_offsets.iter().collect();
+        strings.sort_by_key(|(_, offset)| **offset);
+
+        writeln!(out, "  ;; String constants")?;
+        for (s, offset) in strings {
+            let data = offset + STRING_HEADER_SIZE;
+            let len = s.len() as u32;
+            let mut bytes = String::new();
+            for word in [data, len, len] {
+                for b in word.to_le_bytes() {
+                    write!(bytes, "\\{:02x}", b)?;
+                }
+            }
+            for b in s.bytes() {
+                if (b == b' ' || b.is_ascii_graphic()) && b != b'"' && b != b'\\' {
+                    bytes.push(b as char);
+                } else {
+                    write!(bytes, "\\{:02x}", b)?;
+                }
+            }
+            writeln!(out, "  (data (i32.const {}) \"{}\")", offset, bytes)?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
     /// Emit global variables for runtime
-    fn emit_globals(&self, out: &mut String) -> WasmCodeGenResult<()> {
+    fn emit_globals(&self, out: &mut String, heap_start: u32) -> WasmCodeGenResult<()> {
         writeln!(out, "  ;; Globals for runtime")?;
-        // Heap pointer for simple memory allocation (starts after reserved area)
-        writeln!(out, "  (global $heap_ptr (mut i32) (i32.const 1024))")?;
+        // Heap pointer for simple memory allocation (starts after string constants)
+        writeln!(out, "  (global $heap_ptr (mut i32) (i32.const {}))", heap_start)?;
         // Buffer pointer for I/O operations
         writeln!(out, "  (global $io_buf i32 (i32.const 0))")?;
         writeln!(out)?;
@@ -155,6 +362,15 @@ impl WasmCodeGen {
                 writeln!(out, "    (func $fd_write (param i32 i32 i32 i32) (result i32)))")?;
                 writeln!(out, "  (import \"wasi_snapshot_preview1\" \"proc_exit\"")?;
                 writeln!(out, "    (func $proc_exit (param i32)))")?;
+                // File system access for the file builtins
+                writeln!(out, "  (import \"wasi_snapshot_preview1\" \"path_open\"")?;
+                writeln!(out, "    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))")?;
+                writeln!(out, "  (import \"wasi_snapshot_preview1\" \"fd_read\"")?;
+                writeln!(out, "    (func $fd_read (param i32 i32 i32 i32) (result i32)))")?;
+                writeln!(out, "  (import \"wasi_snapshot_preview1\" \"fd_close\"")?;
+                writeln!(out, "    (func $fd_close (param i32) (result i32)))")?;
+                writeln!(out, "  (import \"wasi_snapshot_preview1\" \"fd_filestat_get\"")?;
+                writeln!(out, "    (func $fd_filestat_get (param i32 i32) (result i32)))")?;
             }
             WasmTarget::Browser => {
                 // JavaScript interface
@@ -169,12 +385,35 @@ impl WasmCodeGen {
             }
         }
 
+        // Math builtins without an f64 instruction come from the host
+        let math = Self::imported_math_builtins(program);
+        if let Some((name, _)) = math.first() {
+            if self.target == WasmTarget::Standalone {
+                return Err(WasmCodeGenError::UnsupportedFeature(format!(
+                    "{} requires a host import, unavailable on the standalone target",
+                    name
+                )));
+            }
+            writeln!(out)?;
+            writeln!(out, "  ;; Math builtins provided by the host")?;
+            for (name, arity) in math {
+                writeln!(out, "  (import \"env\" \"{}\"", name)?;
+                writeln!(out, "    (func ${} (param{}) (result f64)))", name, " f64".repeat(arity))?;
+            }
+        }
+
         // User-defined extern function imports (v0.13.0)
         if !program.extern_fns.is_empty() {
             writeln!(out)?;
             writeln!(out, "  ;; User-defined extern imports")?;
             for ext_fn in &program.extern_fns {
-                self.emit_extern_import(out, ext_fn)?;
+                // WASI functions the runtime already imports would be duplicates
+                let runtime_import = self.target == WasmTarget::Wasi
+                    && ext_fn.module == "wasi_snapshot_preview1"
+                    && WASI_RUNTIME_IMPORTS.contains(&ext_fn.name.as_str());
+                if !runtime_import {
+                    self.emit_extern_import(out, ext_fn)?;
+                }
             }
         }
 
@@ -182,6 +421,24 @@ impl WasmCodeGen {
         Ok(())
     }
 
+    /// The math builtins of [`MATH_IMPORTS`] the program calls without
+    /// defining or importing a function of the same name
+    fn imported_math_builtins(program: &MirProgram) -> Vec<(&'static str, usize)> {
+        let defined = |name: &str| {
+            program.functions.iter().any(|f| f.name == name)
+                || program.extern_fns.iter().any(|f| f.name == name)
+        };
+        let called = |name: &str, arity: usize| {
+            program.functions.iter()
+                .flat_map(|f| &f.blocks)
+                .flat_map(|b| &b.instructions)
+                .any(|inst| matches!(inst, MirInst::Call { func, args, .. } if func == name && args.len() == arity))
+        };
+        MATH_IMPORTS.into_iter()
+            .filter(|&(name, arity)| !defined(name) && called(name, arity))
+            .collect()
+    }
+
     /// Emit a single extern function import (v0.13.0)
     fn emit_extern_import(&self, out: &mut String, ext_fn: &MirExternFn) -> WasmCodeGenResult<()> {
         let params: Vec<&str> = ext_fn
@@ -227,7 +484,7 @@ impl WasmCodeGen {
                 .iter()
                 .map(|(_, ty)| self.mir_type_to_wasm(ty))
                 .collect();
-            let ret = self.mir_type_to_wasm_result(&func.ret_ty);
+            let results = self.result_types(&func.ret_ty);
 
             write!(out, "  (type $type_{} (func", i)?;
             if !params.is_empty() {
@@ -237,8 +494,8 @@ impl WasmCodeGen {
                 }
                 write!(out, ")")?;
             }
-            if !ret.is_empty() {
-                write!(out, " (result {})", ret)?;
+            if !results.is_empty() {
+                write!(out, " (result {})", results.join(" "))?;
             }
             writeln!(out, "))")?;
         }
@@ -251,6 +508,26 @@ impl WasmCodeGen {
     fn emit_runtime_functions(&self, out: &mut String) -> WasmCodeGenResult<()> {
         writeln!(out, "  ;; Runtime helper functions")?;
 
+        // alloc: bump allocator over $heap_ptr
+        writeln!(out, "  ;; $alloc: Bump-allocate $size bytes (8-byte aligned), growing memory as needed")?;
+        writeln!(out, "  (func $alloc (param $size i32) (result i32)")?;
+        writeln!(out, "    (local $ptr i32)")?;
+        writeln!(out, "    (local $end i32)")?;
+        writeln!(out, "    (local.set $ptr (global.get $heap_ptr))")?;
+        writeln!(out, "    (local.set $end (i32.and (i32.add (i32.add (local.get $ptr) (local.get $size)) (i32.const 7)) (i32.const -8)))")?;
+        writeln!(out, "    (if (i32.gt_u (local.get $end) (i32.mul (memory.size) (i32.const {})))", PAGE_SIZE)?;
+        writeln!(out, "      (then")?;
+        writeln!(out, "        (if (i32.eq (memory.grow (i32.div_u")?;
+        writeln!(out, "              (i32.add (i32.sub (local.get $end) (i32.mul (memory.size) (i32.const {}))) (i32.const {}))", PAGE_SIZE, PAGE_SIZE - 1)?;
+        writeln!(out, "              (i32.const {}))) (i32.const -1))", PAGE_SIZE)?;
+        writeln!(out, "          (then unreachable))")?;
+        writeln!(out, "      )")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (global.set $heap_ptr (local.get $end))")?;
+        writeln!(out, "    (local.get $ptr)")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
         match self.target {
             WasmTarget::Wasi => {
                 self.emit_wasi_runtime(out)?;
@@ -401,6 +678,152 @@ impl WasmCodeGen {
         writeln!(out, "      (then (call $proc_exit (i32.const 1)))")?;
         writeln!(out, "    )")?;
         writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        self.emit_wasi_file_runtime(out)
+    }
+
+    /// Emit WASI glue for the file builtins.
+    /// Scratch memory: 200..212 iovec + nwritten, 216 opened fd, 224 nread,
+    /// 232..296 filestat (size at offset 32).
+    fn emit_wasi_file_runtime(&self, out: &mut String) -> WasmCodeGenResult<()> {
+        // open_path: path_open relative to the first preopened directory
+        writeln!(out, "  ;; $open_path: Open a string path under preopen fd 3; returns fd or -1")?;
+        writeln!(out, "  (func $open_path (param $path i32) (param $oflags i32) (param $rights i64) (param $fdflags i32) (result i32)")?;
+        writeln!(out, "    (if (call $path_open")?;
+        writeln!(out, "          (i32.const 3)                         ;; dirfd: first preopen")?;
+        writeln!(out, "          (i32.const 1)                         ;; dirflags: follow symlinks")?;
+        writeln!(out, "          (i32.load (local.get $path))          ;; path data")?;
+        writeln!(out, "          (i32.load offset=4 (local.get $path)) ;; path len")?;
+        writeln!(out, "          (local.get $oflags)")?;
+        writeln!(out, "          (local.get $rights)")?;
+        writeln!(out, "          (i64.const 0)")?;
+        writeln!(out, "          (local.get $fdflags)")?;
+        writeln!(out, "          (i32.const 216))")?;
+        writeln!(out, "      (then (return (i32.const -1)))")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (i32.load (i32.const 216))")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        // read_file: returns a fresh string (empty on error)
+        writeln!(out, "  ;; $read_file: Read a whole file into a new string (empty on error)")?;
+        writeln!(out, "  (func $read_file (param $path i32) (result i32)")?;
+        writeln!(out, "    (local $fd i32)")?;
+        writeln!(out, "    (local $size i32)")?;
+        writeln!(out, "    (local $str i32)")?;
+        writeln!(out, "    (local $len i32)")?;
+        writeln!(out, "    ;; rights: fd_read | fd_filestat_get")?;
+        writeln!(out, "    (local.set $fd (call $open_path (local.get $path) (i32.const 0) (i64.const 2097154) (i32.const 0)))")?;
+        writeln!(out, "    (if (i32.ge_s (local.get $fd) (i32.const 0))")?;
+        writeln!(out, "      (then")?;
+        writeln!(out, "        (if (i32.eqz (call $fd_filestat_get (local.get $fd) (i32.const 232)))")?;
+        writeln!(out, "          (then (local.set $size (i32.wrap_i64 (i64.load (i32.const 264))))))")?;
+        writeln!(out, "      )")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (local.set $str (call $alloc (i32.add (local.get $size) (i32.const {}))))", STRING_HEADER_SIZE)?;
+        writeln!(out, "    (i32.store (local.get $str) (i32.add (local.get $str) (i32.const {})))", STRING_HEADER_SIZE)?;
+        writeln!(out, "    (if (i32.ge_s (local.get $fd) (i32.const 0))")?;
+        writeln!(out, "      (then")?;
+        writeln!(out, "        ;; Read until EOF or the buffer is full")?;
+        writeln!(out, "        (block $done")?;
+        writeln!(out, "          (loop $read")?;
+        writeln!(out, "            (br_if $done (i32.ge_u (local.get $len) (local.get $size)))")?;
+        writeln!(out, "            (i32.store (i32.const 200) (i32.add (i32.load (local.get $str)) (local.get $len)))")?;
+        writeln!(out, "            (i32.store (i32.const 204) (i32.sub (local.get $size) (local.get $len)))")?;
+        writeln!(out, "            (br_if $done (call $fd_read (local.get $fd) (i32.const 200) (i32.const 1) (i32.const 224)))")?;
+        writeln!(out, "            (br_if $done (i32.eqz (i32.load (i32.const 224))))")?;
+        writeln!(out, "            (local.set $len (i32.add (local.get $len) (i32.load (i32.const 224))))")?;
+        writeln!(out, "            (br $read)")?;
+        writeln!(out, "          )")?;
+        writeln!(out, "        )")?;
+        writeln!(out, "        (drop (call $fd_close (local.get $fd)))")?;
+        writeln!(out, "      )")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (i32.store offset=4 (local.get $str) (local.get $len))")?;
+        writeln!(out, "    (i32.store offset=8 (local.get $str) (local.get $size))")?;
+        writeln!(out, "    (local.get $str)")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        // write_path: shared by write_file and append_file
+        writeln!(out, "  ;; $write_path: Write a string to a path; returns 0 on success, -1 on error")?;
+        writeln!(out, "  (func $write_path (param $path i32) (param $content i32) (param $oflags i32) (param $fdflags i32) (result i64)")?;
+        writeln!(out, "    (local $fd i32)")?;
+        writeln!(out, "    (local $ptr i32)")?;
+        writeln!(out, "    (local $remaining i32)")?;
+        writeln!(out, "    (local $status i64)")?;
+        writeln!(out, "    ;; rights: fd_write")?;
+        writeln!(out, "    (local.set $fd (call $open_path (local.get $path) (local.get $oflags) (i64.const 64) (local.get $fdflags)))")?;
+        writeln!(out, "    (if (i32.lt_s (local.get $fd) (i32.const 0))")?;
+        writeln!(out, "      (then (return (i64.const -1)))")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (local.set $ptr (i32.load (local.get $content)))")?;
+        writeln!(out, "    (local.set $remaining (i32.load offset=4 (local.get $content)))")?;
+        writeln!(out, "    ;; Loop over partial writes")?;
+        writeln!(out, "    (block $done")?;
+        writeln!(out, "      (loop $write")?;
+        writeln!(out, "        (br_if $done (i32.eqz (local.get $remaining)))")?;
+        writeln!(out, "        (i32.store (i32.const 200) (local.get $ptr))")?;
+        writeln!(out, "        (i32.store (i32.const 204) (local.get $remaining))")?;
+        writeln!(out, "        (if (call $fd_write (local.get $fd) (i32.const 200) (i32.const 1) (i32.const 208))")?;
+        writeln!(out, "          (then")?;
+        writeln!(out, "            (local.set $status (i64.const -1))")?;
+        writeln!(out, "            (br $done)")?;
+        writeln!(out, "          )")?;
+        writeln!(out, "        )")?;
+        writeln!(out, "        (local.set $ptr (i32.add (local.get $ptr) (i32.load (i32.const 208))))")?;
+        writeln!(out, "        (local.set $remaining (i32.sub (local.get $remaining) (i32.load (i32.const 208))))")?;
+        writeln!(out, "        (br $write)")?;
+        writeln!(out, "      )")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (drop (call $fd_close (local.get $fd)))")?;
+        writeln!(out, "    (local.get $status)")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        writeln!(out, "  ;; $write_file: Create or truncate a file and write a string to it")?;
+        writeln!(out, "  (func $write_file (param $path i32) (param $content i32) (result i64)")?;
+        writeln!(out, "    ;; oflags: creat | trunc")?;
+        writeln!(out, "    (call $write_path (local.get $path) (local.get $content) (i32.const 9) (i32.const 0))")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        writeln!(out, "  ;; $append_file: Append a string to a file, creating it if needed")?;
+        writeln!(out, "  (func $append_file (param $path i32) (param $content i32) (result i64)")?;
+        writeln!(out, "    ;; oflags: creat, fdflags: append")?;
+        writeln!(out, "    (call $write_path (local.get $path) (local.get $content) (i32.const 1) (i32.const 1))")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        writeln!(out, "  ;; $file_exists: 1 if the path can be opened, 0 otherwise")?;
+        writeln!(out, "  (func $file_exists (param $path i32) (result i64)")?;
+        writeln!(out, "    (local $fd i32)")?;
+        writeln!(out, "    (local.set $fd (call $open_path (local.get $path) (i32.const 0) (i64.const 0) (i32.const 0)))")?;
+        writeln!(out, "    (if (i32.lt_s (local.get $fd) (i32.const 0))")?;
+        writeln!(out, "      (then (return (i64.const 0)))")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (drop (call $fd_close (local.get $fd)))")?;
+        writeln!(out, "    (i64.const 1)")?;
+        writeln!(out, "  )")?;
+        writeln!(out)?;
+
+        writeln!(out, "  ;; $file_size: Size of a file in bytes, or -1 on error")?;
+        writeln!(out, "  (func $file_size (param $path i32) (result i64)")?;
+        writeln!(out, "    (local $fd i32)")?;
+        writeln!(out, "    (local $size i64)")?;
+        writeln!(out, "    ;; rights: fd_filestat_get")?;
+        writeln!(out, "    (local.set $fd (call $open_path (local.get $path) (i32.const 0) (i64.const 2097152) (i32.const 0)))")?;
+        writeln!(out, "    (if (i32.lt_s (local.get $fd) (i32.const 0))")?;
+        writeln!(out, "      (then (return (i64.const -1)))")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (local.set $size (i64.const -1))")?;
+        writeln!(out, "    (if (i32.eqz (call $fd_filestat_get (local.get $fd) (i32.const 232)))")?;
+        writeln!(out, "      (then (local.set $size (i64.load (i32.const 264))))")?;
+        writeln!(out, "    )")?;
+        writeln!(out, "    (drop (call $fd_close (local.get $fd)))")?;
+        writeln!(out, "    (local.get $size)")?;
+        writeln!(out, "  )")?;
 
         Ok(())
     }
@@ -448,11 +871,11 @@ impl WasmCodeGen {
             .map(|(name, ty)| format!("(param ${} {})", name, self.mir_type_to_wasm(ty)))
             .collect();
 
-        let ret = self.mir_type_to_wasm_result(&func.ret_ty);
-        let result_str = if ret.is_empty() {
+        let results = self.result_types(&func.ret_ty);
+        let result_str = if results.is_empty() {
             String::new()
         } else {
-            format!(" (result {})", ret)
+            format!(" (result {})", results.join(" "))
         };
 
         writeln!(out, "  (func ${} {}{}", func.name, params.join(" "), result_str)?;
@@ -469,6 +892,12 @@ impl WasmCodeGen {
                         all_locals.entry(name).or_insert(ty);
                     }
                 }
+                // Elements of a multi-value result, held until they are packed
+                if let MirInst::Call { dest: Some(dest), func: callee, .. } = inst {
+                    for (i, ty) in self.multi_value_elements(callee).iter().enumerate() {
+                        all_locals.entry(format!("{}.{}", dest.name, i)).or_insert(ty.clone());
+                    }
+                }
             }
         }
 
@@ -577,13 +1006,25 @@ impl WasmCodeGen {
                     writeln!(out, "    i32.or")?;
                     // Store result
                     writeln!(out, "    local.set ${}", dest.name)?;
+                } else if matches!(op, MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked) {
+                    self.emit_checked_binop(out, &dest.name, *op, lhs, rhs, func)?;
+                } else if matches!(op, MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows) {
+                    self.emit_overflow_test(out, &dest.name, *op, lhs, rhs, func)?;
+                } else if matches!(op, MirBinOp::AddSat | MirBinOp::SubSat | MirBinOp::MulSat) {
+                    self.emit_saturating_binop(out, &dest.name, *op, lhs, rhs, func)?;
                 } else {
-                    // Push operands
-                    self.emit_operand(out, lhs)?;
-                    self.emit_operand(out, rhs)?;
+                    // Push operands; an integer literal takes the width of
+                    // the i32 value it is combined with
+                    let typed = match lhs {
+                        Operand::Constant(Constant::Int(_)) => rhs,
+                        _ => lhs,
+                    };
+                    let ty = self.infer_operand_wasm_type(typed, func);
+                    self.emit_operand_as(out, lhs, ty)?;
+                    self.emit_operand_as(out, rhs, ty)?;
 
                     // Apply operation
-                    let wasm_op = self.binop_to_wasm(*op, lhs, func)?;
+                    let wasm_op = self.binop_to_wasm(*op, typed, func)?;
                     writeln!(out, "    {}", wasm_op)?;
 
                     // Store result
@@ -620,16 +1061,72 @@ impl WasmCodeGen {
                 writeln!(out, "    local.set ${}", dest.name)?;
             }
 
+            MirInst::Cast { dest, src, ty } => {
+                let src_ty = self.infer_operand_mir_type(src, func);
+                let unsigned_src = matches!(src_ty, MirType::U32 | MirType::U64 | MirType::Char | MirType::Bool);
+                let unsigned_dest = matches!(ty, MirType::U32 | MirType::U64);
+                let sign = |unsigned: bool| if unsigned { "u" } else { "s" };
+                let (from, to) = (self.mir_type_to_wasm(&src_ty), self.mir_type_to_wasm(ty));
+                self.emit_operand(out, src)?;
+                if *ty == MirType::Bool && src_ty != MirType::Bool {
+                    writeln!(out, "    {}.const 0", from)?;
+                    writeln!(out, "    {}.ne", from)?;
+                } else {
+                    match (from, to) {
+                        ("i64", "i32") => writeln!(out, "    i32.wrap_i64")?,
+                        ("i32", "i64") => writeln!(out, "    i64.extend_i32_{}", sign(unsigned_src))?,
+                        ("f64", "i32" | "i64") => writeln!(out, "    {}.trunc_f64_{}", to, sign(unsigned_dest))?,
+                        ("i32" | "i64", "f64") => writeln!(out, "    f64.convert_{}_{}", from, sign(unsigned_src))?,
+                        _ => {}
+                    }
+                }
+                writeln!(out, "    local.set ${}", dest.name)?;
+            }
+
             MirInst::Call { dest, func: fn_name, args } => {
+                if FILE_BUILTINS.contains(&fn_name.as_str()) && self.target != WasmTarget::Wasi {
+                    return Err(WasmCodeGenError::UnsupportedFeature(format!(
+                        "{} requires the WASI target",
+                        fn_name
+                    )));
+                }
+
                 // Push arguments
                 for arg in args {
                     self.emit_operand(out, arg)?;
                 }
 
-                // Call function
-                writeln!(out, "    call ${}", fn_name)?;
+                // Math builtins with a native f64 instruction; others stay calls
+                match (fn_name.as_str(), args.len()) {
+                    ("sqrt", 1) => writeln!(out, "    f64.sqrt")?,
+                    ("floor", 1) => writeln!(out, "    f64.floor")?,
+                    ("ceil", 1) => writeln!(out, "    f64.ceil")?,
+                    _ => writeln!(out, "    call ${}", fn_name)?,
+                }
+
+                let elements = self.multi_value_elements(fn_name);
+                if !elements.is_empty() {
+                    self.emit_pack_results(out, dest.as_ref().map(|d| d.name.as_str()), elements)?;
+                } else if let Some(d) = dest {
+                    // Store result if any
+                    writeln!(out, "    local.set ${}", d.name)?;
+                }
+            }
 
-                // Store result if any
+            MirInst::CallIndirect { dest, callee, args } => {
+                // Arguments, then the callee's table index
+                for arg in args {
+                    self.emit_operand(out, arg)?;
+                }
+                self.emit_operand(out, callee)?;
+                writeln!(out, "    i32.wrap_i64")?;
+                let params: Vec<&str> = args.iter().map(|arg| self.infer_operand_wasm_type(arg, func)).collect();
+                let result = dest.as_ref()
+                    .map(|d| self.mir_type_to_wasm_result(&self.infer_place_mir_type(&d.name, func)))
+                    .filter(|ty| !ty.is_empty())
+                    .map(|ty| format!(" (result {})", ty))
+                    .unwrap_or_default();
+                writeln!(out, "    call_indirect (param {}){}", params.join(" "), result)?;
                 if let Some(d) = dest {
                     writeln!(out, "    local.set ${}", d.name)?;
                 }
@@ -663,6 +1160,20 @@ impl WasmCodeGen {
                 }
             }
 
+            MirInst::FieldAccess { dest, base, field } if enum_field_word(field).is_some()
+                && self.infer_operand_wasm_type(&Operand::Place(base.clone()), func) == "i64" =>
+            {
+                // Enum values are i64 handles to a block of words (tag, payload...)
+                let word = enum_field_word(field).unwrap_or(0);
+                writeln!(out, "    ;; enum field .{} from ${}", field, base.name)?;
+                writeln!(out, "    local.get ${}", base.name)?;
+                writeln!(out, "    i32.wrap_i64")?;
+                writeln!(out, "    i64.load offset={}", word * 8)?;
+                // Undo the widening `EnumVariant` applied to the payload
+                self.emit_narrow_from_word(out, self.mir_type_to_wasm(&self.infer_place_mir_type(&dest.name, func)))?;
+                writeln!(out, "    local.set ${}", dest.name)?;
+            }
+
             MirInst::FieldAccess { dest, base, field } => {
                 // Load field from struct in linear memory
                 writeln!(out, "    ;; field access .{} from ${}", field, base.name)?;
@@ -682,41 +1193,41 @@ impl WasmCodeGen {
 
             // v0.19.1: Enum variant
             MirInst::EnumVariant { dest, enum_name, variant, args } => {
-                // Enums are represented as tagged unions in linear memory
+                // Enums are tagged unions in linear memory, held as i64 handles:
+                // the discriminant word followed by one word per argument
                 writeln!(out, "    ;; enum {}::{} with {} args", enum_name, variant, args.len())?;
-                // Calculate discriminant (simplified: hash of variant name)
-                let discriminant: i64 = variant.bytes().fold(0i64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as i64));
-                // Allocate memory (simplified: just use a constant address)
-                writeln!(out, "    i32.const 0  ;; TODO: proper memory allocation")?;
+                writeln!(out, "    i32.const {}", (1 + args.len()) * 8)?;
+                writeln!(out, "    call $alloc")?;
+                writeln!(out, "    i64.extend_i32_u")?;
                 writeln!(out, "    local.set ${}", dest.name)?;
                 // Store discriminant at offset 0
                 writeln!(out, "    local.get ${}", dest.name)?;
-                writeln!(out, "    i64.const {}", discriminant)?;
+                writeln!(out, "    i32.wrap_i64")?;
+                writeln!(out, "    i64.const {}", variant_discriminant(variant))?;
                 writeln!(out, "    i64.store")?;
                 // Store variant arguments at subsequent offsets
                 for (i, arg) in args.iter().enumerate() {
                     writeln!(out, "    local.get ${}", dest.name)?;
-                    writeln!(out, "    i32.const {}", (i + 1) * 8)?;
-                    writeln!(out, "    i32.add")?;
+                    writeln!(out, "    i32.wrap_i64")?;
                     self.emit_operand(out, arg)?;
-                    writeln!(out, "    i64.store")?;
+                    self.emit_widen_to_word(out, self.infer_operand_wasm_type(arg, func))?;
+                    writeln!(out, "    i64.store offset={}", (i + 1) * 8)?;
                 }
             }
 
             // v0.19.3: Array operations
             MirInst::ArrayInit { dest, element_type: _, elements } => {
+                // Arrays and tuples are blocks of one i64 word per element
                 writeln!(out, "    ;; array init with {} elements", elements.len())?;
-                // Allocate memory for array (simplified: use constant address)
-                let size = elements.len() * 8; // 8 bytes per element
-                writeln!(out, "    i32.const 0  ;; TODO: proper memory allocation for {} bytes", size)?;
+                writeln!(out, "    i32.const {}", elements.len() * 8)?;
+                writeln!(out, "    call $alloc")?;
                 writeln!(out, "    local.set ${}", dest.name)?;
                 // Store each element
                 for (i, elem) in elements.iter().enumerate() {
                     writeln!(out, "    local.get ${}", dest.name)?;
-                    writeln!(out, "    i32.const {}", i * 8)?;
-                    writeln!(out, "    i32.add")?;
                     self.emit_operand(out, elem)?;
-                    writeln!(out, "    i64.store")?;
+                    self.emit_widen_to_word(out, self.infer_operand_wasm_type(elem, func))?;
+                    writeln!(out, "    i64.store offset={}", i * 8)?;
                 }
             }
 
@@ -730,6 +1241,9 @@ impl WasmCodeGen {
                 writeln!(out, "    i32.mul")?;
                 writeln!(out, "    i32.add")?;
                 writeln!(out, "    i64.load")?;
+                if let Some(ty) = self.tuple_element_type(array, index, func) {
+                    self.emit_narrow_from_word(out, self.mir_type_to_wasm(&ty))?;
+                }
                 writeln!(out, "    local.set ${}", dest.name)?;
             }
 
@@ -745,6 +1259,9 @@ impl WasmCodeGen {
                 self.emit_operand(out, value)?;
                 writeln!(out, "    i64.store")?;
             }
+
+            // WASM output carries no debug info
+            MirInst::DebugLoc { .. } => {}
         }
 
         Ok(())
@@ -757,13 +1274,20 @@ impl WasmCodeGen {
             Constant::Float(f) => writeln!(out, "    f64.const {}", f)?,
             Constant::Bool(b) => writeln!(out, "    i32.const {}", if *b { 1 } else { 0 })?,
             Constant::Unit => writeln!(out, "    ;; unit (no value)")?,
-            Constant::String(_s) => {
-                // String constants require memory management
-                writeln!(out, "    ;; TODO: string constant")?;
-                writeln!(out, "    i32.const 0")?;
+            Constant::String(s) => {
+                let offset = self.string_offsets.get(s).ok_or_else(|| {
+                    WasmCodeGenError::UnsupportedFeature(format!("string constant {:?} not laid out", s))
+                })?;
+                writeln!(out, "    i32.const {}  ;; string {:?}", offset, s)?;
             }
             // v0.64: Character constant (Unicode codepoint as i32)
             Constant::Char(c) => writeln!(out, "    i32.const {}", *c as u32)?,
+            Constant::FnAddr(name) => {
+                let index = self.fn_table.iter().position(|f| f == name).ok_or_else(|| {
+                    WasmCodeGenError::UnsupportedFeature(format!("function {} not in the table", name))
+                })?;
+                writeln!(out, "    i64.const {}  ;; &{}", index, name)?;
+            }
         }
         Ok(())
     }
@@ -777,6 +1301,292 @@ impl WasmCodeGen {
         Ok(())
     }
 
+    /// Push an operand of an operation on `wasm_ty` values, narrowing an
+    /// integer literal to `i32.const` where needed
+    fn emit_operand_as(&self, out: &mut String, op: &Operand, wasm_ty: &str) -> WasmCodeGenResult<()> {
+        match op {
+            Operand::Constant(Constant::Int(n)) if wasm_ty == "i32" => writeln!(out, "    i32.const {}", *n as i32)?,
+            _ => self.emit_operand(out, op)?,
+        }
+        Ok(())
+    }
+
+    /// Widen the value on the stack to the i64 word it is stored as
+    fn emit_widen_to_word(&self, out: &mut String, wasm_ty: &str) -> WasmCodeGenResult<()> {
+        match wasm_ty {
+            "i32" => writeln!(out, "    i64.extend_i32_u")?,
+            "f64" => writeln!(out, "    i64.reinterpret_f64")?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Turn the i64 word on the stack back into a `wasm_ty` value
+    fn emit_narrow_from_word(&self, out: &mut String, wasm_ty: &str) -> WasmCodeGenResult<()> {
+        match wasm_ty {
+            "i32" => writeln!(out, "    i32.wrap_i64")?,
+            "f64" => writeln!(out, "    f64.reinterpret_i64")?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Pack the results of a multi-value call into a tuple block stored in
+    /// `dest`, or drop them when the call's value is unused
+    fn emit_pack_results(&self, out: &mut String, dest: Option<&str>, elements: &[MirType]) -> WasmCodeGenResult<()> {
+        let Some(dest) = dest else {
+            for _ in elements {
+                writeln!(out, "    drop")?;
+            }
+            return Ok(());
+        };
+        // The last result is on top of the stack
+        for i in (0..elements.len()).rev() {
+            writeln!(out, "    local.set ${}.{}", dest, i)?;
+        }
+        writeln!(out, "    i32.const {}", elements.len() * 8)?;
+        writeln!(out, "    call $alloc")?;
+        writeln!(out, "    local.set ${}", dest)?;
+        for (i, ty) in elements.iter().enumerate() {
+            writeln!(out, "    local.get ${}", dest)?;
+            writeln!(out, "    local.get ${}.{}", dest, i)?;
+            self.emit_widen_to_word(out, self.mir_type_to_wasm(ty))?;
+            writeln!(out, "    i64.store offset={}", i * 8)?;
+        }
+        Ok(())
+    }
+
+    /// Emit `lhs op rhs` for a checked op: an `Option` block holding the
+    /// wrapped result, tagged `None` when the operation overflowed
+    fn emit_checked_binop(
+        &self,
+        out: &mut String,
+        dest: &str,
+        op: MirBinOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        func: &MirFunction,
+    ) -> WasmCodeGenResult<()> {
+        let ty = self.infer_operand_wasm_type(lhs, func);
+        writeln!(out, "    i32.const 16")?;
+        writeln!(out, "    call $alloc")?;
+        writeln!(out, "    i64.extend_i32_u")?;
+        writeln!(out, "    local.set ${}", dest)?;
+        // Payload word: the wrapped result
+        writeln!(out, "    local.get ${}", dest)?;
+        writeln!(out, "    i32.wrap_i64")?;
+        self.emit_operand(out, lhs)?;
+        self.emit_operand(out, rhs)?;
+        writeln!(out, "    {}", self.binop_to_wasm(op, lhs, func)?)?;
+        if ty == "i32" {
+            writeln!(out, "    i64.extend_i32_s")?;
+        }
+        writeln!(out, "    i64.store offset=8")?;
+        // Tag word: None if the operation overflowed, Some otherwise
+        writeln!(out, "    local.get ${}", dest)?;
+        writeln!(out, "    i32.wrap_i64")?;
+        writeln!(out, "    i64.const {}", variant_discriminant("None"))?;
+        writeln!(out, "    i64.const {}", variant_discriminant("Some"))?;
+        let mut result = vec![format!("local.get ${}", dest), "i32.wrap_i64".to_string(), "i64.load offset=8".to_string()];
+        if ty == "i32" {
+            result.push("i32.wrap_i64".to_string());
+        }
+        self.emit_overflow_flag(out, op, lhs, rhs, &result, ty)?;
+        writeln!(out, "    select")?;
+        writeln!(out, "    i64.store")?;
+        Ok(())
+    }
+
+    /// Set `dest` to whether `lhs op rhs` overflows, judged against the
+    /// wrapped result recomputed from the operands
+    fn emit_overflow_test(
+        &self,
+        out: &mut String,
+        dest: &str,
+        op: MirBinOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        func: &MirFunction,
+    ) -> WasmCodeGenResult<()> {
+        // An integer literal takes the width of the value it is combined with
+        let typed = match lhs {
+            Operand::Constant(Constant::Int(_)) => rhs,
+            _ => lhs,
+        };
+        let ty = self.infer_operand_wasm_type(typed, func);
+        let mut pushes = String::new();
+        self.emit_operand_as(&mut pushes, lhs, ty)?;
+        self.emit_operand_as(&mut pushes, rhs, ty)?;
+        writeln!(pushes, "    {}", self.binop_to_wasm(op, typed, func)?)?;
+        let result: Vec<String> = pushes.lines().map(|line| line.trim().to_string()).collect();
+        let unsigned = [lhs, rhs].into_iter().any(|operand| {
+            matches!(operand, Operand::Place(_)) && self.infer_operand_mir_type(operand, func).is_unsigned()
+        });
+        if unsigned {
+            self.emit_unsigned_overflow_flag(out, op, lhs, rhs, &result, ty)?;
+        } else {
+            self.emit_overflow_flag(out, op, lhs, rhs, &result, ty)?;
+        }
+        writeln!(out, "    local.set ${}", dest)?;
+        Ok(())
+    }
+
+    /// Emit `lhs op rhs` for a saturating op, clamping to the type's min/max on overflow
+    fn emit_saturating_binop(
+        &self,
+        out: &mut String,
+        dest: &str,
+        op: MirBinOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        func: &MirFunction,
+    ) -> WasmCodeGenResult<()> {
+        let ty = self.infer_operand_wasm_type(lhs, func);
+        let (min, max) = if ty == "i32" {
+            (i32::MIN as i64, i32::MAX as i64)
+        } else {
+            (i64::MIN, i64::MAX)
+        };
+        self.emit_operand(out, lhs)?;
+        self.emit_operand(out, rhs)?;
+        writeln!(out, "    {}", self.binop_to_wasm(op, lhs, func)?)?;
+        writeln!(out, "    local.set ${}", dest)?;
+        // Clamp towards the sign of the exact result
+        writeln!(out, "    {}.const {}", ty, min)?;
+        writeln!(out, "    {}.const {}", ty, max)?;
+        self.emit_operand(out, lhs)?;
+        if op == MirBinOp::MulSat {
+            self.emit_operand(out, rhs)?;
+            writeln!(out, "    {}.xor", ty)?;
+        }
+        writeln!(out, "    {}.const 0", ty)?;
+        writeln!(out, "    {}.lt_s", ty)?;
+        writeln!(out, "    select")?;
+        writeln!(out, "    local.get ${}", dest)?;
+        self.emit_overflow_flag(out, op, lhs, rhs, &[format!("local.get ${}", dest)], ty)?;
+        writeln!(out, "    select")?;
+        writeln!(out, "    local.set ${}", dest)?;
+        Ok(())
+    }
+
+    /// Push an i32 flag that is set when `lhs op rhs` overflowed, given the
+    /// instructions that push its wrapped `result`
+    fn emit_overflow_flag(
+        &self,
+        out: &mut String,
+        op: MirBinOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        result: &[String],
+        ty: &str,
+    ) -> WasmCodeGenResult<()> {
+        let push_result = |out: &mut String| -> WasmCodeGenResult<()> {
+            for line in result {
+                writeln!(out, "    {}", line)?;
+            }
+            Ok(())
+        };
+        match op {
+            MirBinOp::AddChecked | MirBinOp::AddSat | MirBinOp::AddOverflows => {
+                // Both operands differ in sign from the result: ((a ^ r) & (b ^ r)) < 0
+                self.emit_operand_as(out, lhs, ty)?;
+                push_result(out)?;
+                writeln!(out, "    {}.xor", ty)?;
+                self.emit_operand_as(out, rhs, ty)?;
+                push_result(out)?;
+                writeln!(out, "    {}.xor", ty)?;
+                writeln!(out, "    {}.and", ty)?;
+                writeln!(out, "    {}.const 0", ty)?;
+                writeln!(out, "    {}.lt_s", ty)?;
+            }
+            MirBinOp::SubChecked | MirBinOp::SubSat | MirBinOp::SubOverflows => {
+                // Operands differ in sign and the result took the sign of b: ((a ^ b) & (a ^ r)) < 0
+                self.emit_operand_as(out, lhs, ty)?;
+                self.emit_operand_as(out, rhs, ty)?;
+                writeln!(out, "    {}.xor", ty)?;
+                self.emit_operand_as(out, lhs, ty)?;
+                push_result(out)?;
+                writeln!(out, "    {}.xor", ty)?;
+                writeln!(out, "    {}.and", ty)?;
+                writeln!(out, "    {}.const 0", ty)?;
+                writeln!(out, "    {}.lt_s", ty)?;
+            }
+            _ => {
+                // a != 0 && r / a != b, with MIN * -1 tested directly since the division traps
+                let min = if ty == "i32" { i32::MIN as i64 } else { i64::MIN };
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.eqz", ty)?;
+                writeln!(out, "    if (result i32)")?;
+                writeln!(out, "    i32.const 0")?;
+                writeln!(out, "    else")?;
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.const -1", ty)?;
+                writeln!(out, "    {}.eq", ty)?;
+                writeln!(out, "    if (result i32)")?;
+                self.emit_operand_as(out, rhs, ty)?;
+                writeln!(out, "    {}.const {}", ty, min)?;
+                writeln!(out, "    {}.eq", ty)?;
+                writeln!(out, "    else")?;
+                push_result(out)?;
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.div_s", ty)?;
+                self.emit_operand_as(out, rhs, ty)?;
+                writeln!(out, "    {}.ne", ty)?;
+                writeln!(out, "    end")?;
+                writeln!(out, "    end")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Push an i32 flag that is set when unsigned `lhs op rhs` overflowed,
+    /// given the instructions that push its wrapped `result`
+    fn emit_unsigned_overflow_flag(
+        &self,
+        out: &mut String,
+        op: MirBinOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        result: &[String],
+        ty: &str,
+    ) -> WasmCodeGenResult<()> {
+        let push_result = |out: &mut String| -> WasmCodeGenResult<()> {
+            for line in result {
+                writeln!(out, "    {}", line)?;
+            }
+            Ok(())
+        };
+        match op {
+            MirBinOp::AddOverflows => {
+                // The sum wrapped below an operand: r < a
+                push_result(out)?;
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.lt_u", ty)?;
+            }
+            MirBinOp::SubOverflows => {
+                // a < b
+                self.emit_operand_as(out, lhs, ty)?;
+                self.emit_operand_as(out, rhs, ty)?;
+                writeln!(out, "    {}.lt_u", ty)?;
+            }
+            _ => {
+                // a != 0 && r / a != b
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.eqz", ty)?;
+                writeln!(out, "    if (result i32)")?;
+                writeln!(out, "    i32.const 0")?;
+                writeln!(out, "    else")?;
+                push_result(out)?;
+                self.emit_operand_as(out, lhs, ty)?;
+                writeln!(out, "    {}.div_u", ty)?;
+                self.emit_operand_as(out, rhs, ty)?;
+                writeln!(out, "    {}.ne", ty)?;
+                writeln!(out, "    end")?;
+            }
+        }
+        Ok(())
+    }
+
     /// Emit a terminator
     fn emit_terminator(
         &self,
@@ -786,16 +1596,31 @@ impl WasmCodeGen {
     ) -> WasmCodeGenResult<()> {
         match term {
             Terminator::Return(None) => {
-                if func.ret_ty != MirType::Unit {
+                match &func.ret_ty {
+                    MirType::Tuple(elements) if self.target.supports_multi_value() => {
+                        for ty in elements {
+                            writeln!(out, "    {}", self.default_value(ty))?;
+                        }
+                    }
+                    MirType::Unit => {}
                     // Return default value
-                    let default = self.default_value(&func.ret_ty);
-                    writeln!(out, "    {}", default)?;
+                    ty => writeln!(out, "    {}", self.default_value(ty))?,
                 }
                 writeln!(out, "    return")?;
             }
 
             Terminator::Return(Some(val)) => {
-                self.emit_operand(out, val)?;
+                match &func.ret_ty {
+                    // Unpack the tuple block into one result per element
+                    MirType::Tuple(elements) if self.target.supports_multi_value() => {
+                        for (i, ty) in elements.iter().enumerate() {
+                            self.emit_operand(out, val)?;
+                            writeln!(out, "    i64.load offset={}", i * 8)?;
+                            self.emit_narrow_from_word(out, self.mir_type_to_wasm(ty))?;
+                        }
+                    }
+                    _ => self.emit_operand(out, val)?,
+                }
                 writeln!(out, "    return")?;
             }
 
@@ -870,6 +1695,10 @@ impl WasmCodeGen {
             MirType::Array { .. } => "i32",
             // v0.64: Character type (Unicode codepoint as i32)
             MirType::Char => "i32",
+            // Closures are i64 handles to a (table index, environment) block
+            MirType::Fn { .. } => "i64",
+            // Tuples are pointers to one word per element
+            MirType::Tuple(_) => "i32",
         }
     }
 
@@ -881,6 +1710,40 @@ impl WasmCodeGen {
         }
     }
 
+    /// WASM results of a function returning `ty`: one per tuple element
+    /// when the target supports multi-value, otherwise at most one
+    fn result_types(&self, ty: &MirType) -> Vec<&'static str> {
+        match ty {
+            MirType::Tuple(elements) if self.target.supports_multi_value() => {
+                elements.iter().map(|e| self.mir_type_to_wasm(e)).collect()
+            }
+            _ => {
+                let ret = self.mir_type_to_wasm_result(ty);
+                if ret.is_empty() { vec![] } else { vec![ret] }
+            }
+        }
+    }
+
+    /// Element types of `callee`'s result when it comes back as several
+    /// values, empty otherwise
+    fn multi_value_elements(&self, callee: &str) -> &[MirType] {
+        match self.fn_returns.get(callee) {
+            Some(MirType::Tuple(elements)) if self.target.supports_multi_value() => elements,
+            _ => &[],
+        }
+    }
+
+    /// Type of a constant-index load from a place known to hold a tuple
+    fn tuple_element_type(&self, array: &Place, index: &Operand, func: &MirFunction) -> Option<MirType> {
+        let MirType::Tuple(elements) = self.infer_place_mir_type(&array.name, func) else {
+            return None;
+        };
+        match index {
+            Operand::Constant(Constant::Int(i)) => usize::try_from(*i).ok().and_then(|i| elements.get(i).cloned()),
+            _ => None,
+        }
+    }
+
     /// Get default value for a type
     fn default_value(&self, ty: &MirType) -> &'static str {
         match ty {
@@ -902,6 +1765,8 @@ impl WasmCodeGen {
             MirType::Enum { .. } => "i32.const 0",
             // v0.19.3: Array pointers default to null (0)
             MirType::Array { .. } => "i32.const 0",
+            MirType::Fn { .. } => "i64.const 0",
+            MirType::Tuple(_) => "i32.const 0",
         }
     }
 
@@ -922,12 +1787,17 @@ impl WasmCodeGen {
             MirBinOp::SubWrap => format!("{}.sub", ty),
             MirBinOp::MulWrap => format!("{}.mul", ty),
 
-            // v0.38: Checked arithmetic (for now, same as normal ops; full Option handling later)
+            // v0.38: Checked arithmetic: the wrapped value, see `emit_checked_binop`
             MirBinOp::AddChecked => format!("{}.add", ty),
             MirBinOp::SubChecked => format!("{}.sub", ty),
             MirBinOp::MulChecked => format!("{}.mul", ty),
 
-            // v0.38: Saturating arithmetic (for now, same as normal ops; full saturation logic later)
+            // Overflow tests: the wrapped value they judge, see `emit_overflow_test`
+            MirBinOp::AddOverflows => format!("{}.add", ty),
+            MirBinOp::SubOverflows => format!("{}.sub", ty),
+            MirBinOp::MulOverflows => format!("{}.mul", ty),
+
+            // v0.38: Saturating arithmetic: the wrapped value, see `emit_saturating_binop`
             MirBinOp::AddSat => format!("{}.add", ty),
             MirBinOp::SubSat => format!("{}.sub", ty),
             MirBinOp::MulSat => format!("{}.mul", ty),
@@ -946,6 +1816,14 @@ impl WasmCodeGen {
             MirBinOp::Le => format!("{}.le_s", ty),
             MirBinOp::Ge => format!("{}.ge_s", ty),
 
+            // Unsigned integer division and comparison (u32, u64)
+            MirBinOp::UDiv => format!("{}.div_u", ty),
+            MirBinOp::UMod => format!("{}.rem_u", ty),
+            MirBinOp::ULt => format!("{}.lt_u", ty),
+            MirBinOp::UGt => format!("{}.gt_u", ty),
+            MirBinOp::ULe => format!("{}.le_u", ty),
+            MirBinOp::UGe => format!("{}.ge_u", ty),
+
             // Floating-point comparison
             MirBinOp::FEq => "f64.eq".to_string(),
             MirBinOp::FNe => "f64.ne".to_string(),
@@ -961,6 +1839,7 @@ impl WasmCodeGen {
             // v0.32: Shift operators
             MirBinOp::Shl => format!("{}.shl", ty),
             MirBinOp::Shr => format!("{}.shr_s", ty),  // arithmetic shift right (signed)
+            MirBinOp::UShr => format!("{}.shr_u", ty),  // logical shift right (u32, u64)
 
             // v0.36: Bitwise operators
             MirBinOp::Band => format!("{}.and", ty),
@@ -987,6 +1866,7 @@ impl WasmCodeGen {
                     // v0.64: Character type
                     Constant::Char(_) => MirType::Char,
                     Constant::Unit => MirType::Unit,
+                    Constant::FnAddr(_) => MirType::I64,
                 };
                 Some((dest.name.clone(), ty))
             }
@@ -1000,9 +1880,13 @@ impl WasmCodeGen {
                 let ty = match op {
                     MirBinOp::Eq | MirBinOp::Ne | MirBinOp::Lt | MirBinOp::Gt |
                     MirBinOp::Le | MirBinOp::Ge | MirBinOp::FEq | MirBinOp::FNe |
+                    MirBinOp::ULt | MirBinOp::UGt | MirBinOp::ULe | MirBinOp::UGe |
                     MirBinOp::FLt | MirBinOp::FGt | MirBinOp::FLe | MirBinOp::FGe |
                     MirBinOp::And | MirBinOp::Or | MirBinOp::Implies => MirType::Bool,
+                    MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows => MirType::Bool,
                     MirBinOp::FAdd | MirBinOp::FSub | MirBinOp::FMul | MirBinOp::FDiv => MirType::F64,
+                    // Checked ops produce an `Option` handle
+                    MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked => MirType::I64,
                     _ => self.infer_operand_mir_type(lhs, func),
                 };
                 Some((dest.name.clone(), ty))
@@ -1017,7 +1901,16 @@ impl WasmCodeGen {
                 };
                 Some((dest.name.clone(), ty))
             }
-            MirInst::Call { dest, .. } => {
+            MirInst::Cast { dest, ty, .. } => Some((dest.name.clone(), ty.clone())),
+            MirInst::Call { dest, func: callee, .. } => {
+                // Calls to builtins and unit functions default to i64
+                let ty = match self.fn_returns.get(callee) {
+                    Some(MirType::Unit) | None => MirType::I64,
+                    Some(ty) => ty.clone(),
+                };
+                dest.as_ref().map(|d| (d.name.clone(), ty))
+            }
+            MirInst::CallIndirect { dest, .. } => {
                 dest.as_ref().map(|d| (d.name.clone(), MirType::I64)) // Default to i64
             }
             MirInst::Phi { dest, values } => {
@@ -1043,13 +1936,8 @@ impl WasmCodeGen {
                 // Field store has no destination
                 None
             }
-            // v0.19.1: Enum variant
-            MirInst::EnumVariant { dest, enum_name, .. } => {
-                Some((dest.name.clone(), MirType::Enum {
-                    name: enum_name.clone(),
-                    variants: vec![],  // Simplified: don't track variants here
-                }))
-            }
+            // v0.19.1: Enum variant, held as an i64 handle
+            MirInst::EnumVariant { dest, .. } => Some((dest.name.clone(), MirType::I64)),
             // v0.19.3: Array operations
             MirInst::ArrayInit { dest, element_type, elements } => {
                 Some((dest.name.clone(), MirType::Array {
@@ -1057,11 +1945,12 @@ impl WasmCodeGen {
                     size: Some(elements.len()),
                 }))
             }
-            MirInst::IndexLoad { dest, .. } => {
-                // Index load result type defaults to i64
-                Some((dest.name.clone(), MirType::I64))
+            MirInst::IndexLoad { dest, array, index } => {
+                // Tuple elements keep their type; other loads default to i64
+                let ty = self.tuple_element_type(array, index, func).unwrap_or(MirType::I64);
+                Some((dest.name.clone(), ty))
             }
-            MirInst::IndexStore { .. } => {
+            MirInst::IndexStore { .. } | MirInst::DebugLoc { .. } => {
                 // Index store has no destination
                 None
             }
@@ -1097,6 +1986,7 @@ impl WasmCodeGen {
                 // v0.64: Character type
                 Constant::Char(_) => MirType::Char,
                 Constant::Unit => MirType::Unit,
+                Constant::FnAddr(_) => MirType::I64,
             },
             Operand::Place(p) => self.infer_place_mir_type(&p.name, func),
         }
@@ -1113,6 +2003,7 @@ impl WasmCodeGen {
                 // v0.64: Character type
                 Constant::Char(_) => "i32",
                 Constant::Unit => "i32",
+                Constant::FnAddr(_) => "i64",
             },
             Operand::Place(p) => {
                 // Check parameters
@@ -1170,6 +2061,8 @@ mod tests {
                 postconditions: vec![],
                 is_pure: false,
                 is_const: false,
+                is_inline: false,
+                is_public: false,
             }],
             extern_fns: vec![],
         };
@@ -1203,6 +2096,8 @@ mod tests {
                 postconditions: vec![],
                 is_pure: false,
                 is_const: false,
+                is_inline: false,
+                is_public: false,
             }],
             extern_fns: vec![],
         };
@@ -1214,6 +2109,67 @@ mod tests {
         assert!(wat.contains("wasi_snapshot_preview1"));
     }
 
+    #[test]
+    fn test_overflow_operators() {
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "ops".to_string(),
+                params: vec![
+                    ("a".to_string(), MirType::I64),
+                    ("b".to_string(), MirType::I64),
+                    ("c".to_string(), MirType::U32),
+                    ("d".to_string(), MirType::U32),
+                ],
+                ret_ty: MirType::I64,
+                locals: vec![],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![
+                        MirInst::BinOp {
+                            dest: Place::new("_t0"),
+                            op: MirBinOp::AddChecked,
+                            lhs: Operand::Place(Place::new("a")),
+                            rhs: Operand::Place(Place::new("b")),
+                        },
+                        MirInst::BinOp {
+                            dest: Place::new("_t1"),
+                            op: MirBinOp::MulSat,
+                            lhs: Operand::Place(Place::new("a")),
+                            rhs: Operand::Place(Place::new("b")),
+                        },
+                        MirInst::BinOp {
+                            dest: Place::new("_t2"),
+                            op: MirBinOp::AddOverflows,
+                            lhs: Operand::Place(Place::new("c")),
+                            rhs: Operand::Place(Place::new("d")),
+                        },
+                    ],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("_t1")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let codegen = WasmCodeGen::with_target(WasmTarget::Standalone);
+        let wat = codegen.generate(&program).unwrap();
+
+        // The allocator is available on every target
+        assert!(wat.contains("(func $alloc (param $size i32) (result i32)"));
+        // Checked ops store the wrapped value and select the tag from the overflow flag
+        assert!(wat.contains("i64.store offset=8"));
+        assert!(wat.contains("i64.const 1034\n    i64.const 1036"));
+        // Multiplication overflow avoids dividing MIN by -1
+        assert!(wat.contains("i64.const -9223372036854775808\n    i64.eq"));
+        // Unsigned overflow tests compare the wrapped result against an operand
+        assert!(wat.contains("i32.add\n    local.get $c\n    i32.lt_u\n    local.set $_t2"));
+    }
+
     #[test]
     fn test_browser_target() {
         let program = MirProgram {
@@ -1323,6 +2279,8 @@ mod tests {
                 postconditions: vec![],
                 is_pure: false,
                 is_const: false,
+                is_inline: false,
+                is_public: false,
             }],
             extern_fns: vec![],
         };
@@ -1334,6 +2292,58 @@ mod tests {
         assert!(wat.contains("i64.add"));
     }
 
+    #[test]
+    fn test_math_builtins_use_native_instructions() {
+        let program = MirProgram {
+            functions: vec![MirFunction {
+                name: "root".to_string(),
+                params: vec![("x".to_string(), MirType::F64)],
+                ret_ty: MirType::F64,
+                locals: vec![("_t0".to_string(), MirType::F64)],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![MirInst::Call {
+                        dest: Some(Place::new("_t0")),
+                        func: "sqrt".to_string(),
+                        args: vec![Operand::Place(Place::new("x"))],
+                    }],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("_t0")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        };
+
+        let codegen = WasmCodeGen::with_target(WasmTarget::Standalone);
+        let wat = codegen.generate(&program).unwrap();
+
+        assert!(wat.contains("f64.sqrt"));
+        assert!(!wat.contains("call $sqrt"));
+    }
+
+    #[test]
+    fn test_math_builtins_import_from_host() {
+        let source = "fn f(x: f64) -> f64 = pow(sin(x), 2.0) + round(x);";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = crate::mir::lower_program(&ast);
+
+        let wat = WasmCodeGen::with_target(WasmTarget::Browser).generate(&mir).unwrap();
+        assert!(wat.contains("(import \"env\" \"sin\"\n    (func $sin (param f64) (result f64)))"));
+        assert!(wat.contains("(import \"env\" \"pow\"\n    (func $pow (param f64 f64) (result f64)))"));
+        assert!(wat.contains("(func $round (param f64) (result f64))"));
+        assert!(!wat.contains("\"cos\""));
+
+        // The standalone target has no host to import from
+        let err = WasmCodeGen::with_target(WasmTarget::Standalone).generate(&mir).unwrap_err();
+        assert!(err.to_string().contains("sin requires a host import"));
+    }
+
     #[test]
     fn test_extern_fn_import() {
         use crate::mir::MirExternFn;
@@ -1357,4 +2367,131 @@ mod tests {
         assert!(wat.contains("(param i32 i32 i32 i32)"));
         assert!(wat.contains("(result i32)"));
     }
+
+    fn file_program(path: &str) -> MirProgram {
+        use crate::mir::Place;
+
+        MirProgram {
+            functions: vec![MirFunction {
+                name: "main".to_string(),
+                params: vec![],
+                ret_ty: MirType::I64,
+                locals: vec![("_t0".to_string(), MirType::I64)],
+                blocks: vec![BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![MirInst::Call {
+                        dest: Some(Place::new("_t0")),
+                        func: "file_size".to_string(),
+                        args: vec![Operand::Constant(Constant::String(path.to_string()))],
+                    }],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("_t0")))),
+                }],
+                preconditions: vec![],
+                postconditions: vec![],
+                is_pure: false,
+                is_const: false,
+                is_inline: false,
+                is_public: false,
+            }],
+            extern_fns: vec![],
+        }
+    }
+
+    #[test]
+    fn test_string_constants_in_data_segments() {
+        let codegen = WasmCodeGen::with_target(WasmTarget::Wasi);
+        let wat = codegen.generate(&file_program("a \"b\".txt")).unwrap();
+
+        // Header { data: 1036, len: 9, cap: 9 } followed by the escaped bytes
+        assert!(wat.contains(r#"(data (i32.const 1024) "\0c\04\00\00\09\00\00\00\09\00\00\00a \22b\22.txt")"#));
+        assert!(wat.contains("i32.const 1024  ;; string"));
+        // The heap starts after the string constants
+        assert!(wat.contains("(global $heap_ptr (mut i32) (i32.const 1048))"));
+    }
+
+    #[test]
+    fn test_wasi_file_builtins() {
+        let codegen = WasmCodeGen::with_target(WasmTarget::Wasi);
+        let wat = codegen.generate(&file_program("data.txt")).unwrap();
+
+        assert!(wat.contains("(import \"wasi_snapshot_preview1\" \"path_open\""));
+        assert!(wat.contains("(func $file_size (param $path i32) (result i64)"));
+        assert!(wat.contains("(func $read_file (param $path i32) (result i32)"));
+        assert!(wat.contains("call $file_size"));
+        // Imports come before any definitions
+        assert!(wat.find("(import").unwrap() < wat.find("(memory").unwrap());
+    }
+
+    #[test]
+    fn test_file_builtins_require_wasi() {
+        let codegen = WasmCodeGen::with_target(WasmTarget::Browser);
+        let err = codegen.generate(&file_program("data.txt")).unwrap_err();
+        assert!(err.to_string().contains("file_size requires the WASI target"));
+    }
+
+    #[test]
+    fn test_closure_calls_go_through_the_table() {
+        let source = "fn f(base: i64) -> i64 = { let add = fn |x: i64| { x + base }; add(5) };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let wat = WasmCodeGen::new().generate(&crate::mir::lower_program(&program)).unwrap();
+
+        assert!(wat.contains("(func $f.closure0 (param $__env i64) (param $x i64) (result i64)"));
+        assert!(wat.contains("i64.const 0  ;; &f.closure0"));
+        assert!(wat.contains("call_indirect (param i64 i64) (result i64)"));
+        assert!(wat.contains("(table 1 funcref)"));
+        assert!(wat.contains("(elem (i32.const 0) func $f.closure0)"));
+    }
+
+    #[test]
+    fn test_unsigned_operators() {
+        let source = "fn half(x: u32) -> u32 = x / 2;\n\
+                      fn below(a: u64, b: u64) -> bool = a < b;\n\
+                      fn signed(a: i32, b: i32) -> bool = a < b;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let wat = WasmCodeGen::new().generate(&crate::mir::lower_program(&program)).unwrap();
+
+        // The literal divisor takes the width of the u32 operand
+        assert!(wat.contains("i32.const 2\n    i32.div_u"));
+        assert!(wat.contains("i64.lt_u"));
+        assert!(wat.contains("i32.lt_s"));
+        validate(&wat);
+    }
+
+    fn tuple_program() -> MirProgram {
+        let source = "fn swap(a: i64, b: i64) -> (i64, i64) = (b, a);\n\
+                      fn split(x: f64) -> (f64, bool) = (x, x > 0.0);\n\
+                      fn main() -> i64 = { let p = swap(1, 2); let q = split(2.5); let r = q.0 + 1.0; p.0 * 10 + p.1 };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        crate::mir::lower_program(&program)
+    }
+
+    fn validate(wat: &str) {
+        let wasm = wat::parse_str(wat).unwrap_or_else(|e| panic!("{e}\n{wat}"));
+        wasmparser::validate(&wasm).unwrap_or_else(|e| panic!("{e}\n{wat}"));
+    }
+
+    #[test]
+    fn test_tuple_returns_use_multi_value() {
+        assert!(WasmTarget::Wasi.supports_multi_value());
+        let wat = WasmCodeGen::new().generate(&tuple_program()).unwrap();
+
+        assert!(wat.contains("(func $swap (param $a i64) (param $b i64) (result i64 i64)"));
+        assert!(wat.contains("(func $split (param $x f64) (result f64 i32)"));
+        // The caller packs both results back into a tuple block
+        assert!(wat.contains("local.set $_t0.1\n    local.set $_t0.0"));
+        validate(&wat);
+    }
+
+    #[test]
+    fn test_standalone_tuple_returns_a_pointer() {
+        assert!(!WasmTarget::Standalone.supports_multi_value());
+        let wat = WasmCodeGen::with_target(WasmTarget::Standalone).generate(&tuple_program()).unwrap();
+
+        assert!(wat.contains("(func $swap (param $a i64) (param $b i64) (result i32)"));
+        assert!(!wat.contains("(result i64 i64)"));
+        validate(&wat);
+    }
 }
@@ -9,8 +9,19 @@
 //! - PartialEq: Generate equality comparison
 //! - Eq: Marker trait for total equality
 //! - Default: Generate default value constructor
+//! - Hash: Generate `hash()` combining field hashes
+//! - Show: Generate `show()` rendering the value as a `String`
+//!
+//! `Eq` and `Show` expand to ordinary methods: [`derived_impls`] builds an
+//! inherent `impl` block per deriving type, which the type checker,
+//! interpreter and MIR lowering register alongside the written ones.
+
+use std::collections::HashMap;
 
-use crate::ast::{Attribute, Expr, StructDef, EnumDef};
+use crate::ast::{
+    Attribute, BinOp, EnumDef, Expr, FnDef, ImplBlock, Item, MatchArm, Param, Pattern, Program, Span,
+    Spanned, StructDef, Type, Visibility,
+};
 
 /// Derivable traits supported by @derive attribute
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,6 +38,8 @@ pub enum DeriveTrait {
     Default,
     /// Hash: Generate hash implementation
     Hash,
+    /// Show: Generate `show()` string rendering
+    Show,
 }
 
 impl DeriveTrait {
@@ -39,10 +52,17 @@ impl DeriveTrait {
             "Eq" => Some(DeriveTrait::Eq),
             "Default" => Some(DeriveTrait::Default),
             "Hash" => Some(DeriveTrait::Hash),
+            "Show" => Some(DeriveTrait::Show),
             _ => None,
         }
     }
 
+    /// Whether deriving this trait also provides `other`
+    /// (`PartialEq` gives the same structural equality as `Eq`)
+    pub fn provides(self, other: DeriveTrait) -> bool {
+        self == other || (self == DeriveTrait::PartialEq && other == DeriveTrait::Eq)
+    }
+
     /// Get trait name as string
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -52,6 +72,7 @@ impl DeriveTrait {
             DeriveTrait::Eq => "Eq",
             DeriveTrait::Default => "Default",
             DeriveTrait::Hash => "Hash",
+            DeriveTrait::Show => "Show",
         }
     }
 }
@@ -78,6 +99,16 @@ pub fn extract_derive_traits(attrs: &[Attribute]) -> Vec<DeriveTrait> {
     traits
 }
 
+/// Span of the `@derive(...)` attribute naming a trait that provides `trait_kind`
+pub fn derive_attr_span(attrs: &[Attribute], trait_kind: DeriveTrait) -> Option<Span> {
+    attrs.iter().find_map(|attr| {
+        let provides = extract_derive_traits(std::slice::from_ref(attr))
+            .iter()
+            .any(|t| t.provides(trait_kind));
+        provides.then(|| attr.span())
+    })
+}
+
 /// Check if a struct has a specific derive trait
 pub fn has_derive_trait(def: &StructDef, trait_kind: DeriveTrait) -> bool {
     extract_derive_traits(&def.attributes).contains(&trait_kind)
@@ -119,6 +150,293 @@ impl<'a> DeriveContext<'a> {
     }
 }
 
+/// Inherent impl blocks for the `eq(self, other)` and `show(self)` methods
+/// of non-generic structs and enums deriving `Eq` (or `PartialEq`) and
+/// `Show`. Every synthesized node carries the span of the derive attribute.
+///
+/// Fields are assumed to support the trait; the type checker rejects the
+/// derive otherwise.
+pub fn derived_impls(program: &Program) -> Vec<ImplBlock> {
+    let aliases: HashMap<&str, &Type> = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::TypeAlias(t) if t.type_params.is_empty() => Some((t.name.node.as_str(), &t.target.node)),
+            _ => None,
+        })
+        .collect();
+
+    let mut impls = Vec::new();
+    for item in &program.items {
+        let (name, attrs, shape) = match item {
+            Item::StructDef(s) if s.type_params.is_empty() => (&s.name, &s.attributes, Shape::Struct(s)),
+            Item::EnumDef(e) if e.type_params.is_empty() => (&e.name, &e.attributes, Shape::Enum(e)),
+            _ => continue,
+        };
+        let synth = |span| Synth { aliases: &aliases, self_ty: Type::Named(name.node.clone()), span };
+
+        let mut methods = Vec::new();
+        let mut impl_span = None;
+        if let Some(span) = derive_attr_span(attrs, DeriveTrait::Eq) {
+            let synth = synth(span);
+            let body = match shape {
+                Shape::Struct(s) => synth.struct_eq(s),
+                Shape::Enum(e) => synth.enum_eq(e),
+            };
+            methods.push(synth.method("eq", true, Type::Bool, body));
+            impl_span.get_or_insert(span);
+        }
+        if let Some(span) = derive_attr_span(attrs, DeriveTrait::Show) {
+            let synth = synth(span);
+            let body = match shape {
+                Shape::Struct(s) => synth.struct_show(s),
+                Shape::Enum(e) => synth.enum_show(e),
+            };
+            methods.push(synth.method("show", false, Type::String, body));
+            impl_span.get_or_insert(span);
+        }
+
+        if let Some(span) = impl_span {
+            impls.push(ImplBlock {
+                attributes: vec![],
+                type_params: vec![],
+                trait_name: None,
+                target_type: Spanned::new(Type::Named(name.node.clone()), span),
+                methods,
+                span,
+            });
+        }
+    }
+    impls
+}
+
+/// Definition a derive expands
+#[derive(Clone, Copy)]
+enum Shape<'a> {
+    Struct(&'a StructDef),
+    Enum(&'a EnumDef),
+}
+
+/// Builds the AST of derived methods for one type
+struct Synth<'a> {
+    aliases: &'a HashMap<&'a str, &'a Type>,
+    self_ty: Type,
+    span: Span,
+}
+
+impl Synth<'_> {
+    fn spanned<T>(&self, node: T) -> Spanned<T> {
+        Spanned::new(node, self.span)
+    }
+
+    fn var(&self, name: &str) -> Spanned<Expr> {
+        self.spanned(Expr::Var(name.to_string()))
+    }
+
+    fn str_lit(&self, s: &str) -> Spanned<Expr> {
+        self.spanned(Expr::StringLit(s.to_string()))
+    }
+
+    fn binary(&self, left: Spanned<Expr>, op: BinOp, right: Spanned<Expr>) -> Spanned<Expr> {
+        self.spanned(Expr::Binary { left: Box::new(left), op, right: Box::new(right) })
+    }
+
+    fn call(&self, func: &str, args: Vec<Spanned<Expr>>) -> Spanned<Expr> {
+        self.spanned(Expr::Call { func: func.to_string(), args })
+    }
+
+    fn field(&self, base: &str, field: &Spanned<String>) -> Spanned<Expr> {
+        self.spanned(Expr::FieldAccess { expr: Box::new(self.var(base)), field: field.clone() })
+    }
+
+    /// `{ let recv: ty = value; recv.method(args) }`. The annotation tells
+    /// MIR lowering which type's method to call.
+    fn typed_method_call(&self, ty: &Type, value: Spanned<Expr>, method: &str, args: Vec<Spanned<Expr>>) -> Spanned<Expr> {
+        let call = self.spanned(Expr::MethodCall {
+            receiver: Box::new(self.var("recv")),
+            method: method.to_string(),
+            args,
+        });
+        self.spanned(Expr::Let {
+            name: "recv".to_string(),
+            mutable: false,
+            ty: Some(self.spanned(ty.clone())),
+            value: Box::new(value),
+            body: Box::new(call),
+        })
+    }
+
+    /// Strings joined with `+`
+    fn concat(&self, parts: Vec<Spanned<Expr>>) -> Spanned<Expr> {
+        parts
+            .into_iter()
+            .reduce(|acc, part| self.binary(acc, BinOp::Add, part))
+            .unwrap_or_else(|| self.str_lit(""))
+    }
+
+    /// Conditions joined with `and`; `true` when there are none
+    fn all(&self, conds: Vec<Spanned<Expr>>) -> Spanned<Expr> {
+        conds
+            .into_iter()
+            .reduce(|acc, cond| self.binary(acc, BinOp::And, cond))
+            .unwrap_or_else(|| self.spanned(Expr::BoolLit(true)))
+    }
+
+    /// Field type with aliases and refinements stripped
+    fn resolve<'t>(&'t self, ty: &'t Type) -> &'t Type {
+        let mut ty = ty.base_type();
+        // Alias cycles are rejected by the type checker; the bound only keeps
+        // this from looping before then
+        for _ in 0..self.aliases.len() {
+            match ty {
+                Type::Named(name) if self.aliases.contains_key(name.as_str()) => {
+                    ty = self.aliases[name.as_str()].base_type();
+                }
+                _ => break,
+            }
+        }
+        ty
+    }
+
+    /// `lhs == rhs` for one field: derived structs and enums call their `eq`
+    fn field_eq(&self, ty: &Type, lhs: Spanned<Expr>, rhs: Spanned<Expr>) -> Spanned<Expr> {
+        match self.resolve(ty) {
+            ty @ Type::Named(_) => self.typed_method_call(ty, lhs, "eq", vec![rhs]),
+            _ => self.binary(lhs, BinOp::Eq, rhs),
+        }
+    }
+
+    /// One field rendered as a `String`
+    fn field_show(&self, ty: &Type, value: Spanned<Expr>) -> Spanned<Expr> {
+        match self.resolve(ty) {
+            Type::I64 => self.call("int_to_string", vec![value]),
+            Type::I32 | Type::U32 | Type::U64 => {
+                let widened = self.spanned(Expr::Cast { expr: Box::new(value), ty: self.spanned(Type::I64) });
+                self.call("int_to_string", vec![widened])
+            }
+            Type::Char => self.call("char_to_string", vec![value]),
+            Type::Bool => self.spanned(Expr::If {
+                cond: Box::new(value),
+                then_branch: Box::new(self.str_lit("true")),
+                else_branch: Box::new(self.str_lit("false")),
+            }),
+            Type::String => self.concat(vec![self.str_lit("\""), value, self.str_lit("\"")]),
+            ty => self.typed_method_call(ty, value, "show", vec![]),
+        }
+    }
+
+    /// `fn name(self: T[, other: T]) -> ret = body`
+    fn method(&self, name: &str, with_other: bool, ret: Type, body: Spanned<Expr>) -> FnDef {
+        let param = |name: &str| Param { name: self.spanned(name.to_string()), ty: self.spanned(self.self_ty.clone()) };
+        let mut params = vec![param("self")];
+        if with_other {
+            params.push(param("other"));
+        }
+        FnDef {
+            attributes: vec![],
+            visibility: Visibility::Public,
+            name: self.spanned(name.to_string()),
+            type_params: vec![],
+            params,
+            ret_name: None,
+            ret_ty: self.spanned(ret),
+            pre: None,
+            post: None,
+            contracts: vec![],
+            body,
+            span: self.span,
+        }
+    }
+
+    fn struct_eq(&self, def: &StructDef) -> Spanned<Expr> {
+        self.all(
+            def.fields
+                .iter()
+                .map(|f| self.field_eq(&f.ty.node, self.field("self", &f.name), self.field("other", &f.name)))
+                .collect(),
+        )
+    }
+
+    /// `Point { x: 1, y: 2 }`, or just `Point` without fields
+    fn struct_show(&self, def: &StructDef) -> Spanned<Expr> {
+        if def.fields.is_empty() {
+            return self.str_lit(&def.name.node);
+        }
+        let mut parts = vec![self.str_lit(&format!("{} {{ ", def.name.node))];
+        for (i, f) in def.fields.iter().enumerate() {
+            let sep = if i == 0 { "" } else { ", " };
+            parts.push(self.str_lit(&format!("{}{}: ", sep, f.name.node)));
+            parts.push(self.field_show(&f.ty.node, self.field("self", &f.name)));
+        }
+        parts.push(self.str_lit(" }"));
+        self.concat(parts)
+    }
+
+    /// `Enum::Variant(prefix0, prefix1, ...)`
+    fn variant_pattern(&self, def: &EnumDef, variant: usize, prefix: &str) -> Spanned<Pattern> {
+        let v = &def.variants[variant];
+        self.spanned(Pattern::EnumVariant {
+            enum_name: def.name.node.clone(),
+            variant: v.name.node.clone(),
+            bindings: (0..v.fields.len()).map(|i| self.spanned(Pattern::Var(format!("{prefix}{i}")))).collect(),
+        })
+    }
+
+    fn arm(&self, pattern: Spanned<Pattern>, body: Spanned<Expr>) -> MatchArm {
+        MatchArm { pattern, guard: None, body }
+    }
+
+    fn match_on(&self, scrutinee: &str, arms: Vec<MatchArm>) -> Spanned<Expr> {
+        self.spanned(Expr::Match { expr: Box::new(self.var(scrutinee)), arms })
+    }
+
+    /// Same variant, then equal payloads
+    fn enum_eq(&self, def: &EnumDef) -> Spanned<Expr> {
+        let arms = (0..def.variants.len())
+            .map(|i| {
+                let fields = &def.variants[i].fields;
+                let payload_eq = self.all(
+                    fields
+                        .iter()
+                        .enumerate()
+                        .map(|(j, ty)| self.field_eq(&ty.node, self.var(&format!("a{j}")), self.var(&format!("b{j}"))))
+                        .collect(),
+                );
+                let mut inner = vec![self.arm(self.variant_pattern(def, i, "b"), payload_eq)];
+                if def.variants.len() > 1 {
+                    inner.push(self.arm(self.spanned(Pattern::Wildcard), self.spanned(Expr::BoolLit(false))));
+                }
+                self.arm(self.variant_pattern(def, i, "a"), self.match_on("other", inner))
+            })
+            .collect();
+        self.match_on("self", arms)
+    }
+
+    /// `Variant(arg, ...)`, or just `Variant` without a payload
+    fn enum_show(&self, def: &EnumDef) -> Spanned<Expr> {
+        let arms = def
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if v.fields.is_empty() {
+                    return self.arm(self.variant_pattern(def, i, "a"), self.str_lit(&v.name.node));
+                }
+                let mut parts = vec![self.str_lit(&format!("{}(", v.name.node))];
+                for (j, ty) in v.fields.iter().enumerate() {
+                    if j > 0 {
+                        parts.push(self.str_lit(", "));
+                    }
+                    parts.push(self.field_show(&ty.node, self.var(&format!("a{j}"))));
+                }
+                parts.push(self.str_lit(")"));
+                self.arm(self.variant_pattern(def, i, "a"), self.concat(parts))
+            })
+            .collect();
+        self.match_on("self", arms)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +459,7 @@ mod tests {
         assert_eq!(DeriveTrait::from_str("Debug"), Some(DeriveTrait::Debug));
         assert_eq!(DeriveTrait::from_str("Clone"), Some(DeriveTrait::Clone));
         assert_eq!(DeriveTrait::from_str("PartialEq"), Some(DeriveTrait::PartialEq));
+        assert_eq!(DeriveTrait::from_str("Show"), Some(DeriveTrait::Show));
         assert_eq!(DeriveTrait::from_str("Unknown"), None);
     }
 
@@ -169,6 +488,43 @@ mod tests {
         assert!(traits.contains(&DeriveTrait::Eq));
     }
 
+    #[test]
+    fn test_derive_trait_provides() {
+        assert!(DeriveTrait::Eq.provides(DeriveTrait::Eq));
+        assert!(DeriveTrait::PartialEq.provides(DeriveTrait::Eq));
+        assert!(!DeriveTrait::Eq.provides(DeriveTrait::Debug));
+        assert!(!DeriveTrait::Debug.provides(DeriveTrait::Eq));
+    }
+
+    #[test]
+    fn test_derived_impls_cover_eq_and_show() {
+        let source = "@derive(PartialEq, Show) struct Point { x: i64 }
+                      @derive(Show) enum Flag { On, Off }
+                      @derive(Debug) struct Plain { x: i64 }
+                      @derive(Eq) struct Boxed<T> { value: T }";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+
+        let impls = derived_impls(&program);
+        let methods: Vec<(String, Vec<&str>)> = impls
+            .iter()
+            .map(|i| {
+                let names = i.methods.iter().map(|m| m.name.node.as_str()).collect();
+                (crate::ast::output::format_type(&i.target_type.node), names)
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                ("Point".to_string(), vec!["eq", "show"]),
+                ("Flag".to_string(), vec!["show"]),
+            ]
+        );
+        // Synthesized code points at the derive attribute
+        let span = impls[0].span;
+        assert_eq!(&source[span.start..span.end], "@derive(PartialEq, Show)");
+    }
+
     #[test]
     fn test_derive_context() {
         use crate::ast::Visibility;
@@ -0,0 +1,352 @@
+//! API documentation for `bmb doc`
+//!
+//! [`collect_symbols`] gathers the public items of a parsed file with their
+//! signatures, contracts and `///` text. The JSON document, the `--human`
+//! table and the Markdown pages of [`render_markdown`] all render from these
+//! symbols.
+
+use crate::ast::{FnDef, Item, LineIndex, Program, Type, Visibility};
+use crate::fmt::{extract_comments, format_expr, format_type, format_type_params};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A named or anonymous `where` contract
+#[derive(Debug, Clone, Serialize)]
+pub struct DocContract {
+    pub name: Option<String>,
+    pub condition: String,
+}
+
+/// A documented public item
+#[derive(Debug, Clone, Serialize)]
+pub struct DocSymbol {
+    pub kind: &'static str,
+    pub name: String,
+    pub file: String,
+    /// 1-based line of the item
+    pub line: usize,
+    pub signature: String,
+    pub pre: Option<String>,
+    pub post: Option<String>,
+    pub contracts: Vec<DocContract>,
+    /// User-defined types the signature mentions, other than the item itself
+    pub types: Vec<String>,
+    pub doc: Option<String>,
+}
+
+impl DocSymbol {
+    /// Markdown anchor, e.g. `struct.Rect`
+    fn anchor(&self) -> String {
+        format!("{}.{}", self.kind, self.name)
+    }
+}
+
+/// Collect the public items of a program with their signatures, contracts
+/// and doc text. Doc comments are the `///` lines between the end of the
+/// previous item and the start of this one.
+pub fn collect_symbols(program: &Program, source: &str, filename: &str) -> Vec<DocSymbol> {
+    let lines = LineIndex::new(source);
+    let comments = extract_comments(source);
+    let mut symbols = Vec::new();
+    let mut prev_end_line = 0;
+
+    for item in &program.items {
+        // Comment line numbers are 0-based
+        let span = item.span();
+        let item_start_line = lines.line_col(span.start).line - 1;
+        let doc_lines: Vec<&str> = comments
+            .iter()
+            .filter(|(line_num, _)| *line_num >= prev_end_line && *line_num < item_start_line)
+            .filter_map(|(_, text)| text.trim().strip_prefix("///"))
+            .map(|text| text.strip_prefix(' ').unwrap_or(text))
+            .collect();
+        prev_end_line = lines.line_col(span.end).line;
+
+        let mut types = Vec::new();
+        let (kind, name, signature, pre, post, contracts) = match item {
+            Item::FnDef(f) if f.visibility == Visibility::Public => {
+                for p in &f.params {
+                    collect_type_names(&p.ty.node, &mut types);
+                }
+                collect_type_names(&f.ret_ty.node, &mut types);
+                let contracts = f.contracts.iter()
+                    .map(|c| DocContract {
+                        name: c.name.as_ref().map(|n| n.node.clone()),
+                        condition: format_expr(&c.condition.node),
+                    })
+                    .collect();
+                ("fn", &f.name.node, format_fn_signature(f),
+                    f.pre.as_ref().map(|e| format_expr(&e.node)),
+                    f.post.as_ref().map(|e| format_expr(&e.node)),
+                    contracts)
+            }
+            Item::StructDef(st) if st.visibility == Visibility::Public => {
+                for f in &st.fields {
+                    collect_type_names(&f.ty.node, &mut types);
+                }
+                let fields: Vec<_> = st.fields.iter()
+                    .map(|f| format!("{}: {}", f.name.node, format_type(&f.ty.node)))
+                    .collect();
+                let sig = format!("struct {}{} {{ {} }}",
+                    st.name.node, format_type_params(&st.type_params), fields.join(", "));
+                ("struct", &st.name.node, sig, None, None, vec![])
+            }
+            Item::EnumDef(e) if e.visibility == Visibility::Public => {
+                for f in e.variants.iter().flat_map(|v| &v.fields) {
+                    collect_type_names(&f.node, &mut types);
+                }
+                let variants: Vec<_> = e.variants.iter()
+                    .map(|v| if v.fields.is_empty() {
+                        v.name.node.clone()
+                    } else {
+                        let fields: Vec<_> = v.fields.iter().map(|f| format_type(&f.node)).collect();
+                        format!("{}({})", v.name.node, fields.join(", "))
+                    })
+                    .collect();
+                let sig = format!("enum {}{} {{ {} }}",
+                    e.name.node, format_type_params(&e.type_params), variants.join(", "));
+                ("enum", &e.name.node, sig, None, None, vec![])
+            }
+            Item::TypeAlias(t) if t.visibility == Visibility::Public => {
+                collect_type_names(&t.target.node, &mut types);
+                let sig = format!("type {}{} = {}",
+                    t.name.node, format_type_params(&t.type_params), format_type(&t.target.node));
+                ("type", &t.name.node, sig, None, None, vec![])
+            }
+            Item::ConstDef(c) if c.visibility == Visibility::Public => {
+                let sig = format!("const {}: {} = {}",
+                    c.name.node, format_type(&c.ty.node), format_expr(&c.value.node));
+                ("const", &c.name.node, sig, None, None, vec![])
+            }
+            Item::TraitDef(t) if t.visibility == Visibility::Public => {
+                for m in &t.methods {
+                    for p in &m.params {
+                        collect_type_names(&p.ty.node, &mut types);
+                    }
+                    collect_type_names(&m.ret_ty.node, &mut types);
+                }
+                let methods: Vec<_> = t.methods.iter()
+                    .map(|m| {
+                        let params: Vec<_> = m.params.iter()
+                            .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+                            .collect();
+                        format!("fn {}({}) -> {};", m.name.node, params.join(", "), format_type(&m.ret_ty.node))
+                    })
+                    .collect();
+                let sig = format!("trait {}{} {{ {} }}",
+                    t.name.node, format_type_params(&t.type_params), methods.join(" "));
+                ("trait", &t.name.node, sig, None, None, vec![])
+            }
+            Item::ExternFn(e) if e.visibility == Visibility::Public => {
+                for p in &e.params {
+                    collect_type_names(&p.ty.node, &mut types);
+                }
+                collect_type_names(&e.ret_ty.node, &mut types);
+                let params: Vec<_> = e.params.iter()
+                    .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+                    .collect();
+                let sig = format!("extern fn {}({}) -> {}",
+                    e.name.node, params.join(", "), format_type(&e.ret_ty.node));
+                ("extern", &e.name.node, sig, None, None, vec![])
+            }
+            _ => continue,
+        };
+        types.retain(|t| t != name);
+
+        symbols.push(DocSymbol {
+            kind,
+            name: name.clone(),
+            file: filename.to_string(),
+            line: item_start_line + 1,
+            signature,
+            pre,
+            post,
+            contracts,
+            types,
+            doc: if doc_lines.is_empty() { None } else { Some(doc_lines.join("\n")) },
+        });
+    }
+
+    symbols
+}
+
+/// Render a function signature without its body
+fn format_fn_signature(fn_def: &FnDef) -> String {
+    let params: Vec<_> = fn_def.params.iter()
+        .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+        .collect();
+    let ret = match &fn_def.ret_name {
+        Some(name) => format!("{}: {}", name.node, format_type(&fn_def.ret_ty.node)),
+        None => format_type(&fn_def.ret_ty.node),
+    };
+    format!("fn {}{}({}) -> {}",
+        fn_def.name.node, format_type_params(&fn_def.type_params), params.join(", "), ret)
+}
+
+/// Names of the user-defined types `ty` mentions, in order of appearance
+fn collect_type_names(ty: &Type, names: &mut Vec<String>) {
+    let mut add = |name: &str| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    };
+    match ty {
+        Type::Named(name) | Type::Struct { name, .. } | Type::Enum { name, .. } => add(name),
+        Type::Generic { name, type_args } => {
+            add(name);
+            for arg in type_args {
+                collect_type_names(arg, names);
+            }
+        }
+        Type::Range(inner)
+        | Type::Ref(inner)
+        | Type::RefMut(inner)
+        | Type::Nullable(inner)
+        | Type::Array(inner, _)
+        | Type::ArrayConst(inner, _) => collect_type_names(inner, names),
+        Type::Refined { base, .. } => collect_type_names(base, names),
+        Type::Fn { params, ret } => {
+            for param in params {
+                collect_type_names(param, names);
+            }
+            collect_type_names(ret, names);
+        }
+        Type::Tuple(elements) => {
+            for elem in elements {
+                collect_type_names(elem, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Render a Markdown page for every source file with public items, linking
+/// the types named in each item to the item that defines them. Pages mirror
+/// the source layout under `root`; the result pairs each page's path,
+/// relative to the output directory, with its text.
+pub fn render_markdown(root: &Path, files: &[PathBuf], symbols: &[DocSymbol]) -> Vec<(PathBuf, String)> {
+    let page_of = |file: &str| {
+        let file = Path::new(file);
+        file.strip_prefix(root).unwrap_or(file).with_extension("md")
+    };
+
+    // Types may be defined in any module
+    let mut definitions: HashMap<&str, (PathBuf, String)> = HashMap::new();
+    for sym in symbols {
+        if matches!(sym.kind, "struct" | "enum" | "type" | "trait") {
+            definitions.insert(&sym.name, (page_of(&sym.file), sym.anchor()));
+        }
+    }
+
+    let mut pages = Vec::new();
+    for file in files {
+        let filename = file.display().to_string();
+        let items: Vec<_> = symbols.iter().filter(|s| s.file == filename).collect();
+        if items.is_empty() {
+            continue;
+        }
+        let page = page_of(&filename);
+
+        let mut md = format!("# Module `{}`\n", page.with_extension("").display());
+        for sym in items {
+            md.push_str(&format!("\n<a id=\"{}\"></a>\n\n## {} `{}`\n\n", sym.anchor(), sym.kind, sym.name));
+            md.push_str(&format!("```bmb\n{}\n```\n", sym.signature));
+            if let Some(doc) = &sym.doc {
+                md.push_str(&format!("\n{}\n", doc));
+            }
+
+            let mut contracts = Vec::new();
+            for (contract, cond) in [("pre", &sym.pre), ("post", &sym.post)] {
+                if let Some(cond) = cond {
+                    contracts.push(format!("- **{}** `{}`", contract, cond));
+                }
+            }
+            for c in &sym.contracts {
+                match &c.name {
+                    Some(label) => contracts.push(format!("- **where** {}: `{}`", label, c.condition)),
+                    None => contracts.push(format!("- **where** `{}`", c.condition)),
+                }
+            }
+            if !contracts.is_empty() {
+                md.push_str(&format!("\n{}\n", contracts.join("\n")));
+            }
+
+            let links: Vec<_> = sym.types.iter()
+                .filter_map(|t| definitions.get(t.as_str()).map(|(target, anchor)| {
+                    let href = if *target == page {
+                        format!("#{}", anchor)
+                    } else {
+                        format!("{}#{}", relative_page_link(&page, target), anchor)
+                    };
+                    format!("[`{}`]({})", t, href)
+                }))
+                .collect();
+            if !links.is_empty() {
+                md.push_str(&format!("\nUses: {}\n", links.join(", ")));
+            }
+        }
+
+        pages.push((page, md));
+    }
+
+    pages
+}
+
+/// Path of page `to` relative to the directory of page `from`
+fn relative_page_link(from: &Path, to: &Path) -> String {
+    let depth = from.parent().map_or(0, |dir| dir.components().count());
+    let mut link = "../".repeat(depth);
+    link.push_str(&to.to_string_lossy().replace('\\', "/"));
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(source: &str) -> Vec<DocSymbol> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        collect_symbols(&program, source, "test.bmb")
+    }
+
+    #[test]
+    fn test_doc_comments_inside_a_body_stay_with_it() {
+        let syms = symbols("/// Adds one
+pub fn inc(x: i64) -> i64 = {
+    /// not documentation for `dec`
+    x + 1
+};
+
+pub fn dec(x: i64) -> i64 = x - 1;
+");
+        assert_eq!(syms[0].doc.as_deref(), Some("Adds one"));
+        assert_eq!(syms[1].name, "dec");
+        assert_eq!(syms[1].doc, None);
+    }
+
+    #[test]
+    fn test_private_items_are_skipped_but_end_their_doc() {
+        let syms = symbols("/// Hidden helper
+fn helper() -> i64 = 0;
+pub struct Pair<T> { a: T, b: T }
+");
+        assert_eq!(syms.len(), 1);
+        assert_eq!(syms[0].signature, "struct Pair<T> { a: T, b: T }");
+        assert_eq!(syms[0].doc, None);
+    }
+
+    #[test]
+    fn test_markdown_links_types_on_the_same_page() {
+        let source = "pub struct Rect { w: i64 }\npub fn area(r: Rect) -> i64 = r.w;\n";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("src/geo.bmb", source, tokens).unwrap();
+        let syms = collect_symbols(&program, source, "src/geo.bmb");
+        let pages = render_markdown(Path::new("src"), &[PathBuf::from("src/geo.bmb")], &syms);
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].0, PathBuf::from("geo.md"));
+        assert!(pages[0].1.contains("Uses: [`Rect`](#struct.Rect)"), "{}", pages[0].1);
+    }
+}
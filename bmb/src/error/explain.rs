@@ -0,0 +1,300 @@
+//! Extended explanations of diagnostic codes (`bmb explain E0308`)
+//!
+//! Each explanation describes what triggers the error, with a program that
+//! produces it and the same program fixed.
+
+/// Extended documentation for one diagnostic code
+#[derive(Debug)]
+pub struct Explanation {
+    pub code: &'static str,
+    /// One-line summary
+    pub title: &'static str,
+    pub description: &'static str,
+    /// A program that reports this code
+    pub erroneous: &'static str,
+    /// The same program, fixed
+    pub fixed: &'static str,
+}
+
+impl Explanation {
+    /// Plain-text rendering with both examples indented
+    pub fn render(&self) -> String {
+        format!(
+            "{}: {}\n\n{}\n\nErroneous code:\n\n{}\n\nFixed:\n\n{}\n",
+            self.code,
+            self.title,
+            self.description,
+            indent(self.erroneous),
+            indent(self.fixed)
+        )
+    }
+}
+
+/// `code` indented as a block, leaving blank lines empty
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {line}") })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The explanation for `code` (`E0308`, also `e0308`), if there is one
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|e| e.code.eq_ignore_ascii_case(code))
+}
+
+/// Every explained code, in order
+pub fn explained_codes() -> impl Iterator<Item = &'static str> {
+    EXPLANATIONS.iter().map(|e| e.code)
+}
+
+static EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0300",
+        title: "type error",
+        description: "A type error with no more specific code, such as assigning to a field of \
+a variable that was not declared `mut`. The message says what went wrong.",
+        erroneous: "\
+struct Point { x: i64, y: i64 }
+
+fn origin_x() -> i64 = {
+    let p = new Point { x: 1, y: 2 };
+    p.x = 0;
+    p.x
+};",
+        fixed: "\
+struct Point { x: i64, y: i64 }
+
+fn origin_x() -> i64 = {
+    let mut p = new Point { x: 1, y: 2 };
+    p.x = 0;
+    p.x
+};",
+    },
+    Explanation {
+        code: "E0301",
+        title: "undefined variable",
+        description: "A name was used that is not a parameter, a `let` binding in scope, or a \
+constant. It is often a typo; the diagnostic suggests similar names in scope.",
+        erroneous: "fn area(width: i64, height: i64) -> i64 = width * heigth;",
+        fixed: "fn area(width: i64, height: i64) -> i64 = width * height;",
+    },
+    Explanation {
+        code: "E0302",
+        title: "undefined function",
+        description: "A call names a function that is neither defined in the file, imported \
+with `use`, nor a builtin. Check the spelling or add the missing `use`.",
+        erroneous: "\
+fn double(x: i64) -> i64 = x * 2;
+
+fn quadruple(x: i64) -> i64 = dubble(dubble(x));",
+        fixed: "\
+fn double(x: i64) -> i64 = x * 2;
+
+fn quadruple(x: i64) -> i64 = double(double(x));",
+    },
+    Explanation {
+        code: "E0303",
+        title: "undefined type",
+        description: "A struct, enum or other type name is not defined or imported.",
+        erroneous: "\
+struct Point { x: i64, y: i64 }
+
+fn origin() -> Point = new Pointt { x: 0, y: 0 };",
+        fixed: "\
+struct Point { x: i64, y: i64 }
+
+fn origin() -> Point = new Point { x: 0, y: 0 };",
+    },
+    Explanation {
+        code: "E0304",
+        title: "unknown or missing field",
+        description: "A field access or struct initializer names a field the struct does not \
+declare, or an initializer leaves out a field that has no default. Give every field without \
+a default a value, or copy the rest from another value with `..base`.",
+        erroneous: "\
+struct Point { x: i64, y: i64 }
+
+fn make() -> Point = new Point { x: 1 };",
+        fixed: "\
+struct Point { x: i64, y: i64 }
+
+fn make() -> Point = new Point { x: 1, y: 0 };",
+    },
+    Explanation {
+        code: "E0305",
+        title: "unknown enum variant",
+        description: "`Enum::Variant` names a variant the enum does not declare.",
+        erroneous: "\
+enum Color { Red, Green, Blue }
+
+fn favorite() -> Color = Color::Purple;",
+        fixed: "\
+enum Color { Red, Green, Blue }
+
+fn favorite() -> Color = Color::Blue;",
+    },
+    Explanation {
+        code: "E0306",
+        title: "unknown method",
+        description: "A method call names a method that neither the type's `impl` blocks, its \
+traits, nor the builtins provide. Define the method in an `impl` block or call a function \
+instead.",
+        erroneous: "\
+struct Counter { n: i64 }
+
+fn next(c: Counter) -> i64 = c.increment();",
+        fixed: "\
+struct Counter { n: i64 }
+
+impl Counter {
+    fn increment(self: Self) -> i64 = self.n + 1;
+}
+
+fn next(c: Counter) -> i64 = c.increment();",
+    },
+    Explanation {
+        code: "E0307",
+        title: "wrong number of arguments",
+        description: "A function, method or enum variant was given more or fewer arguments than \
+it declares parameters.",
+        erroneous: "\
+fn add(a: i64, b: i64) -> i64 = a + b;
+
+fn three() -> i64 = add(1, 2, 0);",
+        fixed: "\
+fn add(a: i64, b: i64) -> i64 = a + b;
+
+fn three() -> i64 = add(1, 2);",
+    },
+    Explanation {
+        code: "E0308",
+        title: "mismatched types",
+        description: "An expression has a different type than its context requires: a function \
+body and its return type, an argument and its parameter, the branches of an `if`, or the two \
+sides of an operator. BMB never converts between types implicitly; use `as` for numeric \
+conversions.",
+        erroneous: "fn is_positive(x: i64) -> bool = if x > 0 { 1 } else { 0 };",
+        fixed: "fn is_positive(x: i64) -> bool = x > 0;",
+    },
+    Explanation {
+        code: "E0309",
+        title: "unsatisfied trait bound",
+        description: "A generic function was called with a type argument that does not \
+implement a trait its type parameter requires. Implement the trait for the type, or pass a \
+value of a type that implements it.",
+        erroneous: "\
+trait Show {
+    fn show(self: Self) -> i64;
+}
+
+fn display<T: Show>(value: T) -> i64 = value.show();
+
+fn main() -> i64 = display(42);",
+        fixed: "\
+trait Show {
+    fn show(self: Self) -> i64;
+}
+
+struct Meters { n: i64 }
+
+impl Show for Meters {
+    fn show(self: Self) -> i64 = self.n;
+}
+
+fn display<T: Show>(value: T) -> i64 = value.show();
+
+fn main() -> i64 = display(new Meters { n: 42 });",
+    },
+    Explanation {
+        code: "E0310",
+        title: "non-constant initializer",
+        description: "The value of a `const` item, or the length of an array type, must be \
+computable at compile time: literals, other constants and operators on them. Function calls \
+and variables are not allowed.",
+        erroneous: "\
+fn limit() -> i64 = 100;
+
+const MAX: i64 = limit();",
+        fixed: "\
+const LIMIT: i64 = 100;
+
+const MAX: i64 = LIMIT;",
+    },
+    Explanation {
+        code: "E0311",
+        title: "cyclic constant",
+        description: "Constants refer to each other in a cycle, so none of them has a value. \
+The message lists the cycle; break it by giving one of them a value that does not depend on \
+the others.",
+        erroneous: "\
+const A: i64 = B + 1;
+const B: i64 = A * 2;",
+        fixed: "\
+const A: i64 = 1;
+const B: i64 = A * 2;",
+    },
+    Explanation {
+        code: "E0312",
+        title: "conflicting implementations",
+        description: "A trait is implemented twice for the same type, or a method is defined \
+twice for it, so calls would be ambiguous. Keep one implementation.",
+        erroneous: "\
+trait Describe {
+    fn id(self: Self) -> i64;
+}
+
+struct Widget { n: i64 }
+
+impl Describe for Widget {
+    fn id(self: Self) -> i64 = self.n;
+}
+
+impl Describe for Widget {
+    fn id(self: Self) -> i64 = 0;
+}",
+        fixed: "\
+trait Describe {
+    fn id(self: Self) -> i64;
+}
+
+struct Widget { n: i64 }
+
+impl Describe for Widget {
+    fn id(self: Self) -> i64 = self.n;
+}",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TypeChecker;
+
+    fn check(source: &str) -> crate::Result<()> {
+        let tokens = crate::lexer::tokenize(source)?;
+        let program = crate::parser::parse("example.bmb", source, tokens)?;
+        TypeChecker::new().check_program(&program)
+    }
+
+    #[test]
+    fn test_examples_report_their_code() {
+        for explanation in EXPLANATIONS {
+            let err = check(explanation.erroneous).expect_err(explanation.code);
+            assert_eq!(err.code(), explanation.code, "{}", err.message());
+            if let Err(err) = check(explanation.fixed) {
+                panic!("fixed example of {} fails: {}", explanation.code, err.message());
+            }
+        }
+    }
+
+    #[test]
+    fn test_explain_lookup() {
+        assert_eq!(explain("e0308").map(|e| e.title), Some("mismatched types"));
+        assert!(explain("E9999").is_none());
+        let text = explain("E0301").unwrap().render();
+        assert!(text.starts_with("E0301: undefined variable\n\n"));
+        assert!(text.contains("Erroneous code:\n\n    fn area("));
+        assert_eq!(explained_codes().count(), EXPLANATIONS.len());
+    }
+}
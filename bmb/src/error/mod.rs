@@ -1,6 +1,9 @@
 //! Error types and reporting
 
-use crate::ast::Span;
+pub mod explain;
+
+use crate::ast::{LineCol, LineIndex, Span};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Result type alias
@@ -132,6 +135,24 @@ pub enum CompileWarning {
         span: Span,
     },
 
+    /// Glob import shadowed by a local definition of the same name
+    /// The local definition is used; the imported item is ignored
+    ShadowedGlobImport {
+        name: String,
+        module: String,
+        span: Span,
+    },
+
+    /// Inherent method with the same name as a trait method on the same type
+    /// Method calls resolve to the inherent method
+    ShadowedTraitMethod {
+        type_name: String,
+        method: String,
+        trait_name: String,
+        span: Span,
+        trait_span: Span,
+    },
+
     /// Generic warning with span
     Generic {
         message: String,
@@ -207,6 +228,32 @@ impl CompileWarning {
         }
     }
 
+    /// Create a warning for a glob-imported name shadowed by a local definition
+    pub fn shadowed_glob_import(name: impl Into<String>, module: impl Into<String>, span: Span) -> Self {
+        Self::ShadowedGlobImport {
+            name: name.into(),
+            module: module.into(),
+            span,
+        }
+    }
+
+    /// Create a warning for an inherent method that hides a trait method
+    pub fn shadowed_trait_method(
+        type_name: impl Into<String>,
+        method: impl Into<String>,
+        trait_name: impl Into<String>,
+        span: Span,
+        trait_span: Span,
+    ) -> Self {
+        Self::ShadowedTraitMethod {
+            type_name: type_name.into(),
+            method: method.into(),
+            trait_name: trait_name.into(),
+            span,
+            trait_span,
+        }
+    }
+
     /// v0.76: Create an unused function warning
     pub fn unused_function(name: impl Into<String>, span: Span) -> Self {
         Self::UnusedFunction {
@@ -311,6 +358,8 @@ impl CompileWarning {
             Self::MissingPostcondition { span, .. } => Some(*span),
             Self::SemanticDuplication { span, .. } => Some(*span),
             Self::TrivialContract { span, .. } => Some(*span),
+            Self::ShadowedGlobImport { span, .. } => Some(*span),
+            Self::ShadowedTraitMethod { span, .. } => Some(*span),
             Self::Generic { span, .. } => *span,
         }
     }
@@ -375,6 +424,15 @@ impl CompileWarning {
                     name, contract_kind
                 )
             }
+            Self::ShadowedGlobImport { name, module, .. } => {
+                format!("local definition of `{}` shadows the item imported by `use {}::*`", name, module)
+            }
+            Self::ShadowedTraitMethod { type_name, method, trait_name, .. } => {
+                format!(
+                    "inherent method `{}::{}` shadows the `{}` trait method of the same name; calls use the inherent method",
+                    type_name, method, trait_name
+                )
+            }
             Self::Generic { message, .. } => message.clone(),
         }
     }
@@ -399,11 +457,108 @@ impl CompileWarning {
             Self::MissingPostcondition { .. } => "missing_postcondition",
             Self::SemanticDuplication { .. } => "semantic_duplication",
             Self::TrivialContract { .. } => "trivial_contract",
+            Self::ShadowedGlobImport { .. } => "shadowed_glob_import",
+            Self::ShadowedTraitMethod { .. } => "shadowed_trait_method",
             Self::Generic { .. } => "warning",
         }
     }
 }
 
+/// Every [`CompileWarning::kind`], in code order
+pub const WARNING_KINDS: &[&str] = &[
+    "unreachable_pattern",
+    "unused_binding",
+    "redundant_pattern",
+    "integer_range_overflow",
+    "guarded_non_exhaustive",
+    "unused_mut",
+    "unreachable_code",
+    "unused_import",
+    "unused_function",
+    "unused_type",
+    "unused_enum",
+    "shadow_binding",
+    "unused_trait",
+    "duplicate_function",
+    "missing_postcondition",
+    "semantic_duplication",
+    "trivial_contract",
+    "shadowed_glob_import",
+    "shadowed_trait_method",
+    "warning",
+];
+
+impl CompileWarning {
+    /// Stable diagnostic code for this warning kind (e.g. `W0002` for unused bindings)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnreachablePattern { .. } => "W0001",
+            Self::UnusedBinding { .. } => "W0002",
+            Self::RedundantPattern { .. } => "W0003",
+            Self::IntegerRangeOverflow { .. } => "W0004",
+            Self::GuardedNonExhaustive { .. } => "W0005",
+            Self::UnusedMut { .. } => "W0006",
+            Self::UnreachableCode { .. } => "W0007",
+            Self::UnusedImport { .. } => "W0008",
+            Self::UnusedFunction { .. } => "W0009",
+            Self::UnusedType { .. } => "W0010",
+            Self::UnusedEnum { .. } => "W0011",
+            Self::ShadowBinding { .. } => "W0012",
+            Self::UnusedTrait { .. } => "W0013",
+            Self::DuplicateFunction { .. } => "W0014",
+            Self::MissingPostcondition { .. } => "W0015",
+            Self::SemanticDuplication { .. } => "W0016",
+            Self::TrivialContract { .. } => "W0017",
+            Self::ShadowedGlobImport { .. } => "W0018",
+            Self::ShadowedTraitMethod { .. } => "W0019",
+            Self::Generic { .. } => "W0000",
+        }
+    }
+
+    /// Secondary locations that explain this warning
+    pub fn related(&self) -> Vec<(Span, &'static str)> {
+        match self {
+            Self::ShadowBinding { original_span, .. } => {
+                vec![(*original_span, "shadowed binding declared here")]
+            }
+            Self::DuplicateFunction { original_span, .. } => {
+                vec![(*original_span, "first definition here")]
+            }
+            Self::ShadowedTraitMethod { trait_span, .. } => {
+                vec![(*trait_span, "trait method implemented here")]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Structured diagnostic for machine output
+    pub fn to_diagnostic(&self, filename: &str, source: &str) -> Diagnostic {
+        self.to_diagnostic_with(filename, &LineIndex::new(source))
+    }
+
+    /// [`Self::to_diagnostic`] against the line index of its file
+    pub fn to_diagnostic_with(&self, filename: &str, index: &LineIndex) -> Diagnostic {
+        let mut diagnostic = Diagnostic::at(Severity::Warning, self.message(), index, self.span())
+            .with_code(self.code())
+            .with_file(filename);
+        diagnostic.kind = Some(self.kind().to_string());
+        diagnostic.related = self
+            .related()
+            .into_iter()
+            .map(|(span, message)| RelatedDiagnostic {
+                message: message.to_string(),
+                span: DiagnosticSpan::resolve(index, span),
+            })
+            .collect();
+        diagnostic
+    }
+
+    /// [`Self::to_diagnostic`] as a JSON value
+    pub fn to_json(&self, filename: &str, source: &str) -> serde_json::Value {
+        self.to_diagnostic(filename, source).to_json()
+    }
+}
+
 impl std::fmt::Display for CompileWarning {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "warning[{}]: {}", self.kind(), self.message())
@@ -420,7 +575,12 @@ pub enum CompileError {
     Parser { message: String, span: Span },
 
     #[error("Type error at {span:?}: {message}")]
-    Type { message: String, span: Span },
+    Type {
+        message: String,
+        span: Span,
+        /// Secondary locations that explain the error
+        related: Vec<(Span, &'static str)>,
+    },
 
     /// IO error (v0.5 Phase 7)
     #[error("IO error: {message}")]
@@ -455,7 +615,16 @@ impl CompileError {
         Self::Type {
             message: message.into(),
             span,
+            related: Vec::new(),
+        }
+    }
+
+    /// Attach a secondary location to a type error
+    pub fn with_related(mut self, span: Span, message: &'static str) -> Self {
+        if let Self::Type { related, .. } = &mut self {
+            related.push((span, message));
         }
+        self
     }
 
     /// Create an IO error (v0.5 Phase 7)
@@ -498,6 +667,14 @@ impl CompileError {
         }
     }
 
+    /// Secondary locations that explain this error
+    pub fn related(&self) -> &[(Span, &'static str)] {
+        match self {
+            Self::Type { related, .. } => related,
+            _ => &[],
+        }
+    }
+
     pub fn message(&self) -> &str {
         match self {
             Self::Lexer { message, .. } => message,
@@ -510,70 +687,396 @@ impl CompileError {
     }
 }
 
-/// Report error with ariadne
-pub fn report_error(filename: &str, source: &str, error: &CompileError) {
-    use ariadne::{Color, Label, Report, ReportKind, Source};
-
-    let kind = match error {
-        CompileError::Lexer { .. } => "Lexer",
-        CompileError::Parser { .. } => "Parser",
-        CompileError::Type { .. } => "Type",
-        CompileError::Io { .. } => "IO",
-        CompileError::Parse { .. } => "Parse",
-        CompileError::Resolve { .. } => "Resolve",
-    };
+impl CompileError {
+    /// Stable diagnostic code. The hundreds digit names the phase
+    /// (E01 lexer, E02 parser, E03 types, E04 modules, E05 I/O) and
+    /// type and module errors are further classified by message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Lexer { .. } => "E0101",
+            Self::Parser { message, .. } if message.starts_with("Unrecognized EOF") => "E0202",
+            Self::Parser { .. } => "E0201",
+            Self::Parse { .. } => "E0200",
+            Self::Type { message, .. } => type_error_code(message),
+            Self::Resolve { message, .. } => {
+                if message.starts_with("Circular import") {
+                    "E0403"
+                } else if message.starts_with("Item '") {
+                    "E0402"
+                } else if message.starts_with("Module '") {
+                    "E0401"
+                } else {
+                    "E0400"
+                }
+            }
+            Self::Io { .. } => "E0501",
+        }
+    }
+
+    /// Message without the trailing `hint:` line, if any
+    pub fn primary_message(&self) -> &str {
+        split_hint(self.message()).0
+    }
+
+    /// Suggestion carried in the message (`hint: did you mean ...`), if any
+    pub fn hint(&self) -> Option<&str> {
+        split_hint(self.message()).1
+    }
+
+    /// Structured diagnostic for machine output, with any `hint:` line
+    /// split out of the message
+    pub fn to_diagnostic(&self, filename: &str, source: &str) -> Diagnostic {
+        self.to_diagnostic_with(filename, &LineIndex::new(source))
+    }
+
+    /// [`Self::to_diagnostic`] against the line index of its file
+    pub fn to_diagnostic_with(&self, filename: &str, index: &LineIndex) -> Diagnostic {
+        let mut diagnostic = Diagnostic::at(Severity::Error, self.primary_message(), index, self.span())
+            .with_code(self.code())
+            .with_file(filename);
+        diagnostic.hint = self.hint().map(str::to_string);
+        diagnostic.related = self
+            .related()
+            .iter()
+            .map(|(span, message)| RelatedDiagnostic {
+                message: message.to_string(),
+                span: DiagnosticSpan::resolve(index, *span),
+            })
+            .collect();
+        diagnostic
+    }
 
-    if let Some(span) = error.span() {
-        Report::build(ReportKind::Error, (filename, span.start..span.end))
-            .with_message(format!("{kind} error"))
-            .with_label(
-                Label::new((filename, span.start..span.end))
-                    .with_message(error.message())
-                    .with_color(Color::Red),
-            )
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap();
+    /// [`Self::to_diagnostic`] as a JSON value
+    pub fn to_json(&self, filename: &str, source: &str) -> serde_json::Value {
+        self.to_diagnostic(filename, source).to_json()
+    }
+}
+
+/// Classify a type error message into a stable code
+fn type_error_code(message: &str) -> &'static str {
+    if message.starts_with("undefined variable") || message.starts_with("undefined constant") {
+        "E0301"
+    } else if message.starts_with("undefined function") {
+        "E0302"
+    } else if message.starts_with("undefined struct")
+        || message.starts_with("undefined enum")
+        || message.starts_with("undefined generic enum")
+        || message.starts_with("undefined type")
+    {
+        "E0303"
+    } else if message.starts_with("unknown field") || message.starts_with("missing field") {
+        "E0304"
+    } else if message.starts_with("unknown variant") {
+        "E0305"
+    } else if message.starts_with("unknown method") || message.contains("has no method") {
+        "E0306"
+    } else if message.contains("arguments, got")
+        || message.contains("args, got")
+        || message.contains("count mismatch")
+        || message.contains("() takes")
+    {
+        "E0307"
+    } else if message.starts_with("trait bound") {
+        "E0309"
+    } else if message.contains("in initializer of constant") || message.starts_with("array length") {
+        "E0310"
+    } else if message.starts_with("cyclic constant") {
+        "E0311"
+    } else if message.starts_with("conflicting implementations") {
+        "E0312"
+    } else if message.contains("mismatch") || message.starts_with("expected ") {
+        "E0308"
     } else {
-        // Errors without span (IO, Parse, Resolve)
-        Report::build(ReportKind::Error, (filename, 0..0))
-            .with_message(format!("{kind} error: {}", error.message()))
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap();
+        "E0300"
     }
 }
 
-/// Report warning with ariadne (v0.47)
-/// P0 Correctness: Visual feedback for potential issues without blocking compilation
-pub fn report_warning(filename: &str, source: &str, warning: &CompileWarning) {
-    use ariadne::{Color, Label, Report, ReportKind, Source};
-
-    if let Some(span) = warning.span() {
-        Report::build(ReportKind::Warning, (filename, span.start..span.end))
-            .with_message(format!("warning[{}]", warning.kind()))
-            .with_label(
-                Label::new((filename, span.start..span.end))
-                    .with_message(warning.message())
-                    .with_color(Color::Yellow),
-            )
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap();
+/// Split `message\n  hint: text` into the message and the hint text
+fn split_hint(message: &str) -> (&str, Option<&str>) {
+    match message.split_once("\n  hint: ") {
+        Some((msg, hint)) => (msg, Some(hint)),
+        None => (message, None),
+    }
+}
+
+/// 1-based line and column (in characters) of a byte offset. Builds a
+/// [`LineIndex`]; keep one instead when resolving many offsets of a file.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let LineCol { line, col } = LineIndex::new(source).line_col(offset);
+    (line, col)
+}
+
+// ============================================================================
+// Structured diagnostics (stable machine-output schema)
+// ============================================================================
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Byte span of a diagnostic plus the 1-based line and column of its start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl DiagnosticSpan {
+    /// Resolve `span` to line/column against `source`
+    pub fn new(source: &str, span: Span) -> Self {
+        Self::resolve(&LineIndex::new(source), span)
+    }
+
+    /// Resolve `span` to line/column with the line index of its file
+    pub fn resolve(index: &LineIndex, span: Span) -> Self {
+        let LineCol { line, col } = index.line_col(span.start);
+        Self { start: span.start, end: span.end, line, col }
+    }
+}
+
+/// Secondary location attached to a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedDiagnostic {
+    pub message: String,
+    pub span: DiagnosticSpan,
+}
+
+/// Function on the interpreter call stack of a runtime error, positioned
+/// where it was executing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticFrame {
+    pub function: String,
+    pub span: Option<DiagnosticSpan>,
+}
+
+/// One machine-readable diagnostic, shared by compile errors, warnings,
+/// runtime errors and CLI failures. Every field is always serialized
+/// (absent values as `null`) except `kind`, which only warnings carry, and
+/// `frames`/`frames_omitted`, which only runtime errors carry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Stable code such as `E0301` or `W0012`
+    pub code: Option<String>,
+    pub severity: Severity,
+    /// Warning category, e.g. `unused_binding`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Option<DiagnosticSpan>,
+    pub hint: Option<String>,
+    #[serde(default)]
+    pub related: Vec<RelatedDiagnostic>,
+    /// Stack trace of a runtime error, innermost frame first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<DiagnosticFrame>,
+    /// Frames beyond those listed in `frames`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub frames_omitted: usize,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl Diagnostic {
+    /// Diagnostic at `span` (resolved against `source`), without code or file
+    pub fn new(severity: Severity, message: impl Into<String>, source: &str, span: Option<Span>) -> Self {
+        Self::at(severity, message, &LineIndex::new(source), span)
+    }
+
+    /// [`Self::new`] with the line index of the file `span` is in
+    pub fn at(severity: Severity, message: impl Into<String>, index: &LineIndex, span: Option<Span>) -> Self {
+        Self {
+            code: None,
+            severity,
+            kind: None,
+            message: message.into(),
+            file: None,
+            span: span.map(|s| DiagnosticSpan::resolve(index, s)),
+            hint: None,
+            related: Vec::new(),
+            frames: Vec::new(),
+            frames_omitted: 0,
+        }
+    }
+
+    /// Error with no source location, for failures outside compilation
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message, "", None)
+    }
+
+    /// Set the diagnostic code
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Set the file the diagnostic belongs to
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    /// The diagnostic as a JSON value
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("diagnostics serialize to JSON")
+    }
+
+    /// Human-readable rendering in the style of rustc:
+    ///
+    /// ```text
+    /// error[E0308]: expected i64, got bool
+    ///  --> main.bmb:2:18
+    ///   |
+    /// 2 |     let x: i64 = true;
+    ///   |                  ^^^^
+    ///   = help: ...
+    /// ```
+    ///
+    /// Spans covering several lines underline the rest of the first line and
+    /// note where they end. Related locations follow as labelled snippets,
+    /// then the stack trace of a runtime error as `at main (main.bmb:7)`.
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, &LineIndex::new(source))
+    }
+
+    /// [`Self::render`] with the line index of `source`
+    pub fn render_with(&self, source: &str, index: &LineIndex) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let mut out = match &self.code {
+            Some(code) => format!("{severity}[{code}]: {}\n", self.message),
+            None => format!("{severity}: {}\n", self.message),
+        };
+        let file = self.file.as_deref().unwrap_or("<input>");
+        let gutter = std::iter::once(self.span)
+            .chain(self.related.iter().map(|r| Some(r.span)))
+            .flatten()
+            .map(|span| span.line)
+            .max()
+            .map_or(0, |line| line.to_string().len());
+        let gutter = " ".repeat(gutter);
+
+        match self.span {
+            Some(span) => render_snippet(&mut out, &gutter, file, source, index, span, '^', None),
+            None if self.file.is_some() => out.push_str(&format!("{gutter}--> {file}\n")),
+            None => {}
+        }
+        if let Some(hint) = &self.hint {
+            out.push_str(&format!("{gutter} = help: {hint}\n"));
+        }
+        for related in &self.related {
+            render_snippet(&mut out, &gutter, file, source, index, related.span, '-', Some(&related.message));
+        }
+        for frame in &self.frames {
+            match frame.span {
+                Some(span) => out.push_str(&format!("{gutter}   at {} ({file}:{})\n", frame.function, span.line)),
+                None => out.push_str(&format!("{gutter}   at {} ({file})\n", frame.function)),
+            }
+        }
+        if self.frames_omitted > 0 {
+            out.push_str(&format!("{gutter}   ... {} more frames\n", self.frames_omitted));
+        }
+        out
+    }
+}
+
+/// Append the `--> file:line:col` header, the first source line of `span`
+/// and an underline of `marker`s (followed by `label`), plus a note when
+/// the span runs on past that line
+#[allow(clippy::too_many_arguments)]
+fn render_snippet(
+    out: &mut String,
+    gutter: &str,
+    file: &str,
+    source: &str,
+    index: &LineIndex,
+    span: DiagnosticSpan,
+    marker: char,
+    label: Option<&str>,
+) {
+    // `end` is exclusive, so a span ending in a newline stays on its line
+    let end_line = index.line_col(span.end.saturating_sub(1).max(span.start)).line;
+    let line = index.line_span(span.line);
+    let text = source.get(line.start..line.end).unwrap_or("").trim_end_matches('\r');
+    // Keep tabs so the underline lines up with the source
+    let pad: String = text
+        .chars()
+        .take(span.col - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = if end_line > span.line {
+        text.chars().count().saturating_sub(span.col - 1)
     } else {
-        // Warnings without span
-        Report::build(ReportKind::Warning, (filename, 0..0))
-            .with_message(warning.message())
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap();
+        source.get(span.start..span.end).map_or(0, |s| s.chars().count())
+    };
+    let underline = marker.to_string().repeat(width.max(1));
+
+    // Secondary locations get rustc's `:::` arrow
+    let arrow = if marker == '^' { "-->" } else { ":::" };
+    out.push_str(&format!("{gutter}{arrow} {file}:{}:{}\n", span.line, span.col));
+    out.push_str(&format!("{gutter} |\n"));
+    out.push_str(&format!("{:>w$} | {text}\n", span.line, w = gutter.len()));
+    match label {
+        Some(label) => out.push_str(&format!("{gutter} | {pad}{underline} {label}\n")),
+        None => out.push_str(&format!("{gutter} | {pad}{underline}\n")),
+    }
+    if end_line > span.line {
+        out.push_str(&format!("{gutter} = note: span continues to line {end_line}\n"));
+    }
+}
+
+/// Envelope of machine output: `{"diagnostics":[...]}`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticReport {
+    /// Envelope holding `diagnostics`
+    pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        Self { diagnostics }
+    }
+}
+
+impl std::fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(&json)
     }
 }
 
+/// Print `diagnostics` as one envelope line
+pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
+    println!("{}", DiagnosticReport::new(diagnostics));
+}
+
+/// Report error in rustc style (see [`Diagnostic::render`])
+pub fn report_error(filename: &str, source: &str, error: &CompileError) {
+    let index = LineIndex::new(source);
+    println!("{}", error.to_diagnostic_with(filename, &index).render_with(source, &index));
+}
+
+/// Report warning in rustc style (v0.47)
+/// P0 Correctness: Visual feedback for potential issues without blocking compilation
+pub fn report_warning(filename: &str, source: &str, warning: &CompileWarning) {
+    report_warnings(filename, source, std::slice::from_ref(warning));
+}
+
 /// Report multiple warnings (v0.47)
 pub fn report_warnings(filename: &str, source: &str, warnings: &[CompileWarning]) {
+    let index = LineIndex::new(source);
     for warning in warnings {
-        report_warning(filename, source, warning);
+        println!("{}", warning.to_diagnostic_with(filename, &index).render_with(source, &index));
     }
 }
 
@@ -581,46 +1084,76 @@ pub fn report_warnings(filename: &str, source: &str, warnings: &[CompileWarning]
 // v0.71: Machine-readable output (AI-friendly)
 // ============================================================================
 
-/// Machine-readable error output (JSON format)
-pub fn report_error_machine(filename: &str, _source: &str, error: &CompileError) {
-    let kind = match error {
-        CompileError::Lexer { .. } => "lexer",
-        CompileError::Parser { .. } => "parser",
-        CompileError::Type { .. } => "type",
-        CompileError::Io { .. } => "io",
-        CompileError::Parse { .. } => "parse",
-        CompileError::Resolve { .. } => "resolve",
-    };
-
-    let (start, end) = error.span().map(|s| (s.start, s.end)).unwrap_or((0, 0));
+/// Machine-readable error output (diagnostics envelope)
+pub fn report_error_machine(filename: &str, source: &str, error: &CompileError) {
+    report_errors_machine(filename, source, std::slice::from_ref(error));
+}
 
-    println!(
-        r#"{{"type":"error","kind":"{}","file":"{}","start":{},"end":{},"message":"{}"}}"#,
-        kind,
-        filename.replace('\\', "\\\\").replace('"', "\\\""),
-        start,
-        end,
-        error.message().replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
-    );
+/// Machine-readable output of several errors in one envelope
+pub fn report_errors_machine(filename: &str, source: &str, errors: &[CompileError]) {
+    let index = LineIndex::new(source);
+    print_diagnostics(errors.iter().map(|e| e.to_diagnostic_with(filename, &index)).collect());
 }
 
-/// Machine-readable warning output (JSON format)
-pub fn report_warning_machine(filename: &str, _source: &str, warning: &CompileWarning) {
-    let (start, end) = warning.span().map(|s| (s.start, s.end)).unwrap_or((0, 0));
-
-    println!(
-        r#"{{"type":"warning","kind":"{}","file":"{}","start":{},"end":{},"message":"{}"}}"#,
-        warning.kind(),
-        filename.replace('\\', "\\\\").replace('"', "\\\""),
-        start,
-        end,
-        warning.message().replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
-    );
+/// Machine-readable warning output (diagnostics envelope)
+pub fn report_warning_machine(filename: &str, source: &str, warning: &CompileWarning) {
+    report_warnings_machine(filename, source, std::slice::from_ref(warning));
 }
 
-/// Machine-readable warnings output
+/// Machine-readable output of several warnings in one envelope
 pub fn report_warnings_machine(filename: &str, source: &str, warnings: &[CompileWarning]) {
-    for warning in warnings {
-        report_warning_machine(filename, source, warning);
+    let index = LineIndex::new(source);
+    print_diagnostics(warnings.iter().map(|w| w.to_diagnostic_with(filename, &index)).collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_single_line_error() {
+        let source = "fn main() -> i64 = {\n    let x: i64 = true;\n    x\n};\n";
+        let error = CompileError::type_error("expected i64, got bool", Span::new(38, 42));
+        assert_eq!(
+            error.to_diagnostic("main.bmb", source).render(source),
+            "error[E0308]: expected i64, got bool\n \
+             --> main.bmb:2:18\n  \
+             |\n\
+             2 |     let x: i64 = true;\n  \
+             |                  ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_multi_line_span_with_hint_and_related() {
+        let source = "fn f() -> i64 = 1;\n\nfn f() -> i64 = {\n    2\n};\n";
+        let mut diagnostic = Diagnostic::new(Severity::Warning, "duplicate", source, Some(Span::new(20, 46)))
+            .with_code("W0014")
+            .with_file("dup.bmb");
+        diagnostic.hint = Some("remove one".to_string());
+        diagnostic.related.push(RelatedDiagnostic {
+            message: "first definition here".to_string(),
+            span: DiagnosticSpan::new(source, Span::new(3, 4)),
+        });
+        assert_eq!(
+            diagnostic.render(source),
+            "warning[W0014]: duplicate\n \
+             --> dup.bmb:3:1\n  \
+             |\n\
+             3 | fn f() -> i64 = {\n  \
+             | ^^^^^^^^^^^^^^^^^\n  \
+             = note: span continues to line 5\n  \
+             = help: remove one\n \
+             ::: dup.bmb:1:4\n  \
+             |\n\
+             1 | fn f() -> i64 = 1;\n  \
+             |    - first definition here\n"
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let diagnostic = Diagnostic::error("cannot open file").with_file("missing.bmb");
+        assert_eq!(diagnostic.render(""), "error: cannot open file\n--> missing.bmb\n");
     }
 }
@@ -0,0 +1,154 @@
+//! Pretty-printing document model
+//!
+//! A [`Doc`] describes text together with the places it may break. Groups
+//! are laid out flat when they fit in the remaining width, otherwise every
+//! [`Doc::Line`] directly inside them becomes a newline (Wadler-style).
+
+/// A layout document
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Text(String),
+    /// A space when flat, a newline when the enclosing group breaks
+    Line,
+    /// Nothing when flat, a newline when the enclosing group breaks
+    SoftLine,
+    /// Always a newline; any group containing one breaks
+    HardLine,
+    /// Text emitted only when the enclosing group breaks (e.g. trailing commas)
+    IfBreak(String),
+    /// Indent lines inside by the given number of spaces
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+impl Doc {
+    pub fn text(s: impl Into<String>) -> Doc {
+        Doc::Text(s.into())
+    }
+
+    pub fn nest(indent: usize, doc: Doc) -> Doc {
+        Doc::Nest(indent, Box::new(doc))
+    }
+
+    pub fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+
+    pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Doc {
+        Doc::Concat(docs.into_iter().collect())
+    }
+
+    /// `docs` separated by `sep`
+    pub fn join(docs: impl IntoIterator<Item = Doc>, sep: Doc) -> Doc {
+        let mut parts = Vec::new();
+        for (i, doc) in docs.into_iter().enumerate() {
+            if i > 0 {
+                parts.push(sep.clone());
+            }
+            parts.push(doc);
+        }
+        Doc::Concat(parts)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Lay out `doc` within `width` columns
+pub fn render(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    // Indentation is written lazily so blank lines carry no trailing spaces
+    let mut pending_indent: Option<usize> = None;
+    let mut stack: Vec<(usize, Mode, &Doc)> = vec![(0, Mode::Break, doc)];
+
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => emit(&mut out, &mut column, &mut pending_indent, s),
+            Doc::IfBreak(s) if mode == Mode::Break => emit(&mut out, &mut column, &mut pending_indent, s),
+            Doc::IfBreak(_) => {}
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if matches!(doc, Doc::Line) {
+                    out.push(' ');
+                    column += 1;
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                out.push('\n');
+                pending_indent = Some(indent);
+                column = indent;
+            }
+            Doc::Nest(n, inner) => stack.push((indent + n, mode, inner)),
+            Doc::Concat(parts) => {
+                stack.extend(parts.iter().rev().map(|part| (indent, mode, part)));
+            }
+            Doc::Group(inner) => {
+                let flat = mode == Mode::Flat
+                    || fits(width.saturating_sub(column), (indent, Mode::Flat, inner), &stack);
+                stack.push((indent, if flat { Mode::Flat } else { Mode::Break }, inner));
+            }
+        }
+    }
+    out
+}
+
+fn emit(out: &mut String, column: &mut usize, pending_indent: &mut Option<usize>, s: &str) {
+    if s.is_empty() {
+        return;
+    }
+    if let Some(n) = pending_indent.take() {
+        out.extend(std::iter::repeat_n(' ', n));
+        *column = n;
+    }
+    out.push_str(s);
+    *column += s.chars().count();
+}
+
+/// Whether `next` laid out flat, followed by the rest of the line, fits in `remaining` columns
+fn fits(mut remaining: usize, next: (usize, Mode, &Doc), rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+    loop {
+        let (indent, mode, doc) = match stack.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some(&item) => item,
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => {
+                let len = s.chars().count();
+                if len > remaining {
+                    return false;
+                }
+                remaining -= len;
+            }
+            Doc::IfBreak(s) if mode == Mode::Break => {
+                if s.len() > remaining {
+                    return false;
+                }
+                remaining -= s.len();
+            }
+            Doc::IfBreak(_) => {}
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if matches!(doc, Doc::Line) {
+                    if remaining == 0 {
+                        return false;
+                    }
+                    remaining -= 1;
+                }
+            }
+            // A forced break inside the flat candidate means it cannot stay flat
+            Doc::HardLine if mode == Mode::Flat => return false,
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::Nest(n, inner) => stack.push((indent + n, mode, inner)),
+            Doc::Concat(parts) => stack.extend(parts.iter().rev().map(|part| (indent, mode, part))),
+            Doc::Group(inner) => stack.push((indent, mode, inner)),
+        }
+    }
+}
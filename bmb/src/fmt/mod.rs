@@ -0,0 +1,1444 @@
+//! Source formatter (`bmb fmt`)
+//!
+//! Each item is turned into a `Doc` and laid out within a maximum line
+//! width: blocks put one statement per line, long argument and parameter
+//! lists wrap one entry per line, and match arms always go on their own
+//! lines. Whole-line comments stay in front of the item, field, statement or
+//! arm they precede, and runs of blank lines collapse to one.
+
+mod doc;
+
+use crate::ast::{
+    Abi, Attribute, BinOp, ConstDef, EnumDef, Expr, ExternFn, FnDef, ImplBlock, Item, LiteralPattern,
+    MatchArm, ModuleHeader, Pattern, Program, RangeKind, Span, Spanned, StructDef, TraitDef,
+    Type, TypeAliasDef, TypeParam, UnOp, UseStmt, Visibility,
+};
+
+use std::ops::RangeInclusive;
+
+use crate::error::CompileError;
+use crate::lexer::parse_int_literal;
+use doc::{render, Doc};
+
+/// Default maximum line width
+pub const DEFAULT_WIDTH: usize = 100;
+
+const INDENT: usize = 4;
+
+/// Format a parsed program, keeping the comments found in `source`
+pub fn format_program(program: &Program, source: &str, width: usize) -> String {
+    let mut formatter = Formatter::new(source);
+    let doc = formatter.program(program);
+    render(&doc, width)
+}
+
+/// Format only the items lying entirely within `lines` (1-based,
+/// inclusive), keeping every other byte of `source` as it is
+pub fn format_range(program: &Program, source: &str, lines: RangeInclusive<usize>, width: usize) -> String {
+    let mut formatter = Formatter::new(source);
+    let mut output = String::new();
+    let mut copied = 0;
+    for item in &program.items {
+        let span = item.span();
+        let first = formatter.line_of(span.start);
+        let last = formatter.line_of(span.end.saturating_sub(1));
+        if !lines.contains(&(first + 1)) || !lines.contains(&(last + 1)) {
+            continue;
+        }
+        // Comments in front of the item are outside its span and stay put
+        formatter.next_comment = formatter.comments.partition_point(|(line, _)| *line < first);
+        let doc = formatter.item(item);
+        output.push_str(&source[copied..span.start]);
+        output.push_str(&render(&doc, width));
+        copied = span.end;
+    }
+    output.push_str(&source[copied..]);
+    output
+}
+
+/// Parse and format `source`, all of it or only the items within `lines`.
+/// This is what `bmb fmt` and the language server's formatting requests run.
+pub fn format_source(
+    filename: &str,
+    source: &str,
+    width: usize,
+    lines: Option<RangeInclusive<usize>>,
+) -> Result<String, CompileError> {
+    let tokens = crate::lexer::tokenize(source)?;
+    let program = crate::parser::parse(filename, source, tokens)?;
+    Ok(match lines {
+        Some(lines) => format_range(&program, source, lines, width),
+        None => format_program(&program, source, width),
+    })
+}
+
+/// Format an expression without a width limit (blocks still span lines)
+pub fn format_expr(expr: &Expr) -> String {
+    let doc = Formatter::new("").expr_at(expr, Span::new(0, 0));
+    render(&doc, usize::MAX)
+}
+
+/// Format a type in source syntax
+pub fn format_type(ty: &Type) -> String {
+    match ty {
+        Type::I32 => "i32".to_string(),
+        Type::I64 => "i64".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::F64 => "f64".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::String => "String".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Unit => "()".to_string(),
+        Type::Never => "!".to_string(),
+        Type::Range(elem) => generic("Range", std::iter::once(format_type(elem))),
+        Type::Named(name) | Type::TypeVar(name) => name.clone(),
+        Type::Struct { name, .. } | Type::Enum { name, .. } => name.clone(),
+        Type::Generic { name, type_args } => generic(name, type_args.iter().map(|t| format_type(t))),
+        Type::Array(elem, size) => format!("[{}; {}]", format_type(elem), size),
+        Type::ArrayConst(elem, name) => format!("[{}; {}]", format_type(elem), name),
+        Type::Ref(inner) => format!("&{}", format_type(inner)),
+        Type::RefMut(inner) => format!("&mut {}", format_type(inner)),
+        Type::Refined { base, constraints } => {
+            let constraints: Vec<_> = constraints.iter().map(|c| format_expr(&c.node)).collect();
+            format!("{}{{{}}}", format_type(base), constraints.join(", "))
+        }
+        Type::Fn { params, ret } => {
+            let params: Vec<_> = params.iter().map(|p| format_type(p)).collect();
+            format!("fn({}) -> {}", params.join(", "), format_type(ret))
+        }
+        Type::Nullable(inner) => format!("{}?", format_type(inner)),
+        Type::Tuple(elems) => {
+            let elems: Vec<_> = elems.iter().map(|t| format_type(t)).collect();
+            if elems.len() == 1 {
+                format!("({},)", elems[0])
+            } else {
+                format!("({})", elems.join(", "))
+            }
+        }
+    }
+}
+
+/// `Name<A, B>`, keeping nested closers apart so they don't lex as `>>`
+fn generic(name: &str, args: impl Iterator<Item = String>) -> String {
+    let args = args.collect::<Vec<_>>().join(", ");
+    if args.ends_with('>') {
+        format!("{}<{} >", name, args)
+    } else {
+        format!("{}<{}>", name, args)
+    }
+}
+
+/// Render generic parameters like `<T: Ord, U>` (empty when there are none)
+pub fn format_type_params(type_params: &[TypeParam]) -> String {
+    if type_params.is_empty() {
+        return String::new();
+    }
+    let params: Vec<_> = type_params
+        .iter()
+        .map(|tp| {
+            if tp.bounds.is_empty() {
+                tp.name.clone()
+            } else {
+                format!("{}: {}", tp.name, tp.bounds.join(" + "))
+            }
+        })
+        .collect();
+    format!("<{}>", params.join(", "))
+}
+
+/// Extract whole-line comments with their 0-indexed line numbers
+pub fn extract_comments(source: &str) -> Vec<(usize, String)> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim();
+            // `--` is the legacy comment syntax
+            trimmed.starts_with("//") || trimmed.starts_with("--")
+        })
+        .map(|(line_num, line)| (line_num, line.to_string()))
+        .collect()
+}
+
+/// Something between two formatted elements that must be kept
+enum Trivia {
+    BlankLine,
+    Comment(String),
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    lines: Vec<&'a str>,
+    line_starts: Vec<usize>,
+    comments: Vec<(usize, String)>,
+    /// Comments before this index have been emitted
+    next_comment: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Formatter {
+            source,
+            lines: source.lines().collect(),
+            line_starts,
+            comments: extract_comments(source),
+            next_comment: 0,
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset).saturating_sub(1)
+    }
+
+    /// Whether a line strictly between `after` and `before` is blank
+    fn blank_between(&self, after: usize, before: usize) -> bool {
+        let end = before.min(self.lines.len());
+        (after + 1..end).any(|line| self.lines[line].trim().is_empty())
+    }
+
+    /// Pending comments located before line `to`, and the blank lines around
+    /// them. `from` is the line the previous sibling ended on; without one no
+    /// blank line is kept in front of the first comment.
+    fn trivia(&mut self, from: Option<usize>, to: usize) -> Vec<Trivia> {
+        let mut items = Vec::new();
+        let mut last = from;
+        while let Some((line, text)) = self.comments.get(self.next_comment) {
+            let (line, text) = (*line, text.trim().to_string());
+            if line >= to {
+                break;
+            }
+            if last.is_some_and(|prev| self.blank_between(prev, line)) {
+                items.push(Trivia::BlankLine);
+            }
+            items.push(Trivia::Comment(text));
+            last = Some(last.map_or(line, |prev| prev.max(line)));
+            self.next_comment += 1;
+        }
+        if last.is_some_and(|prev| self.blank_between(prev, to)) {
+            items.push(Trivia::BlankLine);
+        }
+        items
+    }
+
+    /// Trivia in front of the element starting at `offset`, each line ending in a break
+    fn leading(&mut self, prev_end: Option<usize>, offset: usize) -> Doc {
+        let from = prev_end.map(|end| self.line_of(end));
+        let to = self.line_of(offset);
+        Doc::concat(self.trivia(from, to).into_iter().map(|item| match item {
+            Trivia::BlankLine => Doc::HardLine,
+            Trivia::Comment(text) => Doc::concat([Doc::Text(text), Doc::HardLine]),
+        }))
+    }
+
+    /// Comments after the last element of a body closed at offset `close`
+    fn closing(&mut self, prev_end: usize, close: usize) -> Vec<Trivia> {
+        let from = Some(self.line_of(prev_end));
+        let to = self.line_of(close);
+        let mut items = self.trivia(from, to);
+        if matches!(items.last(), Some(Trivia::BlankLine)) {
+            items.pop();
+        }
+        items
+    }
+
+    /// [`Self::closing`] trivia for a body whose last element has no line break after it
+    fn trailing(&mut self, prev_end: usize, close: usize) -> Doc {
+        Doc::concat(self.closing(prev_end, close).into_iter().map(|item| match item {
+            Trivia::BlankLine => Doc::HardLine,
+            Trivia::Comment(text) => Doc::concat([Doc::HardLine, Doc::Text(text)]),
+        }))
+    }
+
+    // ========================================================================
+    // Items
+    // ========================================================================
+
+    fn program(&mut self, program: &Program) -> Doc {
+        let mut parts = Vec::new();
+        let mut prev_end = None;
+        if let Some(header) = &program.header {
+            parts.push(self.leading(None, header.span.start));
+            parts.push(module_header(header));
+            prev_end = Some(header.span.end);
+        }
+        for item in &program.items {
+            if prev_end.is_some() {
+                parts.push(Doc::HardLine);
+            }
+            parts.push(self.leading(None, item.span().start));
+            parts.push(self.item(item));
+            parts.push(Doc::HardLine);
+            prev_end = Some(item.span().end);
+        }
+
+        // Comments after the last item
+        let from = prev_end.map(|end| self.line_of(end));
+        let mut rest = self.trivia(from, usize::MAX);
+        if matches!(rest.last(), Some(Trivia::BlankLine)) {
+            rest.pop();
+        }
+        parts.extend(rest.into_iter().map(|item| match item {
+            Trivia::BlankLine => Doc::HardLine,
+            Trivia::Comment(text) => Doc::concat([Doc::Text(text), Doc::HardLine]),
+        }));
+        Doc::concat(parts)
+    }
+
+    fn item(&mut self, item: &Item) -> Doc {
+        match item {
+            Item::FnDef(f) => self.fn_def(f),
+            Item::StructDef(s) => self.struct_def(s),
+            Item::EnumDef(e) => self.enum_def(e),
+            Item::TypeAlias(t) => self.type_alias(t),
+            Item::ConstDef(c) => self.const_def(c),
+            Item::Use(u) => use_stmt(u),
+            Item::ExternFn(e) => self.extern_fn(e),
+            Item::TraitDef(t) => self.trait_def(t),
+            Item::ImplBlock(i) => self.impl_block(i),
+        }
+    }
+
+    /// Attributes, one per line, followed by the visibility keyword
+    fn item_prefix(&mut self, attributes: &[Attribute], visibility: Visibility) -> Doc {
+        let mut parts: Vec<Doc> = attributes
+            .iter()
+            .map(|attr| Doc::concat([self.attribute(attr), Doc::HardLine]))
+            .collect();
+        if visibility == Visibility::Public {
+            parts.push(Doc::text("pub "));
+        }
+        Doc::concat(parts)
+    }
+
+    fn attribute(&mut self, attr: &Attribute) -> Doc {
+        match attr {
+            Attribute::Simple { name, .. } => Doc::text(format!("@{}", name.node)),
+            Attribute::WithArgs { name, args, .. } => {
+                let args = self.comma_list(args);
+                Doc::concat([Doc::text(format!("@{}", name.node)), delimited("(", args, ")")])
+            }
+            Attribute::WithReason { name, reason, .. } => {
+                Doc::text(format!("@{} \"{}\"", name.node, reason.node))
+            }
+        }
+    }
+
+    fn fn_def(&mut self, f: &FnDef) -> Doc {
+        let mut parts = vec![
+            self.item_prefix(&f.attributes, f.visibility),
+            Doc::text(format!("fn {}{}", f.name.node, format_type_params(&f.type_params))),
+            params(&f.params),
+        ];
+        let ret = match &f.ret_name {
+            Some(name) => format!(" -> {}: {}", name.node, format_type(&f.ret_ty.node)),
+            None => format!(" -> {}", format_type(&f.ret_ty.node)),
+        };
+        parts.push(Doc::Text(ret));
+
+        // Contract clauses stay on their own lines
+        for (keyword, clause) in [("pre", &f.pre), ("post", &f.post)] {
+            if let Some(cond) = clause {
+                let cond = self.expr(cond);
+                parts.push(Doc::nest(2, Doc::concat([Doc::HardLine, Doc::text(keyword), Doc::text(" "), cond])));
+            }
+        }
+        if !f.contracts.is_empty() {
+            let contracts: Vec<Doc> = f
+                .contracts
+                .iter()
+                .map(|c| {
+                    let cond = self.expr(&c.condition);
+                    match &c.name {
+                        Some(name) => Doc::concat([Doc::text(format!("{}: ", name.node)), cond]),
+                        None => cond,
+                    }
+                })
+                .collect();
+            let clause = Doc::concat([Doc::text("where "), braced_list(contracts)]);
+            parts.push(Doc::nest(2, Doc::concat([Doc::HardLine, clause])));
+        }
+
+        let has_contracts = f.pre.is_some() || f.post.is_some() || !f.contracts.is_empty();
+        parts.push(self.fn_body(&f.body, has_contracts));
+        Doc::concat(parts)
+    }
+
+    /// `= body;`, with the body on the next line when it holds statements
+    fn fn_body(&mut self, body: &Spanned<Expr>, after_contracts: bool) -> Doc {
+        let eq = if after_contracts {
+            Doc::concat([Doc::HardLine, Doc::text("=")])
+        } else {
+            Doc::text(" =")
+        };
+        let comments = self.leading(None, body.span.start);
+        let has_comments = !matches!(&comments, Doc::Concat(parts) if parts.is_empty());
+        let layout = if has_comments || matches!(body.node, Expr::Let { .. }) {
+            let body = self.expr(body);
+            Doc::nest(INDENT, Doc::concat([Doc::HardLine, comments, body]))
+        } else if matches!(body.node, Expr::Block(_)) {
+            Doc::concat([Doc::text(" "), self.expr(body)])
+        } else {
+            Doc::group(Doc::nest(INDENT, Doc::concat([Doc::Line, self.expr(body)])))
+        };
+        Doc::concat([eq, layout, Doc::text(";")])
+    }
+
+    fn struct_def(&mut self, s: &StructDef) -> Doc {
+        let mut fields = Vec::new();
+        let mut prev_end = None;
+        for field in &s.fields {
+            fields.push(self.leading(prev_end, field.name.span.start));
+            let decl = format!("{}: {}", field.name.node, format_type(&field.ty.node));
+            match &field.default {
+                Some(default) => {
+                    fields.push(Doc::text(format!("{decl} = ")));
+                    fields.push(self.expr(default));
+                    fields.push(Doc::text(","));
+                    prev_end = Some(default.span.end);
+                }
+                None => {
+                    fields.push(Doc::text(format!("{decl},")));
+                    prev_end = Some(field.ty.span.end);
+                }
+            }
+            fields.push(Doc::HardLine);
+        }
+        Doc::concat([
+            self.item_prefix(&s.attributes, s.visibility),
+            Doc::text(format!("struct {}{} ", s.name.node, format_type_params(&s.type_params))),
+            self.item_body(fields, prev_end, s.span.end.saturating_sub(1)),
+        ])
+    }
+
+    fn enum_def(&mut self, e: &EnumDef) -> Doc {
+        let mut variants = Vec::new();
+        let mut prev_end = None;
+        for variant in &e.variants {
+            variants.push(self.leading(prev_end, variant.name.span.start));
+            let text = if variant.fields.is_empty() {
+                format!("{},", variant.name.node)
+            } else {
+                let fields: Vec<_> = variant.fields.iter().map(|f| format_type(&f.node)).collect();
+                format!("{}({}),", variant.name.node, fields.join(", "))
+            };
+            variants.push(Doc::Text(text));
+            variants.push(Doc::HardLine);
+            prev_end = Some(variant.fields.last().map_or(variant.name.span.end, |f| f.span.end));
+        }
+        Doc::concat([
+            self.item_prefix(&e.attributes, e.visibility),
+            Doc::text(format!("enum {}{} ", e.name.node, format_type_params(&e.type_params))),
+            self.item_body(variants, prev_end, e.span.end.saturating_sub(1)),
+        ])
+    }
+
+    /// `{ ... }` around member lines that each end in a break
+    fn item_body(&mut self, members: Vec<Doc>, prev_end: Option<usize>, close: usize) -> Doc {
+        if members.is_empty() {
+            return Doc::text("{}");
+        }
+        let mut lines = members;
+        if let Some(end) = prev_end {
+            for item in self.closing(end, close) {
+                match item {
+                    Trivia::BlankLine => lines.push(Doc::HardLine),
+                    Trivia::Comment(text) => lines.extend([Doc::Text(text), Doc::HardLine]),
+                }
+            }
+        }
+        // The final break belongs outside the nest so `}` returns to the item's indent
+        lines.pop();
+        Doc::concat([
+            Doc::text("{"),
+            Doc::nest(INDENT, Doc::concat([Doc::HardLine, Doc::concat(lines)])),
+            Doc::HardLine,
+            Doc::text("}"),
+        ])
+    }
+
+    fn type_alias(&mut self, t: &TypeAliasDef) -> Doc {
+        let mut parts = vec![
+            self.item_prefix(&t.attributes, t.visibility),
+            Doc::text(format!(
+                "type {}{} = {}",
+                t.name.node,
+                format_type_params(&t.type_params),
+                format_type(&t.target.node)
+            )),
+        ];
+        if let Some(refinement) = &t.refinement {
+            parts.push(Doc::text(" where { "));
+            parts.push(self.expr(refinement));
+            parts.push(Doc::text(" }"));
+        }
+        parts.push(Doc::text(";"));
+        Doc::concat(parts)
+    }
+
+    fn const_def(&mut self, c: &ConstDef) -> Doc {
+        Doc::concat([
+            self.item_prefix(&c.attributes, c.visibility),
+            Doc::text(format!("const {}: {} = ", c.name.node, format_type(&c.ty.node))),
+            self.expr(&c.value),
+            Doc::text(";"),
+        ])
+    }
+
+    fn extern_fn(&mut self, e: &ExternFn) -> Doc {
+        let abi = match e.abi {
+            Abi::Bmb => "",
+            Abi::C => "\"C\" ",
+            Abi::System => "\"system\" ",
+        };
+        Doc::concat([
+            self.item_prefix(&e.attributes, e.visibility),
+            Doc::text(format!("extern {}fn {}", abi, e.name.node)),
+            params(&e.params),
+            Doc::text(format!(" -> {};", format_type(&e.ret_ty.node))),
+        ])
+    }
+
+    fn trait_def(&mut self, t: &TraitDef) -> Doc {
+        let mut methods = Vec::new();
+        let mut prev_end = None;
+        for method in &t.methods {
+            methods.push(self.leading(prev_end, method.span.start));
+            methods.push(Doc::text(format!("fn {}", method.name.node)));
+            methods.push(params(&method.params));
+            methods.push(Doc::text(format!(" -> {}", format_type(&method.ret_ty.node))));
+            match &method.body {
+                Some(body) => methods.push(self.fn_body(body, false)),
+                None => methods.push(Doc::text(";")),
+            }
+            methods.push(Doc::HardLine);
+            prev_end = Some(method.span.end);
+        }
+        Doc::concat([
+            self.item_prefix(&t.attributes, t.visibility),
+            Doc::text(format!("trait {}{} ", t.name.node, format_type_params(&t.type_params))),
+            self.item_body(methods, prev_end, t.span.end.saturating_sub(1)),
+        ])
+    }
+
+    fn impl_block(&mut self, i: &ImplBlock) -> Doc {
+        let mut methods = Vec::new();
+        let mut prev_end = None;
+        for method in &i.methods {
+            methods.push(self.leading(prev_end, method.span.start));
+            methods.push(self.fn_def(method));
+            methods.push(Doc::HardLine);
+            prev_end = Some(method.span.end);
+        }
+        Doc::concat([
+            self.item_prefix(&i.attributes, Visibility::Private),
+            Doc::text(match &i.trait_name {
+                Some(trait_name) => format!(
+                    "impl{} {} for {} ",
+                    format_type_params(&i.type_params),
+                    trait_name.node,
+                    format_type(&i.target_type.node)
+                ),
+                None => format!("impl{} {} ", format_type_params(&i.type_params), format_type(&i.target_type.node)),
+            }),
+            self.item_body(methods, prev_end, i.span.end.saturating_sub(1)),
+        ])
+    }
+
+    // ========================================================================
+    // Expressions
+    // ========================================================================
+
+    fn expr(&mut self, expr: &Spanned<Expr>) -> Doc {
+        self.expr_at(&expr.node, expr.span)
+    }
+
+    /// `expr`, parenthesized when it binds looser than `min_prec`
+    fn operand(&mut self, expr: &Spanned<Expr>, min_prec: u8) -> Doc {
+        let doc = self.expr(expr);
+        if precedence(&expr.node) < min_prec {
+            Doc::concat([Doc::text("("), doc, Doc::text(")")])
+        } else {
+            doc
+        }
+    }
+
+    fn expr_at(&mut self, expr: &Expr, span: Span) -> Doc {
+        match expr {
+            Expr::IntLit(n) => Doc::Text(written_int(self.source, span, *n)),
+            Expr::FloatLit(f) => Doc::Text(format_float(*f)),
+            Expr::BoolLit(b) => Doc::Text(b.to_string()),
+            Expr::StringLit(s) => {
+                // Keep raw strings raw; contents with quotes or backslashes
+                // can only be written as one
+                let written = self.source.get(span.start..span.end).unwrap_or("");
+                if written.starts_with('r') || s.contains(['"', '\\']) {
+                    Doc::Text(format_raw_string(s))
+                } else {
+                    Doc::Text(format!("\"{}\"", s))
+                }
+            }
+            Expr::CharLit(c) => Doc::Text(format_char(*c)),
+            Expr::Unit => Doc::text("()"),
+            Expr::Var(name) => Doc::text(name),
+            Expr::Ret => Doc::text("ret"),
+            Expr::It => Doc::text("it"),
+            Expr::Todo { message } => match message {
+                Some(msg) => Doc::Text(format!("todo \"{}\"", msg)),
+                None => Doc::text("todo"),
+            },
+
+            Expr::Binary { .. } => self.binary_chain(expr),
+
+            Expr::Unary { op, expr: operand } => {
+                let (prefix, clashes) = match op {
+                    UnOp::Neg => ("-", matches!(operand.node, Expr::Unary { op: UnOp::Neg, .. })),
+                    UnOp::Not => ("not ", false),
+                    UnOp::Bnot => ("bnot ", false),
+                };
+                self.prefixed(prefix, operand, clashes)
+            }
+            Expr::Ref(inner) => {
+                let clashes = matches!(inner.node, Expr::Ref(_) | Expr::RefMut(_));
+                self.prefixed("&", inner, clashes)
+            }
+            Expr::RefMut(inner) => self.prefixed("&mut ", inner, false),
+            Expr::Deref(inner) => self.prefixed("*", inner, false),
+
+            Expr::If { .. } => self.if_chain(expr),
+
+            Expr::Let { name, mutable, ty, value, body } => {
+                let header = let_header(name, *mutable, ty);
+                let value_doc = self.expr(value);
+                let comments = self.leading(Some(value.span.end), body.span.start);
+                Doc::concat([
+                    Doc::Text(header),
+                    value_doc,
+                    Doc::text(";"),
+                    Doc::HardLine,
+                    comments,
+                    self.expr(body),
+                ])
+            }
+
+            // The `;` is written by the enclosing block
+            Expr::LetStmt { name, mutable, ty, value } => {
+                let header = let_header(name, *mutable, ty);
+                Doc::concat([Doc::Text(header), self.expr(value)])
+            }
+
+            Expr::Assign { name, value } => {
+                Doc::concat([Doc::text(format!("{} = ", name)), self.expr(value)])
+            }
+
+            Expr::IndexAssign { array, index, value } => Doc::concat([
+                self.operand(array, POSTFIX),
+                Doc::text("["),
+                self.expr(index),
+                Doc::text("] = "),
+                self.expr(value),
+            ]),
+
+            Expr::FieldAssign { object, field, value } => Doc::concat([
+                self.operand(object, POSTFIX),
+                Doc::text(format!(".{} = ", field.node)),
+                self.expr(value),
+            ]),
+
+            Expr::Block(stmts) => self.block(stmts, span),
+
+            Expr::Call { func, args } => {
+                let args = self.comma_list(args);
+                Doc::concat([Doc::text(func), delimited("(", args, ")")])
+            }
+
+            Expr::MethodCall { receiver, method, args } => {
+                let receiver = self.operand(receiver, POSTFIX);
+                let args = self.comma_list(args);
+                Doc::concat([receiver, Doc::text(format!(".{}", method)), delimited("(", args, ")")])
+            }
+
+            Expr::EnumVariant { enum_name, variant, args } => {
+                let path = Doc::text(format!("{}::{}", enum_name, variant));
+                // `Type::new()` needs its parens: `new` is a keyword
+                if args.is_empty() && variant != "new" {
+                    path
+                } else {
+                    let args = self.comma_list(args);
+                    Doc::concat([path, delimited("(", args, ")")])
+                }
+            }
+
+            Expr::FieldAccess { expr: receiver, field } => {
+                let receiver = self.operand(receiver, POSTFIX);
+                Doc::concat([receiver, Doc::text(format!(".{}", field.node))])
+            }
+
+            Expr::TupleField { expr: receiver, index } => {
+                // `t.0.1` and `5.0` would lex as float literals
+                let receiver = if matches!(receiver.node, Expr::TupleField { .. } | Expr::IntLit(_)) {
+                    let inner = self.expr(receiver);
+                    Doc::concat([Doc::text("("), inner, Doc::text(")")])
+                } else {
+                    self.operand(receiver, POSTFIX)
+                };
+                Doc::concat([receiver, Doc::text(format!(".{}", index))])
+            }
+
+            Expr::StateRef { expr: receiver, state } => {
+                let receiver = self.operand(receiver, POSTFIX);
+                Doc::concat([receiver, Doc::text(state.to_string())])
+            }
+
+            Expr::Cast { expr: operand, ty } => {
+                let operand = self.operand(operand, POSTFIX);
+                Doc::concat([operand, Doc::text(format!(" as {}", format_type(&ty.node)))])
+            }
+
+            Expr::Index { expr: base, index } => {
+                let base = self.operand(base, POSTFIX);
+                let index = self.expr(index);
+                Doc::concat([base, Doc::text("["), index, Doc::text("]")])
+            }
+
+            Expr::ArrayLit(elems) => {
+                let elems = self.comma_list(elems);
+                delimited("[", elems, "]")
+            }
+
+            Expr::Tuple(elems) => {
+                if elems.len() == 1 {
+                    let elem = self.expr(&elems[0]);
+                    Doc::concat([Doc::text("("), elem, Doc::text(",)")])
+                } else {
+                    let elems = self.comma_list(elems);
+                    delimited("(", elems, ")")
+                }
+            }
+
+            Expr::StructInit { name, fields, base } => {
+                let fields: Vec<Doc> = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        Doc::concat([Doc::text(format!("{}: ", field.node)), self.expr(value)])
+                    })
+                    .collect();
+                let body = match base {
+                    Some(base) => braced_list_with_rest(fields, Doc::concat([Doc::text(".."), self.expr(base)])),
+                    None => braced_list(fields),
+                };
+                Doc::concat([Doc::text(format!("new {} ", name)), body])
+            }
+
+            Expr::Match { expr: scrutinee, arms } => self.match_expr(scrutinee, arms, span),
+
+            Expr::While { label, cond, invariant, body } => {
+                let mut head = vec![Doc::text(format!("{}while ", label_prefix(label))), self.expr(cond)];
+                if let Some(inv) = invariant {
+                    head.push(Doc::text(" invariant "));
+                    head.push(self.expr(inv));
+                }
+                let body = self.expr(body);
+                braced_body(Doc::concat(head), body)
+            }
+
+            Expr::For { label, var, iter, body } => {
+                let iter = match iter.node {
+                    Expr::Range { .. } => self.expr(iter),
+                    _ => self.operand(iter, IMPLIES),
+                };
+                let head = Doc::concat([Doc::text(format!("{}for {} in ", label_prefix(label), var)), iter]);
+                let body = self.expr(body);
+                braced_body(head, body)
+            }
+
+            Expr::Loop { label, body } => {
+                let body = self.expr(body);
+                braced_body(Doc::text(format!("{}loop", label_prefix(label))), body)
+            }
+
+            Expr::Range { start, end, kind } => {
+                let op = match kind {
+                    RangeKind::Exclusive => "..<",
+                    RangeKind::Inclusive => "..=",
+                };
+                let start = self.operand(start, ADD);
+                let end = self.operand(end, ADD);
+                Doc::concat([start, Doc::text(op), end])
+            }
+
+            Expr::Break { label, value } => self.keyword_with_value(&with_label("break", label), value),
+            Expr::Continue { label } => Doc::text(with_label("continue", label)),
+            Expr::Return { value } => self.keyword_with_value("return", value),
+
+            Expr::Forall { var, ty, body } | Expr::Exists { var, ty, body } => {
+                let keyword = if matches!(expr, Expr::Forall { .. }) { "forall" } else { "exists" };
+                let body = self.operand(body, IMPLIES);
+                Doc::concat([
+                    Doc::text(format!("{} {}: {}, ", keyword, var.node, format_type(&ty.node))),
+                    body,
+                ])
+            }
+
+            Expr::Closure { params, ret_ty, body } => {
+                let params: Vec<_> = params
+                    .iter()
+                    .map(|p| match &p.ty {
+                        Some(ty) => format!("{}: {}", p.name.node, format_type(&ty.node)),
+                        None => p.name.node.clone(),
+                    })
+                    .collect();
+                let mut head = if params.is_empty() {
+                    "fn ||".to_string()
+                } else {
+                    format!("fn |{}|", params.join(", "))
+                };
+                if let Some(ty) = ret_ty {
+                    head.push_str(&format!(" -> {}", format_type(&ty.node)));
+                }
+                head.push(' ');
+                Doc::concat([Doc::Text(head), self.expr(body)])
+            }
+        }
+    }
+
+    fn prefixed(&mut self, prefix: &str, operand: &Spanned<Expr>, clashes: bool) -> Doc {
+        // `--x` would start a comment and `&&x` lexes as `&&`
+        let min_prec = if clashes { PRIMARY } else { UNARY };
+        let operand = self.operand(operand, min_prec);
+        Doc::concat([Doc::text(prefix), operand])
+    }
+
+    fn keyword_with_value(&mut self, keyword: &str, value: &Option<Box<Spanned<Expr>>>) -> Doc {
+        match value {
+            Some(v) => Doc::concat([Doc::text(format!("{} ", keyword)), self.expr(v)]),
+            None => Doc::text(keyword),
+        }
+    }
+
+    /// A run of same-precedence binary operators, breaking before each operator
+    fn binary_chain(&mut self, expr: &Expr) -> Doc {
+        let Expr::Binary { op, .. } = expr else {
+            unreachable!("binary_chain on a non-binary expression")
+        };
+        let level = binary_precedence(*op);
+
+        // Walk down the left spine while the operators share a level
+        let mut spine = Vec::new();
+        let mut leftmost = expr;
+        let mut leftmost_operand = None;
+        while let Expr::Binary { left, op, right } = leftmost {
+            if binary_precedence(*op) != level || (!spine.is_empty() && level == CMP) {
+                break;
+            }
+            spine.push((*op, right));
+            leftmost_operand = Some(left);
+            leftmost = &left.node;
+        }
+        let left = leftmost_operand.expect("binary expression has a left operand");
+        spine.reverse();
+
+        // Comparisons don't associate, and the right operand of a
+        // left-associative operator must bind tighter
+        let first = self.operand(left, if level == CMP { level + 1 } else { level });
+        let mut rest = Vec::new();
+        for (op, right) in spine {
+            rest.push(Doc::Line);
+            rest.push(Doc::text(format!("{} ", binary_op(op))));
+            rest.push(self.operand(right, level + 1));
+        }
+        Doc::group(Doc::concat([first, Doc::nest(INDENT, Doc::concat(rest))]))
+    }
+
+    /// `if a { .. } else if b { .. } else { .. }`
+    fn if_chain(&mut self, expr: &Expr) -> Doc {
+        let mut parts = Vec::new();
+        let mut current = expr;
+        let mut keyword = "if ";
+        while let Expr::If { cond, then_branch, else_branch } = current {
+            parts.push(Doc::text(keyword));
+            parts.push(self.expr(cond));
+            parts.push(Doc::text(" {"));
+            let then_doc = self.expr(then_branch);
+            parts.push(Doc::nest(INDENT, Doc::concat([Doc::Line, then_doc])));
+            parts.push(Doc::Line);
+            parts.push(Doc::text("}"));
+            keyword = " else if ";
+            current = &else_branch.node;
+            if !matches!(current, Expr::If { .. }) {
+                parts.push(Doc::text(" else {"));
+                let else_doc = self.expr(else_branch);
+                parts.push(Doc::nest(INDENT, Doc::concat([Doc::Line, else_doc])));
+                parts.push(Doc::Line);
+                parts.push(Doc::text("}"));
+            }
+        }
+        Doc::group(Doc::concat(parts))
+    }
+
+    fn block(&mut self, stmts: &[Spanned<Expr>], span: Span) -> Doc {
+        if stmts.is_empty() {
+            return Doc::text("{}");
+        }
+        let mut parts = Vec::new();
+        let mut prev_end = None;
+        for (i, stmt) in stmts.iter().enumerate() {
+            parts.push(self.leading(prev_end, stmt.span.start));
+            parts.push(self.expr(stmt));
+            if i + 1 < stmts.len() {
+                parts.push(Doc::text(";"));
+                parts.push(Doc::HardLine);
+            } else if matches!(stmt.node, Expr::LetStmt { .. }) {
+                parts.push(Doc::text(";"));
+            }
+            prev_end = Some(stmt.span.end);
+        }
+        if let Some(end) = prev_end {
+            parts.push(self.trailing(end, span.end.saturating_sub(1)));
+        }
+        Doc::group(Doc::concat([
+            Doc::text("{"),
+            Doc::nest(INDENT, Doc::concat([Doc::Line, Doc::concat(parts)])),
+            Doc::Line,
+            Doc::text("}"),
+        ]))
+    }
+
+    fn match_expr(&mut self, scrutinee: &Spanned<Expr>, arms: &[MatchArm], span: Span) -> Doc {
+        let scrutinee = self.expr(scrutinee);
+        if arms.is_empty() {
+            return Doc::concat([Doc::text("match "), scrutinee, Doc::text(" {}")]);
+        }
+        let mut lines = Vec::new();
+        let mut prev_end = None;
+        for arm in arms {
+            lines.push(Doc::HardLine);
+            lines.push(self.leading(prev_end, arm.pattern.span.start));
+            lines.push(Doc::Text(format_pattern(&arm.pattern, self.source)));
+            if let Some(guard) = &arm.guard {
+                lines.push(Doc::text(" if "));
+                lines.push(self.expr(guard));
+            }
+            lines.push(Doc::text(" => "));
+            lines.push(self.expr(&arm.body));
+            lines.push(Doc::text(","));
+            prev_end = Some(arm.body.span.end);
+        }
+        if let Some(end) = prev_end {
+            lines.push(self.trailing(end, span.end.saturating_sub(1)));
+        }
+        Doc::concat([
+            Doc::text("match "),
+            scrutinee,
+            Doc::text(" {"),
+            Doc::nest(INDENT, Doc::concat(lines)),
+            Doc::HardLine,
+            Doc::text("}"),
+        ])
+    }
+
+    fn comma_list(&mut self, exprs: &[Spanned<Expr>]) -> Vec<Doc> {
+        exprs.iter().map(|e| self.expr(e)).collect()
+    }
+}
+
+/// Header block ending in the `===` separator
+fn module_header(header: &ModuleHeader) -> Doc {
+    let mut lines = vec![format!("module {}", header.name.node)];
+    if let Some(version) = &header.version {
+        lines.push(format!("  version \"{}\"", version.node));
+    }
+    if let Some(summary) = &header.summary {
+        lines.push(format!("  summary \"{}\"", summary.node));
+    }
+    if !header.exports.is_empty() {
+        let exports: Vec<_> = header.exports.iter().map(|e| e.node.as_str()).collect();
+        lines.push(format!("  exports {}", exports.join(", ")));
+    }
+    if !header.depends.is_empty() {
+        lines.push("  depends".to_string());
+        for dep in &header.depends {
+            if dep.imports.is_empty() {
+                lines.push(format!("    {}", dep.module_path.node));
+            } else {
+                let imports: Vec<_> = dep.imports.iter().map(|i| i.node.as_str()).collect();
+                lines.push(format!("    {} ({})", dep.module_path.node, imports.join(", ")));
+            }
+        }
+    }
+    lines.push("===".to_string());
+    Doc::concat(lines.into_iter().map(|line| Doc::concat([Doc::Text(line), Doc::HardLine])))
+}
+
+fn use_stmt(u: &UseStmt) -> Doc {
+    Doc::Text(format!("use {};", u.path_text()))
+}
+
+/// `(a: T, b: U)`, one parameter per line when too long
+fn params(params: &[crate::ast::Param]) -> Doc {
+    let params = params
+        .iter()
+        .map(|p| Doc::Text(format!("{}: {}", p.name.node, format_type(&p.ty.node))))
+        .collect();
+    delimited("(", params, ")")
+}
+
+/// `open a, b close`, or one entry per line with a trailing comma
+fn delimited(open: &str, items: Vec<Doc>, close: &str) -> Doc {
+    if items.is_empty() {
+        return Doc::text(format!("{}{}", open, close));
+    }
+    Doc::group(Doc::concat([
+        Doc::text(open),
+        Doc::nest(
+            INDENT,
+            Doc::concat([
+                Doc::SoftLine,
+                Doc::join(items, Doc::concat([Doc::text(","), Doc::Line])),
+                Doc::IfBreak(",".to_string()),
+            ]),
+        ),
+        Doc::SoftLine,
+        Doc::text(close),
+    ]))
+}
+
+/// `{ a, b }` with spaces inside, or one entry per line with a trailing comma
+fn braced_list(items: Vec<Doc>) -> Doc {
+    if items.is_empty() {
+        return Doc::text("{}");
+    }
+    Doc::group(Doc::concat([
+        Doc::text("{"),
+        Doc::nest(
+            INDENT,
+            Doc::concat([
+                Doc::Line,
+                Doc::join(items, Doc::concat([Doc::text(","), Doc::Line])),
+                Doc::IfBreak(",".to_string()),
+            ]),
+        ),
+        Doc::Line,
+        Doc::text("}"),
+    ]))
+}
+
+/// `{ a, b, ..rest }`, like `braced_list` but without a trailing comma
+/// after the rest entry when broken over lines
+fn braced_list_with_rest(mut items: Vec<Doc>, rest: Doc) -> Doc {
+    items.push(rest);
+    Doc::group(Doc::concat([
+        Doc::text("{"),
+        Doc::nest(
+            INDENT,
+            Doc::concat([Doc::Line, Doc::join(items, Doc::concat([Doc::text(","), Doc::Line]))]),
+        ),
+        Doc::Line,
+        Doc::text("}"),
+    ]))
+}
+
+/// `'outer: ` in front of a labeled loop
+fn label_prefix(label: &Option<String>) -> String {
+    label.as_ref().map(|l| format!("'{l}: ")).unwrap_or_default()
+}
+
+/// `break`/`continue` with its optional target label
+fn with_label(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(l) => format!("{keyword} '{l}"),
+        None => keyword.to_string(),
+    }
+}
+
+/// `head { body }` for loops
+fn braced_body(head: Doc, body: Doc) -> Doc {
+    Doc::group(Doc::concat([
+        head,
+        Doc::text(" {"),
+        Doc::nest(INDENT, Doc::concat([Doc::Line, body])),
+        Doc::Line,
+        Doc::text("}"),
+    ]))
+}
+
+// Binding strength of each grammar level, loosest first
+const STATEMENT: u8 = 0;
+const IMPLIES: u8 = 1;
+const OR: u8 = 2;
+const AND: u8 = 3;
+const CMP: u8 = 4;
+const BOR: u8 = 5;
+const BXOR: u8 = 6;
+const BAND: u8 = 7;
+const SHIFT: u8 = 8;
+const ADD: u8 = 9;
+const MUL: u8 = 10;
+const UNARY: u8 = 11;
+const POSTFIX: u8 = 12;
+const PRIMARY: u8 = 13;
+
+fn binary_precedence(op: BinOp) -> u8 {
+    match op {
+        BinOp::Implies => IMPLIES,
+        BinOp::Or => OR,
+        BinOp::And => AND,
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => CMP,
+        BinOp::Bor => BOR,
+        BinOp::Bxor => BXOR,
+        BinOp::Band => BAND,
+        BinOp::Shl | BinOp::Shr => SHIFT,
+        BinOp::Add
+        | BinOp::Sub
+        | BinOp::AddWrap
+        | BinOp::SubWrap
+        | BinOp::AddChecked
+        | BinOp::SubChecked
+        | BinOp::AddSat
+        | BinOp::SubSat => ADD,
+        BinOp::Mul
+        | BinOp::Div
+        | BinOp::Mod
+        | BinOp::MulWrap
+        | BinOp::MulChecked
+        | BinOp::MulSat => MUL,
+    }
+}
+
+/// `let [mut] name[: T] = `
+fn let_header(name: &str, mutable: bool, ty: &Option<Spanned<Type>>) -> String {
+    let mut header = format!("let {}{}", if mutable { "mut " } else { "" }, name);
+    if let Some(ty) = ty {
+        header.push_str(&format!(": {}", format_type(&ty.node)));
+    }
+    header.push_str(" = ");
+    header
+}
+
+/// The grammar level an expression parses at
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Binary { op, .. } => binary_precedence(*op),
+        Expr::Unary { .. } | Expr::Ref(_) | Expr::RefMut(_) | Expr::Deref(_) => UNARY,
+        Expr::Cast { .. }
+        | Expr::StateRef { .. }
+        | Expr::MethodCall { .. }
+        | Expr::FieldAccess { .. }
+        | Expr::TupleField { .. }
+        | Expr::Index { .. } => POSTFIX,
+        Expr::IntLit(_)
+        | Expr::FloatLit(_)
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::CharLit(_)
+        | Expr::Unit
+        | Expr::Var(_)
+        | Expr::Ret
+        | Expr::It
+        | Expr::Todo { .. }
+        | Expr::Call { .. }
+        | Expr::EnumVariant { .. }
+        | Expr::ArrayLit(_)
+        | Expr::Tuple(_)
+        | Expr::StructInit { .. }
+        | Expr::Closure { .. } => PRIMARY,
+        Expr::If { .. }
+        | Expr::Let { .. }
+        | Expr::LetStmt { .. }
+        | Expr::Assign { .. }
+        | Expr::IndexAssign { .. }
+        | Expr::FieldAssign { .. }
+        | Expr::Block(_)
+        | Expr::Match { .. }
+        | Expr::While { .. }
+        | Expr::For { .. }
+        | Expr::Loop { .. }
+        | Expr::Range { .. }
+        | Expr::Break { .. }
+        | Expr::Continue { .. }
+        | Expr::Return { .. }
+        | Expr::Forall { .. }
+        | Expr::Exists { .. } => STATEMENT,
+    }
+}
+
+fn binary_op(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::AddWrap => "+%",
+        BinOp::SubWrap => "-%",
+        BinOp::MulWrap => "*%",
+        BinOp::AddChecked => "+?",
+        BinOp::SubChecked => "-?",
+        BinOp::MulChecked => "*?",
+        BinOp::AddSat => "+|",
+        BinOp::SubSat => "-|",
+        BinOp::MulSat => "*|",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Le => "<=",
+        BinOp::Gt => ">",
+        BinOp::Ge => ">=",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+        BinOp::Shl => "<<",
+        BinOp::Shr => ">>",
+        BinOp::Band => "band",
+        BinOp::Bor => "bor",
+        BinOp::Bxor => "bxor",
+        BinOp::Implies => "implies",
+    }
+}
+
+/// Float literal that still lexes as a float (`1.0`, not `1`)
+fn format_float(f: f64) -> String {
+    let s = f.to_string();
+    if s.contains(['.', 'e', 'E']) || !f.is_finite() {
+        s
+    } else {
+        format!("{}.0", s)
+    }
+}
+
+/// Raw string literal with the fewest hashes that still delimit `s`
+fn format_raw_string(s: &str) -> String {
+    let hashes = (0..)
+        .map(|n| "#".repeat(n))
+        .find(|hashes| !s.contains(&format!("\"{}", hashes)))
+        .unwrap_or_default();
+    format!("r{hashes}\"{s}\"{hashes}")
+}
+
+fn format_char(c: char) -> String {
+    let escaped = match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\'' => "\\'".to_string(),
+        '\0' => "\\0".to_string(),
+        c => c.to_string(),
+    };
+    format!("'{}'", escaped)
+}
+
+fn format_literal_pattern(lit: &LiteralPattern) -> String {
+    match lit {
+        LiteralPattern::Int(n) => n.to_string(),
+        LiteralPattern::Float(f) => format_float(*f),
+        LiteralPattern::Bool(b) => b.to_string(),
+        LiteralPattern::String(s) => format!("\"{}\"", s),
+    }
+}
+
+/// An integer literal as `source` writes it at `span`, keeping its radix and
+/// `_` separators, or in decimal when the span holds something else
+fn written_int(source: &str, span: Span, n: i64) -> String {
+    source
+        .get(span.start..span.end)
+        .filter(|text| parse_int_literal(text) == Ok(n))
+        .map_or_else(|| n.to_string(), str::to_string)
+}
+
+fn format_pattern(pattern: &Spanned<Pattern>, source: &str) -> String {
+    let list = |pats: &[Spanned<Pattern>]| {
+        pats.iter().map(|p| format_pattern(p, source)).collect::<Vec<_>>().join(", ")
+    };
+    match &pattern.node {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Var(name) => name.clone(),
+        Pattern::Literal(LiteralPattern::Int(n)) => written_int(source, pattern.span, *n),
+        Pattern::Literal(lit) => format_literal_pattern(lit),
+        Pattern::EnumVariant { enum_name, variant, bindings } => {
+            if bindings.is_empty() {
+                format!("{}::{}", enum_name, variant)
+            } else {
+                format!("{}::{}({})", enum_name, variant, list(bindings))
+            }
+        }
+        Pattern::Struct { name, fields } => {
+            if fields.is_empty() {
+                return format!("{} {{}}", name);
+            }
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(n, p)| format!("{}: {}", n.node, format_pattern(p, source)))
+                .collect();
+            format!("{} {{ {} }}", name, fields.join(", "))
+        }
+        Pattern::Range { start, end, inclusive } => {
+            let op = if *inclusive { "..=" } else { ".." };
+            format!("{}{}{}", format_literal_pattern(start), op, format_literal_pattern(end))
+        }
+        Pattern::Or(alts) => alts.iter().map(|p| format_pattern(p, source)).collect::<Vec<_>>().join(" | "),
+        Pattern::Binding { name, pattern } => format!("{} @ {}", name, format_pattern(pattern, source)),
+        Pattern::Tuple(elems) => {
+            if elems.len() == 1 {
+                format!("({},)", format_pattern(&elems[0], source))
+            } else {
+                format!("({})", list(elems))
+            }
+        }
+        Pattern::Array(elems) => format!("[{}]", list(elems)),
+        Pattern::ArrayRest { prefix, suffix } => match (prefix.is_empty(), suffix.is_empty()) {
+            (true, true) => "[..]".to_string(),
+            (false, true) => format!("[{}, ..]", list(prefix)),
+            (true, false) => format!("[.., {}]", list(suffix)),
+            (false, false) => format!("[{}, .., {}]", list(prefix), list(suffix)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn fmt(source: &str, width: usize) -> String {
+        let ast = parse("test.bmb", source, tokenize(source).unwrap()).unwrap();
+        format_program(&ast, source, width)
+    }
+
+    #[test]
+    fn test_block_statements_one_per_line() {
+        let out = fmt("fn f(x: i64) -> i64 = { g(x); g(x + 1); x };", DEFAULT_WIDTH);
+        assert_eq!(out, "fn f(x: i64) -> i64 = {\n    g(x);\n    g(x + 1);\n    x\n};\n");
+    }
+
+    #[test]
+    fn test_let_statements_one_per_line() {
+        let out = fmt("fn f(x: i64) -> i64 = { let mut y = x; y = y + 1; let z: i64 = y * 2; z };", DEFAULT_WIDTH);
+        assert_eq!(out, "fn f(x: i64) -> i64 = {\n    let mut y = x;\n    y = y + 1;\n    let z: i64 = y * 2;\n    z\n};\n");
+
+        // A trailing let keeps its semicolon
+        let out = fmt("fn f() -> () = { let _x = g(); };", DEFAULT_WIDTH);
+        assert_eq!(out, "fn f() -> () = { let _x = g(); };\n");
+    }
+
+    #[test]
+    fn test_struct_update_base_last_without_trailing_comma() {
+        let out = fmt("fn f(p: P) -> P = new P { x: 1, ..p };", DEFAULT_WIDTH);
+        assert_eq!(out, "fn f(p: P) -> P = new P { x: 1, ..p };\n");
+
+        let out = fmt("fn f(p: P) -> P = new P { first_field: 1, second_field: 2, ..original_point };", 40);
+        assert!(out.contains("        ..original_point\n    }"), "{}", out);
+    }
+
+    #[test]
+    fn test_long_call_arguments_wrap() {
+        let source = "fn f(a: i64, b: i64, c: i64) -> i64 = add3(alpha_value, beta_value, gamma_value);";
+        let out = fmt(source, 40);
+        assert!(out.contains("add3(\n"), "{}", out);
+        assert!(out.contains("        gamma_value,\n"), "{}", out);
+        assert!(out.lines().all(|line| line.len() <= 40), "{}", out);
+        assert_eq!(fmt(&out, 40), out);
+    }
+
+    #[test]
+    fn test_match_arms_on_own_lines() {
+        let out = fmt("fn f(x: i64) -> i64 = match x { 0 => 1, _ => x };", DEFAULT_WIDTH);
+        assert!(out.contains("match x {\n        0 => 1,\n        _ => x,\n    }"), "{}", out);
+    }
+
+    #[test]
+    fn test_contract_clauses_on_separate_lines() {
+        let out = fmt("fn f(x: i64) -> i64 pre x > 0 post ret > x = x + 1;", DEFAULT_WIDTH);
+        assert_eq!(out, "fn f(x: i64) -> i64\n  pre x > 0\n  post ret > x\n= x + 1;\n");
+    }
+
+    #[test]
+    fn test_comments_preserved_in_body() {
+        let source = "fn f() -> i64 = {\n    // first\n    let x = 1;\n\n    -- second\n    x\n};\n";
+        let out = fmt(source, DEFAULT_WIDTH);
+        assert!(out.contains("    // first\n    let x = 1;\n\n    -- second\n    x\n"), "{}", out);
+    }
+
+    #[test]
+    fn test_item_body_closing_brace_at_item_indent() {
+        let out = fmt("struct P { x: i64, y: i64 }\ntrait T {\n    fn f(self: Self) -> i64;\n    // last\n}", DEFAULT_WIDTH);
+        assert_eq!(
+            out,
+            "struct P {\n    x: i64,\n    y: i64,\n}\n\ntrait T {\n    fn f(self: Self) -> i64;\n    // last\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_else_if_chain_flattened() {
+        let source = "fn sign(x: i64) -> i64 = if x < 0 then 0 - 1 else if x == 0 then 0 else 1;";
+        let out = fmt(source, DEFAULT_WIDTH);
+        assert_eq!(out, "fn sign(x: i64) -> i64 = if x < 0 { 0 - 1 } else if x == 0 { 0 } else { 1 };\n");
+
+        // A chain that does not fit keeps every branch at the same depth
+        let out = fmt(source, 30);
+        assert_eq!(
+            out,
+            "fn sign(x: i64) -> i64 =\n    if x < 0 {\n        0 - 1\n    } else if x == 0 {\n        0\n    } else {\n        1\n    };\n"
+        );
+        assert_eq!(fmt(&out, 30), out);
+    }
+
+    #[test]
+    fn test_tokens_that_need_spacing() {
+        let source = "struct P { v: Vec<Vec<i64> > }\nfn f() -> P = new P { v: Vec::new() };";
+        let out = fmt(source, DEFAULT_WIDTH);
+        assert!(out.contains("Vec<Vec<i64> >"), "{}", out);
+        assert!(out.contains("new P { v: Vec::new() }"), "{}", out);
+        assert_eq!(fmt(&out, DEFAULT_WIDTH), out);
+    }
+
+    #[test]
+    fn test_raw_strings_stay_raw() {
+        let source = "fn f() -> String = r\"C:\\temp\";\nfn g() -> String = r##\"say \"hi\"\"##;\nfn h() -> String = r\"ab\";";
+        let out = fmt(source, DEFAULT_WIDTH);
+        assert!(out.contains("r\"C:\\temp\""), "{}", out);
+        assert!(out.contains("r#\"say \"hi\"\"#"), "{}", out);
+        assert!(out.contains("r\"ab\""), "{}", out);
+        assert_eq!(fmt(&out, DEFAULT_WIDTH), out);
+    }
+
+    #[test]
+    fn test_int_literals_keep_their_radix() {
+        let source = "fn f(x: i64) -> i64 =\n    match x band 0xFF_00 {\n        0b1010 => 0o755,\n        _ => 1_000_000,\n    };\n";
+        assert_eq!(fmt(source, DEFAULT_WIDTH), source);
+    }
+
+    #[test]
+    fn test_struct_field_defaults() {
+        let source = "struct Config {\n    depth: i64 = 3,\n    width: i64,\n    tries: i64 = 1 + 2,\n}\n";
+        assert_eq!(fmt(source, DEFAULT_WIDTH), source);
+        let cramped = "struct Config { depth: i64=3, width: i64 }";
+        assert_eq!(fmt(cramped, DEFAULT_WIDTH), "struct Config {\n    depth: i64 = 3,\n    width: i64,\n}\n");
+    }
+
+    #[test]
+    fn test_index_assign() {
+        let source = "fn f() -> i64 = {\n    let mut m = [[0]];\n    m[0][0] = 1 + 2;\n    m[0][0]\n};\n";
+        assert_eq!(fmt(source, DEFAULT_WIDTH), source);
+        assert_eq!(fmt("fn g() -> () = { let mut a = [1]; a[0]=2; };", DEFAULT_WIDTH), "fn g() -> () = {\n    let mut a = [1];\n    a[0] = 2\n};\n");
+    }
+
+    #[test]
+    fn test_field_assign() {
+        let source = "fn f(p: P) -> i64 = {\n    let mut q = p;\n    q.a.b = q.a.b + 1;\n    q.a.b\n};\n";
+        assert_eq!(fmt(source, DEFAULT_WIDTH), source);
+    }
+    #[test]
+    fn test_format_range_only_touches_items_inside() {
+        let source = "fn a()->i64=1;\n\n// doubles\nfn b(x:i64)->i64={\n  // keep\n  x*2 };\nfn c()->i64=3;\n";
+        let out = format_source("test.bmb", source, DEFAULT_WIDTH, Some(3..=6)).unwrap();
+        assert_eq!(out, "fn a()->i64=1;\n\n// doubles\nfn b(x: i64) -> i64 = {\n    // keep\n    x * 2\n};\nfn c()->i64=3;\n");
+
+        // `b` reaches past line 5, so nothing is formatted
+        let out = format_source("test.bmb", source, DEFAULT_WIDTH, Some(1..=5)).unwrap();
+        assert_eq!(out, source.replacen("fn a()->i64=1;", "fn a() -> i64 = 1;", 1));
+    }
+}
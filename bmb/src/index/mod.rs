@@ -3,8 +3,10 @@
 //! Generates index files for AI tools to query BMB projects.
 //! RFC-0001: AI-Native Code Query System
 
-use crate::ast::{self, Expr, FnDef, Item, Program, StateKind, Type, Visibility};
+use crate::ast::{self, Expr, FnDef, Item, LineIndex, Program, Span, Spanned, StateKind, Type, Visibility};
+use crate::lsp::references::collect_occurrences;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::path::Path;
 
 /// Index manifest containing metadata about the index
@@ -113,6 +115,17 @@ pub struct BodyInfo {
     pub calls: Vec<String>,
     pub recursive: bool,
     pub has_loop: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub todos: Vec<TodoSite>,
+}
+
+/// A `todo` placeholder left in a function body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoSite {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub line: usize,
+    pub span: Span,
 }
 
 /// Type entry for the index
@@ -147,6 +160,17 @@ pub struct RefinementInfo {
     pub constraint: String,
 }
 
+/// A use of a global name (function, type, trait or constant)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceEntry {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    /// Item the use is in; absent in `impl` blocks and `use` statements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
 /// The complete index
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectIndex {
@@ -154,6 +178,9 @@ pub struct ProjectIndex {
     pub symbols: Vec<SymbolEntry>,
     pub functions: Vec<FunctionEntry>,
     pub types: Vec<TypeEntry>,
+    /// Missing from indexes written before reference sites were recorded
+    #[serde(default)]
+    pub references: Vec<ReferenceEntry>,
 }
 
 // =============================================================================
@@ -259,26 +286,46 @@ pub fn read_proof_index(project_root: &Path) -> std::io::Result<ProofIndex> {
 /// Index generator
 pub struct IndexGenerator {
     project_name: String,
-    files_indexed: usize,
+    /// Every file indexed so far, whether or not it defined anything
+    files: BTreeSet<String>,
+    /// Text of the file being indexed, used to find reference sites
+    source: String,
+    /// Line index of `source`, used to turn spans into line numbers
+    lines: LineIndex,
     symbols: Vec<SymbolEntry>,
     functions: Vec<FunctionEntry>,
     types: Vec<TypeEntry>,
+    references: Vec<ReferenceEntry>,
 }
 
 impl IndexGenerator {
     pub fn new(project_name: &str) -> Self {
         Self {
             project_name: project_name.to_string(),
-            files_indexed: 0,
+            files: BTreeSet::new(),
+            source: String::new(),
+            lines: LineIndex::new(""),
             symbols: Vec::new(),
             functions: Vec::new(),
             types: Vec::new(),
+            references: Vec::new(),
         }
     }
 
-    /// Index a single file
+    /// Index a single file, resolving line numbers against its source text
+    pub fn index_source(&mut self, filename: &str, source: &str, program: &Program) {
+        self.source = source.to_string();
+        self.lines = LineIndex::new(source);
+        self.index_file(filename, program);
+        self.source.clear();
+        self.lines = LineIndex::new("");
+    }
+
+    /// Index a single file, replacing the entries of an earlier indexing of it
     pub fn index_file(&mut self, filename: &str, program: &Program) {
-        self.files_indexed += 1;
+        if !self.files.insert(filename.to_string()) {
+            self.drop_entries(filename);
+        }
 
         for item in &program.items {
             match item {
@@ -297,14 +344,59 @@ impl IndexGenerator {
                 Item::ExternFn(e) => {
                     self.index_extern_fn(filename, e);
                 }
+                Item::ConstDef(c) => {
+                    self.index_const(filename, c);
+                }
                 _ => {}
             }
         }
+        self.index_references(filename, program);
+    }
+
+    /// Forget a deleted file; returns false if it was never indexed
+    pub fn remove_file(&mut self, filename: &str) -> bool {
+        let known = self.files.remove(filename);
+        if known {
+            self.drop_entries(filename);
+        }
+        known
+    }
+
+    fn drop_entries(&mut self, filename: &str) {
+        self.symbols.retain(|s| s.file != filename);
+        self.functions.retain(|f| f.file != filename);
+        self.types.retain(|t| t.file != filename);
+        self.references.retain(|r| r.file != filename);
+    }
+
+    /// Record every use of a global name. Uses found by searching the source
+    /// text, such as call sites, need [`Self::index_source`].
+    fn index_references(&mut self, filename: &str, program: &Program) {
+        for occurrence in collect_occurrences(program, &self.source) {
+            if occurrence.is_definition || occurrence.local.is_some() {
+                continue;
+            }
+            let from = program
+                .items
+                .iter()
+                .find(|item| {
+                    let span = item.span();
+                    span.start <= occurrence.span.start && occurrence.span.end <= span.end
+                })
+                .and_then(Item::name)
+                .map(str::to_string);
+            self.references.push(ReferenceEntry {
+                name: occurrence.name,
+                file: filename.to_string(),
+                line: self.line_of(occurrence.span.start),
+                from,
+            });
+        }
     }
 
     fn index_function(&mut self, filename: &str, fn_def: &FnDef) {
         let is_pub = fn_def.visibility == Visibility::Public;
-        let line = 1; // Would need span info for accurate line numbers
+        let line = self.line_of(fn_def.span.start);
 
         // Create symbol entry
         let signature = self.format_fn_signature(fn_def);
@@ -329,7 +421,7 @@ impl IndexGenerator {
             .collect();
 
         let contracts = self.extract_contracts(fn_def);
-        let body_info = self.analyze_body(&fn_def.body.node, &fn_def.name.node);
+        let body_info = self.analyze_body(&fn_def.body, &fn_def.name.node);
 
         self.functions.push(FunctionEntry {
             name: fn_def.name.node.clone(),
@@ -347,7 +439,7 @@ impl IndexGenerator {
 
     fn index_struct(&mut self, filename: &str, s: &ast::StructDef) {
         let is_pub = s.visibility == Visibility::Public;
-        let line = 1;
+        let line = self.line_of(s.name.span.start);
 
         self.symbols.push(SymbolEntry {
             kind: SymbolKind::Struct,
@@ -382,7 +474,7 @@ impl IndexGenerator {
 
     fn index_enum(&mut self, filename: &str, e: &ast::EnumDef) {
         let is_pub = e.visibility == Visibility::Public;
-        let line = 1;
+        let line = self.line_of(e.name.span.start);
 
         self.symbols.push(SymbolEntry {
             kind: SymbolKind::Enum,
@@ -410,7 +502,7 @@ impl IndexGenerator {
 
     fn index_trait(&mut self, filename: &str, t: &ast::TraitDef) {
         let is_pub = t.visibility == Visibility::Public;
-        let line = 1;
+        let line = self.line_of(t.name.span.start);
 
         self.symbols.push(SymbolEntry {
             kind: SymbolKind::Trait,
@@ -436,7 +528,7 @@ impl IndexGenerator {
 
     fn index_extern_fn(&mut self, filename: &str, e: &ast::ExternFn) {
         let is_pub = e.visibility == Visibility::Public;
-        let line = 1;
+        let line = self.line_of(e.name.span.start);
 
         let params: Vec<String> = e
             .params
@@ -460,6 +552,25 @@ impl IndexGenerator {
         });
     }
 
+    fn index_const(&mut self, filename: &str, c: &ast::ConstDef) {
+        let signature = format!(
+            "const {}: {} = {}",
+            c.name.node,
+            self.format_type(&c.ty.node),
+            self.format_expr(&c.value.node)
+        );
+
+        self.symbols.push(SymbolEntry {
+            kind: SymbolKind::Const,
+            name: c.name.node.clone(),
+            file: filename.to_string(),
+            line: self.line_of(c.name.span.start),
+            is_pub: c.visibility == Visibility::Public,
+            signature: Some(signature),
+            doc: None,
+        });
+    }
+
     fn format_fn_signature(&self, fn_def: &FnDef) -> String {
         let params: Vec<String> = fn_def
             .params
@@ -495,6 +606,7 @@ impl IndexGenerator {
             Type::Struct { name, .. } => name.clone(),
             Type::Enum { name, .. } => name.clone(),
             Type::Array(elem, size) => format!("[{}; {}]", self.format_type(elem), size),
+            Type::ArrayConst(elem, name) => format!("[{}; {}]", self.format_type(elem), name),
             Type::Ref(inner) => format!("&{}", self.format_type(inner)),
             Type::RefMut(inner) => format!("&mut {}", self.format_type(inner)),
             Type::Range(elem) => format!("Range<{}>", self.format_type(elem)),
@@ -694,30 +806,60 @@ impl IndexGenerator {
                 self.collect_calls(&value.node, calls);
                 self.collect_calls(&body.node, calls);
             }
+            Expr::LetStmt { value, .. } => {
+                self.collect_calls(&value.node, calls);
+            }
+            Expr::Block(stmts) => {
+                for stmt in stmts {
+                    self.collect_calls(&stmt.node, calls);
+                }
+            }
             _ => {}
         }
     }
 
-    fn analyze_body(&self, expr: &Expr, fn_name: &str) -> Option<BodyInfo> {
+    fn collect_todos(&self, expr: &Spanned<Expr>, todos: &mut Vec<TodoSite>) {
+        if let Expr::Todo { message } = &expr.node {
+            todos.push(TodoSite {
+                message: message.clone(),
+                line: self.line_of(expr.span.start),
+                span: expr.span,
+            });
+        }
+        expr.node.for_each_child(&mut |child| self.collect_todos(child, todos));
+    }
+
+    fn analyze_body(&self, body: &Spanned<Expr>, fn_name: &str) -> Option<BodyInfo> {
+        let expr = &body.node;
         let mut calls = Vec::new();
         self.collect_calls(expr, &mut calls);
 
         let recursive = calls.contains(&fn_name.to_string());
         let has_loop = self.contains_loop(expr);
 
+        let mut todos = Vec::new();
+        self.collect_todos(body, &mut todos);
+
         Some(BodyInfo {
             calls,
             recursive,
             has_loop,
+            todos,
         })
     }
 
+    /// 1-based line of a byte offset, or 1 when the source is unknown
+    fn line_of(&self, offset: usize) -> usize {
+        self.lines.line_col(offset).line
+    }
+
     fn contains_loop(&self, expr: &Expr) -> bool {
         match expr {
             Expr::While { .. } | Expr::For { .. } => true,
             Expr::Let { value, body, .. } => {
                 self.contains_loop(&value.node) || self.contains_loop(&body.node)
             }
+            Expr::LetStmt { value, .. } => self.contains_loop(&value.node),
             Expr::If { cond, then_branch, else_branch } => {
                 self.contains_loop(&cond.node)
                     || self.contains_loop(&then_branch.node)
@@ -730,15 +872,36 @@ impl IndexGenerator {
 
     /// Generate the final index
     pub fn generate(self) -> ProjectIndex {
+        ProjectIndex {
+            manifest: self.manifest(),
+            symbols: self.symbols,
+            functions: self.functions,
+            types: self.types,
+            references: self.references,
+        }
+    }
+
+    /// The index as of now, leaving the generator open to further updates
+    pub fn snapshot(&self) -> ProjectIndex {
+        ProjectIndex {
+            manifest: self.manifest(),
+            symbols: self.symbols.clone(),
+            functions: self.functions.clone(),
+            types: self.types.clone(),
+            references: self.references.clone(),
+        }
+    }
+
+    fn manifest(&self) -> Manifest {
         let now = chrono::Utc::now();
         let indexed_at = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
 
-        let manifest = Manifest {
+        Manifest {
             version: "1".to_string(),
             bmb_version: env!("CARGO_PKG_VERSION").to_string(),
-            project: self.project_name,
+            project: self.project_name.clone(),
             indexed_at,
-            files: self.files_indexed,
+            files: self.files.len(),
             functions: self.functions.len(),
             types: self.types.len(),
             structs: self.types.iter().filter(|t| t.kind == "struct").count(),
@@ -748,13 +911,6 @@ impl IndexGenerator {
                 .iter()
                 .filter(|f| f.contracts.is_some())
                 .count(),
-        };
-
-        ProjectIndex {
-            manifest,
-            symbols: self.symbols,
-            functions: self.functions,
-            types: self.types,
         }
     }
 }
@@ -784,6 +940,11 @@ pub fn write_index(index: &ProjectIndex, project_root: &Path) -> std::io::Result
     let types_json = serde_json::to_string_pretty(&index.types)?;
     std::fs::write(&types_path, types_json)?;
 
+    // Write reference sites
+    let references_path = index_dir.join("references.json");
+    let references_json = serde_json::to_string_pretty(&index.references)?;
+    std::fs::write(&references_path, references_json)?;
+
     Ok(())
 }
 
@@ -807,11 +968,20 @@ pub fn read_index(project_root: &Path) -> std::io::Result<ProjectIndex> {
     let types_json = std::fs::read_to_string(&types_path)?;
     let types: Vec<TypeEntry> = serde_json::from_str(&types_json)?;
 
+    // Older indexes have no reference sites
+    let references_path = index_dir.join("references.json");
+    let references: Vec<ReferenceEntry> = match std::fs::read_to_string(&references_path) {
+        Ok(references_json) => serde_json::from_str(&references_json)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
     Ok(ProjectIndex {
         manifest,
         symbols,
         functions,
         types,
+        references,
     })
 }
 
@@ -822,6 +992,73 @@ mod tests {
     #[test]
     fn test_index_generator() {
         let generator = IndexGenerator::new("test-project");
-        assert_eq!(generator.files_indexed, 0);
+        assert!(generator.files.is_empty());
+    }
+
+    #[test]
+    fn test_index_records_todo_sites() {
+        let source = "fn done() -> i64 = 1;\n\nfn open(x: i64) -> i64 =\n    if x > 0 { todo \"positive\" } else { todo };\n";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("t.bmb", source, tokens).unwrap();
+
+        let mut generator = IndexGenerator::new("test-project");
+        generator.index_source("t.bmb", source, &program);
+        let index = generator.generate();
+
+        let done = &index.functions[0];
+        assert!(done.body_info.as_ref().unwrap().todos.is_empty());
+
+        let open = &index.functions[1];
+        assert_eq!(open.line, 3);
+        let todos = &open.body_info.as_ref().unwrap().todos;
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].message.as_deref(), Some("positive"));
+        assert_eq!(todos[0].line, 4);
+        assert_eq!(&source[todos[0].span.start..todos[0].span.end], "todo \"positive\"");
+        assert_eq!(todos[1].message, None);
+    }
+
+    fn parse(source: &str) -> Program {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        crate::parser::parse("t.bmb", source, tokens).unwrap()
+    }
+
+    fn entries_of(index: &ProjectIndex, file: &str) -> serde_json::Value {
+        serde_json::json!({
+            "symbols": index.symbols.iter().filter(|s| s.file == file).collect::<Vec<_>>(),
+            "functions": index.functions.iter().filter(|f| f.file == file).collect::<Vec<_>>(),
+            "types": index.types.iter().filter(|t| t.file == file).collect::<Vec<_>>(),
+            "references": index.references.iter().filter(|r| r.file == file).collect::<Vec<_>>(),
+        })
+    }
+
+    #[test]
+    fn test_reindexing_a_file_replaces_only_its_entries() {
+        let a = "fn helper(x: i64) -> i64 = x + 1;\nstruct Point { x: i64, y: i64 }\n";
+        let b = "fn main() -> i64 = 0;\nfn other() -> i64 pre true = 1;\n";
+        let mut generator = IndexGenerator::new("test-project");
+        generator.index_source("a.bmb", a, &parse(a));
+        generator.index_source("b.bmb", b, &parse(b));
+        let before = generator.snapshot();
+        assert_eq!((before.manifest.files, before.manifest.functions, before.manifest.types), (2, 3, 1));
+
+        let a2 = "fn helper2(x: i64, y: i64) -> i64 post ret > 0 = x + y;\n";
+        generator.index_source("a.bmb", a2, &parse(a2));
+        let after = generator.snapshot();
+
+        assert_eq!(entries_of(&before, "b.bmb"), entries_of(&after, "b.bmb"));
+        assert_ne!(entries_of(&before, "a.bmb"), entries_of(&after, "a.bmb"));
+        let names: Vec<_> = after.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["main", "other", "helper2"]);
+        assert!(after.types.is_empty());
+        assert_eq!(after.manifest.files, 2);
+        assert_eq!(after.manifest.functions, 3);
+        assert_eq!(after.manifest.contracts, 2);
+
+        assert!(generator.remove_file("b.bmb"));
+        assert!(!generator.remove_file("b.bmb"));
+        let index = generator.generate();
+        assert_eq!((index.manifest.files, index.manifest.functions, index.manifest.contracts), (1, 1, 1));
+        assert!(index.symbols.iter().all(|s| s.file == "a.bmb"));
     }
 }
@@ -0,0 +1,131 @@
+//! Line coverage from the spans the interpreter evaluated
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::ast::{Item, Program, Span};
+use crate::lexer::{tokenize, Token};
+
+/// Line coverage of one source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineCoverage {
+    /// Executable lines (1-based)
+    pub lines: Vec<usize>,
+    /// Executable lines on which no evaluated expression starts
+    pub uncovered: Vec<usize>,
+}
+
+impl LineCoverage {
+    pub fn covered(&self) -> usize {
+        self.lines.len() - self.uncovered.len()
+    }
+
+    /// Percentage of executable lines covered; 100 when there are none
+    pub fn percent(&self) -> f64 {
+        if self.lines.is_empty() {
+            100.0
+        } else {
+            self.covered() as f64 * 100.0 / self.lines.len() as f64
+        }
+    }
+}
+
+/// Line coverage of the function and method bodies in `program`, excluding
+/// the `test_` functions themselves.
+///
+/// A line is executable when it lies in a body and holds a token other than
+/// closing punctuation (`}`, `};`, `} else {`, ...). It is covered when an
+/// expression starting on it was evaluated.
+pub fn line_coverage(program: &Program, source: &str, executed: &HashSet<Span>) -> LineCoverage {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+
+    let mut bodies = Vec::new();
+    for item in &program.items {
+        match item {
+            Item::FnDef(f) if !f.name.node.starts_with("test_") => bodies.push(f.body.span),
+            Item::ImplBlock(i) => bodies.extend(i.methods.iter().map(|m| m.body.span)),
+            Item::TraitDef(t) => bodies.extend(t.methods.iter().filter_map(|m| m.body.as_ref().map(|b| b.span))),
+            _ => {}
+        }
+    }
+    let in_body = |offset: usize| bodies.iter().any(|b| b.start <= offset && offset < b.end);
+
+    let lines: BTreeSet<usize> = tokenize(source)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(token, span)| !is_closing_punctuation(token) && in_body(span.start))
+        .map(|(_, span)| line_of(span.start))
+        .collect();
+    let covered: HashSet<usize> = executed.iter().map(|span| line_of(span.start)).collect();
+
+    LineCoverage {
+        uncovered: lines.iter().copied().filter(|line| !covered.contains(line)).collect(),
+        lines: lines.into_iter().collect(),
+    }
+}
+
+fn is_closing_punctuation(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::LBrace | Token::RBrace | Token::RParen | Token::RBracket | Token::Semi | Token::Comma | Token::Else
+    )
+}
+
+/// Collapse sorted line numbers into ranges: `[3, 4, 5, 9]` -> `"3-5, 9"`
+pub fn format_line_ranges(lines: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interp::Interpreter;
+    use crate::parser::parse;
+
+    fn coverage(source: &str, test: &str) -> LineCoverage {
+        let ast = parse("test.bmb", source, tokenize(source).unwrap()).unwrap();
+        let mut interp = Interpreter::new();
+        interp.load(&ast);
+        interp.enable_coverage();
+        interp.run_function(test).unwrap();
+        line_coverage(&ast, source, interp.covered_spans().unwrap())
+    }
+
+    #[test]
+    fn test_untaken_branch_uncovered() {
+        let source = "fn sign(x: i64) -> i64 =\n    if x < 0 {\n        0 - 1\n    } else {\n        1\n    };\n\nfn test_pos() -> bool = sign(5) == 1;\n";
+        let cov = coverage(source, "test_pos");
+        // Lines 2, 3 and 5 are executable; `} else {` and `};` are not
+        assert_eq!(cov.lines, vec![2, 3, 5]);
+        assert_eq!(cov.uncovered, vec![3]);
+        assert_eq!(cov.covered(), 2);
+    }
+
+    #[test]
+    fn test_uncalled_function_uncovered() {
+        let source = "fn used() -> i64 = 1;\nfn unused() -> i64 = 2;\nfn test_used() -> bool = used() == 1;\n";
+        let cov = coverage(source, "test_used");
+        assert_eq!(cov.lines, vec![1, 2]);
+        assert_eq!(cov.uncovered, vec![2]);
+        assert_eq!(cov.percent(), 50.0);
+    }
+
+    #[test]
+    fn test_format_line_ranges() {
+        assert_eq!(format_line_ranges(&[3, 4, 5, 9, 11, 12]), "3-5, 9, 11-12");
+        assert_eq!(format_line_ranges(&[]), "");
+    }
+}
@@ -1,5 +1,6 @@
 //! Runtime errors for the interpreter
 
+use crate::ast::Span;
 use std::fmt;
 
 /// Runtime error during interpretation
@@ -7,8 +8,25 @@ use std::fmt;
 pub struct RuntimeError {
     pub kind: ErrorKind,
     pub message: String,
+    /// Source location of the failing operation, when known
+    pub span: Option<Span>,
+    /// Rendered `(left, right)` operands of a failed `assert_eq`/`assert_ne`
+    pub operands: Option<(String, String)>,
+    /// Interpreter call stack when the error was raised, innermost first
+    pub frames: Box<[StackFrame]>,
 }
 
+/// A function on the interpreter call stack
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    pub function: String,
+    /// Span of the call expression in the caller; `None` for entry points
+    pub call_site: Option<Span>,
+}
+
+/// Frames shown in a stack trace; deeper ones are only counted
+pub const MAX_TRACE_FRAMES: usize = 20;
+
 /// Kinds of runtime errors
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
@@ -34,6 +52,15 @@ pub enum ErrorKind {
     IndexOutOfBounds,
     /// v0.31: Todo placeholder reached at runtime
     TodoNotImplemented,
+    /// Integer arithmetic overflow in an unqualified `+`, `-`, `*` or negation
+    ArithmeticOverflow,
+    /// `break`/`continue` unwinding to its target loop; only surfaces when
+    /// no enclosing loop matches
+    LoopControl,
+    /// `as char` on an integer that is not a Unicode scalar value
+    InvalidChar,
+    /// `match` where no arm's pattern and guard accept the value
+    NoMatchingArm,
 }
 
 impl RuntimeError {
@@ -41,6 +68,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::UndefinedVariable,
             message: format!("undefined variable: {name}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -48,6 +78,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::UndefinedFunction,
             message: format!("undefined function: {name}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -55,6 +88,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::TypeError,
             message: format!("type error: expected {expected}, got {got}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -62,6 +98,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::DivisionByZero,
             message: "division by zero".to_string(),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -71,6 +110,25 @@ impl RuntimeError {
             message: msg
                 .map(|m| format!("assertion failed: {m}"))
                 .unwrap_or_else(|| "assertion failed".to_string()),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `assert_eq`/`assert_ne` failure carrying both rendered operands
+    pub fn assertion_mismatch(left: String, right: String, expect_equal: bool) -> Self {
+        let message = if expect_equal {
+            format!("assertion failed: left = {left}, right = {right}")
+        } else {
+            format!("assertion failed: left = {left}, right = {right} (expected values to differ)")
+        };
+        RuntimeError {
+            kind: ErrorKind::AssertionFailed,
+            message,
+            span: None,
+            operands: Some((left, right)),
+            frames: Box::default(),
         }
     }
 
@@ -80,6 +138,9 @@ impl RuntimeError {
             message: format!(
                 "function {name} expects {expected} argument(s), got {got}"
             ),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -87,6 +148,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::PreConditionFailed,
             message: format!("pre-condition failed for function: {func}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -94,6 +158,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::StackOverflow,
             message: "stack overflow: too deep recursion".to_string(),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -101,6 +168,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::IoError,
             message: format!("IO error: {msg}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 
@@ -108,7 +178,135 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::IndexOutOfBounds,
             message: format!("index {} out of bounds for length {}", index, len),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `arr[i]` past the end; `container` is "array" or "string" (byte length)
+    pub fn index_out_of_bounds_in(container: &str, index: i64, len: usize) -> Self {
+        RuntimeError {
+            kind: ErrorKind::IndexOutOfBounds,
+            message: format!("index {} out of bounds for {} of length {}", index, container, len),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `arr[i]` with a negative `i`
+    pub fn negative_index(container: &str, index: i64) -> Self {
+        RuntimeError {
+            kind: ErrorKind::IndexOutOfBounds,
+            message: format!("negative {} index: {}", container, index),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// Integer overflow in default arithmetic; `expr` is the operation with
+    /// its operand values, e.g. "9223372036854775807 + 1"
+    pub fn arithmetic_overflow(expr: &str) -> Self {
+        RuntimeError {
+            kind: ErrorKind::ArithmeticOverflow,
+            message: format!("integer overflow in `{expr}`"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `break`, `continue` or `return` leaving its body; the enclosing loop
+    /// or function catches it
+    pub fn loop_control(keyword: &str) -> Self {
+        let target = if keyword == "return" { "function" } else { "matching loop" };
+        RuntimeError {
+            kind: ErrorKind::LoopControl,
+            message: format!("`{keyword}` outside of a {target}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `code as char` where `code` is not a Unicode scalar value
+    pub fn invalid_char(code: i64) -> Self {
+        RuntimeError {
+            kind: ErrorKind::InvalidChar,
+            message: format!("cannot cast {code} to char: not a Unicode scalar value"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// `match` on `value` where every arm's pattern or guard failed
+    pub fn no_matching_arm(value: &impl fmt::Display) -> Self {
+        RuntimeError {
+            kind: ErrorKind::NoMatchingArm,
+            message: format!("no match arm matches {value}: every pattern or guard failed"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
+        }
+    }
+
+    /// Stable diagnostic code (E07xx) for machine output
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::UndefinedVariable => "E0701",
+            ErrorKind::UndefinedFunction => "E0702",
+            ErrorKind::TypeError => "E0703",
+            ErrorKind::DivisionByZero => "E0704",
+            ErrorKind::AssertionFailed => "E0705",
+            ErrorKind::ArityMismatch => "E0706",
+            ErrorKind::PreConditionFailed => "E0707",
+            ErrorKind::StackOverflow => "E0708",
+            ErrorKind::IoError => "E0709",
+            ErrorKind::IndexOutOfBounds => "E0710",
+            ErrorKind::TodoNotImplemented => "E0711",
+            ErrorKind::ArithmeticOverflow => "E0712",
+            ErrorKind::LoopControl => "E0713",
+            ErrorKind::InvalidChar => "E0714",
+            ErrorKind::NoMatchingArm => "E0715",
+        }
+    }
+
+    /// Structured diagnostic for machine output, same shape as compile errors
+    pub fn to_diagnostic(&self, filename: &str, source: &str) -> crate::error::Diagnostic {
+        let index = crate::ast::LineIndex::new(source);
+        let mut diagnostic =
+            crate::error::Diagnostic::at(crate::error::Severity::Error, self.message.as_str(), &index, self.span)
+                .with_code(self.code())
+                .with_file(filename);
+        // Each frame is positioned where its function was executing: the
+        // innermost at the error itself, the others at the call they made
+        let locations = std::iter::once(self.span).chain(self.frames.iter().map(|f| f.call_site));
+        diagnostic.frames = self.frames.iter()
+            .zip(locations)
+            .take(MAX_TRACE_FRAMES)
+            .map(|(frame, span)| crate::error::DiagnosticFrame {
+                function: frame.function.clone(),
+                span: span.map(|s| crate::error::DiagnosticSpan::resolve(&index, s)),
+            })
+            .collect();
+        diagnostic.frames_omitted = self.frames.len().saturating_sub(MAX_TRACE_FRAMES);
+        diagnostic
+    }
+
+    /// [`Self::to_diagnostic`] as a JSON value
+    pub fn to_json(&self, filename: &str, source: &str) -> serde_json::Value {
+        self.to_diagnostic(filename, source).to_json()
+    }
+
+    /// Attach a source span, keeping an existing (more precise) one
+    pub fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
         }
+        self
     }
 
     /// v0.31: Todo placeholder reached at runtime
@@ -116,6 +314,9 @@ impl RuntimeError {
         RuntimeError {
             kind: ErrorKind::TodoNotImplemented,
             message: format!("todo: {msg}"),
+            span: None,
+            operands: None,
+            frames: Box::default(),
         }
     }
 }
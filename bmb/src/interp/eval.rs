@@ -1,12 +1,16 @@
 //! Expression evaluator
 
 use super::env::{child_env, EnvRef, Environment};
-use super::error::{InterpResult, RuntimeError};
+use super::error::{ErrorKind, InterpResult, RuntimeError, StackFrame};
 use super::scope::ScopeStack;
-use super::value::Value;
-use crate::ast::{BinOp, EnumDef, Expr, FnDef, LiteralPattern, Pattern, Program, Spanned, StructDef, Type, UnOp};
+use super::value::{Closure, MapKey, Value};
+use crate::ast::{
+    BinOp, EnumDef, Expr, FnDef, LiteralPattern, Pattern, Program, Span, Spanned, StructDef, TraitDef, Type, UnOp,
+    Visibility,
+};
+use crate::types::consts::ConstValue;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::io::{self, BufRead, Write};
@@ -21,6 +25,36 @@ thread_local! {
     static PROGRAM_ARGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
 }
 
+// Buffered stdout behind the print builtins. Writing through the
+// line-buffered `io::stdout()` costs a syscall per line; this buffer is
+// flushed when an interpreter entry point returns, before stdin is read or
+// a subprocess is spawned, and by the `flush` builtin.
+thread_local! {
+    static STDOUT_BUF: RefCell<io::BufWriter<io::Stdout>> = RefCell::new(io::BufWriter::new(io::stdout()));
+}
+
+/// Write program output into the buffered stdout
+fn write_stdout(args: std::fmt::Arguments) -> InterpResult<()> {
+    STDOUT_BUF
+        .with(|out| out.borrow_mut().write_fmt(args))
+        .map_err(|e| RuntimeError::io_error(&e.to_string()))
+}
+
+/// Flush buffered program output to the real stdout
+fn flush_stdout() -> InterpResult<()> {
+    STDOUT_BUF
+        .with(|out| out.borrow_mut().flush())
+        .map_err(|e| RuntimeError::io_error(&e.to_string()))
+}
+
+/// Flush program output once control returns to the host, so it lands
+/// before anything the caller prints next
+fn finish_output(result: InterpResult<Value>) -> InterpResult<Value> {
+    let flushed = flush_stdout();
+    let value = result?;
+    flushed.map(|()| value)
+}
+
 /// v0.46: Set program arguments for the interpreter
 /// Called before running a BMB program to pass command-line arguments
 pub fn set_program_args(args: Vec<String>) {
@@ -49,6 +83,32 @@ const MAX_RECURSION_DEPTH: usize = 100000;
 const STACK_RED_ZONE: usize = 128 * 1024; // 128KB remaining triggers growth
 const STACK_GROW_SIZE: usize = 4 * 1024 * 1024; // Grow by 4MB each time
 
+/// Result of evaluating a function body expression in tail position
+enum TailEval {
+    /// The expression produced a value
+    Value(Value),
+    /// The expression is a self-recursive call: re-enter the current frame
+    /// with these arguments instead of growing the stack
+    SelfCall(Vec<Value>),
+}
+
+/// A `break`, `continue` or `return` in flight. It unwinds as a
+/// `LoopControl` error while the payload waits here for the loop or
+/// function it targets.
+enum LoopSignal {
+    Break { label: Option<String>, value: Value },
+    Continue { label: Option<String> },
+    Return { value: Value },
+}
+
+/// What a loop does after one evaluation of its body
+enum LoopFlow {
+    /// Go on with the next iteration
+    Next,
+    /// Leave the loop with this value
+    Exit(Value),
+}
+
 /// Builtin function type
 pub type BuiltinFn = fn(&[Value]) -> InterpResult<Value>;
 
@@ -62,16 +122,37 @@ pub struct Interpreter {
     struct_defs: HashMap<String, StructDef>,
     /// Enum definitions
     enum_defs: HashMap<String, EnumDef>,
+    /// Trait methods by (implementing type, method name), including inherited defaults
+    impl_methods: HashMap<(String, String), FnDef>,
     /// Builtin functions
     builtins: HashMap<String, BuiltinFn>,
     /// Current recursion depth
     recursion_depth: usize,
+    /// User function calls made so far, including self tail calls
+    calls: u64,
     /// v0.30.280: Stack-based scope for efficient let binding evaluation
     scope_stack: ScopeStack,
     /// v0.30.280: Flag to enable ScopeStack-based evaluation
     use_scope_stack: bool,
     /// v0.35.1: String intern table for O(1) literal reuse (json_parse optimization)
     string_intern: HashMap<String, Rc<String>>,
+    /// Detect overflow in unqualified `+`, `-`, `*` and negation (debug semantics)
+    overflow_checks: bool,
+    /// Width of the `+`, `-` and `*` the type checker found on `i32`, `u32`
+    /// or `u64` operands, by span; all other integer arithmetic is `i64`
+    int_op_types: HashMap<Span, Type>,
+    /// Spans of evaluated expressions, recorded when coverage is enabled
+    coverage: Option<HashSet<Span>>,
+    /// Values of `const` items, visible wherever no local shadows them
+    consts: HashMap<String, Value>,
+    /// Pending `break`/`continue` while it unwinds to its loop
+    loop_signal: Option<LoopSignal>,
+    /// Functions currently executing, outermost first
+    call_stack: Vec<StackFrame>,
+    /// Span of the call being dispatched, taken by the callee's frame
+    call_site: Option<Span>,
+    /// `call_stack` as it was when the error now unwinding was raised
+    error_trace: Option<Vec<StackFrame>>,
 }
 
 impl Interpreter {
@@ -82,11 +163,21 @@ impl Interpreter {
             functions: HashMap::new(),
             struct_defs: HashMap::new(),
             enum_defs: HashMap::new(),
+            impl_methods: HashMap::new(),
             builtins: HashMap::new(),
             recursion_depth: 0,
+            calls: 0,
             scope_stack: ScopeStack::new(),
             use_scope_stack: false,
             string_intern: HashMap::new(),
+            overflow_checks: true,
+            int_op_types: HashMap::new(),
+            coverage: None,
+            consts: HashMap::new(),
+            loop_signal: None,
+            call_stack: Vec::new(),
+            call_site: None,
+            error_trace: None,
         };
         interp.register_builtins();
         interp
@@ -110,7 +201,10 @@ impl Interpreter {
         self.builtins.insert("println".to_string(), builtin_println);
         self.builtins.insert("print_str".to_string(), builtin_print_str);
         self.builtins.insert("println_str".to_string(), builtin_println_str);
+        self.builtins.insert("flush".to_string(), builtin_flush);
         self.builtins.insert("assert".to_string(), builtin_assert);
+        self.builtins.insert("assert_eq".to_string(), builtin_assert_eq);
+        self.builtins.insert("assert_ne".to_string(), builtin_assert_ne);
         self.builtins.insert("read_int".to_string(), builtin_read_int);
         self.builtins.insert("abs".to_string(), builtin_abs);
         self.builtins.insert("min".to_string(), builtin_min);
@@ -147,11 +241,29 @@ impl Interpreter {
         self.builtins.insert("char_at".to_string(), builtin_char_at);
         self.builtins
             .insert("char_to_string".to_string(), builtin_char_to_string);
+        self.builtins.insert("int_to_string".to_string(), builtin_int_to_string);
         // v0.67: String utilities
         self.builtins.insert("str_len".to_string(), builtin_str_len);
+        self.builtins.insert("str_contains".to_string(), builtin_str_contains);
+        self.builtins.insert("str_starts_with".to_string(), builtin_str_starts_with);
+        self.builtins.insert("str_ends_with".to_string(), builtin_str_ends_with);
+        self.builtins.insert("str_index_of".to_string(), builtin_str_index_of);
+        self.builtins.insert("str_replace".to_string(), builtin_str_replace);
+        self.builtins.insert("str_trim".to_string(), builtin_str_trim);
+        self.builtins.insert("parse_int".to_string(), builtin_parse_int);
+        self.builtins.insert("str_split".to_string(), builtin_str_split);
+        self.builtins.insert("str_from_id".to_string(), builtin_str_from_id);
 
         // v0.34: Math intrinsics for Phase 34.4 Benchmark Gate (n_body, mandelbrot_fp)
         self.builtins.insert("sqrt".to_string(), builtin_sqrt);
+        self.builtins.insert("sin".to_string(), builtin_sin);
+        self.builtins.insert("cos".to_string(), builtin_cos);
+        self.builtins.insert("pow".to_string(), builtin_pow);
+        self.builtins.insert("floor".to_string(), builtin_floor);
+        self.builtins.insert("ceil".to_string(), builtin_ceil);
+        self.builtins.insert("round".to_string(), builtin_round);
+        self.builtins.insert("ln".to_string(), builtin_ln);
+        self.builtins.insert("exp".to_string(), builtin_exp);
         self.builtins.insert("i64_to_f64".to_string(), builtin_i64_to_f64);
         self.builtins.insert("f64_to_i64".to_string(), builtin_f64_to_i64);
 
@@ -179,6 +291,8 @@ impl Interpreter {
         self.builtins.insert("vec_cap".to_string(), builtin_vec_cap);
         self.builtins.insert("vec_free".to_string(), builtin_vec_free);
         self.builtins.insert("vec_clear".to_string(), builtin_vec_clear);
+        self.builtins.insert("vec_push_str".to_string(), builtin_vec_push_str);
+        self.builtins.insert("vec_get_str".to_string(), builtin_vec_get_str);
 
         // v0.34.24: Hash builtins
         self.builtins.insert("hash_i64".to_string(), builtin_hash_i64);
@@ -197,6 +311,16 @@ impl Interpreter {
             .insert("hashmap_len".to_string(), builtin_hashmap_len);
         self.builtins
             .insert("hashmap_free".to_string(), builtin_hashmap_free);
+        self.builtins
+            .insert("hashmap_insert_str".to_string(), builtin_hashmap_insert_str);
+        self.builtins
+            .insert("hashmap_get_str".to_string(), builtin_hashmap_get_str);
+        self.builtins
+            .insert("hashmap_insert_str_key".to_string(), builtin_hashmap_insert_str_key);
+        self.builtins
+            .insert("hashmap_get_str_key".to_string(), builtin_hashmap_get_str_key);
+        self.builtins
+            .insert("hashmap_contains_str_key".to_string(), builtin_hashmap_contains_str_key);
 
         // v0.34.24: HashSet builtins
         self.builtins
@@ -224,6 +348,35 @@ impl Interpreter {
         self.use_scope_stack = false;
     }
 
+    /// Enable or disable overflow detection for default integer arithmetic.
+    /// When disabled, `+`, `-`, `*` and negation wrap like `+%`, `-%`, `*%`.
+    pub fn set_overflow_checks(&mut self, enabled: bool) {
+        self.overflow_checks = enabled;
+    }
+
+    /// Check and wrap `+`, `-` and `*` at the widths recorded by
+    /// `TypeChecker::int_op_types` for the program about to run, so `u32`
+    /// and `u64` arithmetic overflows at their own bounds
+    pub fn set_int_op_types(&mut self, types: HashMap<Span, Type>) {
+        self.int_op_types = types;
+    }
+
+    /// Start recording the span of every evaluated expression
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(HashSet::new());
+    }
+
+    /// Spans evaluated since coverage was enabled
+    pub fn covered_spans(&self) -> Option<&HashSet<Span>> {
+        self.coverage.as_ref()
+    }
+
+    fn record_coverage(&mut self, span: Span) {
+        if let Some(spans) = &mut self.coverage {
+            spans.insert(span);
+        }
+    }
+
     /// Load a program (register functions, structs, enums)
     pub fn load(&mut self, program: &Program) {
         for item in &program.items {
@@ -244,21 +397,128 @@ impl Interpreter {
                 crate::ast::Item::Use(_) => {}
                 // v0.13.0: Extern functions are handled at compile time (FFI)
                 crate::ast::Item::ExternFn(_) => {}
-                // v0.20.1: Trait system not yet supported in interpreter
+                // v0.20.1: Trait methods are registered below, once all traits are known
                 crate::ast::Item::TraitDef(_) => {}
                 crate::ast::Item::ImplBlock(_) => {}
                 // v0.50.6: Type aliases are resolved at compile time
                 crate::ast::Item::TypeAlias(_) => {}
+                // Constants are evaluated together below, in dependency order
+                crate::ast::Item::ConstDef(_) => {}
+            }
+        }
+        self.load_impls(program);
+        self.load_consts(program);
+    }
+
+    /// Evaluate `const` items. Invalid initializers are rejected by the type
+    /// checker; if one slips through, its constant stays undefined.
+    fn load_consts(&mut self, program: &Program) {
+        let Ok(values) = crate::types::consts::eval_consts(program) else {
+            return;
+        };
+        for (name, value) in values {
+            let value = match value {
+                ConstValue::Int(n) => Value::Int(n),
+                ConstValue::Float(f) => Value::Float(f),
+                ConstValue::Bool(b) => Value::Bool(b),
+                ConstValue::Char(c) => Value::Char(c),
+                ConstValue::Str(s) => Value::Str(self.intern_string(&s)),
+            };
+            self.consts.insert(name, value);
+        }
+    }
+
+    /// Register impl methods, filling in trait default bodies the impl omits.
+    /// Inherent methods win over trait methods of the same name.
+    fn load_impls(&mut self, program: &Program) {
+        let traits: HashMap<&str, &TraitDef> = program
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                crate::ast::Item::TraitDef(t) => Some((t.name.node.as_str(), t)),
+                _ => None,
+            })
+            .collect();
+        let mut inherent = std::collections::HashSet::new();
+
+        // `@derive(Eq)` and `@derive(Show)` methods come as inherent impls
+        let derived = crate::derive::derived_impls(program);
+        let impl_blocks = program
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                crate::ast::Item::ImplBlock(impl_block) => Some(impl_block),
+                _ => None,
+            })
+            .chain(&derived);
+        for impl_block in impl_blocks {
+            let type_name = match &impl_block.target_type.node {
+                Type::Generic { name, .. } => name.clone(),
+                ty => crate::ast::output::format_type(ty),
+            };
+            let Some(trait_name) = &impl_block.trait_name else {
+                for method in &impl_block.methods {
+                    let key = (type_name.clone(), method.name.node.clone());
+                    inherent.insert(key.clone());
+                    self.impl_methods.insert(key, method.clone());
+                }
+                continue;
+            };
+            for method in &impl_block.methods {
+                let key = (type_name.clone(), method.name.node.clone());
+                if !inherent.contains(&key) {
+                    self.impl_methods.insert(key, method.clone());
+                }
             }
+
+            let Some(trait_def) = traits.get(trait_name.node.as_str()) else {
+                continue;
+            };
+            for m in &trait_def.methods {
+                let Some(body) = &m.body else { continue };
+                // Methods defined in the impl block take priority over defaults
+                let key = (type_name.clone(), m.name.node.clone());
+                self.impl_methods.entry(key).or_insert_with(|| FnDef {
+                    attributes: vec![],
+                    visibility: Visibility::Private,
+                    name: m.name.clone(),
+                    type_params: vec![],
+                    params: m.params.clone(),
+                    ret_name: None,
+                    ret_ty: m.ret_ty.clone(),
+                    pre: None,
+                    post: None,
+                    contracts: vec![],
+                    body: body.clone(),
+                    span: m.span,
+                });
+            }
+        }
+    }
+
+    /// Find a user-defined trait method for the receiver's runtime type
+    fn lookup_impl_method(&self, receiver: &Value, method: &str) -> Option<FnDef> {
+        if self.impl_methods.is_empty() {
+            return None;
         }
+        let type_name = match receiver {
+            Value::Int(_) => "i64",
+            Value::Float(_) => "f64",
+            Value::Str(_) | Value::StringRope(_) => "String",
+            other => other.type_name(),
+        };
+        self.impl_methods
+            .get(&(type_name.to_string(), method.to_string()))
+            .cloned()
     }
 
     /// Run a program (find and call main)
     pub fn run(&mut self, program: &Program) -> InterpResult<Value> {
         self.load(program);
+        self.reset_call_stack();
 
         // Look for a main function or evaluate the last function
-        if let Some(main_fn) = self.functions.get("main").cloned() {
+        let result = if let Some(main_fn) = self.functions.get("main").cloned() {
             self.call_function(&main_fn, &[])
         } else if let Some(last_item) = program.items.last() {
             match last_item {
@@ -279,15 +539,64 @@ impl Interpreter {
                 crate::ast::Item::TraitDef(_) | crate::ast::Item::ImplBlock(_) => Ok(Value::Unit),
                 // v0.50.6: Type aliases don't produce values
                 crate::ast::Item::TypeAlias(_) => Ok(Value::Unit),
+                crate::ast::Item::ConstDef(_) => Ok(Value::Unit),
             }
         } else {
             Ok(Value::Unit)
-        }
+        };
+        finish_output(self.attach_trace(result))
     }
 
     /// Evaluate a single expression (for REPL)
     pub fn eval_expr(&mut self, expr: &Spanned<Expr>) -> InterpResult<Value> {
-        self.eval(expr, &self.global_env.clone())
+        self.reset_call_stack();
+        let result = self.eval(expr, &self.global_env.clone());
+        finish_output(self.attach_trace(result))
+    }
+
+    /// Forget call tracking left over from an earlier entry into the program
+    fn reset_call_stack(&mut self) {
+        self.call_stack.clear();
+        self.call_site = None;
+        self.error_trace = None;
+    }
+
+    /// Give an error leaving the interpreter the stack trace captured when
+    /// it was raised
+    fn attach_trace(&mut self, result: InterpResult<Value>) -> InterpResult<Value> {
+        let trace = self.error_trace.take();
+        result.map_err(|mut err| {
+            if let Some(mut frames) = trace {
+                frames.reverse();
+                err.frames = frames.into_boxed_slice();
+            }
+            err
+        })
+    }
+
+    /// Run a function body as a new call frame, capturing the stack if an
+    /// error starts unwinding from it
+    fn in_frame(
+        &mut self,
+        fn_def: &FnDef,
+        body: impl FnOnce(&mut Self) -> InterpResult<Value>,
+    ) -> InterpResult<Value> {
+        self.call_stack.push(StackFrame {
+            function: fn_def.name.node.clone(),
+            call_site: self.call_site.take(),
+        });
+        let result = body(self);
+        if result.is_err() && self.error_trace.is_none() {
+            self.error_trace = Some(self.call_stack.clone());
+        }
+        self.call_stack.pop();
+        result
+    }
+
+    /// Number of user function calls made so far, counting each self tail
+    /// call too
+    pub fn call_count(&self) -> u64 {
+        self.calls
     }
 
     /// Get list of test function names (functions starting with "test_")
@@ -299,10 +608,24 @@ impl Interpreter {
             .collect()
     }
 
+    /// Get benchmark function names (argument-less functions starting with "bench_"), sorted
+    pub fn get_bench_functions(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .functions
+            .iter()
+            .filter(|(name, fn_def)| name.starts_with("bench_") && fn_def.params.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
     /// Run a single function by name (for testing)
     pub fn run_function(&mut self, name: &str) -> InterpResult<Value> {
         if let Some(fn_def) = self.functions.get(name).cloned() {
-            self.call_function(&fn_def, &[])
+            self.reset_call_stack();
+            let result = self.call_function(&fn_def, &[]);
+            finish_output(self.attach_trace(result))
         } else {
             Err(RuntimeError::undefined_variable(name))
         }
@@ -312,16 +635,19 @@ impl Interpreter {
     pub fn call_function_with_args(&mut self, name: &str, args: Vec<Value>) -> InterpResult<Value> {
         // Check builtins first
         if let Some(builtin) = self.builtins.get(name) {
-            return builtin(&args);
+            return finish_output(builtin(&args));
         }
 
         // Then user-defined functions
         if let Some(fn_def) = self.functions.get(name).cloned() {
+            self.reset_call_stack();
             // v0.30.280: Use ScopeStack fast path when enabled
-            if self.use_scope_stack {
-                return self.call_function_fast(&fn_def, &args);
-            }
-            return self.call_function(&fn_def, &args);
+            let result = if self.use_scope_stack {
+                self.call_function_fast(&fn_def, &args)
+            } else {
+                self.call_function(&fn_def, &args)
+            };
+            return finish_output(self.attach_trace(result));
         }
 
         Err(RuntimeError::undefined_function(name))
@@ -329,6 +655,7 @@ impl Interpreter {
 
     /// Evaluate an expression with automatic stack growth for deep recursion
     fn eval(&mut self, expr: &Spanned<Expr>, env: &EnvRef) -> InterpResult<Value> {
+        self.record_coverage(expr.span);
         // Grow stack if we're running low
         stacker::maybe_grow(STACK_RED_ZONE, STACK_GROW_SIZE, || self.eval_inner(expr, env))
     }
@@ -347,6 +674,7 @@ impl Interpreter {
             Expr::Var(name) => {
                 env.borrow()
                     .get(name)
+                    .or_else(|| self.consts.get(name).cloned())
                     .ok_or_else(|| RuntimeError::undefined_variable(name))
             }
 
@@ -372,7 +700,8 @@ impl Interpreter {
                     _ => {
                         let lval = self.eval(left, env)?;
                         let rval = self.eval(right, env)?;
-                        self.eval_binary(*op, lval, rval)
+                        self.eval_binary(*op, lval, rval, expr.span)
+                            .map_err(|e| e.with_span(expr.span))
                     }
                 }
             }
@@ -380,6 +709,7 @@ impl Interpreter {
             Expr::Unary { op, expr: inner } => {
                 let val = self.eval(inner, env)?;
                 self.eval_unary(*op, val)
+                    .map_err(|e| e.with_span(expr.span))
             }
 
             Expr::If {
@@ -410,6 +740,14 @@ impl Interpreter {
                 self.eval(body, env)
             }
 
+            // Statement-form let: the enclosing Block's env holds it for the
+            // rest of the block
+            Expr::LetStmt { name, value, .. } => {
+                let val = self.eval(value, env)?;
+                env.borrow_mut().define(name.clone(), val);
+                Ok(Value::Unit)
+            }
+
             Expr::Assign { name, value } => {
                 let val = self.eval(value, env)?;
                 if !env.borrow_mut().set(name, val.clone()) {
@@ -418,10 +756,33 @@ impl Interpreter {
                 Ok(Value::Unit)
             }
 
+            Expr::IndexAssign { array, index, value } => {
+                let (root, mut steps) = place_path(array, &mut |e| self.eval(e, env))?;
+                steps.push(PlaceStep::Index(self.eval(index, env)?, index.span));
+                let val = self.eval(value, env)?;
+                let mut target = env.borrow().get(root).ok_or_else(|| RuntimeError::undefined_variable(root))?;
+                store_place(&mut target, &steps, val)?;
+                env.borrow_mut().set(root, target);
+                Ok(Value::Unit)
+            }
+
+            Expr::FieldAssign { object, field, value } => {
+                let (root, mut steps) = place_path(object, &mut |e| self.eval(e, env))?;
+                steps.push(PlaceStep::Field(&field.node));
+                let val = self.eval(value, env)?;
+                let mut target = env.borrow().get(root).ok_or_else(|| RuntimeError::undefined_variable(root))?;
+                store_place(&mut target, &steps, val)?;
+                env.borrow_mut().set(root, target);
+                Ok(Value::Unit)
+            }
+
             // v0.37: Invariant is for SMT verification, not runtime
-            Expr::While { cond, invariant: _, body } => {
+            Expr::While { label, cond, invariant: _, body } => {
                 while self.eval(cond, env)?.is_truthy() {
-                    self.eval(body, env)?;
+                    let result = self.eval(body, env);
+                    if let LoopFlow::Exit(_) = self.loop_flow(result, label)? {
+                        break;
+                    }
                 }
                 Ok(Value::Unit)
             }
@@ -447,14 +808,17 @@ impl Interpreter {
             }
 
             // v0.5 Phase 3: For loop
-            Expr::For { var, iter, body } => {
+            Expr::For { label, var, iter, body } => {
                 let iter_val = self.eval(iter, env)?;
                 match iter_val {
                     Value::Range(start, end) => {
                         let child = child_env(env);
                         for i in start..end {
                             child.borrow_mut().define(var.clone(), Value::Int(i));
-                            self.eval(body, &child)?;
+                            let result = self.eval(body, &child);
+                            if let LoopFlow::Exit(_) = self.loop_flow(result, label)? {
+                                break;
+                            }
                         }
                         Ok(Value::Unit)
                     }
@@ -463,12 +827,17 @@ impl Interpreter {
             }
 
             Expr::Call { func, args } => {
+                let closure = closure_named(env, func);
                 let arg_vals: Vec<Value> = args
                     .iter()
                     .map(|a| self.eval(a, env))
                     .collect::<InterpResult<Vec<_>>>()?;
 
-                self.call(func, arg_vals)
+                self.call_site = Some(expr.span);
+                match closure {
+                    Some(closure) => self.call_closure(&closure, arg_vals),
+                    None => self.call(func, arg_vals),
+                }
             }
 
             Expr::Block(exprs) => {
@@ -485,12 +854,17 @@ impl Interpreter {
                 Err(RuntimeError::type_error("value", "ret"))
             }
 
-            Expr::StructInit { name, fields } => {
+            Expr::StructInit { name, fields, base } => {
                 let mut field_values = HashMap::new();
                 for (field_name, field_expr) in fields {
                     let val = self.eval(field_expr, env)?;
                     field_values.insert(field_name.node.clone(), val);
                 }
+                if let Some(base) = base {
+                    fill_from_base(&mut field_values, self.eval(base, env)?)?;
+                } else {
+                    self.fill_defaults(name, &mut field_values)?;
+                }
                 Ok(Value::Struct(name.clone(), field_values))
             }
 
@@ -518,6 +892,9 @@ impl Interpreter {
             }
 
             Expr::EnumVariant { enum_name, variant, args } => {
+                if enum_name == "HashMap" && variant == "new" && !self.enum_defs.contains_key(enum_name) {
+                    return Ok(Value::Map(Rc::new(RefCell::new(HashMap::new()))));
+                }
                 let arg_vals: Vec<Value> = args
                     .iter()
                     .map(|a| self.eval(a, env))
@@ -545,7 +922,7 @@ impl Interpreter {
                     }
                 }
 
-                Err(RuntimeError::type_error("matching arm", "no match found"))
+                Err(RuntimeError::no_matching_arm(&val))
             }
 
             // v0.5 Phase 5: References
@@ -588,39 +965,7 @@ impl Interpreter {
             Expr::Index { expr, index } => {
                 let arr_val = self.eval(expr, env)?;
                 let idx_val = self.eval(index, env)?;
-
-                let idx = match idx_val {
-                    Value::Int(n) => n as usize,
-                    _ => return Err(RuntimeError::type_error("integer", idx_val.type_name())),
-                };
-
-                match arr_val {
-                    Value::Array(arr) => {
-                        if idx < arr.len() {
-                            Ok(arr[idx].clone())
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, arr.len()))
-                        }
-                    }
-                    Value::Str(s) => {
-                        if idx < s.len() {
-                            Ok(Value::Int(s.as_bytes()[idx] as i64))
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, s.len()))
-                        }
-                    }
-                    // v0.93: Handle StringRope (lazy concatenated strings)
-                    Value::StringRope(_) => {
-                        let s = arr_val.materialize_string()
-                            .ok_or_else(|| RuntimeError::type_error("string", "invalid StringRope"))?;
-                        if idx < s.len() {
-                            Ok(Value::Int(s.as_bytes()[idx] as i64))
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, s.len()))
-                        }
-                    }
-                    _ => Err(RuntimeError::type_error("array or string", arr_val.type_name())),
-                }
+                index_value(arr_val, idx_val).map_err(|e| e.with_span(index.span))
             }
 
             // v0.5 Phase 8: Method calls
@@ -630,6 +975,12 @@ impl Interpreter {
                     .iter()
                     .map(|a| self.eval(a, env))
                     .collect::<InterpResult<Vec<_>>>()?;
+                if let Some(fn_def) = self.lookup_impl_method(&recv_val, method) {
+                    let mut call_args = vec![recv_val];
+                    call_args.extend(arg_vals);
+                    self.call_site = Some(expr.span);
+                    return self.call_function(&fn_def, &call_args);
+                }
                 self.eval_method_call(recv_val, method, arg_vals)
             }
 
@@ -650,12 +1001,14 @@ impl Interpreter {
             }
 
 
-            // v0.20.0: Closure expressions
-            // TODO: Implement closure evaluation with proper capture
-            Expr::Closure { body, .. } => {
-                // For now, just evaluate the body directly
-                // Full closure semantics (capture, delayed execution) will be implemented later
-                self.eval(body, env)
+            // v0.20.0: Closure expressions copy the variables they mention
+            Expr::Closure { params, body, .. } => {
+                let params: Vec<String> = params.iter().map(|p| p.name.node.clone()).collect();
+                let captures = body.node.mentioned_names().into_iter()
+                    .filter(|name| !params.contains(name))
+                    .filter_map(|name| env.borrow().get(&name).map(|value| (name, value)))
+                    .collect();
+                Ok(Value::Closure(Rc::new(Closure { params, body: (**body).clone(), captures })))
             }
 
             // v0.31: Todo expression - panics at runtime
@@ -666,31 +1019,39 @@ impl Interpreter {
 
             // v0.36: Additional control flow
             // Loop - infinite loop, exits only via break
-            Expr::Loop { body } => {
+            Expr::Loop { label, body } => {
                 loop {
-                    match self.eval(body, env) {
-                        Ok(_) => continue,
-                        Err(e) => return Err(e),
+                    let result = self.eval(body, env);
+                    if let LoopFlow::Exit(value) = self.loop_flow(result, label)? {
+                        return Ok(value);
                     }
                 }
             }
 
-            // Break - not yet fully implemented (needs loop context)
-            Expr::Break { .. } => {
-                Err(RuntimeError::type_error("loop context", "break outside loop"))
+            // Break/continue unwind to the innermost loop, or the loop
+            // carrying their label
+            Expr::Break { label, value } => {
+                let value = match value {
+                    Some(v) => self.eval(v, env)?,
+                    None => Value::Unit,
+                };
+                self.loop_signal = Some(LoopSignal::Break { label: label.clone(), value });
+                Err(RuntimeError::loop_control("break"))
             }
 
-            // Continue - not yet fully implemented (needs loop context)
-            Expr::Continue => {
-                Err(RuntimeError::type_error("loop context", "continue outside loop"))
+            Expr::Continue { label } => {
+                self.loop_signal = Some(LoopSignal::Continue { label: label.clone() });
+                Err(RuntimeError::loop_control("continue"))
             }
 
-            // Return - early return from function
+            // Return - unwinds to the enclosing function or closure
             Expr::Return { value } => {
-                match value {
-                    Some(v) => self.eval(v, env),
-                    None => Ok(Value::Unit),
-                }
+                let value = match value {
+                    Some(v) => self.eval(v, env)?,
+                    None => Value::Unit,
+                };
+                self.loop_signal = Some(LoopSignal::Return { value });
+                Err(RuntimeError::loop_control("return"))
             }
 
             // v0.37: Quantifiers (verification-only, cannot be executed at runtime)
@@ -718,6 +1079,14 @@ impl Interpreter {
     /// Evaluate method call (v0.5 Phase 8, v0.30.283: StringRope support)
     fn eval_method_call(&self, receiver: Value, method: &str, args: Vec<Value>) -> InterpResult<Value> {
         match receiver {
+            // `@derive(Debug)`: the type checker only allows this on deriving types
+            Value::Struct(..) | Value::Enum(..) if method == "to_debug_string" && args.is_empty() => {
+                Ok(Value::Str(Rc::new(self.debug_string(&receiver))))
+            }
+            // `@derive(Hash)`, likewise checked by the type checker
+            Value::Struct(..) | Value::Enum(..) if method == "hash" && args.is_empty() => {
+                Ok(Value::Int(self.derived_hash(&receiver)))
+            }
             // v0.30.283: Handle StringRope by materializing
             Value::StringRope(_) => {
                 let materialized = receiver.materialize_string()
@@ -770,6 +1139,7 @@ impl Interpreter {
                     _ => Err(RuntimeError::undefined_function(&format!("Array.{}", method))),
                 }
             }
+            Value::Map(map) => eval_map_method(&map, method, args),
             // v0.18: Option<T> methods
             Value::Enum(enum_name, variant, values) if enum_name == "Option" => {
                 match method {
@@ -810,6 +1180,73 @@ impl Interpreter {
         }
     }
 
+    /// Render a value for a derived `to_debug_string()`: struct fields in
+    /// declaration order, enum variants as `Variant(args...)`
+    fn debug_string(&self, value: &Value) -> String {
+        let join = |items: &[Value]| {
+            items.iter().map(|v| self.debug_string(v)).collect::<Vec<_>>().join(", ")
+        };
+        match value {
+            Value::Struct(name, fields) => {
+                let order = self.field_order(name, fields);
+                if order.is_empty() {
+                    return name.clone();
+                }
+                let parts: Vec<String> = order.iter()
+                    .filter_map(|f| fields.get(*f).map(|v| format!("{}: {}", f, self.debug_string(v))))
+                    .collect();
+                format!("{} {{ {} }}", name, parts.join(", "))
+            }
+            Value::Enum(_, variant, args) if args.is_empty() => variant.clone(),
+            Value::Enum(_, variant, args) => format!("{}({})", variant, join(args)),
+            Value::Array(items) => format!("[{}]", join(items)),
+            Value::Tuple(items) if items.len() == 1 => format!("({},)", join(items)),
+            Value::Tuple(items) => format!("({})", join(items)),
+            Value::Ref(r) => self.debug_string(&r.borrow()),
+            Value::Str(_) | Value::StringRope(_) => {
+                format!("{:?}", value.materialize_string().unwrap_or_default())
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// `hash()` of a `@derive(Hash)` value: field hashes folded through
+    /// `hash_i64`, starting from the struct or variant name. The result only
+    /// depends on the value, so it is the same in every run.
+    fn derived_hash(&self, value: &Value) -> i64 {
+        let combine = |h: i64, v: i64| hash_i64(h.wrapping_mul(31) ^ v);
+        let str_hash = |s: &str| s.bytes().fold(0, |h, b| combine(h, b as i64));
+        match value {
+            Value::Int(n) => hash_i64(*n),
+            Value::Bool(b) => hash_i64(*b as i64),
+            Value::Char(c) => hash_i64(*c as i64),
+            Value::Str(_) | Value::StringRope(_) => str_hash(&value.materialize_string().unwrap_or_default()),
+            Value::Struct(name, fields) => self
+                .field_order(name, fields)
+                .into_iter()
+                .filter_map(|f| fields.get(f))
+                .fold(str_hash(name), |h, v| combine(h, self.derived_hash(v))),
+            Value::Enum(_, variant, args) => {
+                args.iter().fold(str_hash(variant), |h, v| combine(h, self.derived_hash(v)))
+            }
+            Value::Ref(r) => self.derived_hash(&r.borrow()),
+            _ => 0,
+        }
+    }
+
+    /// Field names of a struct value in declaration order (sorted when the
+    /// definition is unknown)
+    fn field_order<'a>(&'a self, name: &str, fields: &'a HashMap<String, Value>) -> Vec<&'a String> {
+        match self.struct_defs.get(name) {
+            Some(def) => def.fields.iter().map(|f| &f.name.node).collect(),
+            None => {
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+                keys
+            }
+        }
+    }
+
     /// Try to match a value against a pattern, returning bindings if successful
     fn match_pattern(&self, pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
         match pattern {
@@ -1006,6 +1443,35 @@ impl Interpreter {
         Err(RuntimeError::undefined_function(name))
     }
 
+    /// Call a closure: its body runs on top of the globals with the
+    /// captured values and the arguments bound
+    fn call_closure(&mut self, closure: &Closure, args: Vec<Value>) -> InterpResult<Value> {
+        if closure.params.len() != args.len() {
+            return Err(RuntimeError::arity_mismatch("closure", closure.params.len(), args.len()));
+        }
+
+        self.recursion_depth += 1;
+        if self.recursion_depth > MAX_RECURSION_DEPTH {
+            self.recursion_depth -= 1;
+            return Err(RuntimeError::stack_overflow());
+        }
+
+        self.calls += 1;
+        let closure_env = child_env(&self.global_env);
+        for (name, value) in &closure.captures {
+            closure_env.borrow_mut().define(name.clone(), value.clone());
+        }
+        for (name, arg) in closure.params.iter().zip(args) {
+            closure_env.borrow_mut().define(name.clone(), arg);
+        }
+        let result = match self.eval(&closure.body, &closure_env) {
+            Err(err) => self.take_return(&err).ok_or(err),
+            ok => ok,
+        };
+        self.recursion_depth -= 1;
+        result
+    }
+
     /// Call a user-defined function with automatic stack growth
     fn call_function(&mut self, fn_def: &FnDef, args: &[Value]) -> InterpResult<Value> {
         stacker::maybe_grow(STACK_RED_ZONE, STACK_GROW_SIZE, || {
@@ -1031,29 +1497,155 @@ impl Interpreter {
             return Err(RuntimeError::stack_overflow());
         }
 
-        // Create new environment for function body
-        let func_env = child_env(&self.global_env);
+        let result = self.in_frame(fn_def, |interp| interp.run_frame(fn_def, args.to_vec()));
+        self.recursion_depth -= 1;
+        result
+    }
+
+    /// Execute a function body, looping on self-recursive tail calls so they
+    /// run in constant stack space
+    fn run_frame(&mut self, fn_def: &FnDef, mut args: Vec<Value>) -> InterpResult<Value> {
+        loop {
+            self.calls += 1;
+            // Create new environment for function body
+            let func_env = child_env(&self.global_env);
+
+            // Bind parameters
+            for (param, arg) in fn_def.params.iter().zip(args) {
+                func_env.borrow_mut().define(param.name.node.clone(), arg);
+            }
+
+            // Evaluate pre-condition if present
+            if let Some(pre) = &fn_def.pre {
+                let pre_val = self.eval(pre, &func_env)?;
+                if !pre_val.is_truthy() {
+                    return Err(RuntimeError::pre_condition_failed(&fn_def.name.node));
+                }
+            }
+
+            // Evaluate body
+            match self.eval_tail(&fn_def.body, &func_env, fn_def) {
+                Ok(TailEval::Value(val)) => return Ok(val),
+                Ok(TailEval::SelfCall(next_args)) => args = next_args,
+                Err(err) => return self.take_return(&err).ok_or(err),
+            }
+        }
+    }
 
-        // Bind parameters
-        for (param, arg) in fn_def.params.iter().zip(args.iter()) {
-            func_env
-                .borrow_mut()
-                .define(param.name.node.clone(), arg.clone());
+    /// The value of a `return` that unwound to the current function, if
+    /// `err` is one. Any other pending signal is left in place.
+    fn take_return(&mut self, err: &RuntimeError) -> Option<Value> {
+        if err.kind != ErrorKind::LoopControl {
+            return None;
+        }
+        match self.loop_signal.take() {
+            Some(LoopSignal::Return { value }) => Some(value),
+            pending => {
+                self.loop_signal = pending;
+                None
+            }
         }
+    }
 
-        // Evaluate pre-condition if present
-        if let Some(pre) = &fn_def.pre {
-            let pre_val = self.eval(pre, &func_env)?;
-            if !pre_val.is_truthy() {
-                self.recursion_depth -= 1;
-                return Err(RuntimeError::pre_condition_failed(&fn_def.name.node));
+    /// Decide how a loop labeled `label` goes on after its body produced
+    /// `result`: a pending `break`/`continue` aimed at this loop (unlabeled,
+    /// or with a matching label) is consumed, anything else propagates
+    fn loop_flow(&mut self, result: InterpResult<Value>, label: &Option<String>) -> InterpResult<LoopFlow> {
+        let err = match result {
+            Ok(_) => return Ok(LoopFlow::Next),
+            Err(err) if err.kind == ErrorKind::LoopControl => err,
+            Err(err) => return Err(err),
+        };
+        let targets_this = |target: &Option<String>| target.is_none() || target == label;
+        match self.loop_signal.take() {
+            Some(LoopSignal::Break { label: target, value }) if targets_this(&target) => Ok(LoopFlow::Exit(value)),
+            Some(LoopSignal::Continue { label: target }) if targets_this(&target) => Ok(LoopFlow::Next),
+            pending => {
+                self.loop_signal = pending;
+                Err(err)
             }
         }
+    }
 
-        // Evaluate body
-        let result = self.eval(&fn_def.body, &func_env);
-        self.recursion_depth -= 1;
-        result
+    /// Whether a call to `name` from inside `fn_def` re-enters `fn_def` itself
+    fn is_self_call(&self, name: &str, fn_def: &FnDef) -> bool {
+        !self.builtins.contains_key(name)
+            && self
+                .functions
+                .get(name)
+                .is_some_and(|f| f.name.span == fn_def.name.span)
+    }
+
+    /// Evaluate an expression in tail position of `fn_def`.
+    /// Tail position extends through `if` branches, `match` arms, `let`
+    /// bodies and the trailing expression of a block; everything else is
+    /// evaluated normally.
+    fn eval_tail(&mut self, expr: &Spanned<Expr>, env: &EnvRef, fn_def: &FnDef) -> InterpResult<TailEval> {
+        self.record_coverage(expr.span);
+        match &expr.node {
+            Expr::Call { func, args } if self.is_self_call(func, fn_def) && closure_named(env, func).is_none() => {
+                let arg_vals = args
+                    .iter()
+                    .map(|a| self.eval(a, env))
+                    .collect::<InterpResult<Vec<_>>>()?;
+                if arg_vals.len() != fn_def.params.len() {
+                    return Err(RuntimeError::arity_mismatch(
+                        &fn_def.name.node,
+                        fn_def.params.len(),
+                        arg_vals.len(),
+                    ));
+                }
+                Ok(TailEval::SelfCall(arg_vals))
+            }
+
+            Expr::If { cond, then_branch, else_branch } => {
+                if self.eval(cond, env)?.is_truthy() {
+                    self.eval_tail(then_branch, env, fn_def)
+                } else {
+                    self.eval_tail(else_branch, env, fn_def)
+                }
+            }
+
+            Expr::Block(exprs) => {
+                let Some((last, init)) = exprs.split_last() else {
+                    return Ok(TailEval::Value(Value::Unit));
+                };
+                let child = child_env(env);
+                for e in init {
+                    self.eval(e, &child)?;
+                }
+                self.eval_tail(last, &child, fn_def)
+            }
+
+            Expr::Let { name, value, body, .. } => {
+                let val = self.eval(value, env)?;
+                env.borrow_mut().define(name.clone(), val);
+                self.eval_tail(body, env, fn_def)
+            }
+
+            Expr::Match { expr: match_expr, arms } => {
+                let val = self.eval(match_expr, env)?;
+                for arm in arms {
+                    if let Some(bindings) = self.match_pattern(&arm.pattern.node, &val) {
+                        let child = child_env(env);
+                        for (name, bound_val) in bindings {
+                            child.borrow_mut().define(name, bound_val);
+                        }
+                        if let Some(guard) = &arm.guard
+                            && !self.eval(guard, &child)?.is_truthy()
+                        {
+                            continue;
+                        }
+                        return self.eval_tail(&arm.body, &child, fn_def);
+                    }
+                }
+                Err(RuntimeError::no_matching_arm(&val))
+            }
+
+            Expr::Return { value: Some(v) } => self.eval_tail(v, env, fn_def),
+
+            _ => self.eval(expr, env).map(TailEval::Value),
+        }
     }
 
     /// v0.39: Evaluate type cast
@@ -1080,6 +1672,14 @@ impl Interpreter {
             (Value::Bool(b), Type::U64) => Ok(Value::Int(if *b { 1 } else { 0 })),
             (Value::Bool(b), Type::F64) => Ok(Value::Float(if *b { 1.0 } else { 0.0 })),
             (Value::Bool(b), Type::Bool) => Ok(Value::Bool(*b)),
+            // char casts go through the codepoint
+            (Value::Char(c), Type::Char) => Ok(Value::Char(*c)),
+            (Value::Char(c), _) => self.eval_cast(Value::Int(*c as i64), target_ty),
+            (Value::Int(n), Type::Char) => u32::try_from(*n)
+                .ok()
+                .and_then(char::from_u32)
+                .map(Value::Char)
+                .ok_or_else(|| RuntimeError::invalid_char(*n)),
             _ => Err(RuntimeError::type_error(
                 &format!("{:?}", target_ty),
                 &format!("cannot cast {} to {:?}", val.type_name(), target_ty),
@@ -1088,11 +1688,11 @@ impl Interpreter {
     }
 
     /// Evaluate binary operation
-    fn eval_binary(&self, op: BinOp, left: Value, right: Value) -> InterpResult<Value> {
+    fn eval_binary(&self, op: BinOp, left: Value, right: Value, span: Span) -> InterpResult<Value> {
         match op {
             // Arithmetic
             BinOp::Add => match (&left, &right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+                (Value::Int(a), Value::Int(b)) => self.int_arith(op, *a, *b, span),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
                 (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + b)),
                 (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a + *b as f64)),
@@ -1111,7 +1711,7 @@ impl Interpreter {
                 )),
             },
             BinOp::Sub => match (&left, &right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a - b)),
+                (Value::Int(a), Value::Int(b)) => self.int_arith(op, *a, *b, span),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
                 (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 - b)),
                 (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a - *b as f64)),
@@ -1121,7 +1721,7 @@ impl Interpreter {
                 )),
             },
             BinOp::Mul => match (&left, &right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a * b)),
+                (Value::Int(a), Value::Int(b)) => self.int_arith(op, *a, *b, span),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
                 (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 * b)),
                 (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a * *b as f64)),
@@ -1288,6 +1888,38 @@ impl Interpreter {
         }
     }
 
+    /// Default `+`, `-` or `*` on two integers, at the width recorded for
+    /// `span` (`i64` if none). Overflow is an error naming the operation
+    /// unless overflow checks are disabled, in which case the result wraps.
+    fn int_arith(&self, op: BinOp, a: i64, b: i64, span: Span) -> InterpResult<Value> {
+        let ty = self.int_op_types.get(&span).unwrap_or(&Type::I64);
+        // Widen to i128 so every operand reads as its own type's value
+        let (a, b, min, max) = match ty {
+            Type::I32 => (a as i32 as i128, b as i32 as i128, i32::MIN as i128, i32::MAX as i128),
+            Type::U32 => (a as u32 as i128, b as u32 as i128, 0, u32::MAX as i128),
+            Type::U64 => (a as u64 as i128, b as u64 as i128, 0, u64::MAX as i128),
+            _ => (a as i128, b as i128, i64::MIN as i128, i64::MAX as i128),
+        };
+        let (checked, wrapped, symbol) = match op {
+            BinOp::Add => (a.checked_add(b), a.wrapping_add(b), "+"),
+            BinOp::Sub => (a.checked_sub(b), a.wrapping_sub(b), "-"),
+            _ => (a.checked_mul(b), a.wrapping_mul(b), "*"),
+        };
+        let value = match checked.filter(|v| (min..=max).contains(v)) {
+            Some(v) => v,
+            None if self.overflow_checks => {
+                return Err(RuntimeError::arithmetic_overflow(&format!("{a} {symbol} {b}")));
+            }
+            None => wrapped,
+        };
+        // Truncating wraps to the width; u64 shares the i64 bit pattern
+        Ok(Value::Int(match ty {
+            Type::I32 => value as i32 as i64,
+            Type::U32 => value as u32 as i64,
+            _ => value as i64,
+        }))
+    }
+
     /// Compare two values
     fn compare_values<F>(&self, left: &Value, right: &Value, f: F) -> InterpResult<Value>
     where
@@ -1311,7 +1943,11 @@ impl Interpreter {
     fn eval_unary(&self, op: UnOp, val: Value) -> InterpResult<Value> {
         match op {
             UnOp::Neg => match val {
-                Value::Int(n) => Ok(Value::Int(-n)),
+                Value::Int(n) => match n.checked_neg() {
+                    Some(v) => Ok(Value::Int(v)),
+                    None if self.overflow_checks => Err(RuntimeError::arithmetic_overflow(&format!("-({n})"))),
+                    None => Ok(Value::Int(n.wrapping_neg())),
+                },
                 Value::Float(f) => Ok(Value::Float(-f)),
                 _ => Err(RuntimeError::type_error("numeric", val.type_name())),
             },
@@ -1324,6 +1960,24 @@ impl Interpreter {
         }
     }
 
+    /// Evaluate the defaults of the fields of struct `name` missing from
+    /// `fields`. Defaults only see globals, and run anew for every value.
+    fn fill_defaults(&mut self, name: &str, fields: &mut HashMap<String, Value>) -> InterpResult<()> {
+        let missing: Vec<(String, Spanned<Expr>)> = match self.struct_defs.get(name) {
+            Some(def) => def.fields.iter()
+                .filter(|f| !fields.contains_key(&f.name.node))
+                .filter_map(|f| Some((f.name.node.clone(), f.default.clone()?)))
+                .collect(),
+            None => return Ok(()),
+        };
+        let global_env = self.global_env.clone();
+        for (field, default) in missing {
+            let value = self.eval(&default, &global_env)?;
+            fields.insert(field, value);
+        }
+        Ok(())
+    }
+
     /// Get the global environment (for REPL)
     pub fn global_env(&self) -> &EnvRef {
         &self.global_env
@@ -1338,6 +1992,7 @@ impl Interpreter {
 
     /// Evaluate an expression using ScopeStack for efficient memory
     fn eval_fast(&mut self, expr: &Spanned<Expr>) -> InterpResult<Value> {
+        self.record_coverage(expr.span);
         stacker::maybe_grow(STACK_RED_ZONE, STACK_GROW_SIZE, || self.eval_fast_inner(expr))
     }
 
@@ -1355,6 +2010,7 @@ impl Interpreter {
             Expr::Var(name) => {
                 self.scope_stack
                     .get(name)
+                    .or_else(|| self.consts.get(name).cloned())
                     .ok_or_else(|| RuntimeError::undefined_variable(name))
             }
 
@@ -1379,7 +2035,8 @@ impl Interpreter {
                     _ => {
                         let lval = self.eval_fast(left)?;
                         let rval = self.eval_fast(right)?;
-                        self.eval_binary(*op, lval, rval)
+                        self.eval_binary(*op, lval, rval, expr.span)
+                            .map_err(|e| e.with_span(expr.span))
                     }
                 }
             }
@@ -1387,6 +2044,7 @@ impl Interpreter {
             Expr::Unary { op, expr: inner } => {
                 let val = self.eval_fast(inner)?;
                 self.eval_unary(*op, val)
+                    .map_err(|e| e.with_span(expr.span))
             }
 
             Expr::If { cond, then_branch, else_branch } => {
@@ -1407,11 +2065,18 @@ impl Interpreter {
                 self.eval_fast(body)
             }
 
+            Expr::LetStmt { name, value, .. } => {
+                let val = self.eval_fast(value)?;
+                self.scope_stack.define(name.clone(), val);
+                Ok(Value::Unit)
+            }
+
             Expr::Call { func, args } => {
                 let arg_vals: Vec<Value> = args
                     .iter()
                     .map(|a| self.eval_fast(a))
                     .collect::<InterpResult<Vec<_>>>()?;
+                self.call_site = Some(expr.span);
                 self.call_fast(func, arg_vals)
             }
 
@@ -1421,18 +2086,29 @@ impl Interpreter {
                     .iter()
                     .map(|a| self.eval_fast(a))
                     .collect::<InterpResult<Vec<_>>>()?;
+                if let Some(fn_def) = self.lookup_impl_method(&recv_val, method) {
+                    let mut call_args = vec![recv_val];
+                    call_args.extend(arg_vals);
+                    self.call_site = Some(expr.span);
+                    return self.call_function_fast(&fn_def, &call_args);
+                }
                 self.eval_method_call(recv_val, method, arg_vals)
             }
 
             // v0.30.280: Block expression - immediate scope deallocation
+            // The scope is popped on errors too, so a `break`/`continue`
+            // unwinding through the block leaves the stack balanced
             Expr::Block(exprs) => {
                 self.scope_stack.push_scope();
-                let mut result = Value::Unit;
+                let mut result = Ok(Value::Unit);
                 for e in exprs {
-                    result = self.eval_fast(e)?;
+                    result = self.eval_fast(e);
+                    if result.is_err() {
+                        break;
+                    }
                 }
                 self.scope_stack.pop_scope();
-                Ok(result)
+                result
             }
 
             // v0.30.280: Assignment using ScopeStack
@@ -1444,15 +2120,70 @@ impl Interpreter {
                 Ok(Value::Unit)
             }
 
+            Expr::IndexAssign { array, index, value } => {
+                let (root, mut steps) = place_path(array, &mut |e| self.eval_fast(e))?;
+                steps.push(PlaceStep::Index(self.eval_fast(index)?, index.span));
+                let val = self.eval_fast(value)?;
+                let mut target = self.scope_stack.get(root).ok_or_else(|| RuntimeError::undefined_variable(root))?;
+                store_place(&mut target, &steps, val)?;
+                self.scope_stack.set(root, target);
+                Ok(Value::Unit)
+            }
+
+            Expr::FieldAssign { object, field, value } => {
+                let (root, mut steps) = place_path(object, &mut |e| self.eval_fast(e))?;
+                steps.push(PlaceStep::Field(&field.node));
+                let val = self.eval_fast(value)?;
+                let mut target = self.scope_stack.get(root).ok_or_else(|| RuntimeError::undefined_variable(root))?;
+                store_place(&mut target, &steps, val)?;
+                self.scope_stack.set(root, target);
+                Ok(Value::Unit)
+            }
+
             // v0.30.280: While loop using ScopeStack
             // v0.37: Invariant is for SMT verification, not runtime
-            Expr::While { cond, invariant: _, body } => {
+            Expr::While { label, cond, invariant: _, body } => {
                 while self.eval_fast(cond)?.is_truthy() {
-                    self.eval_fast(body)?;
+                    let result = self.eval_fast(body);
+                    if let LoopFlow::Exit(_) = self.loop_flow(result, label)? {
+                        break;
+                    }
                 }
                 Ok(Value::Unit)
             }
 
+            Expr::Loop { label, body } => {
+                loop {
+                    let result = self.eval_fast(body);
+                    if let LoopFlow::Exit(value) = self.loop_flow(result, label)? {
+                        return Ok(value);
+                    }
+                }
+            }
+
+            Expr::Break { label, value } => {
+                let value = match value {
+                    Some(v) => self.eval_fast(v)?,
+                    None => Value::Unit,
+                };
+                self.loop_signal = Some(LoopSignal::Break { label: label.clone(), value });
+                Err(RuntimeError::loop_control("break"))
+            }
+
+            Expr::Continue { label } => {
+                self.loop_signal = Some(LoopSignal::Continue { label: label.clone() });
+                Err(RuntimeError::loop_control("continue"))
+            }
+
+            Expr::Return { value } => {
+                let value = match value {
+                    Some(v) => self.eval_fast(v)?,
+                    None => Value::Unit,
+                };
+                self.loop_signal = Some(LoopSignal::Return { value });
+                Err(RuntimeError::loop_control("return"))
+            }
+
             // v0.30.280: Match expression using ScopeStack
             Expr::Match { expr: match_expr, arms } => {
                 let val = self.eval_fast(match_expr)?;
@@ -1475,16 +2206,21 @@ impl Interpreter {
                         return result;
                     }
                 }
-                Err(RuntimeError::type_error("matching arm", "no match found"))
+                Err(RuntimeError::no_matching_arm(&val))
             }
 
             // v0.30.280: Struct support
-            Expr::StructInit { name, fields } => {
+            Expr::StructInit { name, fields, base } => {
                 let mut field_values = std::collections::HashMap::new();
                 for (field_name, field_expr) in fields {
                     let val = self.eval_fast(field_expr)?;
                     field_values.insert(field_name.node.clone(), val);
                 }
+                if let Some(base) = base {
+                    fill_from_base(&mut field_values, self.eval_fast(base)?)?;
+                } else {
+                    self.fill_defaults(name, &mut field_values)?;
+                }
                 Ok(Value::Struct(name.clone(), field_values))
             }
 
@@ -1513,6 +2249,9 @@ impl Interpreter {
 
             // v0.30.280: Enum support
             Expr::EnumVariant { enum_name, variant, args } => {
+                if enum_name == "HashMap" && variant == "new" && !self.enum_defs.contains_key(enum_name) {
+                    return Ok(Value::Map(Rc::new(RefCell::new(HashMap::new()))));
+                }
                 let arg_vals: Vec<Value> = args
                     .iter()
                     .map(|a| self.eval_fast(a))
@@ -1541,37 +2280,7 @@ impl Interpreter {
             Expr::Index { expr, index } => {
                 let arr_val = self.eval_fast(expr)?;
                 let idx_val = self.eval_fast(index)?;
-                let idx = match idx_val {
-                    Value::Int(n) => n as usize,
-                    _ => return Err(RuntimeError::type_error("integer", idx_val.type_name())),
-                };
-                match arr_val {
-                    Value::Array(arr) => {
-                        if idx < arr.len() {
-                            Ok(arr[idx].clone())
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, arr.len()))
-                        }
-                    }
-                    Value::Str(s) => {
-                        if idx < s.len() {
-                            Ok(Value::Int(s.as_bytes()[idx] as i64))
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, s.len()))
-                        }
-                    }
-                    // v0.93: Handle StringRope (lazy concatenated strings)
-                    Value::StringRope(_) => {
-                        let s = arr_val.materialize_string()
-                            .ok_or_else(|| RuntimeError::type_error("string", "invalid StringRope"))?;
-                        if idx < s.len() {
-                            Ok(Value::Int(s.as_bytes()[idx] as i64))
-                        } else {
-                            Err(RuntimeError::index_out_of_bounds(idx as i64, s.len()))
-                        }
-                    }
-                    _ => Err(RuntimeError::type_error("array or string", arr_val.type_name())),
-                }
+                index_value(arr_val, idx_val).map_err(|e| e.with_span(index.span))
             }
 
             // For unsupported expressions, return error (force explicit handling)
@@ -1609,16 +2318,99 @@ impl Interpreter {
             return Err(RuntimeError::stack_overflow());
         }
 
-        self.scope_stack.push_scope();
-        for (param, arg) in fn_def.params.iter().zip(args.iter()) {
-            self.scope_stack.define(param.name.node.clone(), arg.clone());
-        }
+        let mut args = args.to_vec();
+        let result = self.in_frame(fn_def, |interp| loop {
+            interp.calls += 1;
+            interp.scope_stack.push_scope();
+            for (param, arg) in fn_def.params.iter().zip(args) {
+                interp.scope_stack.define(param.name.node.clone(), arg);
+            }
 
-        let result = self.eval_fast(&fn_def.body);
-        self.scope_stack.pop_scope();
+            let step = interp.eval_fast_tail(&fn_def.body, fn_def);
+            interp.scope_stack.pop_scope();
+            match step {
+                Ok(TailEval::SelfCall(next_args)) => args = next_args,
+                Ok(TailEval::Value(val)) => break Ok(val),
+                Err(e) => break interp.take_return(&e).ok_or(e),
+            }
+        });
         self.recursion_depth -= 1;
         result
     }
+
+    /// ScopeStack counterpart of `eval_tail`
+    fn eval_fast_tail(&mut self, expr: &Spanned<Expr>, fn_def: &FnDef) -> InterpResult<TailEval> {
+        self.record_coverage(expr.span);
+        match &expr.node {
+            Expr::Call { func, args } if self.is_self_call(func, fn_def) => {
+                let arg_vals = args
+                    .iter()
+                    .map(|a| self.eval_fast(a))
+                    .collect::<InterpResult<Vec<_>>>()?;
+                if arg_vals.len() != fn_def.params.len() {
+                    return Err(RuntimeError::arity_mismatch(
+                        &fn_def.name.node,
+                        fn_def.params.len(),
+                        arg_vals.len(),
+                    ));
+                }
+                Ok(TailEval::SelfCall(arg_vals))
+            }
+
+            Expr::If { cond, then_branch, else_branch } => {
+                if self.eval_fast(cond)?.is_truthy() {
+                    self.eval_fast_tail(then_branch, fn_def)
+                } else {
+                    self.eval_fast_tail(else_branch, fn_def)
+                }
+            }
+
+            Expr::Block(exprs) => {
+                let Some((last, init)) = exprs.split_last() else {
+                    return Ok(TailEval::Value(Value::Unit));
+                };
+                self.scope_stack.push_scope();
+                for e in init {
+                    self.eval_fast(e)?;
+                }
+                let result = self.eval_fast_tail(last, fn_def);
+                self.scope_stack.pop_scope();
+                result
+            }
+
+            Expr::Let { name, value, body, .. } => {
+                let val = self.eval_fast(value)?;
+                self.scope_stack.define(name.clone(), val);
+                self.eval_fast_tail(body, fn_def)
+            }
+
+            Expr::Match { expr: match_expr, arms } => {
+                let val = self.eval_fast(match_expr)?;
+                for arm in arms {
+                    if let Some(bindings) = self.match_pattern(&arm.pattern.node, &val) {
+                        self.scope_stack.push_scope();
+                        for (name, bound_val) in bindings {
+                            self.scope_stack.define(name, bound_val);
+                        }
+                        if let Some(guard) = &arm.guard
+                            && !self.eval_fast(guard)?.is_truthy()
+                        {
+                            self.scope_stack.pop_scope();
+                            continue;
+                        }
+                        let result = self.eval_fast_tail(&arm.body, fn_def);
+                        self.scope_stack.pop_scope();
+                        return result;
+                    }
+                }
+                Err(RuntimeError::no_matching_arm(&val))
+            }
+
+            Expr::Return { value: Some(v) } => self.eval_fast_tail(v, fn_def),
+
+            _ => self.eval_fast(expr).map(TailEval::Value),
+        }
+    }
 }
 
 impl Default for Interpreter {
@@ -1627,27 +2419,177 @@ impl Default for Interpreter {
     }
 }
 
+/// The closure bound to `name` in `env`, which a call to `name` goes
+/// through instead of any function of that name
+fn closure_named(env: &EnvRef, name: &str) -> Option<Rc<Closure>> {
+    match env.borrow().get(name) {
+        Some(Value::Closure(closure)) => Some(closure),
+        _ => None,
+    }
+}
+
+/// `arr[i]` on an array, or on a string for the byte at `i`
+fn index_value(arr_val: Value, idx_val: Value) -> InterpResult<Value> {
+    let idx = match idx_val {
+        Value::Int(n) => n,
+        _ => return Err(RuntimeError::type_error("integer", idx_val.type_name())),
+    };
+    let (container, len) = match &arr_val {
+        Value::Array(arr) => ("array", arr.len()),
+        Value::Str(s) => ("string", s.len()),
+        // v0.93: Handle StringRope (lazy concatenated strings)
+        Value::StringRope(_) => {
+            let s = arr_val.materialize_string()
+                .ok_or_else(|| RuntimeError::type_error("string", "invalid StringRope"))?;
+            return index_value(Value::Str(Rc::new(s)), Value::Int(idx));
+        }
+        _ => return Err(RuntimeError::type_error("array or string", arr_val.type_name())),
+    };
+    let Ok(i) = usize::try_from(idx) else {
+        return Err(RuntimeError::negative_index(container, idx));
+    };
+    if i >= len {
+        return Err(RuntimeError::index_out_of_bounds_in(container, idx, len));
+    }
+    Ok(match arr_val {
+        Value::Array(arr) => arr[i].clone(),
+        Value::Str(s) => Value::Int(s.as_bytes()[i] as i64),
+        _ => unreachable!("checked above"),
+    })
+}
+
+/// One step below the root variable of an assignment target
+enum PlaceStep<'a> {
+    /// `[i]`, with the evaluated index and its span
+    Index(Value, Span),
+    /// `.field`
+    Field(&'a str),
+}
+
+/// Root variable and steps, outermost first, of the assignment target
+/// `target`, evaluating its indices in order with `eval`
+fn place_path<'a>(
+    target: &'a Spanned<Expr>,
+    eval: &mut impl FnMut(&Spanned<Expr>) -> InterpResult<Value>,
+) -> InterpResult<(&'a str, Vec<PlaceStep<'a>>)> {
+    match &target.node {
+        Expr::Var(root) => Ok((root, Vec::new())),
+        Expr::Index { expr, index } => {
+            let (root, mut steps) = place_path(expr, eval)?;
+            steps.push(PlaceStep::Index(eval(index)?, index.span));
+            Ok((root, steps))
+        }
+        Expr::FieldAccess { expr, field } => {
+            let (root, mut steps) = place_path(expr, eval)?;
+            steps.push(PlaceStep::Field(&field.node));
+            Ok((root, steps))
+        }
+        _ => Err(RuntimeError::type_error("variable", "expression")),
+    }
+}
+
+/// Replace the part of `target` at the path `steps` with `value`, with the
+/// bounds checks of `index_value`
+fn store_place(target: &mut Value, steps: &[PlaceStep], value: Value) -> InterpResult<()> {
+    let Some((step, rest)) = steps.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    let elem = match step {
+        PlaceStep::Index(idx_val, span) => {
+            let Value::Array(arr) = target else {
+                return Err(RuntimeError::type_error("array", target.type_name()).with_span(*span));
+            };
+            let idx = match idx_val {
+                Value::Int(n) => *n,
+                _ => return Err(RuntimeError::type_error("integer", idx_val.type_name()).with_span(*span)),
+            };
+            let len = arr.len();
+            let Ok(i) = usize::try_from(idx) else {
+                return Err(RuntimeError::negative_index("array", idx).with_span(*span));
+            };
+            arr.get_mut(i).ok_or_else(|| RuntimeError::index_out_of_bounds_in("array", idx, len).with_span(*span))?
+        }
+        PlaceStep::Field(name) => {
+            let Value::Struct(_, fields) = target else {
+                return Err(RuntimeError::type_error("struct", target.type_name()));
+            };
+            fields.get_mut(*name).ok_or_else(|| RuntimeError::type_error("field", name))?
+        }
+    };
+    store_place(elem, rest, value)
+}
+
+/// Add the fields of struct value `base` not already in `fields`
+/// (`new P { x: 1, ..base }`)
+fn fill_from_base(fields: &mut HashMap<String, Value>, base: Value) -> InterpResult<()> {
+    let Value::Struct(_, base_fields) = base else {
+        return Err(RuntimeError::type_error("struct", base.type_name()));
+    };
+    for (name, value) in base_fields {
+        fields.entry(name).or_insert(value);
+    }
+    Ok(())
+}
+
+/// `HashMap<K, V>` methods; lookups produce `Option<V>` values
+fn eval_map_method(map: &RefCell<HashMap<MapKey, Value>>, method: &str, args: Vec<Value>) -> InterpResult<Value> {
+    let arity = match method {
+        "insert" => 2,
+        "get" | "contains" | "remove" => 1,
+        "len" => 0,
+        _ => return Err(RuntimeError::undefined_function(&format!("HashMap.{}", method))),
+    };
+    if args.len() != arity {
+        return Err(RuntimeError::arity_mismatch(method, arity, args.len()));
+    }
+    if method == "len" {
+        return Ok(Value::Int(map.borrow().len() as i64));
+    }
+
+    let mut args = args.into_iter();
+    let key_val = args.next().unwrap_or(Value::Unit);
+    let key = MapKey::from_value(&key_val)
+        .ok_or_else(|| RuntimeError::type_error("hashable key", key_val.type_name()))?;
+    let found = match method {
+        "insert" => map.borrow_mut().insert(key, args.next().unwrap_or(Value::Unit)),
+        "remove" => map.borrow_mut().remove(&key),
+        "contains" => return Ok(Value::Bool(map.borrow().contains_key(&key))),
+        _ => map.borrow().get(&key).cloned(),
+    };
+    Ok(match found {
+        Some(v) => Value::Enum("Option".to_string(), "Some".to_string(), vec![v]),
+        None => Value::Enum("Option".to_string(), "None".to_string(), vec![]),
+    })
+}
+
 // ============ Built-in Functions ============
 
-fn builtin_print(args: &[Value]) -> InterpResult<Value> {
+/// Write `args` space-separated, strings and chars without their quotes
+fn write_printed(args: &[Value]) -> InterpResult<()> {
     for (i, arg) in args.iter().enumerate() {
         if i > 0 {
-            print!(" ");
+            write_stdout(format_args!(" "))?;
+        }
+        match arg {
+            Value::Char(c) => write_stdout(format_args!("{c}"))?,
+            _ => match arg.materialize_string() {
+                Some(s) => write_stdout(format_args!("{s}"))?,
+                None => write_stdout(format_args!("{arg}"))?,
+            },
         }
-        print!("{arg}");
     }
-    io::stdout().flush().map_err(|e| RuntimeError::io_error(&e.to_string()))?;
+    Ok(())
+}
+
+fn builtin_print(args: &[Value]) -> InterpResult<Value> {
+    write_printed(args)?;
     Ok(Value::Unit)
 }
 
 fn builtin_println(args: &[Value]) -> InterpResult<Value> {
-    for (i, arg) in args.iter().enumerate() {
-        if i > 0 {
-            print!(" ");
-        }
-        print!("{arg}");
-    }
-    println!();
+    write_printed(args)?;
+    write_stdout(format_args!("\n"))?;
     Ok(Value::Unit)
 }
 
@@ -1660,8 +2602,7 @@ fn builtin_print_str(args: &[Value]) -> InterpResult<Value> {
     }
     // v0.35.5: Handle both Value::Str and Value::StringRope using materialize_string
     if let Some(s) = args[0].materialize_string() {
-        print!("{}", s);
-        io::stdout().flush().map_err(|e| RuntimeError::io_error(&e.to_string()))?;
+        write_stdout(format_args!("{}", s))?;
         Ok(Value::Int(0))
     } else {
         Err(RuntimeError::type_error("String", args[0].type_name()))
@@ -1676,13 +2617,23 @@ fn builtin_println_str(args: &[Value]) -> InterpResult<Value> {
         return Err(RuntimeError::arity_mismatch("println_str", 1, args.len()));
     }
     if let Some(s) = args[0].materialize_string() {
-        println!("{}", s);
+        write_stdout(format_args!("{}\n", s))?;
         Ok(Value::Unit)
     } else {
         Err(RuntimeError::type_error("String", args[0].type_name()))
     }
 }
 
+/// flush() -> Unit
+/// Writes out buffered `print`/`println` output immediately.
+fn builtin_flush(args: &[Value]) -> InterpResult<Value> {
+    if !args.is_empty() {
+        return Err(RuntimeError::arity_mismatch("flush", 0, args.len()));
+    }
+    flush_stdout()?;
+    Ok(Value::Unit)
+}
+
 fn builtin_assert(args: &[Value]) -> InterpResult<Value> {
     if args.is_empty() {
         return Err(RuntimeError::arity_mismatch("assert", 1, 0));
@@ -1693,7 +2644,31 @@ fn builtin_assert(args: &[Value]) -> InterpResult<Value> {
     Ok(Value::Unit)
 }
 
+/// assert_eq(a, b): fails with both rendered values when they differ
+fn builtin_assert_eq(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("assert_eq", 2, args.len()));
+    }
+    if args[0] != args[1] {
+        return Err(RuntimeError::assertion_mismatch(args[0].to_string(), args[1].to_string(), true));
+    }
+    Ok(Value::Unit)
+}
+
+/// assert_ne(a, b): fails with both rendered values when they are equal
+fn builtin_assert_ne(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("assert_ne", 2, args.len()));
+    }
+    if args[0] == args[1] {
+        return Err(RuntimeError::assertion_mismatch(args[0].to_string(), args[1].to_string(), false));
+    }
+    Ok(Value::Unit)
+}
+
 fn builtin_read_int(_args: &[Value]) -> InterpResult<Value> {
+    // A prompt printed before the read must be visible while waiting
+    flush_stdout()?;
     let stdin = io::stdin();
     let line = stdin
         .lock()
@@ -1730,31 +2705,89 @@ fn builtin_min(args: &[Value]) -> InterpResult<Value> {
     }
 }
 
-fn builtin_max(args: &[Value]) -> InterpResult<Value> {
-    if args.len() != 2 {
-        return Err(RuntimeError::arity_mismatch("max", 2, args.len()));
-    }
-    match (&args[0], &args[1]) {
-        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(*a.max(b))),
-        (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a.max(*b))),
-        _ => Err(RuntimeError::type_error("numeric", "mixed types")),
-    }
+fn builtin_max(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("max", 2, args.len()));
+    }
+    match (&args[0], &args[1]) {
+        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(*a.max(b))),
+        (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a.max(*b))),
+        _ => Err(RuntimeError::type_error("numeric", "mixed types")),
+    }
+}
+
+// ============ v0.34: Math Intrinsics for Phase 34.4 Benchmark Gate ============
+
+/// sqrt(x: f64) -> f64
+/// Returns the square root of a floating-point number.
+/// Returns NaN for negative inputs.
+fn builtin_sqrt(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 1 {
+        return Err(RuntimeError::arity_mismatch("sqrt", 1, args.len()));
+    }
+    match &args[0] {
+        Value::Float(f) => Ok(Value::Float(f.sqrt())),
+        Value::Int(n) => Ok(Value::Float((*n as f64).sqrt())),
+        _ => Err(RuntimeError::type_error("f64", args[0].type_name())),
+    }
+}
+
+/// Extract an f64 argument for a unary math builtin
+fn f64_arg(name: &str, args: &[Value]) -> InterpResult<f64> {
+    if args.len() != 1 {
+        return Err(RuntimeError::arity_mismatch(name, 1, args.len()));
+    }
+    match &args[0] {
+        Value::Float(f) => Ok(*f),
+        Value::Int(n) => Ok(*n as f64),
+        _ => Err(RuntimeError::type_error("f64", args[0].type_name())),
+    }
+}
+
+/// sin(x: f64) -> f64 (x in radians)
+fn builtin_sin(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("sin", args)?.sin()))
+}
+
+/// cos(x: f64) -> f64 (x in radians)
+fn builtin_cos(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("cos", args)?.cos()))
+}
+
+/// floor(x: f64) -> f64
+fn builtin_floor(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("floor", args)?.floor()))
+}
+
+/// ceil(x: f64) -> f64
+fn builtin_ceil(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("ceil", args)?.ceil()))
+}
+
+/// round(x: f64) -> f64
+/// Rounds half away from zero (same as llvm.round.f64).
+fn builtin_round(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("round", args)?.round()))
+}
+
+/// ln(x: f64) -> f64 (natural logarithm)
+fn builtin_ln(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("ln", args)?.ln()))
 }
 
-// ============ v0.34: Math Intrinsics for Phase 34.4 Benchmark Gate ============
+/// exp(x: f64) -> f64 (e^x)
+fn builtin_exp(args: &[Value]) -> InterpResult<Value> {
+    Ok(Value::Float(f64_arg("exp", args)?.exp()))
+}
 
-/// sqrt(x: f64) -> f64
-/// Returns the square root of a floating-point number.
-/// Returns NaN for negative inputs.
-fn builtin_sqrt(args: &[Value]) -> InterpResult<Value> {
-    if args.len() != 1 {
-        return Err(RuntimeError::arity_mismatch("sqrt", 1, args.len()));
-    }
-    match &args[0] {
-        Value::Float(f) => Ok(Value::Float(f.sqrt())),
-        Value::Int(n) => Ok(Value::Float((*n as f64).sqrt())),
-        _ => Err(RuntimeError::type_error("f64", args[0].type_name())),
+/// pow(base: f64, exp: f64) -> f64
+fn builtin_pow(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("pow", 2, args.len()));
     }
+    let base = f64_arg("pow", &args[..1])?;
+    let exp = f64_arg("pow", &args[1..])?;
+    Ok(Value::Float(base.powf(exp)))
 }
 
 /// i64_to_f64(x: i64) -> f64
@@ -2270,16 +3303,17 @@ fn builtin_hash_i64(args: &[Value]) -> InterpResult<Value> {
         return Err(RuntimeError::arity_mismatch("hash_i64", 1, args.len()));
     }
     match &args[0] {
-        Value::Int(x) => {
-            // FNV-1a inspired hash: multiply by prime, xor with shifted value
-            let h = (*x as u64).wrapping_mul(0x517cc1b727220a95);
-            let result = (h ^ (h >> 32)) as i64;
-            Ok(Value::Int(result))
-        }
+        Value::Int(x) => Ok(Value::Int(hash_i64(*x))),
         _ => Err(RuntimeError::type_error("i64", args[0].type_name())),
     }
 }
 
+/// FNV-1a inspired hash: multiply by prime, xor with shifted value
+fn hash_i64(x: i64) -> i64 {
+    let h = (x as u64).wrapping_mul(0x517cc1b727220a95);
+    (h ^ (h >> 32)) as i64
+}
+
 // ============ v0.34.24: HashMap Builtins ============
 // Layout: [count: i64, capacity: i64, keys_ptr: i64, values_ptr: i64, states_ptr: i64]
 // Header: 40 bytes (5 * 8)
@@ -2828,6 +3862,8 @@ fn builtin_exec(args: &[Value]) -> InterpResult<Value> {
     match (extract_string(&args[0]), extract_string(&args[1])) {
         (Some(command), Some(args_str)) => {
             let parsed_args = parse_args(&args_str);
+            // Output printed so far goes before the child's
+            flush_stdout()?;
             match Command::new(&command).args(&parsed_args).status() {
                 Ok(status) => {
                     Ok(Value::Int(status.code().unwrap_or(-1) as i64))
@@ -2851,6 +3887,8 @@ fn builtin_exec_output(args: &[Value]) -> InterpResult<Value> {
     match (extract_string(&args[0]), extract_string(&args[1])) {
         (Some(command), Some(args_str)) => {
             let parsed_args = parse_args(&args_str);
+            // The child's stderr still reaches the terminal
+            flush_stdout()?;
             match Command::new(&command).args(&parsed_args).output() {
                 Ok(output) => {
                     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -2874,6 +3912,8 @@ fn builtin_system(args: &[Value]) -> InterpResult<Value> {
     }
     match extract_string(&args[0]) {
         Some(command) => {
+            // Output printed so far goes before the child's
+            flush_stdout()?;
             // Use platform-specific shell
             #[cfg(windows)]
             let result = Command::new("cmd").args(["/C", &command]).status();
@@ -3148,6 +4188,18 @@ fn builtin_char_to_string(args: &[Value]) -> InterpResult<Value> {
     }
 }
 
+/// int_to_string(n: i64) -> String
+/// Decimal digits of n, with a leading '-' if negative.
+fn builtin_int_to_string(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 1 {
+        return Err(RuntimeError::arity_mismatch("int_to_string", 1, args.len()));
+    }
+    match &args[0] {
+        Value::Int(n) => Ok(Value::Str(Rc::new(n.to_string()))),
+        _ => Err(RuntimeError::type_error("i64", args[0].type_name())),
+    }
+}
+
 /// str_len(s: String) -> i64
 /// Returns the Unicode character count of a string.
 /// Note: This is O(n) for UTF-8. Use s.len() for O(1) byte length.
@@ -3166,6 +4218,220 @@ fn builtin_str_len(args: &[Value]) -> InterpResult<Value> {
     }
 }
 
+/// String arguments of a builtin, checking arity and types
+fn string_args(name: &str, args: &[Value], arity: usize) -> InterpResult<Vec<String>> {
+    if args.len() != arity {
+        return Err(RuntimeError::arity_mismatch(name, arity, args.len()));
+    }
+    args.iter()
+        .map(|arg| extract_string(arg).ok_or_else(|| RuntimeError::type_error("String", arg.type_name())))
+        .collect()
+}
+
+/// str_contains(s: String, needle: String) -> bool
+fn builtin_str_contains(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_contains", args, 2)?;
+    Ok(Value::Bool(a[0].contains(a[1].as_str())))
+}
+
+/// str_starts_with(s: String, prefix: String) -> bool
+fn builtin_str_starts_with(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_starts_with", args, 2)?;
+    Ok(Value::Bool(a[0].starts_with(a[1].as_str())))
+}
+
+/// str_ends_with(s: String, suffix: String) -> bool
+fn builtin_str_ends_with(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_ends_with", args, 2)?;
+    Ok(Value::Bool(a[0].ends_with(a[1].as_str())))
+}
+
+/// str_index_of(s: String, needle: String) -> i64
+/// Byte offset of the first occurrence (usable with s.slice), or -1 when absent.
+fn builtin_str_index_of(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_index_of", args, 2)?;
+    Ok(Value::Int(a[0].find(a[1].as_str()).map_or(-1, |i| i as i64)))
+}
+
+/// str_replace(s: String, from: String, to: String) -> String
+/// Replaces every occurrence of `from`; an empty `from` leaves `s` unchanged.
+fn builtin_str_replace(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_replace", args, 3)?;
+    if a[1].is_empty() {
+        return Ok(Value::Str(Rc::new(a[0].clone())));
+    }
+    Ok(Value::Str(Rc::new(a[0].replace(a[1].as_str(), &a[2]))))
+}
+
+/// str_trim(s: String) -> String
+/// Strips leading and trailing ASCII whitespace, like the native runtime.
+fn builtin_str_trim(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_trim", args, 1)?;
+    Ok(Value::Str(Rc::new(a[0].trim_matches(|c: char| c.is_ascii_whitespace()).to_string())))
+}
+
+/// Value returned by `parse_int` for malformed or out-of-range input
+const PARSE_INT_ERROR: i64 = i64::MIN;
+
+/// parse_int(s: String) -> i64
+/// Decimal integer with an optional sign and no surrounding whitespace;
+/// [`PARSE_INT_ERROR`] otherwise.
+fn builtin_parse_int(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("parse_int", args, 1)?;
+    let s = a[0].as_str();
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let valid = !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+    Ok(Value::Int(if valid { s.parse().unwrap_or(PARSE_INT_ERROR) } else { PARSE_INT_ERROR }))
+}
+
+thread_local! {
+    /// Strings handed out by id (`str_split` elements, `*_str` vec and map
+    /// values), read back with `str_from_id`
+    static STRING_IDS: SbRefCell<Vec<Rc<String>>> = const { SbRefCell::new(Vec::new()) };
+    /// Id shared by every string used as a hashmap key with the same contents
+    static STRING_KEY_IDS: SbRefCell<HashMap<String, i64>> = SbRefCell::new(HashMap::new());
+}
+
+/// Store `s` under a fresh id
+fn store_string(s: Rc<String>) -> i64 {
+    STRING_IDS.with(|ids| {
+        let mut ids = ids.borrow_mut();
+        ids.push(s);
+        ids.len() as i64
+    })
+}
+
+/// String stored under `id`
+fn stored_string(name: &str, id: i64) -> InterpResult<Rc<String>> {
+    STRING_IDS
+        .with(|ids| usize::try_from(id - 1).ok().and_then(|i| ids.borrow().get(i).cloned()))
+        .ok_or_else(|| RuntimeError::io_error(&format!("{}: unknown string id {}", name, id)))
+}
+
+/// Id of `key` as a hashmap key, stored on first use
+fn string_key_id(key: &str) -> i64 {
+    if let Some(id) = existing_string_key_id(key) {
+        return id;
+    }
+    let id = store_string(Rc::new(key.to_string()));
+    STRING_KEY_IDS.with(|keys| keys.borrow_mut().insert(key.to_string(), id));
+    id
+}
+
+/// Id of `key` if it was ever used as a hashmap key
+fn existing_string_key_id(key: &str) -> Option<i64> {
+    STRING_KEY_IDS.with(|keys| keys.borrow().get(key).copied())
+}
+
+/// String argument `index` of a builtin that also takes other types
+fn string_arg(args: &[Value], index: usize) -> InterpResult<String> {
+    extract_string(&args[index]).ok_or_else(|| RuntimeError::type_error("String", args[index].type_name()))
+}
+
+/// str_split(s: String, sep: String) -> i64
+/// Returns a vector (see `vec_*`) of string ids, one per piece. An empty
+/// separator yields `s` as the only piece.
+fn builtin_str_split(args: &[Value]) -> InterpResult<Value> {
+    let a = string_args("str_split", args, 2)?;
+    let pieces: Vec<&str> = if a[1].is_empty() { vec![a[0].as_str()] } else { a[0].split(a[1].as_str()).collect() };
+    let vec = builtin_vec_new(&[])?;
+    for piece in pieces {
+        let id = store_string(Rc::new(piece.to_string()));
+        builtin_vec_push(&[vec.clone(), Value::Int(id)])?;
+    }
+    Ok(vec)
+}
+
+/// str_from_id(id: i64) -> String
+/// String for an id stored by `str_split`.
+fn builtin_str_from_id(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 1 {
+        return Err(RuntimeError::arity_mismatch("str_from_id", 1, args.len()));
+    }
+    match &args[0] {
+        Value::Int(id) => stored_string("str_from_id", *id).map(Value::Str),
+        other => Err(RuntimeError::type_error("i64", other.type_name())),
+    }
+}
+
+/// vec_push_str(vec: i64, value: String) -> Unit
+/// Appends the id of a copy of `value`.
+fn builtin_vec_push_str(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("vec_push_str", 2, args.len()));
+    }
+    let id = store_string(Rc::new(string_arg(args, 1)?));
+    builtin_vec_push(&[args[0].clone(), Value::Int(id)])
+}
+
+/// vec_get_str(vec: i64, index: i64) -> String
+fn builtin_vec_get_str(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("vec_get_str", 2, args.len()));
+    }
+    match builtin_vec_get(args)? {
+        Value::Int(id) => stored_string("vec_get_str", id).map(Value::Str),
+        other => Err(RuntimeError::type_error("i64", other.type_name())),
+    }
+}
+
+/// hashmap_insert_str(map: i64, key: i64, value: String) -> Unit
+fn builtin_hashmap_insert_str(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 3 {
+        return Err(RuntimeError::arity_mismatch("hashmap_insert_str", 3, args.len()));
+    }
+    let id = store_string(Rc::new(string_arg(args, 2)?));
+    builtin_hashmap_insert(&[args[0].clone(), args[1].clone(), Value::Int(id)])?;
+    Ok(Value::Unit)
+}
+
+/// hashmap_get_str(map: i64, key: i64) -> String
+/// Returns "" if not found
+fn builtin_hashmap_get_str(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("hashmap_get_str", 2, args.len()));
+    }
+    match builtin_hashmap_get(args)? {
+        Value::Int(i64::MIN) => Ok(Value::Str(Rc::new(String::new()))),
+        Value::Int(id) => stored_string("hashmap_get_str", id).map(Value::Str),
+        other => Err(RuntimeError::type_error("i64", other.type_name())),
+    }
+}
+
+/// hashmap_insert_str_key(map: i64, key: String, value: i64) -> i64
+/// Returns previous value if key existed, or 0 if new
+fn builtin_hashmap_insert_str_key(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 3 {
+        return Err(RuntimeError::arity_mismatch("hashmap_insert_str_key", 3, args.len()));
+    }
+    let key = string_key_id(&string_arg(args, 1)?);
+    builtin_hashmap_insert(&[args[0].clone(), Value::Int(key), args[2].clone()])
+}
+
+/// hashmap_get_str_key(map: i64, key: String) -> i64
+/// Returns value if found, or i64::MIN if not found
+fn builtin_hashmap_get_str_key(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("hashmap_get_str_key", 2, args.len()));
+    }
+    match existing_string_key_id(&string_arg(args, 1)?) {
+        Some(key) => builtin_hashmap_get(&[args[0].clone(), Value::Int(key)]),
+        None => Ok(Value::Int(i64::MIN)),
+    }
+}
+
+/// hashmap_contains_str_key(map: i64, key: String) -> i64
+/// Returns 1 if key exists, 0 otherwise
+fn builtin_hashmap_contains_str_key(args: &[Value]) -> InterpResult<Value> {
+    if args.len() != 2 {
+        return Err(RuntimeError::arity_mismatch("hashmap_contains_str_key", 2, args.len()));
+    }
+    match existing_string_key_id(&string_arg(args, 1)?) {
+        Some(key) => builtin_hashmap_contains(&[args[0].clone(), Value::Int(key)]),
+        None => Ok(Value::Int(0)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3262,6 +4528,107 @@ mod tests {
     }
 
 
+    fn int_binary(left: i64, op: BinOp, right: i64) -> Spanned<Expr> {
+        Spanned {
+            node: Expr::Binary {
+                left: Box::new(spanned(Expr::IntLit(left))),
+                op,
+                right: Box::new(spanned(Expr::IntLit(right))),
+            },
+            span: Span { start: 4, end: 9 },
+        }
+    }
+
+    #[test]
+    fn test_add_overflow_detected() {
+        let mut interp = Interpreter::new();
+        let env = interp.global_env.clone();
+
+        let err = interp.eval(&int_binary(i64::MAX, BinOp::Add, 1), &env).unwrap_err();
+        assert_eq!(err.kind, crate::interp::ErrorKind::ArithmeticOverflow);
+        assert_eq!(err.message, "integer overflow in `9223372036854775807 + 1`");
+        assert_eq!(err.span, Some(Span { start: 4, end: 9 }));
+
+        let err = interp.eval(&int_binary(i64::MIN, BinOp::Sub, 1), &env).unwrap_err();
+        assert_eq!(err.message, "integer overflow in `-9223372036854775808 - 1`");
+    }
+
+    /// Interpreter that treats `int_binary` as arithmetic on `ty`
+    fn typed_interp(ty: Type) -> Interpreter {
+        let mut interp = Interpreter::new();
+        interp.set_int_op_types(HashMap::from([(Span { start: 4, end: 9 }, ty)]));
+        interp
+    }
+
+    #[test]
+    fn test_unsigned_boundary_overflow_detected() {
+        // u64 values share the i64 bit pattern, so u64::MAX is stored as -1
+        let mut interp = typed_interp(Type::U64);
+        let env = interp.global_env.clone();
+        let err = interp.eval(&int_binary(-1, BinOp::Add, 1), &env).unwrap_err();
+        assert_eq!(err.kind, crate::interp::ErrorKind::ArithmeticOverflow);
+        assert_eq!(err.message, "integer overflow in `18446744073709551615 + 1`");
+        let err = interp.eval(&int_binary(0, BinOp::Sub, 1), &env).unwrap_err();
+        assert_eq!(err.message, "integer overflow in `0 - 1`");
+        // Past i64::MAX is still in range for u64
+        let half = 1i64 << 62;
+        assert_eq!(interp.eval(&int_binary(half, BinOp::Mul, 2), &env).unwrap(), Value::Int(i64::MIN));
+
+        let mut interp = typed_interp(Type::U32);
+        let env = interp.global_env.clone();
+        let err = interp.eval(&int_binary(4_000_000_000, BinOp::Add, 1_000_000_000), &env).unwrap_err();
+        assert_eq!(err.message, "integer overflow in `4000000000 + 1000000000`");
+        assert_eq!(interp.eval(&int_binary(65536, BinOp::Mul, 65535), &env).unwrap(), Value::Int(4294901760));
+
+        let mut interp = typed_interp(Type::I32);
+        let env = interp.global_env.clone();
+        let err = interp.eval(&int_binary(i32::MAX as i64, BinOp::Add, 1), &env).unwrap_err();
+        assert_eq!(err.message, "integer overflow in `2147483647 + 1`");
+    }
+
+    #[test]
+    fn test_unsigned_overflow_wraps_at_its_width_when_unchecked() {
+        let mut interp = typed_interp(Type::U32);
+        interp.set_overflow_checks(false);
+        let env = interp.global_env.clone();
+        assert_eq!(
+            interp.eval(&int_binary(4_000_000_000, BinOp::Add, 1_000_000_000), &env).unwrap(),
+            Value::Int(705_032_704)
+        );
+        assert_eq!(interp.eval(&int_binary(0, BinOp::Sub, 1), &env).unwrap(), Value::Int(u32::MAX as i64));
+    }
+
+    #[test]
+    fn test_explicit_arithmetic_unaffected_by_overflow_checks() {
+        let mut interp = Interpreter::new();
+        let env = interp.global_env.clone();
+
+        assert_eq!(
+            interp.eval(&int_binary(i64::MAX, BinOp::AddWrap, 1), &env).unwrap(),
+            Value::Int(i64::MIN)
+        );
+        assert_eq!(
+            interp.eval(&int_binary(i64::MAX, BinOp::AddSat, 1), &env).unwrap(),
+            Value::Int(i64::MAX)
+        );
+        assert_eq!(
+            interp.eval(&int_binary(i64::MAX, BinOp::AddChecked, 1), &env).unwrap(),
+            Value::Enum("Option".to_string(), "None".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_overflow_checks_disabled_wraps() {
+        let mut interp = Interpreter::new();
+        interp.set_overflow_checks(false);
+        let env = interp.global_env.clone();
+
+        assert_eq!(
+            interp.eval(&int_binary(i64::MAX, BinOp::Add, 1), &env).unwrap(),
+            Value::Int(i64::MIN)
+        );
+    }
+
     #[test]
     fn test_eval_string() {
         let mut interp = Interpreter::new();
@@ -3334,4 +4701,327 @@ mod tests {
             Value::Bool(true)
         );
     }
+
+    fn run_source(interp: &mut Interpreter, source: &str) -> InterpResult<Value> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        interp.run(&program)
+    }
+
+    #[test]
+    fn test_tail_call_through_if_runs_in_constant_stack() {
+        let source = "
+            fn sum(n: i64, acc: i64) -> i64 = if n == 0 { acc } else { sum(n - 1, acc + n) };
+            fn main() -> i64 = sum(1000000, 0);
+        ";
+        let mut interp = Interpreter::new();
+        assert_eq!(run_source(&mut interp, source).unwrap(), Value::Int(500000500000));
+    }
+
+    #[test]
+    fn test_tail_call_through_match_and_block() {
+        let source = "
+            fn count(n: i64, acc: i64) -> i64 = {
+                let next = acc + 1;
+                match n {
+                    0 => acc,
+                    _ => count(n - 1, next)
+                }
+            };
+            fn main() -> i64 = count(1000000, 0);
+        ";
+        let mut interp = Interpreter::new();
+        assert_eq!(run_source(&mut interp, source).unwrap(), Value::Int(1000000));
+    }
+
+    #[test]
+    fn test_tail_call_with_scope_stack() {
+        let source = "
+            fn sum(n: i64, acc: i64) -> i64 = if n == 0 { acc } else { sum(n - 1, acc + n) };
+        ";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mut interp = Interpreter::new();
+        interp.load(&program);
+        interp.enable_scope_stack();
+        let result = interp.call_function_with_args("sum", vec![Value::Int(1000000), Value::Int(0)]);
+        assert_eq!(result.unwrap(), Value::Int(500000500000));
+    }
+
+    #[test]
+    fn test_labeled_break_exits_two_loops() {
+        // `break 'outer` leaves both loops from the innermost one
+        let source = "
+            fn main() -> i64 = {
+                let mut hits: i64 = 0;
+                'outer: for i in 0..<10 {
+                    for j in 0..<10 {
+                        loop { {
+                            if i * 10 + j == 23 { break 'outer } else { () };
+                            hits = hits + 1;
+                            break
+                        } }
+                    }
+                };
+                hits
+            };
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(23));
+    }
+
+    #[test]
+    fn test_labeled_continue_skips_rest_of_outer_body() {
+        // Row i counts i cells, and `continue 'rows` skips the row tally
+        let source = "
+            fn main() -> i64 = {
+                let mut i: i64 = 0;
+                let mut rows: i64 = 0;
+                let mut cells: i64 = 0;
+                'rows: while i < 5 { {
+                    i = i + 1;
+                    let mut j: i64 = 0;
+                    while true { {
+                        j = j + 1;
+                        if j > i { continue 'rows } else { () };
+                        cells = cells + 1
+                    } };
+                    rows = rows + 1
+                } };
+                rows * 100 + cells
+            };
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(15));
+    }
+
+    #[test]
+    fn test_unlabeled_break_targets_innermost_loop() {
+        let source = "
+            fn main() -> i64 = {
+                let mut outer: i64 = 0;
+                'a: for i in 0..<3 { {
+                    for j in 0..<3 {
+                        if j == 1 { break } else { () }
+                    };
+                    outer = outer + 1
+                } };
+                outer
+            };
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn test_return_unwinds_loops_on_both_paths() {
+        // `return` leaves the loop and the function, not just the loop
+        let source = "
+            fn find(limit: i64) -> i64 = {
+                let mut i: i64 = 0;
+                while true { {
+                    i = i + 1;
+                    if i * i > limit { return i } else { () }
+                } };
+                0
+            };
+            fn main() -> i64 = find(50) * 100 + find(3);
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(802));
+
+        let mut interp = Interpreter::new();
+        run_source(&mut interp, source).unwrap();
+        interp.enable_scope_stack();
+        let result = interp.call_function_with_args("find", vec![Value::Int(50)]);
+        assert_eq!(result.unwrap(), Value::Int(8));
+    }
+
+    #[test]
+    fn test_non_tail_self_call_keeps_semantics() {
+        // `1 + depth(..)` and the nested call argument are not tail calls
+        let source = "
+            fn depth(n: i64) -> i64 = if n == 0 { 0 } else { 1 + depth(n - 1) };
+            fn ack(m: i64, n: i64) -> i64 =
+                if m == 0 { n + 1 }
+                else if n == 0 { ack(m - 1, 1) }
+                else { ack(m - 1, ack(m, n - 1)) };
+            fn main() -> i64 = depth(1000) + ack(2, 3);
+        ";
+        let mut interp = Interpreter::new();
+        assert_eq!(run_source(&mut interp, source).unwrap(), Value::Int(1009));
+    }
+
+    #[test]
+    fn test_closures_capture_by_value() {
+        // `k` changes after the closure is made; the closure keeps the old value
+        let source = "
+            fn main() -> i64 = {
+                let mut k: i64 = 5;
+                let scale = fn |x: i64| { x * k };
+                k = k + 95;
+                let nest = fn |x: i64| { let inner = fn |y: i64| { y + k }; inner(x) };
+                scale(2) * 1000 + nest(1)
+            };
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(10101));
+    }
+
+    #[test]
+    fn test_closure_arity_is_checked() {
+        let source = "fn main() -> i64 = { let add = fn |a: i64, b: i64| { a + b }; add(1) };";
+        let err = run_source(&mut Interpreter::new(), source).unwrap_err();
+        assert!(err.message.contains("closure"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_match_guard_picks_between_same_pattern() {
+        // Both arms bind `n`; the guard decides, and a failed guard falls through
+        let source = "
+            fn sign(x: i64) -> i64 = match x {
+                n if n < 0 => 0 - 1,
+                n if n > 0 => 1,
+                _ => 0
+            };
+            fn main() -> i64 = sign(0 - 7) * 100 + sign(7) * 10 + sign(0);
+        ";
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(-90));
+    }
+
+    #[test]
+    fn test_match_with_every_guard_failing_is_an_error() {
+        let source = "
+            fn classify(x: i64) -> i64 = match x {
+                n if n < 0 => 1,
+                n if n > 10 => 2
+            };
+            fn main() -> i64 = classify(5);
+        ";
+        let err = run_source(&mut Interpreter::new(), source).unwrap_err();
+        assert_eq!(err.kind, crate::interp::ErrorKind::NoMatchingArm);
+        assert_eq!(err.message, "no match arm matches 5: every pattern or guard failed");
+        assert_eq!(err.code(), "E0715");
+    }
+
+    #[test]
+    fn test_runtime_error_records_call_stack() {
+        let source = "
+            fn check(n: i64) -> i64 = { assert(n < 3); n };
+            fn walk(n: i64) -> i64 = if n == 5 { 0 } else { check(n) + walk(n + 1) };
+            fn main() -> i64 = walk(0);
+        ";
+        let err = run_source(&mut Interpreter::new(), source).unwrap_err();
+        let names: Vec<&str> = err.frames.iter().map(|f| f.function.as_str()).collect();
+        assert_eq!(names, ["check", "walk", "walk", "walk", "walk", "main"]);
+        assert!(err.frames[0].call_site.is_some());
+        assert_eq!(err.frames[5].call_site, None);
+
+        // A later run starts with a fresh stack
+        let mut interp = Interpreter::new();
+        let _ = run_source(&mut interp, source);
+        let err = run_source(&mut interp, "fn main() -> i64 = 1 / 0;").unwrap_err();
+        assert_eq!(err.frames.len(), 1);
+    }
+
+    fn index_error(source: &str) -> RuntimeError {
+        run_source(&mut Interpreter::new(), source).unwrap_err()
+    }
+
+    #[test]
+    fn test_array_index_out_of_bounds() {
+        let source = "fn main() -> i64 = { let a = [1, 2, 3]; a[1 + 2] };";
+        let err = index_error(source);
+        assert_eq!(err.kind, crate::interp::ErrorKind::IndexOutOfBounds);
+        assert_eq!(err.message, "index 3 out of bounds for array of length 3");
+        let span = err.span.unwrap();
+        assert_eq!(&source[span.start..span.end], "1 + 2");
+    }
+
+    #[test]
+    fn test_negative_array_index() {
+        let err = index_error("fn main() -> i64 = { let a = [1, 2, 3]; a[0 - 1] };");
+        assert_eq!(err.kind, crate::interp::ErrorKind::IndexOutOfBounds);
+        assert_eq!(err.message, "negative array index: -1");
+
+        // i64::MIN must not wrap around into a valid usize
+        let err = index_error("fn main() -> i64 = { let a = [1, 2, 3]; a[0 - 9223372036854775807 - 1] };");
+        assert_eq!(err.message, "negative array index: -9223372036854775808");
+    }
+
+    #[test]
+    fn test_string_index_bounds() {
+        let source = r#"fn main() -> i64 = { let s = "héllo"; s[6] };"#;
+        let err = index_error(source);
+        assert_eq!(err.message, "index 6 out of bounds for string of length 6");
+        assert_eq!(index_error(r#"fn main() -> i64 = { let s = "abc"; s[0 - 2] };"#).message, "negative string index: -2");
+        assert_eq!(
+            run_source(&mut Interpreter::new(), r#"fn main() -> i64 = { let s = "héllo"; s[5] };"#).unwrap(),
+            Value::Int(b'o' as i64)
+        );
+    }
+
+    #[test]
+    fn test_index_bounds_in_scope_stack_path() {
+        let source = "fn get(i: i64) -> i64 = { let a = [10, 20]; a[i] };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mut interp = Interpreter::new();
+        interp.load(&program);
+        interp.enable_scope_stack();
+        assert_eq!(interp.call_function_with_args("get", vec![Value::Int(1)]).unwrap(), Value::Int(20));
+        let err = interp.call_function_with_args("get", vec![Value::Int(2)]).unwrap_err();
+        assert_eq!(err.message, "index 2 out of bounds for array of length 2");
+        let err = interp.call_function_with_args("get", vec![Value::Int(-3)]).unwrap_err();
+        assert_eq!(err.message, "negative array index: -3");
+    }
+
+    fn str_value(s: &str) -> Value {
+        Value::Str(Rc::new(s.to_string()))
+    }
+
+    #[test]
+    fn test_string_search_builtins() {
+        let s = str_value("let x = 42;");
+        assert_eq!(builtin_str_contains(&[s.clone(), str_value("x =")]).unwrap(), Value::Bool(true));
+        assert_eq!(builtin_str_contains(&[s.clone(), str_value("y")]).unwrap(), Value::Bool(false));
+        assert_eq!(builtin_str_starts_with(&[s.clone(), str_value("let ")]).unwrap(), Value::Bool(true));
+        assert_eq!(builtin_str_ends_with(&[s.clone(), str_value("2;")]).unwrap(), Value::Bool(true));
+        assert_eq!(builtin_str_ends_with(&[s.clone(), str_value("let")]).unwrap(), Value::Bool(false));
+        // Byte offsets, consistent with len() and slice()
+        assert_eq!(builtin_str_index_of(&[str_value("héllo"), str_value("l")]).unwrap(), Value::Int(3));
+        assert_eq!(builtin_str_index_of(&[s.clone(), str_value("z")]).unwrap(), Value::Int(-1));
+        assert!(builtin_str_contains(&[s, Value::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn test_string_transform_builtins() {
+        let replaced = builtin_str_replace(&[str_value("a-b--c"), str_value("-"), str_value("+")]).unwrap();
+        assert_eq!(replaced, str_value("a+b++c"));
+        let unchanged = builtin_str_replace(&[str_value("abc"), str_value(""), str_value("x")]).unwrap();
+        assert_eq!(unchanged, str_value("abc"));
+        assert_eq!(builtin_str_trim(&[str_value(" \t hi there\n")]).unwrap(), str_value("hi there"));
+    }
+
+    #[test]
+    fn test_parse_int_builtin() {
+        let parse = |s: &str| builtin_parse_int(&[str_value(s)]).unwrap();
+        assert_eq!(parse("42"), Value::Int(42));
+        assert_eq!(parse("-17"), Value::Int(-17));
+        assert_eq!(parse("+8"), Value::Int(8));
+        assert_eq!(parse("-9223372036854775808"), Value::Int(i64::MIN));
+        for bad in ["", "-", " 1", "1x", "9223372036854775808"] {
+            assert_eq!(parse(bad), Value::Int(PARSE_INT_ERROR), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_str_split_returns_vec_of_string_ids() {
+        let source = r#"
+            fn main() -> i64 = {
+                let parts = str_split("a,,bc", ",");
+                let last = str_from_id(vec_get(parts, 2));
+                vec_len(parts) * 10 + last.len()
+            };
+        "#;
+        assert_eq!(run_source(&mut Interpreter::new(), source).unwrap(), Value::Int(32));
+        let whole = builtin_str_split(&[str_value("abc"), str_value("")]).unwrap();
+        assert_eq!(builtin_vec_len(&[whole]).unwrap(), Value::Int(1));
+        assert!(builtin_str_from_id(&[Value::Int(0)]).is_err());
+    }
 }
@@ -1,13 +1,15 @@
 //! Interpreter module for BMB
 
+mod coverage;
 mod env;
 mod error;
 mod eval;
 mod scope;
 mod value;
 
+pub use coverage::{format_line_ranges, line_coverage, LineCoverage};
 pub use env::{child_env, EnvRef, Environment};
-pub use error::{ErrorKind, InterpResult, RuntimeError};
+pub use error::{ErrorKind, InterpResult, RuntimeError, StackFrame, MAX_TRACE_FRAMES};
 pub use eval::{set_program_args, BuiltinFn, Interpreter};
 pub use scope::ScopeStack;
-pub use value::Value;
+pub use value::{Closure, MapKey, Value};
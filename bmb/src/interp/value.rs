@@ -1,9 +1,12 @@
 //! Runtime values for the interpreter
 
+use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::ast::{Expr, Spanned};
+
 /// Runtime value
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -34,6 +37,74 @@ pub enum Value {
     Array(Vec<Value>),
     /// Tuple value (v0.42): heterogeneous fixed-size collection
     Tuple(Vec<Value>),
+    /// `HashMap<K, V>` value, shared by reference like the `hashmap_*` handles
+    Map(Rc<RefCell<HashMap<MapKey, Value>>>),
+    /// Closure value (v0.20.0)
+    Closure(Rc<Closure>),
+}
+
+/// A closure together with the values it captured when it was created.
+/// Captures are copies: later assignments on either side are not shared.
+#[derive(Debug)]
+pub struct Closure {
+    pub params: Vec<String>,
+    pub body: Spanned<Expr>,
+    pub captures: Vec<(String, Value)>,
+}
+
+/// Hashable subset of values usable as `HashMap` keys
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Int(i64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// `@derive(Hash, Eq)` struct: (type_name, fields sorted by name)
+    Struct(String, Vec<(String, MapKey)>),
+    /// `@derive(Hash, Eq)` enum: (enum_name, variant_name, values)
+    Enum(String, String, Vec<MapKey>),
+}
+
+impl MapKey {
+    /// Convert a value to a map key (strings and ropes compare by content)
+    pub fn from_value(value: &Value) -> Option<MapKey> {
+        match value {
+            Value::Int(n) => Some(MapKey::Int(*n)),
+            Value::Bool(b) => Some(MapKey::Bool(*b)),
+            Value::Char(c) => Some(MapKey::Char(*c)),
+            Value::Str(_) | Value::StringRope(_) => value.materialize_string().map(MapKey::Str),
+            Value::Struct(name, fields) => {
+                let mut keys = fields
+                    .iter()
+                    .map(|(field, v)| Some((field.clone(), MapKey::from_value(v)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                keys.sort_by(|a, b| a.0.cmp(&b.0));
+                Some(MapKey::Struct(name.clone(), keys))
+            }
+            Value::Enum(name, variant, args) => {
+                let keys = args.iter().map(MapKey::from_value).collect::<Option<_>>()?;
+                Some(MapKey::Enum(name.clone(), variant.clone(), keys))
+            }
+            _ => None,
+        }
+    }
+
+    /// Convert a map key back into a value
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Int(n) => Value::Int(*n),
+            MapKey::Bool(b) => Value::Bool(*b),
+            MapKey::Char(c) => Value::Char(*c),
+            MapKey::Str(s) => Value::Str(Rc::new(s.clone())),
+            MapKey::Struct(name, fields) => Value::Struct(
+                name.clone(),
+                fields.iter().map(|(field, k)| (field.clone(), k.to_value())).collect(),
+            ),
+            MapKey::Enum(name, variant, args) => {
+                Value::Enum(name.clone(), variant.clone(), args.iter().map(MapKey::to_value).collect())
+            }
+        }
+    }
 }
 
 impl Value {
@@ -54,6 +125,8 @@ impl Value {
             Value::Ref(r) => r.borrow().is_truthy(),
             Value::Array(arr) => !arr.is_empty(),
             Value::Tuple(_) => true, // Tuples are always truthy
+            Value::Map(map) => !map.borrow().is_empty(),
+            Value::Closure(_) => true,
         }
     }
 
@@ -74,6 +147,8 @@ impl Value {
             Value::Ref(_) => "&ref",
             Value::Array(_) => "array",
             Value::Tuple(_) => "tuple",
+            Value::Map(_) => "HashMap",
+            Value::Closure(_) => "closure",
         }
     }
 
@@ -224,6 +299,23 @@ impl fmt::Display for Value {
                 }
                 write!(f, ")")
             }
+            Value::Map(map) => {
+                // Sort entries so output is deterministic
+                let map = map.borrow();
+                let mut entries: Vec<_> = map.iter()
+                    .map(|(k, v)| (k.to_value().to_string(), v.to_string()))
+                    .collect();
+                entries.sort();
+                write!(f, "{{")?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", k, v)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Closure(closure) => write!(f, "<closure |{}|>", closure.params.join(", ")),
         }
     }
 }
@@ -266,6 +358,8 @@ impl PartialEq for Value {
             (Value::Array(a1), Value::Array(a2)) => a1 == a2,
             // v0.42: Tuple equality
             (Value::Tuple(t1), Value::Tuple(t2)) => t1 == t2,
+            (Value::Map(m1), Value::Map(m2)) => *m1.borrow() == *m2.borrow(),
+            (Value::Closure(c1), Value::Closure(c2)) => Rc::ptr_eq(c1, c2),
             _ => false,
         }
     }
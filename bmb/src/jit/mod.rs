@@ -0,0 +1,106 @@
+//! In-process JIT execution
+//!
+//! Runs a program without producing a binary: MIR is lowered to LLVM IR
+//! text with `TextCodeGen`, parsed back into an LLVM module and compiled by
+//! LLVM's JIT execution engine. Calls to the C runtime are bound to the
+//! Rust implementations in [`runtime`], so no runtime object is linked.
+//!
+//! ```text
+//! MIR → llvm_text.rs → .ll (in memory) → LLVM JIT → bmb_user_main()
+//! ```
+//!
+//! Requires the `llvm` feature; without it [`run`] returns
+//! [`JitError::LlvmNotAvailable`] so callers can fall back to the interpreter.
+
+pub mod runtime;
+
+use thiserror::Error;
+
+use crate::codegen::TextCodeGenError;
+use crate::mir::{MirProgram, MirType};
+
+/// JIT execution error
+#[derive(Debug, Error)]
+pub enum JitError {
+    #[error("LLVM JIT is not available. Build with --features llvm")]
+    LlvmNotAvailable,
+
+    #[error("Code generation failed: {0}")]
+    CodeGen(#[from] TextCodeGenError),
+
+    #[error("LLVM error: {0}")]
+    Llvm(String),
+
+    #[error("No main function found")]
+    NoMain,
+}
+
+/// Result type for JIT execution
+pub type JitResult<T> = Result<T, JitError>;
+
+/// Compile `program` in-process and run its `main`, returning the exit code.
+///
+/// `args` are the program arguments, `[program_name, arg1, ...]`.
+/// A `main` returning unit exits with 0.
+pub fn run(program: &MirProgram, args: &[String]) -> JitResult<i64> {
+    let main = program
+        .functions
+        .iter()
+        .find(|f| f.name == "main")
+        .ok_or(JitError::NoMain)?;
+    let returns_value = main.ret_ty != MirType::Unit;
+    execute(program, args, returns_value)
+}
+
+#[cfg(feature = "llvm")]
+fn execute(program: &MirProgram, args: &[String], returns_value: bool) -> JitResult<i64> {
+    use inkwell::context::Context;
+    use inkwell::memory_buffer::MemoryBuffer;
+    use inkwell::targets::{InitializationConfig, Target};
+    use inkwell::OptimizationLevel;
+
+    let ir = crate::codegen::TextCodeGen::new().generate(program)?;
+
+    Target::initialize_native(&InitializationConfig::default()).map_err(JitError::Llvm)?;
+    let context = Context::create();
+    let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "bmb_jit");
+    let module = context
+        .create_module_from_ir(buffer)
+        .map_err(|e| JitError::Llvm(e.to_string()))?;
+    let engine = module
+        .create_jit_execution_engine(OptimizationLevel::Default)
+        .map_err(|e| JitError::Llvm(e.to_string()))?;
+
+    // Bind runtime declarations the module actually uses
+    for (name, addr) in runtime::symbols() {
+        if let Some(func) = module.get_function(name) {
+            engine.add_global_mapping(&func, addr);
+        }
+    }
+
+    runtime::set_args(args.to_vec());
+
+    // SAFETY: bmb_user_main takes no arguments and returns i64 or void,
+    // matching the signature TextCodeGen emits for `main`.
+    let code = unsafe {
+        if returns_value {
+            let main = engine
+                .get_function::<unsafe extern "C" fn() -> i64>("bmb_user_main")
+                .map_err(|_| JitError::NoMain)?;
+            main.call()
+        } else {
+            let main = engine
+                .get_function::<unsafe extern "C" fn()>("bmb_user_main")
+                .map_err(|_| JitError::NoMain)?;
+            main.call();
+            0
+        }
+    };
+    runtime::flush_stdout();
+    Ok(code)
+}
+
+#[cfg(not(feature = "llvm"))]
+fn execute(_program: &MirProgram, _args: &[String], _returns_value: bool) -> JitResult<i64> {
+    Err(JitError::LlvmNotAvailable)
+}
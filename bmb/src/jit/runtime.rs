@@ -0,0 +1,870 @@
+//! In-process runtime for JIT execution
+//!
+//! Rust implementations of the functions native builds link from
+//! `runtime/runtime.c`, with the same C ABI and `BmbString` layout, so code
+//! generated by `TextCodeGen` can call them directly from a JIT-compiled module.
+//! Allocations go through the C allocator because generated code may `free`
+//! or `realloc` what the runtime returns.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_void, CStr};
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+
+unsafe extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn calloc(count: usize, size: usize) -> *mut c_void;
+    fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+/// Native string layout, identical to `BmbString` in `runtime.c`
+#[repr(C)]
+pub struct BmbString {
+    pub data: *mut u8,
+    pub len: i64,
+    pub cap: i64,
+}
+
+/// Program arguments returned by `arg_count` / `get_arg`
+static PROGRAM_ARGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// StringBuilder buffers, addressed by handle
+static STRING_BUILDERS: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+
+/// Interned string keys of the `*_str_key` hashmap builtins: equal contents
+/// map to the same `BmbString`, whose address is the i64 key
+static STRING_KEYS: Mutex<BTreeMap<Vec<u8>, i64>> = Mutex::new(BTreeMap::new());
+
+/// Set the arguments visible to the program: `[program_name, arg1, ...]`
+pub fn set_args(args: Vec<String>) {
+    *PROGRAM_ARGS.lock().unwrap() = args;
+}
+
+/// Flush buffered program output (before returning or exiting)
+pub fn flush_stdout() {
+    let _ = std::io::stdout().flush();
+}
+
+/// Runtime symbols by the name generated code calls them with
+pub fn symbols() -> Vec<(&'static str, usize)> {
+    vec![
+        // Basic I/O
+        ("println", rt_println as *const () as usize),
+        ("print", rt_print as *const () as usize),
+        ("read_int", rt_read_int as *const () as usize),
+        ("assert", rt_assert as *const () as usize),
+        ("bmb_abs", rt_abs as *const () as usize),
+        ("min", rt_min as *const () as usize),
+        ("max", rt_max as *const () as usize),
+        ("bmb_contract_fail", rt_contract_fail as *const () as usize),
+        ("flush", rt_flush as *const () as usize),
+        // Strings
+        ("bmb_string_new", rt_string_new as *const () as usize),
+        ("bmb_string_from_cstr", rt_string_from_cstr as *const () as usize),
+        ("bmb_string_len", rt_string_len as *const () as usize),
+        ("bmb_string_char_at", rt_string_char_at as *const () as usize),
+        ("bmb_string_slice", rt_string_slice as *const () as usize),
+        ("bmb_string_concat", rt_string_concat as *const () as usize),
+        ("bmb_string_eq", rt_string_eq as *const () as usize),
+        ("bmb_cstr_eq", rt_cstr_eq as *const () as usize),
+        ("bmb_chr", rt_chr as *const () as usize),
+        ("bmb_ord", rt_ord as *const () as usize),
+        ("bmb_print_str", rt_print_str as *const () as usize),
+        ("bmb_println_str", rt_println_str as *const () as usize),
+        ("len", rt_string_len as *const () as usize),
+        ("char_at", rt_string_char_at as *const () as usize),
+        ("byte_at", rt_string_char_at as *const () as usize),
+        ("slice", rt_string_slice as *const () as usize),
+        ("chr", rt_chr as *const () as usize),
+        ("ord", rt_ord as *const () as usize),
+        ("char_to_string", rt_char_to_string as *const () as usize),
+        ("print_str", rt_print_str as *const () as usize),
+        ("println_str", rt_println_str as *const () as usize),
+        ("str_contains", rt_str_contains as *const () as usize),
+        ("str_starts_with", rt_str_starts_with as *const () as usize),
+        ("str_ends_with", rt_str_ends_with as *const () as usize),
+        ("str_index_of", rt_str_index_of as *const () as usize),
+        ("str_replace", rt_str_replace as *const () as usize),
+        ("str_trim", rt_str_trim as *const () as usize),
+        ("parse_int", rt_parse_int as *const () as usize),
+        ("str_split", rt_str_split as *const () as usize),
+        ("str_from_id", rt_str_from_id as *const () as usize),
+        // HashMap<i64, i64> and String-valued vec/hashmap builtins
+        ("hashmap_new", rt_hashmap_new as *const () as usize),
+        ("hashmap_insert", rt_hashmap_insert as *const () as usize),
+        ("hashmap_get", rt_hashmap_get as *const () as usize),
+        ("hashmap_contains", rt_hashmap_contains as *const () as usize),
+        ("hashmap_remove", rt_hashmap_remove as *const () as usize),
+        ("hashmap_len", rt_hashmap_len as *const () as usize),
+        ("hashmap_free", rt_hashmap_free as *const () as usize),
+        ("hashmap_insert_str", rt_hashmap_insert_str as *const () as usize),
+        ("hashmap_get_str", rt_hashmap_get_str as *const () as usize),
+        ("hashmap_insert_str_key", rt_hashmap_insert_str_key as *const () as usize),
+        ("hashmap_get_str_key", rt_hashmap_get_str_key as *const () as usize),
+        ("hashmap_contains_str_key", rt_hashmap_contains_str_key as *const () as usize),
+        ("vec_push_str", rt_vec_push_str as *const () as usize),
+        ("vec_get_str", rt_vec_get_str as *const () as usize),
+        // File I/O
+        ("bmb_file_exists", rt_file_exists as *const () as usize),
+        ("bmb_file_size", rt_file_size as *const () as usize),
+        ("bmb_read_file", rt_read_file as *const () as usize),
+        ("bmb_write_file", rt_write_file as *const () as usize),
+        ("bmb_append_file", rt_append_file as *const () as usize),
+        ("file_exists", rt_file_exists as *const () as usize),
+        ("file_size", rt_file_size as *const () as usize),
+        ("read_file", rt_read_file as *const () as usize),
+        ("write_file", rt_write_file as *const () as usize),
+        ("append_file", rt_append_file as *const () as usize),
+        // StringBuilder
+        ("bmb_sb_new", rt_sb_new as *const () as usize),
+        ("bmb_sb_push", rt_sb_push as *const () as usize),
+        ("bmb_sb_len", rt_sb_len as *const () as usize),
+        ("bmb_sb_build", rt_sb_build as *const () as usize),
+        ("bmb_sb_clear", rt_sb_clear as *const () as usize),
+        ("sb_new", rt_sb_new as *const () as usize),
+        ("sb_push", rt_sb_push as *const () as usize),
+        ("sb_len", rt_sb_len as *const () as usize),
+        ("sb_build", rt_sb_build as *const () as usize),
+        ("sb_clear", rt_sb_clear as *const () as usize),
+        // Process and environment
+        ("bmb_system", rt_system as *const () as usize),
+        ("bmb_getenv", rt_getenv as *const () as usize),
+        ("arg_count", rt_arg_count as *const () as usize),
+        ("bmb_arg_count", rt_arg_count as *const () as usize),
+        ("get_arg", rt_get_arg as *const () as usize),
+        ("bmb_get_arg", rt_get_arg as *const () as usize),
+        // Memory allocation
+        ("malloc", malloc as *const () as usize),
+        ("calloc", calloc as *const () as usize),
+        ("realloc", realloc as *const () as usize),
+        ("free", free as *const () as usize),
+    ]
+}
+
+/// Allocate a NUL-terminated `BmbString` holding a copy of `bytes`
+fn new_string(bytes: &[u8]) -> *mut BmbString {
+    unsafe {
+        let data = malloc(bytes.len() + 1) as *mut u8;
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+        *data.add(bytes.len()) = 0;
+        let s = malloc(std::mem::size_of::<BmbString>()) as *mut BmbString;
+        s.write(BmbString {
+            data,
+            len: bytes.len() as i64,
+            cap: bytes.len() as i64 + 1,
+        });
+        s
+    }
+}
+
+/// View a runtime string's bytes (None for a null pointer)
+unsafe fn bytes<'a>(s: *const BmbString) -> Option<&'a [u8]> {
+    if s.is_null() {
+        return None;
+    }
+    let s = unsafe { &*s };
+    if s.data.is_null() || s.len <= 0 {
+        return Some(&[]);
+    }
+    Some(unsafe { std::slice::from_raw_parts(s.data, s.len as usize) })
+}
+
+/// Decode a runtime string as a path or command (lossy UTF-8)
+unsafe fn text(s: *const BmbString) -> Option<String> {
+    unsafe { bytes(s) }.map(|b| String::from_utf8_lossy(b).into_owned())
+}
+
+/// Print an assertion failure and exit like the C runtime
+fn fail(message: &str) -> ! {
+    flush_stdout();
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+// ============ Basic I/O ============
+
+extern "C" fn rt_println(x: i64) {
+    println!("{}", x);
+}
+
+extern "C" fn rt_print(x: i64) {
+    print!("{}", x);
+}
+
+extern "C" fn rt_flush() {
+    flush_stdout();
+}
+
+extern "C" fn rt_read_int() -> i64 {
+    flush_stdout();
+    let mut line = String::new();
+    let _ = std::io::stdin().lock().read_line(&mut line);
+    line.trim()
+        .parse()
+        .unwrap_or_else(|_| fail("Error: failed to read integer"))
+}
+
+/// Takes an `i1`; only the low bit is meaningful
+extern "C" fn rt_assert(cond: u8) {
+    if cond & 1 == 0 {
+        fail("Assertion failed");
+    }
+}
+
+/// Checked contract violation: report and exit with code 101 like the C runtime
+unsafe extern "C" fn rt_contract_fail(msg: *const BmbString) {
+    flush_stdout();
+    eprintln!("contract violation: {}", unsafe { text(msg) }.unwrap_or_default());
+    std::process::exit(101);
+}
+
+extern "C" fn rt_abs(x: i64) -> i64 {
+    x.wrapping_abs()
+}
+
+extern "C" fn rt_min(a: i64, b: i64) -> i64 {
+    a.min(b)
+}
+
+extern "C" fn rt_max(a: i64, b: i64) -> i64 {
+    a.max(b)
+}
+
+// ============ Strings ============
+
+unsafe extern "C" fn rt_string_new(data: *const u8, len: i64) -> *mut BmbString {
+    if data.is_null() || len <= 0 {
+        return new_string(&[]);
+    }
+    new_string(unsafe { std::slice::from_raw_parts(data, len as usize) })
+}
+
+unsafe extern "C" fn rt_string_from_cstr(cstr: *const c_char) -> *mut BmbString {
+    if cstr.is_null() {
+        return new_string(&[]);
+    }
+    new_string(unsafe { CStr::from_ptr(cstr) }.to_bytes())
+}
+
+unsafe extern "C" fn rt_string_len(s: *const BmbString) -> i64 {
+    unsafe { bytes(s) }.map_or(0, |b| b.len() as i64)
+}
+
+unsafe extern "C" fn rt_string_char_at(s: *const BmbString, idx: i64) -> i64 {
+    let b = unsafe { bytes(s) }.unwrap_or(&[]);
+    usize::try_from(idx).ok().and_then(|i| b.get(i)).map_or(0, |&c| c as i64)
+}
+
+unsafe extern "C" fn rt_string_slice(s: *const BmbString, start: i64, end: i64) -> *mut BmbString {
+    let b = unsafe { bytes(s) }.unwrap_or(&[]);
+    let start = start.max(0) as usize;
+    let end = (end.max(0) as usize).min(b.len());
+    if start >= end {
+        return new_string(&[]);
+    }
+    new_string(&b[start..end])
+}
+
+unsafe extern "C" fn rt_string_concat(a: *const BmbString, b: *const BmbString) -> *mut BmbString {
+    let a = unsafe { bytes(a) }.unwrap_or(&[]);
+    let b = unsafe { bytes(b) }.unwrap_or(&[]);
+    new_string(&[a, b].concat())
+}
+
+unsafe extern "C" fn rt_string_eq(a: *const BmbString, b: *const BmbString) -> i64 {
+    (unsafe { bytes(a) } == unsafe { bytes(b) }) as i64
+}
+
+unsafe extern "C" fn rt_cstr_eq(a: *const c_char, b: *const c_char) -> i64 {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => 1,
+        (false, false) => (unsafe { CStr::from_ptr(a) == CStr::from_ptr(b) }) as i64,
+        _ => 0,
+    }
+}
+
+extern "C" fn rt_chr(code: i64) -> *mut BmbString {
+    new_string(&[code as u8])
+}
+
+extern "C" fn rt_char_to_string(code: i32) -> *mut BmbString {
+    let c = char::from_u32(code as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+    new_string(c.to_string().as_bytes())
+}
+
+unsafe extern "C" fn rt_ord(s: *const BmbString) -> i64 {
+    unsafe { bytes(s) }.and_then(|b| b.first()).map_or(0, |&c| c as i64)
+}
+
+unsafe extern "C" fn rt_print_str(s: *const BmbString) {
+    if let Some(b) = unsafe { bytes(s) } {
+        let _ = std::io::stdout().write_all(b);
+    }
+}
+
+unsafe extern "C" fn rt_println_str(s: *const BmbString) {
+    unsafe { rt_print_str(s) };
+    println!();
+}
+
+// ============ String search and transformation ============
+// Offsets are byte offsets, matching `len` and `slice`
+
+/// Byte offset of the first occurrence of `needle` at or after `from`
+fn find(s: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return (from <= s.len()).then_some(from);
+    }
+    s.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
+
+/// Byte offsets of every non-overlapping occurrence of a non-empty `needle`
+fn find_all<'a>(s: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    std::iter::successors(find(s, needle, 0), move |&i| find(s, needle, i + needle.len()))
+}
+
+unsafe extern "C" fn rt_str_contains(s: *const BmbString, needle: *const BmbString) -> bool {
+    match (unsafe { bytes(s) }, unsafe { bytes(needle) }) {
+        (Some(s), Some(needle)) => find(s, needle, 0).is_some(),
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn rt_str_starts_with(s: *const BmbString, prefix: *const BmbString) -> bool {
+    match (unsafe { bytes(s) }, unsafe { bytes(prefix) }) {
+        (Some(s), Some(prefix)) => s.starts_with(prefix),
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn rt_str_ends_with(s: *const BmbString, suffix: *const BmbString) -> bool {
+    match (unsafe { bytes(s) }, unsafe { bytes(suffix) }) {
+        (Some(s), Some(suffix)) => s.ends_with(suffix),
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn rt_str_index_of(s: *const BmbString, needle: *const BmbString) -> i64 {
+    match (unsafe { bytes(s) }, unsafe { bytes(needle) }) {
+        (Some(s), Some(needle)) => find(s, needle, 0).map_or(-1, |i| i as i64),
+        _ => -1,
+    }
+}
+
+/// Replace every occurrence of `from`; an empty `from` leaves `s` unchanged
+unsafe extern "C" fn rt_str_replace(
+    s: *const BmbString,
+    from: *const BmbString,
+    to: *const BmbString,
+) -> *mut BmbString {
+    let s = unsafe { bytes(s) }.unwrap_or(&[]);
+    let from = unsafe { bytes(from) }.unwrap_or(&[]);
+    let to = unsafe { bytes(to) }.unwrap_or(&[]);
+    if from.is_empty() {
+        return new_string(s);
+    }
+    let mut out = Vec::with_capacity(s.len());
+    let mut prev = 0;
+    for i in find_all(s, from) {
+        out.extend_from_slice(&s[prev..i]);
+        out.extend_from_slice(to);
+        prev = i + from.len();
+    }
+    out.extend_from_slice(&s[prev..]);
+    new_string(&out)
+}
+
+/// Strip leading and trailing ASCII whitespace, `\v` included
+unsafe extern "C" fn rt_str_trim(s: *const BmbString) -> *mut BmbString {
+    let s = unsafe { bytes(s) }.unwrap_or(&[]);
+    let is_space = |c: &u8| matches!(c, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c);
+    let start = s.iter().position(|c| !is_space(c)).unwrap_or(s.len());
+    let end = s.iter().rposition(|c| !is_space(c)).map_or(start, |i| i + 1);
+    new_string(&s[start..end])
+}
+
+/// Decimal integer with optional sign; `i64::MIN` when malformed or out of range
+unsafe extern "C" fn rt_parse_int(s: *const BmbString) -> i64 {
+    unsafe { bytes(s) }
+        .and_then(|b| std::str::from_utf8(b).ok())
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(i64::MIN)
+}
+
+/// Split on every occurrence of `sep` (an empty `sep` yields `s` itself).
+/// Returns a vec handle `{data, len, cap}` whose elements are string ids.
+unsafe extern "C" fn rt_str_split(s: *const BmbString, sep: *const BmbString) -> i64 {
+    let s = unsafe { bytes(s) }.unwrap_or(&[]);
+    let sep = unsafe { bytes(sep) }.unwrap_or(&[]);
+    let mut parts = Vec::new();
+    let mut prev = 0;
+    if !sep.is_empty() {
+        for i in find_all(s, sep) {
+            parts.push(new_string(&s[prev..i]) as i64);
+            prev = i + sep.len();
+        }
+    }
+    parts.push(new_string(&s[prev..]) as i64);
+    unsafe {
+        let items = malloc(parts.len() * 8) as *mut i64;
+        std::ptr::copy_nonoverlapping(parts.as_ptr(), items, parts.len());
+        let header = malloc(3 * 8) as *mut i64;
+        header.write(items as i64);
+        header.add(1).write(parts.len() as i64);
+        header.add(2).write(parts.len() as i64);
+        header as i64
+    }
+}
+
+/// String for an id returned by `str_split`
+extern "C" fn rt_str_from_id(id: i64) -> *mut BmbString {
+    if id == 0 {
+        new_string(&[])
+    } else {
+        id as *mut BmbString
+    }
+}
+
+// ============ HashMap and String-valued collections ============
+// Same layout and probing as runtime.c and the interpreter: a hashmap is a
+// header `[count, capacity, keys, values, states]` with states 0 = empty,
+// 1 = occupied, 2 = deleted; a vec is a header `{data, len, cap}`
+
+const HASHMAP_DEFAULT_CAPACITY: usize = 16;
+
+/// The count and the key, value and state arrays behind a hashmap handle
+unsafe fn hashmap_parts<'a>(map: i64) -> (&'a mut i64, &'a mut [i64], &'a mut [i64], &'a mut [i64]) {
+    let header = map as *mut i64;
+    unsafe {
+        let capacity = *header.add(1) as usize;
+        let [keys, values, states] =
+            [2, 3, 4].map(|i| std::slice::from_raw_parts_mut(*header.add(i) as *mut i64, capacity));
+        (&mut *header, keys, values, states)
+    }
+}
+
+/// Slot holding `key`, or where it would go, and whether it was found
+fn hashmap_find_slot(keys: &[i64], states: &[i64], key: i64) -> (usize, bool) {
+    let mask = keys.len() - 1;
+    let h = (key as u64).wrapping_mul(0x517cc1b727220a95);
+    let mut idx = (h ^ (h >> 32)) as usize & mask;
+    let mut first_deleted = None;
+    for _ in 0..keys.len() {
+        match states[idx] {
+            0 => return (first_deleted.unwrap_or(idx), false),
+            2 => {
+                first_deleted.get_or_insert(idx);
+            }
+            _ if keys[idx] == key => return (idx, true),
+            _ => {}
+        }
+        idx = (idx + 1) & mask;
+    }
+    (first_deleted.unwrap_or(0), false)
+}
+
+/// Zeroed array of `capacity` i64s from the C allocator
+fn zeroed<'a>(capacity: usize) -> &'a mut [i64] {
+    unsafe { std::slice::from_raw_parts_mut(calloc(capacity, 8) as *mut i64, capacity) }
+}
+
+extern "C" fn rt_hashmap_new() -> i64 {
+    let capacity = HASHMAP_DEFAULT_CAPACITY;
+    let [keys, values, states] = [(); 3].map(|_| zeroed(capacity).as_mut_ptr() as i64);
+    let header = zeroed(5);
+    header.copy_from_slice(&[0, capacity as i64, keys, values, states]);
+    header.as_mut_ptr() as i64
+}
+
+/// Move a hashmap's entries into arrays of twice the capacity
+unsafe fn hashmap_grow(map: i64) {
+    let header = map as *mut i64;
+    let (_, keys, values, states) = unsafe { hashmap_parts(map) };
+    let capacity = keys.len() * 2;
+    let [new_keys, new_values, new_states] = [(); 3].map(|_| zeroed(capacity));
+    for i in (0..keys.len()).filter(|&i| states[i] == 1) {
+        let (idx, _) = hashmap_find_slot(new_keys, new_states, keys[i]);
+        (new_keys[idx], new_values[idx], new_states[idx]) = (keys[i], values[i], 1);
+    }
+    let arrays = [(keys, new_keys), (values, new_values), (states, new_states)];
+    unsafe {
+        for (i, (old, new)) in arrays.into_iter().enumerate() {
+            free(old.as_mut_ptr() as *mut c_void);
+            *header.add(2 + i) = new.as_mut_ptr() as i64;
+        }
+        *header.add(1) = capacity as i64;
+    }
+}
+
+/// Returns the previous value, or 0 for a new key
+unsafe extern "C" fn rt_hashmap_insert(map: i64, key: i64, value: i64) -> i64 {
+    let (count, keys, _, _) = unsafe { hashmap_parts(map) };
+    // Double the capacity above 70% load
+    if *count * 10 > keys.len() as i64 * 7 {
+        unsafe { hashmap_grow(map) };
+    }
+    let (count, keys, values, states) = unsafe { hashmap_parts(map) };
+    let (idx, found) = hashmap_find_slot(keys, states, key);
+    let old = if found { values[idx] } else { 0 };
+    if !found {
+        *count += 1;
+    }
+    (keys[idx], values[idx], states[idx]) = (key, value, 1);
+    old
+}
+
+/// Returns the value, or `i64::MIN` when the key is missing
+unsafe extern "C" fn rt_hashmap_get(map: i64, key: i64) -> i64 {
+    let (_, keys, values, states) = unsafe { hashmap_parts(map) };
+    match hashmap_find_slot(keys, states, key) {
+        (idx, true) => values[idx],
+        _ => i64::MIN,
+    }
+}
+
+unsafe extern "C" fn rt_hashmap_contains(map: i64, key: i64) -> i64 {
+    let (_, keys, _, states) = unsafe { hashmap_parts(map) };
+    hashmap_find_slot(keys, states, key).1 as i64
+}
+
+/// Returns the removed value, or `i64::MIN` when the key is missing
+unsafe extern "C" fn rt_hashmap_remove(map: i64, key: i64) -> i64 {
+    let (count, keys, values, states) = unsafe { hashmap_parts(map) };
+    match hashmap_find_slot(keys, states, key) {
+        (idx, true) => {
+            states[idx] = 2;
+            *count -= 1;
+            values[idx]
+        }
+        _ => i64::MIN,
+    }
+}
+
+unsafe extern "C" fn rt_hashmap_len(map: i64) -> i64 {
+    if map == 0 { 0 } else { unsafe { *(map as *const i64) } }
+}
+
+unsafe extern "C" fn rt_hashmap_free(map: i64) {
+    if map == 0 {
+        return;
+    }
+    let header = map as *mut i64;
+    unsafe {
+        for i in 2..5 {
+            free(*header.add(i) as *mut c_void);
+        }
+        free(header as *mut c_void);
+    }
+}
+
+/// String values are stored as `BmbString` pointers, read back like `str_from_id`
+unsafe extern "C" fn rt_hashmap_insert_str(map: i64, key: i64, value: *const BmbString) {
+    let copy = new_string(unsafe { bytes(value) }.unwrap_or(&[]));
+    unsafe { rt_hashmap_insert(map, key, copy as i64) };
+}
+
+/// Returns "" when the key is missing
+unsafe extern "C" fn rt_hashmap_get_str(map: i64, key: i64) -> *mut BmbString {
+    match unsafe { rt_hashmap_get(map, key) } {
+        i64::MIN => new_string(&[]),
+        value => rt_str_from_id(value),
+    }
+}
+
+/// Key for an interned string, or 0 if `s` was never used as a key
+unsafe fn existing_key(s: *const BmbString) -> i64 {
+    let Some(b) = (unsafe { bytes(s) }) else { return 0 };
+    STRING_KEYS.lock().unwrap().get(b).copied().unwrap_or(0)
+}
+
+unsafe extern "C" fn rt_hashmap_insert_str_key(map: i64, key: *const BmbString, value: i64) -> i64 {
+    let b = unsafe { bytes(key) }.unwrap_or(&[]);
+    let id = *STRING_KEYS.lock().unwrap().entry(b.to_vec()).or_insert_with(|| new_string(b) as i64);
+    unsafe { rt_hashmap_insert(map, id, value) }
+}
+
+/// Returns the value, or `i64::MIN` when the key is missing
+unsafe extern "C" fn rt_hashmap_get_str_key(map: i64, key: *const BmbString) -> i64 {
+    match unsafe { existing_key(key) } {
+        0 => i64::MIN,
+        id => unsafe { rt_hashmap_get(map, id) },
+    }
+}
+
+unsafe extern "C" fn rt_hashmap_contains_str_key(map: i64, key: *const BmbString) -> i64 {
+    match unsafe { existing_key(key) } {
+        0 => 0,
+        id => unsafe { rt_hashmap_contains(map, id) },
+    }
+}
+
+unsafe extern "C" fn rt_vec_push_str(vec: i64, value: *const BmbString) {
+    let header = vec as *mut i64;
+    unsafe {
+        let (len, cap) = (*header.add(1), *header.add(2));
+        if len >= cap {
+            let cap = if cap == 0 { 4 } else { cap * 2 };
+            *header = realloc(*header as *mut c_void, cap as usize * 8) as i64;
+            *header.add(2) = cap;
+        }
+        let copy = new_string(bytes(value).unwrap_or(&[]));
+        *(*header as *mut i64).add(len as usize) = copy as i64;
+        *header.add(1) = len + 1;
+    }
+}
+
+unsafe extern "C" fn rt_vec_get_str(vec: i64, index: i64) -> *mut BmbString {
+    let items = unsafe { *(vec as *const i64) } as *const i64;
+    rt_str_from_id(unsafe { *items.add(index as usize) })
+}
+
+// ============ File I/O ============
+
+unsafe extern "C" fn rt_file_exists(path: *const BmbString) -> i64 {
+    unsafe { text(path) }.is_some_and(|p| std::fs::metadata(p).is_ok()) as i64
+}
+
+unsafe extern "C" fn rt_file_size(path: *const BmbString) -> i64 {
+    unsafe { text(path) }
+        .and_then(|p| std::fs::metadata(p).ok())
+        .map_or(-1, |m| m.len() as i64)
+}
+
+unsafe extern "C" fn rt_read_file(path: *const BmbString) -> *mut BmbString {
+    let content = unsafe { text(path) }.and_then(|p| std::fs::read(p).ok());
+    new_string(&content.unwrap_or_default())
+}
+
+unsafe extern "C" fn rt_write_file(path: *const BmbString, content: *const BmbString) -> i64 {
+    match (unsafe { text(path) }, unsafe { bytes(content) }) {
+        (Some(p), Some(c)) => std::fs::write(p, c).map_or(-1, |_| 0),
+        _ => -1,
+    }
+}
+
+unsafe extern "C" fn rt_append_file(path: *const BmbString, content: *const BmbString) -> i64 {
+    let (Some(p), Some(c)) = (unsafe { text(path) }, unsafe { bytes(content) }) else {
+        return -1;
+    };
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(p)
+        .and_then(|mut f| f.write_all(c))
+        .map_or(-1, |_| 0)
+}
+
+// ============ StringBuilder ============
+
+extern "C" fn rt_sb_new() -> i64 {
+    let mut builders = STRING_BUILDERS.lock().unwrap();
+    builders.push(Vec::new());
+    builders.len() as i64 - 1
+}
+
+unsafe extern "C" fn rt_sb_push(handle: i64, s: *const BmbString) -> i64 {
+    let mut builders = STRING_BUILDERS.lock().unwrap();
+    match (usize::try_from(handle).ok().and_then(|h| builders.get_mut(h)), unsafe { bytes(s) }) {
+        (Some(buf), Some(b)) => {
+            buf.extend_from_slice(b);
+            0
+        }
+        _ => -1,
+    }
+}
+
+extern "C" fn rt_sb_len(handle: i64) -> i64 {
+    let builders = STRING_BUILDERS.lock().unwrap();
+    usize::try_from(handle)
+        .ok()
+        .and_then(|h| builders.get(h))
+        .map_or(0, |buf| buf.len() as i64)
+}
+
+extern "C" fn rt_sb_build(handle: i64) -> *mut BmbString {
+    let builders = STRING_BUILDERS.lock().unwrap();
+    let buf = usize::try_from(handle).ok().and_then(|h| builders.get(h));
+    new_string(buf.map_or(&[][..], |b| b.as_slice()))
+}
+
+extern "C" fn rt_sb_clear(handle: i64) -> i64 {
+    let mut builders = STRING_BUILDERS.lock().unwrap();
+    match usize::try_from(handle).ok().and_then(|h| builders.get_mut(h)) {
+        Some(buf) => {
+            buf.clear();
+            0
+        }
+        None => -1,
+    }
+}
+
+// ============ Process and environment ============
+
+unsafe extern "C" fn rt_system(cmd: *const BmbString) -> i64 {
+    let Some(cmd) = (unsafe { text(cmd) }) else {
+        return -1;
+    };
+    flush_stdout();
+    let status = if cfg!(windows) {
+        std::process::Command::new("cmd").args(["/C", &cmd]).status()
+    } else {
+        std::process::Command::new("sh").args(["-c", &cmd]).status()
+    };
+    status.ok().and_then(|s| s.code()).map_or(-1, |c| c as i64)
+}
+
+unsafe extern "C" fn rt_getenv(name: *const BmbString) -> *mut BmbString {
+    let value = unsafe { text(name) }.and_then(|n| std::env::var(n).ok());
+    new_string(value.unwrap_or_default().as_bytes())
+}
+
+extern "C" fn rt_arg_count() -> i64 {
+    PROGRAM_ARGS.lock().unwrap().len() as i64
+}
+
+extern "C" fn rt_get_arg(idx: i64) -> *mut BmbString {
+    let args = PROGRAM_ARGS.lock().unwrap();
+    let arg = usize::try_from(idx).ok().and_then(|i| args.get(i));
+    new_string(arg.map_or(&[][..], |a| a.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(s: *mut BmbString) -> Vec<u8> {
+        let b = unsafe { bytes(s) }.unwrap().to_vec();
+        unsafe {
+            free((*s).data as *mut c_void);
+            free(s as *mut c_void);
+        }
+        b
+    }
+
+    #[test]
+    fn test_string_layout_and_ops() {
+        let hello = new_string(b"hello");
+        let world = unsafe { rt_string_from_cstr(c" world".as_ptr()) };
+        assert_eq!(unsafe { rt_string_len(hello) }, 5);
+        assert_eq!(unsafe { *(*hello).data.add(5) }, 0, "NUL-terminated like runtime.c");
+        assert_eq!(unsafe { rt_string_char_at(hello, 1) }, b'e' as i64);
+        assert_eq!(unsafe { rt_string_char_at(hello, 9) }, 0);
+
+        assert_eq!(owned(unsafe { rt_string_concat(hello, world) }), b"hello world");
+        assert_eq!(owned(unsafe { rt_string_slice(hello, 1, 99) }), b"ello");
+        assert_eq!(unsafe { rt_string_eq(hello, hello) }, 1);
+        assert_eq!(unsafe { rt_string_eq(hello, world) }, 0);
+        assert_eq!(owned(rt_char_to_string('é' as i32)), "é".as_bytes());
+        owned(hello);
+        owned(world);
+    }
+
+    #[test]
+    fn test_string_search_and_split() {
+        let s = new_string(b"  a,b,,c \x0b");
+        let comma = new_string(b",");
+        let trimmed = unsafe { rt_str_trim(s) };
+        assert_eq!(unsafe { bytes(trimmed) }.unwrap(), b"a,b,,c");
+        assert!(unsafe { rt_str_contains(s, comma) });
+        assert_eq!(unsafe { rt_str_index_of(trimmed, comma) }, 1);
+        assert!(unsafe { rt_str_starts_with(trimmed, trimmed) });
+        assert!(!unsafe { rt_str_ends_with(comma, trimmed) });
+
+        let semi = new_string(b";;");
+        assert_eq!(owned(unsafe { rt_str_replace(trimmed, comma, semi) }), b"a;;b;;;;c");
+
+        let parts = unsafe { rt_str_split(trimmed, comma) } as *const i64;
+        let (items, len) = unsafe { (*parts as *const i64, *parts.add(1)) };
+        let fields: Vec<_> = (0..len).map(|i| owned(rt_str_from_id(unsafe { *items.add(i as usize) }))).collect();
+        assert_eq!(fields, [&b"a"[..], b"b", b"", b"c"]);
+        unsafe {
+            free(items as *mut c_void);
+            free(parts as *mut c_void);
+        }
+
+        for (text, value) in [("-42", -42), ("+7", 7), ("", i64::MIN), ("1x", i64::MIN), ("99999999999999999999", i64::MIN)] {
+            let t = new_string(text.as_bytes());
+            assert_eq!(unsafe { rt_parse_int(t) }, value, "{text:?}");
+            owned(t);
+        }
+        for ptr in [s, comma, trimmed, semi] {
+            owned(ptr);
+        }
+    }
+
+    #[test]
+    fn test_hashmap_grows_and_interns_string_keys() {
+        let map = rt_hashmap_new();
+        for key in 0..100 {
+            assert_eq!(unsafe { rt_hashmap_insert(map, key, key * 10) }, 0);
+        }
+        assert_eq!(unsafe { rt_hashmap_len(map) }, 100);
+        assert_eq!(unsafe { rt_hashmap_insert(map, 7, 1) }, 70);
+        assert_eq!(unsafe { rt_hashmap_get(map, 99) }, 990);
+        assert_eq!(unsafe { rt_hashmap_remove(map, 99) }, 990);
+        assert_eq!(unsafe { rt_hashmap_get(map, 99) }, i64::MIN);
+        assert_eq!(unsafe { rt_hashmap_contains(map, 98) }, 1);
+
+        let (key, same) = (new_string(b"name"), new_string(b"name"));
+        let other = new_string(b"other");
+        unsafe { rt_hashmap_insert_str_key(map, key, 5) };
+        assert_eq!(unsafe { rt_hashmap_get_str_key(map, same) }, 5);
+        assert_eq!(unsafe { rt_hashmap_contains_str_key(map, other) }, 0);
+        unsafe { rt_hashmap_insert_str(map, 1000, other) };
+        assert_eq!(unsafe { bytes(rt_hashmap_get_str(map, 1000)) }.unwrap(), b"other");
+        assert_eq!(owned(unsafe { rt_hashmap_get_str(map, 1001) }), b"");
+        unsafe { rt_hashmap_free(map) };
+
+        let vec = unsafe { calloc(3, 8) } as i64;
+        for _ in 0..5 {
+            unsafe { rt_vec_push_str(vec, key) };
+        }
+        assert_eq!(unsafe { *(vec as *const i64).add(1) }, 5);
+        assert_eq!(unsafe { bytes(rt_vec_get_str(vec, 4)) }.unwrap(), b"name");
+        for s in [key, same, other] {
+            owned(s);
+        }
+    }
+
+    #[test]
+    fn test_string_builder() {
+        let sb = rt_sb_new();
+        let part = new_string(b"ab");
+        assert_eq!(unsafe { rt_sb_push(sb, part) }, 0);
+        assert_eq!(unsafe { rt_sb_push(sb, part) }, 0);
+        assert_eq!(rt_sb_len(sb), 4);
+        assert_eq!(owned(rt_sb_build(sb)), b"abab");
+        assert_eq!(rt_sb_clear(sb), 0);
+        assert_eq!(rt_sb_len(sb), 0);
+        assert_eq!(unsafe { rt_sb_push(-1, part) }, -1);
+        owned(part);
+    }
+
+    #[test]
+    fn test_file_io() {
+        let path = std::env::temp_dir().join(format!("bmb_jit_rt_{}.txt", std::process::id()));
+        let path_str = new_string(path.display().to_string().as_bytes());
+        let content = new_string(b"line\n");
+
+        assert_eq!(unsafe { rt_write_file(path_str, content) }, 0);
+        assert_eq!(unsafe { rt_append_file(path_str, content) }, 0);
+        assert_eq!(unsafe { rt_file_exists(path_str) }, 1);
+        assert_eq!(unsafe { rt_file_size(path_str) }, 10);
+        assert_eq!(owned(unsafe { rt_read_file(path_str) }), b"line\nline\n");
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(unsafe { rt_file_exists(path_str) }, 0);
+        assert_eq!(unsafe { rt_file_size(path_str) }, -1);
+        owned(path_str);
+        owned(content);
+    }
+
+    #[test]
+    fn test_symbols_cover_runtime_declarations() {
+        let names: Vec<_> = symbols().into_iter().map(|(name, _)| name).collect();
+        for name in ["println", "flush", "print_str", "str_split", "parse_int", "hashmap_get_str_key", "vec_push_str", "read_file", "sb_build", "get_arg", "malloc", "free"] {
+            assert!(names.contains(&name), "missing JIT symbol {}", name);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 mod token;
 
-pub use token::Token;
+pub use token::{parse_int_literal, Token};
 
 use crate::ast::Span;
 use crate::error::{CompileError, Result};
@@ -17,14 +17,85 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, Span)>> {
         let span = Span::new(lexer.span().start, lexer.span().end);
         match result {
             Ok(token) => tokens.push((token, span)),
+            Err(_) if is_raw_string_start(lexer.slice()) => {
+                return Err(CompileError::lexer("unterminated raw string literal", span));
+            }
             Err(_) => {
-                return Err(CompileError::lexer(
-                    format!("unexpected character: {:?}", lexer.slice()),
-                    span,
-                ));
+                let slice = lexer.slice();
+                let message = match parse_int_literal(slice) {
+                    // A malformed integer literal, not a stray character
+                    Err(message) if slice.starts_with(|c: char| c.is_ascii_digit()) => message,
+                    _ => format!("unexpected character: {:?}", slice),
+                };
+                return Err(CompileError::lexer(message, span));
             }
         }
     }
 
     Ok(tokens)
 }
+
+/// Whether `slice` is the opening `r#*"` of a raw string
+fn is_raw_string_start(slice: &str) -> bool {
+    slice
+        .strip_prefix('r')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .is_some_and(|hashes| hashes.chars().all(|c| c == '#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_lits(source: &str) -> Vec<i64> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(t, _)| match t {
+                Token::IntLit(n) => Some(n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn lex_error(source: &str) -> String {
+        tokenize(source).expect_err(source).to_string()
+    }
+
+    #[test]
+    fn test_radix_and_separated_int_literals() {
+        assert_eq!(int_lits("0b1010 0o755 0xFF_FF 1_000_000 42"), [10, 493, 0xFFFF, 1_000_000, 42]);
+        assert_eq!(int_lits("0b1111_0000 0x7fff_ffff_ffff_ffff"), [0xF0, i64::MAX]);
+        // Ranges and method calls after a literal still lex
+        assert_eq!(int_lits("0..0x10"), [0, 16]);
+    }
+
+    #[test]
+    fn test_malformed_int_literals_are_rejected() {
+        assert!(lex_error("1_").contains("cannot end with `_`"));
+        assert!(lex_error("0xFF_").contains("cannot end with `_`"));
+        assert!(lex_error("0x_FF").contains("`_` cannot follow the `0x` prefix"));
+        assert!(lex_error("0b_1").contains("`_` cannot follow the `0b` prefix"));
+        assert!(lex_error("0b").contains("missing digits after `0b`"));
+        assert!(lex_error("0b102").contains("invalid digit `2` in binary literal"));
+        assert!(lex_error("0o8").contains("invalid digit `8` in octal literal"));
+        assert!(lex_error("0xFG").contains("invalid digit `G` in hexadecimal literal"));
+        assert!(lex_error("0x1_0000_0000_0000_0000").contains("does not fit in i64"));
+        assert!(lex_error("99999999999999999999").contains("does not fit in i64"));
+    }
+
+    #[test]
+    fn test_kind_names_cover_every_token() {
+        let source = "fn let var if then else pre post true false ret and or not struct enum match new \
+            mut while for in loop break continue return band bor bxor bnot pub use mod where it extern \
+            try as trait impl todo type const invariant implies forall exists module version summary \
+            exports depends === i32 i64 u32 u64 f64 bool String char 1.5 42 \"s\" 'c' 'outer name \
+            : :: -> => _ ..< ..= .. . = ; , ( ) { } [ ] & @ ? | + - * / % == != <= >= < > << >> \
+            +% -% *% +? -? *? +| -| *| && || !";
+        let kinds: Vec<&str> = tokenize(source).unwrap().iter().map(|(t, _)| t.kind()).collect();
+        assert_eq!(kinds, Token::KINDS);
+        let unique: std::collections::HashSet<_> = kinds.iter().collect();
+        assert_eq!(unique.len(), Token::KINDS.len());
+        assert_eq!(Token::Ident("x".to_string()).kind(), "Ident");
+    }
+}
@@ -1,6 +1,44 @@
 //! Token definitions
 
-use logos::Logos;
+use logos::{Lexer, Logos};
+
+/// Lex the rest of a raw string whose opening `r#*"` has been matched,
+/// up to the `"` followed by as many hashes
+fn raw_string(lex: &mut Lexer<Token>) -> Option<String> {
+    let hashes = lex.slice().len() - 2;
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let end = lex.remainder().find(&closing)?;
+    let content = lex.remainder()[..end].to_string();
+    lex.bump(end + closing.len());
+    Some(content)
+}
+
+/// Value of an integer literal: decimal, or hexadecimal, octal or binary
+/// after a `0x`, `0o` or `0b` prefix, with `_` allowed between digits.
+/// The error says what is wrong with a malformed one.
+pub fn parse_int_literal(text: &str) -> Result<i64, String> {
+    let (radix, name, prefix) = match text.get(..2) {
+        Some("0x") => (16, "hexadecimal", "0x"),
+        Some("0o") => (8, "octal", "0o"),
+        Some("0b") => (2, "binary", "0b"),
+        _ => (10, "decimal", ""),
+    };
+    let digits = &text[prefix.len()..];
+    if digits.is_empty() {
+        return Err(format!("missing digits after `{prefix}` in {name} literal"));
+    }
+    if digits.starts_with('_') {
+        return Err(format!("`_` cannot follow the `{prefix}` prefix in `{text}`"));
+    }
+    if digits.ends_with('_') {
+        return Err(format!("integer literal `{text}` cannot end with `_`"));
+    }
+    if let Some(c) = digits.chars().find(|&c| c != '_' && !c.is_digit(radix)) {
+        return Err(format!("invalid digit `{c}` in {name} literal `{text}`"));
+    }
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    i64::from_str_radix(&digits, radix).map_err(|_| format!("integer literal `{text}` does not fit in i64"))
+}
 
 /// BMB Token
 #[derive(Logos, Debug, Clone, PartialEq)]
@@ -108,6 +146,9 @@ pub enum Token {
     // v0.50.6: Type aliases and refinement types
     #[token("type")]
     Type,
+    // Compile-time constants
+    #[token("const")]
+    Const,
 
     // v0.36: Contract keywords
     #[token("invariant")]
@@ -160,7 +201,10 @@ pub enum Token {
     #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?|[0-9]+[eE][+-]?[0-9]+", |lex| lex.slice().parse::<f64>().ok(), priority = 3)]
     FloatLit(f64),
 
-    #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().ok(), priority = 2)]
+    // Decimal with `_` separators (`1_000`), or radix-prefixed (`0xFF_FF`,
+    // `0o755`, `0b1010`); malformed ones are rejected by the lexer
+    #[regex(r"[0-9][0-9_]*", |lex| parse_int_literal(lex.slice()).ok(), priority = 2)]
+    #[regex(r"0[xob][0-9a-zA-Z_]*", |lex| parse_int_literal(lex.slice()).ok())]
     IntLit(i64),
 
     #[regex(r#""([^"\\]|\.)*""#, |lex| {
@@ -168,6 +212,8 @@ pub enum Token {
         // Remove surrounding quotes
         s[1..s.len()-1].to_string()
     })]
+    // Raw strings: `r"..."`, or `r#"..."#` with any number of hashes
+    #[regex(r##"r#*""##, raw_string)]
     StringLit(String),
 
     // v0.64: Character literals with escape sequences
@@ -194,6 +240,11 @@ pub enum Token {
     })]
     CharLit(char),
 
+    // Loop labels: `'outer`. A complete char literal like `'a'` is longer
+    // and wins, so only an unterminated quote-identifier lexes as a label
+    #[regex(r"'[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice()[1..].to_string())]
+    Label(String),
+
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string(), priority = 1)]
     Ident(String),
 
@@ -361,6 +412,7 @@ impl std::fmt::Display for Token {
             Token::StringLit(s) => write!(f, "\"{s}\""),
             // v0.64: Character literal display
             Token::CharLit(c) => write!(f, "'{c}'"),
+            Token::Label(s) => write!(f, "'{s}"),
             Token::Ident(s) => write!(f, "{s}"),
             Token::Colon => write!(f, ":"),
             Token::ColonColon => write!(f, "::"),
@@ -417,6 +469,7 @@ impl std::fmt::Display for Token {
             Token::Todo => write!(f, "todo"),
             // v0.50.6: Type aliases
             Token::Type => write!(f, "type"),
+            Token::Const => write!(f, "const"),
             // v0.31: Module header tokens
             Token::Module => write!(f, "module"),
             Token::Version => write!(f, "version"),
@@ -443,3 +496,144 @@ impl std::fmt::Display for Token {
         }
     }
 }
+
+impl Token {
+    /// Stable names of every token kind, in declaration order: the `kind`
+    /// of `bmb tokens` records
+    pub const KINDS: &'static [&'static str] = &[
+        "Fn", "Let", "Var", "If", "Then", "Else", "Pre", "Post", "True", "False", "Ret", "And",
+        "Or", "Not", "Struct", "Enum", "Match", "New", "Mut", "While", "For", "In", "Loop", "Break",
+        "Continue", "Return", "Band", "Bor", "Bxor", "Bnot", "Pub", "Use", "Mod", "Where", "It",
+        "Extern", "Try", "As", "Trait", "Impl", "Todo", "Type", "Const", "Invariant", "Implies",
+        "Forall", "Exists", "Module", "Version", "Summary", "Exports", "Depends", "HeaderSep",
+        "TyI32", "TyI64", "TyU32", "TyU64", "TyF64", "TyBool", "TyString", "TyChar", "FloatLit",
+        "IntLit", "StringLit", "CharLit", "Label", "Ident", "Colon", "ColonColon", "Arrow",
+        "FatArrow", "Underscore", "DotDotLt", "DotDotEq", "DotDot", "Dot", "Eq", "Semi", "Comma",
+        "LParen", "RParen", "LBrace", "RBrace", "LBracket", "RBracket", "Ampersand", "At",
+        "Question", "Pipe", "Plus", "Minus", "Star", "Slash", "Percent", "EqEq", "NotEq", "LtEq",
+        "GtEq", "Lt", "Gt", "LtLt", "GtGt", "PlusPercent", "MinusPercent", "StarPercent",
+        "PlusQuestion", "MinusQuestion", "StarQuestion", "PlusPipe", "MinusPipe", "StarPipe",
+        "AmpAmp", "PipePipe", "Bang",
+    ];
+
+    /// Name of this token's kind, one of [`Token::KINDS`]. Unlike the
+    /// `Debug` form it doesn't include the literal value.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::Fn => "Fn",
+            Token::Let => "Let",
+            Token::Var => "Var",
+            Token::If => "If",
+            Token::Then => "Then",
+            Token::Else => "Else",
+            Token::Pre => "Pre",
+            Token::Post => "Post",
+            Token::True => "True",
+            Token::False => "False",
+            Token::Ret => "Ret",
+            Token::And => "And",
+            Token::Or => "Or",
+            Token::Not => "Not",
+            Token::Struct => "Struct",
+            Token::Enum => "Enum",
+            Token::Match => "Match",
+            Token::New => "New",
+            Token::Mut => "Mut",
+            Token::While => "While",
+            Token::For => "For",
+            Token::In => "In",
+            Token::Loop => "Loop",
+            Token::Break => "Break",
+            Token::Continue => "Continue",
+            Token::Return => "Return",
+            Token::Band => "Band",
+            Token::Bor => "Bor",
+            Token::Bxor => "Bxor",
+            Token::Bnot => "Bnot",
+            Token::Pub => "Pub",
+            Token::Use => "Use",
+            Token::Mod => "Mod",
+            Token::Where => "Where",
+            Token::It => "It",
+            Token::Extern => "Extern",
+            Token::Try => "Try",
+            Token::As => "As",
+            Token::Trait => "Trait",
+            Token::Impl => "Impl",
+            Token::Todo => "Todo",
+            Token::Type => "Type",
+            Token::Const => "Const",
+            Token::Invariant => "Invariant",
+            Token::Implies => "Implies",
+            Token::Forall => "Forall",
+            Token::Exists => "Exists",
+            Token::Module => "Module",
+            Token::Version => "Version",
+            Token::Summary => "Summary",
+            Token::Exports => "Exports",
+            Token::Depends => "Depends",
+            Token::HeaderSep => "HeaderSep",
+            Token::TyI32 => "TyI32",
+            Token::TyI64 => "TyI64",
+            Token::TyU32 => "TyU32",
+            Token::TyU64 => "TyU64",
+            Token::TyF64 => "TyF64",
+            Token::TyBool => "TyBool",
+            Token::TyString => "TyString",
+            Token::TyChar => "TyChar",
+            Token::FloatLit(_) => "FloatLit",
+            Token::IntLit(_) => "IntLit",
+            Token::StringLit(_) => "StringLit",
+            Token::CharLit(_) => "CharLit",
+            Token::Label(_) => "Label",
+            Token::Ident(_) => "Ident",
+            Token::Colon => "Colon",
+            Token::ColonColon => "ColonColon",
+            Token::Arrow => "Arrow",
+            Token::FatArrow => "FatArrow",
+            Token::Underscore => "Underscore",
+            Token::DotDotLt => "DotDotLt",
+            Token::DotDotEq => "DotDotEq",
+            Token::DotDot => "DotDot",
+            Token::Dot => "Dot",
+            Token::Eq => "Eq",
+            Token::Semi => "Semi",
+            Token::Comma => "Comma",
+            Token::LParen => "LParen",
+            Token::RParen => "RParen",
+            Token::LBrace => "LBrace",
+            Token::RBrace => "RBrace",
+            Token::LBracket => "LBracket",
+            Token::RBracket => "RBracket",
+            Token::Ampersand => "Ampersand",
+            Token::At => "At",
+            Token::Question => "Question",
+            Token::Pipe => "Pipe",
+            Token::Plus => "Plus",
+            Token::Minus => "Minus",
+            Token::Star => "Star",
+            Token::Slash => "Slash",
+            Token::Percent => "Percent",
+            Token::EqEq => "EqEq",
+            Token::NotEq => "NotEq",
+            Token::LtEq => "LtEq",
+            Token::GtEq => "GtEq",
+            Token::Lt => "Lt",
+            Token::Gt => "Gt",
+            Token::LtLt => "LtLt",
+            Token::GtGt => "GtGt",
+            Token::PlusPercent => "PlusPercent",
+            Token::MinusPercent => "MinusPercent",
+            Token::StarPercent => "StarPercent",
+            Token::PlusQuestion => "PlusQuestion",
+            Token::MinusQuestion => "MinusQuestion",
+            Token::StarQuestion => "StarQuestion",
+            Token::PlusPipe => "PlusPipe",
+            Token::MinusPipe => "MinusPipe",
+            Token::StarPipe => "StarPipe",
+            Token::AmpAmp => "AmpAmp",
+            Token::PipePipe => "PipePipe",
+            Token::Bang => "Bang",
+        }
+    }
+}
@@ -36,15 +36,21 @@
 pub mod ast;
 pub mod build;
 pub mod cfg;
+pub mod cli_output;
 pub mod codegen;
 pub mod derive;
+pub mod doc;
 pub mod error;
+pub mod fmt;
 pub mod index;
 pub mod interp;
+pub mod jit;
 pub mod lexer;
+pub mod lint;
 pub mod lsp;
 pub mod mir;
 pub mod parser;
+pub mod project;
 pub mod query;
 pub mod repl;
 pub mod resolver;
@@ -0,0 +1,223 @@
+//! Lint configuration
+//!
+//! `bmb lint` gives each warning kind a level, read from the `[lint]` table
+//! of the project's `bmb.toml` or from a file passed with `--lint-config`:
+//!
+//! ```toml
+//! [lint]
+//! shadow_binding = "allow"
+//! missing_postcondition = "deny"
+//! ```
+//!
+//! Allowed warnings are dropped and denied ones are reported as errors that
+//! fail the run; every other kind stays a warning. A function marked
+//! `@allow(shadow_binding)` allows that kind inside its own body.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::ast::{Attribute, Expr, FnDef, Item, Program, Span};
+use crate::error::{CompileWarning, WARNING_KINDS};
+use crate::project::{Project, ProjectError, ProjectResult, MANIFEST_FILE};
+
+/// How `bmb lint` treats one warning kind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Suppressed
+    Allow,
+    /// Reported as a warning
+    #[default]
+    Warn,
+    /// Reported as an error
+    Deny,
+}
+
+/// Lint file given with `--lint-config`; only its `[lint]` table is read,
+/// so a `bmb.toml` works too
+#[derive(Debug, Deserialize)]
+struct LintFile {
+    #[serde(default)]
+    lint: BTreeMap<String, LintLevel>,
+}
+
+/// Level of each warning kind
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: BTreeMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    /// Config from a `[lint]` table; `path` is only used in errors
+    pub fn new(levels: BTreeMap<String, LintLevel>, path: &Path) -> ProjectResult<Self> {
+        if let Some(rule) = levels.keys().find(|rule| !WARNING_KINDS.contains(&rule.as_str())) {
+            return Err(ProjectError::InvalidManifest {
+                path: path.to_path_buf(),
+                message: format!("unknown lint rule `{}`; known rules are {}", rule, WARNING_KINDS.join(", ")),
+            });
+        }
+        Ok(Self { levels })
+    }
+
+    /// Parse the `[lint]` table of a TOML file
+    pub fn parse(text: &str, path: &Path) -> ProjectResult<Self> {
+        let file: LintFile = toml::from_str(text).map_err(|e| ProjectError::InvalidManifest {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })?;
+        Self::new(file.lint, path)
+    }
+
+    /// Load a `--lint-config` file
+    pub fn load(path: &Path) -> ProjectResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(|source| ProjectError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// The `[lint]` table of the project containing `dir`; every kind warns
+    /// outside a project
+    pub fn discover(dir: &Path) -> ProjectResult<Self> {
+        match Project::discover(dir)? {
+            Some(project) => {
+                let path = project.root.join(MANIFEST_FILE);
+                Self::new(project.manifest.lint, &path)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Level of the warning kind `rule`
+    pub fn level(&self, rule: &str) -> LintLevel {
+        self.levels.get(rule).copied().unwrap_or_default()
+    }
+
+    /// Sort the warnings of `program` by their level, dropping those allowed
+    /// by the config or by an `@allow` on the function they are in
+    pub fn apply(&self, program: &Program, warnings: Vec<CompileWarning>) -> Linted {
+        let scopes = allow_scopes(program);
+        let allowed_in_scope = |warning: &CompileWarning| {
+            warning.span().is_some_and(|span| {
+                scopes.iter().any(|(scope, rules)| {
+                    scope.start <= span.start && span.start < scope.end && rules.iter().any(|r| r == warning.kind())
+                })
+            })
+        };
+
+        let mut linted = Linted::default();
+        for warning in warnings {
+            if allowed_in_scope(&warning) {
+                linted.allowed += 1;
+                continue;
+            }
+            match self.level(warning.kind()) {
+                LintLevel::Allow => linted.allowed += 1,
+                LintLevel::Warn => linted.warnings.push(warning),
+                LintLevel::Deny => linted.denied.push(warning),
+            }
+        }
+        linted
+    }
+}
+
+/// Warnings left after applying a [`LintConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct Linted {
+    /// Reported as warnings
+    pub warnings: Vec<CompileWarning>,
+    /// Reported as errors
+    pub denied: Vec<CompileWarning>,
+    /// Number suppressed
+    pub allowed: usize,
+}
+
+impl Linted {
+    /// Number of reported warnings and errors of each kind
+    pub fn counts_by_rule(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for warning in self.warnings.iter().chain(&self.denied) {
+            *counts.entry(warning.kind()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// Span of each function with an `@allow(...)`, and the kinds it allows
+fn allow_scopes(program: &Program) -> Vec<(Span, Vec<String>)> {
+    let functions = program.items.iter().flat_map(|item| -> Box<dyn Iterator<Item = &FnDef>> {
+        match item {
+            Item::FnDef(f) => Box::new(std::iter::once(f)),
+            Item::ImplBlock(block) => Box::new(block.methods.iter()),
+            _ => Box::new(std::iter::empty()),
+        }
+    });
+    functions
+        .filter_map(|f| {
+            let rules: Vec<String> = f
+                .attributes
+                .iter()
+                .filter(|attr| attr.name() == "allow")
+                .flat_map(|attr| match attr {
+                    Attribute::WithArgs { args, .. } => args.as_slice(),
+                    _ => &[],
+                })
+                .filter_map(|arg| match &arg.node {
+                    Expr::Var(rule) => Some(rule.clone()),
+                    _ => None,
+                })
+                .collect();
+            (!rules.is_empty()).then_some((f.span, rules))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str, config: &LintConfig) -> Linted {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("t.bmb", source, tokens).unwrap();
+        let mut checker = crate::types::TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        config.apply(&program, checker.warnings().to_vec())
+    }
+
+    const SOURCE: &str = "fn shadowing() -> i64 = {\n    let x = 1;\n    let x = x + 1;\n    x\n};\n\n\
+                          @allow(shadow_binding)\nfn allowed() -> i64 = {\n    let x = 1;\n    let x = x + 1;\n    let unused = 0;\n    x\n};\n";
+
+    #[test]
+    fn test_levels_and_allow_attribute() {
+        let default = lint(SOURCE, &LintConfig::default());
+        let kinds: Vec<_> = default.warnings.iter().map(|w| w.kind()).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == "shadow_binding").count(), 1);
+        assert!(kinds.contains(&"unused_binding"));
+        assert!(default.denied.is_empty());
+        assert_eq!(default.allowed, 1);
+
+        let config = LintConfig::parse(
+            "[package]\nname = \"app\"\n\n[lint]\nshadow_binding = \"deny\"\nunused_binding = \"allow\"\n",
+            Path::new("bmb.toml"),
+        )
+        .unwrap();
+        assert_eq!(config.level("shadow_binding"), LintLevel::Deny);
+        assert_eq!(config.level("unused_mut"), LintLevel::Warn);
+        let linted = lint(SOURCE, &config);
+        assert!(linted.warnings.iter().all(|w| w.kind() != "unused_binding"));
+        assert_eq!(linted.denied.len(), 1);
+        assert_eq!(linted.counts_by_rule().get("shadow_binding"), Some(&1));
+        assert_eq!(linted.counts_by_rule().get("unused_binding"), None);
+    }
+
+    #[test]
+    fn test_unknown_rules_and_levels_are_rejected() {
+        let err = LintConfig::parse("[lint]\nshadow_bindings = \"deny\"\n", Path::new("lint.toml")).unwrap_err();
+        assert!(err.to_string().contains("unknown lint rule `shadow_bindings`"), "{err}");
+        let err = LintConfig::parse("[lint]\nshadow_binding = \"forbid\"\n", Path::new("lint.toml")).unwrap_err();
+        assert!(matches!(err, ProjectError::InvalidManifest { .. }), "{err}");
+    }
+}
@@ -0,0 +1,684 @@
+//! Context-aware completion
+//!
+//! What to complete is decided from the tokens before the cursor, so it
+//! works while the line being typed does not parse: `Enum::` offers the
+//! variants, `receiver.` the fields and methods of the receiver's type, and
+//! anything else the names in scope. Scopes and types come from the last
+//! AST that parsed.
+
+use std::path::Path;
+
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, InsertTextFormat, MarkupContent, MarkupKind,
+};
+
+use crate::ast::{Expr, FnDef, Item, Pattern, Program, Span, Spanned, Type};
+use crate::lexer::{self, Token};
+use crate::resolver::Resolver;
+use crate::types::TypeChecker;
+
+use super::{format_type, BMB_KEYWORDS};
+
+/// What the cursor is completing
+#[derive(Debug, PartialEq)]
+pub enum CompletionContext {
+    /// After `Name::`
+    Path(String),
+    /// After `a.b.`: the identifiers of the receiver chain, or empty when
+    /// the receiver is not a plain chain
+    Member(Vec<String>),
+    /// Anywhere else
+    Name,
+}
+
+/// Everything completion knows about a document
+pub struct CompletionSource<'a> {
+    pub content: &'a str,
+    pub program: Option<&'a Program>,
+    /// Inferred expression types by span
+    pub expr_types: &'a [(Span, Type)],
+    /// Directory that `use` paths resolve against
+    pub base_dir: Option<&'a Path>,
+}
+
+/// Completion context at byte `offset`, from the tokens before it
+pub fn context_at(content: &str, offset: usize) -> CompletionContext {
+    let Some(prefix) = content.get(..offset) else { return CompletionContext::Name };
+    let Ok(mut tokens) = lexer::tokenize(prefix) else { return CompletionContext::Name };
+
+    // Drop the partial identifier being typed
+    if let Some((Token::Ident(_), span)) = tokens.last()
+        && span.end == offset
+    {
+        tokens.pop();
+    }
+
+    match tokens.as_slice() {
+        [.., (Token::Ident(name), _), (Token::ColonColon, _)] => CompletionContext::Path(name.clone()),
+        [.., (Token::Dot, _)] => {
+            let mut chain = Vec::new();
+            let mut rest = &tokens[..tokens.len() - 1];
+            loop {
+                let [before @ .., (Token::Ident(name), _)] = rest else {
+                    // Call results, index expressions, ...: type unknown here
+                    chain.clear();
+                    break;
+                };
+                chain.push(name.clone());
+                match before {
+                    [earlier @ .., (Token::Dot, _)] => rest = earlier,
+                    _ => break,
+                }
+            }
+            chain.reverse();
+            CompletionContext::Member(chain)
+        }
+        _ => CompletionContext::Name,
+    }
+}
+
+/// Completion items at byte `offset`
+pub fn completions(source: &CompletionSource, offset: usize) -> Vec<CompletionItem> {
+    let imported = source
+        .program
+        .zip(source.base_dir)
+        .map(|(program, dir)| imported_items(program, dir))
+        .unwrap_or_default();
+    let programs: Vec<&Program> = source
+        .program
+        .into_iter()
+        .chain(imported.iter().map(|(_, program)| program))
+        .collect();
+
+    match context_at(source.content, offset) {
+        CompletionContext::Path(enum_name) => variant_items(&programs, &enum_name),
+        CompletionContext::Member(chain) => {
+            let locals = source.program.map(|p| locals_at(p, source.expr_types, offset)).unwrap_or_default();
+            member_items(&programs, &locals, &chain)
+        }
+        CompletionContext::Name => {
+            let mut items = Vec::new();
+            if let Some(program) = source.program {
+                for local in locals_at(program, source.expr_types, offset).iter().rev() {
+                    if items.iter().any(|i: &CompletionItem| i.label == local.name) {
+                        continue; // shadowed
+                    }
+                    items.push(CompletionItem {
+                        label: local.name.clone(),
+                        kind: Some(CompletionItemKind::VARIABLE),
+                        detail: local.ty.as_ref().map(|ty| format!("{}: {}", local.name, format_type(ty))),
+                        ..Default::default()
+                    });
+                }
+                for item in &program.items {
+                    items.extend(item_completion(item, None));
+                }
+            }
+            for (name, module_items) in &imported {
+                items.extend(module_items.items.iter().filter_map(|item| item_completion(item, Some(name))));
+            }
+            items.extend(builtin_items());
+            items.extend(BMB_KEYWORDS.iter().map(|keyword| CompletionItem {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                detail: Some("keyword".to_string()),
+                ..Default::default()
+            }));
+            items
+        }
+    }
+}
+
+/// Items brought in by `use`, with the name they are imported under, as
+/// single-item programs
+fn imported_items(program: &Program, base_dir: &Path) -> Vec<(String, Program)> {
+    let mut resolver = Resolver::new(base_dir);
+    let Ok(imports) = resolver.resolve_uses(program) else { return Vec::new() };
+    let mut items: Vec<(String, Program)> = imports
+        .all_imports()
+        .filter_map(|(name, info)| {
+            let module = resolver.get_module(&info.module)?;
+            let item = module.program.items.iter().find(|item| item.name() == Some(info.item.name()))?;
+            Some((name.clone(), Program { header: None, items: vec![item.clone()] }))
+        })
+        .collect();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    items
+}
+
+/// Completion for a top-level item, optionally under an imported name
+fn item_completion(item: &Item, imported_as: Option<&String>) -> Option<CompletionItem> {
+    let label = imported_as.cloned().or_else(|| item.name().map(str::to_string))?;
+    let completion = match item {
+        Item::FnDef(f) => function_item(&label, f),
+        Item::ExternFn(e) => {
+            let params: Vec<String> = e.params.iter()
+                .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+                .collect();
+            CompletionItem {
+                label: label.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(format!("extern fn {}({}) -> {}", e.name.node, params.join(", "), format_type(&e.ret_ty.node))),
+                insert_text: Some(format!("{}($0)", label)),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                ..Default::default()
+            }
+        }
+        Item::StructDef(s) => CompletionItem {
+            label,
+            kind: Some(CompletionItemKind::STRUCT),
+            detail: Some(format!("struct {}", s.name.node)),
+            ..Default::default()
+        },
+        Item::EnumDef(e) => CompletionItem {
+            label,
+            kind: Some(CompletionItemKind::ENUM),
+            detail: Some(format!("enum {}", e.name.node)),
+            ..Default::default()
+        },
+        Item::TraitDef(t) => CompletionItem {
+            label,
+            kind: Some(CompletionItemKind::INTERFACE),
+            detail: Some(format!("trait {}", t.name.node)),
+            ..Default::default()
+        },
+        Item::TypeAlias(t) => CompletionItem {
+            label,
+            kind: Some(CompletionItemKind::TYPE_PARAMETER),
+            detail: Some(format!("type {} = {}", t.name.node, format_type(&t.target.node))),
+            ..Default::default()
+        },
+        Item::ConstDef(c) => CompletionItem {
+            label,
+            kind: Some(CompletionItemKind::CONSTANT),
+            detail: Some(format!(
+                "const {}: {} = {}",
+                c.name.node,
+                format_type(&c.ty.node),
+                crate::fmt::format_expr(&c.value.node)
+            )),
+            ..Default::default()
+        },
+        Item::Use(_) | Item::ImplBlock(_) => return None,
+    };
+    Some(completion)
+}
+
+/// Completion for a function or method: signature as detail, contracts as
+/// documentation, and a call snippet with one placeholder per parameter
+fn function_item(label: &str, f: &FnDef) -> CompletionItem {
+    let placeholders: Vec<String> = f.params.iter()
+        .enumerate()
+        .map(|(i, p)| format!("${{{}:{}}}", i + 1, p.name.node))
+        .collect();
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(CompletionItemKind::FUNCTION),
+        detail: Some(signature(f)),
+        documentation: contract_text(f).map(|text| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```bmb\n{}\n```", text),
+            })
+        }),
+        insert_text: Some(format!("{}({})", label, placeholders.join(", "))),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    }
+}
+
+/// `fn name(a: T, ...) -> R`
+fn signature(f: &FnDef) -> String {
+    let params: Vec<String> = f.params.iter()
+        .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+        .collect();
+    format!("fn {}({}) -> {}", f.name.node, params.join(", "), format_type(&f.ret_ty.node))
+}
+
+/// The function's `pre`, `post` and named contracts, one per line
+fn contract_text(f: &FnDef) -> Option<String> {
+    let mut lines = Vec::new();
+    if let Some(pre) = &f.pre {
+        lines.push(format!("pre {}", crate::fmt::format_expr(&pre.node)));
+    }
+    if let Some(post) = &f.post {
+        lines.push(format!("post {}", crate::fmt::format_expr(&post.node)));
+    }
+    for contract in &f.contracts {
+        let condition = crate::fmt::format_expr(&contract.condition.node);
+        lines.push(match &contract.name {
+            Some(name) => format!("where {}: {}", name.node, condition),
+            None => format!("where {}", condition),
+        });
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Built-in functions from the type checker's table
+fn builtin_items() -> Vec<CompletionItem> {
+    TypeChecker::builtin_signatures()
+        .into_iter()
+        .map(|(name, params, ret)| {
+            let params: Vec<String> = params.iter().map(format_type).collect();
+            CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(format!("fn {}({}) -> {}", name, params.join(", "), format_type(&ret))),
+                insert_text: Some(format!("{}($0)", name)),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Variants of the enum `enum_name`
+fn variant_items(programs: &[&Program], enum_name: &str) -> Vec<CompletionItem> {
+    let Some(def) = programs.iter().flat_map(|p| &p.items).find_map(|item| match item {
+        Item::EnumDef(e) if e.name.node == enum_name => Some(e),
+        _ => None,
+    }) else {
+        return Vec::new();
+    };
+    def.variants
+        .iter()
+        .map(|variant| {
+            let detail = if variant.fields.is_empty() {
+                format!("{}::{}", enum_name, variant.name.node)
+            } else {
+                let fields: Vec<String> = variant.fields.iter().map(|f| format_type(&f.node)).collect();
+                format!("{}::{}({})", enum_name, variant.name.node, fields.join(", "))
+            };
+            CompletionItem {
+                label: variant.name.node.clone(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some(detail),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Fields and methods of the type reached by the receiver chain `a.b.c`
+fn member_items(programs: &[&Program], locals: &[Local], chain: &[String]) -> Vec<CompletionItem> {
+    let Some((first, fields)) = chain.split_first() else { return Vec::new() };
+    let Some(mut ty) = locals.iter().rev().find(|l| l.name == *first).and_then(|l| l.ty.clone()) else {
+        return Vec::new();
+    };
+    for field in fields {
+        let Some(field_ty) = struct_def(programs, &ty)
+            .and_then(|s| s.fields.iter().find(|f| f.name.node == *field))
+            .map(|f| f.ty.node.clone())
+        else {
+            return Vec::new();
+        };
+        ty = field_ty;
+    }
+    let Some(type_name) = type_name(&ty) else { return Vec::new() };
+
+    let mut items = Vec::new();
+    if let Some(def) = struct_def(programs, &ty) {
+        items.extend(def.fields.iter().map(|field| CompletionItem {
+            label: field.name.node.clone(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(format!("{}: {}", field.name.node, format_type(&field.ty.node))),
+            ..Default::default()
+        }));
+    }
+    for item in programs.iter().flat_map(|p| &p.items) {
+        if let Item::ImplBlock(block) = item
+            && type_name_of(&block.target_type.node) == Some(type_name)
+        {
+            items.extend(block.methods.iter().map(|m| CompletionItem {
+                kind: Some(CompletionItemKind::METHOD),
+                ..function_item(&m.name.node, m)
+            }));
+        }
+    }
+    items
+}
+
+fn struct_def<'p>(programs: &[&'p Program], ty: &Type) -> Option<&'p crate::ast::StructDef> {
+    let name = type_name(ty)?;
+    programs.iter().flat_map(|p| &p.items).find_map(|item| match item {
+        Item::StructDef(s) if s.name.node == name => Some(s),
+        _ => None,
+    })
+}
+
+/// Name of the user-defined type behind references and `?`
+fn type_name(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Ref(inner) | Type::RefMut(inner) | Type::Nullable(inner) => type_name(inner),
+        other => type_name_of(other),
+    }
+}
+
+fn type_name_of(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Named(name) | Type::Struct { name, .. } | Type::Enum { name, .. } | Type::Generic { name, .. } => {
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/// A local variable or parameter in scope
+#[derive(Debug)]
+pub struct Local {
+    pub name: String,
+    pub ty: Option<Type>,
+}
+
+/// Locals in scope at `offset`, outermost first.
+///
+/// `program` may predate the latest edits, so the cursor belongs to the last
+/// function starting before it even when it lies past that function's end.
+pub fn locals_at(program: &Program, expr_types: &[(Span, Type)], offset: usize) -> Vec<Local> {
+    let functions = program.items.iter().flat_map(|item| match item {
+        Item::FnDef(f) => std::slice::from_ref(f),
+        Item::ImplBlock(block) => block.methods.as_slice(),
+        _ => &[],
+    });
+    let Some(func) = functions.filter(|f| f.span.start <= offset).max_by_key(|f| f.span.start) else {
+        return Vec::new();
+    };
+
+    let mut scope = Scope { offset, expr_types, locals: Vec::new() };
+    for param in &func.params {
+        scope.bind(&param.name.node, Some(param.ty.node.clone()));
+    }
+    scope.enter(&func.body);
+    scope.locals
+}
+
+struct Scope<'a> {
+    offset: usize,
+    expr_types: &'a [(Span, Type)],
+    locals: Vec<Local>,
+}
+
+impl Scope<'_> {
+    fn bind(&mut self, name: &str, ty: Option<Type>) {
+        self.locals.push(Local { name: name.to_string(), ty });
+    }
+
+    fn contains(&self, e: &Spanned<Expr>) -> bool {
+        e.span.start <= self.offset && self.offset <= e.span.end
+    }
+
+    fn type_of(&self, e: &Spanned<Expr>) -> Option<Type> {
+        self.expr_types.iter().rev().find(|(span, _)| *span == e.span).map(|(_, ty)| ty.clone())
+    }
+
+    fn bind_let(&mut self, name: &str, ty: Option<&Spanned<Type>>, value: &Spanned<Expr>) {
+        let ty = ty.map(|t| t.node.clone()).or_else(|| self.type_of(value));
+        self.bind(name, ty);
+    }
+
+    /// Bind the lets of an earlier block statement; they stay visible to the
+    /// rest of the block
+    fn bind_statement_lets(&mut self, mut stmt: &Spanned<Expr>) {
+        if let Expr::LetStmt { name, ty, value, .. } = &stmt.node {
+            self.bind_let(name, ty.as_ref(), value);
+            return;
+        }
+        while let Expr::Let { name, ty, value, body, .. } = &stmt.node {
+            self.bind_let(name, ty.as_ref(), value);
+            stmt = body;
+        }
+    }
+
+    /// Bind the locals in scope at the offset, if it is inside `e`
+    fn expr(&mut self, e: &Spanned<Expr>) {
+        if self.contains(e) {
+            self.enter(e);
+        }
+    }
+
+    /// Bind the locals in scope at the offset within `e`
+    fn enter(&mut self, e: &Spanned<Expr>) {
+        match &e.node {
+            Expr::Let { name, ty, value, body, .. } => {
+                if self.offset <= value.span.end {
+                    self.expr(value);
+                } else {
+                    self.bind_let(name, ty.as_ref(), value);
+                    self.expr(body);
+                }
+            }
+            Expr::LetStmt { value, .. } => self.expr(value),
+            Expr::Block(stmts) => {
+                for stmt in stmts {
+                    if stmt.span.end < self.offset {
+                        self.bind_statement_lets(stmt);
+                    } else {
+                        self.expr(stmt);
+                        break;
+                    }
+                }
+            }
+            Expr::For { var, iter, body, .. } => {
+                if self.contains(body) {
+                    let elem = match self.type_of(iter) {
+                        Some(Type::Range(elem)) => Some(*elem),
+                        _ => None,
+                    };
+                    self.bind(var, elem);
+                    self.expr(body);
+                } else {
+                    self.expr(iter);
+                }
+            }
+            Expr::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    let in_guard = arm.guard.as_ref().is_some_and(|g| self.contains(g));
+                    if in_guard || self.contains(&arm.body) {
+                        self.pattern(&arm.pattern.node);
+                        if let Some(guard) = &arm.guard {
+                            self.expr(guard);
+                        }
+                        self.expr(&arm.body);
+                    }
+                }
+            }
+            Expr::Closure { params, body, .. } => {
+                for param in params {
+                    self.bind(&param.name.node, param.ty.as_ref().map(|t| t.node.clone()));
+                }
+                self.expr(body);
+            }
+            Expr::Forall { var, ty, body } | Expr::Exists { var, ty, body } => {
+                self.bind(&var.node, Some(ty.node.clone()));
+                self.expr(body);
+            }
+            Expr::Call { args, .. } | Expr::EnumVariant { args, .. } => {
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::StructInit { fields, base, .. } => {
+                fields.iter().for_each(|(_, v)| self.expr(v));
+                base.iter().for_each(|b| self.expr(b));
+            }
+            Expr::ArrayLit(elems) | Expr::Tuple(elems) => elems.iter().for_each(|x| self.expr(x)),
+            Expr::Binary { left, right, .. } | Expr::Index { expr: left, index: right } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::IndexAssign { array, index, value } => {
+                self.expr(array);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::If { cond, then_branch, else_branch } => {
+                self.expr(cond);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            Expr::While { cond, invariant, body, .. } => {
+                self.expr(cond);
+                if let Some(inv) = invariant {
+                    self.expr(inv);
+                }
+                self.expr(body);
+            }
+            Expr::Assign { value: inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::FieldAccess { expr: inner, .. }
+            | Expr::TupleField { expr: inner, .. }
+            | Expr::StateRef { expr: inner, .. }
+            | Expr::Cast { expr: inner, .. }
+            | Expr::Ref(inner)
+            | Expr::RefMut(inner)
+            | Expr::Deref(inner)
+            | Expr::Loop { body: inner, .. }
+            | Expr::Break { value: Some(inner), .. }
+            | Expr::Return { value: Some(inner) } => self.expr(inner),
+            Expr::Break { value: None, .. }
+            | Expr::Return { value: None }
+            | Expr::IntLit(_)
+            | Expr::FloatLit(_)
+            | Expr::BoolLit(_)
+            | Expr::StringLit(_)
+            | Expr::CharLit(_)
+            | Expr::Var(_)
+            | Expr::Unit
+            | Expr::Continue { .. }
+            | Expr::Ret
+            | Expr::It
+            | Expr::Todo { .. } => {}
+        }
+    }
+
+    /// Bind the variables of a match pattern (their types are not tracked)
+    fn pattern(&mut self, p: &Pattern) {
+        match p {
+            Pattern::Var(name) => self.bind(name, None),
+            Pattern::Binding { name, pattern } => {
+                self.bind(name, None);
+                self.pattern(&pattern.node);
+            }
+            Pattern::EnumVariant { bindings: pats, .. }
+            | Pattern::Or(pats)
+            | Pattern::Tuple(pats)
+            | Pattern::Array(pats) => pats.iter().for_each(|x| self.pattern(&x.node)),
+            Pattern::Struct { fields, .. } => fields.iter().for_each(|(_, f)| self.pattern(&f.node)),
+            Pattern::ArrayRest { prefix, suffix } => {
+                prefix.iter().chain(suffix).for_each(|x| self.pattern(&x.node));
+            }
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_source(source: &str) -> Program {
+        let tokens = lexer::tokenize(source).unwrap();
+        crate::parser::parse("test.bmb", source, tokens).unwrap()
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_context_from_tokens_before_cursor() {
+        let source = "fn f() -> i64 = Color::R";
+        assert_eq!(context_at(source, source.len()), CompletionContext::Path("Color".to_string()));
+        let source = "fn f(p: P) -> i64 = p.inner.";
+        assert_eq!(
+            context_at(source, source.len()),
+            CompletionContext::Member(vec!["p".to_string(), "inner".to_string()])
+        );
+        let source = "fn f() -> i64 = g().";
+        assert_eq!(context_at(source, source.len()), CompletionContext::Member(vec![]));
+        let source = "fn f() -> i64 = ab";
+        assert_eq!(context_at(source, source.len()), CompletionContext::Name);
+    }
+
+    #[test]
+    fn test_locals_in_scope_at_cursor() {
+        let source = "fn f(n: i64) -> i64 = {\n    let a: i64 = n;\n    let b = a + 1;\n    b\n};\n\nfn g(m: i64) -> i64 = m;\n";
+        let program = parse_source(source);
+        let offset = source.find("    b\n").unwrap() + 4;
+        let names: Vec<String> = locals_at(&program, &[], offset).into_iter().map(|l| l.name).collect();
+        assert_eq!(names, ["n", "a", "b"]);
+
+        // The initializer cannot see its own binding
+        let offset = source.find("a + 1").unwrap();
+        let names: Vec<String> = locals_at(&program, &[], offset).into_iter().map(|l| l.name).collect();
+        assert_eq!(names, ["n", "a"]);
+    }
+
+    #[test]
+    fn test_member_and_variant_completion() {
+        let source = "struct Inner { v: i64 }\nstruct Outer { inner: Inner, n: i64 }\nenum Color { Red, Rgb(i64, i64, i64) }\n\n\
+                      fn f(o: Outer) -> i64\n  pre o.n > 0\n= o.n;\n";
+        let program = parse_source(source);
+
+        // `o.inner.` typed at the end of f's body, with f's old AST
+        let edited = source.replace("= o.n;", "= o.inner.;");
+        let offset = edited.find("inner.;").unwrap() + "inner.".len();
+        let items = completions(
+            &CompletionSource { content: &edited, program: Some(&program), expr_types: &[], base_dir: None },
+            offset,
+        );
+        assert_eq!(labels(&items), ["v"]);
+        assert_eq!(items[0].detail.as_deref(), Some("v: i64"));
+
+        let edited = source.replace("= o.n;", "= Color::;");
+        let offset = edited.find("::;").unwrap() + 2;
+        let items = completions(
+            &CompletionSource { content: &edited, program: Some(&program), expr_types: &[], base_dir: None },
+            offset,
+        );
+        assert_eq!(labels(&items), ["Red", "Rgb"]);
+        assert_eq!(items[1].detail.as_deref(), Some("Color::Rgb(i64, i64, i64)"));
+
+        let offset = source.find("o.n;").unwrap();
+        let items = completions(
+            &CompletionSource { content: source, program: Some(&program), expr_types: &[], base_dir: None },
+            offset,
+        );
+        let f = items.iter().find(|i| i.label == "f").unwrap();
+        assert_eq!(f.detail.as_deref(), Some("fn f(o: Outer) -> i64"));
+        let Some(Documentation::MarkupContent(doc)) = &f.documentation else { panic!("no contract docs") };
+        assert!(doc.value.contains("pre o.n > 0"));
+        assert!(labels(&items).contains(&"o"));
+        assert!(labels(&items).contains(&"read_file"));
+    }
+
+    #[test]
+    fn test_imported_items_complete() {
+        let dir = std::env::temp_dir().join(format!("bmb_lsp_completion_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("mathx.bmb"), "pub fn gcd(a: i64, b: i64) -> i64 = a;\n").unwrap();
+
+        let source = "use mathx::gcd;\n\nfn f() -> i64 = 1;\n";
+        let program = parse_source(source);
+        let offset = source.find("1;").unwrap();
+        let items = completions(
+            &CompletionSource { content: source, program: Some(&program), expr_types: &[], base_dir: Some(&dir) },
+            offset,
+        );
+        let gcd = items.iter().find(|i| i.label == "gcd").unwrap();
+        assert_eq!(gcd.detail.as_deref(), Some("fn gcd(a: i64, b: i64) -> i64"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,26 +1,42 @@
 //! BMB Language Server Protocol implementation
 //!
 //! Provides IDE features:
-//! - Diagnostics (type errors, parse errors)
+//! - Diagnostics (type errors, parse errors, lint warnings)
+//! - Code actions (quick fixes for lint warnings)
 //! - Hover (type information)
-//! - Completion (keywords, built-ins)
+//! - Completion (scoped locals, items, imports, enum variants, struct fields)
 //! - Formatting (v0.9.0)
 //! - Go to Definition (v0.9.0)
-//! - Find References (v0.9.0)
+//! - Find References (v0.9.0), across workspace files
+//! - Rename, across workspace files
+//! - Semantic tokens (syntax highlighting)
+
+mod completion;
+mod quick_fix;
+pub(crate) mod references;
+pub mod rename;
+mod semantic_tokens;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::RwLock;
 
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use crate::ast::{Expr, Item, Program, Span};
-use crate::error::CompileError;
+use crate::ast::{Expr, FnDef, Item, LineCol, LineIndex, Program, Span, Type};
+use crate::error::{CompileError, CompileWarning};
 use crate::lexer;
 use crate::parser;
+use crate::resolver;
 use crate::types::TypeChecker;
 
+use completion::{completions, CompletionSource};
+use quick_fix::quick_fix;
+use references::{collect_occurrences, occurrence_at, Occurrence};
+use rename::{rename_symbol, RenameFile};
+
 /// BMB Language keywords for completion
 const BMB_KEYWORDS: &[&str] = &[
     "fn", "let", "mut", "if", "then", "else", "match", "for", "in", "while",
@@ -30,17 +46,6 @@ const BMB_KEYWORDS: &[&str] = &[
     "invariant", "decreases",
 ];
 
-/// BMB built-in functions for completion
-const BMB_BUILTINS: &[(&str, &str)] = &[
-    ("print", "print(x: i64) -> Unit"),
-    ("println", "println(x: i64) -> Unit"),
-    ("assert", "assert(cond: bool) -> Unit"),
-    ("read_int", "read_int() -> i64"),
-    ("abs", "abs(n: i64) -> i64"),
-    ("min", "min(a: i64, b: i64) -> i64"),
-    ("max", "max(a: i64, b: i64) -> i64"),
-];
-
 /// Symbol definition with location
 #[derive(Debug, Clone)]
 struct SymbolDef {
@@ -50,13 +55,6 @@ struct SymbolDef {
     span: Span,
 }
 
-/// Symbol reference (usage)
-#[derive(Debug, Clone)]
-struct SymbolRef {
-    name: String,
-    span: Span,
-}
-
 /// Symbol kind for definition
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[allow(dead_code)]
@@ -68,24 +66,46 @@ enum SymbolKind {
     Parameter,
     Trait,   // v0.20.1
     Method,  // v0.20.1
+    Constant,
 }
 
 /// Document state
 struct DocumentState {
     content: String,
+    /// Items that parsed, even if others had syntax errors
     ast: Option<Program>,
+    /// Whether `ast` covers the whole document (no syntax errors)
+    complete: bool,
     /// Symbol definitions in this document
     definitions: Vec<SymbolDef>,
-    /// Symbol references in this document
-    references: Vec<SymbolRef>,
+    /// Definitions and uses of every name, for find-references
+    occurrences: Vec<Occurrence>,
+    /// Inferred expression types by span, for hover
+    expr_types: Vec<(Span, Type)>,
+    /// Lint warnings, for code actions
+    warnings: Vec<CompileWarning>,
+    /// AST and expression types of the last version that parsed, kept for
+    /// completion while the document has syntax errors
+    last_parsed: Option<(Program, Vec<(Span, Type)>)>,
     #[allow(dead_code)]
     version: i32,
 }
 
+/// Result of checking a document
+#[derive(Default)]
+struct Analysis {
+    diagnostics: Vec<Diagnostic>,
+    /// Inferred expression types by span
+    expr_types: Vec<(Span, Type)>,
+    warnings: Vec<CompileWarning>,
+}
+
 /// BMB Language Server Backend
 pub struct Backend {
     client: Client,
     documents: RwLock<HashMap<Url, DocumentState>>,
+    /// Workspace root, searched for cross-file references
+    root: RwLock<Option<PathBuf>>,
 }
 
 impl Backend {
@@ -93,19 +113,23 @@ impl Backend {
         Self {
             client,
             documents: RwLock::new(HashMap::new()),
+            root: RwLock::new(None),
         }
     }
 
     /// Analyze document and publish diagnostics
     async fn analyze_document(&self, uri: &Url, content: &str, version: i32) {
-        let diagnostics = self.get_diagnostics(uri, content);
+        let Analysis { diagnostics, expr_types, warnings } = self.get_diagnostics(uri, content);
 
-        // Parse AST if successful for hover/completion
-        let ast = self.try_parse(content);
+        // Parse what we can for hover/completion
+        let (ast, complete) = match self.try_parse_partial(content) {
+            Some((ast, errors)) => (Some(ast), errors.is_empty()),
+            None => (None, false),
+        };
 
         // Collect symbols from AST
-        let (definitions, references) = if let Some(ref ast) = ast {
-            self.collect_symbols(ast)
+        let (definitions, occurrences) = if let Some(ref ast) = ast {
+            (self.collect_symbols(ast), collect_occurrences(ast, content))
         } else {
             (Vec::new(), Vec::new())
         };
@@ -113,11 +137,22 @@ impl Backend {
         // Store document state
         {
             let mut docs = self.documents.write().unwrap();
+            let last_parsed = match docs.remove(uri) {
+                Some(previous) if !complete => match previous.ast {
+                    Some(previous_ast) if previous.complete => Some((previous_ast, previous.expr_types)),
+                    _ => previous.last_parsed,
+                },
+                _ => None,
+            };
             docs.insert(uri.clone(), DocumentState {
                 content: content.to_string(),
                 ast,
+                complete,
                 definitions,
-                references,
+                occurrences,
+                expr_types,
+                warnings,
+                last_parsed,
                 version,
             });
         }
@@ -128,10 +163,9 @@ impl Backend {
             .await;
     }
 
-    /// Collect symbol definitions and references from AST
-    fn collect_symbols(&self, ast: &Program) -> (Vec<SymbolDef>, Vec<SymbolRef>) {
+    /// Collect symbol definitions from AST
+    fn collect_symbols(&self, ast: &Program) -> Vec<SymbolDef> {
         let mut definitions = Vec::new();
-        let mut references = Vec::new();
 
         for item in &ast.items {
             match item {
@@ -151,17 +185,6 @@ impl Backend {
                             span: param.name.span,
                         });
                     }
-
-                    // Collect references from body
-                    self.collect_expr_refs(&f.body.node, &mut references);
-
-                    // Pre/post conditions
-                    if let Some(pre) = &f.pre {
-                        self.collect_expr_refs(&pre.node, &mut references);
-                    }
-                    if let Some(post) = &f.post {
-                        self.collect_expr_refs(&post.node, &mut references);
-                    }
                 }
                 Item::StructDef(s) => {
                     definitions.push(SymbolDef {
@@ -202,156 +225,79 @@ impl Backend {
                             kind: SymbolKind::Method,
                             span: method.name.span,
                         });
-                        self.collect_expr_refs(&method.body.node, &mut references);
                     }
                 }
                 // v0.50.6: Type aliases - register as type definitions
                 Item::TypeAlias(_) => {}
-            }
-        }
-
-        (definitions, references)
-    }
-
-    /// Collect symbol references from expression
-    fn collect_expr_refs(&self, expr: &Expr, refs: &mut Vec<SymbolRef>) {
-        match expr {
-            Expr::Var(_name) => {
-                // This is a reference to a variable/function
-                // Note: We can't easily get the span here from Expr::Var
-                // For a more complete implementation, Expr::Var would need to be Spanned
-            }
-            Expr::Call { func: _, args, .. } => {
-                // Function call is a reference (name-only, no span in current AST)
-                for arg in args {
-                    self.collect_expr_refs(&arg.node, refs);
-                }
-            }
-            Expr::Let { value, body, .. } => {
-                self.collect_expr_refs(&value.node, refs);
-                self.collect_expr_refs(&body.node, refs);
-            }
-            Expr::If { cond, then_branch, else_branch } => {
-                self.collect_expr_refs(&cond.node, refs);
-                self.collect_expr_refs(&then_branch.node, refs);
-                self.collect_expr_refs(&else_branch.node, refs);
-            }
-            Expr::Binary { left, right, .. } => {
-                self.collect_expr_refs(&left.node, refs);
-                self.collect_expr_refs(&right.node, refs);
-            }
-            Expr::Unary { expr, .. } => {
-                self.collect_expr_refs(&expr.node, refs);
-            }
-            Expr::Block(stmts) => {
-                for stmt in stmts {
-                    self.collect_expr_refs(&stmt.node, refs);
-                }
-            }
-            // v0.37: Include invariant in refs collection
-            Expr::While { cond, invariant, body } => {
-                self.collect_expr_refs(&cond.node, refs);
-                if let Some(inv) = invariant {
-                    self.collect_expr_refs(&inv.node, refs);
-                }
-                self.collect_expr_refs(&body.node, refs);
-            }
-            Expr::For { iter, body, .. } => {
-                self.collect_expr_refs(&iter.node, refs);
-                self.collect_expr_refs(&body.node, refs);
-            }
-            Expr::Match { expr, arms } => {
-                self.collect_expr_refs(&expr.node, refs);
-                for arm in arms {
-                    self.collect_expr_refs(&arm.body.node, refs);
-                }
-            }
-            Expr::MethodCall { receiver, args, .. } => {
-                self.collect_expr_refs(&receiver.node, refs);
-                for arg in args {
-                    self.collect_expr_refs(&arg.node, refs);
-                }
-            }
-            Expr::FieldAccess { expr, .. } => {
-                self.collect_expr_refs(&expr.node, refs);
-            }
-            // v0.43: Tuple field access
-            Expr::TupleField { expr, .. } => {
-                self.collect_expr_refs(&expr.node, refs);
-            }
-            Expr::Index { expr, index } => {
-                self.collect_expr_refs(&expr.node, refs);
-                self.collect_expr_refs(&index.node, refs);
-            }
-            Expr::ArrayLit(elems) => {
-                for elem in elems {
-                    self.collect_expr_refs(&elem.node, refs);
-                }
-            }
-            // v0.42: Tuple expressions
-            Expr::Tuple(elems) => {
-                for elem in elems {
-                    self.collect_expr_refs(&elem.node, refs);
-                }
-            }
-            Expr::StructInit { fields, .. } => {
-                for (_, value) in fields {
-                    self.collect_expr_refs(&value.node, refs);
-                }
-            }
-            Expr::Range { start, end, .. } => {
-                self.collect_expr_refs(&start.node, refs);
-                self.collect_expr_refs(&end.node, refs);
-            }
-            Expr::Assign { value, .. } => {
-                self.collect_expr_refs(&value.node, refs);
-            }
-            Expr::Ref(inner) | Expr::RefMut(inner) | Expr::Deref(inner) => {
-                self.collect_expr_refs(&inner.node, refs);
-            }
-            Expr::EnumVariant { args, .. } => {
-                for arg in args {
-                    self.collect_expr_refs(&arg.node, refs);
+                Item::ConstDef(c) => {
+                    definitions.push(SymbolDef {
+                        name: c.name.node.clone(),
+                        kind: SymbolKind::Constant,
+                        span: c.name.span,
+                    });
                 }
             }
-            Expr::StateRef { expr, .. } => {
-                self.collect_expr_refs(&expr.node, refs);
-            }
-            // Literals and simple expressions
-            _ => {}
         }
+
+        definitions
     }
 
     /// Get diagnostics from lexer, parser, and type checker
-    fn get_diagnostics(&self, uri: &Url, content: &str) -> Vec<Diagnostic> {
-        let mut diagnostics = Vec::new();
+    /// Diagnostics plus the expression types recorded while type checking
+    fn get_diagnostics(&self, uri: &Url, content: &str) -> Analysis {
+        let mut analysis = Analysis::default();
         let filename = uri.path();
 
         // Try to tokenize
         let tokens = match lexer::tokenize(content) {
             Ok(tokens) => tokens,
             Err(e) => {
-                diagnostics.push(self.error_to_diagnostic(&e, content));
-                return diagnostics;
+                analysis.diagnostics.push(self.error_to_diagnostic(&e, content));
+                return analysis;
             }
         };
 
-        // Try to parse
-        let ast = match parser::parse(filename, content, tokens) {
-            Ok(ast) => ast,
-            Err(e) => {
-                diagnostics.push(self.error_to_diagnostic(&e, content));
-                return diagnostics;
-            }
+        // Parse, keeping the items around any syntax errors
+        let (ast, parse_errors) = parser::parse_with_recovery(filename, content, tokens);
+        for e in &parse_errors {
+            analysis.diagnostics.push(self.error_to_diagnostic(e, content));
+        }
+
+        // Type check like `bmb lint`, resolving imports next to the file on
+        // disk, and keep the types inferred before any error for hover.
+        // Errors and warnings are only reported for a file that parsed
+        // cleanly, since references to skipped items would show up as bogus
+        // errors.
+        let mut checker = TypeChecker::with_span_recording().recover_errors();
+        let mut import_warnings = Vec::new();
+        let result = match uri.to_file_path().ok().map(|path| resolver::Resolver::for_file(&path)) {
+            Some(Ok(mut resolver)) => match resolver.resolve_uses(&ast) {
+                Ok(mut imports) => {
+                    checker.register_imports(&resolver, &imports);
+                    let result = checker.check_program_with_imports(&ast, &mut imports);
+                    import_warnings = imports.warnings();
+                    result
+                }
+                Err(e) => Err(e),
+            },
+            _ => checker.check_program(&ast),
         };
-
-        // Type check
-        let mut checker = TypeChecker::new();
-        if let Err(e) = checker.check_program(&ast) {
-            diagnostics.push(self.error_to_diagnostic(&e, content));
+        if parse_errors.is_empty() {
+            if let Err(e) = result {
+                analysis.diagnostics.push(self.error_to_diagnostic(&e, content));
+            }
+            for e in checker.take_errors() {
+                analysis.diagnostics.push(self.error_to_diagnostic(&e, content));
+            }
+            analysis.warnings = checker.take_warnings();
+            analysis.warnings.extend(import_warnings);
+            for w in &analysis.warnings {
+                analysis.diagnostics.push(self.warning_to_diagnostic(w, content));
+            }
         }
 
-        diagnostics
+        analysis.expr_types = checker.span_types().to_vec();
+        analysis
     }
 
     /// Try to parse content, returning AST if successful
@@ -360,6 +306,12 @@ impl Backend {
         parser::parse("<lsp>", content, tokens).ok()
     }
 
+    /// Parse content, keeping the items around any syntax errors
+    fn try_parse_partial(&self, content: &str) -> Option<(Program, Vec<CompileError>)> {
+        let tokens = lexer::tokenize(content).ok()?;
+        Some(parser::parse_with_recovery("<lsp>", content, tokens))
+    }
+
     /// Convert CompileError to LSP Diagnostic
     fn error_to_diagnostic(&self, error: &CompileError, content: &str) -> Diagnostic {
         let (range, severity) = if let Some(span) = error.span() {
@@ -384,54 +336,35 @@ impl Backend {
         }
     }
 
-    /// Convert Span (byte offset) to LSP Range (line/character)
-    fn span_to_range(&self, span: Span, content: &str) -> Range {
-        let start = self.offset_to_position(span.start, content);
-        let end = self.offset_to_position(span.end, content);
-        Range { start, end }
-    }
-
-    /// Convert byte offset to LSP Position
-    fn offset_to_position(&self, offset: usize, content: &str) -> Position {
-        let mut line = 0u32;
-        let mut col = 0u32;
-
-        for (i, c) in content.char_indices() {
-            if i >= offset {
-                break;
-            }
-            if c == '\n' {
-                line += 1;
-                col = 0;
-            } else {
-                col += 1;
-            }
+    /// Convert CompileWarning to LSP Diagnostic, with the warning code
+    fn warning_to_diagnostic(&self, warning: &CompileWarning, content: &str) -> Diagnostic {
+        Diagnostic {
+            range: warning.span().map(|span| self.span_to_range(span, content)).unwrap_or_default(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(warning.code().to_string())),
+            source: Some("bmb-lint".to_string()),
+            message: warning.message(),
+            ..Default::default()
         }
+    }
 
-        Position::new(line, col)
+    /// Convert Span (byte offset) to LSP Range (line/character)
+    fn span_to_range(&self, span: Span, content: &str) -> Range {
+        let (start, end) = span.resolve(&LineIndex::new(content));
+        Range { start: Self::to_position(start), end: Self::to_position(end) }
     }
 
     /// Convert LSP Position to byte offset
     fn position_to_offset(&self, position: Position, content: &str) -> usize {
-        let mut current_line = 0u32;
-        let mut current_col = 0u32;
-
-        for (i, c) in content.char_indices() {
-            if current_line == position.line && current_col == position.character {
-                return i;
-            }
-            if c == '\n' {
-                if current_line == position.line {
-                    return i;
-                }
-                current_line += 1;
-                current_col = 0;
-            } else {
-                current_col += 1;
-            }
-        }
+        LineIndex::new(content).offset(LineCol {
+            line: position.line as usize + 1,
+            col: position.character as usize + 1,
+        })
+    }
 
-        content.len()
+    /// LSP positions are 0-based
+    fn to_position(LineCol { line, col }: LineCol) -> Position {
+        Position::new((line - 1) as u32, (col - 1) as u32)
     }
 
     /// Get word at position for hover
@@ -463,27 +396,72 @@ impl Backend {
     fn is_ident_char(c: char) -> bool {
         c.is_alphanumeric() || c == '_'
     }
+
+    /// Edit replacing the document with its formatted text, as `bmb fmt`
+    /// (`--range` when `lines` is given) would write it. None when the
+    /// document does not parse, and no edits when it is already formatted.
+    fn format_edit(&self, uri: &Url, lines: Option<std::ops::RangeInclusive<usize>>) -> Option<Vec<TextEdit>> {
+        let docs = self.documents.read().unwrap();
+        let content = &docs.get(uri)?.content;
+        let formatted = crate::fmt::format_source(uri.path(), content, crate::fmt::DEFAULT_WIDTH, lines).ok()?;
+        if formatted == *content {
+            return Some(vec![]);
+        }
+
+        let last_line = content.matches('\n').count() as u32;
+        let last_col = content.rsplit('\n').next().map_or(0, |l| l.encode_utf16().count() as u32);
+        Some(vec![TextEdit {
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(last_line, last_col),
+            },
+            new_text: formatted,
+        }])
+    }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)] // root_uri is still what most clients send
+        let root = params
+            .workspace_folders
+            .as_ref()
+            .and_then(|folders| folders.first())
+            .map(|folder| folder.uri.clone())
+            .or(params.root_uri)
+            .and_then(|uri| uri.to_file_path().ok());
+        *self.root.write().unwrap() = root;
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
-                )),
+                text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::FULL),
+                    save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                    ..Default::default()
+                })),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 completion_provider: Some(CompletionOptions {
-                    trigger_characters: Some(vec![".".to_string()]),
+                    trigger_characters: Some(vec![".".to_string(), ":".to_string()]),
                     ..Default::default()
                 }),
                 // v0.9.0: Formatting support
                 document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
                 // v0.9.0: Go to definition
                 definition_provider: Some(OneOf::Left(true)),
                 // v0.9.0: Find references
                 references_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+                    SemanticTokensOptions {
+                        legend: semantic_tokens::legend(),
+                        full: Some(SemanticTokensFullOptions::Bool(true)),
+                        ..Default::default()
+                    },
+                )),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -521,6 +499,18 @@ impl LanguageServer for Backend {
         }
     }
 
+    /// Re-check on save: imported modules may have changed on disk
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let saved = {
+            let docs = self.documents.read().unwrap();
+            docs.get(&uri).map(|doc| (doc.content.clone(), doc.version))
+        };
+        if let Some((content, version)) = saved {
+            self.analyze_document(&uri, &params.text.unwrap_or(content), version).await;
+        }
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let mut docs = self.documents.write().unwrap();
         docs.remove(&params.text_document.uri);
@@ -553,16 +543,27 @@ impl LanguageServer for Backend {
         }
 
         // Check if it's a built-in function
-        for (name, sig) in BMB_BUILTINS {
-            if *name == word {
-                return Ok(Some(Hover {
-                    contents: HoverContents::Markup(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: format!("**Built-in**: `{}`", sig),
-                    }),
-                    range: None,
-                }));
-            }
+        if let Some((name, params, ret)) = TypeChecker::builtin_signatures().into_iter().find(|(name, ..)| *name == word) {
+            let params: Vec<String> = params.iter().map(format_type).collect();
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("**Built-in**: `{}({}) -> {}`", name, params.join(", "), format_type(&ret)),
+                }),
+                range: None,
+            }));
+        }
+
+        // Check inferred types of the identifier under the cursor
+        let offset = self.position_to_offset(position, &doc.content);
+        if let Some(ty) = expr_type_at(&doc.expr_types, &doc.content, offset, &word) {
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```bmb\n{}: {}\n```", word, ty),
+                }),
+                range: None,
+            }));
         }
 
         // Check AST for user-defined symbols
@@ -570,14 +571,7 @@ impl LanguageServer for Backend {
             for item in &ast.items {
                 match item {
                     crate::ast::Item::FnDef(f) if f.name.node == word => {
-                        let params: Vec<String> = f.params.iter()
-                            .map(|p| format!("{}: {:?}", p.name.node, p.ty.node))
-                            .collect();
-                        let sig = format!("fn {}({}) -> {:?}",
-                            f.name.node,
-                            params.join(", "),
-                            f.ret_ty.node
-                        );
+                        let sig = fn_signature(f, &doc.content);
                         return Ok(Some(Hover {
                             contents: HoverContents::Markup(MarkupContent {
                                 kind: MarkupKind::Markdown,
@@ -622,109 +616,38 @@ impl LanguageServer for Backend {
 
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
         let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
 
-        let mut items = Vec::new();
-
-        // Add keywords
-        for keyword in BMB_KEYWORDS {
-            items.push(CompletionItem {
-                label: keyword.to_string(),
-                kind: Some(CompletionItemKind::KEYWORD),
-                detail: Some("keyword".to_string()),
-                ..Default::default()
-            });
-        }
-
-        // Add built-in functions
-        for (name, sig) in BMB_BUILTINS {
-            items.push(CompletionItem {
-                label: name.to_string(),
-                kind: Some(CompletionItemKind::FUNCTION),
-                detail: Some(sig.to_string()),
-                insert_text: Some(format!("{}($0)", name)),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-        }
-
-        // Add user-defined symbols from AST
         let docs = self.documents.read().unwrap();
-        if let Some(doc) = docs.get(uri)
-            && let Some(ast) = &doc.ast
-        {
-            for item in &ast.items {
-                match item {
-                    crate::ast::Item::FnDef(f) => {
-                        let params: Vec<String> = f.params.iter()
-                            .enumerate()
-                            .map(|(i, p)| format!("${{{}:{}}}", i + 1, p.name.node))
-                            .collect();
-                        items.push(CompletionItem {
-                            label: f.name.node.clone(),
-                            kind: Some(CompletionItemKind::FUNCTION),
-                            detail: Some(format!("fn -> {:?}", f.ret_ty.node)),
-                            insert_text: Some(format!("{}({})", f.name.node, params.join(", "))),
-                            insert_text_format: Some(InsertTextFormat::SNIPPET),
-                            ..Default::default()
-                        });
-                    }
-                    crate::ast::Item::StructDef(s) => {
-                        items.push(CompletionItem {
-                            label: s.name.node.clone(),
-                            kind: Some(CompletionItemKind::STRUCT),
-                            detail: Some("struct".to_string()),
-                            ..Default::default()
-                        });
-                    }
-                    crate::ast::Item::EnumDef(e) => {
-                        items.push(CompletionItem {
-                            label: e.name.node.clone(),
-                            kind: Some(CompletionItemKind::ENUM),
-                            detail: Some("enum".to_string()),
-                            ..Default::default()
-                        });
-                    }
-                    _ => {}
-                }
-            }
-        }
+        let Some(doc) = docs.get(uri) else { return Ok(None) };
+        // While the document has syntax errors, the last complete version
+        // still knows the locals of the function being edited
+        let (program, expr_types) = match (&doc.ast, &doc.last_parsed) {
+            (Some(ast), _) if doc.complete => (Some(ast), doc.expr_types.as_slice()),
+            (_, Some((ast, expr_types))) => (Some(ast), expr_types.as_slice()),
+            (ast, None) => (ast.as_ref(), doc.expr_types.as_slice()),
+        };
+        let path = uri.to_file_path().ok();
+        let source = CompletionSource {
+            content: &doc.content,
+            program,
+            expr_types,
+            base_dir: path.as_deref().and_then(|p| p.parent()),
+        };
+        let offset = self.position_to_offset(position, &doc.content);
 
-        Ok(Some(CompletionResponse::Array(items)))
+        Ok(Some(CompletionResponse::Array(completions(&source, offset))))
     }
 
     /// v0.9.0: Format document
     async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
-        let uri = &params.text_document.uri;
-
-        let docs = self.documents.read().unwrap();
-        let doc = match docs.get(uri) {
-            Some(doc) => doc,
-            None => return Ok(None),
-        };
-
-        // Only format if we have a valid AST
-        let ast = match &doc.ast {
-            Some(ast) => ast,
-            None => return Ok(None),
-        };
-
-        // Format the AST
-        let formatted = format_program(ast);
-
-        // Create a text edit that replaces the entire document
-        let lines: Vec<&str> = doc.content.lines().collect();
-        let last_line = lines.len().saturating_sub(1) as u32;
-        let last_col = lines.last().map(|l| l.len() as u32).unwrap_or(0);
-
-        let edit = TextEdit {
-            range: Range {
-                start: Position::new(0, 0),
-                end: Position::new(last_line, last_col),
-            },
-            new_text: formatted,
-        };
+        Ok(self.format_edit(&params.text_document.uri, None))
+    }
 
-        Ok(Some(vec![edit]))
+    /// Format the items lying entirely within the selection
+    async fn range_formatting(&self, params: DocumentRangeFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let lines = params.range.start.line as usize + 1..=params.range.end.line as usize + 1;
+        Ok(self.format_edit(&params.text_document.uri, Some(lines)))
     }
 
     /// v0.9.0: Go to definition
@@ -762,9 +685,12 @@ impl LanguageServer for Backend {
     }
 
     /// v0.9.0: Find all references
+    /// Locals resolve within their scope; globals are also searched in
+    /// workspace files that may mention them
     async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
         let uri = &params.text_document_position.text_document.uri;
         let position = params.text_document_position.position;
+        let include_declaration = params.context.include_declaration;
 
         let docs = self.documents.read().unwrap();
         let doc = match docs.get(uri) {
@@ -772,33 +698,42 @@ impl LanguageServer for Backend {
             None => return Ok(None),
         };
 
-        // Get the word at cursor position
-        let word = match self.get_word_at_position(&doc.content, position) {
-            Some(w) => w,
+        let offset = self.position_to_offset(position, &doc.content);
+        let target = match occurrence_at(&doc.occurrences, offset) {
+            Some(occ) => occ.clone(),
             None => return Ok(None),
         };
 
         let mut locations = Vec::new();
-
-        // Include definition if include_declaration is true
-        if params.context.include_declaration {
-            for def in &doc.definitions {
-                if def.name == word {
+        let mut push_matches = |file_uri: &Url, content: &str, occurrences: &[Occurrence]| {
+            for occ in occurrences {
+                if occ.same_symbol(&target) && (include_declaration || !occ.is_definition) {
                     locations.push(Location {
-                        uri: uri.clone(),
-                        range: self.span_to_range(def.span, &doc.content),
+                        uri: file_uri.clone(),
+                        range: self.span_to_range(occ.span, content),
                     });
                 }
             }
-        }
+        };
+        push_matches(uri, &doc.content, &doc.occurrences);
 
-        // Add all references
-        for reference in &doc.references {
-            if reference.name == word {
-                locations.push(Location {
-                    uri: uri.clone(),
-                    range: self.span_to_range(reference.span, &doc.content),
-                });
+        // Global names: other open documents first, then files on disk
+        let root = self.root.read().unwrap().clone();
+        if target.local.is_none() {
+            for (other_uri, other) in docs.iter().filter(|(u, _)| *u != uri) {
+                push_matches(other_uri, &other.content, &other.occurrences);
+            }
+            if let Some(root) = root {
+                for path in references::candidate_files(&root, &target.name) {
+                    let Ok(file_uri) = Url::from_file_path(&path) else { continue };
+                    if docs.contains_key(&file_uri) {
+                        continue;
+                    }
+                    let Ok(content) = std::fs::read_to_string(&path) else { continue };
+                    if let Some((ast, _)) = self.try_parse_partial(&content) {
+                        push_matches(&file_uri, &content, &collect_occurrences(&ast, &content));
+                    }
+                }
             }
         }
 
@@ -808,133 +743,142 @@ impl LanguageServer for Backend {
             Ok(Some(locations))
         }
     }
-}
 
-/// Format a BMB program to source code (v0.9.0)
-fn format_program(program: &Program) -> String {
-    use crate::ast::Visibility;
-
-    let mut output = String::new();
-
-    for (i, item) in program.items.iter().enumerate() {
-        if i > 0 {
-            output.push_str("\n\n");
-        }
+    /// Rename a symbol and all its uses.
+    /// Globals are renamed in open documents and workspace files that may
+    /// mention them, `use` statements included
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
 
-        match item {
-            Item::FnDef(fn_def) => {
-                output.push_str(&format_fn_def(fn_def));
-            }
-            Item::StructDef(s) => {
-                if s.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("struct {} {{\n", s.name.node));
-                for field in &s.fields {
-                    output.push_str(&format!("    {}: {},\n", field.name.node, format_type(&field.ty.node)));
-                }
-                output.push('}');
-            }
-            Item::EnumDef(e) => {
-                if e.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("enum {} {{\n", e.name.node));
-                for variant in &e.variants {
-                    output.push_str(&format!("    {},\n", variant.name.node));
-                }
-                output.push('}');
-            }
-            Item::Use(u) => {
-                let path_str: Vec<_> = u.path.iter().map(|s| s.node.as_str()).collect();
-                output.push_str(&format!("use {};", path_str.join("::")));
-            }
-            // v0.13.0: Format extern function declarations
-            Item::ExternFn(e) => {
-                if e.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("extern fn {}(", e.name.node));
-                let params: Vec<_> = e.params.iter()
-                    .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
-                    .collect();
-                output.push_str(&params.join(", "));
-                output.push_str(&format!(") -> {};", format_type(&e.ret_ty.node)));
-            }
-            // v0.20.1: Format trait definitions
-            Item::TraitDef(t) => {
-                if t.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("trait {} {{\n", t.name.node));
-                for method in &t.methods {
-                    let params: Vec<_> = method.params.iter()
-                        .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
-                        .collect();
-                    output.push_str(&format!("    fn {}({}) -> {};\n",
-                        method.name.node, params.join(", "), format_type(&method.ret_ty.node)));
+        let docs = self.documents.read().unwrap();
+        let Some(doc) = docs.get(uri) else { return Ok(None) };
+        let Some(ast) = doc.ast.as_ref().filter(|_| doc.complete) else { return Ok(None) };
+        let offset = self.position_to_offset(position, &doc.content);
+        let Some(target) = occurrence_at(&doc.occurrences, offset) else { return Ok(None) };
+
+        // The document being edited comes first, then every file the name may reach
+        let mut uris = vec![uri.clone()];
+        let mut files = vec![RenameFile { content: &doc.content, program: ast }];
+        let mut loaded: Vec<(Url, String, Program)> = Vec::new();
+        if target.local.is_none() {
+            for (other_uri, other) in docs.iter().filter(|(u, _)| *u != uri) {
+                if let Some(other_ast) = other.ast.as_ref().filter(|_| other.complete) {
+                    uris.push(other_uri.clone());
+                    files.push(RenameFile { content: &other.content, program: other_ast });
                 }
-                output.push('}');
             }
-            // v0.20.1: Format impl blocks
-            Item::ImplBlock(i) => {
-                output.push_str(&format!("impl {} for {} {{\n", i.trait_name.node, format_type(&i.target_type.node)));
-                for method in &i.methods {
-                    output.push_str(&format!("    {}\n", format_fn_def(method).trim()));
+            let root = self.root.read().unwrap().clone();
+            for path in root.iter().flat_map(|root| references::candidate_files(root, &target.name)) {
+                let Ok(file_uri) = Url::from_file_path(&path) else { continue };
+                if docs.contains_key(&file_uri) {
+                    continue;
                 }
-                output.push('}');
-            }
-            // v0.50.6: Format type aliases
-            Item::TypeAlias(t) => {
-                if t.visibility == Visibility::Public {
-                    output.push_str("pub ");
+                let Ok(content) = std::fs::read_to_string(&path) else { continue };
+                if let Some(program) = self.try_parse(&content) {
+                    loaded.push((file_uri, content, program));
                 }
-                output.push_str(&format!("type {} = {};", t.name.node, format_type(&t.target.node)));
             }
         }
-    }
-
-    output.push('\n');
-    output
-}
-
-fn format_fn_def(fn_def: &crate::ast::FnDef) -> String {
-    use crate::ast::Visibility;
-
-    let mut s = String::new();
-
-    if fn_def.visibility == Visibility::Public {
-        s.push_str("pub ");
-    }
+        for (file_uri, content, program) in &loaded {
+            uris.push(file_uri.clone());
+            files.push(RenameFile { content, program });
+        }
 
-    // Function signature
-    s.push_str(&format!("fn {}(", fn_def.name.node));
+        let edits = rename_symbol(&files, 0, offset, &params.new_name)
+            .map_err(|e| tower_lsp::jsonrpc::Error::invalid_params(e.to_string()))?;
 
-    for (i, param) in fn_def.params.iter().enumerate() {
-        if i > 0 {
-            s.push_str(", ");
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (i, span) in edits {
+            changes.entry(uris[i].clone()).or_default().push(TextEdit {
+                range: self.span_to_range(span, files[i].content),
+                new_text: params.new_name.clone(),
+            });
         }
-        s.push_str(&format!("{}: {}", param.name.node, format_type(&param.ty.node)));
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
     }
 
-    s.push_str(&format!(") -> {}", format_type(&fn_def.ret_ty.node)));
-
-    // Contracts
-    if let Some(pre) = &fn_def.pre {
-        s.push_str(&format!("\n  pre {}", format_expr(&pre.node)));
+    /// Quick fixes for the lint warnings in the requested range
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        let docs = self.documents.read().unwrap();
+        let Some(doc) = docs.get(uri) else { return Ok(None) };
+        let Some(ast) = doc.ast.as_ref().filter(|_| doc.complete) else { return Ok(None) };
+        let start = self.position_to_offset(params.range.start, &doc.content);
+        let end = self.position_to_offset(params.range.end, &doc.content);
+
+        let mut actions = Vec::new();
+        for warning in &doc.warnings {
+            let Some(span) = warning.span() else { continue };
+            if span.end < start || end < span.start {
+                continue;
+            }
+            let Some(fix) = quick_fix(warning, ast, &doc.content) else { continue };
+            let edit = TextEdit {
+                range: self.span_to_range(fix.span, &doc.content),
+                new_text: fix.new_text,
+            };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![self.warning_to_diagnostic(warning, &doc.content)]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }));
+        }
+        Ok(Some(actions))
     }
 
-    if let Some(post) = &fn_def.post {
-        s.push_str(&format!("\n  post {}", format_expr(&post.node)));
+    /// Classify every token of the document for highlighting.
+    /// Items after a syntax error are still covered by the partial AST
+    async fn semantic_tokens_full(&self, params: SemanticTokensParams) -> Result<Option<SemanticTokensResult>> {
+        let docs = self.documents.read().unwrap();
+        let Some(doc) = docs.get(&params.text_document.uri) else { return Ok(None) };
+        let data = semantic_tokens::semantic_tokens(doc.ast.as_ref(), &doc.content);
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens { result_id: None, data })))
     }
+}
 
-    // Body
-    s.push_str(&format!("\n= {};", format_expr(&fn_def.body.node)));
+/// Type of the innermost expression at `offset` whose source text is `word`
+fn expr_type_at<'a>(
+    expr_types: &'a [(Span, Type)],
+    content: &str,
+    offset: usize,
+    word: &str,
+) -> Option<&'a Type> {
+    expr_types
+        .iter()
+        .filter(|(span, _)| span.start <= offset && offset <= span.end)
+        .filter(|(span, _)| content.get(span.start..span.end) == Some(word))
+        .min_by_key(|(span, _)| span.end - span.start)
+        .map(|(_, ty)| ty)
+}
 
-    s
+/// Function signature with its contracts, as written in the source
+/// (everything from `fn` up to the body's `=`)
+fn fn_signature(fn_def: &FnDef, content: &str) -> String {
+    let header = content
+        .get(fn_def.name.span.start..fn_def.body.span.start)
+        .map(|s| s.trim_end().trim_end_matches('=').trim_end());
+    match header {
+        Some(header) => format!("fn {}", header),
+        None => {
+            let params: Vec<String> = fn_def.params.iter()
+                .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
+                .collect();
+            format!("fn {}({}) -> {}", fn_def.name.node, params.join(", "), format_type(&fn_def.ret_ty.node))
+        }
+    }
 }
 
-fn format_type(ty: &crate::ast::Type) -> String {
+fn format_type(ty: &Type) -> String {
     use crate::ast::Type;
 
     match ty {
@@ -964,6 +908,7 @@ fn format_type(ty: &crate::ast::Type) -> String {
         Type::Struct { name, .. } => name.clone(),
         Type::Enum { name, .. } => name.clone(),
         Type::Array(elem, size) => format!("[{}; {}]", format_type(elem), size),
+        Type::ArrayConst(elem, name) => format!("[{}; {}]", format_type(elem), name),
         Type::Ref(inner) => format!("&{}", format_type(inner)),
         Type::RefMut(inner) => format!("&mut {}", format_type(inner)),
         Type::Refined { base, constraints } => {
@@ -993,6 +938,11 @@ fn format_type(ty: &crate::ast::Type) -> String {
     }
 }
 
+/// `'outer: ` in front of a labeled loop
+fn label_prefix(label: &Option<String>) -> String {
+    label.as_ref().map(|l| format!("'{l}: ")).unwrap_or_default()
+}
+
 fn format_expr(expr: &Expr) -> String {
     use crate::ast::{BinOp, UnOp};
 
@@ -1080,6 +1030,12 @@ fn format_expr(expr: &Expr) -> String {
             )
         }
 
+        Expr::LetStmt { name, mutable, ty, value } => {
+            let mut_str = if *mutable { "mut " } else { "" };
+            let ty_str = ty.as_ref().map(|t| format!(": {}", format_type(&t.node))).unwrap_or_default();
+            format!("let {}{}{} = {}", mut_str, name, ty_str, format_expr(&value.node))
+        }
+
         Expr::Call { func, args } => {
             let args_str: Vec<_> = args.iter().map(|a| format_expr(&a.node)).collect();
             format!("{}({})", func, args_str.join(", "))
@@ -1109,10 +1065,13 @@ fn format_expr(expr: &Expr) -> String {
             }
         }
 
-        Expr::StructInit { name, fields } => {
-            let fields_str: Vec<_> = fields.iter()
+        Expr::StructInit { name, fields, base } => {
+            let mut fields_str: Vec<_> = fields.iter()
                 .map(|(n, v)| format!("{}: {}", n.node, format_expr(&v.node)))
                 .collect();
+            if let Some(base) = base {
+                fields_str.push(format!("..{}", format_expr(&base.node)));
+            }
             format!("{} {{ {} }}", name, fields_str.join(", "))
         }
 
@@ -1137,7 +1096,8 @@ fn format_expr(expr: &Expr) -> String {
                 "{}".to_string()
             } else {
                 let stmts_str: Vec<_> = stmts.iter().map(|s| format_expr(&s.node)).collect();
-                format!("{{ {} }}", stmts_str.join("; "))
+                let trailing = if matches!(stmts.last().map(|s| &s.node), Some(Expr::LetStmt { .. })) { ";" } else { "" };
+                format!("{{ {}{} }}", stmts_str.join("; "), trailing)
             }
         }
 
@@ -1145,26 +1105,37 @@ fn format_expr(expr: &Expr) -> String {
             format!("{} = {}", name, format_expr(&value.node))
         }
 
+        Expr::IndexAssign { array, index, value } => {
+            format!("{}[{}] = {}", format_expr(&array.node), format_expr(&index.node), format_expr(&value.node))
+        }
+
+        Expr::FieldAssign { object, field, value } => {
+            format!("{}.{} = {}", format_expr(&object.node), field.node, format_expr(&value.node))
+        }
+
         // v0.37: Include invariant in format if present
-        Expr::While { cond, invariant, body } => {
+        Expr::While { label, cond, invariant, body } => {
             match invariant {
                 Some(inv) => format!(
-                    "while {} invariant {} {{ {} }}",
+                    "{}while {} invariant {} {{ {} }}",
+                    label_prefix(label),
                     format_expr(&cond.node),
                     format_expr(&inv.node),
                     format_expr(&body.node)
                 ),
                 None => format!(
-                    "while {} {{ {} }}",
+                    "{}while {} {{ {} }}",
+                    label_prefix(label),
                     format_expr(&cond.node),
                     format_expr(&body.node)
                 ),
             }
         }
 
-        Expr::For { var, iter, body } => {
+        Expr::For { label, var, iter, body } => {
             format!(
-                "for {} in {} {{ {} }}",
+                "{}for {} in {} {{ {} }}",
+                label_prefix(label),
                 var,
                 format_expr(&iter.node),
                 format_expr(&body.node)
@@ -1233,12 +1204,17 @@ fn format_expr(expr: &Expr) -> String {
         }
 
         // v0.36: Additional control flow
-        Expr::Loop { body } => format!("loop {{ {} }}", format_expr(&body.node)),
-        Expr::Break { value } => match value {
-            Some(v) => format!("break {}", format_expr(&v.node)),
-            None => "break".to_string(),
-        },
-        Expr::Continue => "continue".to_string(),
+        Expr::Loop { label, body } => format!("{}loop {{ {} }}", label_prefix(label), format_expr(&body.node)),
+        Expr::Break { label, value } => {
+            let target = label.as_ref().map(|l| format!(" '{l}")).unwrap_or_default();
+            match value {
+                Some(v) => format!("break{} {}", target, format_expr(&v.node)),
+                None => format!("break{}", target),
+            }
+        }
+        Expr::Continue { label } => {
+            format!("continue{}", label.as_ref().map(|l| format!(" '{l}")).unwrap_or_default())
+        }
         Expr::Return { value } => match value {
             Some(v) => format!("return {}", format_expr(&v.node)),
             None => "return".to_string(),
@@ -1342,3 +1318,38 @@ pub async fn run_server() {
     let (service, socket) = LspService::new(Backend::new);
     Server::new(stdin, stdout, socket).serve(service).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_source(source: &str) -> Program {
+        let tokens = lexer::tokenize(source).unwrap();
+        parser::parse("test.bmb", source, tokens).unwrap()
+    }
+
+    #[test]
+    fn test_fn_signature_keeps_source_contracts() {
+        let source = "fn safe_div(a: i64, b: i64) -> i64\n  pre b != 0\n  post ret * b <= a\n= a / b;\n";
+        let ast = parse_source(source);
+        let Item::FnDef(f) = &ast.items[0] else { panic!("expected fn") };
+        assert_eq!(
+            fn_signature(f, source),
+            "fn safe_div(a: i64, b: i64) -> i64\n  pre b != 0\n  post ret * b <= a"
+        );
+    }
+
+    #[test]
+    fn test_expr_type_at_matches_identifier() {
+        let source = "fn twice(x: i64) -> i64 = x + x;\n";
+        let ast = parse_source(source);
+        let mut checker = TypeChecker::with_span_recording();
+        checker.check_program(&ast).unwrap();
+
+        let x = source.rfind('x').unwrap();
+        let ty = expr_type_at(checker.span_types(), source, x, "x");
+        assert_eq!(ty, Some(&Type::I64));
+        // The enclosing `x + x` is not reported for a different word
+        assert_eq!(expr_type_at(checker.span_types(), source, x, "twice"), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Quick fixes for lint warnings
+//!
+//! Mechanical fixes offered as code actions: prefix an unused binding with
+//! `_`, delete an unused `use` statement, and add a stub postcondition to a
+//! function that has none. Each fix is a single text replacement.
+
+use crate::ast::{FnDef, Item, Program, Span};
+use crate::error::CompileWarning;
+use crate::lexer::{tokenize, Token};
+
+/// A fix for one warning: replace `span` with `new_text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickFix {
+    pub title: String,
+    pub span: Span,
+    pub new_text: String,
+}
+
+/// The fix for `warning`, if it has a mechanical one
+pub fn quick_fix(warning: &CompileWarning, program: &Program, content: &str) -> Option<QuickFix> {
+    match warning {
+        CompileWarning::UnusedBinding { name, span } => {
+            let at = binding_name(content, *span, name)?;
+            Some(QuickFix {
+                title: format!("Prefix `{}` with `_`", name),
+                span: Span::new(at, at),
+                new_text: "_".to_string(),
+            })
+        }
+        CompileWarning::UnusedImport { name, span } => {
+            let use_span = program.items.iter().find_map(|item| match item {
+                Item::Use(u) if u.span.start <= span.start && span.end <= u.span.end => Some(u.span),
+                _ => None,
+            })?;
+            Some(QuickFix {
+                title: format!("Remove unused import `{}`", name),
+                span: whole_lines(content, use_span),
+                new_text: String::new(),
+            })
+        }
+        CompileWarning::MissingPostcondition { name, span } => {
+            let f = function_named_at(program, *span)?;
+            // A `where { }` block takes the place of `pre`/`post`
+            if !f.contracts.is_empty() {
+                return None;
+            }
+            let at = f.pre.as_ref().map_or(f.ret_ty.span.end, |pre| pre.span.end);
+            Some(QuickFix {
+                title: format!("Add a postcondition to `{}`", name),
+                span: Span::new(at, at),
+                new_text: " post ret == ret".to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Offset of the identifier `name` bound within `span`: the first token
+/// spelling it, after any `let`/`mut`
+fn binding_name(content: &str, span: Span, name: &str) -> Option<usize> {
+    let text = content.get(span.start..span.end)?;
+    let tokens = tokenize(text).ok()?;
+    tokens.iter().find_map(|(token, token_span)| match token {
+        Token::Ident(ident) if ident == name => Some(span.start + token_span.start),
+        _ => None,
+    })
+}
+
+/// Function or method whose name is at `span`
+fn function_named_at(program: &Program, span: Span) -> Option<&FnDef> {
+    program.items.iter().find_map(|item| match item {
+        Item::FnDef(f) if f.name.span == span => Some(f),
+        Item::ImplBlock(i) => i.methods.iter().find(|m| m.name.span == span),
+        _ => None,
+    })
+}
+
+/// `span` widened to the lines it covers, including the final newline, when
+/// nothing else shares those lines
+fn whole_lines(content: &str, span: Span) -> Span {
+    let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[span.end..].find('\n').map_or(content.len(), |i| span.end + i + 1);
+    let alone = content[line_start..span.start].trim().is_empty() && content[span.end..line_end].trim().is_empty();
+    if alone {
+        Span::new(line_start, line_end)
+    } else {
+        span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TypeChecker;
+
+    fn parse(source: &str) -> Program {
+        let tokens = tokenize(source).unwrap();
+        crate::parser::parse("test.bmb", source, tokens).unwrap()
+    }
+
+    /// `source` with the fix for its first warning of `kind` applied
+    fn fixed(source: &str, kind: &str) -> Option<String> {
+        let program = parse(source);
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        let warning = checker.warnings().iter().find(|w| w.kind() == kind).expect("warning");
+        let fix = quick_fix(warning, &program, source)?;
+        let mut out = source.to_string();
+        out.replace_range(fix.span.start..fix.span.end, &fix.new_text);
+        Some(out)
+    }
+
+    #[test]
+    fn test_prefix_unused_binding() {
+        let source = "fn f(x: i64) -> i64 post ret == 1 = 1;\n";
+        assert_eq!(fixed(source, "unused_binding").unwrap(), "fn f(_x: i64) -> i64 post ret == 1 = 1;\n");
+        let source = "fn f(n: i64) -> i64 post ret == n = { let s = \"s\"; n };\n";
+        assert_eq!(
+            fixed(source, "unused_binding").unwrap(),
+            "fn f(n: i64) -> i64 post ret == n = { let _s = \"s\"; n };\n"
+        );
+    }
+
+    #[test]
+    fn test_add_stub_postcondition() {
+        let source = "fn f(x: i64) -> i64 = x;\n";
+        assert_eq!(
+            fixed(source, "missing_postcondition").unwrap(),
+            "fn f(x: i64) -> i64 post ret == ret = x;\n"
+        );
+        let source = "fn g(x: i64) -> i64\n  pre x > 0\n= x;\n";
+        assert_eq!(
+            fixed(source, "missing_postcondition").unwrap(),
+            "fn g(x: i64) -> i64\n  pre x > 0 post ret == ret\n= x;\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_unused_use_line() {
+        let source = "use math::gcd;\nuse math::lcm;\n\nfn main() -> i64 = lcm(2, 3);\n";
+        let program = parse(source);
+        let gcd = source.find("gcd").unwrap();
+        let warning = CompileWarning::unused_import("gcd", Span::new(gcd, gcd + 3));
+        let fix = quick_fix(&warning, &program, source).unwrap();
+        assert_eq!(&source[fix.span.start..fix.span.end], "use math::gcd;\n");
+        assert_eq!(fix.new_text, "");
+    }
+}
@@ -0,0 +1,655 @@
+//! Symbol occurrences for find-references
+//!
+//! Walks a parsed program and records every place a name is defined or used,
+//! with the exact span of the identifier. Local bindings (parameters, `let`,
+//! `for`, pattern and closure variables) are resolved through lexical scopes
+//! and tagged with a per-file binding id, so a shadowed `x` is never confused
+//! with an outer one. Everything else is a global name matched across files.
+//!
+//! Most AST nodes only carry the span of the whole expression, so identifier
+//! spans are recovered by searching the source text inside that span.
+
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Expr, FnDef, Item, Pattern, Program, Span, Spanned, Type};
+use crate::index::ProjectIndex;
+
+/// A definition or use of a name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub name: String,
+    /// Span of the identifier itself
+    pub span: Span,
+    pub is_definition: bool,
+    /// Binding id for locals; None for global names (functions, types, ...)
+    pub local: Option<usize>,
+}
+
+/// How a local was bound, indexed by `Occurrence::local`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    /// Function, method or closure parameter
+    Parameter,
+    /// `let`, `for`, pattern or quantifier variable
+    Variable { mutable: bool },
+}
+
+impl Occurrence {
+    /// Whether `other` refers to the same symbol as this occurrence
+    pub fn same_symbol(&self, other: &Occurrence) -> bool {
+        self.name == other.name && self.local == other.local
+    }
+}
+
+/// Collect all occurrences in `program`, whose source is `content`
+pub fn collect_occurrences(program: &Program, content: &str) -> Vec<Occurrence> {
+    collect_bindings(program, content).0
+}
+
+/// All occurrences in `program`, with how each local binding id was bound
+pub fn collect_bindings(program: &Program, content: &str) -> (Vec<Occurrence>, Vec<Binding>) {
+    let mut collector = Collector {
+        content,
+        occurrences: Vec::new(),
+        scopes: Vec::new(),
+        bindings: Vec::new(),
+    };
+    for item in &program.items {
+        collector.item(item);
+    }
+    (collector.occurrences, collector.bindings)
+}
+
+/// The occurrence whose identifier contains `offset`
+pub fn occurrence_at(occurrences: &[Occurrence], offset: usize) -> Option<&Occurrence> {
+    occurrences
+        .iter()
+        .find(|o| o.span.start <= offset && offset <= o.span.end)
+}
+
+/// Source files that may reference the global `name`.
+///
+/// Uses the project index (`bmb index`) when it is newer than every source
+/// file: only files defining `name`, calling it, or mentioning it in a
+/// signature are returned. A missing or stale index falls back to every
+/// `.bmb` file under `root`.
+pub fn candidate_files(root: &Path, name: &str) -> Vec<PathBuf> {
+    let files = bmb_files(root);
+    match crate::index::read_index(root) {
+        Ok(index) if !index_is_stale(&index, &files) => files_from_index(&index, root, name),
+        _ => files,
+    }
+}
+
+fn files_from_index(index: &ProjectIndex, root: &Path, name: &str) -> Vec<PathBuf> {
+    let mentions = |text: &str| find_words(text, name).next().is_some();
+    let mut files: Vec<&str> = Vec::new();
+
+    files.extend(index.symbols.iter().filter(|s| s.name == name).map(|s| s.file.as_str()));
+    for f in &index.functions {
+        let calls = f.body_info.iter().flat_map(|b| b.calls.iter());
+        let contract_calls = f
+            .contracts
+            .iter()
+            .flat_map(|c| c.pre.iter().chain(c.post.iter()).flatten())
+            .flat_map(|e| e.calls.iter());
+        let in_signature = f.signature.params.iter().any(|p| mentions(&p.ty))
+            || mentions(&f.signature.return_type);
+        if in_signature || calls.chain(contract_calls).any(|c| c == name) {
+            files.push(&f.file);
+        }
+    }
+    for t in &index.types {
+        if t.fields.iter().any(|f| mentions(&f.ty)) {
+            files.push(&t.file);
+        }
+    }
+
+    files.sort_unstable();
+    files.dedup();
+    files
+        .into_iter()
+        .map(|f| {
+            let path = PathBuf::from(f);
+            if path.is_relative() { root.join(path) } else { path }
+        })
+        .collect()
+}
+
+/// An index is stale once any source file was modified after it was written
+fn index_is_stale(index: &ProjectIndex, files: &[PathBuf]) -> bool {
+    let Ok(indexed_at) = chrono::DateTime::parse_from_rfc3339(&index.manifest.indexed_at) else {
+        return true;
+    };
+    let indexed_at = std::time::SystemTime::from(indexed_at);
+    files.iter().any(|f| {
+        std::fs::metadata(f)
+            .and_then(|m| m.modified())
+            .map_or(true, |modified| modified > indexed_at)
+    })
+}
+
+/// All `.bmb` files under `root`, skipping hidden and build directories
+fn bmb_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if path.is_dir() {
+                if !hidden && entry.file_name() != "target" {
+                    dirs.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "bmb") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Byte offsets of whole-word occurrences of `word` in `text`
+fn find_words<'a>(text: &'a str, word: &'a str) -> impl Iterator<Item = usize> + 'a {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).map(|(i, _)| i).filter(move |&i| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + word.len()..].chars().next();
+        !word.is_empty() && !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+const IMMUTABLE: Binding = Binding::Variable { mutable: false };
+
+struct Collector<'a> {
+    content: &'a str,
+    occurrences: Vec<Occurrence>,
+    /// Innermost scope last: (name, binding id)
+    scopes: Vec<Vec<(String, usize)>>,
+    /// Kind of each binding id handed out so far
+    bindings: Vec<Binding>,
+}
+
+impl Collector<'_> {
+    /// Span of the first whole-word `name` inside `within`
+    fn find(&self, within: Span, name: &str) -> Option<Span> {
+        let text = self.content.get(within.start..within.end)?;
+        let i = find_words(text, name).next()?;
+        Some(Span::new(within.start + i, within.start + i + name.len()))
+    }
+
+    fn push(&mut self, name: &str, span: Span, is_definition: bool, local: Option<usize>) {
+        self.occurrences.push(Occurrence {
+            name: name.to_string(),
+            span,
+            is_definition,
+            local,
+        });
+    }
+
+    /// Record a global definition at an exact span
+    fn define_global(&mut self, name: &Spanned<String>) {
+        self.push(&name.node, name.span, true, None);
+    }
+
+    /// Bind a local in the innermost scope
+    fn define_local(&mut self, name: &str, span: Span, binding: Binding) {
+        let id = self.bindings.len();
+        self.bindings.push(binding);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), id));
+        }
+        self.push(name, span, true, Some(id));
+    }
+
+    /// Bind a local whose identifier is found by searching `within`
+    fn define_local_in(&mut self, name: &str, within: Span, binding: Binding) {
+        if let Some(span) = self.find(within, name) {
+            self.define_local(name, span, binding);
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, id)| *id)
+    }
+
+    /// Record a use of `name`, local if a binding is in scope
+    fn use_name(&mut self, name: &str, span: Span) {
+        let local = self.resolve(name);
+        self.push(name, span, false, local);
+    }
+
+    fn use_name_in(&mut self, name: &str, within: Span) {
+        if let Some(span) = self.find(within, name) {
+            self.use_name(name, span);
+        }
+    }
+
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(Vec::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::FnDef(f) => {
+                self.define_global(&f.name);
+                self.function(f);
+            }
+            Item::StructDef(s) => {
+                self.define_global(&s.name);
+                for field in &s.fields {
+                    self.ty(&field.ty);
+                }
+            }
+            Item::EnumDef(e) => {
+                self.define_global(&e.name);
+                for variant in &e.variants {
+                    for field in &variant.fields {
+                        self.ty(field);
+                    }
+                }
+            }
+            Item::ExternFn(e) => {
+                self.define_global(&e.name);
+                for param in &e.params {
+                    self.ty(&param.ty);
+                }
+                self.ty(&e.ret_ty);
+            }
+            Item::TraitDef(t) => {
+                self.define_global(&t.name);
+                for m in &t.methods {
+                    self.define_global(&m.name);
+                    self.scoped(|c| {
+                        for param in &m.params {
+                            c.ty(&param.ty);
+                            c.define_local(&param.name.node, param.name.span, Binding::Parameter);
+                        }
+                        c.ty(&m.ret_ty);
+                        if let Some(body) = &m.body {
+                            c.expr(body);
+                        }
+                    });
+                }
+            }
+            Item::ImplBlock(i) => {
+                if let Some(trait_name) = &i.trait_name {
+                    self.use_name(&trait_name.node, trait_name.span);
+                }
+                self.ty(&i.target_type);
+                for method in &i.methods {
+                    self.define_global(&method.name);
+                    self.function(method);
+                }
+            }
+            Item::TypeAlias(t) => {
+                self.define_global(&t.name);
+                self.ty(&t.target);
+            }
+            Item::ConstDef(c) => {
+                self.define_global(&c.name);
+                self.ty(&c.ty);
+                self.expr(&c.value);
+            }
+            Item::Use(u) => {
+                // A glob's last segment is the module, not an item
+                if let Some(last) = u.path.last().filter(|_| !u.glob) {
+                    self.use_name(&last.node, last.span);
+                }
+                if let Some(alias) = &u.alias {
+                    self.define_global(alias);
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, f: &FnDef) {
+        self.scoped(|c| {
+            for param in &f.params {
+                c.ty(&param.ty);
+                c.define_local(&param.name.node, param.name.span, Binding::Parameter);
+            }
+            c.ty(&f.ret_ty);
+            if let Some(ret_name) = &f.ret_name {
+                c.define_local(&ret_name.node, ret_name.span, IMMUTABLE);
+            }
+            if let Some(pre) = &f.pre {
+                c.expr(pre);
+            }
+            if let Some(post) = &f.post {
+                c.expr(post);
+            }
+            for contract in &f.contracts {
+                c.expr(&contract.condition);
+            }
+            c.expr(&f.body);
+        });
+    }
+
+    /// Record type names mentioned in a type annotation
+    fn ty(&mut self, ty: &Spanned<Type>) {
+        let mut names = Vec::new();
+        type_names(&ty.node, &mut names);
+        names.sort_unstable();
+        names.dedup();
+        let text = self.content.get(ty.span.start..ty.span.end).unwrap_or("");
+        for name in names {
+            for i in find_words(text, name).collect::<Vec<_>>() {
+                let start = ty.span.start + i;
+                self.push(name, Span::new(start, start + name.len()), false, None);
+            }
+        }
+    }
+
+    fn expr(&mut self, e: &Spanned<Expr>) {
+        let span = e.span;
+        match &e.node {
+            Expr::Var(name) => self.use_name(name, span),
+            Expr::Call { func, args } => {
+                self.use_name_in(func, span);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::Let { name, mutable, ty, value, body } => {
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+                self.expr(value);
+                self.scoped(|c| {
+                    c.define_local_in(name, span, Binding::Variable { mutable: *mutable });
+                    c.expr(body);
+                });
+            }
+            // The enclosing block's scope holds the binding
+            Expr::LetStmt { name, mutable, ty, value } => {
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+                self.expr(value);
+                self.define_local_in(name, span, Binding::Variable { mutable: *mutable });
+            }
+            Expr::Assign { name, value } => {
+                self.use_name_in(name, span);
+                self.expr(value);
+            }
+            Expr::For { var, iter, body, .. } => {
+                self.expr(iter);
+                self.scoped(|c| {
+                    c.define_local_in(var, span, IMMUTABLE);
+                    c.expr(body);
+                });
+            }
+            Expr::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    self.scoped(|c| {
+                        c.pattern(&arm.pattern);
+                        if let Some(guard) = &arm.guard {
+                            c.expr(guard);
+                        }
+                        c.expr(&arm.body);
+                    });
+                }
+            }
+            Expr::Closure { params, ret_ty, body } => {
+                self.scoped(|c| {
+                    for param in params {
+                        if let Some(ty) = &param.ty {
+                            c.ty(ty);
+                        }
+                        c.define_local(&param.name.node, param.name.span, Binding::Parameter);
+                    }
+                    if let Some(ret_ty) = ret_ty {
+                        c.ty(ret_ty);
+                    }
+                    c.expr(body);
+                });
+            }
+            Expr::Forall { var, ty, body } | Expr::Exists { var, ty, body } => {
+                self.ty(ty);
+                self.scoped(|c| {
+                    c.define_local(&var.node, var.span, IMMUTABLE);
+                    c.expr(body);
+                });
+            }
+            Expr::StructInit { name, fields, base } => {
+                self.use_name_in(name, span);
+                fields.iter().for_each(|(_, v)| self.expr(v));
+                base.iter().for_each(|b| self.expr(b));
+            }
+            Expr::EnumVariant { enum_name, args, .. } => {
+                self.use_name_in(enum_name, span);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::MethodCall { receiver, method, args } => {
+                self.expr(receiver);
+                self.use_name_in(method, Span::new(receiver.span.end, span.end));
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::Cast { expr, ty } => {
+                self.expr(expr);
+                self.ty(ty);
+            }
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            Expr::IndexAssign { array, index, value } => {
+                self.expr(array);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            Expr::If { cond, then_branch, else_branch } => {
+                self.expr(cond);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            Expr::While { cond, invariant, body, .. } => {
+                self.expr(cond);
+                if let Some(inv) = invariant {
+                    self.expr(inv);
+                }
+                self.expr(body);
+            }
+            Expr::Block(stmts) => self.scoped(|c| stmts.iter().for_each(|s| c.expr(s))),
+            Expr::ArrayLit(elems) | Expr::Tuple(elems) => elems.iter().for_each(|x| self.expr(x)),
+            Expr::Unary { expr, .. }
+            | Expr::FieldAccess { expr, .. }
+            | Expr::TupleField { expr, .. }
+            | Expr::StateRef { expr, .. }
+            | Expr::Ref(expr)
+            | Expr::RefMut(expr)
+            | Expr::Deref(expr) => self.expr(expr),
+            Expr::Loop { body, .. } => self.expr(body),
+            Expr::Break { value, .. } | Expr::Return { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Expr::IntLit(_)
+            | Expr::FloatLit(_)
+            | Expr::BoolLit(_)
+            | Expr::StringLit(_)
+            | Expr::CharLit(_)
+            | Expr::Unit
+            | Expr::Continue { .. }
+            | Expr::Ret
+            | Expr::It
+            | Expr::Todo { .. } => {}
+        }
+    }
+
+    /// Bind pattern variables in the current scope
+    fn pattern(&mut self, p: &Spanned<Pattern>) {
+        match &p.node {
+            Pattern::Var(name) => self.define_local_in(name, p.span, IMMUTABLE),
+            Pattern::Binding { name, pattern } => {
+                self.define_local_in(name, p.span, IMMUTABLE);
+                self.pattern(pattern);
+            }
+            Pattern::EnumVariant { enum_name, bindings, .. } => {
+                self.use_name_in(enum_name, p.span);
+                bindings.iter().for_each(|b| self.pattern(b));
+            }
+            Pattern::Struct { name, fields } => {
+                self.use_name_in(name, p.span);
+                fields.iter().for_each(|(_, f)| self.pattern(f));
+            }
+            Pattern::Or(alts) => alts.iter().for_each(|a| self.pattern(a)),
+            Pattern::Tuple(elems) | Pattern::Array(elems) => elems.iter().for_each(|x| self.pattern(x)),
+            Pattern::ArrayRest { prefix, suffix } => {
+                prefix.iter().chain(suffix).for_each(|x| self.pattern(x));
+            }
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+        }
+    }
+}
+
+/// User-defined type names mentioned in `ty`
+fn type_names<'t>(ty: &'t Type, out: &mut Vec<&'t str>) {
+    match ty {
+        Type::Named(name) => out.push(name),
+        Type::Generic { name, type_args } => {
+            out.push(name);
+            type_args.iter().for_each(|t| type_names(t, out));
+        }
+        Type::Struct { name, .. } | Type::Enum { name, .. } => out.push(name),
+        Type::Range(t)
+        | Type::Ref(t)
+        | Type::RefMut(t)
+        | Type::Array(t, _)
+        | Type::Nullable(t)
+        | Type::Refined { base: t, .. } => type_names(t, out),
+        Type::ArrayConst(t, len) => {
+            out.push(len);
+            type_names(t, out);
+        }
+        Type::Tuple(elems) => elems.iter().for_each(|t| type_names(t, out)),
+        Type::Fn { params, ret } => {
+            params.iter().for_each(|t| type_names(t, out));
+            type_names(ret, out);
+        }
+        Type::I32
+        | Type::I64
+        | Type::U32
+        | Type::U64
+        | Type::F64
+        | Type::Bool
+        | Type::Unit
+        | Type::String
+        | Type::Char
+        | Type::TypeVar(_)
+        | Type::Never => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn occurrences(source: &str) -> Vec<Occurrence> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        collect_occurrences(&program, source)
+    }
+
+    fn spans_of(source: &str, occs: &[Occurrence], target: &Occurrence) -> Vec<String> {
+        occs.iter()
+            .filter(|o| o.same_symbol(target))
+            .map(|o| format!("{}@{}", &source[o.span.start..o.span.end], o.span.start))
+            .collect()
+    }
+
+    #[test]
+    fn test_shadowed_locals_are_distinct() {
+        let source = "fn f(x: i64) -> i64 = {\n    let y = x + 1;\n    let x = y * 2;\n    x + y\n};\n";
+        let occs = occurrences(source);
+        let param = occurrence_at(&occs, source.find("x:").unwrap()).unwrap().clone();
+        assert!(param.is_definition && param.local.is_some());
+        assert_eq!(spans_of(source, &occs, &param).len(), 2, "param and its one use");
+
+        let inner = occurrence_at(&occs, source.rfind("x + y").unwrap()).unwrap().clone();
+        assert_ne!(inner.local, param.local);
+        assert_eq!(spans_of(source, &occs, &inner).len(), 2, "shadowing let and final use");
+    }
+
+    #[test]
+    fn test_global_references_have_identifier_spans() {
+        let source = "struct P { x: i64 }\n\nfn mk(v: i64) -> P = new P { x: v };\n\nfn main() -> i64 = {\n    let p = mk(1);\n    p.x\n};\n";
+        let occs = occurrences(source);
+        let def = occs.iter().find(|o| o.name == "P" && o.is_definition).unwrap();
+        let uses = spans_of(source, &occs, def);
+        assert_eq!(uses, vec!["P@7", "P@38", "P@46"]);
+
+        let mk = occs.iter().find(|o| o.name == "mk" && !o.is_definition).unwrap();
+        assert_eq!(&source[mk.span.start..mk.span.end], "mk");
+        assert_eq!(mk.local, None);
+    }
+
+    #[test]
+    fn test_match_bindings_scope_to_arm() {
+        let source = "enum E { A(i64), B }\n\nfn f(e: E) -> i64 = match e {\n    E::A(n) => n,\n    E::B => 0,\n};\n";
+        let occs = occurrences(source);
+        let n = occurrence_at(&occs, source.find("n)").unwrap()).unwrap().clone();
+        assert!(n.is_definition);
+        assert_eq!(spans_of(source, &occs, &n).len(), 2);
+        let e_uses = occs.iter().filter(|o| o.name == "E" && o.local.is_none()).count();
+        assert_eq!(e_uses, 4, "definition, param type and two patterns");
+    }
+
+    #[test]
+    fn test_candidate_files_use_fresh_index() {
+        let root = std::env::temp_dir().join(format!("bmb_lsp_refs_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let lib = root.join("lib.bmb");
+        let app = root.join("app.bmb");
+        let other = root.join("other.bmb");
+        std::fs::write(&lib, "pub fn helper(x: i64) -> i64 = x;\n").unwrap();
+        std::fs::write(&app, "fn main() -> i64 = helper(1);\n").unwrap();
+        std::fs::write(&other, "fn unrelated() -> i64 = 0;\n").unwrap();
+
+        // No index yet: every file is a candidate
+        assert_eq!(candidate_files(&root, "helper").len(), 3);
+
+        let mut generator = crate::index::IndexGenerator::new("refs");
+        for path in [&lib, &app, &other] {
+            let source = std::fs::read_to_string(path).unwrap();
+            let tokens = crate::lexer::tokenize(&source).unwrap();
+            let program = crate::parser::parse("f.bmb", &source, tokens).unwrap();
+            generator.index_file(&path.display().to_string(), &program);
+        }
+        let mut index = generator.generate();
+        // Date the index after the sources so it counts as fresh
+        let later = chrono::Utc::now() + chrono::Duration::minutes(1);
+        index.manifest.indexed_at = later.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        crate::index::write_index(&index, &root).unwrap();
+
+        assert_eq!(candidate_files(&root, "helper"), vec![app.clone(), lib.clone()]);
+
+        // Touching a file makes the index stale again
+        index.manifest.indexed_at = "2000-01-01T00:00:00Z".to_string();
+        crate::index::write_index(&index, &root).unwrap();
+        assert_eq!(candidate_files(&root, "helper").len(), 3);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
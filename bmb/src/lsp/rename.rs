@@ -0,0 +1,199 @@
+//! Rename planning
+//!
+//! Computes the identifier spans to rewrite when a symbol is renamed, using
+//! the occurrences from `super::references`. Locals are renamed inside
+//! their enclosing function only; global names are renamed in every file
+//! given, including the `use` statements that import them.
+
+use thiserror::Error;
+
+use crate::ast::{Item, Program, Span};
+use crate::lexer::{tokenize, Token};
+
+use super::references::{collect_occurrences, occurrence_at, Occurrence};
+
+/// A parsed source file taking part in a rename
+#[derive(Debug, Clone, Copy)]
+pub struct RenameFile<'a> {
+    pub content: &'a str,
+    pub program: &'a Program,
+}
+
+/// Why a rename was rejected
+#[derive(Debug, Error, PartialEq)]
+pub enum RenameError {
+    #[error("`{0}` is not a valid identifier")]
+    InvalidName(String),
+
+    #[error("No symbol to rename at this position")]
+    NoSymbol,
+
+    #[error("Cannot rename `{0}`: it is not defined in the workspace")]
+    NotDefined(String),
+
+    #[error("Cannot rename to `{name}`: {scope} already has a symbol with that name")]
+    Collision { name: String, scope: String },
+}
+
+/// Identifier spans to replace with `new_name`, as `(file index, span)`.
+///
+/// `files[file]` holds the symbol under `offset`. Edits are ordered by file
+/// and position, and never overlap.
+pub fn rename_symbol(
+    files: &[RenameFile],
+    file: usize,
+    offset: usize,
+    new_name: &str,
+) -> Result<Vec<(usize, Span)>, RenameError> {
+    if !is_identifier(new_name) {
+        return Err(RenameError::InvalidName(new_name.to_string()));
+    }
+
+    let occurrences: Vec<Vec<Occurrence>> = files
+        .iter()
+        .map(|f| collect_occurrences(f.program, f.content))
+        .collect();
+    let target = occurrence_at(&occurrences[file], offset)
+        .cloned()
+        .ok_or(RenameError::NoSymbol)?;
+
+    if target.local.is_some() {
+        return rename_local(files, &occurrences, file, &target, new_name);
+    }
+
+    let mut edits = Vec::new();
+    let mut defined = false;
+    for (i, occs) in occurrences.iter().enumerate() {
+        let matches: Vec<&Occurrence> = occs.iter().filter(|o| o.same_symbol(&target)).collect();
+        if matches.is_empty() {
+            continue;
+        }
+        defined |= matches.iter().any(|o| o.is_definition);
+        // The new name must be free in every file the rename touches
+        if occs.iter().any(|o| o.name == new_name && o.local.is_none() && o.is_definition) {
+            return Err(RenameError::Collision {
+                name: new_name.to_string(),
+                scope: "the module".to_string(),
+            });
+        }
+        edits.extend(matches.into_iter().map(|o| (i, o.span)));
+    }
+    if !defined {
+        return Err(RenameError::NotDefined(target.name));
+    }
+
+    edits.sort_by_key(|(i, span)| (*i, span.start));
+    edits.dedup();
+    Ok(edits)
+}
+
+/// Rename a local binding within its enclosing function
+fn rename_local(
+    files: &[RenameFile],
+    occurrences: &[Vec<Occurrence>],
+    file: usize,
+    target: &Occurrence,
+    new_name: &str,
+) -> Result<Vec<(usize, Span)>, RenameError> {
+    let (fn_name, scope) = enclosing_function(files[file].program, target.span)
+        .unwrap_or_else(|| ("<file>".to_string(), Span::new(0, files[file].content.len())));
+    let in_scope: Vec<&Occurrence> = occurrences[file]
+        .iter()
+        .filter(|o| scope.start <= o.span.start && o.span.end <= scope.end)
+        .collect();
+
+    // Any other use of the new name in the function would be captured or shadowed
+    if in_scope.iter().any(|o| o.name == new_name) {
+        return Err(RenameError::Collision {
+            name: new_name.to_string(),
+            scope: format!("function `{}`", fn_name),
+        });
+    }
+
+    let mut edits: Vec<(usize, Span)> = in_scope
+        .into_iter()
+        .filter(|o| o.same_symbol(target))
+        .map(|o| (file, o.span))
+        .collect();
+    edits.sort_by_key(|(_, span)| span.start);
+    Ok(edits)
+}
+
+/// Name and span of the function (or method) containing `span`
+fn enclosing_function(program: &Program, span: Span) -> Option<(String, Span)> {
+    let contains = |outer: Span| outer.start <= span.start && span.end <= outer.end;
+    program.items.iter().find_map(|item| match item {
+        Item::FnDef(f) if contains(f.span) => Some((f.name.node.clone(), f.span)),
+        Item::ImplBlock(i) => i
+            .methods
+            .iter()
+            .find(|m| contains(m.span))
+            .map(|m| (m.name.node.clone(), m.span)),
+        Item::TraitDef(t) => t
+            .methods
+            .iter()
+            .find(|m| contains(m.span))
+            .map(|m| (m.name.node.clone(), m.span)),
+        _ => None,
+    })
+}
+
+/// Whether `name` lexes as a single identifier (not a keyword or literal)
+fn is_identifier(name: &str) -> bool {
+    match tokenize(name).as_deref() {
+        Ok([(Token::Ident(ident), span)]) => ident == name && span.start == 0 && span.end == name.len(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Program {
+        let tokens = tokenize(source).unwrap();
+        crate::parser::parse("test.bmb", source, tokens).unwrap()
+    }
+
+    fn renamed(source: &str, at: &str, new_name: &str) -> Result<String, RenameError> {
+        let program = parse(source);
+        let files = [RenameFile { content: source, program: &program }];
+        let edits = rename_symbol(&files, 0, source.find(at).unwrap(), new_name)?;
+        let mut out = source.to_string();
+        for (_, span) in edits.iter().rev() {
+            out.replace_range(span.start..span.end, new_name);
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn test_rename_local_stays_in_function() {
+        let source = "fn f(x: i64) -> i64 = x + 1;\n\nfn g(x: i64) -> i64 = x * 2;\n";
+        assert_eq!(
+            renamed(source, "x:", "n").unwrap(),
+            "fn f(n: i64) -> i64 = n + 1;\n\nfn g(x: i64) -> i64 = x * 2;\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_rejects_invalid_names_and_collisions() {
+        let source = "fn f(x: i64, y: i64) -> i64 = x + y;\n\nfn g() -> i64 = f(1, 2);\n";
+        assert_eq!(renamed(source, "x:", "1x"), Err(RenameError::InvalidName("1x".to_string())));
+        assert_eq!(renamed(source, "x:", "match"), Err(RenameError::InvalidName("match".to_string())));
+        assert!(matches!(renamed(source, "x:", "y"), Err(RenameError::Collision { .. })));
+        assert!(matches!(renamed(source, "f(1", "g"), Err(RenameError::Collision { .. })));
+        assert_eq!(
+            renamed(source, "f(1", "h").unwrap(),
+            "fn h(x: i64, y: i64) -> i64 = x + y;\n\nfn g() -> i64 = h(1, 2);\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_rejects_builtins() {
+        let source = "fn main() -> i64 = { println(1); 0 };\n";
+        assert_eq!(
+            renamed(source, "println", "show"),
+            Err(RenameError::NotDefined("println".to_string()))
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Semantic tokens for syntax highlighting
+//!
+//! Every lexer token that means something beyond punctuation becomes a
+//! token: keywords, literals, primitive types and identifiers. Identifiers
+//! are classified with the find-references occurrences — locals as
+//! parameters or variables, globals by the item that defines them — and,
+//! where the AST has no record (fields, builtins, imports), by the tokens
+//! around them. Anything inside a `pre`/`post` condition, a named contract
+//! or a refinement carries the `contract` modifier.
+
+use std::collections::{HashMap, HashSet};
+
+use logos::Logos;
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend};
+
+use super::references::{collect_bindings, Binding, Occurrence};
+use crate::ast::{FnDef, Item, Program, Span, Type};
+use crate::lexer::Token;
+
+/// Token types, in legend order
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Keyword,
+    Function,
+    Method,
+    Parameter,
+    Variable,
+    Property,
+    Type,
+    EnumMember,
+    Number,
+    String,
+}
+
+const TOKEN_TYPES: [SemanticTokenType; 10] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::STRING,
+];
+
+/// Modifier bits, in legend order
+const DECLARATION: u32 = 1;
+const READONLY: u32 = 1 << 1;
+const MUTABLE: u32 = 1 << 2;
+const CONTRACT: u32 = 1 << 3;
+
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 4] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::new("mutable"),
+    SemanticTokenModifier::new("contract"),
+];
+
+/// The legend advertised in the server capabilities
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Delta-encoded semantic tokens for `content`, using whatever part of it
+/// parsed as `program`
+pub fn semantic_tokens(program: Option<&Program>, content: &str) -> Vec<SemanticToken> {
+    // Lex leniently so a stray character doesn't blank the whole document
+    let tokens: Vec<(Token, Span)> = Token::lexer(content)
+        .spanned()
+        .filter_map(|(token, range)| Some((token.ok()?, Span::new(range.start, range.end))))
+        .collect();
+
+    let classifier = program.map(|p| Classifier::new(p, content));
+    let mut encoder = Encoder::new(content);
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        let classified = match token {
+            Token::Ident(name) => {
+                let prev = i.checked_sub(1).map(|j| &tokens[j].0);
+                let next = tokens.get(i + 1).map(|(t, _)| t);
+                match &classifier {
+                    Some(c) => c.identifier(name, *span, prev, next),
+                    None => by_context(name, prev, next),
+                }
+            }
+            _ => token_kind(token).map(|kind| (kind, 0)),
+        };
+        let Some((kind, mut modifiers)) = classified else { continue };
+        if classifier.as_ref().is_some_and(|c| c.in_contract(*span)) {
+            modifiers |= CONTRACT;
+        }
+        encoder.push(*span, kind, modifiers);
+    }
+    encoder.tokens
+}
+
+/// Kind of a non-identifier token, if it is highlighted at all
+fn token_kind(token: &Token) -> Option<Kind> {
+    use Token::*;
+    Some(match token {
+        IntLit(_) | FloatLit(_) => Kind::Number,
+        StringLit(_) | CharLit(_) => Kind::String,
+        TyI32 | TyI64 | TyU32 | TyU64 | TyF64 | TyBool | TyString | TyChar => Kind::Type,
+        Fn | Let | Var | If | Then | Else | Pre | Post | True | False | Ret | And | Or | Not | Struct
+        | Enum | Match | New | Mut | While | For | In | Loop | Break | Continue | Return | Band | Bor
+        | Bxor | Bnot | Pub | Use | Mod | Where | It | Extern | Try | As | Trait | Impl | Todo | Type
+        | Const | Invariant | Implies | Forall | Exists | Module | Version | Summary | Exports
+        | Depends => Kind::Keyword,
+        _ => return None,
+    })
+}
+
+/// Classify an identifier the AST knows nothing about from its neighbours
+fn by_context(name: &str, prev: Option<&Token>, next: Option<&Token>) -> Option<(Kind, u32)> {
+    let kind = match (prev, next) {
+        (Some(Token::Dot), Some(Token::LParen)) => Kind::Method,
+        (Some(Token::Dot), _) => Kind::Property,
+        (Some(Token::ColonColon), Some(Token::LParen)) if name.starts_with(char::is_lowercase) => Kind::Function,
+        (Some(Token::ColonColon), _) => Kind::EnumMember,
+        (_, Some(Token::LParen)) if name.starts_with(char::is_lowercase) => Kind::Function,
+        _ if name.starts_with(char::is_uppercase) => Kind::Type,
+        _ => return None,
+    };
+    Some((kind, 0))
+}
+
+/// What the parsed program says about identifiers
+struct Classifier<'a> {
+    /// Occurrences by the start of their identifier
+    occurrences: HashMap<usize, Occurrence>,
+    bindings: Vec<Binding>,
+    /// Kind and modifiers of each item name
+    globals: HashMap<&'a str, (Kind, u32)>,
+    variants: HashSet<&'a str>,
+    /// Spans of contract conditions and refinements
+    contracts: Vec<Span>,
+}
+
+impl<'a> Classifier<'a> {
+    fn new(program: &'a Program, content: &str) -> Self {
+        let (occurrences, bindings) = collect_bindings(program, content);
+        let mut classifier = Classifier {
+            occurrences: occurrences.into_iter().map(|o| (o.span.start, o)).collect(),
+            bindings,
+            globals: HashMap::new(),
+            variants: HashSet::new(),
+            contracts: Vec::new(),
+        };
+        for item in &program.items {
+            classifier.item(item);
+        }
+        classifier
+    }
+
+    fn item(&mut self, item: &'a Item) {
+        match item {
+            Item::FnDef(f) => {
+                self.globals.insert(&f.name.node, (Kind::Function, 0));
+                self.function(f);
+            }
+            Item::ExternFn(e) => {
+                self.globals.insert(&e.name.node, (Kind::Function, 0));
+            }
+            Item::StructDef(s) => {
+                self.globals.insert(&s.name.node, (Kind::Type, 0));
+            }
+            Item::EnumDef(e) => {
+                self.globals.insert(&e.name.node, (Kind::Type, 0));
+                self.variants.extend(e.variants.iter().map(|v| v.name.node.as_str()));
+            }
+            Item::TraitDef(t) => {
+                self.globals.insert(&t.name.node, (Kind::Type, 0));
+                for m in &t.methods {
+                    self.globals.insert(&m.name.node, (Kind::Method, 0));
+                }
+            }
+            Item::ImplBlock(i) => {
+                for method in &i.methods {
+                    self.globals.insert(&method.name.node, (Kind::Method, 0));
+                    self.function(method);
+                }
+            }
+            Item::TypeAlias(t) => {
+                self.globals.insert(&t.name.node, (Kind::Type, 0));
+            }
+            Item::ConstDef(c) => {
+                self.globals.insert(&c.name.node, (Kind::Variable, READONLY));
+            }
+            Item::Use(_) => {}
+        }
+    }
+
+    fn function(&mut self, f: &FnDef) {
+        self.contracts.extend(f.pre.iter().chain(&f.post).map(|c| c.span));
+        self.contracts.extend(f.contracts.iter().map(|c| c.condition.span));
+        for ty in f.params.iter().map(|p| &p.ty).chain([&f.ret_ty]) {
+            if let Type::Refined { constraints, .. } = &ty.node {
+                self.contracts.extend(constraints.iter().map(|c| c.span));
+            }
+        }
+    }
+
+    fn identifier(&self, name: &str, span: Span, prev: Option<&Token>, next: Option<&Token>) -> Option<(Kind, u32)> {
+        let occurrence = self.occurrences.get(&span.start).filter(|o| o.span == span);
+        let declaration = if occurrence.is_some_and(|o| o.is_definition) { DECLARATION } else { 0 };
+        match occurrence.map(|o| o.local) {
+            Some(Some(id)) => match self.bindings.get(id)? {
+                Binding::Parameter => Some((Kind::Parameter, declaration)),
+                Binding::Variable { mutable } => {
+                    Some((Kind::Variable, declaration | if *mutable { MUTABLE } else { 0 }))
+                }
+            },
+            _ if prev == Some(&Token::Dot) => by_context(name, prev, next),
+            _ if self.variants.contains(name) && !self.globals.contains_key(name) => Some((Kind::EnumMember, 0)),
+            Some(None) if self.globals.contains_key(name) => {
+                self.globals.get(name).map(|&(kind, modifiers)| (kind, modifiers | declaration))
+            }
+            _ => by_context(name, prev, next),
+        }
+    }
+
+    fn in_contract(&self, span: Span) -> bool {
+        self.contracts.iter().any(|c| c.start <= span.start && span.end <= c.end)
+    }
+}
+
+/// Relative encoding of tokens in source order, with character columns as
+/// in the rest of the server
+struct Encoder<'a> {
+    content: &'a str,
+    tokens: Vec<SemanticToken>,
+    /// Byte offset, line and column scanned up to
+    offset: usize,
+    line: u32,
+    col: u32,
+    /// Position of the previous token
+    prev_line: u32,
+    prev_col: u32,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(content: &'a str) -> Self {
+        Encoder { content, tokens: Vec::new(), offset: 0, line: 0, col: 0, prev_line: 0, prev_col: 0 }
+    }
+
+    /// Advance the scan position to `offset`
+    fn advance(&mut self, offset: usize) {
+        for c in self.content[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.col = 0;
+            } else {
+                self.col += 1;
+            }
+        }
+        self.offset = offset;
+    }
+
+    /// Push a token, one per line if it spans several
+    fn push(&mut self, span: Span, kind: Kind, modifiers: u32) {
+        self.advance(span.start);
+        for (i, piece) in self.content[span.start..span.end].split('\n').enumerate() {
+            if i > 0 {
+                self.advance(self.offset + 1);
+            }
+            let length = piece.chars().count() as u32;
+            if length > 0 {
+                let delta_line = self.line - self.prev_line;
+                let delta_start = if delta_line == 0 { self.col - self.prev_col } else { self.col };
+                self.tokens.push(SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length,
+                    token_type: kind as u32,
+                    token_modifiers_bitset: modifiers,
+                });
+                self.prev_line = self.line;
+                self.prev_col = self.col;
+            }
+            self.advance(self.offset + piece.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode to (line, col, text, kind, modifiers)
+    fn decoded(source: &str) -> Vec<(u32, u32, String, Kind, u32)> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let lines: Vec<&str> = source.split('\n').collect();
+        let kinds = [
+            Kind::Keyword,
+            Kind::Function,
+            Kind::Method,
+            Kind::Parameter,
+            Kind::Variable,
+            Kind::Property,
+            Kind::Type,
+            Kind::EnumMember,
+            Kind::Number,
+            Kind::String,
+        ];
+        let (mut line, mut col) = (0, 0);
+        semantic_tokens(Some(&program), source)
+            .into_iter()
+            .map(|t| {
+                if t.delta_line > 0 {
+                    col = 0;
+                }
+                line += t.delta_line;
+                col += t.delta_start;
+                let text: String = lines[line as usize].chars().skip(col as usize).take(t.length as usize).collect();
+                (line, col, text, kinds[t.token_type as usize], t.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    fn kind_of(tokens: &[(u32, u32, String, Kind, u32)], text: &str, nth: usize) -> (Kind, u32) {
+        let (_, _, _, kind, modifiers) = tokens.iter().filter(|t| t.2 == text).nth(nth).unwrap();
+        (*kind, *modifiers)
+    }
+
+    #[test]
+    fn test_identifiers_are_classified() {
+        let source = "enum Dir { Up, Down }\n\nfn step(d: Dir, n: i64) -> i64\n  pre n >= 0\n= {\n    let mut acc = n;\n    let k = match d { Dir::Up => 1, Dir::Down => 0 - 1 };\n    acc = acc + k;\n    acc\n};\n";
+        let tokens = decoded(source);
+        assert_eq!(kind_of(&tokens, "enum", 0), (Kind::Keyword, 0));
+        assert_eq!(kind_of(&tokens, "Dir", 0), (Kind::Type, DECLARATION));
+        assert_eq!(kind_of(&tokens, "Up", 0), (Kind::EnumMember, 0));
+        assert_eq!(kind_of(&tokens, "step", 0), (Kind::Function, DECLARATION));
+        assert_eq!(kind_of(&tokens, "n", 0), (Kind::Parameter, DECLARATION));
+        assert_eq!(kind_of(&tokens, "n", 1), (Kind::Parameter, CONTRACT));
+        assert_eq!(kind_of(&tokens, "0", 0), (Kind::Number, CONTRACT));
+        assert_eq!(kind_of(&tokens, "i64", 0), (Kind::Type, 0));
+        assert_eq!(kind_of(&tokens, "acc", 0), (Kind::Variable, DECLARATION | MUTABLE));
+        assert_eq!(kind_of(&tokens, "acc", 2), (Kind::Variable, MUTABLE));
+        assert_eq!(kind_of(&tokens, "k", 0), (Kind::Variable, DECLARATION));
+        assert_eq!(kind_of(&tokens, "Up", 1), (Kind::EnumMember, 0));
+    }
+
+    #[test]
+    fn test_encoding_is_relative_and_splits_lines() {
+        let source = "fn f() -> String = \"a\nbc\";\n";
+        let tokens = decoded(source);
+        let positions: Vec<(u32, u32, &str)> = tokens.iter().map(|t| (t.0, t.1, t.2.as_str())).collect();
+        assert_eq!(
+            positions,
+            vec![(0, 0, "fn"), (0, 3, "f"), (0, 10, "String"), (0, 19, "\"a"), (1, 0, "bc\"")]
+        );
+    }
+}
@@ -1,6 +1,9 @@
 //! BMB Compiler CLI
 
+use bmb::cli_output::{CliOutcome, ExitStatus, Reported, UsageError};
+use bmb::project::Project;
 use clap::{Parser, Subcommand};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -25,10 +28,15 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Command {
+    /// Create a new project with a bmb.toml manifest
+    New {
+        /// Package name, also the directory created
+        name: String,
+    },
     /// Build a native executable (requires LLVM)
     Build {
-        /// Source file to compile
-        file: PathBuf,
+        /// Source file to compile (default: the project's src/main.bmb)
+        file: Option<PathBuf>,
         /// Output file path
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -44,6 +52,9 @@ enum Command {
         /// Emit MIR (Mid-level IR) - v0.21.2
         #[arg(long)]
         emit_mir: bool,
+        /// Emit MIR control-flow graphs as Graphviz DOT (<file>.cfg.dot)
+        #[arg(long)]
+        emit_cfg: bool,
         /// Emit WASM text format (.wat)
         #[arg(long)]
         emit_wasm: bool,
@@ -55,8 +66,14 @@ enum Command {
         all_targets: bool,
         /// Target triple for cross-compilation (v0.50.23)
         /// Examples: x86_64-unknown-linux-gnu, x86_64-pc-windows-msvc, aarch64-apple-darwin
-        #[arg(long)]
+        #[arg(long, visible_alias = "target-triple", value_name = "TRIPLE")]
         target: Option<String>,
+        /// Runtime contract checking in native builds (off, check)
+        #[arg(long, value_enum, default_value = "off")]
+        contracts: ContractsMode,
+        /// Emit DWARF debug info (default: on unless --release or --aggressive)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+        debug: Option<bool>,
         /// Verbose output
         #[arg(short, long)]
         verbose: bool,
@@ -71,27 +88,60 @@ enum Command {
         /// v0.71: Human-readable output (colors, formatting). Default: machine/JSON
         #[arg(long)]
         human: bool,
+        /// Compile in-process with the LLVM JIT instead of interpreting
+        #[arg(long)]
+        jit: bool,
+        /// What plain `+`, `-`, `*` and negation do when an integer overflows
+        #[arg(long, value_enum, default_value = "check")]
+        overflow: OverflowMode,
     },
     /// Start interactive REPL
     Repl,
     /// Type check a BMB source file
     Check {
-        /// Source file to check
-        file: PathBuf,
+        /// Source file or directory to check (default: the project's src/main.bmb)
+        file: Option<PathBuf>,
         /// v0.17: Additional include paths for module resolution
         #[arg(short = 'I', long = "include", value_name = "PATH")]
         include_paths: Vec<PathBuf>,
+        /// Re-check whenever a .bmb file next to the target or under an include path changes
+        #[arg(long)]
+        watch: bool,
+        /// Milliseconds to wait for changes to settle before re-checking
+        #[arg(long, value_name = "MS", default_value = "500", requires = "watch")]
+        debounce: u64,
     },
     /// Verify contracts (pre/post conditions) using SMT solver
     Verify {
         /// Source file to verify
         file: PathBuf,
-        /// Path to Z3 executable (default: z3)
-        #[arg(long, default_value = "z3")]
-        z3_path: String,
+        /// SMT solver to run
+        #[arg(long, value_enum, default_value = "z3")]
+        solver: SolverKind,
+        /// Path to the solver executable (default: `z3` or `cvc5` from PATH)
+        #[arg(long, visible_alias = "z3-path", value_name = "PATH")]
+        solver_path: Option<String>,
         /// Timeout in seconds
         #[arg(long, short = 't', default_value = "10")]
         timeout: u32,
+        /// Write a `bmb test` file for each counterexample into this directory
+        #[arg(long, value_name = "DIR")]
+        emit_tests: Option<PathBuf>,
+        /// Also prove that every array index is within bounds
+        #[arg(long)]
+        check_bounds: bool,
+        /// Also prove that no integer division or remainder divides by zero
+        #[arg(long)]
+        check_division: bool,
+        /// Re-verify every function instead of reusing `.bmb/verify-cache/`
+        #[arg(long)]
+        no_cache: bool,
+        /// Verify only this function (repeatable); the others are reported as skipped
+        #[arg(long = "function", value_name = "NAME")]
+        functions: Vec<String>,
+        /// Print one JSON report with every function's conditions and counterexamples
+        #[arg(long)]
+        json: bool,
     },
     /// Parse and dump AST (debug)
     Parse {
@@ -100,6 +150,9 @@ enum Command {
         /// Output format: json or sexpr (S-expression)
         #[arg(long, short, default_value = "json")]
         format: String,
+        /// Dump only the item (function, struct, enum, trait, ...) with this name
+        #[arg(long, value_name = "NAME")]
+        item: Option<String>,
     },
     /// Tokenize and dump tokens (debug)
     Tokens {
@@ -108,22 +161,46 @@ enum Command {
     },
     /// Run tests in a BMB file
     Test {
-        /// Source file or directory to test
-        file: PathBuf,
+        /// Source file or directory to test (default: the project's tests/)
+        file: Option<PathBuf>,
         /// Filter tests by pattern
         #[arg(long, short)]
         filter: Option<String>,
         /// Verbose output (show all test results)
         #[arg(short, long)]
         verbose: bool,
+        /// Report line coverage of the tested files
+        #[arg(long)]
+        coverage: bool,
+    },
+    /// Run `bench_*` functions repeatedly and report time per iteration
+    Bench {
+        /// Source file or directory of `bench_*.bmb` files
+        file: PathBuf,
+        /// Measured iterations per benchmark (after warmup)
+        #[arg(long, short = 'n', default_value = "100")]
+        iterations: u32,
+        /// Filter benchmarks by pattern
+        #[arg(long, short)]
+        filter: Option<String>,
     },
     /// Format a BMB source file
     Fmt {
         /// Source file or directory to format
-        file: PathBuf,
+        #[arg(required_unless_present = "stdin")]
+        file: Option<PathBuf>,
         /// Check formatting without modifying files
         #[arg(long)]
         check: bool,
+        /// Maximum line width
+        #[arg(long, default_value_t = bmb::fmt::DEFAULT_WIDTH)]
+        width: usize,
+        /// Read the source from stdin and write the formatted source to stdout
+        #[arg(long, conflicts_with_all = ["file", "check"])]
+        stdin: bool,
+        /// Format only the items within lines START:END (1-based, inclusive)
+        #[arg(long, value_name = "START:END", value_parser = parse_line_range)]
+        range: Option<RangeInclusive<usize>>,
     },
     /// Lint a BMB source file (v0.45)
     Lint {
@@ -135,6 +212,23 @@ enum Command {
         /// Additional include paths for module resolution
         #[arg(short = 'I', long = "include", value_name = "PATH")]
         include_paths: Vec<PathBuf>,
+        /// TOML file whose `[lint]` table sets each rule to allow, warn or
+        /// deny (default: the project's bmb.toml)
+        #[arg(long, value_name = "PATH")]
+        lint_config: Option<PathBuf>,
+    },
+    /// Generate API documentation from `///` doc comments
+    Doc {
+        /// Source file or directory to document
+        file: PathBuf,
+        /// Write one Markdown page per source file to this directory
+        #[arg(long, value_name = "DIR")]
+        markdown: Option<PathBuf>,
+    },
+    /// Explain a diagnostic code, with an example and its fix
+    Explain {
+        /// Diagnostic code, such as E0308
+        code: String,
     },
     /// Start Language Server Protocol server
     Lsp,
@@ -171,6 +265,87 @@ enum Command {
     },
 }
 
+impl Command {
+    /// Subcommand name, as reported in the summary record
+    fn name(&self) -> &'static str {
+        match self {
+            Command::New { .. } => "new",
+            Command::Build { .. } => "build",
+            Command::Run { .. } => "run",
+            Command::Repl => "repl",
+            Command::Check { .. } => "check",
+            Command::Verify { .. } => "verify",
+            Command::Parse { .. } => "parse",
+            Command::Tokens { .. } => "tokens",
+            Command::Test { .. } => "test",
+            Command::Bench { .. } => "bench",
+            Command::Fmt { .. } => "fmt",
+            Command::Lint { .. } => "lint",
+            Command::Doc { .. } => "doc",
+            Command::Explain { .. } => "explain",
+            Command::Lsp => "lsp",
+            Command::Index { .. } => "index",
+            Command::Query { .. } => "q",
+            Command::VerifyStage3 { .. } => "verify-stage3",
+        }
+    }
+
+    /// Whether stdout belongs to the program being run or to a protocol,
+    /// leaving no room for a summary record
+    fn owns_stdout(&self) -> bool {
+        matches!(self, Command::Run { .. } | Command::Repl | Command::Lsp | Command::Fmt { stdin: true, .. })
+    }
+}
+
+/// Runtime treatment of pre/post conditions in native builds
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum ContractsMode {
+    /// Contracts are not checked at runtime (default)
+    #[default]
+    Off,
+    /// Failed contracts print the function and condition and exit with code 101;
+    /// so does integer overflow in `+`, `-` and `*`
+    Check,
+}
+
+impl From<ContractsMode> for bmb::mir::ContractMode {
+    fn from(mode: ContractsMode) -> Self {
+        match mode {
+            ContractsMode::Off => bmb::mir::ContractMode::Off,
+            ContractsMode::Check => bmb::mir::ContractMode::Check,
+        }
+    }
+}
+
+/// Interpreter treatment of integer overflow in unqualified arithmetic
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+enum OverflowMode {
+    /// Overflow is a runtime error (default)
+    #[default]
+    Check,
+    /// Overflow wraps like `+%`, `-%`, `*%`, for programs relying on it
+    Wrap,
+}
+
+/// SMT solver for `bmb verify`
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum SolverKind {
+    /// Z3 (default)
+    #[default]
+    Z3,
+    /// CVC5
+    Cvc5,
+}
+
+impl From<SolverKind> for bmb::smt::SolverBackend {
+    fn from(kind: SolverKind) -> Self {
+        match kind {
+            SolverKind::Z3 => bmb::smt::SolverBackend::Z3,
+            SolverKind::Cvc5 => bmb::smt::SolverBackend::Cvc5,
+        }
+    }
+}
+
 /// Output format for queries (v0.48 - RFC-0001)
 #[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
 enum OutputFormat {
@@ -189,7 +364,7 @@ enum QueryType {
     Sym {
         /// Pattern to search for
         pattern: String,
-        /// Filter by kind (fn, struct, enum, type, trait)
+        /// Filter by kind (fn, struct, enum, type, trait, const)
         #[arg(long)]
         kind: Option<String>,
         /// Only show public symbols
@@ -235,6 +410,18 @@ enum QueryType {
         #[arg(long, short = 'f', value_enum, default_value = "json")]
         format: OutputFormat,
     },
+    /// List functions that still contain `todo` placeholders
+    Todo {
+        /// Output format (json, compact, llm)
+        #[arg(long, short = 'f', value_enum, default_value = "json")]
+        format: OutputFormat,
+    },
+    /// List private symbols that no file in the project uses
+    Unused {
+        /// Output format (json, compact, llm)
+        #[arg(long, short = 'f', value_enum, default_value = "json")]
+        format: OutputFormat,
+    },
     /// Query dependencies (v0.47 - RFC-0001)
     Deps {
         /// Target to query (e.g., fn:main, type:Order)
@@ -249,6 +436,28 @@ enum QueryType {
         #[arg(long, short = 'f', value_enum, default_value = "json")]
         format: OutputFormat,
     },
+    /// List the functions that call a function
+    Callers {
+        /// Function to query (e.g., fn:main or main)
+        target: String,
+        /// Include callers of callers
+        #[arg(long)]
+        transitive: bool,
+        /// Output format (json, compact, llm)
+        #[arg(long, short = 'f', value_enum, default_value = "json")]
+        format: OutputFormat,
+    },
+    /// List the functions a function calls
+    Callees {
+        /// Function to query (e.g., fn:main or main)
+        target: String,
+        /// Include everything the callees call in turn
+        #[arg(long)]
+        transitive: bool,
+        /// Output format (json, compact, llm)
+        #[arg(long, short = 'f', value_enum, default_value = "json")]
+        format: OutputFormat,
+    },
     /// Query contract details (v0.47 - RFC-0001)
     Contract {
         /// Function name to query contracts for
@@ -316,6 +525,9 @@ enum QueryType {
         /// Host to bind to
         #[arg(long, default_value = "127.0.0.1")]
         host: String,
+        /// Number of connections handled at once (worker threads)
+        #[arg(long, default_value = "8")]
+        max_connections: usize,
     },
     /// Query verification proof results (v0.50.24 - Task 47.7-47.8)
     Proof {
@@ -338,14 +550,20 @@ enum QueryType {
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => usage_error(e),
+    };
 
     // v0.71: Set human output mode (default: machine)
     if cli.human {
         HUMAN_OUTPUT.store(true, Ordering::Relaxed);
     }
 
+    let command = cli.command.name();
+    let owns_stdout = cli.command.owns_stdout();
     let result = match cli.command {
+        Command::New { name } => new_project(&name),
         Command::Build {
             file,
             output,
@@ -353,39 +571,163 @@ fn main() {
             aggressive,
             emit_ir,
             emit_mir,
+            emit_cfg,
             emit_wasm,
             wasm_target,
             all_targets,
             target,
+            contracts,
+            debug,
             verbose,
-        } => build_file(&file, output, release, aggressive, emit_ir, emit_mir, emit_wasm, &wasm_target, all_targets, target.as_deref(), verbose),
-        Command::Run { file, args, human: _ } => run_file(&file, &args),
+        } => {
+            let debug = debug.unwrap_or(!release && !aggressive);
+            build_target(file, output).and_then(|(file, output)| {
+                build_file(&file, output, release, aggressive, emit_ir, emit_mir, emit_cfg, emit_wasm, &wasm_target, all_targets, target.as_deref(), contracts.into(), debug, verbose)
+            })
+        }
+        Command::Run { file, args, human: _, jit, overflow } => {
+            if jit {
+                run_jit_file(&file, &args, overflow)
+            } else {
+                run_file(&file, &args, overflow)
+            }
+        }
         Command::Repl => start_repl(),
-        Command::Check { file, include_paths } => check_file_with_includes(&file, &include_paths),
-        Command::Verify { file, z3_path, timeout } => verify_file(&file, &z3_path, timeout),
-        Command::Parse { file, format } => parse_file(&file, &format),
+        Command::Check { file, include_paths, watch, debounce } => project_file(file, Project::main_file)
+            .and_then(|file| match watch {
+                true => watch_check(&file, &include_paths, debounce),
+                false => check_path(&file, &include_paths),
+            }),
+        Command::Verify { file, solver, solver_path, timeout, emit_tests, check_bounds, check_division, no_cache, functions, json } => {
+            verify_file(&file, solver, solver_path.as_deref(), timeout, emit_tests.as_ref(), check_bounds, check_division, !no_cache, &functions, json)
+        }
+        Command::Parse { file, format, item } => parse_file(&file, &format, item.as_deref()),
         Command::Tokens { file } => tokenize_file(&file),
-        Command::Test { file, filter, verbose } => test_file(&file, filter.as_deref(), verbose),
-        Command::Fmt { file, check } => fmt_file(&file, check),
-        Command::Lint { file, strict, include_paths } => lint_file(&file, strict, &include_paths),
+        Command::Test { file, filter, verbose, coverage } => project_file(file, Project::tests_dir)
+            .and_then(|file| test_file(&file, filter.as_deref(), verbose, coverage)),
+        Command::Bench { file, iterations, filter } => bench_file(&file, iterations, filter),
+        Command::Fmt { file: Some(file), check, width, stdin: false, range } => fmt_file(&file, check, width, range),
+        Command::Fmt { width, range, .. } => fmt_stdin(width, range),
+        Command::Lint { file, strict, include_paths, lint_config } => {
+            lint_file(&file, strict, &include_paths, lint_config.as_deref())
+        }
+        Command::Doc { file, markdown } => doc_file(&file, markdown.as_deref()),
+        Command::Explain { code } => explain_code(&code),
         Command::Lsp => start_lsp(),
         Command::Index { path, watch, verbose } => index_project(&path, watch, verbose),
         Command::Query { query_type } => run_query(query_type),
         Command::VerifyStage3 { file, verbose, output } => verify_stage3(&file, verbose, output.as_ref()),
     };
 
-    if let Err(e) = result {
-        // v0.71: Default machine output, --human for human-readable
-        if is_human_output() {
-            eprintln!("Error: {e}");
-        } else {
-            println!(r#"{{"type":"error","message":"{}"}}"#,
-                e.to_string().replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"));
-        }
-        std::process::exit(1);
+    let mut outcome = result.unwrap_or_else(|e| {
+        print_error(e.as_ref());
+        CliOutcome::from_error(command, e.as_ref())
+    });
+    if owns_stdout {
+        outcome = outcome.quiet();
+    }
+    outcome.emit(is_human_output());
+    std::process::exit(outcome.exit_code());
+}
+
+/// Report a command line clap rejected and exit with the usage status.
+/// `--help` and `--version` are not errors and print as usual.
+fn usage_error(e: clap::Error) -> ! {
+    if !e.use_stderr() {
+        e.exit();
+    }
+    let human = std::env::args().any(|arg| arg == "--human");
+    if human {
+        let _ = e.print();
+    } else {
+        bmb::error::print_diagnostics(vec![bmb::error::Diagnostic::error(e.render().to_string().trim_end())]);
+    }
+    let outcome = CliOutcome::new("bmb").with_status(ExitStatus::Usage);
+    outcome.emit(human);
+    std::process::exit(outcome.exit_code());
+}
+
+/// Print a command's error, unless it was already reported as diagnostics
+fn print_error(e: &(dyn std::error::Error + 'static)) {
+    // Structured diagnostics were already printed by the command
+    if e.is::<Reported>() {
+        return;
+    }
+    // v0.71: Default machine output, --human for human-readable
+    if is_human_output() {
+        eprintln!("Error: {e}");
+    } else {
+        bmb::error::print_diagnostics(vec![bmb::error::Diagnostic::error(e.to_string())]);
+    }
+}
+
+/// Surface a compile error in the active output mode: the structured
+/// diagnostic (`CompileError::to_diagnostic`) for machine output, or its
+/// rustc-style rendering with the offending source line for --human.
+fn compile_error(filename: &str, source: &str, error: bmb::error::CompileError) -> Box<dyn std::error::Error> {
+    if is_human_output() {
+        bmb::error::report_error(filename, source, &error);
+    } else {
+        bmb::error::report_error_machine(filename, source, &error);
+    }
+    Box::new(Reported)
+}
+
+/// Create `name/` with a manifest, `src/main.bmb` and `tests/test_main.bmb`
+fn new_project(name: &str) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let root = bmb::project::create(Path::new("."), name)?;
+    if is_human_output() {
+        println!("Created project '{}' in {}", name, root.display());
+    } else {
+        println!("{}", serde_json::json!({
+            "type": "project_created",
+            "name": name,
+            "path": root.display().to_string(),
+        }));
+    }
+    Ok(CliOutcome::new("new"))
+}
+
+/// The project containing the current directory
+fn current_project() -> Result<Project, Box<dyn std::error::Error>> {
+    Project::discover(Path::new("."))?.ok_or_else(|| {
+        UsageError(format!("no file given and no {} found in the current directory or its parents",
+            bmb::project::MANIFEST_FILE)).into()
+    })
+}
+
+/// `file` if given, otherwise the path `pick` selects from the current project
+fn project_file(
+    file: Option<PathBuf>,
+    pick: fn(&Project) -> PathBuf,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    match file {
+        Some(file) => Ok(file),
+        None => Ok(pick(&current_project()?)),
     }
 }
 
+/// Entry file and output of `bmb build`. Without a file, the current
+/// project's main file is built to `target/<name>` unless `-o` is given.
+fn build_target(
+    file: Option<PathBuf>,
+    output: Option<PathBuf>,
+) -> Result<(PathBuf, Option<PathBuf>), Box<dyn std::error::Error>> {
+    if let Some(file) = file {
+        return Ok((file, output));
+    }
+    let project = current_project()?;
+    let output = match output {
+        Some(output) => output,
+        None => {
+            let target_dir = project.root.join("target");
+            std::fs::create_dir_all(&target_dir)?;
+            target_dir.join(project.name()).with_extension(if cfg!(windows) { "exe" } else { "" })
+        }
+    };
+    Ok((project.main_file(), Some(output)))
+}
+
 #[allow(clippy::too_many_arguments)]
 fn build_file(
     path: &PathBuf,
@@ -394,15 +736,27 @@ fn build_file(
     aggressive: bool,
     emit_ir: bool,
     emit_mir: bool,
+    emit_cfg: bool,
     emit_wasm: bool,
     wasm_target: &str,
     all_targets: bool,
     target: Option<&str>,
+    contracts: bmb::mir::ContractMode,
+    debug: bool,
     verbose: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     // v0.21.2: If emitting MIR, just output MIR and return
-    if emit_mir {
-        return emit_mir_file(path, output, verbose);
+    let mir_opt_level = if aggressive {
+        bmb::mir::OptLevel::Aggressive
+    } else if release {
+        bmb::mir::OptLevel::Release
+    } else {
+        bmb::mir::OptLevel::Debug
+    };
+
+    if emit_mir || emit_cfg {
+        emit_mir_file(path, output, mir_opt_level, contracts, emit_cfg, verbose)?;
+        return Ok(CliOutcome::new("build"));
     }
 
     // v0.12.4: Build for all targets (native + WASM)
@@ -415,29 +769,28 @@ fn build_file(
         if verbose {
             println!("\n=== Native Build ===");
         }
-        build_native(path, output.clone(), release, aggressive, emit_ir, target, verbose)?;
+        build_native(path, output.clone(), release, aggressive, emit_ir, target, contracts, debug, verbose)?;
 
         // Then build WASM
         if verbose {
             println!("\n=== WASM Build ===");
         }
-        build_wasm(path, None, wasm_target, verbose)?;
+        build_wasm(path, None, wasm_target, mir_opt_level, verbose)?;
 
         if verbose {
             println!("\n=== All targets built successfully! ===");
         }
-        return Ok(());
-    }
-
-    // If emitting WASM, use the WASM code generator
-    if emit_wasm {
-        return build_wasm(path, output, wasm_target, verbose);
+    } else if emit_wasm {
+        // If emitting WASM, use the WASM code generator
+        build_wasm(path, output, wasm_target, mir_opt_level, verbose)?;
+    } else {
+        // Default: build native
+        build_native(path, output, release, aggressive, emit_ir, target, contracts, debug, verbose)?;
     }
-
-    // Default: build native
-    build_native(path, output, release, aggressive, emit_ir, target, verbose)
+    Ok(CliOutcome::new("build"))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_native(
     path: &Path,
     output: Option<PathBuf>,
@@ -445,12 +798,16 @@ fn build_native(
     aggressive: bool,
     emit_ir: bool,
     target: Option<&str>,
+    contracts: bmb::mir::ContractMode,
+    debug: bool,
     verbose: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use bmb::build::{BuildConfig, OptLevel};
 
     let mut config = BuildConfig::new(path.to_path_buf())
         .emit_ir(emit_ir)
+        .contracts(contracts)
+        .debug_info(debug)
         .verbose(verbose);
 
     // v0.50.23: Cross-compilation target
@@ -471,7 +828,16 @@ fn build_native(
         config = config.opt_level(OptLevel::Release);
     }
 
-    bmb::build::build(&config)?;
+    if let Err(e) = bmb::build::build(&config) {
+        if let bmb::build::BuildError::Compile(error) = e {
+            let source = std::fs::read_to_string(path).unwrap_or_default();
+            return Err(compile_error(&path.display().to_string(), &source, error));
+        }
+        if let bmb::build::BuildError::InvalidTarget(message) = e {
+            return Err(Box::new(UsageError(message)));
+        }
+        return Err(e.into());
+    }
 
     if !emit_ir {
         if is_human_output() {
@@ -479,8 +845,10 @@ fn build_native(
                 println!("Build complete: {}", config.output.display());
             }
         } else {
-            println!(r#"{{"type":"build_success","output":"{}"}}"#,
-                config.output.display().to_string().replace('\\', "\\\\"));
+            println!("{}", serde_json::json!({
+                "type": "build_success",
+                "output": config.output.display().to_string(),
+            }));
         }
     }
 
@@ -491,6 +859,7 @@ fn build_wasm(
     path: &PathBuf,
     output: Option<PathBuf>,
     wasm_target: &str,
+    opt_level: bmb::mir::OptLevel,
     verbose: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use bmb::cfg::{CfgEvaluator, Target};
@@ -504,10 +873,12 @@ fn build_wasm(
     }
 
     // Tokenize
-    let tokens = bmb::lexer::tokenize(&source)?;
+    let tokens = bmb::lexer::tokenize(&source)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // Parse
-    let ast = bmb::parser::parse(&filename, &source, tokens)?;
+    let ast = bmb::parser::parse(&filename, &source, tokens)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     if verbose {
         println!("  Parsed {} items", ast.items.len());
@@ -523,10 +894,13 @@ fn build_wasm(
 
     // Type check
     let mut checker = bmb::types::TypeChecker::new();
-    checker.check_program(&ast)?;
+    checker
+        .check_program(&ast)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // Lower to MIR
-    let mir = bmb::mir::lower_program(&ast);
+    let mut mir = bmb::mir::lower_program(&ast);
+    bmb::mir::OptimizationPipeline::for_level(opt_level).optimize(&mut mir);
 
     // Parse WASM target
     let target = match wasm_target {
@@ -558,17 +932,25 @@ fn build_wasm(
             println!("  Size: {} bytes", wat.len());
         }
     } else {
-        println!(r#"{{"type":"build_success","output":"{}","target":"{:?}","size":{}}}"#,
-            output_path.display().to_string().replace('\\', "\\\\"), target, wat.len());
+        println!("{}", serde_json::json!({
+            "type": "build_success",
+            "output": output_path.display().to_string(),
+            "target": format!("{:?}", target),
+            "size": wat.len(),
+        }));
     }
 
     Ok(())
 }
 
 /// v0.21.2: Emit MIR output for bootstrap comparison
+/// (or, with `as_cfg`, its control-flow graphs as Graphviz DOT)
 fn emit_mir_file(
     path: &PathBuf,
     output: Option<PathBuf>,
+    opt_level: bmb::mir::OptLevel,
+    contracts: bmb::mir::ContractMode,
+    as_cfg: bool,
     verbose: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let source = std::fs::read_to_string(path)?;
@@ -579,10 +961,12 @@ fn emit_mir_file(
     }
 
     // Tokenize
-    let tokens = bmb::lexer::tokenize(&source)?;
+    let tokens = bmb::lexer::tokenize(&source)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // Parse
-    let ast = bmb::parser::parse(&filename, &source, tokens)?;
+    let ast = bmb::parser::parse(&filename, &source, tokens)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     if verbose {
         println!("  Parsed {} items", ast.items.len());
@@ -590,17 +974,29 @@ fn emit_mir_file(
 
     // Type check
     let mut checker = bmb::types::TypeChecker::new();
-    checker.check_program(&ast)?;
+    checker
+        .check_program(&ast)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // Lower to MIR
-    let mir = bmb::mir::lower_program(&ast);
+    let mut mir = bmb::mir::lower_program_with_contracts(&ast, contracts);
+
+    // Apply the MIR optimizations of the requested level (none for debug)
+    let stats = bmb::mir::OptimizationPipeline::for_level(opt_level).optimize(&mut mir);
+    if verbose && !stats.pass_counts.is_empty() {
+        println!("  MIR optimizations applied: {:?}", stats.pass_counts);
+    }
 
-    // Format MIR as text
-    let mir_text = bmb::mir::format_mir(&mir);
+    // Format MIR as text, or as one DOT digraph per function
+    let mir_text = if as_cfg {
+        bmb::mir::format_cfg_dot(&mir)
+    } else {
+        bmb::mir::format_mir(&mir)
+    };
 
     // Determine output path
     let output_path = output.unwrap_or_else(|| {
-        path.with_extension("mir")
+        path.with_extension(if as_cfg { "cfg.dot" } else { "mir" })
     });
 
     // Write output
@@ -613,17 +1009,57 @@ fn emit_mir_file(
             println!("  Size: {} bytes", mir_text.len());
         }
     } else {
-        println!(r#"{{"type":"build_success","output":"{}","functions":{},"size":{}}}"#,
-            output_path.display().to_string().replace('\\', "\\\\"), mir.functions.len(), mir_text.len());
+        println!("{}", serde_json::json!({
+            "type": "build_success",
+            "output": output_path.display().to_string(),
+            "functions": mir.functions.len(),
+            "size": mir_text.len(),
+        }));
     }
 
     Ok(())
 }
 
+/// Run through MIR and the LLVM JIT, exiting with main's return value.
+/// Falls back to the interpreter when LLVM is not available.
+fn run_jit_file(path: &Path, extra_args: &[String], overflow: OverflowMode) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    use bmb::cfg::{CfgEvaluator, Target};
+    use bmb::jit::JitError;
+
+    let source = std::fs::read_to_string(path)?;
+    let filename = path.display().to_string();
+
+    let tokens = bmb::lexer::tokenize(&source)
+        .map_err(|e| compile_error(&filename, &source, e))?;
+    let ast = bmb::parser::parse(&filename, &source, tokens)
+        .map_err(|e| compile_error(&filename, &source, e))?;
+    let ast = CfgEvaluator::new(Target::Native).filter_program(&ast);
+
+    let mut checker = bmb::types::TypeChecker::new();
+    checker
+        .check_program(&ast)
+        .map_err(|e| compile_error(&filename, &source, e))?;
+
+    let mut mir = bmb::mir::lower_program(&ast);
+    bmb::mir::OptimizationPipeline::for_level(bmb::mir::OptLevel::Release).optimize(&mut mir);
+
+    let mut program_args = vec![filename];
+    program_args.extend(extra_args.iter().cloned());
+
+    match bmb::jit::run(&mir, &program_args) {
+        Ok(code) => std::process::exit(code as i32),
+        Err(JitError::LlvmNotAvailable) => {
+            eprintln!("Warning: --jit requires LLVM (build with --features llvm); falling back to the interpreter");
+            run_file(path, extra_args, overflow)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// v0.30.241: Stack size for interpreter thread (64MB for deep recursion in bootstrap)
 const INTERPRETER_STACK_SIZE: usize = 64 * 1024 * 1024;
 
-fn run_file(path: &Path, extra_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+fn run_file(path: &Path, extra_args: &[String], overflow: OverflowMode) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     // v0.30.241: Run entire pipeline in a thread with larger stack to prevent overflow
     // Bootstrap files have deep recursion that exceeds default 1MB Windows stack
     // We run everything in the thread because Value uses Rc<RefCell<>> (not Send)
@@ -641,154 +1077,288 @@ fn run_file(path: &Path, extra_args: &[String]) -> Result<(), Box<dyn std::error
             // v0.46: Set program arguments in thread-local storage
             bmb::interp::set_program_args(program_args);
 
-            let source = std::fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
             let filename = path.display().to_string();
+            let source = std::fs::read_to_string(&path).map_err(|e| {
+                let message = format!("Failed to read file: {}", e);
+                if is_human_output() {
+                    message
+                } else {
+                    let error = bmb::error::CompileError::io_error(message);
+                    bmb::error::DiagnosticReport::new(vec![error.to_diagnostic(&filename, "")]).to_string()
+                }
+            })?;
+
+            // Failures are formatted for the active output mode: a rendered
+            // snippet for --human, a structured JSON diagnostic otherwise
+            let failure = |diagnostic: bmb::error::Diagnostic| {
+                if is_human_output() {
+                    diagnostic.render(&source)
+                } else {
+                    bmb::error::DiagnosticReport::new(vec![diagnostic]).to_string()
+                }
+            };
+            let compile_failure = |e: bmb::error::CompileError| failure(e.to_diagnostic(&filename, &source));
 
             // Tokenize
             let tokens = bmb::lexer::tokenize(&source)
-                .map_err(|e| format!("Lexer error: {}", e))?;
+                .map_err(&compile_failure)?;
 
             // Parse
             let ast = bmb::parser::parse(&filename, &source, tokens)
-                .map_err(|e| format!("Parser error: {}", e))?;
+                .map_err(&compile_failure)?;
 
             // Type check first
             let mut checker = bmb::types::TypeChecker::new();
             checker.check_program(&ast)
-                .map_err(|e| format!("Type error: {}", e))?;
+                .map_err(&compile_failure)?;
 
             // Run with interpreter
             let mut interpreter = bmb::interp::Interpreter::new();
+            interpreter.set_overflow_checks(overflow == OverflowMode::Check);
+            interpreter.set_int_op_types(checker.int_op_types().clone());
             interpreter.load(&ast);
-            interpreter.run(&ast)
-                .map_err(|e| format!("Runtime error: {}", e.message))?;
+            interpreter.run(&ast).map_err(|e| failure(e.to_diagnostic(&filename, &source)))?;
 
             Ok(())
         })?;
 
     match handle.join() {
-        Ok(Ok(_)) => Ok(()),
+        Ok(Ok(_)) => Ok(CliOutcome::new("run")),
         Ok(Err(e)) => {
             if is_human_output() {
                 eprintln!("{}", e);
             } else {
-                println!(r#"{{"type":"error","message":"{}"}}"#, e.to_string().replace('"', "\\\""));
+                println!("{}", e);
             }
-            std::process::exit(1);
+            Err(Box::new(Reported))
         }
         Err(_) => {
             if is_human_output() {
                 eprintln!("Runtime error: interpreter thread panicked");
             } else {
-                println!(r#"{{"type":"error","message":"interpreter thread panicked"}}"#);
+                bmb::error::print_diagnostics(vec![bmb::error::Diagnostic::error("interpreter thread panicked")]);
             }
-            std::process::exit(1);
+            Ok(CliOutcome::new("run").with_status(ExitStatus::Internal))
         }
     }
 }
 
-fn start_repl() -> Result<(), Box<dyn std::error::Error>> {
+fn start_repl() -> Result<CliOutcome, Box<dyn std::error::Error>> {
     let mut repl = bmb::repl::Repl::new()?;
     repl.run()?;
-    Ok(())
+    Ok(CliOutcome::new("repl"))
 }
 
 /// v0.17: Check file with additional include paths for module resolution
-fn check_file_with_includes(path: &PathBuf, include_paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+/// Returns the number of warnings
+fn check_file_with_includes(path: &PathBuf, include_paths: &[PathBuf]) -> Result<usize, Box<dyn std::error::Error>> {
     let source = std::fs::read_to_string(path)?;
     let filename = path.display().to_string();
 
     // Tokenize
-    let tokens = bmb::lexer::tokenize(&source)?;
+    let tokens = bmb::lexer::tokenize(&source)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // Parse
-    let ast = bmb::parser::parse(&filename, &source, tokens)?;
+    let ast = bmb::parser::parse(&filename, &source, tokens)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // v0.17: Create type checker and register imported modules
-    let mut checker = bmb::types::TypeChecker::new();
-
-    // Resolve use statements and register imported modules
-    let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
-    let mut resolver = bmb::resolver::Resolver::new(base_dir);
+    // Recover after per-function errors so every error is reported in one run
+    let mut checker = bmb::types::TypeChecker::new().recover_errors();
 
-    // Also try include paths for module resolution
+    // Resolve use statements against the file's directory, its project
+    // (bmb.toml source root and dependencies) and any -I paths
+    let mut resolver = bmb::resolver::Resolver::for_file(path)?;
     for include_path in include_paths {
-        // Try loading modules from include paths
-        for item in &ast.items {
-            if let bmb::ast::Item::Use(use_stmt) = item
-                && !use_stmt.path.is_empty()
-            {
-                let module_name = &use_stmt.path[0].node;
-                // Convert underscore to hyphen for package names (bmb_option -> bmb-option)
-                let pkg_dir_name = module_name.replace('_', "-");
-                let module_path = include_path.join(&pkg_dir_name).join("src").join("lib.bmb");
-                if module_path.exists() {
-                    // Load using the original filename convention
-                    let lib_source = std::fs::read_to_string(&module_path)?;
-                    let lib_tokens = bmb::lexer::tokenize(&lib_source)?;
-                    let lib_ast = bmb::parser::parse(&module_path.display().to_string(), &lib_source, lib_tokens)?;
-                    // Create a temporary module to register
-                    let module = bmb::resolver::Module {
-                        name: module_name.clone(),
-                        path: module_path.clone(),
-                        program: lib_ast,
-                        exports: std::collections::HashMap::new(), // Not needed for type registration
-                    };
-                    checker.register_module(&module);
-                }
-            }
-        }
+        resolver.add_include_path(include_path);
     }
 
-    // Also resolve from the file's own directory
     // v0.68: Propagate resolver errors (includes module name suggestions)
     // v0.74: Make imports mutable for usage tracking
-    let mut imports = resolver.resolve_uses(&ast)?;
-    for (_, info) in imports.all_imports() {
-        if let Some(module) = resolver.get_module(&info.module) {
-            checker.register_module(module);
-        }
-    }
+    let mut imports = resolver
+        .resolve_uses(&ast)
+        .map_err(|e| compile_error(&filename, &source, e))?;
+    checker.register_imports(&resolver, &imports);
 
     // Type check
     // v0.74: Pass imports for usage tracking
-    checker.check_program_with_imports(&ast, &mut imports)?;
+    checker
+        .check_program_with_imports(&ast, &mut imports)
+        .map_err(|e| compile_error(&filename, &source, e))?;
 
     // v0.74: Collect unused import warnings
     let mut all_warnings: Vec<bmb::error::CompileWarning> = checker.warnings().to_vec();
-    for (name, span) in imports.get_unused() {
-        all_warnings.push(bmb::error::CompileWarning::unused_import(name, span));
-    }
+    all_warnings.extend(imports.warnings());
 
     // v0.47: Report warnings (non-fatal diagnostics)
     // v0.71: Default machine output, --human for human-readable
     let warnings = &all_warnings;
+    let errors = checker.take_errors();
+
+    // Machine output: warnings and errors share one diagnostics envelope
+    if !is_human_output() {
+        if !warnings.is_empty() || !errors.is_empty() {
+            let diagnostics = warnings
+                .iter()
+                .map(|w| w.to_diagnostic(&filename, &source))
+                .chain(errors.iter().map(|e| e.to_diagnostic(&filename, &source)))
+                .collect();
+            bmb::error::print_diagnostics(diagnostics);
+        }
+        if !errors.is_empty() {
+            return Err(Box::new(Reported));
+        }
+        println!("{}", serde_json::json!({
+            "type": "success",
+            "file": filename,
+            "warnings": warnings.len(),
+        }));
+        return Ok(warnings.len());
+    }
+
     if !warnings.is_empty() {
-        if is_human_output() {
-            for warning in warnings {
-                bmb::error::report_warning(&filename, &source, warning);
+        for warning in warnings {
+            bmb::error::report_warning(&filename, &source, warning);
+        }
+        println!("  {} warning(s) generated", warnings.len());
+    }
+
+    if !errors.is_empty() {
+        for error in &errors {
+            bmb::error::report_error(&filename, &source, error);
+        }
+        println!("  {} error(s) generated", errors.len());
+        return Err(Box::new(Reported));
+    }
+
+    println!("✓ {} type checks successfully", filename);
+    Ok(warnings.len())
+}
+
+/// Type check a file, or every .bmb file under a directory
+fn check_path(path: &Path, include_paths: &[PathBuf]) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let files = if path.is_dir() {
+        let mut files = collect_bmb_files(path)?;
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut outcome = CliOutcome::new("check");
+    let (mut failed, mut warnings) = (0, 0);
+    for file in &files {
+        match check_file_with_includes(file, include_paths) {
+            Ok(count) => warnings += count,
+            Err(e) => {
+                print_error(e.as_ref());
+                outcome = outcome.with_status(ExitStatus::of_error(e.as_ref()));
+                failed += 1;
             }
-            println!("  {} warning(s) generated", warnings.len());
-        } else {
-            bmb::error::report_warnings_machine(&filename, &source, warnings);
         }
     }
+    Ok(outcome.count("files", files.len()).count("failed", failed).count("warnings", warnings))
+}
 
-    if is_human_output() {
-        println!("✓ {} type checks successfully", filename);
+/// `bmb check --watch`: check `path`, then check it again whenever a .bmb
+/// file in its directory tree or under an include path changes. Each run
+/// ends with its own summary; failed checks don't stop the watcher.
+fn watch_check(path: &Path, include_paths: &[PathBuf], debounce_ms: u64) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+    use std::collections::HashMap;
+    use std::sync::mpsc::channel;
+    use std::time::{Duration, SystemTime};
+
+    let run = |changed: &[PathBuf]| {
+        if is_human_output() {
+            // Clear the screen so only the latest run is visible
+            print!("\x1B[2J\x1B[H");
+            if !changed.is_empty() {
+                let names: Vec<_> = changed.iter().map(|p| p.display().to_string()).collect();
+                println!("changed: {}", names.join(", "));
+            }
+        } else if !changed.is_empty() {
+            println!("{}", serde_json::json!({
+                "type": "changed",
+                "files": changed.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+            }));
+        }
+        match check_path(path, include_paths) {
+            Ok(outcome) => outcome.emit(is_human_output()),
+            Err(e) => print_error(e.as_ref()),
+        }
+        if is_human_output() {
+            println!("\nWatching {} for changes... (Press Ctrl+C to stop)", path.display());
+        }
+    };
+
+    let (tx, rx) = channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(debounce_ms), tx)?;
+    let root = if path.is_dir() {
+        path
     } else {
-        println!(r#"{{"type":"success","file":"{}","warnings":{}}}"#, filename, warnings.len());
+        path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."))
+    };
+    debouncer.watcher().watch(root, RecursiveMode::Recursive)?;
+    for include_path in include_paths {
+        debouncer.watcher().watch(include_path, RecursiveMode::Recursive)?;
     }
-    Ok(())
+
+    // Reading a file is an event too, so only files whose modification
+    // time moved count as changed
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    let mut seen: HashMap<PathBuf, Option<SystemTime>> = HashMap::new();
+    for dir in std::iter::once(root).chain(include_paths.iter().map(PathBuf::as_path)) {
+        for file in collect_bmb_files(dir).unwrap_or_default() {
+            let file = file.canonicalize().unwrap_or(file);
+            seen.insert(file.clone(), modified(&file));
+        }
+    }
+
+    run(&[]);
+    loop {
+        match rx.recv() {
+            Ok(Ok(events)) => {
+                let mut changed: Vec<PathBuf> = events
+                    .into_iter()
+                    .map(|e| e.path.canonicalize().unwrap_or(e.path))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "bmb"))
+                    .collect();
+                changed.sort();
+                changed.dedup();
+                changed.retain(|p| {
+                    let time = modified(p);
+                    seen.insert(p.clone(), time) != Some(time)
+                });
+                if !changed.is_empty() {
+                    run(&changed);
+                }
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {}", e),
+            Err(e) => {
+                eprintln!("Channel error: {}", e);
+                break;
+            }
+        }
+    }
+
+    Ok(CliOutcome::new("check"))
 }
 
 /// Lint a BMB source file or directory (v0.45)
-/// Collects and reports all warnings from type checking
-fn lint_file(path: &PathBuf, strict: bool, include_paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+/// Collects the warnings from type checking and applies the lint config
+fn lint_file(path: &PathBuf, strict: bool, include_paths: &[PathBuf], lint_config: Option<&Path>) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    use bmb::lint::LintConfig;
+
+    let config = match lint_config {
+        Some(config_path) => LintConfig::load(config_path)?,
+        None if path.is_dir() => LintConfig::discover(path)?,
+        None => LintConfig::discover(path.parent().unwrap_or(Path::new(".")))?,
+    };
+
     // Handle directory recursively
     if path.is_dir() {
-        return lint_directory(path, strict, include_paths);
+        return lint_directory(path, strict, include_paths, &config);
     }
 
     let source = std::fs::read_to_string(path)?;
@@ -797,124 +1367,128 @@ fn lint_file(path: &PathBuf, strict: bool, include_paths: &[PathBuf]) -> Result<
     // Tokenize
     let tokens = match bmb::lexer::tokenize(&source) {
         Ok(t) => t,
-        Err(e) => {
-            bmb::error::report_error(&filename, &source, &e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(compile_error(&filename, &source, e)),
     };
 
     // Parse
     let ast = match bmb::parser::parse(&filename, &source, tokens) {
         Ok(a) => a,
-        Err(e) => {
-            bmb::error::report_error(&filename, &source, &e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(compile_error(&filename, &source, e)),
     };
 
     // Create type checker
     let mut checker = bmb::types::TypeChecker::new();
 
-    // Resolve use statements and register imported modules
-    let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
-    let mut resolver = bmb::resolver::Resolver::new(base_dir);
-
-    // Try include paths for module resolution
+    // Resolve use statements against the file's directory, its project
+    // and any -I paths
+    let mut resolver = bmb::resolver::Resolver::for_file(path)?;
     for include_path in include_paths {
-        for item in &ast.items {
-            if let bmb::ast::Item::Use(use_stmt) = item
-                && !use_stmt.path.is_empty()
-            {
-                let module_name = &use_stmt.path[0].node;
-                let pkg_dir_name = module_name.replace('_', "-");
-                let module_path = include_path.join(&pkg_dir_name).join("src").join("lib.bmb");
-                if module_path.exists()
-                    && let Ok(lib_source) = std::fs::read_to_string(&module_path)
-                    && let Ok(lib_tokens) = bmb::lexer::tokenize(&lib_source)
-                    && let Ok(lib_ast) = bmb::parser::parse(
-                        &module_path.display().to_string(),
-                        &lib_source,
-                        lib_tokens,
-                    )
-                {
-                    let module = bmb::resolver::Module {
-                        name: module_name.clone(),
-                        path: module_path.clone(),
-                        program: lib_ast,
-                        exports: std::collections::HashMap::new(),
-                    };
-                    checker.register_module(&module);
-                }
-            }
-        }
+        resolver.add_include_path(include_path);
     }
 
-    // Resolve from file's directory
-    let mut imports = resolver.resolve_uses(&ast)?;
-    for (_, info) in imports.all_imports() {
-        if let Some(module) = resolver.get_module(&info.module) {
-            checker.register_module(module);
-        }
-    }
+    let mut imports = resolver
+        .resolve_uses(&ast)
+        .map_err(|e| compile_error(&filename, &source, e))?;
+    checker.register_imports(&resolver, &imports);
 
     // Type check (continue even with errors to collect all warnings)
     let type_result = checker.check_program_with_imports(&ast, &mut imports);
 
-    // Collect all warnings
+    // Collect all warnings and sort them by their lint level
     let mut all_warnings: Vec<bmb::error::CompileWarning> = checker.warnings().to_vec();
-    for (name, span) in imports.get_unused() {
-        all_warnings.push(bmb::error::CompileWarning::unused_import(name, span));
-    }
+    all_warnings.extend(imports.warnings());
+    let linted = config.apply(&ast, all_warnings);
+    let by_rule = linted.counts_by_rule();
 
     // Report type errors if any
     if let Err(e) = type_result {
-        bmb::error::report_error(&filename, &source, &e);
         // Still report warnings before returning error
-        if !all_warnings.is_empty() {
-            if is_human_output() {
+        if !is_human_output() {
+            let mut diagnostics = lint_diagnostics(&filename, &source, &linted);
+            diagnostics.push(e.to_diagnostic(&filename, &source));
+            bmb::error::print_diagnostics(diagnostics);
+        } else {
+            bmb::error::report_error(&filename, &source, &e);
+            if !by_rule.is_empty() {
                 println!("\n  Warnings:");
-                for warning in &all_warnings {
-                    bmb::error::report_warning(&filename, &source, warning);
+                for diagnostic in lint_diagnostics(&filename, &source, &linted) {
+                    println!("{}", diagnostic.render(&source));
                 }
-            } else {
-                bmb::error::report_warnings_machine(&filename, &source, &all_warnings);
             }
         }
-        return Err(e.into());
+        return Ok(lint_outcome(1, &linted, 1, strict, &by_rule));
     }
 
     // Report warnings
-    let warning_count = all_warnings.len();
-    if warning_count > 0 {
+    let warning_count = linted.warnings.len();
+    let denied_count = linted.denied.len();
+    if !by_rule.is_empty() {
         if is_human_output() {
-            for warning in &all_warnings {
-                bmb::error::report_warning(&filename, &source, warning);
+            for diagnostic in lint_diagnostics(&filename, &source, &linted) {
+                println!("{}", diagnostic.render(&source));
             }
             println!("\n  {} warning(s) in {}", warning_count, filename);
+            if denied_count > 0 {
+                println!("  {} error(s) from denied lints", denied_count);
+            }
+            for (rule, count) in &by_rule {
+                println!("    {}: {}", rule, count);
+            }
         } else {
-            bmb::error::report_warnings_machine(&filename, &source, &all_warnings);
+            bmb::error::print_diagnostics(lint_diagnostics(&filename, &source, &linted));
         }
     } else if is_human_output() {
         println!("✓ {} - no warnings", filename);
     } else {
-        println!(r#"{{"type":"lint","file":"{}","warnings":0}}"#, filename);
+        println!("{}", serde_json::json!({ "type": "lint", "file": filename, "warnings": 0 }));
     }
 
-    // In strict mode, any warning is an error
-    if strict && warning_count > 0 {
-        if is_human_output() {
+    if is_human_output() {
+        if denied_count > 0 {
+            eprintln!("\n  Lint failed: {} denied warning(s)", denied_count);
+        } else if strict && warning_count > 0 {
+            // In strict mode, any warning is an error
             eprintln!("\n  Lint failed: {} warning(s) in strict mode", warning_count);
         }
-        std::process::exit(1);
     }
 
-    Ok(())
+    Ok(lint_outcome(1, &linted, 0, strict, &by_rule))
 }
 
-/// Lint all .bmb files in a directory recursively (v0.45)
-fn lint_directory(dir: &PathBuf, strict: bool, _include_paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
-    let mut total_warnings = 0;
-    let mut total_files = 0;
+/// Diagnostics for linted warnings, denied ones as errors
+fn lint_diagnostics(filename: &str, source: &str, linted: &bmb::lint::Linted) -> Vec<bmb::error::Diagnostic> {
+    let index = bmb::ast::LineIndex::new(source);
+    let warnings = linted.warnings.iter().map(|w| w.to_diagnostic_with(filename, &index));
+    let denied = linted.denied.iter().map(|w| bmb::error::Diagnostic {
+        severity: bmb::error::Severity::Error,
+        ..w.to_diagnostic_with(filename, &index)
+    });
+    warnings.chain(denied).collect()
+}
+
+/// Outcome of linting `files` files, with a count per reported rule. Denied
+/// warnings always fail the run; in strict mode any warning does.
+fn lint_outcome(
+    files: usize,
+    linted: &bmb::lint::Linted,
+    failed: usize,
+    strict: bool,
+    by_rule: &std::collections::BTreeMap<&'static str, usize>,
+) -> CliOutcome {
+    let (warnings, denied) = (linted.warnings.len(), linted.denied.len());
+    let outcome = CliOutcome::new("lint")
+        .count("files", files)
+        .count("warnings", warnings)
+        .count("denied", denied)
+        .count("failed", failed)
+        .failed_if(failed > 0 || denied > 0 || (strict && warnings > 0));
+    by_rule.iter().fold(outcome, |outcome, (&rule, &count)| outcome.count(rule, count))
+}
+
+/// Lint all .bmb files in a directory recursively (v0.45)
+fn lint_directory(dir: &PathBuf, strict: bool, include_paths: &[PathBuf], config: &bmb::lint::LintConfig) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let mut total = bmb::lint::Linted::default();
+    let mut total_files = 0;
     let mut failed_files = 0;
 
     // Collect all .bmb files
@@ -969,33 +1543,36 @@ fn lint_directory(dir: &PathBuf, strict: bool, _include_paths: &[PathBuf]) -> Re
 
         // Type check
         let mut checker = bmb::types::TypeChecker::new();
-        let base_dir = file.parent().unwrap_or(std::path::Path::new("."));
-        let mut resolver = bmb::resolver::Resolver::new(base_dir);
+        let Ok(mut resolver) = bmb::resolver::Resolver::for_file(file) else {
+            failed_files += 1;
+            continue;
+        };
+        for include_path in include_paths {
+            resolver.add_include_path(include_path);
+        }
 
         // Resolve imports
         if let Ok(mut imports) = resolver.resolve_uses(&ast) {
-            for (_, info) in imports.all_imports() {
-                if let Some(module) = resolver.get_module(&info.module) {
-                    checker.register_module(module);
-                }
-            }
+            checker.register_imports(&resolver, &imports);
 
             if checker.check_program_with_imports(&ast, &mut imports).is_ok() {
                 let mut warnings: Vec<bmb::error::CompileWarning> = checker.warnings().to_vec();
-                for (name, span) in imports.get_unused() {
-                    warnings.push(bmb::error::CompileWarning::unused_import(name, span));
-                }
+                warnings.extend(imports.warnings());
+                let linted = config.apply(&ast, warnings);
 
-                if !warnings.is_empty() {
-                    total_warnings += warnings.len();
+                let diagnostics = lint_diagnostics(&filename, &source, &linted);
+                if !diagnostics.is_empty() {
                     if is_human_output() {
-                        for warning in &warnings {
-                            bmb::error::report_warning(&filename, &source, warning);
+                        for diagnostic in &diagnostics {
+                            println!("{}", diagnostic.render(&source));
                         }
                     } else {
-                        bmb::error::report_warnings_machine(&filename, &source, &warnings);
+                        bmb::error::print_diagnostics(diagnostics);
                     }
                 }
+                total.warnings.extend(linted.warnings);
+                total.denied.extend(linted.denied);
+                total.allowed += linted.allowed;
             } else {
                 failed_files += 1;
             }
@@ -1003,30 +1580,43 @@ fn lint_directory(dir: &PathBuf, strict: bool, _include_paths: &[PathBuf]) -> Re
     }
 
     // Summary
+    let by_rule = total.counts_by_rule();
     if is_human_output() {
         println!("\nLint summary:");
         println!("  Files checked: {}", total_files);
-        println!("  Total warnings: {}", total_warnings);
+        println!("  Total warnings: {}", total.warnings.len());
+        if !total.denied.is_empty() {
+            println!("  Denied: {}", total.denied.len());
+        }
+        for (rule, count) in &by_rule {
+            println!("    {}: {}", rule, count);
+        }
         if failed_files > 0 {
             println!("  Failed to lint: {}", failed_files);
         }
-    } else {
-        println!(r#"{{"type":"lint_summary","files":{},"warnings":{},"errors":{}}}"#,
-            total_files, total_warnings, failed_files);
-    }
-
-    // In strict mode, any warning is an error
-    if strict && total_warnings > 0 {
-        if is_human_output() {
-            eprintln!("\nLint failed: {} warning(s) in strict mode", total_warnings);
+        if !total.denied.is_empty() {
+            eprintln!("\nLint failed: {} denied warning(s)", total.denied.len());
+        } else if strict && !total.warnings.is_empty() {
+            eprintln!("\nLint failed: {} warning(s) in strict mode", total.warnings.len());
         }
-        std::process::exit(1);
     }
 
-    Ok(())
+    Ok(lint_outcome(total_files, &total, failed_files, strict, &by_rule))
 }
 
-fn verify_file(path: &PathBuf, z3_path: &str, timeout: u32) -> Result<(), Box<dyn std::error::Error>> {
+#[allow(clippy::too_many_arguments)]
+fn verify_file(
+    path: &PathBuf,
+    solver: SolverKind,
+    solver_path: Option<&str>,
+    timeout: u32,
+    emit_tests: Option<&PathBuf>,
+    check_bounds: bool,
+    check_division: bool,
+    use_cache: bool,
+    functions: &[String],
+    json: bool,
+) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     use bmb::index::{ProofEntry, ProofIndex, ProofStatus, write_proof_index};
     use bmb::smt::VerifyResult;
 
@@ -1039,38 +1629,76 @@ fn verify_file(path: &PathBuf, z3_path: &str, timeout: u32) -> Result<(), Box<dy
     // Parse
     let ast = bmb::parser::parse(&filename, &source, tokens)?;
 
+    // Every selected function must exist
+    let names: Vec<&str> = ast.items.iter()
+        .filter_map(|item| match item {
+            bmb::ast::Item::FnDef(func) => Some(func.name.node.as_str()),
+            _ => None,
+        })
+        .collect();
+    if let Some(name) = functions.iter().find(|name| !names.contains(&name.as_str())) {
+        let hint = match bmb::types::find_similar_name(name, &names, 2) {
+            Some(similar) => format!("\n  hint: did you mean `{}`?", similar),
+            None => String::new(),
+        };
+        return Err(Box::new(UsageError(format!("no function named `{}` in {}{}", name, filename, hint))));
+    }
+
     // Type check first
     let mut checker = bmb::types::TypeChecker::new();
     checker.check_program(&ast)?;
 
     // Set up verifier
-    let verifier = bmb::verify::ContractVerifier::new()
-        .with_z3_path(z3_path)
-        .with_timeout(timeout);
+    let backend = bmb::smt::SolverBackend::from(solver);
+    let solver_path = solver_path.unwrap_or(backend.default_path());
+    let mut verifier = bmb::verify::ContractVerifier::new()
+        .with_solver(backend)
+        .with_solver_path(solver_path)
+        .with_timeout(timeout)
+        .with_bounds_checking(check_bounds)
+        .with_division_checking(check_division)
+        .with_refinement_obligations(checker.refinement_obligations().to_vec());
+    if !functions.is_empty() {
+        verifier = verifier.with_functions(functions);
+    }
 
     // Check if solver is available
-    let z3_available = verifier.is_solver_available();
-    if !z3_available {
+    let solver_available = verifier.is_solver_available();
+    if !solver_available {
         if is_human_output() {
-            eprintln!("Warning: Z3 solver not found at '{}'. Install Z3 or specify --z3-path.", z3_path);
+            eprintln!(
+                "Warning: {} solver not found at '{}'. Install {} or specify --solver-path.",
+                backend, solver_path, backend
+            );
             eprintln!("Skipping contract verification.");
         } else {
-            println!(r#"{{"type":"verify_skip","reason":"z3_not_found"}}"#);
+            println!(r#"{{"type":"verify_skip","reason":"{}_not_found"}}"#, backend.default_path());
         }
-        return Ok(());
+        return Ok(CliOutcome::new("verify"));
     }
 
-    // Get Z3 version if available
-    let z3_version = get_z3_version(z3_path);
+    let solver_version = verifier.solver_version();
 
-    // Verify contracts
+    // Verify contracts, skipping functions unchanged since the cached run
+    let current_dir = std::env::current_dir()?;
     let start_time = std::time::Instant::now();
-    let report = verifier.verify_program(&ast);
+    let report = if use_cache {
+        let mut cache = bmb::verify::VerifyCache::load(&current_dir, path);
+        let report = verifier.verify_program_cached(&ast, &source, &mut cache);
+        if let Err(e) = cache.save(&current_dir, path)
+            && is_human_output()
+        {
+            eprintln!("Warning: Could not save verification cache: {}", e);
+        }
+        report
+    } else {
+        verifier.verify_program(&ast)
+    };
     let verify_time_ms = start_time.elapsed().as_millis() as u64;
 
     // v0.50.24: Create proof index entries from verification report
-    let mut proof_index = ProofIndex::new(z3_available, z3_version);
-    for func_report in &report.functions {
+    let mut proof_index = ProofIndex::new(solver_available, solver_version);
+    for func_report in report.functions.iter().filter(|f| !f.skipped) {
         let pre_status = func_report.pre_result.as_ref().map(|r| match r {
             VerifyResult::Verified => ProofStatus::Verified,
             VerifyResult::Failed(_) => ProofStatus::Failed,
@@ -1117,60 +1745,212 @@ fn verify_file(path: &PathBuf, z3_path: &str, timeout: u32) -> Result<(), Box<dy
     }
 
     // Save proof index to .bmb/index/proofs.json
-    let current_dir = std::env::current_dir()?;
-    if let Err(e) = write_proof_index(&proof_index, &current_dir) {
-        if is_human_output() {
-            eprintln!("Warning: Could not save proof index: {}", e);
-        }
+    if let Err(e) = write_proof_index(&proof_index, &current_dir)
+        && is_human_output()
+    {
+        eprintln!("Warning: Could not save proof index: {}", e);
     }
 
     // Print report
-    if is_human_output() {
+    if json {
+        println!("{}", verify_report_json(&report, &ast, &source, &filename));
+    } else if is_human_output() {
         print!("{}", report);
     } else {
-        let verified = report.verified_count();
-        let failed = report.failed_count();
-        let total = verified + failed;
-        println!(r#"{{"type":"verify_result","total":{},"verified":{},"failed":{}}}"#,
-            total, verified, failed);
+        let line_index = bmb::ast::LineIndex::new(&source);
+        for func_report in &report.functions {
+            for (span, index, result) in &func_report.bounds_results {
+                let VerifyResult::Failed(ce) = result else { continue };
+                let bmb::ast::LineCol { line, col } = line_index.line_col(span.start);
+                let counterexample: serde_json::Map<_, _> = ce.assignments.iter()
+                    .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                    .collect();
+                let record = serde_json::json!({
+                    "type": "bounds_violation",
+                    "function": func_report.name,
+                    "index": index,
+                    "file": filename,
+                    "span": { "start": span.start, "end": span.end },
+                    "line": line,
+                    "col": col,
+                    "counterexample": counterexample,
+                });
+                println!("{}", record);
+            }
+            for (span, division, result) in &func_report.division_results {
+                let VerifyResult::Failed(ce) = result else { continue };
+                let bmb::ast::LineCol { line, col } = line_index.line_col(span.start);
+                let counterexample: serde_json::Map<_, _> = ce.assignments.iter()
+                    .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                    .collect();
+                let record = serde_json::json!({
+                    "type": "division_by_zero",
+                    "function": func_report.name,
+                    "division": division,
+                    "file": filename,
+                    "span": { "start": span.start, "end": span.end },
+                    "line": line,
+                    "col": col,
+                    "counterexample": counterexample,
+                });
+                println!("{}", record);
+            }
+            for (span, check, result) in &func_report.loop_results {
+                let VerifyResult::Failed(ce) = result else { continue };
+                let bmb::ast::LineCol { line, col } = line_index.line_col(span.start);
+                let counterexample: serde_json::Map<_, _> = ce.assignments.iter()
+                    .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                    .collect();
+                let record = serde_json::json!({
+                    "type": "loop_invariant_violation",
+                    "function": func_report.name,
+                    "condition": check.name(),
+                    "file": filename,
+                    "span": { "start": span.start, "end": span.end },
+                    "line": line,
+                    "col": col,
+                    "counterexample": counterexample,
+                });
+                println!("{}", record);
+            }
+        }
     }
 
-    // Exit with error if any verification failed
-    if !report.all_verified() {
-        std::process::exit(1);
+    if let Some(dir) = emit_tests {
+        emit_counterexample_tests(dir, &ast, &source, &report)?;
     }
 
-    Ok(())
+    let skipped = report.skipped_count();
+    Ok(CliOutcome::new("verify")
+        .count("total", report.functions.len() - skipped)
+        .count("verified", report.verified_count())
+        .count("cached", report.cached_count())
+        .count("failed", report.failed_count())
+        .count("skipped", skipped)
+        .count("unknown", report.unknown_count())
+        .failed_if(!report.all_verified()))
 }
 
-/// Get Z3 version string
-fn get_z3_version(z3_path: &str) -> Option<String> {
-    use std::process::Command;
-    Command::new(z3_path)
-        .arg("--version")
-        .output()
-        .ok()
-        .and_then(|output| {
-            String::from_utf8(output.stdout).ok()
+/// The `verify --json` report: every function's verdict and checked
+/// conditions, with source lines for functions and spanned conditions
+fn verify_report_json(
+    report: &bmb::verify::VerificationReport,
+    ast: &bmb::ast::Program,
+    source: &str,
+    filename: &str,
+) -> serde_json::Value {
+    use std::collections::HashMap;
+
+    let line_index = bmb::ast::LineIndex::new(source);
+    let fn_spans: HashMap<&str, bmb::ast::Span> = ast.items.iter()
+        .filter_map(|item| match item {
+            bmb::ast::Item::FnDef(func) => Some((func.name.node.as_str(), func.name.span)),
+            _ => None,
+        })
+        .collect();
+    let functions: Vec<serde_json::Value> = report.entries().into_iter()
+        .map(|entry| {
+            let line = fn_spans.get(entry.name.as_str()).map(|span| line_index.line_col(span.start).line);
+            let mut value = serde_json::to_value(&entry).unwrap_or_default();
+            value["line"] = serde_json::json!(line);
+            if let Some(conditions) = value["conditions"].as_array_mut() {
+                for (condition, json) in entry.conditions.iter().zip(conditions) {
+                    if let Some(span) = condition.span {
+                        let bmb::ast::LineCol { line, col } = line_index.line_col(span.start);
+                        json["line"] = serde_json::json!(line);
+                        json["col"] = serde_json::json!(col);
+                    }
+                }
+            }
+            value
         })
-        .map(|s| s.trim().to_string())
+        .collect();
+    serde_json::json!({
+        "type": "verify_report",
+        "file": filename,
+        "ok": report.all_verified(),
+        "counts": {
+            "total": report.functions.len() - report.skipped_count(),
+            "verified": report.verified_count(),
+            "cached": report.cached_count(),
+            "failed": report.failed_count(),
+            "skipped": report.skipped_count(),
+            "unknown": report.unknown_count(),
+        },
+        "functions": functions,
+    })
+}
+
+/// Write regression tests for the counterexamples in `report` (`verify --emit-tests`)
+fn emit_counterexample_tests(
+    dir: &PathBuf,
+    ast: &bmb::ast::Program,
+    source: &str,
+    report: &bmb::verify::VerificationReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let generated = bmb::verify::generate_counterexample_tests(ast, source, report);
+    if !generated.tests.is_empty() {
+        std::fs::create_dir_all(dir)?;
+    }
+    for test in &generated.tests {
+        let path = dir.join(&test.file_name);
+        std::fs::write(&path, &test.source)?;
+        if is_human_output() {
+            println!("Wrote counterexample test for {}: {}", test.function, path.display());
+        } else {
+            let record = serde_json::json!({
+                "type": "counterexample_test",
+                "function": test.function,
+                "path": path.display().to_string(),
+            });
+            println!("{}", record);
+        }
+    }
+    for (function, reason) in &generated.skipped {
+        if is_human_output() {
+            println!("Skipped counterexample test for {}: {}", function, reason);
+        } else {
+            let record = serde_json::json!({
+                "type": "counterexample_skip",
+                "function": function,
+                "reason": reason,
+            });
+            println!("{}", record);
+        }
+    }
+    Ok(())
 }
 
-fn parse_file(path: &PathBuf, format: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn parse_file(path: &PathBuf, format: &str, item: Option<&str>) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     let source = std::fs::read_to_string(path)?;
     let filename = path.display().to_string();
 
     let tokens = bmb::lexer::tokenize(&source)?;
-    let ast = bmb::parser::parse(&filename, &source, tokens)?;
+    let mut ast = bmb::parser::parse(&filename, &source, tokens)?;
+
+    if let Some(name) = item {
+        let names: Vec<&str> = ast.items.iter().filter_map(|i| i.name()).collect();
+        if !names.contains(&name) {
+            let hint = match bmb::types::find_similar_name(name, &names, 2) {
+                Some(similar) => format!("\n  hint: did you mean `{}`?", similar),
+                None => String::new(),
+            };
+            return Err(Box::new(UsageError(format!("no item named `{}` in {}{}", name, filename, hint))));
+        }
+        ast.header = None;
+        ast.items.retain(|i| i.name() == Some(name));
+    }
 
     match format {
         "sexpr" | "s-expression" => println!("{}", bmb::ast::output::to_sexpr(&ast)),
         _ => println!("{}", serde_json::to_string_pretty(&ast)?),
     }
-    Ok(())
+    Ok(CliOutcome::new("parse").count("items", ast.items.len()))
 }
 
-fn tokenize_file(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn tokenize_file(path: &PathBuf) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    use bmb::lexer::Token;
+
     let source = std::fs::read_to_string(path)?;
 
     let tokens = bmb::lexer::tokenize(&source)?;
@@ -1180,19 +1960,43 @@ fn tokenize_file(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
             println!("{:?} @ {}..{}", tok, span.start, span.end);
         }
     } else {
-        // JSON array of tokens
-        print!("[");
-        for (i, (tok, span)) in tokens.iter().enumerate() {
-            if i > 0 { print!(","); }
-            print!(r#"{{"token":"{:?}","start":{},"end":{}}}"#, tok, span.start, span.end);
+        // A header naming every kind, then one record per token with its
+        // literal value, if any, and byte span
+        println!("{}", serde_json::json!({
+            "type": "token_schema",
+            "version": 1,
+            "kinds": Token::KINDS,
+        }));
+        for (tok, span) in &tokens {
+            let mut record = serde_json::json!({
+                "type": "token",
+                "kind": tok.kind(),
+                "start": span.start,
+                "end": span.end,
+            });
+            let value = match tok {
+                Token::IntLit(n) => Some(serde_json::json!(n)),
+                Token::FloatLit(f) => Some(serde_json::json!(f)),
+                Token::StringLit(s) | Token::Label(s) | Token::Ident(s) => Some(serde_json::json!(s)),
+                Token::CharLit(c) => Some(serde_json::json!(c)),
+                _ => None,
+            };
+            if let Some(value) = value {
+                record["value"] = value;
+            }
+            println!("{}", record);
         }
-        println!("]");
     }
 
-    Ok(())
+    Ok(CliOutcome::new("tokens").count("tokens", tokens.len()))
 }
 
-fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn test_file(
+    path: &PathBuf,
+    filter: Option<&str>,
+    verbose: bool,
+    coverage: bool,
+) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     use std::time::Instant;
 
     // Collect test files
@@ -1205,15 +2009,14 @@ fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(),
     if test_files.is_empty() {
         if is_human_output() {
             println!("No test files found");
-        } else {
-            println!(r#"{{"type":"test_result","tests":0,"passed":0,"failed":0}}"#);
         }
-        return Ok(());
+        return Ok(CliOutcome::new("test").count("tests", 0).count("passed", 0).count("failed", 0));
     }
 
     let mut total_passed = 0;
     let mut total_failed = 0;
     let mut total_tests = 0;
+    let mut coverage_reports = Vec::new();
     let start_time = Instant::now();
 
     for test_file in &test_files {
@@ -1226,19 +2029,37 @@ fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(),
         // Parse
         let ast = bmb::parser::parse(&filename, &source, tokens)?;
 
+        // Resolve imports against the file's directory and its project
+        let mut resolver = bmb::resolver::Resolver::for_file(test_file)?;
+        let mut imports = resolver.resolve_uses(&ast)?;
+
         // Type check
         let mut checker = bmb::types::TypeChecker::new();
-        checker.check_program(&ast)?;
+        checker.register_imports(&resolver, &imports);
+        checker.check_program_with_imports(&ast, &mut imports)?;
 
-        // Run tests with interpreter
+        // Run tests with interpreter, imported modules loaded alongside
         let mut interpreter = bmb::interp::Interpreter::new();
-        interpreter.load(&ast);
+        interpreter.set_int_op_types(checker.int_op_types().clone());
+        interpreter.load(&resolver.link(&ast));
+        if coverage {
+            interpreter.enable_coverage();
+        }
 
+        // Only tests defined in this file; imported modules may have their own
+        let local_fns: std::collections::HashSet<&str> = ast
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                bmb::ast::Item::FnDef(fn_def) => Some(fn_def.name.node.as_str()),
+                _ => None,
+            })
+            .collect();
         let test_names = interpreter.get_test_functions();
         let filtered_tests: Vec<_> = test_names
             .iter()
             .filter(|name| {
-                filter.is_none_or(|f| name.contains(f))
+                local_fns.contains(name.as_str()) && filter.is_none_or(|f| name.contains(f))
             })
             .collect();
 
@@ -1274,8 +2095,13 @@ fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(),
                         if is_human_output() {
                             println!("  ❌ {} - returned false ({:.2?})", test_name, test_start.elapsed());
                         } else {
-                            println!(r#"{{"type":"test_fail","name":"{}","file":"{}","reason":"returned false","ms":{}}}"#,
-                                test_name, filename, elapsed_ms);
+                            println!("{}", serde_json::json!({
+                                "type": "test_fail",
+                                "name": test_name,
+                                "file": filename,
+                                "reason": "returned false",
+                                "ms": elapsed_ms,
+                            }));
                         }
                     }
                 }
@@ -1284,16 +2110,35 @@ fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(),
                     if is_human_output() {
                         println!("  ❌ {} - {}", test_name, e.message);
                     } else {
-                        println!(r#"{{"type":"test_fail","name":"{}","file":"{}","reason":"{}"}}"#,
-                            test_name, filename, e.message.replace('"', "\\\""));
+                        let mut record = serde_json::json!({
+                            "type": "test_fail",
+                            "name": test_name,
+                            "file": filename,
+                            "reason": e.message,
+                        });
+                        // assert_eq/assert_ne failures carry both operands
+                        if let Some((left, right)) = &e.operands {
+                            record["left"] = left.as_str().into();
+                            record["right"] = right.as_str().into();
+                        }
+                        println!("{}", record);
                     }
                 }
             }
         }
+
+        if let Some(spans) = interpreter.covered_spans() {
+            let file_coverage = bmb::interp::line_coverage(&ast, &source, spans);
+            coverage_reports.push((filename, file_coverage));
+        }
     }
 
     let elapsed = start_time.elapsed();
 
+    if coverage {
+        print_coverage(&coverage_reports);
+    }
+
     // Print summary
     if is_human_output() {
         println!();
@@ -1306,20 +2151,70 @@ fn test_file(path: &PathBuf, filter: Option<&str>, verbose: bool) -> Result<(),
                 "❌ {} passed, {} failed of {} tests ({:.2?})",
                 total_passed, total_failed, total_tests, elapsed
             );
-            std::process::exit(1);
         }
+    }
+
+    Ok(CliOutcome::new("test")
+        .count("tests", total_tests)
+        .count("passed", total_passed)
+        .count("failed", total_failed)
+        .count("ms", elapsed.as_millis() as usize)
+        .failed_if(total_failed > 0))
+}
+
+/// Print per-file line coverage gathered by `bmb test --coverage`
+fn print_coverage(reports: &[(String, bmb::interp::LineCoverage)]) {
+    let total_lines: usize = reports.iter().map(|(_, c)| c.lines.len()).sum();
+    let total_covered: usize = reports.iter().map(|(_, c)| c.covered()).sum();
+    let total_percent = if total_lines == 0 {
+        100.0
     } else {
-        println!(r#"{{"type":"test_result","tests":{},"passed":{},"failed":{},"ms":{}}}"#,
-            total_tests, total_passed, total_failed, elapsed.as_millis());
-        if total_failed > 0 {
-            std::process::exit(1);
+        total_covered as f64 * 100.0 / total_lines as f64
+    };
+
+    if is_human_output() {
+        println!("\nCoverage:");
+        for (file, c) in reports {
+            println!("  {} {:.1}% ({}/{} lines)", file, c.percent(), c.covered(), c.lines.len());
+            if !c.uncovered.is_empty() {
+                println!("    uncovered: {}", bmb::interp::format_line_ranges(&c.uncovered));
+            }
+        }
+        if reports.len() > 1 {
+            println!("  total {:.1}% ({}/{} lines)", total_percent, total_covered, total_lines);
         }
+    } else {
+        let files: Vec<_> = reports
+            .iter()
+            .map(|(file, c)| {
+                serde_json::json!({
+                    "file": file,
+                    "lines": c.lines.len(),
+                    "covered": c.covered(),
+                    "percent": (c.percent() * 10.0).round() / 10.0,
+                    "uncovered": c.uncovered,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::json!({
+                "type": "coverage",
+                "files": files,
+                "lines": total_lines,
+                "covered": total_covered,
+                "percent": (total_percent * 10.0).round() / 10.0,
+            })
+        );
     }
-
-    Ok(())
 }
 
 fn collect_test_files(dir: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    collect_prefixed_files(dir, "test_")
+}
+
+/// `.bmb` files under `dir` whose name starts with `prefix`
+fn collect_prefixed_files(dir: &PathBuf, prefix: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
     let mut files = Vec::new();
 
     for entry in std::fs::read_dir(dir)? {
@@ -1327,109 +2222,273 @@ fn collect_test_files(dir: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error:
         let path = entry.path();
 
         if path.is_dir() {
-            files.extend(collect_test_files(&path)?);
+            files.extend(collect_prefixed_files(&path, prefix)?);
         } else if let Some(name) = path.file_name() {
             let name_str = name.to_string_lossy();
-            if name_str.starts_with("test_") && name_str.ends_with(".bmb") {
+            if name_str.starts_with(prefix) && name_str.ends_with(".bmb") {
                 files.push(path);
             }
         }
     }
 
+    files.sort();
     Ok(files)
 }
 
-/// Extract comments from source code with their line numbers
-/// Returns a Vec of (line_number, comment_text) where line_number is 0-indexed
-fn extract_comments(source: &str) -> Vec<(usize, String)> {
-    let mut comments = Vec::new();
+/// Timing of one benchmark, in nanoseconds per iteration
+struct BenchStats {
+    min: u128,
+    median: u128,
+    mean: u128,
+}
+
+impl BenchStats {
+    fn from_samples(samples: &mut [u128]) -> Self {
+        samples.sort_unstable();
+        let n = samples.len().max(1) as u128;
+        let median = match samples.len() {
+            0 => 0,
+            len if len % 2 == 0 => (samples[len / 2 - 1] + samples[len / 2]) / 2,
+            len => samples[len / 2],
+        };
+        Self {
+            min: samples.first().copied().unwrap_or(0),
+            median,
+            mean: samples.iter().sum::<u128>() / n,
+        }
+    }
+}
+
+fn bench_file(path: &PathBuf, iterations: u32, filter: Option<String>) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let bench_files = if path.is_dir() {
+        collect_prefixed_files(path, "bench_")?
+    } else {
+        vec![path.clone()]
+    };
+    let iterations = iterations.max(1);
+    let warmup = (iterations / 10).max(1);
+
+    // Benchmarks run in a thread with a large stack, like `bmb run`
+    let handle = std::thread::Builder::new()
+        .name("bmb-bench".to_string())
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(move || -> Result<(usize, usize), String> {
+            use std::time::Instant;
+
+            let mut total = 0;
+            let mut failed = 0;
+            for bench_file in &bench_files {
+                let filename = bench_file.display().to_string();
+                let source = std::fs::read_to_string(bench_file)
+                    .map_err(|e| {
+                        let message = format!("Failed to read {}: {}", filename, e);
+                        if is_human_output() {
+                            message
+                        } else {
+                            let diagnostic = bmb::error::Diagnostic::error(message).with_file(&filename);
+                            bmb::error::DiagnosticReport::new(vec![diagnostic]).to_string()
+                        }
+                    })?;
+                let compile_failure = |e: bmb::error::CompileError| {
+                    let diagnostic = e.to_diagnostic(&filename, &source);
+                    if is_human_output() {
+                        diagnostic.render(&source)
+                    } else {
+                        bmb::error::DiagnosticReport::new(vec![diagnostic]).to_string()
+                    }
+                };
+                let tokens = bmb::lexer::tokenize(&source)
+                    .map_err(&compile_failure)?;
+                let ast = bmb::parser::parse(&filename, &source, tokens)
+                    .map_err(&compile_failure)?;
+                let mut checker = bmb::types::TypeChecker::new();
+                checker.check_program(&ast)
+                    .map_err(&compile_failure)?;
+
+                let mut interpreter = bmb::interp::Interpreter::new();
+                interpreter.set_int_op_types(checker.int_op_types().clone());
+                interpreter.load(&ast);
+                let benches: Vec<_> = interpreter
+                    .get_bench_functions()
+                    .into_iter()
+                    .filter(|name| filter.as_deref().is_none_or(|f| name.contains(f)))
+                    .collect();
+                if benches.is_empty() {
+                    continue;
+                }
+                if is_human_output() && bench_files.len() > 1 {
+                    println!("\n📂 {}", filename);
+                }
+                let width = benches.iter().map(String::len).max().unwrap_or(0);
+
+                'benches: for name in &benches {
+                    total += 1;
+                    let mut samples = Vec::with_capacity(iterations as usize);
+                    for i in 0..warmup + iterations {
+                        let start = Instant::now();
+                        let result = interpreter.run_function(name);
+                        let elapsed = start.elapsed().as_nanos();
+                        if let Err(e) = result {
+                            failed += 1;
+                            if is_human_output() {
+                                println!("  ❌ {} - {}", name, e.message);
+                            } else {
+                                let record = serde_json::json!({
+                                    "type": "bench_fail",
+                                    "name": name,
+                                    "file": filename,
+                                    "reason": e.message,
+                                });
+                                println!("{}", record);
+                            }
+                            continue 'benches;
+                        }
+                        if i >= warmup {
+                            samples.push(elapsed);
+                        }
+                    }
+
+                    let stats = BenchStats::from_samples(&mut samples);
+                    if is_human_output() {
+                        println!(
+                            "  {:width$}  min {:>10} ns  median {:>10} ns  mean {:>10} ns",
+                            name, stats.min, stats.median, stats.mean, width = width
+                        );
+                    } else {
+                        let record = serde_json::json!({
+                            "type": "bench",
+                            "name": name,
+                            "file": filename,
+                            "iterations": iterations,
+                            "warmup": warmup,
+                            "min_ns": stats.min as u64,
+                            "median_ns": stats.median as u64,
+                            "mean_ns": stats.mean as u64,
+                        });
+                        println!("{}", record);
+                    }
+                }
+            }
+            Ok((total, failed))
+        })?;
+
+    let (total, failed) = match handle.join() {
+        Ok(Ok(counts)) => counts,
+        Ok(Err(e)) => {
+            if is_human_output() {
+                eprintln!("{}", e);
+            } else {
+                println!("{}", e);
+            }
+            return Err(Box::new(Reported));
+        }
+        Err(_) => return Err("benchmark thread panicked".into()),
+    };
 
-    for (line_num, line) in source.lines().enumerate() {
-        let trimmed = line.trim();
-        // Check for // style comments (whole line only)
-        if trimmed.starts_with("//") {
-            comments.push((line_num, line.to_string()));
-        } else if trimmed.starts_with("--") {
-            // Legacy -- comment (whole line)
-            comments.push((line_num, line.to_string()));
+    if is_human_output() {
+        if total == 0 {
+            println!("No benchmarks found");
+        } else {
+            println!("\n{} benchmark(s), {} iteration(s) each after {} warmup", total, iterations, warmup);
         }
     }
 
-    comments
+    Ok(CliOutcome::new("bench").count("benchmarks", total).count("failed", failed).failed_if(failed > 0))
 }
 
-/// Get the line number from a byte offset in source
-fn line_number_at_offset(source: &str, offset: usize) -> usize {
-    source[..offset.min(source.len())].matches('\n').count()
+/// Parse a `--range` value such as `10:25`
+fn parse_line_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (start, end) = value.split_once(':').ok_or("expected START:END")?;
+    let start: usize = start.parse().map_err(|_| format!("invalid start line `{start}`"))?;
+    let end: usize = end.parse().map_err(|_| format!("invalid end line `{end}`"))?;
+    if start == 0 || start > end {
+        return Err(format!("`{value}` is not a range of lines counted from 1"));
+    }
+    Ok(start..=end)
 }
 
-fn fmt_file(path: &PathBuf, check: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn fmt_file(
+    path: &Path,
+    check: bool,
+    width: usize,
+    range: Option<RangeInclusive<usize>>,
+) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     let files = if path.is_dir() {
         collect_bmb_files(path)?
     } else {
-        vec![path.clone()]
+        vec![path.to_path_buf()]
     };
 
-    if files.is_empty() {
-        if is_human_output() {
-            println!("No BMB files found");
-        } else {
-            println!(r#"{{"type":"fmt_result","files":0}}"#);
-        }
-        return Ok(());
+    if files.is_empty() && is_human_output() {
+        println!("No BMB files found");
     }
 
-    let mut needs_formatting = false;
-    let mut _formatted_count = 0;
+    let mut unformatted_count = 0;
+    let mut formatted_count = 0;
 
     for file in &files {
         let source = std::fs::read_to_string(file)?;
         let filename = file.display().to_string();
 
-        // Extract comments before parsing (they get lost during tokenization)
-        let comments = extract_comments(&source);
-
-        // Tokenize
-        let tokens = bmb::lexer::tokenize(&source)?;
-
-        // Parse
-        let ast = bmb::parser::parse(&filename, &source, tokens)?;
-
-        // Format AST back to source, preserving comments
-        let formatted = format_program_with_comments(&ast, &source, &comments);
+        // Format AST back to source; comments are re-attached from the source text
+        let formatted = bmb::fmt::format_source(&filename, &source, width, range.clone())?;
 
         if check {
             if source != formatted {
-                needs_formatting = true;
+                unformatted_count += 1;
                 if is_human_output() {
                     println!("❌ {} needs formatting", filename);
                 } else {
-                    println!(r#"{{"type":"fmt_needed","file":"{}"}}"#, filename);
+                    println!("{}", serde_json::json!({ "type": "fmt_needed", "file": filename }));
                 }
             } else if is_human_output() {
                 println!("✓ {} is formatted", filename);
             }
         } else if source != formatted {
             std::fs::write(file, &formatted)?;
-            _formatted_count += 1;
+            formatted_count += 1;
             if is_human_output() {
                 println!("✓ formatted {}", filename);
             } else {
-                println!(r#"{{"type":"fmt_formatted","file":"{}"}}"#, filename);
+                println!("{}", serde_json::json!({ "type": "fmt_formatted", "file": filename }));
             }
         } else if is_human_output() {
             println!("✓ {} (unchanged)", filename);
         }
     }
 
-    if check && needs_formatting {
-        std::process::exit(1);
-    }
+    Ok(CliOutcome::new("fmt")
+        .count("files", files.len())
+        .count("formatted", formatted_count)
+        .count("unformatted", unformatted_count)
+        .failed_if(unformatted_count > 0))
+}
 
-    Ok(())
+/// `bmb fmt --stdin`: format stdin to stdout for editors. Nothing but the
+/// formatted source goes to stdout; a parse error goes to stderr.
+fn fmt_stdin(width: usize, range: Option<RangeInclusive<usize>>) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    use std::io::{Read, Write};
+
+    let mut source = String::new();
+    std::io::stdin().read_to_string(&mut source)?;
+    match bmb::fmt::format_source("<stdin>", &source, width, range) {
+        Ok(formatted) => {
+            std::io::stdout().write_all(formatted.as_bytes())?;
+            Ok(CliOutcome::new("fmt").count("files", 1))
+        }
+        Err(e) => {
+            let diagnostic = e.to_diagnostic("<stdin>", &source);
+            if is_human_output() {
+                eprintln!("{}", diagnostic.render(&source));
+            } else {
+                eprintln!("{}", bmb::error::DiagnosticReport::new(vec![diagnostic]));
+            }
+            Err(Box::new(Reported))
+        }
+    }
 }
 
-fn collect_bmb_files(dir: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+fn collect_bmb_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
     let mut files = Vec::new();
 
     for entry in std::fs::read_dir(dir)? {
@@ -1446,624 +2505,125 @@ fn collect_bmb_files(dir: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error::
     Ok(files)
 }
 
-/// Get the starting span of an Item (for comment attachment)
-fn get_item_span(item: &bmb::ast::Item) -> bmb::ast::Span {
-    use bmb::ast::Item;
-    match item {
-        Item::FnDef(f) => f.span,
-        Item::StructDef(s) => s.span,
-        Item::EnumDef(e) => e.span,
-        Item::TypeAlias(t) => t.span,
-        Item::Use(u) => u.span,
-        Item::ExternFn(e) => e.span,
-        Item::TraitDef(t) => t.span,
-        Item::ImplBlock(i) => i.span,
-    }
+/// Print the extended explanation of a diagnostic code
+fn explain_code(code: &str) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let Some(explanation) = bmb::error::explain::explain(code) else {
+        let known: Vec<_> = bmb::error::explain::explained_codes().collect();
+        return Err(UsageError(format!("no explanation for `{}`; explained codes are {}", code, known.join(", "))).into());
+    };
+    if is_human_output() {
+        print!("{}", explanation.render());
+    } else {
+        println!("{}", serde_json::json!({
+            "type": "explanation",
+            "code": explanation.code,
+            "title": explanation.title,
+            "description": explanation.description,
+            "erroneous": explanation.erroneous,
+            "fixed": explanation.fixed,
+        }));
+    }
+    Ok(CliOutcome::new("explain"))
 }
 
-/// Format program with comment preservation
-/// Attaches comments to the items they precede based on line numbers
-fn format_program_with_comments(
-    program: &bmb::ast::Program,
-    source: &str,
-    comments: &[(usize, String)],
-) -> String {
-    use bmb::ast::{Item, Visibility};
-
-    let mut output = String::new();
-    let mut used_comments: std::collections::HashSet<usize> = std::collections::HashSet::new();
+/// Generate API documentation for public items (machine JSON by default,
+/// or Markdown pages written to `markdown`)
+fn doc_file(path: &Path, markdown: Option<&Path>) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let files = if path.is_dir() {
+        collect_bmb_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
 
-    // Collect item spans (line numbers)
-    let mut item_lines: Vec<(usize, usize)> = Vec::new(); // (item_index, start_line)
-    for (idx, item) in program.items.iter().enumerate() {
-        let span = get_item_span(item);
-        let start_line = line_number_at_offset(source, span.start);
-        item_lines.push((idx, start_line));
+    let mut symbols = Vec::new();
+    for file in &files {
+        let source = std::fs::read_to_string(file)?;
+        let filename = file.display().to_string();
+        let tokens = bmb::lexer::tokenize(&source)?;
+        let ast = bmb::parser::parse(&filename, &source, tokens)?;
+        symbols.extend(bmb::doc::collect_symbols(&ast, &source, &filename));
     }
 
-    // Find file-level comments (before first item)
-    let first_item_line = item_lines.first().map(|(_, l)| *l).unwrap_or(usize::MAX);
-    for (line_num, comment_text) in comments {
-        if *line_num < first_item_line && !used_comments.contains(line_num) {
-            output.push_str(comment_text);
-            output.push('\n');
-            used_comments.insert(*line_num);
+    if let Some(out_dir) = markdown {
+        let root = if path.is_dir() { path } else { path.parent().unwrap_or(Path::new("")) };
+        let pages = bmb::doc::render_markdown(root, &files, &symbols);
+        for (page, md) in &pages {
+            let out_path = out_dir.join(page);
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&out_path, md)?;
         }
-    }
-
-    // Process each item with its preceding comments
-    for (i, item) in program.items.iter().enumerate() {
-        let item_start_line = item_lines.iter().find(|(idx, _)| *idx == i).map(|(_, l)| *l).unwrap_or(0);
-
-        // Find the end of the previous item (or file start)
-        let prev_end_line = if i > 0 {
-            item_lines.iter().find(|(idx, _)| *idx == i - 1).map(|(_, l)| *l + 1).unwrap_or(0)
+        if is_human_output() {
+            println!("Wrote {} page(s) to {}", pages.len(), out_dir.display());
         } else {
-            0
-        };
-
-        // Add blank line between items (if not first item)
-        if i > 0 {
-            output.push('\n');
-        }
-
-        // Find comments between previous item end and this item start
-        for (line_num, comment_text) in comments {
-            if *line_num >= prev_end_line && *line_num < item_start_line && !used_comments.contains(line_num) {
-                output.push_str(comment_text);
-                output.push('\n');
-                used_comments.insert(*line_num);
-            }
+            let doc = serde_json::json!({
+                "type": "doc",
+                "files": files.len(),
+                "pages": pages.len(),
+                "dir": out_dir.display().to_string(),
+            });
+            println!("{}", serde_json::to_string(&doc)?);
         }
+        return Ok(CliOutcome::new("doc").count("files", files.len()).count("symbols", symbols.len()).count("pages", pages.len()));
+    }
 
-        // Format the item
-        match item {
-            Item::FnDef(fn_def) => {
-                output.push_str(&format_fn_def(fn_def));
-            }
-            Item::StructDef(s) => {
-                if s.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("struct {} {{\n", s.name.node));
-                for field in &s.fields {
-                    output.push_str(&format!("    {}: {},\n", field.name.node, format_type(&field.ty.node)));
-                }
-                output.push('}');
-            }
-            Item::EnumDef(e) => {
-                if e.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("enum {} {{\n", e.name.node));
-                for variant in &e.variants {
-                    output.push_str(&format!("    {},\n", variant.name.node));
-                }
-                output.push('}');
-            }
-            Item::Use(u) => {
-                let path_str: Vec<_> = u.path.iter().map(|s| s.node.as_str()).collect();
-                output.push_str(&format!("use {};", path_str.join("::")));
-            }
-            Item::ExternFn(e) => {
-                if e.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("extern fn {}(", e.name.node));
-                let params: Vec<_> = e.params.iter()
-                    .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
-                    .collect();
-                output.push_str(&params.join(", "));
-                output.push_str(&format!(") -> {};", format_type(&e.ret_ty.node)));
-            }
-            Item::TraitDef(t) => {
-                if t.visibility == Visibility::Public {
-                    output.push_str("pub ");
-                }
-                output.push_str(&format!("trait {} {{\n", t.name.node));
-                for method in &t.methods {
-                    let params: Vec<_> = method.params.iter()
-                        .map(|p| format!("{}: {}", p.name.node, format_type(&p.ty.node)))
-                        .collect();
-                    output.push_str(&format!("    fn {}({}) -> {};\n",
-                        method.name.node, params.join(", "), format_type(&method.ret_ty.node)));
-                }
-                output.push('}');
-            }
-            Item::ImplBlock(i) => {
-                output.push_str(&format!("impl {} for {} {{\n", i.trait_name.node, format_type(&i.target_type.node)));
-                for method in &i.methods {
-                    output.push_str("    ");
-                    output.push_str(&format_fn_def(method));
-                    output.push('\n');
+    let outcome = CliOutcome::new("doc").count("files", files.len()).count("symbols", symbols.len());
+    if is_human_output() {
+        if symbols.is_empty() {
+            println!("No public symbols found");
+        }
+        for sym in &symbols {
+            println!("{:<8} {:<24} {}", sym.kind, sym.name, sym.signature);
+            for (contract, cond) in [("pre", &sym.pre), ("post", &sym.post)] {
+                if let Some(cond) = cond {
+                    println!("{:<8} {:<24}   {} {}", "", "", contract, cond);
                 }
-                output.push('}');
             }
-            Item::TypeAlias(t) => {
-                if t.visibility == Visibility::Public {
-                    output.push_str("pub ");
+            if let Some(doc) = &sym.doc {
+                for line in doc.lines() {
+                    println!("{:<8} {:<24}   {}", "", "", line);
                 }
-                output.push_str(&format!("type {} = {};", t.name.node, format_type(&t.target.node)));
-            }
-        }
-        output.push('\n');
-    }
-
-    // Add any trailing comments (after last item)
-    let last_item_line = item_lines.last().map(|(_, l)| *l).unwrap_or(0);
-    for (line_num, comment_text) in comments {
-        if *line_num > last_item_line && !used_comments.contains(line_num) {
-            output.push_str(comment_text);
-            output.push('\n');
-            used_comments.insert(*line_num);
-        }
-    }
-
-    output
-}
-
-fn format_fn_def(fn_def: &bmb::ast::FnDef) -> String {
-    use bmb::ast::Visibility;
-
-    let mut s = String::new();
-
-    // Visibility
-    if fn_def.visibility == Visibility::Public {
-        s.push_str("pub ");
-    }
-
-    // Function signature
-    s.push_str(&format!("fn {}(", fn_def.name.node));
-
-    for (i, param) in fn_def.params.iter().enumerate() {
-        if i > 0 {
-            s.push_str(", ");
-        }
-        s.push_str(&format!("{}: {}", param.name.node, format_type(&param.ty.node)));
-    }
-
-    s.push_str(&format!(") -> {}", format_type(&fn_def.ret_ty.node)));
-
-    // Contracts
-    if let Some(pre) = &fn_def.pre {
-        s.push_str(&format!("\n  pre {}", format_expr(&pre.node)));
-    }
-
-    if let Some(post) = &fn_def.post {
-        s.push_str(&format!("\n  post {}", format_expr(&post.node)));
-    }
-
-    // Body
-    s.push_str(&format!("\n= {};", format_expr(&fn_def.body.node)));
-
-    s
-}
-
-fn format_type(ty: &bmb::ast::Type) -> String {
-    use bmb::ast::Type;
-
-    match ty {
-        Type::I32 => "i32".to_string(),
-        Type::I64 => "i64".to_string(),
-        // v0.38: Unsigned types
-        Type::U32 => "u32".to_string(),
-        Type::U64 => "u64".to_string(),
-        Type::F64 => "f64".to_string(),
-        Type::Bool => "bool".to_string(),
-        Type::String => "String".to_string(),
-        // v0.64: Character type
-        Type::Char => "char".to_string(),
-        Type::Unit => "()".to_string(),
-        Type::Range(elem) => format!("Range<{}>", format_type(elem)),
-        Type::Named(name) => name.clone(),
-        // v0.13.1: Type variable
-        Type::TypeVar(name) => name.clone(),
-        // v0.13.1: Generic type
-        Type::Generic { name, type_args } => {
-            let args_str = type_args.iter()
-                .map(|t| format_type(t))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{}<{}>", name, args_str)
-        }
-        Type::Struct { name, .. } => name.clone(),
-        Type::Enum { name, .. } => name.clone(),
-        Type::Array(elem, size) => format!("[{}; {}]", format_type(elem), size),
-        Type::Ref(inner) => format!("&{}", format_type(inner)),
-        Type::RefMut(inner) => format!("&mut {}", format_type(inner)),
-        // v0.2: Refined types display base{constraints}
-        Type::Refined { base, constraints } => {
-            let constraint_str = constraints.iter()
-                .map(|c| format_expr(&c.node))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{}{{{}}}", format_type(base), constraint_str)
-        }
-        // v0.20.0: Fn type
-        Type::Fn { params, ret } => {
-            let params_str = params.iter()
-                .map(|p| format_type(p))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("fn({}) -> {}", params_str, format_type(ret))
-        }
-        // v0.31: Never type
-        Type::Never => "!".to_string(),
-        // v0.37: Nullable type
-        Type::Nullable(inner) => format!("{}?", format_type(inner)),
-        // v0.42: Tuple type
-        Type::Tuple(elems) => {
-            let elems_str: Vec<_> = elems.iter().map(|t| format_type(t)).collect();
-            format!("({})", elems_str.join(", "))
-        }
-    }
-}
-
-fn format_expr(expr: &bmb::ast::Expr) -> String {
-    use bmb::ast::{Expr, BinOp, UnOp};
-
-    match expr {
-        Expr::IntLit(n) => n.to_string(),
-        Expr::FloatLit(f) => f.to_string(),
-        Expr::BoolLit(b) => b.to_string(),
-        Expr::StringLit(s) => format!("\"{}\"", s),
-        // v0.64: Character literal
-        Expr::CharLit(c) => format!("'{}'", c.escape_default()),
-        Expr::Unit => "()".to_string(),
-        Expr::Var(name) => name.clone(),
-        Expr::Ret => "ret".to_string(),
-        Expr::It => "it".to_string(),
-
-        Expr::Binary { left, op, right } => {
-            let op_str = match op {
-                BinOp::Add => "+",
-                BinOp::Sub => "-",
-                BinOp::Mul => "*",
-                BinOp::Div => "/",
-                BinOp::Mod => "%",
-                // v0.37: Wrapping arithmetic
-                BinOp::AddWrap => "+%",
-                BinOp::SubWrap => "-%",
-                BinOp::MulWrap => "*%",
-                // v0.38: Checked arithmetic
-                BinOp::AddChecked => "+?",
-                BinOp::SubChecked => "-?",
-                BinOp::MulChecked => "*?",
-                // v0.38: Saturating arithmetic
-                BinOp::AddSat => "+|",
-                BinOp::SubSat => "-|",
-                BinOp::MulSat => "*|",
-                BinOp::Eq => "==",
-                BinOp::Ne => "!=",
-                BinOp::Lt => "<",
-                BinOp::Le => "<=",
-                BinOp::Gt => ">",
-                BinOp::Ge => ">=",
-                BinOp::And => "and",
-                BinOp::Or => "or",
-                // v0.32: Shift operators
-                BinOp::Shl => "<<",
-                BinOp::Shr => ">>",
-                // v0.36: Bitwise operators
-                BinOp::Band => "band",
-                BinOp::Bor => "bor",
-                BinOp::Bxor => "bxor",
-                // v0.36: Logical implication
-                BinOp::Implies => "implies",
-            };
-            format!("{} {} {}", format_expr(&left.node), op_str, format_expr(&right.node))
-        }
-
-        Expr::Unary { op, expr } => {
-            let op_str = match op {
-                UnOp::Neg => "-",
-                UnOp::Not => "not ",
-                // v0.36: Bitwise not
-                UnOp::Bnot => "bnot ",
-            };
-            format!("{}{}", op_str, format_expr(&expr.node))
-        }
-
-        Expr::If { cond, then_branch, else_branch } => {
-            format!(
-                "if {} then {} else {}",
-                format_expr(&cond.node),
-                format_expr(&then_branch.node),
-                format_expr(&else_branch.node)
-            )
-        }
-
-        Expr::Let { name, mutable, ty, value, body } => {
-            let mut_str = if *mutable { "mut " } else { "" };
-            let ty_str = ty.as_ref().map(|t| format!(": {}", format_type(&t.node))).unwrap_or_default();
-            format!(
-                "let {}{}{} = {};\n    {}",
-                mut_str,
-                name,
-                ty_str,
-                format_expr(&value.node),
-                format_expr(&body.node)
-            )
-        }
-
-        Expr::Call { func, args } => {
-            let args_str: Vec<_> = args.iter().map(|a| format_expr(&a.node)).collect();
-            format!("{}({})", func, args_str.join(", "))
-        }
-
-        Expr::MethodCall { receiver, method, args } => {
-            let args_str: Vec<_> = args.iter().map(|a| format_expr(&a.node)).collect();
-            format!("{}.{}({})", format_expr(&receiver.node), method, args_str.join(", "))
-        }
-
-        Expr::Index { expr: arr, index } => {
-            format!("{}[{}]", format_expr(&arr.node), format_expr(&index.node))
-        }
-
-        Expr::ArrayLit(elems) => {
-            let elems_str: Vec<_> = elems.iter().map(|e| format_expr(&e.node)).collect();
-            format!("[{}]", elems_str.join(", "))
-        }
-
-        // v0.42: Tuple expression
-        Expr::Tuple(elems) => {
-            let elems_str: Vec<_> = elems.iter().map(|e| format_expr(&e.node)).collect();
-            if elems.len() == 1 {
-                format!("({},)", elems_str.join(", "))
-            } else {
-                format!("({})", elems_str.join(", "))
-            }
-        }
-
-        Expr::StructInit { name, fields } => {
-            let fields_str: Vec<_> = fields.iter()
-                .map(|(n, v)| format!("{}: {}", n.node, format_expr(&v.node)))
-                .collect();
-            format!("{} {{ {} }}", name, fields_str.join(", "))
-        }
-
-        Expr::FieldAccess { expr, field } => {
-            format!("{}.{}", format_expr(&expr.node), field.node)
-        }
-
-        // v0.43: Tuple field access
-        Expr::TupleField { expr, index } => {
-            format!("{}.{}", format_expr(&expr.node), index)
-        }
-
-        Expr::Match { expr, arms } => {
-            let arms_str: Vec<_> = arms.iter()
-                .map(|arm| format!("{} => {}", format_pattern(&arm.pattern.node), format_expr(&arm.body.node)))
-                .collect();
-            format!("match {} {{ {} }}", format_expr(&expr.node), arms_str.join(", "))
-        }
-
-        Expr::Block(stmts) => {
-            if stmts.is_empty() {
-                "{}".to_string()
-            } else {
-                let stmts_str: Vec<_> = stmts.iter().map(|s| format_expr(&s.node)).collect();
-                format!("{{ {} }}", stmts_str.join("; "))
-            }
-        }
-
-        Expr::Assign { name, value } => {
-            format!("{} = {}", name, format_expr(&value.node))
-        }
-
-        // v0.37: Include invariant in format if present
-        Expr::While { cond, invariant, body } => {
-            match invariant {
-                Some(inv) => format!(
-                    "while {} invariant {} {{ {} }}",
-                    format_expr(&cond.node),
-                    format_expr(&inv.node),
-                    format_expr(&body.node)
-                ),
-                None => format!(
-                    "while {} {{ {} }}",
-                    format_expr(&cond.node),
-                    format_expr(&body.node)
-                ),
-            }
-        }
-
-        Expr::For { var, iter, body } => {
-            format!(
-                "for {} in {} {{ {} }}",
-                var,
-                format_expr(&iter.node),
-                format_expr(&body.node)
-            )
-        }
-
-        Expr::Range { start, end, kind } => {
-            let op = match kind {
-                bmb::ast::RangeKind::Exclusive => "..<",
-                bmb::ast::RangeKind::Inclusive => "..=",
-            };
-            format!("{}{}{}", format_expr(&start.node), op, format_expr(&end.node))
-        }
-
-        Expr::EnumVariant { enum_name, variant, args } => {
-            if args.is_empty() {
-                format!("{}::{}", enum_name, variant)
-            } else {
-                let args_str: Vec<_> = args.iter().map(|a| format_expr(&a.node)).collect();
-                format!("{}::{}({})", enum_name, variant, args_str.join(", "))
-            }
-        }
-
-        Expr::Ref(inner) => {
-            format!("&{}", format_expr(&inner.node))
-        }
-
-        Expr::RefMut(inner) => {
-            format!("&mut {}", format_expr(&inner.node))
-        }
-
-        Expr::Deref(inner) => {
-            format!("*{}", format_expr(&inner.node))
-        }
-
-        Expr::StateRef { expr, state } => {
-            format!("{}{}", format_expr(&expr.node), state)
-        }
-
-        // v0.20.0: Closure expressions
-        Expr::Closure { params, ret_ty, body } => {
-            let params_str = params
-                .iter()
-                .map(|p| {
-                    if let Some(ty) = &p.ty {
-                        format!("{}: {}", p.name.node, format_type(&ty.node))
-                    } else {
-                        p.name.node.clone()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
-            let ret_str = ret_ty
-                .as_ref()
-                .map(|t| format!(" -> {}", format_type(&t.node)))
-                .unwrap_or_default();
-            format!("fn |{}|{} {{ {} }}", params_str, ret_str, format_expr(&body.node))
-        }
-
-        // v0.31: Todo expression
-        Expr::Todo { message } => {
-            match message {
-                Some(msg) => format!("todo \"{}\"", msg),
-                None => "todo".to_string(),
             }
         }
-
-        // v0.36: Additional control flow
-        Expr::Loop { body } => format!("loop {{ {} }}", format_expr(&body.node)),
-        Expr::Break { value } => match value {
-            Some(v) => format!("break {}", format_expr(&v.node)),
-            None => "break".to_string(),
-        },
-        Expr::Continue => "continue".to_string(),
-        Expr::Return { value } => match value {
-            Some(v) => format!("return {}", format_expr(&v.node)),
-            None => "return".to_string(),
-        },
-
-        // v0.37: Quantifiers
-        Expr::Forall { var, ty, body } => {
-            format!("forall {}: {}, {}", var.node, format_type(&ty.node), format_expr(&body.node))
-        }
-        Expr::Exists { var, ty, body } => {
-            format!("exists {}: {}, {}", var.node, format_type(&ty.node), format_expr(&body.node))
-        }
-        // v0.39: Type cast
-        Expr::Cast { expr, ty } => {
-            format!("{} as {}", format_expr(&expr.node), format_type(&ty.node))
-        }
-    }
-}
-
-fn format_literal_pattern(lit: &bmb::ast::LiteralPattern) -> String {
-    use bmb::ast::LiteralPattern;
-    match lit {
-        LiteralPattern::Int(n) => n.to_string(),
-        LiteralPattern::Float(f) => f.to_string(),
-        LiteralPattern::Bool(b) => b.to_string(),
-        LiteralPattern::String(s) => format!("\"{}\"", s),
+    } else {
+        let doc = serde_json::json!({
+            "type": "doc",
+            "files": files.len(),
+            "symbols": symbols,
+        });
+        println!("{}", serde_json::to_string(&doc)?);
     }
-}
-
-fn format_pattern(pattern: &bmb::ast::Pattern) -> String {
-    use bmb::ast::Pattern;
 
-    match pattern {
-        Pattern::Wildcard => "_".to_string(),
-        Pattern::Var(name) => name.clone(),
-        Pattern::Literal(lit) => format_literal_pattern(lit),
-        // v0.41: Nested patterns in enum bindings
-        Pattern::EnumVariant { enum_name, variant, bindings } => {
-            if bindings.is_empty() {
-                format!("{}::{}", enum_name, variant)
-            } else {
-                let bindings_str: Vec<_> = bindings.iter()
-                    .map(|b| format_pattern(&b.node))
-                    .collect();
-                format!("{}::{}({})", enum_name, variant, bindings_str.join(", "))
-            }
-        }
-        Pattern::Struct { name, fields } => {
-            let fields_str: Vec<_> = fields.iter()
-                .map(|(n, p)| format!("{}: {}", n.node, format_pattern(&p.node)))
-                .collect();
-            format!("{} {{ {} }}", name, fields_str.join(", "))
-        }
-        // v0.39: Range pattern
-        Pattern::Range { start, end, inclusive } => {
-            let op = if *inclusive { "..=" } else { ".." };
-            format!("{}{}{}", format_literal_pattern(start), op, format_literal_pattern(end))
-        }
-        // v0.40: Or-pattern
-        Pattern::Or(alts) => {
-            let alts_str: Vec<_> = alts.iter().map(|p| format_pattern(&p.node)).collect();
-            alts_str.join(" | ")
-        }
-        // v0.41: Binding pattern
-        Pattern::Binding { name, pattern } => {
-            format!("{} @ {}", name, format_pattern(&pattern.node))
-        }
-        // v0.42: Tuple pattern
-        Pattern::Tuple(elems) => {
-            let elems_str: Vec<_> = elems.iter().map(|p| format_pattern(&p.node)).collect();
-            if elems.len() == 1 {
-                format!("({},)", elems_str.join(", "))
-            } else {
-                format!("({})", elems_str.join(", "))
-            }
-        }
-        // v0.44: Array pattern
-        Pattern::Array(elems) => {
-            let elems_str: Vec<_> = elems.iter().map(|p| format_pattern(&p.node)).collect();
-            format!("[{}]", elems_str.join(", "))
-        }
-        // v0.45: Array rest pattern
-        Pattern::ArrayRest { prefix, suffix } => {
-            let prefix_str: Vec<_> = prefix.iter().map(|p| format_pattern(&p.node)).collect();
-            let suffix_str: Vec<_> = suffix.iter().map(|p| format_pattern(&p.node)).collect();
-            match (prefix.is_empty(), suffix.is_empty()) {
-                (true, true) => "[..]".to_string(),
-                (false, true) => format!("[{}, ..]", prefix_str.join(", ")),
-                (true, false) => format!("[.., {}]", suffix_str.join(", ")),
-                (false, false) => format!("[{}, .., {}]", prefix_str.join(", "), suffix_str.join(", ")),
-            }
-        }
-    }
+    Ok(outcome)
 }
 
-fn start_lsp() -> Result<(), Box<dyn std::error::Error>> {
+fn start_lsp() -> Result<CliOutcome, Box<dyn std::error::Error>> {
     // Create tokio runtime for async LSP server
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(bmb::lsp::run_server());
-    Ok(())
+    Ok(CliOutcome::new("lsp"))
 }
 
 /// v0.25: Generate project index for AI tools
 /// v0.50.21: Added --watch mode for real-time index updates
-fn index_project(path: &PathBuf, watch: bool, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn index_project(path: &Path, watch: bool, verbose: bool) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     // Initial index generation
-    do_index_project(path, verbose)?;
+    let (outcome, generator) = do_index_project(path, verbose)?;
 
     // If watch mode, start file watcher
     if watch {
-        run_index_watcher(path, verbose)?;
+        outcome.emit(is_human_output());
+        run_index_watcher(path, generator, verbose)?;
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
-/// Perform the actual indexing operation
-fn do_index_project(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
-    use bmb::index::{IndexGenerator, write_index};
+/// Perform the actual indexing operation, returning the generator so that
+/// watch mode can update it in place
+fn do_index_project(path: &Path, verbose: bool) -> Result<(CliOutcome, bmb::index::IndexGenerator), Box<dyn std::error::Error>> {
+    use bmb::index::IndexGenerator;
 
     // Determine project name from directory
     let project_name = path
@@ -2076,72 +2636,95 @@ fn do_index_project(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::er
         println!("Indexing project: {} at {}", project_name, path.display());
     }
 
+    // Create index generator
+    let mut generator = IndexGenerator::new(&project_name);
+
     // Collect all .bmb files
     let bmb_files = collect_bmb_files(path)?;
 
     if bmb_files.is_empty() {
-        println!("No BMB files found in {}", path.display());
-        return Ok(());
+        if is_human_output() {
+            println!("No BMB files found in {}", path.display());
+        }
+        return Ok((CliOutcome::new("index").count("files", 0), generator));
     }
 
     if verbose {
         println!("Found {} BMB files", bmb_files.len());
     }
 
-    // Create index generator
-    let mut generator = IndexGenerator::new(&project_name);
-
     // Index each file
     for file in &bmb_files {
         let source = std::fs::read_to_string(file)?;
-        let filename = file.display().to_string();
+        index_bmb_file(&mut generator, file, &source, verbose);
+    }
 
-        // Try to parse the file
-        match bmb::lexer::tokenize(&source) {
-            Ok(tokens) => {
-                match bmb::parser::parse(&filename, &source, tokens) {
-                    Ok(ast) => {
-                        if verbose {
-                            println!("  Indexed: {}", filename);
-                        }
-                        generator.index_file(&filename, &ast);
-                    }
-                    Err(e) => {
-                        if verbose {
-                            eprintln!("  Skipped {} (parse error: {})", filename, e);
-                        }
-                    }
+    let outcome = write_project_index(path, &generator)?;
+    Ok((outcome, generator))
+}
+
+/// Parse one file and (re-)index every item that parses
+fn index_bmb_file(generator: &mut bmb::index::IndexGenerator, file: &Path, source: &str, verbose: bool) {
+    let filename = file.display().to_string();
+    match bmb::lexer::tokenize(source) {
+        Ok(tokens) => {
+            let (ast, errors) = bmb::parser::parse_with_recovery(&filename, source, tokens);
+            if verbose {
+                println!("  Indexed: {}", filename);
+                for e in &errors {
+                    eprintln!("    Skipped an item (parse error: {})", e);
                 }
             }
-            Err(e) => {
-                if verbose {
-                    eprintln!("  Skipped {} (lex error: {})", filename, e);
-                }
+            generator.index_source(&filename, source, &ast);
+        }
+        Err(e) => {
+            // Keep the last good entries of a file that is mid-edit
+            if verbose {
+                eprintln!("  Skipped {} (lex error: {})", filename, e);
             }
         }
     }
+}
 
-    // Generate and write index
-    let index = generator.generate();
-    write_index(&index, path)?;
-
-    println!("✓ Index generated: .bmb/index/");
-    println!("  Files: {}", index.manifest.files);
-    println!("  Functions: {}", index.manifest.functions);
-    println!("  Types: {}", index.manifest.types);
-    println!("  Contracts: {}", index.manifest.contracts);
+/// Write the generator's current index and summarize it
+fn write_project_index(path: &Path, generator: &bmb::index::IndexGenerator) -> Result<CliOutcome, Box<dyn std::error::Error>> {
+    let index = generator.snapshot();
+    bmb::index::write_index(&index, path)?;
 
-    Ok(())
+    if is_human_output() {
+        println!("✓ Index generated: .bmb/index/");
+        println!("  Files: {}", index.manifest.files);
+        println!("  Functions: {}", index.manifest.functions);
+        println!("  Types: {}", index.manifest.types);
+        println!("  Contracts: {}", index.manifest.contracts);
+    }
+
+    Ok(CliOutcome::new("index")
+        .count("files", index.manifest.files)
+        .count("functions", index.manifest.functions)
+        .count("types", index.manifest.types)
+        .count("contracts", index.manifest.contracts))
 }
 
 /// v0.50.21: Watch for file changes and re-index automatically
-fn run_index_watcher(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
+/// Only the files named in a debounced batch are re-parsed; deleted files
+/// are dropped from the index.
+fn run_index_watcher(path: &Path, mut generator: bmb::index::IndexGenerator, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
     use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+    use std::collections::BTreeSet;
     use std::sync::mpsc::channel;
     use std::time::Duration;
 
     println!("👀 Watching for changes... (Press Ctrl+C to stop)");
 
+    // Event paths may be absolute; map them back under `path` so they
+    // match the names the files were first indexed under
+    let root = path.canonicalize()?;
+    let project_path = |changed: &Path| match changed.strip_prefix(&root) {
+        Ok(relative) => path.join(relative),
+        Err(_) => changed.to_path_buf(),
+    };
+
     // Create a channel to receive events
     let (tx, rx) = channel();
 
@@ -2149,7 +2732,7 @@ fn run_index_watcher(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::e
     let mut debouncer = new_debouncer(Duration::from_millis(500), tx)?;
 
     // Watch the directory recursively
-    debouncer.watcher().watch(path.as_path(), RecursiveMode::Recursive)?;
+    debouncer.watcher().watch(path, RecursiveMode::Recursive)?;
 
     // Process events
     loop {
@@ -2157,21 +2740,36 @@ fn run_index_watcher(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::e
             Ok(result) => {
                 match result {
                     Ok(events) => {
-                        // Check if any .bmb file changed
-                        let bmb_changed = events.iter().any(|e| {
-                            e.path.extension().is_some_and(|ext| ext == "bmb")
-                        });
-
-                        if bmb_changed {
+                        // Collect the .bmb files that changed
+                        let changed: BTreeSet<PathBuf> = events
+                            .iter()
+                            .filter(|e| e.path.extension().is_some_and(|ext| ext == "bmb"))
+                            .map(|e| project_path(&e.path))
+                            .collect();
+
+                        if !changed.is_empty() {
                             if verbose {
-                                println!("\n📝 Detected .bmb file change, re-indexing...");
+                                println!("\n📝 Detected {} changed .bmb file(s), re-indexing...", changed.len());
                             } else {
                                 println!("\n🔄 Re-indexing...");
                             }
 
-                            // Re-index the project
-                            if let Err(e) = do_index_project(path, verbose) {
-                                eprintln!("  Error during re-index: {}", e);
+                            for file in &changed {
+                                match std::fs::read_to_string(file) {
+                                    Ok(source) => index_bmb_file(&mut generator, file, &source, verbose),
+                                    Err(_) if !file.exists() => {
+                                        generator.remove_file(&file.display().to_string());
+                                        if verbose {
+                                            println!("  Removed: {}", file.display());
+                                        }
+                                    }
+                                    Err(e) => eprintln!("  Error reading {}: {}", file.display(), e),
+                                }
+                            }
+
+                            match write_project_index(path, &generator) {
+                                Ok(outcome) => outcome.emit(is_human_output()),
+                                Err(e) => eprintln!("  Error during re-index: {}", e),
                             }
                         }
                     }
@@ -2191,20 +2789,15 @@ fn run_index_watcher(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::e
 }
 
 /// v0.25: Run query against project index
-fn run_query(query_type: QueryType) -> Result<(), Box<dyn std::error::Error>> {
+fn run_query(query_type: QueryType) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     use bmb::index::{read_index, SymbolKind};
     use bmb::query::{QueryEngine, format_output};
 
     // Try to read index from current directory
     let current_dir = std::env::current_dir()?;
-    let index = match read_index(&current_dir) {
-        Ok(idx) => idx,
-        Err(e) => {
-            eprintln!("Error: No index found at {:?}. Run 'bmb index' first.", current_dir);
-            eprintln!("  Detail: {}", e);
-            std::process::exit(1);
-        }
-    };
+    let index = read_index(&current_dir).map_err(|e| {
+        UsageError(format!("no index found at {}; run 'bmb index' first ({})", current_dir.display(), e))
+    })?;
 
     let engine = QueryEngine::new(index);
 
@@ -2223,6 +2816,7 @@ fn run_query(query_type: QueryType) -> Result<(), Box<dyn std::error::Error>> {
                 "enum" => Some(SymbolKind::Enum),
                 "type" => Some(SymbolKind::Type),
                 "trait" => Some(SymbolKind::Trait),
+                "const" => Some(SymbolKind::Const),
                 _ => None,
             });
 
@@ -2260,11 +2854,36 @@ fn run_query(query_type: QueryType) -> Result<(), Box<dyn std::error::Error>> {
             println!("{}", format_output(&metrics, fmt_str(format))?);
         }
 
+        QueryType::Todo { format } => {
+            let result = engine.query_todos();
+            match format {
+                OutputFormat::Llm => println!("{}", result.to_checklist()),
+                _ => println!("{}", format_output(&result, fmt_str(format))?),
+            }
+            return Ok(CliOutcome::new("q").count("todos", result.total));
+        }
+
+        QueryType::Unused { format } => {
+            let result = engine.query_unused();
+            println!("{}", format_output(&result, fmt_str(format))?);
+            return Ok(CliOutcome::new("q").count("unused", result.total));
+        }
+
         QueryType::Deps { target, reverse, transitive, format } => {
             let result = engine.query_deps(&target, reverse, transitive);
             println!("{}", format_output(&result, fmt_str(format))?);
         }
 
+        QueryType::Callers { target, transitive, format } => {
+            let result = engine.query_callers(&target, transitive);
+            println!("{}", format_output(&result, fmt_str(format))?);
+        }
+
+        QueryType::Callees { target, transitive, format } => {
+            let result = engine.query_callees(&target, transitive);
+            println!("{}", format_output(&result, fmt_str(format))?);
+        }
+
         QueryType::Contract { name, uses_old, format } => {
             let result = engine.query_contract(&name, uses_old);
             println!("{}", format_output(&result, fmt_str(format))?);
@@ -2290,8 +2909,8 @@ fn run_query(query_type: QueryType) -> Result<(), Box<dyn std::error::Error>> {
             println!("{}", format_output(&result, fmt_str(format))?);
         }
 
-        QueryType::Serve { port, host } => {
-            return run_query_server(&host, port, engine);
+        QueryType::Serve { port, host, max_connections } => {
+            run_query_server(&host, port, max_connections, engine)?;
         }
 
         QueryType::Proof { name, unverified, failed, timeout, format } => {
@@ -2332,210 +2951,145 @@ fn run_query(query_type: QueryType) -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    Ok(())
+    Ok(CliOutcome::new("q"))
 }
 
 /// v0.50.22: HTTP query server for AI tools (RFC-0001 Task 50.7)
+///
+/// Connections are served by a pool of `max_connections` worker threads
+/// sharing the read-only engine. Ctrl+C stops accepting, lets the workers
+/// finish the connections already accepted, and prints a summary.
 fn run_query_server(
     host: &str,
     port: u16,
+    max_connections: usize,
     engine: bmb::query::QueryEngine,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    use std::io::Read;
-    use std::net::TcpListener;
-    use bmb::query::format_output;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Instant;
 
-    let addr = format!("{}:{}", host, port);
-    let listener = TcpListener::bind(&addr)?;
+    let listener = TcpListener::bind(format!("{}:{}", host, port))?;
+    let max_connections = max_connections.max(1);
 
     println!("BMB Query Server v0.50.22");
-    println!("Listening on http://{}", addr);
+    println!("Listening on http://{}", listener.local_addr()?);
     println!("Endpoints:");
     println!("  GET  /health      - Health check");
     println!("  POST /query       - Run query (JSON body)");
     println!("  GET  /metrics     - Project metrics");
-    println!("Press Ctrl+C to stop");
+    println!("Serving up to {} connections at once; press Ctrl+C to stop", max_connections);
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = Arc::clone(&shutdown);
+        // Connecting wakes the accept loop, which then sees the flag
+        let mut wake_addr = listener.local_addr()?;
+        if wake_addr.ip().is_unspecified() {
+            wake_addr.set_ip(match wake_addr {
+                std::net::SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                std::net::SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        std::thread::spawn(move || {
+            let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+                return;
+            };
+            if runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+                shutdown.store(true, Ordering::SeqCst);
+                let _ = TcpStream::connect(wake_addr);
+            }
+        });
+    }
+
+    let started = Instant::now();
+    let engine = Arc::new(engine);
+    let stats = Arc::new(QueryServerStats::default());
+    // Bounded, so a burst of connections waits in the listen backlog
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(max_connections);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers: Vec<_> = (0..max_connections)
+        .map(|_| {
+            let (engine, stats, receiver) = (Arc::clone(&engine), Arc::clone(&stats), Arc::clone(&receiver));
+            std::thread::spawn(move || {
+                loop {
+                    let next = match receiver.lock() {
+                        Ok(queue) => queue.recv(),
+                        Err(_) => break,
+                    };
+                    let Ok(stream) = next else { break };
+                    handle_query_connection(stream, &engine, &stats);
+                }
+            })
+        })
+        .collect();
 
     for stream in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
         match stream {
-            Ok(mut stream) => {
-                // Read request
-                let mut buffer = [0; 8192];
-                let n = stream.read(&mut buffer)?;
-                let request = String::from_utf8_lossy(&buffer[..n]);
-
-                // Parse request line
-                let first_line = request.lines().next().unwrap_or("");
-                let parts: Vec<&str> = first_line.split_whitespace().collect();
-
-                if parts.len() < 2 {
-                    send_response(&mut stream, 400, "Bad Request")?;
-                    continue;
+            Ok(stream) => {
+                if sender.send(stream).is_err() {
+                    break;
                 }
-
-                let method = parts[0];
-                let path = parts[1];
-
-                // Route request
-                let (status, body) = match (method, path) {
-                    ("GET", "/health") => {
-                        (200, r#"{"status":"ok","version":"0.50.22"}"#.to_string())
-                    }
-                    ("GET", "/metrics") => {
-                        let metrics = engine.query_metrics();
-                        match format_output(&metrics, "json") {
-                            Ok(json) => (200, json),
-                            Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-                        }
-                    }
-                    ("POST", "/query") => {
-                        // Extract JSON body
-                        let body_start = request.find("\r\n\r\n").map(|i| i + 4)
-                            .or_else(|| request.find("\n\n").map(|i| i + 2));
-
-                        match body_start {
-                            Some(start) => {
-                                let json_body = &request[start..];
-                                handle_query_request(&engine, json_body.trim())
-                            }
-                            None => (400, r#"{"error":"No request body"}"#.to_string()),
-                        }
-                    }
-                    _ => {
-                        (404, r#"{"error":"Not found"}"#.to_string())
-                    }
-                };
-
-                send_json_response(&mut stream, status, &body)?;
-            }
-            Err(e) => {
-                eprintln!("Connection error: {}", e);
             }
+            Err(e) => eprintln!("Connection error: {}", e),
         }
     }
 
+    println!("Shutting down: finishing active connections...");
+    drop(listener);
+    // Workers exit once the queued connections are handled
+    drop(sender);
+    for worker in workers {
+        let _ = worker.join();
+    }
+    println!(
+        "Served {} requests ({} failed) in {:.1}s",
+        stats.served.load(Ordering::SeqCst),
+        stats.failed.load(Ordering::SeqCst),
+        started.elapsed().as_secs_f64()
+    );
+
     Ok(())
 }
 
-/// Handle POST /query request
-fn handle_query_request(engine: &bmb::query::QueryEngine, json_body: &str) -> (u16, String) {
-    use bmb::query::format_output;
+/// Request counts of the query server, for the shutdown summary
+#[derive(Default)]
+struct QueryServerStats {
+    /// Requests answered, whatever the status
+    served: std::sync::atomic::AtomicUsize,
+    /// Requests answered with an error status, or that could not be read
+    failed: std::sync::atomic::AtomicUsize,
+}
 
-    // Parse query JSON
-    let query: serde_json::Value = match serde_json::from_str(json_body) {
-        Ok(v) => v,
-        Err(e) => return (400, format!(r#"{{"error":"Invalid JSON: {}"}}"#, e)),
-    };
+/// Serve one query server connection: read the request, route it, respond
+fn handle_query_connection(
+    mut stream: std::net::TcpStream,
+    engine: &bmb::query::QueryEngine,
+    stats: &QueryServerStats,
+) {
+    use bmb::query::http;
 
-    let query_type = query.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    // A stalled client must not hold up shutdown
+    let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(10)));
 
-    match query_type {
-        "sym" => {
-            let pattern = query.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
-            let public = query.get("public").and_then(|v| v.as_bool()).unwrap_or(false);
-            let result = engine.query_symbols(pattern, None, public);
-            match format_output(&result, "json") {
-                Ok(json) => (200, json),
-                Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-            }
-        }
-        "fn" => {
-            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            if !name.is_empty() {
-                let result = engine.query_function(name);
-                match format_output(&result, "json") {
-                    Ok(json) => (200, json),
-                    Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-                }
-            } else {
-                (400, r#"{"error":"Missing 'name' field"}"#.to_string())
-            }
-        }
-        "type" => {
-            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            if !name.is_empty() {
-                let result = engine.query_type(name);
-                match format_output(&result, "json") {
-                    Ok(json) => (200, json),
-                    Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-                }
-            } else {
-                (400, r#"{"error":"Missing 'name' field"}"#.to_string())
-            }
-        }
-        "metrics" => {
-            let result = engine.query_metrics();
-            match format_output(&result, "json") {
-                Ok(json) => (200, json),
-                Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-            }
-        }
-        "deps" => {
-            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
-            let reverse = query.get("reverse").and_then(|v| v.as_bool()).unwrap_or(false);
-            let transitive = query.get("transitive").and_then(|v| v.as_bool()).unwrap_or(false);
-            let result = engine.query_deps(target, reverse, transitive);
-            match format_output(&result, "json") {
-                Ok(json) => (200, json),
-                Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-            }
-        }
-        "contract" => {
-            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            let uses_old = query.get("uses_old").and_then(|v| v.as_bool()).unwrap_or(false);
-            let result = engine.query_contract(name, uses_old);
-            match format_output(&result, "json") {
-                Ok(json) => (200, json),
-                Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-            }
-        }
-        "impact" => {
-            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
-            let change = query.get("change").and_then(|v| v.as_str()).unwrap_or("");
-            let result = engine.query_impact(target, change);
-            match format_output(&result, "json") {
-                Ok(json) => (200, json),
-                Err(e) => (500, format!(r#"{{"error":"{}"}}"#, e)),
-            }
-        }
-        _ => {
-            (400, format!(r#"{{"error":"Unknown query type: {}"}}"#, query_type))
+    let (status, body) = match http::read_request(&mut stream) {
+        Ok((head, body)) => http::route_request(engine, &head, &body),
+        Err(e) => {
+            eprintln!("Read error: {}", e);
+            stats.failed.fetch_add(1, Ordering::SeqCst);
+            return;
         }
-    }
-}
-
-/// Send HTTP response with status code and body
-fn send_response(stream: &mut std::net::TcpStream, status: u16, body: &str) -> std::io::Result<()> {
-    use std::io::Write;
-    let status_text = match status {
-        200 => "OK",
-        400 => "Bad Request",
-        404 => "Not Found",
-        500 => "Internal Server Error",
-        _ => "Unknown",
-    };
-    let response = format!(
-        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
-        status, status_text, body.len(), body
-    );
-    stream.write_all(response.as_bytes())
-}
-
-/// Send JSON HTTP response
-fn send_json_response(stream: &mut std::net::TcpStream, status: u16, body: &str) -> std::io::Result<()> {
-    use std::io::Write;
-    let status_text = match status {
-        200 => "OK",
-        400 => "Bad Request",
-        404 => "Not Found",
-        500 => "Internal Server Error",
-        _ => "Unknown",
     };
-    let response = format!(
-        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
-        status, status_text, body.len(), body
-    );
-    stream.write_all(response.as_bytes())
+    stats.served.fetch_add(1, Ordering::SeqCst);
+    if status >= 400 {
+        stats.failed.fetch_add(1, Ordering::SeqCst);
+    }
+    if let Err(e) = http::write_json_response(&mut stream, status, &body) {
+        eprintln!("Write error: {}", e);
+    }
 }
 
 /// v0.30.246: Stage 3 self-hosting verification
@@ -2544,7 +3098,7 @@ fn verify_stage3(
     path: &PathBuf,
     verbose: bool,
     output: Option<&PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<CliOutcome, Box<dyn std::error::Error>> {
     println!("Stage 3 Verification: {}", path.display());
     println!("==========================================");
 
@@ -2667,11 +3221,7 @@ fn verify_stage3(
         println!("\nReport written to: {}", out_path.display());
     }
 
-    if is_exact_match || is_semantic_match {
-        Ok(())
-    } else {
-        Err("Stage 3 verification failed: IR mismatch".into())
-    }
+    Ok(CliOutcome::new("verify-stage3").failed_if(!is_exact_match && !is_semantic_match))
 }
 
 /// Generate LLVM IR using Rust compiler pipeline
@@ -0,0 +1,224 @@
+//! Graphviz DOT rendering of MIR control-flow graphs
+//!
+//! Each function becomes its own `digraph` whose nodes are basic blocks
+//! (labelled with their instructions and terminator) and whose edges are
+//! the jumps out of each terminator. The function signature and contract
+//! facts are written as a comment above every graph so a rendered diagram
+//! can be traced back to its source without the MIR text alongside it.
+//!
+//! Lives here rather than in `cfg`, which is conditional compilation.
+
+use super::{
+    format_mir_inst, format_mir_type, format_terminator, CmpOp, ContractFact, MirFunction,
+    MirProgram, Terminator,
+};
+
+/// Format every function of a MIR program as a DOT digraph
+pub fn format_cfg_dot(program: &MirProgram) -> String {
+    let mut output = String::new();
+
+    for (i, func) in program.functions.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&format_function_dot(func));
+    }
+
+    output
+}
+
+/// Format a single function as a DOT digraph
+fn format_function_dot(func: &MirFunction) -> String {
+    let mut out = String::new();
+
+    // Header comment: signature and contracts
+    let params_str: Vec<_> = func.params.iter()
+        .map(|(name, ty)| format!("{}: {}", name, format_mir_type(ty)))
+        .collect();
+    out.push_str(&format!("// fn {}({}) -> {}\n",
+        func.name,
+        params_str.join(", "),
+        format_mir_type(&func.ret_ty)));
+    for fact in &func.preconditions {
+        out.push_str(&format!("//   pre {}\n", format_fact(fact)));
+    }
+    for fact in &func.postconditions {
+        out.push_str(&format!("//   post {}\n", format_fact(fact)));
+    }
+
+    out.push_str(&format!("digraph {} {{\n", quote(&func.name)));
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    // Nodes: one per block, instructions left-aligned
+    for block in &func.blocks {
+        let mut label = format!("{}:\\l", escape(&block.label));
+        for inst in &block.instructions {
+            label.push_str(&format!("  {}\\l", escape(&format_mir_inst(inst))));
+        }
+        label.push_str(&format!("  {}\\l", escape(&format_terminator(&block.terminator))));
+        out.push_str(&format!("  {} [label=\"{}\"];\n", quote(&block.label), label));
+    }
+
+    // Edges: one per terminator successor
+    for block in &func.blocks {
+        let from = quote(&block.label);
+        match &block.terminator {
+            Terminator::Goto(target) => {
+                out.push_str(&format!("  {} -> {};\n", from, quote(target)));
+            }
+            Terminator::Branch { then_label, else_label, .. } => {
+                out.push_str(&format!("  {} -> {} [label=\"true\"];\n", from, quote(then_label)));
+                out.push_str(&format!("  {} -> {} [label=\"false\"];\n", from, quote(else_label)));
+            }
+            Terminator::Switch { cases, default, .. } => {
+                for (value, target) in cases {
+                    out.push_str(&format!("  {} -> {} [label=\"{}\"];\n", from, quote(target), value));
+                }
+                out.push_str(&format!("  {} -> {} [label=\"default\"];\n", from, quote(default)));
+            }
+            Terminator::Return(_) | Terminator::Unreachable => {}
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Format a contract fact as a source-like condition
+fn format_fact(fact: &ContractFact) -> String {
+    match fact {
+        ContractFact::VarCmp { var, op, value } => {
+            format!("{} {} {}", var, format_cmp_op(*op), value)
+        }
+        ContractFact::VarVarCmp { lhs, op, rhs } => {
+            format!("{} {} {}", lhs, format_cmp_op(*op), rhs)
+        }
+        ContractFact::ArrayBounds { index, array } => format!("{} < len({})", index, array),
+        ContractFact::NonNull { var } => format!("{} != null", var),
+    }
+}
+
+fn format_cmp_op(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Lt => "<",
+        CmpOp::Le => "<=",
+        CmpOp::Gt => ">",
+        CmpOp::Ge => ">=",
+        CmpOp::Eq => "==",
+        CmpOp::Ne => "!=",
+    }
+}
+
+/// Quote an identifier for use as a DOT node or graph id
+fn quote(id: &str) -> String {
+    format!("\"{}\"", escape(id))
+}
+
+/// Escape text for a double-quoted DOT string
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\l"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mir::{BasicBlock, Constant, MirBinOp, MirInst, MirType, Operand, Place};
+
+    fn make_max_function() -> MirFunction {
+        MirFunction {
+            name: "max".to_string(),
+            params: vec![("a".to_string(), MirType::I64), ("b".to_string(), MirType::I64)],
+            ret_ty: MirType::I64,
+            locals: vec![],
+            blocks: vec![
+                BasicBlock {
+                    label: "entry".to_string(),
+                    instructions: vec![MirInst::BinOp {
+                        dest: Place::new("c"),
+                        op: MirBinOp::Gt,
+                        lhs: Operand::Place(Place::new("a")),
+                        rhs: Operand::Place(Place::new("b")),
+                    }],
+                    terminator: Terminator::Branch {
+                        cond: Operand::Place(Place::new("c")),
+                        then_label: "then_0".to_string(),
+                        else_label: "else_0".to_string(),
+                    },
+                },
+                BasicBlock {
+                    label: "then_0".to_string(),
+                    instructions: vec![],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("a")))),
+                },
+                BasicBlock {
+                    label: "else_0".to_string(),
+                    instructions: vec![],
+                    terminator: Terminator::Return(Some(Operand::Place(Place::new("b")))),
+                },
+            ],
+            preconditions: vec![ContractFact::VarCmp {
+                var: "a".to_string(),
+                op: CmpOp::Ge,
+                value: 0,
+            }],
+            postconditions: vec![ContractFact::VarVarCmp {
+                lhs: "ret".to_string(),
+                op: CmpOp::Ge,
+                rhs: "a".to_string(),
+            }],
+            is_pure: true,
+            is_const: false,
+            is_inline: false,
+            is_public: false,
+        }
+    }
+
+    #[test]
+    fn test_cfg_dot_blocks_and_branch_edges() {
+        let program = MirProgram { functions: vec![make_max_function()], extern_fns: vec![] };
+        let dot = format_cfg_dot(&program);
+
+        assert!(dot.contains("digraph \"max\" {"));
+        assert!(dot.contains("\"entry\" [label=\"entry:\\l  %c = > %a, %b\\l  branch %c, then_0, else_0\\l\"];"));
+        assert!(dot.contains("\"entry\" -> \"then_0\" [label=\"true\"];"));
+        assert!(dot.contains("\"entry\" -> \"else_0\" [label=\"false\"];"));
+        assert!(!dot.contains("\"then_0\" ->"));
+    }
+
+    #[test]
+    fn test_cfg_dot_header_lists_contracts() {
+        let program = MirProgram { functions: vec![make_max_function()], extern_fns: vec![] };
+        let dot = format_cfg_dot(&program);
+
+        assert!(dot.starts_with("// fn max(a: i64, b: i64) -> i64\n//   pre a >= 0\n//   post ret >= a\ndigraph"));
+    }
+
+    #[test]
+    fn test_cfg_dot_switch_edges_and_escaping() {
+        let mut func = make_max_function();
+        func.blocks[0].instructions = vec![MirInst::Const {
+            dest: Place::new("s"),
+            value: Constant::String("say \"hi\"".to_string()),
+        }];
+        func.blocks[0].terminator = Terminator::Switch {
+            discriminant: Operand::Place(Place::new("a")),
+            cases: vec![(0, "then_0".to_string())],
+            default: "else_0".to_string(),
+        };
+        let program = MirProgram { functions: vec![func], extern_fns: vec![] };
+        let dot = format_cfg_dot(&program);
+
+        assert!(dot.contains("\\\"hi\\\""));
+        assert!(dot.contains("\"entry\" -> \"then_0\" [label=\"0\"];"));
+        assert!(dot.contains("\"entry\" -> \"else_0\" [label=\"default\"];"));
+    }
+}
@@ -5,34 +5,131 @@
 //! - Making control flow explicit through basic blocks
 //! - Converting operators based on operand types
 
-use crate::ast::{Attribute, BinOp, Expr, FnDef, Item, LiteralPattern, MatchArm, Pattern, Program, Spanned, Type, UnOp};
+use crate::ast::{
+    Attribute, BinOp, ClosureParam, Expr, FnDef, Item, LiteralPattern, MatchArm, Pattern, Program, Spanned, StateKind, Type, UnOp,
+};
+use crate::types::consts::ConstValue;
 
 use super::{
-    CmpOp, Constant, ContractFact, LoweringContext, MirBinOp, MirExternFn, MirFunction, MirInst,
-    MirProgram, MirType, MirUnaryOp, Operand, Place, Terminator,
+    variant_discriminant, CmpOp, Constant, ContractFact, LoweringContext, MirBinOp, MirExternFn,
+    MirFunction, MirInst, MirProgram, MirType, MirUnaryOp, Operand, Place, Terminator,
+    CLOSURE_ENV_FIELD, CLOSURE_FN_FIELD, ENUM_TAG_FIELD,
 };
 
+/// How `pre`/`post` conditions are treated when lowering to MIR
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContractMode {
+    /// Contracts are dropped; they only feed contract-based optimizations
+    #[default]
+    Off,
+    /// Contracts are checked at runtime; a violation calls `bmb_contract_fail`,
+    /// as does integer overflow in `+`, `-` and `*`
+    Check,
+}
+
+/// Runtime function called when a checked contract fails.
+/// Takes a message string, reports it and aborts with exit code 101.
+pub const CONTRACT_FAIL_FN: &str = "bmb_contract_fail";
+
 /// Lower an entire program to MIR
 pub fn lower_program(program: &Program) -> MirProgram {
+    lower_program_with_contracts(program, ContractMode::Off)
+}
+
+/// Lower an entire program to MIR, treating contracts according to `contracts`
+pub fn lower_program_with_contracts(program: &Program, contracts: ContractMode) -> MirProgram {
+    lower(program, contracts, false)
+}
+
+/// Like [`lower_program_with_contracts`], also recording the source location
+/// of every statement as a [`MirInst::DebugLoc`] for debug info
+pub fn lower_program_with_debug_info(program: &Program, contracts: ContractMode) -> MirProgram {
+    lower(program, contracts, true)
+}
+
+fn lower(program: &Program, contracts: ContractMode, debug_info: bool) -> MirProgram {
+
+    // Constants are substituted at their use sites; invalid initializers are
+    // rejected by the type checker before lowering
+    let consts: std::collections::HashMap<String, Constant> = crate::types::consts::eval_consts(program)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                ConstValue::Int(n) => Constant::Int(n),
+                ConstValue::Float(f) => Constant::Float(f),
+                ConstValue::Bool(b) => Constant::Bool(b),
+                ConstValue::Char(c) => Constant::Char(c),
+                ConstValue::Str(s) => Constant::String(s),
+            };
+            (name, value)
+        })
+        .collect();
+
+    let struct_fields: std::collections::HashMap<String, Vec<crate::ast::StructField>> = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::StructDef(s) => Some((s.name.node.clone(), s.fields.clone())),
+            _ => None,
+        })
+        .collect();
+
     // v0.35.4: First pass - collect all function return types
     let mut func_return_types = std::collections::HashMap::new();
     for item in &program.items {
         if let Item::FnDef(fn_def) = item {
-            let ret_ty = ast_type_to_mir(&fn_def.ret_ty.node);
+            let ret_ty = struct_aware_type(&fn_def.ret_ty.node, &struct_fields, None);
             func_return_types.insert(fn_def.name.node.clone(), ret_ty);
         }
     }
 
-    let functions = program
+    // Inherent impl methods lower to ordinary functions named `Type.method`,
+    // including the `eq` and `show` methods of `@derive(Eq)` and `@derive(Show)`
+    let mut inherent_methods = std::collections::HashMap::new();
+    let mut methods = Vec::new();
+    let derived = crate::derive::derived_impls(program);
+    let impl_blocks = program
         .items
         .iter()
         .filter_map(|item| match item {
-            Item::FnDef(fn_def) => Some(lower_function(fn_def, &func_return_types)),
-            // Type definitions, use statements, extern fns, traits, impl blocks, and type aliases don't produce MIR functions
+            Item::ImplBlock(impl_block) => Some(impl_block),
+            _ => None,
+        })
+        .chain(&derived);
+    for impl_block in impl_blocks {
+        if impl_block.trait_name.is_some() {
+            continue;
+        }
+        let Some(type_name) = nominal_type_name(&impl_block.target_type.node, None) else {
+            continue;
+        };
+        for method in &impl_block.methods {
+            let mut fn_def = method.clone();
+            fn_def.name.node = format!("{}.{}", type_name, method.name.node);
+            let ret_ty = struct_aware_type(&fn_def.ret_ty.node, &struct_fields, Some(&type_name));
+            func_return_types.insert(fn_def.name.node.clone(), ret_ty);
+            inherent_methods.insert((type_name.clone(), method.name.node.clone()), fn_def.name.node.clone());
+            methods.push((fn_def, type_name.clone()));
+        }
+    }
+
+    let mut functions: Vec<_> = program
+        .items
+        .iter()
+        .flat_map(|item| match item {
+            Item::FnDef(fn_def) => {
+                Some(lower_function(fn_def, None, &func_return_types, &inherent_methods, &consts, &struct_fields, contracts, debug_info))
+            }
+            // Type definitions, use statements, extern fns, traits, impl blocks, type aliases and constants don't produce MIR functions
             Item::StructDef(_) | Item::EnumDef(_) | Item::Use(_) | Item::ExternFn(_) |
-            Item::TraitDef(_) | Item::ImplBlock(_) | Item::TypeAlias(_) => None,
+            Item::TraitDef(_) | Item::ImplBlock(_) | Item::TypeAlias(_) | Item::ConstDef(_) => None,
         })
+        .flatten()
         .collect();
+    functions.extend(methods.iter().flat_map(|(fn_def, type_name)| {
+        lower_function(fn_def, Some(type_name), &func_return_types, &inherent_methods, &consts, &struct_fields, contracts, debug_info)
+    }));
 
     // Collect extern function declarations (v0.13.0)
     let extern_fns = program
@@ -88,9 +185,26 @@ fn extract_module_from_attrs(attrs: &[Attribute]) -> String {
     "env".to_string()
 }
 
-/// Lower a function definition to MIR
-fn lower_function(fn_def: &FnDef, func_return_types: &std::collections::HashMap<String, MirType>) -> MirFunction {
+/// Lower a function definition to MIR; `self_type` is the impl type of a
+/// method. The function comes first, followed by the closures lifted out of it.
+#[allow(clippy::too_many_arguments)]
+fn lower_function(
+    fn_def: &FnDef,
+    self_type: Option<&str>,
+    func_return_types: &std::collections::HashMap<String, MirType>,
+    inherent_methods: &std::collections::HashMap<(String, String), String>,
+    consts: &std::collections::HashMap<String, Constant>,
+    struct_fields: &std::collections::HashMap<String, Vec<crate::ast::StructField>>,
+    contracts: ContractMode,
+    debug_info: bool,
+) -> Vec<MirFunction> {
     let mut ctx = LoweringContext::new();
+    ctx.fn_name = fn_def.name.node.clone();
+    ctx.inherent_methods = inherent_methods.clone();
+    ctx.consts = consts.clone();
+    ctx.struct_fields = struct_fields.clone();
+    ctx.debug_info = debug_info;
+    ctx.debug_loc(fn_def.name.span);
 
     // v0.35.4: Add user-defined function return types to context
     for (name, ty) in func_return_types {
@@ -102,32 +216,59 @@ fn lower_function(fn_def: &FnDef, func_return_types: &std::collections::HashMap<
         .params
         .iter()
         .map(|p| {
-            let ty = ast_type_to_mir(&p.ty.node);
+            let ty = struct_aware_type(&p.ty.node, struct_fields, self_type);
             ctx.params.insert(p.name.node.clone(), ty.clone());
+            if is_hashmap_type(&p.ty.node) {
+                ctx.map_vars.insert(p.name.node.clone());
+            }
+            if let Some(type_name) = nominal_type_name(&p.ty.node, self_type) {
+                ctx.var_types.insert(p.name.node.clone(), type_name);
+            }
             (p.name.node.clone(), ty)
         })
         .collect();
 
-    let ret_ty = ast_type_to_mir(&fn_def.ret_ty.node);
+    let ret_ty = struct_aware_type(&fn_def.ret_ty.node, struct_fields, self_type);
+
+    let check_contracts = contracts == ContractMode::Check;
+    let fn_name = &fn_def.name.node;
+    ctx.check_overflow = check_contracts;
+
+    if check_contracts && let Some(pre) = &fn_def.pre {
+        lower_contract_check(pre, "precondition", fn_name, &mut ctx);
+    }
 
     // Lower the function body
     let result = lower_expr(&fn_def.body, &mut ctx);
 
+    if check_contracts && let Some(post) = &fn_def.post {
+        ctx.ret_value = Some(result.clone());
+        lower_contract_check(post, "postcondition", fn_name, &mut ctx);
+        ctx.ret_value = None;
+    }
+
     // Finish with a return
     ctx.finish_block(Terminator::Return(Some(result)));
 
     // Collect locals
     let locals: Vec<(String, MirType)> = ctx.locals.clone().into_iter().collect();
 
-    // v0.38: Extract contract facts for optimization
-    let preconditions = extract_contract_facts(fn_def.pre.as_ref());
+    // v0.38: Extract contract facts for optimization.
+    // Checked preconditions are not recorded: the optimizer would use them
+    // to fold away the very comparisons that check them.
+    let preconditions = if check_contracts {
+        Vec::new()
+    } else {
+        extract_contract_facts(fn_def.pre.as_ref())
+    };
     let postconditions = extract_contract_facts(fn_def.post.as_ref());
 
     // v0.38.3: Extract @pure and @const attributes
     let is_pure = has_attribute(&fn_def.attributes, "pure");
     let is_const = has_attribute(&fn_def.attributes, "const");
+    let is_inline = fn_def.attributes.iter().any(|attr| attr.is_inline());
 
-    MirFunction {
+    let function = MirFunction {
         name: fn_def.name.node.clone(),
         params,
         ret_ty,
@@ -137,6 +278,257 @@ fn lower_function(fn_def: &FnDef, func_return_types: &std::collections::HashMap<
         postconditions,
         is_pure,
         is_const,
+        is_inline,
+        is_public: fn_def.visibility == crate::ast::Visibility::Public,
+    };
+    std::iter::once(function).chain(ctx.closures).collect()
+}
+
+/// Parameter of a lifted closure that holds its environment
+const CLOSURE_ENV_PARAM: &str = "__env";
+
+/// Closure conversion: lift the closure body into a function whose first
+/// parameter is the environment, and build the closure value, a
+/// (function address, environment) pair, where the closure is created.
+///
+/// Captures are copies of every enclosing local or parameter the body
+/// mentions, taken when the closure is created, as in the interpreter.
+fn lower_closure(
+    params: &[ClosureParam],
+    ret_ty: Option<&Spanned<Type>>,
+    body: &Spanned<Expr>,
+    ctx: &mut LoweringContext,
+) -> Operand {
+    let name = ctx.fresh_closure_name();
+    let captures: Vec<(String, MirType)> = body.node.mentioned_names().into_iter()
+        .filter(|capture| !params.iter().any(|p| p.name.node == *capture))
+        .filter_map(|capture| {
+            let ty = ctx.locals.get(&capture).or_else(|| ctx.params.get(&capture))?.clone();
+            Some((capture, ty))
+        })
+        .collect();
+
+    let mut inner = LoweringContext::new();
+    inner.fn_name = name.clone();
+    inner.func_return_types = ctx.func_return_types.clone();
+    inner.inherent_methods = ctx.inherent_methods.clone();
+    inner.consts = ctx.consts.clone();
+    inner.struct_fields = ctx.struct_fields.clone();
+    inner.debug_info = ctx.debug_info;
+    inner.check_overflow = ctx.check_overflow;
+    inner.debug_loc(body.span);
+
+    let mut fn_params = vec![(CLOSURE_ENV_PARAM.to_string(), MirType::I64)];
+    for param in params {
+        let ty = param.ty.as_ref()
+            .map_or(MirType::I64, |ty| struct_aware_type(&ty.node, &ctx.struct_fields, None));
+        inner.params.insert(param.name.node.clone(), ty.clone());
+        fn_params.push((param.name.node.clone(), ty));
+    }
+    for (i, (capture, ty)) in captures.iter().enumerate() {
+        inner.locals.insert(capture.clone(), ty.clone());
+        if ctx.map_vars.contains(capture) {
+            inner.map_vars.insert(capture.clone());
+        }
+        if let Some(type_name) = ctx.var_types.get(capture) {
+            inner.var_types.insert(capture.clone(), type_name.clone());
+        }
+        inner.push_inst(MirInst::FieldAccess {
+            dest: Place::new(capture.clone()),
+            base: Place::new(CLOSURE_ENV_PARAM),
+            field: format!("_{}", i),
+        });
+    }
+
+    let result = lower_expr(body, &mut inner);
+    let fn_ret_ty = match ret_ty {
+        Some(ty) => struct_aware_type(&ty.node, &ctx.struct_fields, None),
+        None => inner.operand_type(&result),
+    };
+    inner.finish_block(Terminator::Return(Some(result)));
+
+    let value_ty = MirType::Fn {
+        params: fn_params[1..].iter().map(|(_, ty)| ty.clone()).collect(),
+        ret: Box::new(fn_ret_ty.clone()),
+    };
+    ctx.closures.push(MirFunction {
+        name: name.clone(),
+        params: fn_params,
+        ret_ty: fn_ret_ty,
+        locals: inner.locals.into_iter().collect(),
+        blocks: inner.blocks,
+        preconditions: Vec::new(),
+        postconditions: Vec::new(),
+        is_pure: false,
+        is_const: false,
+        is_inline: false,
+        is_public: false,
+    });
+    ctx.closures.extend(inner.closures);
+
+    let env = if captures.is_empty() {
+        Operand::Constant(Constant::Int(0))
+    } else {
+        let env = ctx.fresh_temp();
+        ctx.locals.insert(env.name.clone(), MirType::I64);
+        ctx.push_inst(MirInst::EnumVariant {
+            dest: env.clone(),
+            enum_name: name.clone(),
+            variant: "env".to_string(),
+            args: captures.into_iter().map(|(capture, _)| Operand::Place(Place::new(capture))).collect(),
+        });
+        Operand::Place(env)
+    };
+    let value = ctx.fresh_temp();
+    ctx.locals.insert(value.name.clone(), value_ty);
+    ctx.push_inst(MirInst::EnumVariant {
+        dest: value.clone(),
+        enum_name: name.clone(),
+        variant: "closure".to_string(),
+        args: vec![Operand::Constant(Constant::FnAddr(name)), env],
+    });
+    Operand::Place(value)
+}
+
+/// Call the closure value in `callee` through its function address, with
+/// its environment as the first argument
+fn lower_closure_call(callee: &str, ret_ty: MirType, args: Vec<Operand>, ctx: &mut LoweringContext) -> Operand {
+    let fn_addr = ctx.fresh_temp();
+    ctx.locals.insert(fn_addr.name.clone(), MirType::I64);
+    ctx.push_inst(MirInst::FieldAccess {
+        dest: fn_addr.clone(),
+        base: Place::new(callee),
+        field: CLOSURE_FN_FIELD.to_string(),
+    });
+    let env = ctx.fresh_temp();
+    ctx.locals.insert(env.name.clone(), MirType::I64);
+    ctx.push_inst(MirInst::FieldAccess {
+        dest: env.clone(),
+        base: Place::new(callee),
+        field: CLOSURE_ENV_FIELD.to_string(),
+    });
+
+    let dest = (ret_ty != MirType::Unit).then(|| ctx.fresh_temp());
+    if let Some(dest) = &dest {
+        ctx.locals.insert(dest.name.clone(), ret_ty);
+    }
+    ctx.push_inst(MirInst::CallIndirect {
+        dest: dest.clone(),
+        callee: Operand::Place(fn_addr),
+        args: std::iter::once(Operand::Place(env)).chain(args).collect(),
+    });
+    dest.map_or(Operand::Constant(Constant::Unit), Operand::Place)
+}
+
+/// Emit a runtime check of a contract condition.
+///
+/// The current block branches on the condition; the failing side calls
+/// [`CONTRACT_FAIL_FN`] with a message naming the function and condition,
+/// and lowering continues in the passing block.
+fn lower_contract_check(cond: &Spanned<Expr>, kind: &str, fn_name: &str, ctx: &mut LoweringContext) {
+    let value = lower_expr(cond, ctx);
+    let ok_label = ctx.fresh_label("contract_ok");
+    let fail_label = ctx.fresh_label("contract_fail");
+
+    ctx.finish_block(Terminator::Branch {
+        cond: value,
+        then_label: ok_label.clone(),
+        else_label: fail_label.clone(),
+    });
+
+    ctx.start_block(fail_label);
+    let message = format!("{} failed in `{}`: {}", kind, fn_name, format_condition(&cond.node));
+    ctx.push_inst(MirInst::Call {
+        dest: None,
+        func: CONTRACT_FAIL_FN.to_string(),
+        args: vec![Operand::Constant(Constant::String(message))],
+    });
+    ctx.finish_block(Terminator::Unreachable);
+
+    ctx.start_block(ok_label);
+}
+
+/// `dest = lhs op rhs` that fails like a contract when it overflows: the
+/// overflow test of `op` branches straight to `bmb_contract_fail` with
+/// `message`, and the wrapped result is only computed past it
+fn lower_overflow_check(
+    dest: &Place,
+    op: MirBinOp,
+    lhs: Operand,
+    rhs: Operand,
+    message: String,
+    ctx: &mut LoweringContext,
+) {
+    let (overflow_op, wrapping_op) = match op {
+        MirBinOp::Add => (MirBinOp::AddOverflows, MirBinOp::AddWrap),
+        MirBinOp::Sub => (MirBinOp::SubOverflows, MirBinOp::SubWrap),
+        _ => (MirBinOp::MulOverflows, MirBinOp::MulWrap),
+    };
+    let overflowed = ctx.fresh_temp();
+    ctx.locals.insert(overflowed.name.clone(), MirType::Bool);
+    ctx.push_inst(MirInst::BinOp {
+        dest: overflowed.clone(),
+        op: overflow_op,
+        lhs: lhs.clone(),
+        rhs: rhs.clone(),
+    });
+
+    let ok_label = ctx.fresh_label("overflow_ok");
+    let fail_label = ctx.fresh_label("overflow_fail");
+    ctx.finish_block(Terminator::Branch {
+        cond: Operand::Place(overflowed),
+        then_label: fail_label.clone(),
+        else_label: ok_label.clone(),
+    });
+
+    ctx.start_block(fail_label);
+    ctx.push_inst(MirInst::Call {
+        dest: None,
+        func: CONTRACT_FAIL_FN.to_string(),
+        args: vec![Operand::Constant(Constant::String(message))],
+    });
+    ctx.finish_block(Terminator::Unreachable);
+
+    ctx.start_block(ok_label);
+    ctx.push_inst(MirInst::BinOp { dest: dest.clone(), op: wrapping_op, lhs, rhs });
+}
+
+/// Render a contract condition as infix source-like text for failure messages
+fn format_condition(expr: &Expr) -> String {
+    match expr {
+        Expr::IntLit(n) => n.to_string(),
+        Expr::FloatLit(f) => f.to_string(),
+        Expr::BoolLit(b) => b.to_string(),
+        Expr::StringLit(s) => format!("\"{}\"", s),
+        Expr::CharLit(c) => format!("'{}'", c.escape_default()),
+        Expr::Var(name) => name.clone(),
+        Expr::Ret => "ret".to_string(),
+        Expr::It => "it".to_string(),
+        Expr::Binary { left, op, right } => {
+            let operand = |e: &Expr| match e {
+                Expr::Binary { .. } => format!("({})", format_condition(e)),
+                _ => format_condition(e),
+            };
+            format!("{} {} {}", operand(&left.node), op, operand(&right.node))
+        }
+        Expr::Unary { op: UnOp::Neg, expr } => format!("-{}", format_condition(&expr.node)),
+        Expr::Unary { op, expr } => format!("{} {}", op, format_condition(&expr.node)),
+        Expr::Call { func, args } => {
+            let args: Vec<_> = args.iter().map(|a| format_condition(&a.node)).collect();
+            format!("{}({})", func, args.join(", "))
+        }
+        Expr::FieldAccess { expr, field } => {
+            format!("{}.{}", format_condition(&expr.node), field.node)
+        }
+        Expr::MethodCall { receiver, method, args } => {
+            let args: Vec<_> = args.iter().map(|a| format_condition(&a.node)).collect();
+            format!("{}.{}({})", format_condition(&receiver.node), method, args.join(", "))
+        }
+        Expr::Index { expr, index } => {
+            format!("{}[{}]", format_condition(&expr.node), format_condition(&index.node))
+        }
+        Expr::StateRef { expr, state } => format!("{}{}", format_condition(&expr.node), state),
+        other => crate::ast::output::format_expr(other),
     }
 }
 
@@ -221,6 +613,95 @@ fn flip_cmp_op(op: CmpOp) -> CmpOp {
     }
 }
 
+/// `min`/`max`/`abs` of f64 operands, which the runtime versions (i64 only)
+/// cannot take: a float compare picks one of the values. `abs(x)` is
+/// `max(x, -x)`. `None` for any other call.
+fn lower_float_min_max(func: &str, args: &[Operand], ctx: &mut LoweringContext) -> Option<Operand> {
+    if !args.first().is_some_and(|arg| ctx.operand_type(arg) == MirType::F64) {
+        return None;
+    }
+    let (op, first, second) = match (func, args) {
+        ("min", [a, b]) => (MirBinOp::FLe, a.clone(), b.clone()),
+        ("max", [a, b]) => (MirBinOp::FGe, a.clone(), b.clone()),
+        ("abs", [x]) => {
+            let negated = ctx.fresh_temp();
+            ctx.locals.insert(negated.name.clone(), MirType::F64);
+            ctx.push_inst(MirInst::UnaryOp { dest: negated.clone(), op: MirUnaryOp::FNeg, src: x.clone() });
+            (MirBinOp::FGe, x.clone(), Operand::Place(negated))
+        }
+        _ => return None,
+    };
+
+    let cond = ctx.fresh_temp();
+    ctx.locals.insert(cond.name.clone(), MirType::Bool);
+    ctx.push_inst(MirInst::BinOp { dest: cond.clone(), op, lhs: first.clone(), rhs: second.clone() });
+
+    let first_label = ctx.fresh_label(func);
+    let second_label = ctx.fresh_label(func);
+    let merge_label = ctx.fresh_label("merge");
+    ctx.finish_block(Terminator::Branch {
+        cond: Operand::Place(cond),
+        then_label: first_label.clone(),
+        else_label: second_label.clone(),
+    });
+    for label in [&first_label, &second_label] {
+        ctx.start_block(label.clone());
+        ctx.finish_block(Terminator::Goto(merge_label.clone()));
+    }
+
+    ctx.start_block(merge_label);
+    let result = ctx.fresh_temp();
+    ctx.locals.insert(result.name.clone(), MirType::F64);
+    ctx.push_inst(MirInst::Phi {
+        dest: result.clone(),
+        values: vec![(first, first_label), (second, second_label)],
+    });
+    Some(Operand::Place(result))
+}
+
+/// Lower the value of a `let` and assign it to a new local `name`
+fn lower_let_binding(name: &str, ty: Option<&Spanned<Type>>, value: &Spanned<Expr>, ctx: &mut LoweringContext) {
+    // Lower the value
+    let value_op = lower_expr(value, ctx);
+
+    // Determine type
+    let mir_ty = if let Some(ty_span) = ty {
+        struct_aware_type(&ty_span.node, &ctx.struct_fields, None)
+    } else {
+        ctx.operand_type(&value_op)
+    };
+
+    // Register local
+    ctx.locals.insert(name.to_string(), mir_ty);
+    if ty.is_some_and(|t| is_hashmap_type(&t.node)) || is_hashmap_new(value) {
+        ctx.map_vars.insert(name.to_string());
+    }
+    let type_name = match ty {
+        Some(t) => nominal_type_name(&t.node, None),
+        None => receiver_type_name(&value.node, ctx),
+    };
+    if let Some(type_name) = type_name {
+        ctx.var_types.insert(name.to_string(), type_name);
+    }
+
+    // Assign to the variable
+    let var_place = Place::new(name.to_string());
+    match value_op {
+        Operand::Constant(c) => {
+            ctx.push_inst(MirInst::Const {
+                dest: var_place,
+                value: c,
+            });
+        }
+        Operand::Place(src) => {
+            ctx.push_inst(MirInst::Copy {
+                dest: var_place,
+                src,
+            });
+        }
+    }
+}
+
 /// Lower an expression, returning the operand holding its result
 fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
     match &expr.node {
@@ -237,7 +718,13 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
 
         Expr::Unit => Operand::Constant(Constant::Unit),
 
-        Expr::Var(name) => Operand::Place(Place::new(name.clone())),
+        Expr::Var(name) if ctx.in_old && ctx.params.contains_key(name) => Operand::Place(ctx.entry_value(name)),
+        Expr::Var(name) => match ctx.consts.get(name) {
+            Some(value) if !ctx.locals.contains_key(name) && !ctx.params.contains_key(name) => {
+                Operand::Constant(value.clone())
+            }
+            _ => Operand::Place(Place::new(name.clone())),
+        },
 
         Expr::Binary { left, op, right } => {
             let lhs = lower_expr(left, ctx);
@@ -252,6 +739,14 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             let result_ty = mir_op.result_type(&lhs_ty);
             ctx.locals.insert(dest.name.clone(), result_ty);
 
+            if ctx.check_overflow && (lhs_ty.is_integer() || lhs_ty.is_unsigned())
+                && matches!(mir_op, MirBinOp::Add | MirBinOp::Sub | MirBinOp::Mul)
+            {
+                let message = format!("integer overflow in `{}`", format_condition(&expr.node));
+                lower_overflow_check(&dest, mir_op, lhs, rhs, message, ctx);
+                return Operand::Place(dest);
+            }
+
             ctx.push_inst(MirInst::BinOp {
                 dest: dest.clone(),
                 op: mir_op,
@@ -345,40 +840,25 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             value,
             body,
         } => {
-            // Lower the value
-            let value_op = lower_expr(value, ctx);
-
-            // Determine type
-            let mir_ty = if let Some(ty_span) = ty {
-                ast_type_to_mir(&ty_span.node)
-            } else {
-                ctx.operand_type(&value_op)
-            };
+            lower_let_binding(name, ty.as_ref(), value, ctx);
 
-            // Register local
-            ctx.locals.insert(name.clone(), mir_ty);
-
-            // Assign to the variable
-            let var_place = Place::new(name.clone());
-            match value_op {
-                Operand::Constant(c) => {
-                    ctx.push_inst(MirInst::Const {
-                        dest: var_place,
-                        value: c,
-                    });
-                }
-                Operand::Place(src) => {
-                    ctx.push_inst(MirInst::Copy {
-                        dest: var_place,
-                        src,
-                    });
-                }
-            }
-
-            // Lower the body
+            // Lower the body; in a block it is the next statement
+            ctx.debug_loc(body.span);
             lower_expr(body, ctx)
         }
 
+        // The binding lives on for the rest of the block (locals are
+        // function-wide), so this is the nested form without a body
+        Expr::LetStmt {
+            name,
+            mutable: _,
+            ty,
+            value,
+        } => {
+            lower_let_binding(name, ty.as_ref(), value, ctx);
+            Operand::Constant(Constant::Unit)
+        }
+
         Expr::Assign { name, value } => {
             // Lower the value
             let value_op = lower_expr(value, ctx);
@@ -404,8 +884,35 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             Operand::Place(Place::new(name.clone()))
         }
 
+        // `a[i][j] = v` stores into the row loaded by `a[i]`
+        Expr::IndexAssign { array, index, value } => {
+            let array_op = lower_expr(array, ctx);
+            let array_place = operand_to_place(array_op, ctx);
+            let index_op = lower_expr(index, ctx);
+            let value_op = lower_expr(value, ctx);
+            ctx.push_inst(MirInst::IndexStore {
+                array: array_place,
+                index: index_op,
+                value: value_op,
+            });
+            Operand::Constant(Constant::Unit)
+        }
+
+        // `a.b.c = v` stores into the struct loaded by `a.b`
+        Expr::FieldAssign { object, field, value } => {
+            let object_op = lower_expr(object, ctx);
+            let base = operand_to_place(object_op, ctx);
+            let value_op = lower_expr(value, ctx);
+            ctx.push_inst(MirInst::FieldStore {
+                base,
+                field: field.node.clone(),
+                value: value_op,
+            });
+            Operand::Constant(Constant::Unit)
+        }
+
         // v0.37: Invariant is for SMT verification, MIR lowering ignores it
-        Expr::While { cond, invariant: _, body } => {
+        Expr::While { cond, invariant: _, body, .. } => {
             // Create labels for loop structure
             let cond_label = ctx.fresh_label("while_cond");
             let body_label = ctx.fresh_label("while_body");
@@ -416,6 +923,7 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
 
             // Condition block
             ctx.start_block(cond_label.clone());
+            ctx.debug_loc(cond.span);
             let cond_op = lower_expr(cond, ctx);
             ctx.finish_block(Terminator::Branch {
                 cond: cond_op,
@@ -435,12 +943,75 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             Operand::Constant(Constant::Unit)
         }
 
+        // assert_eq(a, b) / assert_ne(a, b) lower to assert(a == b) / assert(a != b)
+        Expr::Call { func, args } if matches!(func.as_str(), "assert_eq" | "assert_ne") && args.len() == 2 => {
+            let op = if func == "assert_eq" { BinOp::Eq } else { BinOp::Ne };
+            let cond = Spanned::new(
+                Expr::Binary {
+                    left: Box::new(args[0].clone()),
+                    op,
+                    right: Box::new(args[1].clone()),
+                },
+                expr.span,
+            );
+            let assert_call = Spanned::new(
+                Expr::Call { func: "assert".to_string(), args: vec![cond] },
+                expr.span,
+            );
+            lower_expr(&assert_call, ctx)
+        }
+
+        // print/println with several arguments or a String print each
+        // argument in turn, separated by spaces
+        Expr::Call { func, args } if matches!(func.as_str(), "print" | "println") => {
+            let arg_ops: Vec<Operand> = args.iter().map(|arg| lower_expr(arg, ctx)).collect();
+            if let [arg] = arg_ops.as_slice()
+                && ctx.operand_type(arg) != MirType::String
+            {
+                ctx.push_inst(MirInst::Call { dest: None, func: func.clone(), args: arg_ops });
+                return Operand::Constant(Constant::Unit);
+            }
+
+            let print_str = |ctx: &mut LoweringContext, arg: Operand| {
+                let dest = ctx.fresh_temp();
+                ctx.locals.insert(dest.name.clone(), MirType::I64);
+                ctx.push_inst(MirInst::Call { dest: Some(dest), func: "print_str".to_string(), args: vec![arg] });
+            };
+            for (i, arg) in arg_ops.into_iter().enumerate() {
+                if i > 0 {
+                    print_str(ctx, Operand::Constant(Constant::String(" ".to_string())));
+                }
+                if ctx.operand_type(&arg) == MirType::String {
+                    print_str(ctx, arg);
+                } else {
+                    ctx.push_inst(MirInst::Call { dest: None, func: "print".to_string(), args: vec![arg] });
+                }
+            }
+            if func == "println" {
+                ctx.push_inst(MirInst::Call {
+                    dest: None,
+                    func: "println_str".to_string(),
+                    args: vec![Operand::Constant(Constant::String(String::new()))],
+                });
+            }
+            Operand::Constant(Constant::Unit)
+        }
+
         Expr::Call { func, args } => {
             // Lower arguments
             let arg_ops: Vec<Operand> = args.iter().map(|arg| lower_expr(arg, ctx)).collect();
 
+            // A closure variable shadows any function of the same name
+            if let MirType::Fn { ret, .. } = ctx.operand_type(&Operand::Place(Place::new(func.clone()))) {
+                return lower_closure_call(func, *ret, arg_ops, ctx);
+            }
+
+            if let Some(result) = lower_float_min_max(func, &arg_ops, ctx) {
+                return result;
+            }
+
             // Check if this is a void function (runtime functions that return void)
-            let is_void_func = matches!(func.as_str(), "println" | "print" | "assert");
+            let is_void_func = matches!(func.as_str(), "println" | "print" | "assert" | "flush");
 
             if is_void_func {
                 ctx.push_inst(MirInst::Call {
@@ -462,12 +1033,13 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
                         // String-returning runtime functions
                         // v0.46: get_arg returns string (pointer to BmbString)
                         // v0.46: sb_build returns string (pointer to BmbString)
-                        "int_to_string" | "read_file" | "slice" | "digit_char" | "get_arg" | "sb_build" => MirType::String,
+                        "int_to_string" | "char_to_string" | "read_file" | "slice" | "digit_char" | "get_arg" | "sb_build" => MirType::String,
                         // i64-returning runtime functions
                         // v0.46: arg_count returns i64
-                        "byte_at" | "len" | "strlen" | "cstr_byte_at" | "arg_count" => MirType::I64,
+                        // file_exists returns 1/0 as i64, matching the type checker
+                        "byte_at" | "len" | "strlen" | "cstr_byte_at" | "arg_count" | "file_exists" => MirType::I64,
                         // Bool-returning runtime functions
-                        "file_exists" | "cstr_eq" => MirType::Bool,
+                        "cstr_eq" => MirType::Bool,
                         // Default to i64 for unknown functions
                         _ => MirType::I64,
                     }
@@ -491,21 +1063,22 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             // Lower all expressions, return the last one
             let mut result = Operand::Constant(Constant::Unit);
             for expr in exprs {
+                ctx.debug_loc(expr.span);
                 result = lower_expr(expr, ctx);
             }
             result
         }
 
         Expr::Ret => {
-            // 'ret' in postconditions refers to the return value
-            // In MIR lowering, we don't handle contracts - just return unit
-            Operand::Constant(Constant::Unit)
+            // 'ret' in postconditions refers to the return value, which is
+            // only bound while lowering a checked postcondition
+            ctx.ret_value.clone().unwrap_or(Operand::Constant(Constant::Unit))
         }
 
         // v0.19.0: Struct initialization
-        Expr::StructInit { name, fields } => {
+        Expr::StructInit { name, fields, base } => {
             // Lower each field value
-            let mir_fields: Vec<(String, Operand)> = fields
+            let mut mir_fields: Vec<(String, Operand)> = fields
                 .iter()
                 .map(|(field_name, field_value)| {
                     let value_op = lower_expr(field_value, ctx);
@@ -513,8 +1086,41 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
                 })
                 .collect();
 
+            let struct_fields = ctx.struct_fields.get(name).cloned().unwrap_or_default();
+            let missing: Vec<_> = struct_fields.into_iter()
+                .filter(|f| !mir_fields.iter().any(|(n, _)| *n == f.name.node))
+                .collect();
+
+            // `..base`: read the remaining fields from the base value
+            if let Some(base) = base {
+                let base_op = lower_expr(base, ctx);
+                let base_place = operand_to_place(base_op, ctx);
+                for field in missing {
+                    let field_name = field.name.node;
+                    let value = ctx.fresh_temp();
+                    register_field_type(&value, &base_place, &field_name, ctx);
+                    ctx.push_inst(MirInst::FieldAccess {
+                        dest: value.clone(),
+                        base: base_place.clone(),
+                        field: field_name.clone(),
+                    });
+                    mir_fields.push((field_name, Operand::Place(value)));
+                }
+            } else {
+                for field in missing {
+                    if let Some(default) = &field.default {
+                        let value = lower_field_default(default, ctx);
+                        mir_fields.push((field.name.node, value));
+                    }
+                }
+            }
+
             // Create destination for the struct
             let dest = ctx.fresh_temp();
+            let ty = struct_aware_type(&Type::Named(name.clone()), &ctx.struct_fields, None);
+            if matches!(ty, MirType::Struct { .. }) {
+                ctx.locals.insert(dest.name.clone(), ty);
+            }
 
             ctx.push_inst(MirInst::StructInit {
                 dest: dest.clone(),
@@ -535,6 +1141,7 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
 
             // Create destination for the field value
             let dest = ctx.fresh_temp();
+            register_field_type(&dest, &base_place, &field.node, ctx);
 
             ctx.push_inst(MirInst::FieldAccess {
                 dest: dest.clone(),
@@ -549,12 +1156,19 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
         Expr::TupleField { expr, index } => {
             // Lower the tuple expression
             let tuple_op = lower_expr(expr, ctx);
+            let elem_ty = match ctx.operand_type(&tuple_op) {
+                MirType::Tuple(elems) => elems.get(*index).cloned(),
+                _ => None,
+            };
 
             // Convert operand to place if needed
             let tuple_place = operand_to_place(tuple_op, ctx);
 
             // Create destination for the element value
             let dest = ctx.fresh_temp();
+            if let Some(ty) = elem_ty {
+                ctx.locals.insert(dest.name.clone(), ty);
+            }
 
             // Use IndexLoad with constant index for tuple field access
             ctx.push_inst(MirInst::IndexLoad {
@@ -566,6 +1180,18 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             Operand::Place(dest)
         }
 
+        // HashMap::new() creates a hashmap handle
+        Expr::EnumVariant { .. } if is_hashmap_new(expr) => {
+            let dest = ctx.fresh_temp();
+            ctx.locals.insert(dest.name.clone(), MirType::I64);
+            ctx.push_inst(MirInst::Call {
+                dest: Some(dest.clone()),
+                func: "hashmap_new".to_string(),
+                args: vec![],
+            });
+            Operand::Place(dest)
+        }
+
         // v0.19.1: Enum variant construction
         Expr::EnumVariant { enum_name, variant, args } => {
             // Lower each argument
@@ -595,7 +1221,7 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
         }
 
         // v0.5 Phase 3: For loop (lowered to while loop pattern)
-        Expr::For { var, iter, body } => {
+        Expr::For { var, iter, body, .. } => {
             // Lower the iterator (expecting Range expression)
             // Extract start and end from range
             let (start_op, end_op) = match &iter.node {
@@ -713,9 +1339,22 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             // Analyze patterns to generate switch cases
             let cases = compile_match_patterns(arms, &arm_labels, &default_label);
 
+            // Enum arms compare against the tag word, not the value itself
+            let discriminant = if arms.iter().any(|arm| matches!(arm.pattern.node, Pattern::EnumVariant { .. })) {
+                let tag = ctx.fresh_temp();
+                ctx.push_inst(MirInst::FieldAccess {
+                    dest: tag.clone(),
+                    base: match_place.clone(),
+                    field: ENUM_TAG_FIELD.to_string(),
+                });
+                tag
+            } else {
+                match_place.clone()
+            };
+
             // Close current block with switch terminator
             ctx.finish_block(Terminator::Switch {
-                discriminant: Operand::Place(match_place.clone()),
+                discriminant: Operand::Place(discriminant),
                 cases,
                 default: default_label.clone(),
             });
@@ -814,6 +1453,8 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             };
 
             let dest = ctx.fresh_temp();
+            let tuple_ty = MirType::Tuple(mir_elements.iter().map(|e| ctx.operand_type(e)).collect());
+            ctx.locals.insert(dest.name.clone(), tuple_ty);
             ctx.push_inst(MirInst::ArrayInit {
                 dest: dest.clone(),
                 element_type,
@@ -851,7 +1492,42 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
         // v0.19.4: Method calls - static dispatch
         // Methods are lowered as function calls with receiver as first argument
         // The method name is prefixed with the receiver type for name mangling
+        // HashMap<K, V> methods map onto the i64-keyed hashmap builtins.
+        // Keys and values of other types travel boxed as their pointer word,
+        // and `Option<V>` results carry the builtin's raw return value.
+        Expr::MethodCall { receiver, method, args }
+            if matches!(&receiver.node, Expr::Var(name) if ctx.map_vars.contains(name))
+                && matches!(method.as_str(), "insert" | "get" | "contains" | "remove" | "len") =>
+        {
+            let mut call_args = vec![lower_expr(receiver, ctx)];
+            for arg in args {
+                call_args.push(lower_expr(arg, ctx));
+            }
+            let dest = ctx.fresh_temp();
+            ctx.locals.insert(dest.name.clone(), MirType::I64);
+            ctx.push_inst(MirInst::Call {
+                dest: Some(dest.clone()),
+                func: format!("hashmap_{}", method),
+                args: call_args,
+            });
+            if method != "contains" {
+                return Operand::Place(dest);
+            }
+            // hashmap_contains returns 1/0; `contains` is a bool
+            let found = ctx.fresh_temp();
+            ctx.locals.insert(found.name.clone(), MirType::Bool);
+            ctx.push_inst(MirInst::BinOp {
+                dest: found.clone(),
+                op: MirBinOp::Ne,
+                lhs: Operand::Place(dest),
+                rhs: Operand::Constant(Constant::Int(0)),
+            });
+            Operand::Place(found)
+        }
+
         Expr::MethodCall { receiver, method, args } => {
+            let func = resolve_inherent_method(&receiver.node, method, ctx).unwrap_or_else(|| method.clone());
+
             // Lower the receiver expression
             let recv_op = lower_expr(receiver, ctx);
 
@@ -868,21 +1544,32 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             // v0.46: Register method call return types
             // String methods have known return types
             let ret_type = match method.as_str() {
+                _ if func != *method => ctx.func_return_types.get(&func).cloned().unwrap_or(MirType::I64),
                 "len" | "byte_at" => MirType::I64,
                 "slice" => MirType::String,
                 // Default to checking user-defined function return types
-                _ => ctx.func_return_types.get(method).cloned().unwrap_or(MirType::I64),
+                _ => ctx.func_return_types.get(&func).cloned().unwrap_or(MirType::I64),
             };
             ctx.locals.insert(dest.name.clone(), ret_type);
 
             ctx.push_inst(MirInst::Call {
                 dest: Some(dest.clone()),
-                func: method.clone(),
+                func,
                 args: call_args,
             });
             Operand::Place(dest)
         }
 
+        // `old(e)` in a checked postcondition reads parameters as they were
+        // on entry
+        Expr::StateRef { expr, state: StateKind::Pre } if ctx.ret_value.is_some() => {
+            let outer = ctx.in_old;
+            ctx.in_old = true;
+            let value = lower_expr(expr, ctx);
+            ctx.in_old = outer;
+            value
+        }
+
         // v0.2: State references (handled during contract verification, not MIR)
         Expr::StateRef { expr, .. } => {
             // During MIR lowering, we just evaluate the expression
@@ -894,9 +1581,7 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
         Expr::It => Operand::Place(Place::new("__it__")),
 
         // v0.20.0: Closure expressions
-        // TODO: Implement closure desugaring to struct with captured variables
-        // For now, just lower the body expression
-        Expr::Closure { body, .. } => lower_expr(body, ctx),
+        Expr::Closure { params, ret_ty, body } => lower_closure(params, ret_ty.as_deref(), body, ctx),
 
         // v0.31: Todo expression - panic at runtime
         Expr::Todo { .. } => {
@@ -907,12 +1592,12 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
 
         // v0.36: Additional control flow
         // Loop - lower body, infinite loop handled at codegen
-        Expr::Loop { body } => {
+        Expr::Loop { body, .. } => {
             lower_expr(body, ctx)
         }
 
         // Break - placeholder, full implementation requires control flow
-        Expr::Break { value } => {
+        Expr::Break { value, .. } => {
             match value {
                 Some(v) => lower_expr(v, ctx),
                 None => Operand::Constant(crate::mir::Constant::Unit),
@@ -920,7 +1605,7 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
         }
 
         // Continue - placeholder
-        Expr::Continue => {
+        Expr::Continue { .. } => {
             Operand::Constant(crate::mir::Constant::Unit)
         }
 
@@ -939,14 +1624,31 @@ fn lower_expr(expr: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
             Operand::Constant(crate::mir::Constant::Unit)
         }
 
-        // v0.39: Type cast - lowered as Copy with type annotation
-        // The actual cast semantics are handled by codegen
-        Expr::Cast { expr, ty: _ } => {
-            lower_expr(expr, ctx)
+        // v0.39: Type cast; the conversion itself is chosen by codegen
+        // from the operand's representation
+        Expr::Cast { expr, ty } => {
+            let src = lower_expr(expr, ctx);
+            let ty = ast_type_to_mir(&ty.node);
+            let dest = ctx.fresh_temp();
+            ctx.locals.insert(dest.name.clone(), ty.clone());
+            ctx.push_inst(MirInst::Cast { dest: dest.clone(), src, ty });
+            Operand::Place(dest)
         }
     }
 }
 
+/// Lower the default value of an omitted struct field. Defaults only see
+/// globals, so locals of the enclosing function must not shadow constants.
+fn lower_field_default(default: &Spanned<Expr>, ctx: &mut LoweringContext) -> Operand {
+    let const_names: Vec<_> = ctx.consts.keys().cloned().collect();
+    let locals: Vec<_> = const_names.iter().filter_map(|n| ctx.locals.remove_entry(n)).collect();
+    let params: Vec<_> = const_names.iter().filter_map(|n| ctx.params.remove_entry(n)).collect();
+    let value = lower_expr(default, ctx);
+    ctx.locals.extend(locals);
+    ctx.params.extend(params);
+    value
+}
+
 /// Convert an operand to a place, emitting a Const instruction if needed
 fn operand_to_place(op: Operand, ctx: &mut LoweringContext) -> Place {
     match op {
@@ -962,7 +1664,97 @@ fn operand_to_place(op: Operand, ctx: &mut LoweringContext) -> Place {
     }
 }
 
+/// Whether an AST type is `HashMap<K, V>`
+fn is_hashmap_type(ty: &Type) -> bool {
+    matches!(ty, Type::Generic { name, .. } if name == "HashMap")
+}
+
+/// Name of the struct or enum a type refers to, with `Self` standing for `self_type`
+fn nominal_type_name(ty: &Type, self_type: Option<&str>) -> Option<String> {
+    match ty {
+        Type::Named(name) if name == "Self" => self_type.map(str::to_string),
+        Type::Named(name) | Type::Generic { name, .. } => Some(name.clone()),
+        Type::Ref(inner) | Type::RefMut(inner) => nominal_type_name(inner, self_type),
+        _ => None,
+    }
+}
+
+/// Struct or enum type of a method receiver, when it is known without type checking
+fn receiver_type_name(expr: &Expr, ctx: &LoweringContext) -> Option<String> {
+    match expr {
+        Expr::Var(name) => ctx.var_types.get(name).cloned(),
+        Expr::StructInit { name, .. } => Some(name.clone()),
+        Expr::Ref(inner) | Expr::RefMut(inner) => receiver_type_name(&inner.node, ctx),
+        _ => None,
+    }
+}
+
+/// Function an inherent method call lowers to. A receiver of unknown type
+/// still resolves when only one type defines an inherent method of that name.
+fn resolve_inherent_method(receiver: &Expr, method: &str, ctx: &LoweringContext) -> Option<String> {
+    if ctx.inherent_methods.is_empty() {
+        return None;
+    }
+    if let Some(type_name) = receiver_type_name(receiver, ctx) {
+        return ctx.inherent_methods.get(&(type_name, method.to_string())).cloned();
+    }
+    let mut candidates = ctx.inherent_methods.iter().filter(|((_, m), _)| m == method);
+    match (candidates.next(), candidates.next()) {
+        (Some((_, func)), None) => Some(func.clone()),
+        _ => None,
+    }
+}
+
+/// Whether an expression is the `HashMap::new()` constructor
+fn is_hashmap_new(expr: &Spanned<Expr>) -> bool {
+    matches!(&expr.node, Expr::EnumVariant { enum_name, variant, .. } if enum_name == "HashMap" && variant == "new")
+}
+
 /// Convert AST type to MIR type
+/// Like [`ast_type_to_mir`], but a struct name (or `Self` in a method of
+/// `self_type`) becomes a [`MirType::Struct`] with its field layout
+fn struct_aware_type(
+    ty: &Type,
+    structs: &std::collections::HashMap<String, Vec<crate::ast::StructField>>,
+    self_type: Option<&str>,
+) -> MirType {
+    fn resolve(
+        ty: &Type,
+        structs: &std::collections::HashMap<String, Vec<crate::ast::StructField>>,
+        self_type: Option<&str>,
+        open: &mut Vec<String>,
+    ) -> MirType {
+        let name = match ty {
+            Type::Named(name) if name == "Self" => self_type,
+            Type::Named(name) => Some(name.as_str()),
+            _ => None,
+        };
+        // A struct containing itself has no finite layout; leave it opaque
+        match name.and_then(|name| structs.get_key_value(name)) {
+            Some((name, fields)) if !open.contains(name) => {
+                open.push(name.clone());
+                let fields = fields
+                    .iter()
+                    .map(|f| (f.name.node.clone(), Box::new(resolve(&f.ty.node, structs, None, open))))
+                    .collect();
+                open.pop();
+                MirType::Struct { name: name.clone(), fields }
+            }
+            _ => ast_type_to_mir(ty),
+        }
+    }
+    resolve(ty, structs, self_type, &mut Vec::new())
+}
+
+/// Record the type of `dest = base.field` when `base` is a known struct
+fn register_field_type(dest: &Place, base: &Place, field: &str, ctx: &mut LoweringContext) {
+    if let MirType::Struct { fields, .. } = ctx.operand_type(&Operand::Place(base.clone()))
+        && let Some((_, ty)) = fields.into_iter().find(|(name, _)| name == field)
+    {
+        ctx.locals.insert(dest.name.clone(), *ty);
+    }
+}
+
 fn ast_type_to_mir(ty: &Type) -> MirType {
     match ty {
         Type::I32 => MirType::I32,
@@ -1003,22 +1795,38 @@ fn ast_type_to_mir(ty: &Type) -> MirType {
         // v0.5 Phase 5: References are pointers
         Type::Ref(_) | Type::RefMut(_) => MirType::I64,
         // v0.5 Phase 6: Arrays are pointers to data
-        Type::Array(_, _) => MirType::I64,
+        Type::Array(_, _) | Type::ArrayConst(_, _) => MirType::I64,
         // v0.2: Refined types use base type
         Type::Refined { base, .. } => ast_type_to_mir(base),
-        // v0.20.0: Fn types are function pointers (pointer-sized)
-        Type::Fn { .. } => MirType::I64,
+        // v0.20.0: Fn types are closure values
+        Type::Fn { params, ret } => MirType::Fn {
+            params: params.iter().map(|p| ast_type_to_mir(p)).collect(),
+            ret: Box::new(ast_type_to_mir(ret)),
+        },
         // v0.31: Never type - unreachable code, use Unit
         Type::Never => MirType::Unit,
         // v0.37: Nullable type - convert inner type (for MIR, nullable is just a tagged union)
         Type::Nullable(inner) => ast_type_to_mir(inner),
-        // v0.42: Tuple type - represent as struct-like aggregate
-        Type::Tuple(_) => MirType::I64, // Simplified for now
+        // v0.42: Tuple type - a handle to one word per element
+        Type::Tuple(elems) => MirType::Tuple(elems.iter().map(|e| ast_type_to_mir(e)).collect()),
     }
 }
 
-/// Convert AST binary operator to MIR operator
+/// Convert AST binary operator to MIR operator; u32/u64 operands take the
+/// unsigned division, ordering and right shift
 fn ast_binop_to_mir(op: BinOp, ty: &MirType) -> MirBinOp {
+    if ty.is_unsigned() {
+        match op {
+            BinOp::Div => return MirBinOp::UDiv,
+            BinOp::Mod => return MirBinOp::UMod,
+            BinOp::Lt => return MirBinOp::ULt,
+            BinOp::Gt => return MirBinOp::UGt,
+            BinOp::Le => return MirBinOp::ULe,
+            BinOp::Ge => return MirBinOp::UGe,
+            BinOp::Shr => return MirBinOp::UShr,
+            _ => {}
+        }
+    }
     match (op, ty.is_float()) {
         (BinOp::Add, false) => MirBinOp::Add,
         (BinOp::Add, true) => MirBinOp::FAdd,
@@ -1102,9 +1910,8 @@ fn compile_match_patterns(
                 cases.push((value, arm_labels[i].clone()));
             }
             Pattern::EnumVariant { variant, .. } => {
-                // For enum variants, use a deterministic discriminant based on variant name
-                // In a real compiler, this would come from the type info
-                let disc = variant_to_discriminant(variant);
+                // Matched against the tag word, see `ENUM_TAG_FIELD`
+                let disc = variant_discriminant(variant);
                 cases.push((disc, arm_labels[i].clone()));
             }
             Pattern::Wildcard | Pattern::Var(_) => {
@@ -1163,17 +1970,6 @@ fn compile_match_patterns(
     cases
 }
 
-/// Convert variant name to discriminant value
-fn variant_to_discriminant(variant: &str) -> i64 {
-    // Simple hash-based discriminant for now
-    // In a full implementation, this would use the enum definition order
-    let mut hash: i64 = 0;
-    for (i, c) in variant.chars().enumerate() {
-        hash = hash.wrapping_add((c as i64).wrapping_mul((i + 1) as i64));
-    }
-    hash
-}
-
 /// Bind pattern variables to values extracted from the match expression
 fn bind_pattern_variables(pattern: &Pattern, match_place: &Place, ctx: &mut LoweringContext) {
     match pattern {
@@ -1198,7 +1994,7 @@ fn bind_pattern_variables(pattern: &Pattern, match_place: &Place, ctx: &mut Lowe
             // For enum variants with bindings, extract fields
             for (i, binding) in bindings.iter().enumerate() {
                 let field_place = ctx.fresh_temp();
-                // Use field access to extract (simplified - real impl needs tag/data extraction)
+                // Payload words follow the tag, see `ENUM_TAG_FIELD`
                 ctx.push_inst(MirInst::FieldAccess {
                     dest: field_place.clone(),
                     base: match_place.clone(),
@@ -1471,6 +2267,7 @@ mod tests {
                 post: None,
                 contracts: vec![],
                 body: spanned(Expr::While {
+                    label: None,
                     cond: Box::new(spanned(Expr::BoolLit(false))),
                     invariant: None,  // v0.37: No invariant in test
                     body: Box::new(spanned(Expr::Unit)),
@@ -1508,6 +2305,7 @@ mod tests {
                         (spanned("x".to_string()), spanned(Expr::IntLit(10))),
                         (spanned("y".to_string()), spanned(Expr::IntLit(20))),
                     ],
+                    base: None,
                 }),
                 span: Span { start: 0, end: 0 },
             })],
@@ -1847,4 +2645,225 @@ mod tests {
         });
         assert!(has_call, "Expected Call instruction for method 'double' with 2 args");
     }
+
+    #[test]
+    fn test_lower_hashmap_methods_to_builtins() {
+        let source = "fn f() -> i64 = {
+            let m: HashMap<i64, i64> = HashMap::new();
+            let old = m.insert(1, 10);
+            if m.contains(1) { m.len() } else { 0 }
+        };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+
+        let calls: Vec<&str> = mir.functions[0].blocks.iter()
+            .flat_map(|b| b.instructions.iter())
+            .filter_map(|inst| match inst {
+                MirInst::Call { func, .. } => Some(func.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, ["hashmap_new", "hashmap_insert", "hashmap_contains", "hashmap_len"]);
+    }
+
+    #[test]
+    fn test_lower_variadic_println_per_argument() {
+        let source = r#"fn f(n: i64, s: String) -> () = { println(n); println(n, s); print("x") };"#;
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+
+        let calls: Vec<&str> = mir.functions[0].blocks.iter()
+            .flat_map(|b| b.instructions.iter())
+            .filter_map(|inst| match inst {
+                MirInst::Call { func, .. } => Some(func.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, ["println", "print", "print_str", "print_str", "println_str", "print_str"]);
+    }
+
+    #[test]
+    fn test_lower_f64_max_to_compare() {
+        let source = "fn f(a: f64, b: f64) -> f64 = max(a, b) + abs(a);";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+        let insts: Vec<_> = mir.functions[0].blocks.iter().flat_map(|b| b.instructions.iter()).collect();
+
+        // No call to the i64-only runtime versions; each picks with a phi
+        assert!(!insts.iter().any(|inst| matches!(inst, MirInst::Call { .. })), "{:?}", insts);
+        let compares = insts.iter().filter(|inst| matches!(inst, MirInst::BinOp { op: MirBinOp::FGe, .. })).count();
+        assert_eq!(compares, 2);
+        assert_eq!(insts.iter().filter(|inst| matches!(inst, MirInst::Phi { .. })).count(), 2);
+    }
+
+    #[test]
+    fn test_lower_index_assign_to_store() {
+        let source = "fn f(i: i64) -> i64 = {
+            let mut grid = [[0, 0], [0, 0]];
+            grid[i][1] = 7;
+            grid[0][0]
+        };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+        let insts: Vec<_> = mir.functions[0].blocks.iter().flat_map(|b| b.instructions.iter()).collect();
+
+        // The row is loaded from `grid`, then the element stored into it
+        let store = insts.iter().position(|inst| matches!(inst, MirInst::IndexStore { .. })).expect("an index store");
+        let MirInst::IndexStore { array, index, value } = insts[store] else { unreachable!() };
+        let Some(MirInst::IndexLoad { dest, array: row_source, index: row }) = insts[..store].iter().rev()
+            .find_map(|inst| matches!(inst, MirInst::IndexLoad { .. }).then_some(*inst)) else {
+            panic!("expected the row load, got {:?}", insts);
+        };
+        assert_eq!(row_source.name, "grid");
+        assert!(matches!(row, Operand::Place(p) if p.name == "i"));
+        assert_eq!(array.name, dest.name);
+        assert!(matches!(index, Operand::Constant(Constant::Int(1))));
+        assert!(matches!(value, Operand::Constant(Constant::Int(7))));
+    }
+
+    #[test]
+    fn test_lower_enum_match_switches_on_tag() {
+        let source = "enum Opt { Some(i64), None }
+        fn f(x: Opt) -> i64 = match x { Opt::Some(v) => v, Opt::None => 0 };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+        let entry = &mir.functions[0].blocks[0];
+
+        let Some(MirInst::FieldAccess { dest, base, field }) = entry.instructions.last() else {
+            panic!("expected a tag read, got {:?}", entry.instructions);
+        };
+        assert_eq!((base.name.as_str(), field.as_str()), ("x", ENUM_TAG_FIELD));
+        let Terminator::Switch { discriminant: Operand::Place(disc), cases, .. } = &entry.terminator else {
+            panic!("expected a switch, got {:?}", entry.terminator);
+        };
+        assert_eq!(disc.name, dest.name);
+        assert_eq!(cases[0].0, variant_discriminant("Some"));
+        assert_eq!(cases[1].0, variant_discriminant("None"));
+    }
+
+    #[test]
+    fn test_lower_closure_to_lifted_function() {
+        let source = "fn f(base: i64) -> i64 = { let add = fn |x: i64| { x + base }; add(5) };";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mir = lower_program(&program);
+        let names: Vec<_> = mir.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["f", "f.closure0"]);
+
+        // The lifted body takes the environment first and reads `base` from it
+        let lifted = &mir.functions[1];
+        assert_eq!(lifted.params, [("__env".to_string(), MirType::I64), ("x".to_string(), MirType::I64)]);
+        let Some(MirInst::FieldAccess { dest, base, field }) = lifted.blocks[0].instructions.first() else {
+            panic!("expected a capture load, got {:?}", lifted.blocks[0].instructions);
+        };
+        assert_eq!((dest.name.as_str(), base.name.as_str(), field.as_str()), ("base", "__env", "_0"));
+
+        // The caller builds the (fn, env) pair and calls through it
+        let insts: Vec<_> = mir.functions[0].blocks.iter().flat_map(|b| b.instructions.iter()).collect();
+        assert!(insts.iter().any(|inst| matches!(inst, MirInst::EnumVariant { args, .. }
+            if matches!(&args[0], Operand::Constant(Constant::FnAddr(name)) if name == "f.closure0"))));
+        let Some(MirInst::CallIndirect { args, .. }) = insts.iter().find(|inst| matches!(inst, MirInst::CallIndirect { .. })) else {
+            panic!("expected an indirect call, got {:?}", insts);
+        };
+        assert_eq!(args.len(), 2);
+        assert!(!insts.iter().any(|inst| matches!(inst, MirInst::Call { func, .. } if func == "add")));
+    }
+
+    #[test]
+    fn test_lower_checked_contracts() {
+        let source = "fn safe_div(a: i64, b: i64) -> i64 pre b != 0 post ret <= a = a / b;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+
+        let fail_messages = |mir: &MirProgram| -> Vec<String> {
+            mir.functions[0].blocks.iter()
+                .flat_map(|b| b.instructions.iter())
+                .filter_map(|inst| match inst {
+                    MirInst::Call { func, args, .. } if func == CONTRACT_FAIL_FN => match &args[0] {
+                        Operand::Constant(Constant::String(s)) => Some(s.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let unchecked = lower_program(&program);
+        assert!(fail_messages(&unchecked).is_empty());
+        assert_eq!(unchecked.functions[0].preconditions.len(), 1);
+
+        let checked = lower_program_with_contracts(&program, ContractMode::Check);
+        assert_eq!(fail_messages(&checked), [
+            "precondition failed in `safe_div`: b != 0",
+            "postcondition failed in `safe_div`: ret <= a",
+        ]);
+        // Checked preconditions must not be used to fold away their own check
+        assert!(checked.functions[0].preconditions.is_empty());
+        let func = &checked.functions[0];
+        assert!(matches!(func.blocks[0].terminator, Terminator::Branch { .. }));
+        assert!(func.blocks.iter().any(|b| matches!(b.terminator, Terminator::Unreachable)));
+    }
+
+    #[test]
+    fn test_lower_checked_overflow() {
+        let source = "fn add(a: i64, b: i64) -> i64 = a + b;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+
+        let ops = |mir: &MirProgram| -> Vec<MirBinOp> {
+            mir.functions[0].blocks.iter()
+                .flat_map(|b| b.instructions.iter())
+                .filter_map(|inst| match inst {
+                    MirInst::BinOp { op, .. } if *op != MirBinOp::Eq => Some(*op),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // Without checks the addition wraps
+        assert_eq!(ops(&lower_program(&program)), [MirBinOp::Add]);
+
+        // With checks the overflow test guards the same addition
+        let checked = lower_program_with_contracts(&program, ContractMode::Check);
+        assert_eq!(ops(&checked), [MirBinOp::AddOverflows, MirBinOp::AddWrap]);
+        let fails: Vec<_> = checked.functions[0].blocks.iter()
+            .flat_map(|b| b.instructions.iter())
+            .filter_map(|inst| match inst {
+                MirInst::Call { func, args, .. } if func == CONTRACT_FAIL_FN => Some(args[0].clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(&fails[..], [Operand::Constant(Constant::String(s))] if s == "integer overflow in `a + b`"));
+
+        // Unsigned arithmetic is checked against its own bounds
+        let source = "fn sub(a: u32, b: u32) -> u32 = a - b;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let checked = lower_program_with_contracts(&program, ContractMode::Check);
+        assert_eq!(ops(&checked), [MirBinOp::SubOverflows, MirBinOp::SubWrap]);
+    }
+
+    #[test]
+    fn test_lower_unsigned_operators() {
+        let source = "fn f(a: u64, b: u64, c: i64, d: i64) -> bool = a / b < a % b and c / d < c;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+
+        let ops: Vec<_> = lower_program(&program).functions[0].blocks.iter()
+            .flat_map(|b| b.instructions.iter())
+            .filter_map(|inst| match inst {
+                MirInst::BinOp { op, .. } => Some(*op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ops[..3], [MirBinOp::UDiv, MirBinOp::UMod, MirBinOp::ULt]);
+        assert!(ops.contains(&MirBinOp::Div) && ops.contains(&MirBinOp::Lt));
+        assert_eq!(ast_binop_to_mir(BinOp::Shr, &MirType::U32), MirBinOp::UShr);
+        assert_eq!(ast_binop_to_mir(BinOp::Shr, &MirType::I32), MirBinOp::Shr);
+    }
 }
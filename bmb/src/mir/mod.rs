@@ -13,18 +13,28 @@
 //! - Common subexpression elimination
 //! - Contract-based optimizations (BMB-specific)
 
+mod dot;
 mod lower;
 mod optimize;
 
-pub use lower::lower_program;
+pub use dot::format_cfg_dot;
+
+pub use lower::{
+    lower_program, lower_program_with_contracts, lower_program_with_debug_info, ContractMode,
+    CONTRACT_FAIL_FN,
+};
 pub use optimize::{
     OptimizationPass, OptimizationPipeline, OptimizationStats, OptLevel,
     ConstantFolding, DeadCodeElimination, SimplifyBranches,
     CopyPropagation, CommonSubexpressionElimination, ContractBasedOptimization,
     ContractUnreachableElimination, PureFunctionCSE, ConstFunctionEval,
+    DeadFunctionElimination, inline_calls,
 };
+pub(crate) use optimize::inst_dest;
+
+use std::collections::{HashMap, HashSet};
 
-use std::collections::HashMap;
+use crate::ast::Span;
 
 /// A MIR program containing all functions
 #[derive(Debug, Clone)]
@@ -72,6 +82,10 @@ pub struct MirFunction {
     /// v0.38.4: Function is marked @const (compile-time evaluatable)
     /// Const functions are pure + can be evaluated at compile time with constant args
     pub is_const: bool,
+    /// Function is marked @inline; calls to it are replaced by its body in release builds
+    pub is_inline: bool,
+    /// Function is declared `pub`; public functions are roots for dead function elimination
+    pub is_public: bool,
 }
 
 /// v0.38: A proven fact from a contract condition
@@ -149,12 +163,25 @@ pub enum MirInst {
         op: MirUnaryOp,
         src: Operand,
     },
+    /// Type conversion (`expr as ty`): %dest = cast %src to ty
+    Cast {
+        dest: Place,
+        src: Operand,
+        ty: MirType,
+    },
     /// Function call: %dest = call func(args...)
     Call {
         dest: Option<Place>,
         func: String,
         args: Vec<Operand>,
     },
+    /// Call through a function address (a `Constant::FnAddr` value):
+    /// %dest = call-indirect %callee(args...)
+    CallIndirect {
+        dest: Option<Place>,
+        callee: Operand,
+        args: Vec<Operand>,
+    },
     /// PHI node for SSA: %dest = phi [(value1, label1), (value2, label2), ...]
     Phi {
         dest: Place,
@@ -203,6 +230,12 @@ pub enum MirInst {
         index: Operand,
         value: Operand,
     },
+    /// Source location of the instructions that follow, up to the next
+    /// `DebugLoc`. Only emitted when lowering with debug info; it has no
+    /// runtime effect.
+    DebugLoc {
+        span: Span,
+    },
 }
 
 /// Block terminator (control flow)
@@ -260,6 +293,8 @@ pub enum Constant {
     /// Character constant (v0.64)
     Char(char),
     Unit,
+    /// Address of the named function, called with `MirInst::CallIndirect`
+    FnAddr(String),
 }
 
 /// MIR binary operators
@@ -279,6 +314,11 @@ pub enum MirBinOp {
     AddChecked,
     SubChecked,
     MulChecked,
+    /// Whether `Add`, `Sub` or `Mul` on the same operands overflows their
+    /// type (signed or unsigned); the test behind `--contracts=check`
+    AddOverflows,
+    SubOverflows,
+    MulOverflows,
     // v0.38: Saturating integer arithmetic (clamps to min/max)
     AddSat,
     SubSat,
@@ -295,6 +335,13 @@ pub enum MirBinOp {
     Gt,
     Le,
     Ge,
+    // Unsigned integer division and comparison (u32, u64)
+    UDiv,
+    UMod,
+    ULt,
+    UGt,
+    ULe,
+    UGe,
     // Floating-point comparison
     FEq,
     FNe,
@@ -308,6 +355,8 @@ pub enum MirBinOp {
     // v0.32: Shift operators
     Shl,
     Shr,
+    /// Logical shift right (u32, u64)
+    UShr,
     // v0.36: Bitwise operators
     Band,
     Bor,
@@ -322,25 +371,28 @@ impl MirBinOp {
         match self {
             // Arithmetic ops return same type as operands
             MirBinOp::Add | MirBinOp::Sub | MirBinOp::Mul | MirBinOp::Div | MirBinOp::Mod |
+            MirBinOp::UDiv | MirBinOp::UMod |
             // v0.37: Wrapping arithmetic also returns same type
             MirBinOp::AddWrap | MirBinOp::SubWrap | MirBinOp::MulWrap |
-            // v0.38: Checked arithmetic (Option wrapper handled at type level)
-            MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked |
             // v0.38: Saturating arithmetic
             MirBinOp::AddSat | MirBinOp::SubSat | MirBinOp::MulSat => {
                 operand_ty.clone()
             }
+            // Checked arithmetic returns an `Option` enum handle
+            MirBinOp::AddChecked | MirBinOp::SubChecked | MirBinOp::MulChecked => MirType::I64,
+            MirBinOp::AddOverflows | MirBinOp::SubOverflows | MirBinOp::MulOverflows => MirType::Bool,
             // Float arithmetic returns f64
             MirBinOp::FAdd | MirBinOp::FSub | MirBinOp::FMul | MirBinOp::FDiv => MirType::F64,
             // All comparisons return bool
             MirBinOp::Eq | MirBinOp::Ne | MirBinOp::Lt | MirBinOp::Gt | MirBinOp::Le | MirBinOp::Ge |
+            MirBinOp::ULt | MirBinOp::UGt | MirBinOp::ULe | MirBinOp::UGe |
             MirBinOp::FEq | MirBinOp::FNe | MirBinOp::FLt | MirBinOp::FGt | MirBinOp::FLe | MirBinOp::FGe => {
                 MirType::Bool
             }
             // Logical ops return bool
             MirBinOp::And | MirBinOp::Or => MirType::Bool,
             // v0.32: Shift ops return same type as left operand
-            MirBinOp::Shl | MirBinOp::Shr => operand_ty.clone(),
+            MirBinOp::Shl | MirBinOp::Shr | MirBinOp::UShr => operand_ty.clone(),
             // v0.36: Bitwise ops return same type as operands (integer)
             MirBinOp::Band | MirBinOp::Bor | MirBinOp::Bxor => operand_ty.clone(),
             // v0.36: Logical implication returns bool
@@ -406,6 +458,14 @@ pub enum MirType {
         element_type: Box<MirType>,
         size: Option<usize>, // None for dynamic arrays (slices)
     },
+    /// Closure value: an i64 handle to a (function address, environment)
+    /// pair; see [`CLOSURE_FN_FIELD`]
+    Fn {
+        params: Vec<MirType>,
+        ret: Box<MirType>,
+    },
+    /// Tuple value: a handle to one word per element, in order
+    Tuple(Vec<MirType>),
 }
 
 impl MirType {
@@ -416,8 +476,52 @@ impl MirType {
     pub fn is_float(&self) -> bool {
         matches!(self, MirType::F64)
     }
+
+    /// u32 and u64, whose division, ordering and right shift differ from
+    /// the signed operations on the same bits
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, MirType::U32 | MirType::U64)
+    }
+}
+
+/// Pseudo-field read by `FieldAccess` to load an enum value's discriminant.
+///
+/// Backends lay out enum values as a heap block of 64-bit words: the
+/// discriminant first, then the variant payload (`_0`, `_1`, ...).
+pub const ENUM_TAG_FIELD: &str = "_tag";
+
+/// Discriminant stored for an enum variant.
+///
+/// Derived from the variant name rather than the declaration order so that
+/// values built by checked arithmetic (`Some`/`None`) agree with the arms of
+/// a user-declared `Option` without needing its definition.
+pub fn variant_discriminant(variant: &str) -> i64 {
+    let mut hash: i64 = 0;
+    for (i, c) in variant.chars().enumerate() {
+        hash = hash.wrapping_add((c as i64).wrapping_mul((i + 1) as i64));
+    }
+    hash
 }
 
+/// Word offset of an enum field: the tag is word 0, payload `_N` is word N + 1.
+/// Returns `None` for ordinary (struct) field names.
+pub fn enum_field_word(field: &str) -> Option<usize> {
+    if field == ENUM_TAG_FIELD {
+        return Some(0);
+    }
+    field.strip_prefix('_')?.parse::<usize>().ok().map(|i| i + 1)
+}
+
+/// Pseudo-fields read by `FieldAccess` to unpack a closure value.
+///
+/// Closures are converted to ordinary functions whose first parameter is
+/// the environment: an enum-style block holding the captured values as
+/// `_0`, `_1`, ... A closure value is a block laid out the same way with
+/// the function address as `_0` and the environment handle as `_1`.
+pub const CLOSURE_FN_FIELD: &str = "_0";
+/// See [`CLOSURE_FN_FIELD`]
+pub const CLOSURE_ENV_FIELD: &str = "_1";
+
 /// Context for MIR lowering
 #[derive(Debug)]
 pub struct LoweringContext {
@@ -437,6 +541,35 @@ pub struct LoweringContext {
     pub params: HashMap<String, MirType>,
     /// v0.35.4: Function return types for Call type inference
     pub func_return_types: HashMap<String, MirType>,
+    /// Variables holding `HashMap<K, V>` values, whose methods lower to the
+    /// `hashmap_*` builtins
+    pub map_vars: HashSet<String>,
+    /// Inherent impl methods: (type name, method) -> lowered function name
+    pub inherent_methods: HashMap<(String, String), String>,
+    /// Struct or enum type name of variables, for resolving method receivers
+    pub var_types: HashMap<String, String>,
+    /// Value of `ret` while lowering a checked postcondition
+    pub ret_value: Option<Operand>,
+    /// Locals holding parameters' entry values, for `old()` in a checked
+    /// postcondition
+    pub entry_values: HashMap<String, String>,
+    /// Lowering the inside of `old(...)`/`.pre`
+    pub in_old: bool,
+    /// Values of `const` items, substituted where no local shadows them
+    pub consts: HashMap<String, Constant>,
+    /// Fields of each struct, for filling `..base` in struct updates and
+    /// omitted fields from their defaults
+    pub struct_fields: HashMap<String, Vec<crate::ast::StructField>>,
+    /// Record source locations as `DebugLoc` instructions
+    pub debug_info: bool,
+    /// Make integer `+`, `-` and `*` fail like a contract on overflow
+    pub check_overflow: bool,
+    /// Name of the function being lowered, prefixing its closures' names
+    pub fn_name: String,
+    /// Closures lifted out of the function being lowered
+    pub closures: Vec<MirFunction>,
+    /// Counter for naming lifted closures
+    closure_counter: usize,
 }
 
 impl LoweringContext {
@@ -445,6 +578,9 @@ impl LoweringContext {
         let mut func_return_types = HashMap::new();
         // Math intrinsics
         func_return_types.insert("sqrt".to_string(), MirType::F64);
+        for name in ["sin", "cos", "pow", "floor", "ceil", "round", "ln", "exp"] {
+            func_return_types.insert(name.to_string(), MirType::F64);
+        }
         func_return_types.insert("abs".to_string(), MirType::I64);
         func_return_types.insert("min".to_string(), MirType::I64);
         func_return_types.insert("max".to_string(), MirType::I64);
@@ -457,6 +593,27 @@ impl LoweringContext {
         func_return_types.insert("println".to_string(), MirType::Unit);
         func_return_types.insert("print".to_string(), MirType::Unit);
         func_return_types.insert("assert".to_string(), MirType::Unit);
+        func_return_types.insert("flush".to_string(), MirType::Unit);
+        // String builtins
+        for name in ["str_contains", "str_starts_with", "str_ends_with"] {
+            func_return_types.insert(name.to_string(), MirType::Bool);
+        }
+        for name in ["str_replace", "str_trim", "str_from_id", "vec_get_str", "hashmap_get_str"] {
+            func_return_types.insert(name.to_string(), MirType::String);
+        }
+        for name in ["str_index_of", "parse_int", "str_split"] {
+            func_return_types.insert(name.to_string(), MirType::I64);
+        }
+        // HashMap builtins (vec_* is lowered inline by the backends)
+        for name in [
+            "hashmap_new", "hashmap_insert", "hashmap_get", "hashmap_contains", "hashmap_remove", "hashmap_len",
+            "hashmap_insert_str_key", "hashmap_get_str_key", "hashmap_contains_str_key",
+        ] {
+            func_return_types.insert(name.to_string(), MirType::I64);
+        }
+        for name in ["vec_push_str", "hashmap_insert_str", "hashmap_free"] {
+            func_return_types.insert(name.to_string(), MirType::Unit);
+        }
 
         Self {
             temp_counter: 0,
@@ -467,6 +624,19 @@ impl LoweringContext {
             locals: HashMap::new(),
             params: HashMap::new(),
             func_return_types,
+            map_vars: HashSet::new(),
+            inherent_methods: HashMap::new(),
+            var_types: HashMap::new(),
+            ret_value: None,
+            entry_values: HashMap::new(),
+            in_old: false,
+            consts: HashMap::new(),
+            struct_fields: HashMap::new(),
+            debug_info: false,
+            check_overflow: false,
+            fn_name: String::new(),
+            closures: Vec::new(),
+            closure_counter: 0,
         }
     }
 
@@ -477,6 +647,13 @@ impl LoweringContext {
         Place::new(name)
     }
 
+    /// Generate a fresh name for a closure lifted out of this function
+    pub fn fresh_closure_name(&mut self) -> String {
+        let name = format!("{}.closure{}", self.fn_name, self.closure_counter);
+        self.closure_counter += 1;
+        name
+    }
+
     /// Generate a fresh block label
     pub fn fresh_label(&mut self, prefix: &str) -> String {
         let label = format!("{}_{}", prefix, self.block_counter);
@@ -489,6 +666,32 @@ impl LoweringContext {
         self.current_instructions.push(inst);
     }
 
+    /// Local holding the value parameter `name` had on entry. It is copied
+    /// at the top of the entry block the first time it is asked for
+    pub fn entry_value(&mut self, name: &str) -> Place {
+        if let Some(local) = self.entry_values.get(name) {
+            return Place::new(local.clone());
+        }
+        let local = format!("__old_{}", name);
+        let ty = self.params.get(name).cloned().unwrap_or(MirType::I64);
+        self.locals.insert(local.clone(), ty);
+        let copy = MirInst::Copy { dest: Place::new(local.clone()), src: Place::new(name) };
+        match self.blocks.first_mut() {
+            Some(entry) => entry.instructions.insert(0, copy),
+            None => self.current_instructions.insert(0, copy),
+        }
+        self.entry_values.insert(name.to_string(), local.clone());
+        Place::new(local)
+    }
+
+    /// Mark the start of the code for the source at `span`, when recording
+    /// debug info
+    pub fn debug_loc(&mut self, span: Span) {
+        if self.debug_info {
+            self.current_instructions.push(MirInst::DebugLoc { span });
+        }
+    }
+
     /// Finish the current block with a terminator
     pub fn finish_block(&mut self, terminator: Terminator) {
         let block = BasicBlock {
@@ -521,6 +724,7 @@ impl LoweringContext {
                 // v0.64: Character type
                 Constant::Char(_) => MirType::Char,
                 Constant::Unit => MirType::Unit,
+                Constant::FnAddr(_) => MirType::I64,
             },
             Operand::Place(p) => {
                 if let Some(ty) = self.locals.get(&p.name) {
@@ -610,6 +814,9 @@ fn format_mir_inst(inst: &MirInst) -> String {
         MirInst::UnaryOp { dest, op, src } => {
             format!("%{} = {} {}", dest.name, format_unaryop(*op), format_operand(src))
         }
+        MirInst::Cast { dest, src, ty } => {
+            format!("%{} = cast {} to {}", dest.name, format_operand(src), format_mir_type(ty))
+        }
         MirInst::Call { dest, func, args } => {
             let args_str: Vec<_> = args.iter().map(format_operand).collect();
             if let Some(d) = dest {
@@ -618,6 +825,14 @@ fn format_mir_inst(inst: &MirInst) -> String {
                 format!("call {}({})", func, args_str.join(", "))
             }
         }
+        MirInst::CallIndirect { dest, callee, args } => {
+            let args_str: Vec<_> = args.iter().map(format_operand).collect();
+            if let Some(d) = dest {
+                format!("%{} = call-indirect {}({})", d.name, format_operand(callee), args_str.join(", "))
+            } else {
+                format!("call-indirect {}({})", format_operand(callee), args_str.join(", "))
+            }
+        }
         MirInst::Phi { dest, values } => {
             let vals: Vec<_> = values.iter()
                 .map(|(v, lbl)| format!("[{}, {}]", format_operand(v), lbl))
@@ -654,6 +869,7 @@ fn format_mir_inst(inst: &MirInst) -> String {
         MirInst::IndexStore { array, index, value } => {
             format!("%{}[{}] = {}", array.name, format_operand(index), format_operand(value))
         }
+        MirInst::DebugLoc { span } => format!("loc {}..{}", span.start, span.end),
     }
 }
 
@@ -694,6 +910,7 @@ fn format_constant(c: &Constant) -> String {
         // v0.64: Character constant
         Constant::Char(c) => format!("C:'{}'", c.escape_default()),
         Constant::Unit => "U".to_string(),
+        Constant::FnAddr(name) => format!("&{}", name),
     }
 }
 
@@ -713,6 +930,9 @@ fn format_binop(op: MirBinOp) -> String {
         MirBinOp::AddChecked => "+?",
         MirBinOp::SubChecked => "-?",
         MirBinOp::MulChecked => "*?",
+        MirBinOp::AddOverflows => "+!",
+        MirBinOp::SubOverflows => "-!",
+        MirBinOp::MulOverflows => "*!",
         // v0.38: Saturating arithmetic
         MirBinOp::AddSat => "+|",
         MirBinOp::SubSat => "-|",
@@ -727,6 +947,12 @@ fn format_binop(op: MirBinOp) -> String {
         MirBinOp::Gt => ">",
         MirBinOp::Le => "<=",
         MirBinOp::Ge => ">=",
+        MirBinOp::UDiv => "/u",
+        MirBinOp::UMod => "%u",
+        MirBinOp::ULt => "<u",
+        MirBinOp::UGt => ">u",
+        MirBinOp::ULe => "<=u",
+        MirBinOp::UGe => ">=u",
         MirBinOp::FEq => "==.",
         MirBinOp::FNe => "!=.",
         MirBinOp::FLt => "<.",
@@ -738,6 +964,7 @@ fn format_binop(op: MirBinOp) -> String {
         // v0.32: Shift operators
         MirBinOp::Shl => "<<",
         MirBinOp::Shr => ">>",
+        MirBinOp::UShr => ">>u",
         // v0.36: Bitwise operators
         MirBinOp::Band => "band",
         MirBinOp::Bor => "bor",
@@ -782,5 +1009,13 @@ fn format_mir_type(ty: &MirType) -> String {
                 format!("[{}]", format_mir_type(element_type))
             }
         }
+        MirType::Fn { params, ret } => {
+            let params: Vec<_> = params.iter().map(format_mir_type).collect();
+            format!("fn({}) -> {}", params.join(", "), format_mir_type(ret))
+        }
+        MirType::Tuple(elements) => {
+            let elements: Vec<_> = elements.iter().map(format_mir_type).collect();
+            format!("({})", elements.join(", "))
+        }
     }
 }
@@ -21,8 +21,8 @@
 use std::collections::{HashMap, HashSet};
 
 use super::{
-    CmpOp, Constant, ContractFact, MirBinOp, MirFunction, MirInst, MirProgram, MirUnaryOp,
-    Operand, Place, Terminator,
+    BasicBlock, CmpOp, Constant, ContractFact, MirBinOp, MirFunction, MirInst, MirProgram,
+    MirUnaryOp, Operand, Place, Terminator,
 };
 
 /// Optimization pass trait
@@ -39,6 +39,10 @@ pub trait OptimizationPass {
 pub struct OptimizationPipeline {
     passes: Vec<Box<dyn OptimizationPass>>,
     max_iterations: usize,
+    /// Drop functions unreachable from the program's roots after the function passes
+    eliminate_dead_functions: bool,
+    /// Inline calls to `@inline` functions before the function passes
+    inline_functions: bool,
 }
 
 impl OptimizationPipeline {
@@ -47,6 +51,8 @@ impl OptimizationPipeline {
         Self {
             passes: Vec::new(),
             max_iterations: 10,
+            eliminate_dead_functions: false,
+            inline_functions: false,
         }
     }
 
@@ -64,6 +70,8 @@ impl OptimizationPipeline {
                 pipeline.add_pass(Box::new(DeadCodeElimination));
                 pipeline.add_pass(Box::new(SimplifyBranches));
                 pipeline.add_pass(Box::new(CopyPropagation));
+                pipeline.eliminate_dead_functions = true;
+                pipeline.inline_functions = true;
             }
             OptLevel::Aggressive => {
                 // All optimizations
@@ -74,6 +82,8 @@ impl OptimizationPipeline {
                 pipeline.add_pass(Box::new(CommonSubexpressionElimination));
                 pipeline.add_pass(Box::new(ContractBasedOptimization));
                 pipeline.add_pass(Box::new(ContractUnreachableElimination));
+                pipeline.eliminate_dead_functions = true;
+                pipeline.inline_functions = true;
             }
         }
 
@@ -94,6 +104,10 @@ impl OptimizationPipeline {
     pub fn optimize(&self, program: &mut MirProgram) -> OptimizationStats {
         let mut stats = OptimizationStats::new();
 
+        if self.inline_functions && inline_calls(program) {
+            stats.record_pass("inline_calls");
+        }
+
         // v0.38.3: Create PureFunctionCSE pass with program-level information
         let pure_cse = PureFunctionCSE::from_program(program);
 
@@ -105,6 +119,11 @@ impl OptimizationPipeline {
             stats.merge(&func_stats);
         }
 
+        // Run after the function passes, which may have removed calls
+        if self.eliminate_dead_functions && DeadFunctionElimination.run_on_program(program) {
+            stats.record_pass(DeadFunctionElimination.name());
+        }
+
         stats
     }
 
@@ -293,6 +312,18 @@ fn fold_binop(op: MirBinOp, lhs: &Constant, rhs: &Constant) -> Option<Constant>
         (MirBinOp::Gt, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool(a > b)),
         (MirBinOp::Ge, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool(a >= b)),
 
+        // Unsigned integer operations on the same bits
+        (MirBinOp::UDiv, Constant::Int(a), Constant::Int(b)) if *b != 0 => {
+            Some(Constant::Int(((*a as u64) / (*b as u64)) as i64))
+        }
+        (MirBinOp::UMod, Constant::Int(a), Constant::Int(b)) if *b != 0 => {
+            Some(Constant::Int(((*a as u64) % (*b as u64)) as i64))
+        }
+        (MirBinOp::ULt, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool((*a as u64) < (*b as u64))),
+        (MirBinOp::ULe, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool((*a as u64) <= (*b as u64))),
+        (MirBinOp::UGt, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool((*a as u64) > (*b as u64))),
+        (MirBinOp::UGe, Constant::Int(a), Constant::Int(b)) => Some(Constant::Bool((*a as u64) >= (*b as u64))),
+
         // Boolean operations
         (MirBinOp::And, Constant::Bool(a), Constant::Bool(b)) => Some(Constant::Bool(*a && *b)),
         (MirBinOp::Or, Constant::Bool(a), Constant::Bool(b)) => Some(Constant::Bool(*a || *b)),
@@ -388,7 +419,7 @@ fn collect_used_in_instruction(inst: &MirInst, used: &mut HashSet<String>) {
             collect_used_in_operand(lhs, used);
             collect_used_in_operand(rhs, used);
         }
-        MirInst::UnaryOp { src, .. } => {
+        MirInst::UnaryOp { src, .. } | MirInst::Cast { src, .. } => {
             collect_used_in_operand(src, used);
         }
         MirInst::Call { args, .. } => {
@@ -396,6 +427,12 @@ fn collect_used_in_instruction(inst: &MirInst, used: &mut HashSet<String>) {
                 collect_used_in_operand(arg, used);
             }
         }
+        MirInst::CallIndirect { callee, args, .. } => {
+            collect_used_in_operand(callee, used);
+            for arg in args {
+                collect_used_in_operand(arg, used);
+            }
+        }
         MirInst::Phi { values, .. } => {
             for (op, _) in values {
                 collect_used_in_operand(op, used);
@@ -432,6 +469,7 @@ fn collect_used_in_instruction(inst: &MirInst, used: &mut HashSet<String>) {
             collect_used_in_operand(index, used);
             collect_used_in_operand(value, used);
         }
+        MirInst::DebugLoc { .. } => {}
     }
 }
 
@@ -447,7 +485,8 @@ fn get_inst_dest(inst: &MirInst) -> Option<&Place> {
         MirInst::Copy { dest, .. } => Some(dest),
         MirInst::BinOp { dest, .. } => Some(dest),
         MirInst::UnaryOp { dest, .. } => Some(dest),
-        MirInst::Call { dest, .. } => dest.as_ref(),
+        MirInst::Cast { dest, .. } => Some(dest),
+        MirInst::Call { dest, .. } | MirInst::CallIndirect { dest, .. } => dest.as_ref(),
         MirInst::Phi { dest, .. } => Some(dest),
         MirInst::StructInit { dest, .. } => Some(dest),
         MirInst::FieldAccess { dest, .. } => Some(dest),
@@ -461,10 +500,363 @@ fn get_inst_dest(inst: &MirInst) -> Option<&Place> {
 fn has_side_effects(inst: &MirInst) -> bool {
     matches!(
         inst,
-        MirInst::Call { .. } | MirInst::FieldStore { .. } | MirInst::IndexStore { .. }
+        MirInst::Call { .. }
+            | MirInst::CallIndirect { .. }
+            | MirInst::FieldStore { .. }
+            | MirInst::IndexStore { .. }
     )
 }
 
+// ============================================================================
+// Dead Function Elimination Pass (program-level)
+// ============================================================================
+
+/// Dead function elimination: drop functions that are unreachable through
+/// the call graph from the roots (`main`, `pub` functions and functions
+/// named by extern declarations). A program without any root is left alone.
+pub struct DeadFunctionElimination;
+
+impl DeadFunctionElimination {
+    pub fn name(&self) -> &'static str {
+        "dead_function_elimination"
+    }
+
+    /// Returns true if any function was removed
+    pub fn run_on_program(&self, program: &mut MirProgram) -> bool {
+        let extern_names: HashSet<&str> = program.extern_fns.iter().map(|e| e.name.as_str()).collect();
+        let mut worklist: Vec<String> = program.functions.iter()
+            .filter(|f| f.name == "main" || f.is_public || extern_names.contains(f.name.as_str()))
+            .map(|f| f.name.clone())
+            .collect();
+        if worklist.is_empty() {
+            return false;
+        }
+
+        let call_graph: HashMap<&str, HashSet<&str>> = program.functions.iter()
+            .map(|f| (f.name.as_str(), collect_callees(f)))
+            .collect();
+
+        let mut reachable: HashSet<String> = HashSet::new();
+        while let Some(name) = worklist.pop() {
+            if !reachable.insert(name.clone()) {
+                continue;
+            }
+            if let Some(callees) = call_graph.get(name.as_str()) {
+                worklist.extend(callees.iter().map(|c| c.to_string()));
+            }
+        }
+
+        let original_len = program.functions.len();
+        program.functions.retain(|f| reachable.contains(&f.name));
+        program.functions.len() != original_len
+    }
+}
+
+/// Names of all functions called from a function body, or whose address
+/// it takes (lifted closures are only reached through `Constant::FnAddr`)
+fn collect_callees(func: &MirFunction) -> HashSet<&str> {
+    let mut callees = HashSet::new();
+    for inst in func.blocks.iter().flat_map(|block| block.instructions.iter()) {
+        if let MirInst::Call { func, .. } = inst {
+            callees.insert(func.as_str());
+        }
+        let args: &[Operand] = match inst {
+            MirInst::Call { args, .. }
+            | MirInst::CallIndirect { args, .. }
+            | MirInst::EnumVariant { args, .. } => args,
+            MirInst::Const { value: Constant::FnAddr(name), .. } => {
+                callees.insert(name.as_str());
+                &[]
+            }
+            _ => &[],
+        };
+        for arg in args {
+            if let Operand::Constant(Constant::FnAddr(name)) = arg {
+                callees.insert(name.as_str());
+            }
+        }
+    }
+    callees
+}
+
+// ============================================================================
+// Inlining Pass
+// ============================================================================
+
+/// Upper bound on inlined call sites per function, to keep code growth in check
+const MAX_INLINES_PER_FUNCTION: usize = 64;
+
+/// Replace calls to `@inline` functions with the callee's body.
+///
+/// Callee params, locals and temporaries are renamed (`x` becomes `x__i1`)
+/// so they cannot capture the caller's names. Recursive callees (directly
+/// or through other functions) are never inlined, nor are callees that
+/// return unit or have more than one return. The callee definition is kept,
+/// so its own contracts are unaffected. Returns true if any call was inlined.
+pub fn inline_calls(program: &mut MirProgram) -> bool {
+    let call_graph: HashMap<&str, HashSet<&str>> = program.functions.iter()
+        .map(|f| (f.name.as_str(), collect_callees(f)))
+        .collect();
+    let inlinable: HashMap<String, MirFunction> = program.functions.iter()
+        .filter(|f| f.is_inline && f.ret_ty != super::MirType::Unit)
+        .filter(|f| !reaches(&call_graph, &f.name, &f.name))
+        .filter(|f| f.blocks.iter().filter(|b| matches!(b.terminator, Terminator::Return(_))).count() == 1)
+        .map(|f| (f.name.clone(), f.clone()))
+        .collect();
+    if inlinable.is_empty() {
+        return false;
+    }
+
+    let mut changed = false;
+    for func in &mut program.functions {
+        let mut counter = 0;
+        while counter < MAX_INLINES_PER_FUNCTION {
+            let site = func.blocks.iter().enumerate().find_map(|(b, block)| {
+                block.instructions.iter().position(|inst| matches!(
+                    inst,
+                    MirInst::Call { func: callee, .. } if callee != &func.name && inlinable.contains_key(callee)
+                )).map(|i| (b, i))
+            });
+            let Some((block_idx, inst_idx)) = site else { break };
+            counter += 1;
+            inline_call_site(func, block_idx, inst_idx, &inlinable, counter);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Whether `to` is reachable from `from`'s callees in the call graph
+fn reaches(call_graph: &HashMap<&str, HashSet<&str>>, from: &str, to: &str) -> bool {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut worklist: Vec<&str> = call_graph.get(from).into_iter().flatten().copied().collect();
+    while let Some(name) = worklist.pop() {
+        if name == to {
+            return true;
+        }
+        if seen.insert(name) {
+            worklist.extend(call_graph.get(name).into_iter().flatten().copied());
+        }
+    }
+    false
+}
+
+/// Inline the call at `func.blocks[block_idx].instructions[inst_idx]`.
+///
+/// The block is split: instructions before the call bind the callee's
+/// params and jump into the copied callee blocks; the callee's return
+/// assigns the call's destination and jumps to a continuation block that
+/// holds the remaining instructions and the original terminator.
+fn inline_call_site(
+    func: &mut MirFunction,
+    block_idx: usize,
+    inst_idx: usize,
+    inlinable: &HashMap<String, MirFunction>,
+    n: usize,
+) {
+    let block = &mut func.blocks[block_idx];
+    let mut rest = block.instructions.split_off(inst_idx);
+    let MirInst::Call { dest, func: callee_name, args } = rest.remove(0) else {
+        unreachable!("inline site must be a call")
+    };
+    let callee = &inlinable[&callee_name];
+
+    // Every name the callee binds gets a fresh suffix
+    let mut bound: HashSet<String> = callee.params.iter().map(|(p, _)| p.clone()).collect();
+    bound.extend(callee.locals.iter().map(|(l, _)| l.clone()));
+    for inst in callee.blocks.iter().flat_map(|b| b.instructions.iter()) {
+        if let Some(d) = inst_dest(inst) {
+            bound.insert(d.name.clone());
+        }
+    }
+    let suffix = format!("__i{}", n);
+    let rename = |p: &Place| -> Place {
+        if bound.contains(&p.name) { Place::new(format!("{}{}", p.name, suffix)) } else { p.clone() }
+    };
+    let relabel = |l: &str| format!("{}{}", l, suffix);
+
+    // Bind arguments to the renamed params
+    for ((param, _), arg) in callee.params.iter().zip(args) {
+        let dest = Place::new(format!("{}{}", param, suffix));
+        block.instructions.push(match arg {
+            Operand::Place(src) => MirInst::Copy { dest, src },
+            Operand::Constant(value) => MirInst::Const { dest, value },
+        });
+    }
+    let cont_label = format!("inline_cont{}", suffix);
+    let original_label = block.label.clone();
+    let terminator = std::mem::replace(
+        &mut block.terminator,
+        Terminator::Goto(relabel(&callee.blocks[0].label)),
+    );
+
+    // Copy the callee's blocks, returning into the continuation
+    let mut inlined: Vec<BasicBlock> = Vec::with_capacity(callee.blocks.len() + 1);
+    for callee_block in &callee.blocks {
+        let mut instructions: Vec<MirInst> = callee_block.instructions.iter()
+            .map(|inst| rename_inst(inst, &rename, &relabel))
+            .collect();
+        let terminator = match &callee_block.terminator {
+            Terminator::Return(value) => {
+                if let (Some(d), Some(value)) = (&dest, value) {
+                    instructions.push(match rename_operand(value, &rename) {
+                        Operand::Place(src) => MirInst::Copy { dest: d.clone(), src },
+                        Operand::Constant(value) => MirInst::Const { dest: d.clone(), value },
+                    });
+                }
+                Terminator::Goto(cont_label.clone())
+            }
+            other => rename_terminator(other, &rename, &relabel),
+        };
+        inlined.push(BasicBlock { label: relabel(&callee_block.label), instructions, terminator });
+    }
+    inlined.push(BasicBlock { label: cont_label.clone(), instructions: rest, terminator });
+
+    // Successors of the split block now see the continuation as their predecessor
+    for other in &mut func.blocks {
+        for inst in &mut other.instructions {
+            if let MirInst::Phi { values, .. } = inst {
+                for (_, label) in values.iter_mut().filter(|(_, l)| *l == original_label) {
+                    *label = cont_label.clone();
+                }
+            }
+        }
+    }
+
+    let callee_locals = callee.params.iter().chain(&callee.locals)
+        .map(|(name, ty)| (format!("{}{}", name, suffix), ty.clone()));
+    func.locals.extend(callee_locals);
+    let at = block_idx + 1;
+    func.blocks.splice(at..at, inlined);
+}
+
+/// The place an instruction assigns, if any
+pub(crate) fn inst_dest(inst: &MirInst) -> Option<&Place> {
+    match inst {
+        MirInst::Const { dest, .. }
+        | MirInst::Copy { dest, .. }
+        | MirInst::BinOp { dest, .. }
+        | MirInst::UnaryOp { dest, .. }
+        | MirInst::Cast { dest, .. }
+        | MirInst::Phi { dest, .. }
+        | MirInst::StructInit { dest, .. }
+        | MirInst::FieldAccess { dest, .. }
+        | MirInst::EnumVariant { dest, .. }
+        | MirInst::ArrayInit { dest, .. }
+        | MirInst::IndexLoad { dest, .. } => Some(dest),
+        MirInst::Call { dest, .. } | MirInst::CallIndirect { dest, .. } => dest.as_ref(),
+        MirInst::FieldStore { .. } | MirInst::IndexStore { .. } | MirInst::DebugLoc { .. } => None,
+    }
+}
+
+fn rename_operand(op: &Operand, rename: &impl Fn(&Place) -> Place) -> Operand {
+    match op {
+        Operand::Place(p) => Operand::Place(rename(p)),
+        Operand::Constant(c) => Operand::Constant(c.clone()),
+    }
+}
+
+fn rename_inst(
+    inst: &MirInst,
+    rename: &impl Fn(&Place) -> Place,
+    relabel: &impl Fn(&str) -> String,
+) -> MirInst {
+    let op = |o: &Operand| rename_operand(o, rename);
+    match inst {
+        MirInst::Const { dest, value } => MirInst::Const { dest: rename(dest), value: value.clone() },
+        MirInst::Copy { dest, src } => MirInst::Copy { dest: rename(dest), src: rename(src) },
+        MirInst::BinOp { dest, op: bin_op, lhs, rhs } => MirInst::BinOp {
+            dest: rename(dest),
+            op: *bin_op,
+            lhs: op(lhs),
+            rhs: op(rhs),
+        },
+        MirInst::UnaryOp { dest, op: un_op, src } => MirInst::UnaryOp {
+            dest: rename(dest),
+            op: *un_op,
+            src: op(src),
+        },
+        MirInst::Cast { dest, src, ty } => MirInst::Cast {
+            dest: rename(dest),
+            src: op(src),
+            ty: ty.clone(),
+        },
+        MirInst::Call { dest, func, args } => MirInst::Call {
+            dest: dest.as_ref().map(rename),
+            func: func.clone(),
+            args: args.iter().map(op).collect(),
+        },
+        MirInst::CallIndirect { dest, callee, args } => MirInst::CallIndirect {
+            dest: dest.as_ref().map(rename),
+            callee: op(callee),
+            args: args.iter().map(op).collect(),
+        },
+        MirInst::Phi { dest, values } => MirInst::Phi {
+            dest: rename(dest),
+            values: values.iter().map(|(v, l)| (op(v), relabel(l))).collect(),
+        },
+        MirInst::StructInit { dest, struct_name, fields } => MirInst::StructInit {
+            dest: rename(dest),
+            struct_name: struct_name.clone(),
+            fields: fields.iter().map(|(f, v)| (f.clone(), op(v))).collect(),
+        },
+        MirInst::FieldAccess { dest, base, field } => MirInst::FieldAccess {
+            dest: rename(dest),
+            base: rename(base),
+            field: field.clone(),
+        },
+        MirInst::FieldStore { base, field, value } => MirInst::FieldStore {
+            base: rename(base),
+            field: field.clone(),
+            value: op(value),
+        },
+        MirInst::EnumVariant { dest, enum_name, variant, args } => MirInst::EnumVariant {
+            dest: rename(dest),
+            enum_name: enum_name.clone(),
+            variant: variant.clone(),
+            args: args.iter().map(op).collect(),
+        },
+        MirInst::ArrayInit { dest, element_type, elements } => MirInst::ArrayInit {
+            dest: rename(dest),
+            element_type: element_type.clone(),
+            elements: elements.iter().map(op).collect(),
+        },
+        MirInst::IndexLoad { dest, array, index } => MirInst::IndexLoad {
+            dest: rename(dest),
+            array: rename(array),
+            index: op(index),
+        },
+        MirInst::IndexStore { array, index, value } => MirInst::IndexStore {
+            array: rename(array),
+            index: op(index),
+            value: op(value),
+        },
+        MirInst::DebugLoc { span } => MirInst::DebugLoc { span: *span },
+    }
+}
+
+fn rename_terminator(
+    term: &Terminator,
+    rename: &impl Fn(&Place) -> Place,
+    relabel: &impl Fn(&str) -> String,
+) -> Terminator {
+    match term {
+        Terminator::Return(value) => Terminator::Return(value.as_ref().map(|v| rename_operand(v, rename))),
+        Terminator::Goto(label) => Terminator::Goto(relabel(label)),
+        Terminator::Branch { cond, then_label, else_label } => Terminator::Branch {
+            cond: rename_operand(cond, rename),
+            then_label: relabel(then_label),
+            else_label: relabel(else_label),
+        },
+        Terminator::Unreachable => Terminator::Unreachable,
+        Terminator::Switch { discriminant, cases, default } => Terminator::Switch {
+            discriminant: rename_operand(discriminant, rename),
+            cases: cases.iter().map(|(v, l)| (*v, relabel(l))).collect(),
+            default: relabel(default),
+        },
+    }
+}
+
 // ============================================================================
 // Simplify Branches Pass
 // ============================================================================
@@ -518,10 +910,21 @@ impl OptimizationPass for CopyPropagation {
         let mut changed = false;
         let mut copies: HashMap<String, Place> = HashMap::new();
 
+        // A struct copy is a separate value in memory that field stores may
+        // change, so it can't stand in for its source
+        let struct_locals: HashSet<&str> = func
+            .locals
+            .iter()
+            .filter(|(_, ty)| matches!(ty, super::MirType::Struct { .. }))
+            .map(|(name, _)| name.as_str())
+            .collect();
+
         for block in &mut func.blocks {
             // Build copy map
             for inst in &block.instructions {
-                if let MirInst::Copy { dest, src } = inst {
+                if let MirInst::Copy { dest, src } = inst
+                    && !struct_locals.contains(dest.name.as_str())
+                {
                     copies.insert(dest.name.clone(), src.clone());
                 }
             }
@@ -554,10 +957,8 @@ fn propagate_copies_in_inst(inst: &mut MirInst, copies: &HashMap<String, Place>)
                 changed = true;
             }
         }
-        MirInst::UnaryOp { src, .. } => {
-            if propagate_operand(src, copies) {
-                changed = true;
-            }
+        MirInst::UnaryOp { src, .. } | MirInst::Cast { src, .. } => {
+            changed |= propagate_operand(src, copies);
         }
         MirInst::Call { args, .. } => {
             for arg in args {
@@ -566,6 +967,12 @@ fn propagate_copies_in_inst(inst: &mut MirInst, copies: &HashMap<String, Place>)
                 }
             }
         }
+        MirInst::CallIndirect { callee, args, .. } => {
+            changed |= propagate_operand(callee, copies);
+            for arg in args {
+                changed |= propagate_operand(arg, copies);
+            }
+        }
         _ => {}
     }
 
@@ -1227,7 +1634,7 @@ impl ContractUnreachableElimination {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mir::{BasicBlock, MirType};
+    use crate::mir::MirType;
 
     fn make_test_function() -> MirFunction {
         MirFunction {
@@ -1259,6 +1666,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         }
     }
 
@@ -1300,6 +1709,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = DeadCodeElimination;
@@ -1310,6 +1721,56 @@ mod tests {
         assert_eq!(func.blocks[0].instructions.len(), 1);
     }
 
+    /// Function returning a call to each of `callees` (or a constant)
+    fn make_caller(name: &str, callees: &[&str], is_public: bool) -> MirFunction {
+        let instructions = callees.iter()
+            .map(|callee| MirInst::Call {
+                dest: None,
+                func: callee.to_string(),
+                args: vec![],
+            })
+            .collect();
+        MirFunction {
+            name: name.to_string(),
+            blocks: vec![BasicBlock {
+                label: "entry".to_string(),
+                instructions,
+                terminator: Terminator::Return(Some(Operand::Constant(Constant::Int(0)))),
+            }],
+            is_public,
+            ..make_test_function()
+        }
+    }
+
+    #[test]
+    fn test_dead_function_elimination() {
+        let mut program = MirProgram {
+            functions: vec![
+                make_caller("main", &["helper"], false),
+                make_caller("helper", &["leaf"], false),
+                make_caller("leaf", &[], false),
+                make_caller("orphan", &["leaf"], false),
+                make_caller("api", &[], true),
+            ],
+            extern_fns: vec![],
+        };
+
+        assert!(DeadFunctionElimination.run_on_program(&mut program));
+        let names: Vec<_> = program.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["main", "helper", "leaf", "api"]);
+    }
+
+    #[test]
+    fn test_dead_function_elimination_without_roots() {
+        // Nothing to anchor reachability on: keep every function
+        let mut program = MirProgram {
+            functions: vec![make_caller("a", &[], false), make_caller("b", &["a"], false)],
+            extern_fns: vec![],
+        };
+        assert!(!DeadFunctionElimination.run_on_program(&mut program));
+        assert_eq!(program.functions.len(), 2);
+    }
+
     #[test]
     fn test_optimization_pipeline() {
         let mut program = MirProgram {
@@ -1354,6 +1815,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = ContractBasedOptimization;
@@ -1398,6 +1861,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = ContractBasedOptimization;
@@ -1462,6 +1927,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = ContractUnreachableElimination;
@@ -1522,6 +1989,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = ContractUnreachableElimination;
@@ -1570,6 +2039,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let pass = ContractUnreachableElimination;
@@ -1619,6 +2090,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         // Create a pure function set containing "square"
@@ -1669,6 +2142,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let mut pure_functions = HashSet::new();
@@ -1707,6 +2182,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         // Empty pure function set - no functions are pure
@@ -1736,6 +2213,8 @@ mod tests {
             postconditions: vec![],
             is_pure: true,
             is_const: true,
+            is_inline: false,
+            is_public: false,
         };
 
         let mut caller_fn = MirFunction {
@@ -1764,6 +2243,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         // Create program with both functions
@@ -1812,6 +2293,8 @@ mod tests {
             postconditions: vec![],
             is_pure: true,
             is_const: true,
+            is_inline: false,
+            is_public: false,
         };
 
         let mut caller_fn = MirFunction {
@@ -1832,6 +2315,8 @@ mod tests {
             postconditions: vec![],
             is_pure: false,
             is_const: false,
+            is_inline: false,
+            is_public: false,
         };
 
         let program = MirProgram {
@@ -1847,4 +2332,67 @@ mod tests {
             "Const function with args should not be inlined (deferred)"
         );
     }
+
+    fn lower_source(source: &str, contracts: crate::mir::ContractMode) -> MirProgram {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        crate::mir::lower_program_with_contracts(&program, contracts)
+    }
+
+    fn function_mir(program: &MirProgram, name: &str) -> String {
+        let func = program.functions.iter().find(|f| f.name == name).unwrap();
+        crate::mir::format_mir(&MirProgram { functions: vec![func.clone()], extern_fns: vec![] })
+    }
+
+    #[test]
+    fn test_inline_calls() {
+        let source = "@inline\nfn square(x: i64) -> i64 = x * x;\n\n\
+                      fn main() -> i64 = { let x = 3; square(x) + square(4) };\n";
+        let mut program = lower_source(source, crate::mir::ContractMode::Off);
+        assert!(program.functions.iter().find(|f| f.name == "square").unwrap().is_inline);
+        assert!(function_mir(&program, "main").contains("call square"));
+
+        assert!(inline_calls(&mut program));
+        let main = function_mir(&program, "main");
+        assert!(!main.contains("call square"), "{}", main);
+        // The callee's `x` is renamed apart from the caller's
+        assert!(main.contains("%x__i1"), "{}", main);
+        assert!(main.contains("%x__i2"), "{}", main);
+        // The definition itself is kept
+        assert!(program.functions.iter().any(|f| f.name == "square"));
+    }
+
+    #[test]
+    fn test_inline_calls_keeps_contract_checks() {
+        let source = "@inline\nfn half(x: i64) -> i64 pre x >= 0 = x / 2;\n\n\
+                      fn main() -> i64 = half(8);\n";
+        let mut program = lower_source(source, crate::mir::ContractMode::Check);
+        assert!(inline_calls(&mut program));
+        let main = function_mir(&program, "main");
+        assert!(!main.contains("call half"), "{}", main);
+        assert!(main.contains(crate::mir::CONTRACT_FAIL_FN), "{}", main);
+        assert!(function_mir(&program, "half").contains(crate::mir::CONTRACT_FAIL_FN));
+    }
+
+    #[test]
+    fn test_inline_calls_skips_recursive_functions() {
+        let source = "@inline\nfn fact(n: i64) -> i64 = if n <= 1 { 1 } else { n * fact(n - 1) };\n\n\
+                      fn main() -> i64 = fact(5);\n";
+        let mut program = lower_source(source, crate::mir::ContractMode::Off);
+        assert!(!inline_calls(&mut program));
+        assert!(function_mir(&program, "main").contains("call fact"));
+    }
+
+    #[test]
+    fn test_inlining_only_in_release() {
+        let source = "@inline\nfn inc(x: i64) -> i64 = x + 1;\n\nfn main() -> i64 = inc(1);\n";
+        let mut debug = lower_source(source, crate::mir::ContractMode::Off);
+        OptimizationPipeline::for_level(OptLevel::Debug).optimize(&mut debug);
+        assert!(function_mir(&debug, "main").contains("call inc"));
+
+        let mut release = lower_source(source, crate::mir::ContractMode::Off);
+        let stats = OptimizationPipeline::for_level(OptLevel::Release).optimize(&mut release);
+        assert!(!function_mir(&release, "main").contains("call inc"));
+        assert!(stats.pass_counts.contains_key("inline_calls"));
+    }
 }
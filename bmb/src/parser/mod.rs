@@ -1,6 +1,6 @@
 //! Parser implementation using lalrpop
 
-use crate::ast::{Program, Span};
+use crate::ast::{Param, Program, Span, Spanned, Type};
 use crate::error::{CompileError, Result};
 use crate::lexer::Token;
 
@@ -35,3 +35,102 @@ pub fn parse(_filename: &str, _source: &str, tokens: Vec<(Token, Span)>) -> Resu
             CompileError::parser(format!("{e}"), span)
         })
 }
+
+/// Parse tokens into AST, skipping items that fail to parse
+///
+/// Panic-mode recovery for tools that want everything they can get from a
+/// broken file (index, LSP): on a syntax error the items before the failing
+/// one are kept, the error is recorded, and parsing resumes at the next
+/// token that can start an item in the first column of a line.
+pub fn parse_with_recovery(filename: &str, source: &str, tokens: Vec<(Token, Span)>) -> (Program, Vec<CompileError>) {
+    let mut program = Program { header: None, items: Vec::new() };
+    let mut errors = Vec::new();
+    let mut start = 0;
+    while start < tokens.len() {
+        let err = match parse(filename, source, tokens[start..].to_vec()) {
+            Ok(rest) => {
+                program.header = program.header.or(rest.header);
+                program.items.extend(rest.items);
+                break;
+            }
+            Err(err) => err,
+        };
+        let err_at = err
+            .span()
+            .map_or(start, |span| tokens.partition_point(|(_, s)| s.start < span.start))
+            .max(start);
+
+        // Everything before the failing item parses on its own
+        let item_start = last_item_boundary(&tokens, start, err_at);
+        if item_start > start
+            && let Ok(prefix) = parse(filename, source, tokens[start..item_start].to_vec())
+        {
+            program.header = program.header.or(prefix.header);
+            program.items.extend(prefix.items);
+        }
+        errors.push(err);
+
+        let after = err_at.max(item_start + 1);
+        let candidates = || (after..tokens.len()).filter(|&i| starts_item(&tokens[i].0));
+        start = candidates()
+            .find(|&i| at_line_start(source, tokens[i].1.start))
+            .or_else(|| candidates().next())
+            .unwrap_or(tokens.len());
+    }
+    (program, errors)
+}
+
+/// Tokens that can begin a top-level item (synchronization points)
+fn starts_item(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Fn
+            | Token::Struct
+            | Token::Enum
+            | Token::Trait
+            | Token::Impl
+            | Token::Use
+            | Token::Pub
+            | Token::Extern
+            | Token::Type
+            | Token::Const
+            | Token::At
+    )
+}
+
+/// Last index in `start..=end` where an item begins: an item token at
+/// bracket depth 0 right after the end of the previous item
+fn last_item_boundary(tokens: &[(Token, Span)], start: usize, end: usize) -> usize {
+    let mut boundary = start;
+    let mut depth = 0i32;
+    for i in start..end.min(tokens.len()) {
+        match tokens[i].0 {
+            Token::LBrace | Token::LParen | Token::LBracket => depth += 1,
+            Token::RBrace | Token::RParen | Token::RBracket => depth -= 1,
+            _ => {}
+        }
+        let ends_item = matches!(tokens[i].0, Token::Semi | Token::RBrace | Token::HeaderSep);
+        if depth == 0 && ends_item && tokens.get(i + 1).is_some_and(|(t, _)| starts_item(t)) {
+            boundary = i + 1;
+        }
+    }
+    boundary
+}
+
+/// Whether `offset` is in the first column of its line
+fn at_line_start(source: &str, offset: usize) -> bool {
+    source.get(..offset).is_some_and(|before| before.is_empty() || before.ends_with('\n'))
+}
+
+/// Bare `self` as the first impl method parameter, typed `Self`
+fn self_param(name: String, l: usize, r: usize) -> std::result::Result<Param, lalrpop_util::ParseError<usize, Token, String>> {
+    if name != "self" {
+        return Err(lalrpop_util::ParseError::User {
+            error: format!("parameter `{}` needs a type annotation", name),
+        });
+    }
+    Ok(Param {
+        name: Spanned::new(name, Span::new(l, r)),
+        ty: Spanned::new(Type::Named("Self".to_string()), Span::new(l, r)),
+    })
+}
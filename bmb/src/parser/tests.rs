@@ -2,7 +2,7 @@
 //!
 //! Phase 13: Comprehensive parser testing
 
-use crate::ast::{Expr, Item, Visibility};
+use crate::ast::{Expr, Item, Type, Visibility};
 use crate::lexer::tokenize;
 use crate::parser::parse;
 
@@ -124,6 +124,15 @@ fn test_parse_logical() {
     parse_ok("fn not_op(a: bool) -> bool = not a;");
 }
 
+#[test]
+fn test_parse_char_cast() {
+    let prog = parse_ok("fn letter(n: i64) -> char = n as char;");
+    if let Item::FnDef(f) = &prog.items[0] {
+        assert!(matches!(&f.body.node, Expr::Cast { ty, .. } if ty.node == Type::Char));
+    }
+    parse_ok("fn code(c: char) -> u32 = c as u32;");
+}
+
 // ============================================
 // Control Flow
 // ============================================
@@ -137,6 +146,23 @@ fn test_parse_if_then_else() {
     }
 }
 
+#[test]
+fn test_parse_then_else_if_chain() {
+    let prog = parse_ok(
+        "fn sign(x: i64) -> i64 = if x < 0 then 0 - 1 else if x == 0 then 0 else 1;",
+    );
+    let Item::FnDef(f) = &prog.items[0] else { panic!("expected fn") };
+    // Right-nested: the else branch of the first `if` is the second `if`
+    let Expr::If { else_branch, .. } = &f.body.node else { panic!("expected if") };
+    let Expr::If { else_branch: last, .. } = &else_branch.node else { panic!("expected else if") };
+    assert!(matches!(last.node, Expr::IntLit(1)));
+
+    // Keyword and braced forms mix within one chain
+    parse_ok("fn f(x: i64) -> i64 = if x < 0 { 1 } else if x == 0 then 2 else 3;");
+    parse_ok("fn f(a: bool, b: bool) -> i64 = if a then if b then 1 else 2 else 3;");
+    assert!(parse_fails("fn f(a: bool) -> i64 = if a then 1;"));
+}
+
 #[test]
 fn test_parse_let_binding() {
     parse_ok("fn test() -> i64 = { let x: i64 = 42; x };");
@@ -163,20 +189,14 @@ fn test_parse_while_loop_invariant() {
     "#;
     let prog = parse_ok(source);
 
-    // Navigate to the while loop through the nested let structure
-    // let x = 0; body is: while ...
+    // The let is a statement of its own; the while follows it
     if let Item::FnDef(f) = &prog.items[0] {
         if let Expr::Block(stmts) = &f.body.node {
-            // stmts[0] is the let expression
-            if let Expr::Let { body, .. } = &stmts[0].node {
-                // body is directly the while expression
-                if let Expr::While { invariant, .. } = &body.node {
-                    assert!(invariant.is_some(), "Expected invariant to be Some");
-                } else {
-                    panic!("Expected While expression, got {:?}", body.node);
-                }
+            assert!(matches!(stmts[0].node, Expr::LetStmt { .. }), "Expected let statement");
+            if let Expr::While { invariant, .. } = &stmts[1].node {
+                assert!(invariant.is_some(), "Expected invariant to be Some");
             } else {
-                panic!("Expected Let expression");
+                panic!("Expected While expression, got {:?}", stmts[1].node);
             }
         } else {
             panic!("Expected Block expression");
@@ -227,6 +247,18 @@ fn test_parse_struct_def() {
     }
 }
 
+#[test]
+fn test_parse_struct_field_defaults() {
+    let prog = parse_ok("struct Config { depth: i64 = 3, name: String, verbose: bool = false }");
+    if let Item::StructDef(s) = &prog.items[0] {
+        let defaults: Vec<_> = s.fields.iter().map(|f| f.default.is_some()).collect();
+        assert_eq!(defaults, [true, false, true]);
+        assert!(matches!(s.fields[0].default.as_ref().unwrap().node, Expr::IntLit(3)));
+    } else {
+        panic!("Expected StructDef");
+    }
+}
+
 #[test]
 fn test_parse_enum_def() {
     let source = r#"
@@ -255,6 +287,24 @@ fn test_parse_struct_init() {
     parse_ok(source);
 }
 
+#[test]
+fn test_parse_struct_update() {
+    let source = "fn f(p: Point) -> Point = new Point { x: 1, ..p };";
+    let prog = parse_ok(source);
+    let Item::FnDef(f) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    let Expr::StructInit { fields, base: Some(base), .. } = &f.body.node else {
+        panic!("Expected struct update, got {:?}", f.body.node);
+    };
+    assert_eq!(fields.len(), 1);
+    assert!(matches!(&base.node, Expr::Var(name) if name == "p"));
+
+    parse_ok("fn f(p: Point) -> Point = new Point { ..p };");
+    // Nothing may follow the base
+    assert!(parse_fails(&source.replace("..p", "..p, y: 2")));
+}
+
 #[test]
 fn test_parse_enum_variant() {
     let source = r#"
@@ -404,6 +454,21 @@ fn test_parse_return() {
     }
 }
 
+#[test]
+fn test_parse_return_value() {
+    let source = r#"
+        fn early(n: i64) -> i64 = if n < 0 then return 0 - n else n;
+    "#;
+    let prog = parse_ok(source);
+    if let Item::FnDef(f) = &prog.items[0]
+        && let Expr::If { then_branch, .. } = &f.body.node
+    {
+        assert!(matches!(then_branch.node, Expr::Return { value: Some(_) }));
+    } else {
+        panic!("expected an if body");
+    }
+}
+
 // ============================================
 // Nullable Type Syntax (v0.37)
 // ============================================
@@ -725,6 +790,150 @@ fn test_parse_quantifiers() {
     }
 }
 
+#[test]
+fn test_parse_old_as_pre_state() {
+    let source = "fn inc(x: i64) -> i64 post x == old(x) + 1 = x + 1;";
+    let prog = parse_ok(source);
+    let Item::FnDef(f) = &prog.items[0] else { panic!("Expected function") };
+    let Some(post) = &f.post else { panic!("Expected postcondition") };
+    let Expr::Binary { right, .. } = &post.node else { panic!("Expected comparison") };
+    let Expr::Binary { left, .. } = &right.node else { panic!("Expected addition") };
+    assert!(matches!(
+        &left.node,
+        Expr::StateRef { expr, state: crate::ast::StateKind::Pre } if matches!(&expr.node, Expr::Var(n) if n == "x")
+    ));
+}
+
+// ============================================
+// Inherent Impl Blocks
+// ============================================
+
+#[test]
+fn test_parse_inherent_impl() {
+    let source = r#"
+        struct Point { x: i64, y: i64 }
+        impl Point {
+            fn dist(self, other: Point) -> i64 = self.x - other.x;
+            fn origin_x(self) -> i64 = self.x;
+        }
+    "#;
+    let prog = parse_ok(source);
+    let Item::ImplBlock(i) = &prog.items[1] else {
+        panic!("Expected ImplBlock");
+    };
+    assert!(i.trait_name.is_none());
+    assert!(matches!(&i.target_type.node, crate::ast::Type::Named(n) if n == "Point"));
+    assert_eq!(i.methods.len(), 2);
+    // A bare `self` parameter has type `Self`
+    let params = &i.methods[0].params;
+    assert_eq!(params.len(), 2);
+    assert_eq!(params[0].name.node, "self");
+    assert!(matches!(&params[0].ty.node, crate::ast::Type::Named(n) if n == "Self"));
+
+    // Only `self` may omit its type annotation
+    assert!(parse_fails("impl Point { fn f(other) -> i64 = 0; }"));
+}
+
+#[test]
+fn test_parse_const_def() {
+    let source = "pub const SIZE: i64 = 4 * 2;\nfn first(xs: [i64; SIZE]) -> i64 = xs[0];";
+    let prog = parse_ok(source);
+    let Item::ConstDef(c) = &prog.items[0] else {
+        panic!("Expected ConstDef");
+    };
+    assert_eq!(c.name.node, "SIZE");
+    assert_eq!(c.visibility, crate::ast::Visibility::Public);
+    assert!(matches!(c.value.node, crate::ast::Expr::Binary { .. }));
+    let Item::FnDef(f) = &prog.items[1] else {
+        panic!("Expected FnDef");
+    };
+    assert!(matches!(
+        &f.params[0].ty.node,
+        crate::ast::Type::ArrayConst(_, n) if n == "SIZE"
+    ));
+
+    // The type annotation and initializer are required
+    assert!(parse_fails("const N = 1;"));
+    assert!(parse_fails("const N: i64;"));
+}
+
+#[test]
+fn test_parse_let_statements_flat_in_block() {
+    let source = "fn f(n: i64) -> i64 = { let mut x = n; x = x + 1; let y = x; y };";
+    let prog = parse_ok(source);
+    let Item::FnDef(f) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    let Expr::Block(stmts) = &f.body.node else {
+        panic!("Expected Block expression");
+    };
+    assert_eq!(stmts.len(), 4);
+    assert!(matches!(&stmts[0].node, Expr::LetStmt { name, mutable: true, .. } if name == "x"));
+    assert!(matches!(stmts[1].node, Expr::Assign { .. }));
+    assert!(matches!(&stmts[2].node, Expr::LetStmt { name, mutable: false, .. } if name == "y"));
+    assert!(matches!(stmts[3].node, Expr::Var(_)));
+    // The statement span ends at the value, before the `;`
+    assert_eq!(&source[stmts[0].span.start..stmts[0].span.end], "let mut x = n");
+
+    // A let may end the block; outside a block it still needs a body
+    let prog = parse_ok("fn g() -> () = { let x = 1; };");
+    let Item::FnDef(g) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    assert!(matches!(&g.body.node, Expr::Block(stmts) if stmts.len() == 1));
+    let prog = parse_ok("fn h() -> i64 = let x = 1; x;");
+    let Item::FnDef(h) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    assert!(matches!(h.body.node, Expr::Let { .. }));
+}
+
+#[test]
+fn test_parse_index_assign() {
+    let source = "fn f() -> i64 = { let mut a = [1, 2]; a[0] = 5; a[1 + 0] = a[0]; a[0] };";
+    let prog = parse_ok(source);
+    let Item::FnDef(f) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    let Expr::Block(stmts) = &f.body.node else {
+        panic!("Expected Block expression");
+    };
+    assert!(matches!(&stmts[1].node, Expr::IndexAssign { array, .. } if matches!(&array.node, Expr::Var(n) if n == "a")));
+    assert!(matches!(&stmts[2].node, Expr::IndexAssign { value, .. } if matches!(value.node, Expr::Index { .. })));
+    assert_eq!(&source[stmts[1].span.start..stmts[1].span.end], "a[0] = 5");
+
+    // Nested targets parse as an assignment to the inner index
+    let prog = parse_ok("fn g() -> () = { let mut m = [[0]]; m[0][0] = 1; };");
+    let Item::FnDef(g) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    let Expr::Block(stmts) = &g.body.node else {
+        panic!("Expected Block expression");
+    };
+    assert!(matches!(&stmts[1].node, Expr::IndexAssign { array, .. } if matches!(array.node, Expr::Index { .. })));
+}
+
+#[test]
+fn test_parse_field_assign() {
+    let source = "fn f() -> () = { let mut p = new P { x: 1 }; p.x = 5; p.a.b = p.x; p.xs[0] = 1; };";
+    let prog = parse_ok(source);
+    let Item::FnDef(f) = &prog.items[0] else {
+        panic!("Expected FnDef");
+    };
+    let Expr::Block(stmts) = &f.body.node else {
+        panic!("Expected Block expression");
+    };
+    assert!(matches!(&stmts[1].node, Expr::FieldAssign { object, field, .. }
+        if matches!(&object.node, Expr::Var(n) if n == "p") && field.node == "x"));
+    assert_eq!(&source[stmts[1].span.start..stmts[1].span.end], "p.x = 5");
+
+    // Nested targets assign to the innermost field
+    assert!(matches!(&stmts[2].node, Expr::FieldAssign { object, field, value }
+        if matches!(object.node, Expr::FieldAccess { .. }) && field.node == "b"
+            && matches!(value.node, Expr::FieldAccess { .. })));
+    assert!(matches!(&stmts[3].node, Expr::IndexAssign { array, .. } if matches!(array.node, Expr::FieldAccess { .. })));
+}
+
 // ============================================
 // Negative Tests (Parser Errors)
 // ============================================
@@ -735,3 +944,44 @@ fn test_parse_invalid_syntax() {
     assert!(parse_fails("fn foo ->")); // Missing return type
     assert!(parse_fails("struct { }")); // Missing struct name
 }
+
+// ============================================
+// Error Recovery
+// ============================================
+
+fn recover(source: &str) -> (crate::ast::Program, Vec<crate::CompileError>) {
+    let tokens = tokenize(source).unwrap();
+    crate::parser::parse_with_recovery("test.bmb", source, tokens)
+}
+
+fn item_names(prog: &crate::ast::Program) -> Vec<&str> {
+    prog.items.iter().filter_map(|item| item.name()).collect()
+}
+
+#[test]
+fn test_recovery_skips_item_missing_semicolon() {
+    let source = "fn a() -> i64 = 1;\nfn b() -> i64 = 2\nstruct P { x: i64 }\npub fn c() -> i64 = 3;\n";
+    let (prog, errors) = recover(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span().unwrap().start, source.find("struct").unwrap());
+    assert_eq!(item_names(&prog), vec!["a", "P", "c"]);
+}
+
+#[test]
+fn test_recovery_resumes_after_broken_impl_method() {
+    let source = "struct S { x: i64 }\nimpl S {\n    fn get(self) -> i64 = self.x +;\n    fn put(self) -> i64 = 0;\n}\nfn after() -> i64 = 1;\nfn bad( -> i64 = 2;\nfn last() -> i64 = 3;\n";
+    let (prog, errors) = recover(source);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(item_names(&prog), vec!["S", "after", "last"]);
+}
+
+#[test]
+fn test_recovery_matches_parse_on_valid_input() {
+    let (prog, errors) = recover("fn a() -> i64 = 1;\nfn b() -> i64 = 2;\n");
+    assert!(errors.is_empty());
+    assert_eq!(item_names(&prog), vec!["a", "b"]);
+
+    let (prog, errors) = recover("fn a() -> i64 = {\n    1\n");
+    assert_eq!(errors.len(), 1);
+    assert!(prog.items.is_empty());
+}
@@ -0,0 +1,288 @@
+//! BMB Projects
+//!
+//! A project is a directory with a `bmb.toml` manifest naming the package,
+//! its source root, and the path dependencies its modules may `use`:
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! version = "0.1.0"
+//!
+//! [dependencies]
+//! mathlib = { path = "../mathlib" }
+//!
+//! [lint]
+//! shadow_binding = "allow"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// File name of the project manifest
+pub const MANIFEST_FILE: &str = "bmb.toml";
+
+/// Project loading and scaffolding error
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid manifest {path}: {message}")]
+    InvalidManifest { path: PathBuf, message: String },
+
+    #[error("dependency '{name}' not found at {path}")]
+    MissingDependency { name: String, path: PathBuf },
+
+    #[error("invalid package name '{0}': use letters, digits, '_' and '-', starting with a letter")]
+    InvalidName(String),
+
+    #[error("destination {0} already exists")]
+    AlreadyExists(PathBuf),
+}
+
+/// Result type for project operations
+pub type ProjectResult<T> = Result<T, ProjectError>;
+
+/// Contents of a `bmb.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// Level of each warning kind under `bmb lint` (see [`crate::lint`])
+    #[serde(default)]
+    pub lint: BTreeMap<String, crate::lint::LintLevel>,
+}
+
+/// The `[package]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Source root relative to the manifest (default: `src`)
+    #[serde(default = "default_source_root")]
+    pub src: PathBuf,
+}
+
+fn default_source_root() -> PathBuf {
+    PathBuf::from("src")
+}
+
+/// An entry of the `[dependencies]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    /// Package directory, relative to the manifest
+    pub path: PathBuf,
+}
+
+impl Manifest {
+    /// Parse manifest text; `path` is only used in errors
+    pub fn parse(text: &str, path: &Path) -> ProjectResult<Self> {
+        toml::from_str(text).map_err(|e| ProjectError::InvalidManifest {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })
+    }
+}
+
+/// A project directory and its manifest
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Directory holding `bmb.toml`
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    /// Load the project whose manifest is in `root`
+    pub fn load(root: &Path) -> ProjectResult<Self> {
+        let path = root.join(MANIFEST_FILE);
+        let text = std::fs::read_to_string(&path).map_err(|source| ProjectError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            manifest: Manifest::parse(&text, &path)?,
+        })
+    }
+
+    /// Find the project containing `dir`: the nearest ancestor with a manifest
+    pub fn discover(dir: &Path) -> ProjectResult<Option<Self>> {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let dir = std::path::absolute(dir).map_err(|source| ProjectError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+        match dir.ancestors().find(|d| d.join(MANIFEST_FILE).is_file()) {
+            Some(root) => Self::load(root).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Package name
+    pub fn name(&self) -> &str {
+        &self.manifest.package.name
+    }
+
+    /// Directory holding the package's modules
+    pub fn source_root(&self) -> PathBuf {
+        self.root.join(&self.manifest.package.src)
+    }
+
+    /// Entry point of an executable package
+    pub fn main_file(&self) -> PathBuf {
+        self.source_root().join("main.bmb")
+    }
+
+    /// Directory holding the package's `test_*.bmb` files
+    pub fn tests_dir(&self) -> PathBuf {
+        self.root.join("tests")
+    }
+
+    /// Each dependency by the module name it is imported as (`my-lib` is
+    /// `use my_lib`), with its source root. A dependency with its own
+    /// manifest uses that manifest's source root.
+    pub fn dependencies(&self) -> ProjectResult<Vec<(String, PathBuf)>> {
+        self.manifest
+            .dependencies
+            .iter()
+            .map(|(name, dep)| {
+                let dir = self.root.join(&dep.path);
+                if !dir.is_dir() {
+                    return Err(ProjectError::MissingDependency { name: name.clone(), path: dir });
+                }
+                let source_root = if dir.join(MANIFEST_FILE).is_file() {
+                    Self::load(&dir)?.source_root()
+                } else {
+                    dir.join("src")
+                };
+                Ok((name.replace('-', "_"), source_root))
+            })
+            .collect()
+    }
+}
+
+/// Create a new project named `name` in `parent`, returning its root
+pub fn create(parent: &Path, name: &str) -> ProjectResult<PathBuf> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(ProjectError::InvalidName(name.to_string()));
+    }
+
+    let root = parent.join(name);
+    if root.exists() {
+        return Err(ProjectError::AlreadyExists(root));
+    }
+
+    let files = [
+        (MANIFEST_FILE.to_string(), manifest_template(name)),
+        ("src/main.bmb".to_string(), main_template(name)),
+        ("tests/test_main.bmb".to_string(), TEST_TEMPLATE.to_string()),
+    ];
+    for (rel, content) in files {
+        let path = root.join(rel);
+        let io_error = |source| ProjectError::Io { path: path.clone(), source };
+        std::fs::create_dir_all(path.parent().unwrap()).map_err(io_error)?;
+        std::fs::write(&path, content).map_err(io_error)?;
+    }
+
+    Ok(root)
+}
+
+fn manifest_template(name: &str) -> String {
+    format!(
+        "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n\n[dependencies]\n# mylib = {{ path = \"../mylib\" }}\n"
+    )
+}
+
+fn main_template(name: &str) -> String {
+    format!(
+        "pub fn greeting() -> String = \"Hello, {name}!\";\n\nfn main() -> i64 = {{\n    println_str(greeting());\n    0\n}};\n"
+    )
+}
+
+const TEST_TEMPLATE: &str = "use main::greeting;\n\nfn test_greeting() -> bool = greeting().len() > 0;\n";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bmb_project_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_manifest_defaults_and_errors() {
+        let path = Path::new("bmb.toml");
+        let manifest = Manifest::parse("[package]\nname = \"app\"\n", path).unwrap();
+        assert_eq!(manifest.package.src, PathBuf::from("src"));
+        assert!(manifest.dependencies.is_empty());
+
+        let manifest = Manifest::parse(
+            "[package]\nname = \"app\"\nsrc = \"lib\"\n[dependencies]\nmy-lib = { path = \"../my-lib\" }\n",
+            path,
+        )
+        .unwrap();
+        assert_eq!(manifest.package.src, PathBuf::from("lib"));
+        assert_eq!(manifest.dependencies["my-lib"].path, PathBuf::from("../my-lib"));
+
+        let err = Manifest::parse("[package]\nnam = \"app\"\n", path).unwrap_err();
+        assert!(matches!(err, ProjectError::InvalidManifest { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_create_and_discover() {
+        let dir = temp_dir("create");
+        let root = create(&dir, "hello-app").unwrap();
+        assert!(root.join("src/main.bmb").is_file());
+        assert!(root.join("tests/test_main.bmb").is_file());
+        assert!(matches!(create(&dir, "hello-app"), Err(ProjectError::AlreadyExists(_))));
+        assert!(matches!(create(&dir, "1app"), Err(ProjectError::InvalidName(_))));
+
+        let project = Project::discover(&root.join("tests")).unwrap().unwrap();
+        assert_eq!(project.name(), "hello-app");
+        assert_eq!(project.main_file(), std::path::absolute(root.join("src/main.bmb")).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_dependencies_resolve_source_roots() {
+        let dir = temp_dir("deps");
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+        std::fs::create_dir_all(dir.join("math-lib/lib")).unwrap();
+        std::fs::write(dir.join("math-lib/bmb.toml"), "[package]\nname = \"math-lib\"\nsrc = \"lib\"\n").unwrap();
+        std::fs::write(
+            dir.join("app/bmb.toml"),
+            "[package]\nname = \"app\"\n[dependencies]\nmath-lib = { path = \"../math-lib\" }\n",
+        )
+        .unwrap();
+
+        let project = Project::load(&dir.join("app")).unwrap();
+        let deps = project.dependencies().unwrap();
+        assert_eq!(deps, vec![("math_lib".to_string(), dir.join("app/../math-lib/lib"))]);
+
+        std::fs::write(
+            dir.join("app/bmb.toml"),
+            "[package]\nname = \"app\"\n[dependencies]\ngone = { path = \"../gone\" }\n",
+        )
+        .unwrap();
+        let project = Project::load(&dir.join("app")).unwrap();
+        assert!(matches!(project.dependencies(), Err(ProjectError::MissingDependency { .. })));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,293 @@
+//! HTTP front end of `bmb q serve`
+//!
+//! Parses a request off a connection and routes it to the [`QueryEngine`];
+//! the server loop itself lives in the CLI.
+
+use super::{format_output, BatchQuery, QueryEngine};
+use serde::Serialize;
+use std::io::{self, Read, Write};
+
+/// Route a query server request to its endpoint, returning status and JSON body
+pub fn route_request(engine: &QueryEngine, head: &str, body: &str) -> (u16, String) {
+    // Parse request line
+    let first_line = head.lines().next().unwrap_or("");
+    let parts: Vec<&str> = first_line.split_whitespace().collect();
+    let [method, path, ..] = parts.as_slice() else {
+        return (400, r#"{"error":"Malformed request line"}"#.to_string());
+    };
+
+    match (*method, *path) {
+        ("GET", "/health") => {
+            (200, r#"{"status":"ok","version":"0.50.22"}"#.to_string())
+        }
+        ("GET", "/metrics") => query_response(&engine.query_metrics()),
+        ("POST", "/query") => {
+            if body.trim().is_empty() {
+                (400, r#"{"error":"No request body"}"#.to_string())
+            } else {
+                handle_query_request(engine, body.trim())
+            }
+        }
+        _ => {
+            (404, r#"{"error":"Not found"}"#.to_string())
+        }
+    }
+}
+
+/// Read an HTTP request, returning its head (request line and headers) and body.
+/// The body is read up to `Content-Length` bytes, however many reads that
+/// takes; without the header it is whatever arrived with the head.
+pub fn read_request(stream: &mut impl Read) -> io::Result<(String, String)> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 8192];
+    let head_end = loop {
+        if let Some(i) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if let Some(i) = data.windows(2).position(|w| w == b"\n\n") {
+            break i + 2;
+        }
+        let n = stream.read(&mut buffer)?;
+        if n == 0 {
+            break data.len();
+        }
+        data.extend_from_slice(&buffer[..n]);
+    };
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| {
+            value.trim().parse::<usize>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid Content-Length: {}", value.trim())))
+        })
+        .transpose()?;
+    if let Some(len) = content_length {
+        while data.len() < head_end + len {
+            let n = stream.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buffer[..n]);
+        }
+        data.truncate(head_end + len);
+    }
+    let body = String::from_utf8_lossy(&data[head_end..]).into_owned();
+    Ok((head, body))
+}
+
+/// Serialize a query result as a 200 response, or a 500 if that fails
+fn query_response<T: Serialize>(result: &T) -> (u16, String) {
+    match format_output(result, "json") {
+        Ok(json) => (200, json),
+        Err(e) => (500, serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
+/// Handle POST /query request
+fn handle_query_request(engine: &QueryEngine, json_body: &str) -> (u16, String) {
+    // Parse query JSON
+    let query: serde_json::Value = match serde_json::from_str(json_body) {
+        Ok(v) => v,
+        Err(e) => return (400, serde_json::json!({ "error": format!("Invalid JSON: {}", e) }).to_string()),
+    };
+
+    let query_type = query.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+    match query_type {
+        "sym" => {
+            let pattern = query.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
+            let public = query.get("public").and_then(|v| v.as_bool()).unwrap_or(false);
+            let result = engine.query_symbols(pattern, None, public);
+            query_response(&result)
+        }
+        "fn" => {
+            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            if !name.is_empty() {
+                let result = engine.query_function(name);
+                query_response(&result)
+            } else {
+                (400, r#"{"error":"Missing 'name' field"}"#.to_string())
+            }
+        }
+        "type" => {
+            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            if !name.is_empty() {
+                let result = engine.query_type(name);
+                query_response(&result)
+            } else {
+                (400, r#"{"error":"Missing 'name' field"}"#.to_string())
+            }
+        }
+        "metrics" => {
+            let result = engine.query_metrics();
+            query_response(&result)
+        }
+        "unused" => query_response(&engine.query_unused()),
+        "deps" => {
+            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
+            let reverse = query.get("reverse").and_then(|v| v.as_bool()).unwrap_or(false);
+            let transitive = query.get("transitive").and_then(|v| v.as_bool()).unwrap_or(false);
+            let result = engine.query_deps(target, reverse, transitive);
+            query_response(&result)
+        }
+        "callers" | "callees" => {
+            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
+            if target.is_empty() {
+                return (400, r#"{"error":"Missing 'target' field"}"#.to_string());
+            }
+            let transitive = query.get("transitive").and_then(|v| v.as_bool()).unwrap_or(false);
+            if query_type == "callers" {
+                query_response(&engine.query_callers(target, transitive))
+            } else {
+                query_response(&engine.query_callees(target, transitive))
+            }
+        }
+        "contract" => {
+            let name = query.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let uses_old = query.get("uses_old").and_then(|v| v.as_bool()).unwrap_or(false);
+            let result = engine.query_contract(name, uses_old);
+            query_response(&result)
+        }
+        "impact" => {
+            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
+            let change = query.get("change").and_then(|v| v.as_str()).unwrap_or("");
+            let result = engine.query_impact(target, change);
+            query_response(&result)
+        }
+        "ctx" => {
+            let target = query.get("target").and_then(|v| v.as_str()).unwrap_or("");
+            if target.is_empty() {
+                return (400, r#"{"error":"Missing 'target' field"}"#.to_string());
+            }
+            let depth = query.get("depth").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+            let include_tests = query.get("include_tests").and_then(|v| v.as_bool()).unwrap_or(false);
+            query_response(&engine.query_context(target, depth, include_tests))
+        }
+        "sig" => {
+            let pattern = query.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
+            let accepts = query.get("accepts").and_then(|v| v.as_str());
+            let returns = query.get("returns").and_then(|v| v.as_str());
+            query_response(&engine.query_signature(pattern, accepts, returns))
+        }
+        "batch" => {
+            let Some(queries) = query.get("queries") else {
+                return (400, r#"{"error":"Missing 'queries' field"}"#.to_string());
+            };
+            let queries: Vec<BatchQuery> = match serde_json::from_value(queries.clone()) {
+                Ok(q) => q,
+                Err(e) => return (400, serde_json::json!({"error": format!("Invalid queries: {}", e)}).to_string()),
+            };
+            match engine.run_batch(&queries) {
+                Ok(result) => query_response(&result),
+                Err(e) => (500, serde_json::json!({"error": e.to_string()}).to_string()),
+            }
+        }
+        _ => {
+            (400, serde_json::json!({ "error": format!("Unknown query type: {}", query_type) }).to_string())
+        }
+    }
+}
+
+/// Write a JSON HTTP response that closes the connection
+pub fn write_json_response(stream: &mut impl Write, status: u16, body: &str) -> io::Result<()> {
+    let status_text = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, status_text, body.len(), body
+    );
+    stream.write_all(response.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A connection that delivers its data in the given pieces, one per read
+    struct Chunked(std::collections::VecDeque<Vec<u8>>);
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some(mut chunk) = self.0.pop_front() else { return Ok(0) };
+            let n = chunk.len().min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            if n < chunk.len() {
+                self.0.push_front(chunk.split_off(n));
+            }
+            Ok(n)
+        }
+    }
+
+    fn chunked(pieces: &[&[u8]]) -> Chunked {
+        Chunked(pieces.iter().map(|p| p.to_vec()).collect())
+    }
+
+    fn engine() -> QueryEngine {
+        let source = "fn main() -> i64 = twice(2);\n\
+                      pub fn twice(x: i64) -> i64 = x * 2;\n";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("main.bmb", source, tokens).unwrap();
+        let mut generator = crate::index::IndexGenerator::new("serve");
+        generator.index_source("main.bmb", source, &program);
+        QueryEngine::new(generator.generate())
+    }
+
+    fn post(engine: &QueryEngine, body: &str) -> (u16, serde_json::Value) {
+        let (status, json) = route_request(engine, "POST /query HTTP/1.1\r\n\r\n", body);
+        (status, serde_json::from_str(&json).unwrap())
+    }
+
+    #[test]
+    fn test_body_longer_than_one_read() {
+        let body = "x".repeat(20_000);
+        let head = format!("POST /query HTTP/1.1\r\nContent-Length: {}\r\n\r\n", body.len());
+        let mut stream = chunked(&[head.as_bytes(), &body.as_bytes()[..5_000], &body.as_bytes()[5_000..], b"trailing"]);
+
+        let (read_head, read_body) = read_request(&mut stream).unwrap();
+        assert_eq!(read_head, head);
+        assert_eq!(read_body.len(), 20_000);
+    }
+
+    #[test]
+    fn test_missing_content_length_takes_what_arrived() {
+        let mut stream = chunked(&[b"POST /query HTTP/1.1\r\n\r\n{\"type\"", b":\"metrics\"}"]);
+        let (_, body) = read_request(&mut stream).unwrap();
+        assert_eq!(body, "{\"type\"");
+    }
+
+    #[test]
+    fn test_invalid_content_length_is_an_error() {
+        let mut stream = chunked(&[b"POST /query HTTP/1.1\r\nContent-Length: ten\r\n\r\n{}"]);
+        let err = read_request(&mut stream).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_ctx_sig_and_batch_queries() {
+        let engine = engine();
+
+        let (status, ctx) = post(&engine, r#"{"type":"ctx","target":"fn:twice"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(ctx["target"]["name"], "twice");
+        assert_eq!(ctx["dependents"][0]["name"], "main");
+        assert_eq!(post(&engine, r#"{"type":"ctx"}"#).0, 400);
+
+        let (status, sig) = post(&engine, r#"{"type":"sig","accepts":"i64"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(sig["matches"][0]["name"], "twice");
+
+        let (status, batch) = post(&engine, r#"{"type":"batch","queries":[{"type":"fn","name":"twice"},{"type":"callers","target":"fn:twice"}]}"#);
+        assert_eq!(status, 200);
+        assert_eq!(batch["results"].as_array().unwrap().len(), 2);
+        assert_eq!(batch["results"][1]["query"], 1);
+        assert_eq!(post(&engine, r#"{"type":"batch"}"#).0, 400);
+    }
+}
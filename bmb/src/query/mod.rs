@@ -8,6 +8,8 @@
 //! - `compact`: Single-line format (space-efficient)
 //! - `llm`: LLM-optimized format (token-efficient, semantic sections)
 
+pub mod http;
+
 use crate::index::{FunctionEntry, ProjectIndex, SymbolEntry, SymbolKind, TypeEntry};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -221,6 +223,14 @@ impl QueryEngine {
             .filter(|f| f.body_info.as_ref().is_some_and(|b| b.recursive))
             .count();
 
+        let todo_count = self
+            .index
+            .functions
+            .iter()
+            .filter_map(|f| f.body_info.as_ref())
+            .map(|b| b.todos.len())
+            .sum();
+
         ProjectMetrics {
             project: ProjectStats {
                 files: self.index.manifest.files,
@@ -236,10 +246,77 @@ impl QueryEngine {
             },
             body_analysis: BodyAnalysis {
                 recursive_functions,
+                todo_count,
             },
         }
     }
 
+    /// List every `todo` placeholder left in a function body
+    pub fn query_todos(&self) -> TodoResult {
+        let todos: Vec<TodoInfo> = self
+            .index
+            .functions
+            .iter()
+            .flat_map(|f| {
+                let sites = f.body_info.as_ref().map(|b| b.todos.as_slice()).unwrap_or_default();
+                sites.iter().map(move |site| TodoInfo {
+                    function: f.name.clone(),
+                    file: f.file.clone(),
+                    line: site.line,
+                    message: site.message.clone(),
+                })
+            })
+            .collect();
+
+        TodoResult {
+            query: "todo".to_string(),
+            total: todos.len(),
+            todos,
+        }
+    }
+
+    /// List private symbols referenced nowhere in the project, except from
+    /// inside their own definition. Like the `unused_function` warning,
+    /// `main` and names starting with `_` are never reported.
+    pub fn query_unused(&self) -> UnusedResult {
+        let used: std::collections::HashSet<&str> = self
+            .index
+            .references
+            .iter()
+            .filter(|r| r.from.as_deref() != Some(r.name.as_str()))
+            .map(|r| r.name.as_str())
+            .collect();
+
+        let mut by_file: std::collections::BTreeMap<&str, Vec<UnusedSymbol>> = std::collections::BTreeMap::new();
+        for symbol in &self.index.symbols {
+            if symbol.is_pub
+                || symbol.name == "main"
+                || symbol.name.starts_with('_')
+                || used.contains(symbol.name.as_str())
+            {
+                continue;
+            }
+            by_file.entry(&symbol.file).or_default().push(UnusedSymbol {
+                kind: symbol.kind,
+                name: symbol.name.clone(),
+                line: symbol.line,
+            });
+        }
+
+        let files: Vec<UnusedFile> = by_file
+            .into_iter()
+            .map(|(file, mut symbols)| {
+                symbols.sort_by_key(|s| s.line);
+                UnusedFile { file: file.to_string(), symbols }
+            })
+            .collect();
+        UnusedResult {
+            query: "unused".to_string(),
+            total: files.iter().map(|f| f.symbols.len()).sum(),
+            files,
+        }
+    }
+
     fn suggest_symbols(&self, pattern: &str) -> Vec<String> {
         let pattern_lower = pattern.to_lowercase();
         self.index
@@ -313,6 +390,63 @@ pub struct ContractUsage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BodyAnalysis {
     pub recursive_functions: usize,
+    pub todo_count: usize,
+}
+
+/// Todo query result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoResult {
+    pub query: String,
+    pub total: usize,
+    pub todos: Vec<TodoInfo>,
+}
+
+/// A `todo` placeholder and the function it sits in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoInfo {
+    pub function: String,
+    pub file: String,
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl TodoResult {
+    /// One unchecked item per todo, for the `llm` format
+    pub fn to_checklist(&self) -> String {
+        let mut lines = vec![format!("TODO: {}", self.total)];
+        for todo in &self.todos {
+            let mut line = format!("- [ ] {} {}:{}", todo.function, todo.file, todo.line);
+            if let Some(message) = &todo.message {
+                line.push_str(&format!(" {}", message));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Unused symbol query result, grouped by file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedResult {
+    pub query: String,
+    pub total: usize,
+    pub files: Vec<UnusedFile>,
+}
+
+/// Unused private symbols of one file, in line order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedFile {
+    pub file: String,
+    pub symbols: Vec<UnusedSymbol>,
+}
+
+/// A private symbol that nothing references
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedSymbol {
+    pub kind: SymbolKind,
+    pub name: String,
+    pub line: usize,
 }
 
 // =============================================================================
@@ -354,6 +488,32 @@ pub struct CallerInfo {
     pub line: usize,
 }
 
+/// Call graph query result (`q callers` / `q callees`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallGraphResult {
+    pub query: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "is_false")]
+    pub transitive: bool,
+    pub total: usize,
+    pub functions: Vec<CallGraphEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<QueryError>,
+}
+
+/// A caller or callee; `depth` is 1 for direct calls and counts the call
+/// edges from the target otherwise
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallGraphEntry {
+    pub name: String,
+    /// Absent for builtins, which have no definition in the project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub depth: usize,
+}
+
 /// Contract query result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractResult {
@@ -609,6 +769,101 @@ impl QueryEngine {
         }
     }
 
+    /// Functions that call `target` (`fn:name` or a bare name); with
+    /// `transitive`, their callers too, each listed once even in a cycle
+    pub fn query_callers(&self, target: &str, transitive: bool) -> CallGraphResult {
+        self.query_call_graph("callers", target, transitive, |name| {
+            self.index
+                .functions
+                .iter()
+                .filter(|f| f.body_info.as_ref().is_some_and(|b| b.calls.iter().any(|c| c == name)))
+                .map(|f| f.name.clone())
+                .collect()
+        })
+    }
+
+    /// Functions that `target` calls; with `transitive`, everything they
+    /// call in turn
+    pub fn query_callees(&self, target: &str, transitive: bool) -> CallGraphResult {
+        self.query_call_graph("callees", target, transitive, |name| {
+            let mut callees: Vec<String> = Vec::new();
+            let calls = self.index.functions.iter().find(|f| f.name == name).and_then(|f| f.body_info.as_ref());
+            for call in calls.into_iter().flat_map(|b| &b.calls) {
+                if !callees.contains(call) {
+                    callees.push(call.clone());
+                }
+            }
+            callees
+        })
+    }
+
+    /// Breadth-first walk of the call graph from `target`, following `edges`
+    fn query_call_graph(
+        &self,
+        query: &str,
+        target: &str,
+        transitive: bool,
+        edges: impl Fn(&str) -> Vec<String>,
+    ) -> CallGraphResult {
+        let (kind, name) = target.split_once(':').unwrap_or(("fn", target));
+        let mut result = CallGraphResult {
+            query: query.to_string(),
+            target: format!("fn:{}", name),
+            transitive,
+            total: 0,
+            functions: Vec::new(),
+            error: None,
+        };
+
+        if kind != "fn" {
+            result.error = Some(QueryError {
+                code: "INVALID_TARGET".to_string(),
+                message: format!("Unknown target kind: {}. Use 'fn:name'", kind),
+                suggestions: vec![format!("fn:{}", name)],
+            });
+            return result;
+        }
+        if !self.index.functions.iter().any(|f| f.name == name) {
+            result.error = Some(QueryError {
+                code: "NOT_FOUND".to_string(),
+                message: format!("Function '{}' not found", name),
+                suggestions: self.suggest_functions(name),
+            });
+            return result;
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let mut frontier = vec![name.to_string()];
+        let mut depth = 1;
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for current in &frontier {
+                for found in edges(current) {
+                    if seen.insert(found.clone()) {
+                        let site = self.index.functions.iter().map(|f| (&f.name, &f.file, f.line))
+                            .chain(self.index.symbols.iter().map(|s| (&s.name, &s.file, s.line)))
+                            .find(|(n, _, _)| **n == found);
+                        result.functions.push(CallGraphEntry {
+                            name: found.clone(),
+                            file: site.map(|(_, file, _)| file.clone()),
+                            line: site.map(|(_, _, line)| line),
+                            depth,
+                        });
+                        next.push(found);
+                    }
+                }
+            }
+            if !transitive {
+                break;
+            }
+            frontier = next;
+            depth += 1;
+        }
+
+        result.total = result.functions.len();
+        result
+    }
+
     /// v0.47: Query contract details for a function
     pub fn query_contract(&self, name: &str, uses_old_filter: bool) -> ContractResult {
         let func = self.index.functions.iter().find(|f| f.name == name);
@@ -1152,10 +1407,14 @@ impl QueryEngine {
     pub fn query_batch(&self, file: &Path) -> Result<BatchResult, Box<dyn std::error::Error>> {
         let content = std::fs::read_to_string(file)?;
         let batch: BatchQueryFile = serde_json::from_str(&content)?;
+        Ok(self.run_batch(&batch.queries)?)
+    }
 
+    /// Run a list of batch queries, one result entry per query
+    pub fn run_batch(&self, queries: &[BatchQuery]) -> Result<BatchResult, serde_json::Error> {
         let mut results = Vec::new();
 
-        for (idx, query) in batch.queries.iter().enumerate() {
+        for (idx, query) in queries.iter().enumerate() {
             let result = match query.query_type.as_str() {
                 "fn" => {
                     if let Some(name) = &query.name {
@@ -1179,6 +1438,18 @@ impl QueryEngine {
                         serde_json::json!({"error": "deps requires target"})
                     }
                 }
+                "callers" | "callees" => {
+                    if let Some(target) = &query.target {
+                        let transitive = query.transitive.unwrap_or(false);
+                        if query.query_type == "callers" {
+                            serde_json::to_value(self.query_callers(target, transitive))?
+                        } else {
+                            serde_json::to_value(self.query_callees(target, transitive))?
+                        }
+                    } else {
+                        serde_json::json!({"error": format!("{} requires target", query.query_type)})
+                    }
+                }
                 "contract" => {
                     if let Some(name) = &query.name {
                         serde_json::to_value(self.query_contract(name, false))?
@@ -1187,6 +1458,7 @@ impl QueryEngine {
                     }
                 }
                 "metrics" => serde_json::to_value(self.query_metrics())?,
+                "unused" => serde_json::to_value(self.query_unused())?,
                 _ => serde_json::json!({"error": format!("unknown query type: {}", query.query_type)}),
             };
 
@@ -1516,6 +1788,84 @@ pub fn query_proofs(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_todo_checklist() {
+        let result = TodoResult {
+            query: "todo".to_string(),
+            total: 2,
+            todos: vec![
+                TodoInfo { function: "parse".to_string(), file: "a.bmb".to_string(), line: 3, message: Some("handle tabs".to_string()) },
+                TodoInfo { function: "emit".to_string(), file: "b.bmb".to_string(), line: 9, message: None },
+            ],
+        };
+        assert_eq!(result.to_checklist(), "TODO: 2\n- [ ] parse a.bmb:3 handle tabs\n- [ ] emit b.bmb:9");
+    }
+
+    fn cycle_engine() -> QueryEngine {
+        let source = "fn main() -> i64 = a(1) + c();\n\
+                      fn a(x: i64) -> i64 = if x > 0 { b(x - 1) } else { 0 };\n\
+                      fn b(x: i64) -> i64 = a(x) + c();\n\
+                      fn c() -> i64 = 1;\n";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("graph.bmb", source, tokens).unwrap();
+        let mut generator = crate::index::IndexGenerator::new("graph");
+        generator.index_source("graph.bmb", source, &program);
+        QueryEngine::new(generator.generate())
+    }
+
+    fn walk(result: &CallGraphResult) -> Vec<(&str, usize)> {
+        result.functions.iter().map(|f| (f.name.as_str(), f.depth)).collect()
+    }
+
+    #[test]
+    fn test_callers_and_callees_through_a_cycle() {
+        let engine = cycle_engine();
+
+        let callers = engine.query_callers("fn:c", false);
+        assert_eq!(walk(&callers), [("main", 1), ("b", 1)]);
+        assert_eq!(callers.functions[1].file.as_deref(), Some("graph.bmb"));
+        assert_eq!(callers.functions[1].line, Some(3));
+        let callers = engine.query_callers("c", true);
+        assert_eq!(walk(&callers), [("main", 1), ("b", 1), ("a", 2)]);
+        assert_eq!(callers.total, 3);
+
+        assert_eq!(walk(&engine.query_callees("fn:a", false)), [("b", 1)]);
+        // `a` reaches itself through `b` and is listed once
+        assert_eq!(walk(&engine.query_callees("fn:a", true)), [("b", 1), ("a", 2), ("c", 2)]);
+        assert!(engine.query_callees("fn:c", true).functions.is_empty());
+
+        let missing = engine.query_callers("fn:d", false);
+        assert_eq!(missing.error.unwrap().code, "NOT_FOUND");
+        let invalid = engine.query_callees("type:c", false);
+        assert_eq!(invalid.error.unwrap().code, "INVALID_TARGET");
+    }
+
+    #[test]
+    fn test_unused_symbols_across_files() {
+        let lib = "fn helper(x: i64) -> i64 = x + 1;\n\
+                   fn dead(n: i64) -> i64 = if n > 0 { dead(n - 1) } else { 0 };\n\
+                   struct Point { x: i64 }\n\
+                   pub fn exported() -> i64 = 1;\n";
+        let app = "fn main() -> i64 = helper(1);\n\
+                   pub fn origin() -> Point = new Point { x: 0 };\n";
+        let mut generator = crate::index::IndexGenerator::new("two-files");
+        for (file, source) in [("lib.bmb", lib), ("app.bmb", app)] {
+            let tokens = crate::lexer::tokenize(source).unwrap();
+            let program = crate::parser::parse(file, source, tokens).unwrap();
+            generator.index_source(file, source, &program);
+        }
+        let engine = QueryEngine::new(generator.generate());
+
+        // `helper` and `Point` are only used from app.bmb; `dead` only calls itself
+        let result = engine.query_unused();
+        let unused: Vec<_> = result.files.iter()
+            .flat_map(|f| f.symbols.iter().map(move |s| (f.file.as_str(), s.name.as_str(), s.line)))
+            .collect();
+        assert_eq!(unused, [("lib.bmb", "dead", 2)]);
+        assert_eq!(result.total, 1);
+        assert!(format_output(&result, "llm").unwrap().contains("NAME: dead"));
+    }
+
     #[test]
     fn test_levenshtein() {
         assert_eq!(levenshtein("hello", "hello"), 0);
@@ -1,26 +1,81 @@
 //! REPL (Read-Eval-Print Loop) for BMB
 
+use crate::ast::output::format_expr;
+use crate::ast::Item;
+use crate::fmt::format_type;
 use crate::interp::Interpreter;
 use crate::lexer::tokenize;
 use crate::parser::parse;
+use crate::types::TypeChecker;
 use rustyline::error::ReadlineError;
-use rustyline::{DefaultEditor, Result as RlResult};
-use std::path::PathBuf;
+use rustyline::{Config, DefaultEditor, Result as RlResult};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
 const HISTORY_FILE: &str = ".bmb_history";
+/// Most history entries kept, in memory and in the history file
+const HISTORY_SIZE: usize = 1000;
+
+/// Meta-commands with their aliases and help text
+const COMMANDS: &[(&str, &str)] = &[
+    (":help, :h, :?", "Show this help"),
+    (":quit, :q", "Exit the REPL"),
+    (":clear", "Clear the screen"),
+    (":type, :t EXPR", "Show the type of EXPR without running it"),
+    (":ast, :a EXPR", "Show how EXPR parses, as an S-expression"),
+    (":time EXPR", "Evaluate EXPR and report time and function calls"),
+    (":load, :l PATH", "Load the definitions of a .bmb file"),
+    (":reload, :r", "Load the last :load file again"),
+];
+
+/// Cost of one evaluation, as reported by `:time`
+#[derive(Debug, Clone, Copy)]
+pub struct RunStats {
+    /// Wall time spent in the interpreter
+    pub elapsed: Duration,
+    /// User function calls, not counting the REPL's own wrapper
+    pub calls: u64,
+}
+
+/// Outcome of feeding one line of input to the REPL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineInput {
+    /// Nothing to do (blank line with no pending input)
+    Empty,
+    /// Input is incomplete; more lines are needed
+    Continue,
+    /// Pending multi-line input was abandoned by an empty line
+    Aborted,
+    /// A complete command, item or expression ready to evaluate
+    Complete(String),
+}
 
 /// REPL state
 pub struct Repl {
     editor: DefaultEditor,
     interpreter: Interpreter,
     history_path: Option<PathBuf>,
+    /// Lines of a multi-line input awaiting completion
+    pending: Vec<String>,
+    /// Source of the functions defined so far, replayed to seed the type checker
+    definitions: Vec<String>,
+    /// Files brought in with `:load`, with the source last loaded from each
+    loaded: Vec<(PathBuf, String)>,
+    /// Target of the most recent `:load`, for `:reload`
+    last_load: Option<PathBuf>,
 }
 
 impl Repl {
     /// Create a new REPL
     pub fn new() -> RlResult<Self> {
-        let editor = DefaultEditor::new()?;
+        // Consecutive repeats of an input are stored once
+        let config = Config::builder()
+            .max_history_size(HISTORY_SIZE)?
+            .history_ignore_dups(true)?
+            .build();
+        let editor = DefaultEditor::with_config(config)?;
         let interpreter = Interpreter::new();
 
         // Try to find history file in home directory
@@ -30,6 +85,10 @@ impl Repl {
             editor,
             interpreter,
             history_path,
+            pending: Vec::new(),
+            definitions: Vec::new(),
+            loaded: Vec::new(),
+            last_load: None,
         };
 
         // Load history if available
@@ -46,30 +105,34 @@ impl Repl {
         println!("Type :help for help, :quit to exit.\n");
 
         loop {
-            match self.editor.readline(PROMPT) {
+            let prompt = if self.pending.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+            match self.editor.readline(prompt) {
                 Ok(line) => {
-                    let line = line.trim();
-
-                    if line.is_empty() {
-                        continue;
-                    }
+                    let input = match self.feed_line(&line) {
+                        LineInput::Complete(input) => input,
+                        LineInput::Aborted => {
+                            println!("(input discarded)");
+                            continue;
+                        }
+                        LineInput::Empty | LineInput::Continue => continue,
+                    };
 
-                    // Add to history
-                    let _ = self.editor.add_history_entry(line);
+                    self.record_history(&input);
 
                     // Handle commands
-                    if line.starts_with(':') {
-                        if self.handle_command(line) {
+                    if input.starts_with(':') {
+                        if self.handle_command(&input) {
                             break;
                         }
                         continue;
                     }
 
                     // Try to parse and evaluate
-                    self.eval_input(line);
+                    self.eval_input(&input);
                 }
                 Err(ReadlineError::Interrupted) => {
                     println!("^C");
+                    self.pending.clear();
                     continue;
                 }
                 Err(ReadlineError::Eof) => {
@@ -83,17 +146,57 @@ impl Repl {
             }
         }
 
-        // Save history
-        if let Some(ref path) = self.history_path {
-            let _ = self.editor.save_history(path);
+        Ok(())
+    }
+
+    /// Add a complete input to the history, appending it to the history
+    /// file right away so it survives the session ending abruptly
+    fn record_history(&mut self, input: &str) {
+        if let Ok(true) = self.editor.add_history_entry(input)
+            && let Some(ref path) = self.history_path
+        {
+            let _ = self.editor.append_history(path);
         }
+    }
 
-        Ok(())
+    /// Feed one line of input, accumulating it with any pending lines.
+    ///
+    /// Returns `Complete` with the combined source once it forms a whole
+    /// command, item or expression; an empty line while input is pending
+    /// aborts it.
+    pub fn feed_line(&mut self, line: &str) -> LineInput {
+        let line = line.trim_end();
+
+        if line.trim().is_empty() {
+            if self.pending.is_empty() {
+                return LineInput::Empty;
+            }
+            self.pending.clear();
+            return LineInput::Aborted;
+        }
+
+        // Commands are always single-line
+        if self.pending.is_empty() && line.trim_start().starts_with(':') {
+            return LineInput::Complete(line.trim().to_string());
+        }
+
+        self.pending.push(line.to_string());
+        let source = self.pending.join("\n");
+        if is_incomplete(&source) {
+            return LineInput::Continue;
+        }
+
+        self.pending.clear();
+        LineInput::Complete(source.trim().to_string())
     }
 
     /// Handle REPL commands (starting with :)
     fn handle_command(&mut self, cmd: &str) -> bool {
-        match cmd {
+        let (name, arg) = match cmd.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (cmd, ""),
+        };
+        match name {
             ":quit" | ":q" | ":exit" => {
                 println!("Goodbye!");
                 true
@@ -106,9 +209,54 @@ impl Repl {
                 print!("\x1B[2J\x1B[1;1H");
                 false
             }
+            ":type" | ":t" => {
+                if arg.is_empty() {
+                    println!("Usage: :type <expr>");
+                } else {
+                    match self.type_of(arg) {
+                        Ok(ty) => println!("{arg} : {ty}"),
+                        Err(err) => eprintln!("{err}"),
+                    }
+                }
+                false
+            }
+            ":ast" | ":a" => {
+                if arg.is_empty() {
+                    println!("Usage: :ast <expr>");
+                } else {
+                    match ast_of(arg) {
+                        Ok(sexpr) => println!("{sexpr}"),
+                        Err(err) => eprintln!("{err}"),
+                    }
+                }
+                false
+            }
+            ":time" => {
+                if arg.is_empty() {
+                    println!("Usage: :time <expr>");
+                } else if let Some(stats) = self.eval_input(arg) {
+                    println!("time: {:.3?}, calls: {}", stats.elapsed, stats.calls);
+                }
+                false
+            }
+            ":load" | ":l" => {
+                if arg.is_empty() {
+                    println!("Usage: :load <path>");
+                } else {
+                    self.load_and_report(Path::new(arg));
+                }
+                false
+            }
+            ":reload" | ":r" => {
+                match self.last_load.clone() {
+                    Some(path) => self.load_and_report(&path),
+                    None => println!("Nothing to reload; use :load <path> first."),
+                }
+                false
+            }
             _ => {
-                println!("Unknown command: {cmd}");
-                println!("Type :help for help.");
+                println!("Unknown command: {name}");
+                println!("Available commands: {}", command_names().join(", "));
                 false
             }
         }
@@ -117,30 +265,33 @@ impl Repl {
     /// Print help message
     fn print_help(&self) {
         println!("BMB REPL Commands:");
-        println!("  :help, :h, :?   Show this help");
-        println!("  :quit, :q       Exit the REPL");
-        println!("  :clear          Clear the screen");
+        for (usage, help) in COMMANDS {
+            println!("  {usage:<16}{help}");
+        }
         println!();
         println!("You can enter:");
         println!("  - Expressions: 1 + 2, if true then 1 else 2");
         println!("  - Function definitions: fn add(a: i32, b: i32) -> i32 = a + b;");
         println!("  - Function calls: add(1, 2)");
         println!();
+        println!("Unfinished input continues on the next line (prompt: ...).");
+        println!("Press Enter on an empty continuation line to discard it.");
+        println!();
         println!("Built-in functions:");
-        println!("  println(x)      Print value with newline");
-        println!("  print(x)        Print value without newline");
+        println!("  println(x, ...) Print values, space-separated, with newline");
+        println!("  print(x, ...)   Print values without newline");
         println!("  assert(cond)    Assert condition is true");
         println!("  abs(n)          Absolute value");
         println!("  min(a, b)       Minimum of two values");
         println!("  max(a, b)       Maximum of two values");
     }
 
-    /// Evaluate user input (v0.45: improved type inference)
-    fn eval_input(&mut self, input: &str) {
+    /// Evaluate user input (v0.45: improved type inference), returning what
+    /// running it cost if it got as far as running
+    fn eval_input(&mut self, input: &str) -> Option<RunStats> {
         // If it's a function definition, use directly
         if input.starts_with("fn ") || input.starts_with("pub fn ") {
-            self.eval_source(input);
-            return;
+            return self.eval_source(input);
         }
 
         // v0.45: Try multiple return types to support more expressions
@@ -169,16 +320,22 @@ impl Repl {
                 }
             };
 
-            // Type check (without function registration for now)
-            let mut checker = crate::types::TypeChecker::new();
-            if checker.check_program(&program).is_err() {
+            // Type check against the functions and files loaded so far
+            if self.check_in_session(&source).is_err() {
                 // Type check failed, try next type
                 continue;
             }
 
-            // Type check passed, now run it
+            // Type check passed, now run it (by name: a loaded file may have a `main`)
             self.interpreter.load(&program);
-            match self.interpreter.run(&program) {
+            let calls = self.interpreter.call_count();
+            let start = Instant::now();
+            let result = self.interpreter.call_function_with_args("__repl__", vec![]);
+            let stats = RunStats {
+                elapsed: start.elapsed(),
+                calls: (self.interpreter.call_count() - calls).saturating_sub(1),
+            };
+            match result {
                 Ok(value) => {
                     // Don't print Unit values (like from println)
                     if !matches!(value, crate::interp::Value::Unit) {
@@ -189,7 +346,7 @@ impl Repl {
                     eprintln!("Runtime error: {}", err.message);
                 }
             }
-            return;
+            return Some(stats);
         }
 
         // If no type worked, show the last error or a generic message
@@ -198,27 +355,108 @@ impl Repl {
         } else {
             // Try to get a better error message with i64
             let source = format!("fn __repl__() -> i64 = {input};");
-            if let Ok(tokens) = tokenize(&source)
-                && let Ok(program) = parse("<repl>", &source, tokens)
-            {
-                let mut checker = crate::types::TypeChecker::new();
-                if let Err(err) = checker.check_program(&program) {
-                    eprintln!("Type error: {}", err.message());
-                    return;
-                }
+            if let Err(err) = self.check_in_session(&source) {
+                eprintln!("{err}");
+                return None;
             }
             eprintln!("Could not evaluate expression");
         }
+        None
+    }
+
+    /// Infer the type of an expression against the functions defined so far
+    fn type_of(&self, input: &str) -> Result<String, String> {
+        let mut checker = self.seeded_checker()?;
+
+        let source = format!("fn __repl__() -> () = {input};");
+        let tokens = tokenize(&source).map_err(|e| format!("Lexer error: {}", e.message()))?;
+        let program = parse("<repl>", &source, tokens).map_err(|e| format!("Parse error: {}", e.message()))?;
+        let Some(Item::FnDef(wrapper)) = program.items.last() else {
+            return Err("Parse error: expected an expression".to_string());
+        };
+
+        let ty = checker
+            .infer_standalone(&wrapper.body)
+            .map_err(|e| format!("Type error: {}", e.message()))?;
+        Ok(format_type(&ty))
+    }
+
+    /// A type checker that has registered every function defined so far
+    fn seeded_checker(&self) -> Result<TypeChecker, String> {
+        self.check_in_session("")
+    }
+
+    /// Type-check `source` together with the loaded files and definitions
+    fn check_in_session(&self, source: &str) -> Result<TypeChecker, String> {
+        let source = self
+            .loaded
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .chain(self.definitions.iter().map(String::as_str))
+            .chain([source])
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tokens = tokenize(&source).map_err(|e| format!("Lexer error: {}", e.message()))?;
+        let program = parse("<repl>", &source, tokens).map_err(|e| format!("Parse error: {}", e.message()))?;
+        let mut checker = TypeChecker::new();
+        checker
+            .check_program(&program)
+            .map_err(|e| format!("Type error: {}", e.message()))?;
+        Ok(checker)
+    }
+
+    /// `:load` a file and print what happened
+    fn load_and_report(&mut self, path: &Path) {
+        match self.load_file(path) {
+            Ok(count) => println!("Loaded {count} item(s) from {}", path.display()),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    /// Load every item of a file into the session, replacing what an earlier
+    /// load of the same file defined. On error the session is left unchanged.
+    fn load_file(&mut self, path: &Path) -> Result<usize, String> {
+        let display = path.display();
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {display}: {e}"))?;
+        let filename = path.to_string_lossy();
+        let tokens = tokenize(&source).map_err(|e| format!("{display}: Lexer error: {}", e.message()))?;
+        let program = parse(&filename, &source, tokens).map_err(|e| format!("{display}: Parse error: {}", e.message()))?;
+        TypeChecker::new()
+            .check_program(&program)
+            .map_err(|e| format!("{display}: Type error: {}", e.message()))?;
+
+        // The file must also fit with the rest of the session
+        let previous = self.loaded.iter().position(|(p, _)| p == path);
+        let saved = match previous {
+            Some(i) => Some(std::mem::replace(&mut self.loaded[i].1, source)),
+            None => {
+                self.loaded.push((path.to_path_buf(), source));
+                None
+            }
+        };
+        if let Err(err) = self.seeded_checker() {
+            match (previous, saved) {
+                (Some(i), Some(old)) => self.loaded[i].1 = old,
+                _ => {
+                    self.loaded.pop();
+                }
+            }
+            return Err(format!("{display}: {err}"));
+        }
+
+        self.interpreter.load(&program);
+        self.last_load = Some(path.to_path_buf());
+        Ok(program.items.len())
     }
 
     /// Evaluate a complete source string (for function definitions)
-    fn eval_source(&mut self, source: &str) {
+    fn eval_source(&mut self, source: &str) -> Option<RunStats> {
         // Tokenize
         let tokens = match tokenize(source) {
             Ok(tokens) => tokens,
             Err(err) => {
                 eprintln!("Lexer error: {}", err.message());
-                return;
+                return None;
             }
         };
 
@@ -228,8 +466,21 @@ impl Repl {
                 // Load any function definitions
                 self.interpreter.load(&program);
 
+                // Remember definitions that type-check alongside the earlier ones
+                self.definitions.push(source.to_string());
+                if self.seeded_checker().is_err() {
+                    self.definitions.pop();
+                }
+
                 // Run the program (which will call __repl__ or main)
-                match self.interpreter.run(&program) {
+                let calls = self.interpreter.call_count();
+                let start = Instant::now();
+                let result = self.interpreter.run(&program);
+                let stats = RunStats {
+                    elapsed: start.elapsed(),
+                    calls: self.interpreter.call_count() - calls,
+                };
+                match result {
                     Ok(value) => {
                         // Don't print Unit values (like from println)
                         if !matches!(value, crate::interp::Value::Unit) {
@@ -240,9 +491,11 @@ impl Repl {
                         eprintln!("Runtime error: {}", err.message);
                     }
                 }
+                Some(stats)
             }
             Err(err) => {
                 eprintln!("Parse error: {}", err.message());
+                None
             }
         }
     }
@@ -254,6 +507,94 @@ impl Default for Repl {
     }
 }
 
+/// The S-expression form of an expression, as `bmb parse` would show it
+fn ast_of(input: &str) -> Result<String, String> {
+    let source = format!("fn __repl__() -> () = {input};");
+    let tokens = tokenize(&source).map_err(|e| format!("Lexer error: {}", e.message()))?;
+    let program = parse("<repl>", &source, tokens).map_err(|e| format!("Parse error: {}", e.message()))?;
+    match program.items.last() {
+        Some(Item::FnDef(wrapper)) => Ok(format_expr(&wrapper.body.node)),
+        _ => Err("Parse error: expected an expression".to_string()),
+    }
+}
+
+/// The primary name of each meta-command
+fn command_names() -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .map(|(usage, _)| usage.split([',', ' ']).next().unwrap_or(usage))
+        .collect()
+}
+
+/// Check whether REPL input needs more lines: unbalanced delimiters,
+/// a trailing `=`, or a parse error at end of input.
+fn is_incomplete(source: &str) -> bool {
+    let source = source.trim_end();
+    if delimiter_depth(source) > 0 || source.ends_with('=') {
+        return true;
+    }
+
+    // Items parse on their own; expressions are wrapped like `eval_input` does
+    let is_item = ["fn ", "pub fn ", "struct ", "enum ", "type ", "trait ", "impl", "@"]
+        .iter()
+        .any(|kw| source.starts_with(kw));
+    let (wrapped, input_end) = if is_item {
+        (source.to_string(), source.len())
+    } else {
+        let prefix = "fn __repl__() -> i64 = ";
+        (format!("{prefix}{source};"), prefix.len() + source.len())
+    };
+
+    let Ok(tokens) = tokenize(&wrapped) else {
+        return false;
+    };
+    match parse("<repl>", &wrapped, tokens) {
+        Ok(_) => false,
+        // An error at or past the end of the user's text means it stopped early
+        Err(err) => err.span().is_some_and(|span| span.start >= input_end),
+    }
+}
+
+/// Net count of open `(`, `[`, `{` outside string/char literals and comments.
+/// A `'` starts a char literal only when its closing `'` follows; otherwise
+/// it begins a loop label.
+fn delimiter_depth(source: &str) -> i64 {
+    let mut depth = 0i64;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '"' => {
+                while let Some(inner) = chars.next() {
+                    if inner == '\\' {
+                        chars.next();
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            }
+            '\'' => {
+                // `'x'` or `'\x'`; otherwise a loop label such as `'outer`
+                let len = if chars.peek() == Some(&'\\') { 3 } else { 2 };
+                if chars.clone().nth(len - 1) == Some('\'') {
+                    chars.nth(len - 1);
+                }
+            }
+            '/' | '-' if chars.peek() == Some(&c) => {
+                // Line comment: skip to end of line
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    depth
+}
+
 /// Get home directory
 fn dirs_home() -> Option<PathBuf> {
     #[cfg(windows)]
@@ -265,3 +606,217 @@ fn dirs_home() -> Option<PathBuf> {
         std::env::var("HOME").ok().map(PathBuf::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interp::Value;
+
+    fn feed_all(repl: &mut Repl, lines: &[&str]) -> Vec<LineInput> {
+        lines.iter().map(|line| repl.feed_line(line)).collect()
+    }
+
+    #[test]
+    fn test_multiline_function_completes_once() {
+        let mut repl = Repl::new().unwrap();
+        let results = feed_all(
+            &mut repl,
+            &["fn add(a: i64, b: i64) -> i64 = {", "  a + b", "};"],
+        );
+        assert_eq!(results[0], LineInput::Continue);
+        assert_eq!(results[1], LineInput::Continue);
+        assert_eq!(
+            results[2],
+            LineInput::Complete("fn add(a: i64, b: i64) -> i64 = {\n  a + b\n};".to_string())
+        );
+
+        let LineInput::Complete(source) = &results[2] else { unreachable!() };
+        repl.eval_input(source);
+        assert_eq!(
+            repl.interpreter
+                .call_function_with_args("add", vec![Value::Int(2), Value::Int(3)])
+                .unwrap(),
+            Value::Int(5)
+        );
+    }
+
+    #[test]
+    fn test_labelled_loop_spans_lines() {
+        let mut repl = Repl::new().unwrap();
+        let lines = [
+            "fn first_even(n: i64) -> i64 = {",
+            "  let mut i: i64 = 0;",
+            "  'skip: while i < 1 { { i = i + 1 } };",
+            "  'search: loop { {",
+            "    if i % 2 == 0 && i > n { break 'search } else { () };",
+            "    i = i + 1",
+            "  } };",
+            "  i",
+            "};",
+        ];
+        let results = feed_all(&mut repl, &lines);
+        // Three label quotes: pairing them as char literals would leave the
+        // last one open and hide the closing braces
+        assert!(results[..8].iter().all(|r| *r == LineInput::Continue), "{results:?}");
+        assert_eq!(results[8], LineInput::Complete(lines.join("\n")));
+
+        let LineInput::Complete(source) = &results[8] else { unreachable!() };
+        repl.eval_input(source);
+        assert_eq!(
+            repl.interpreter
+                .call_function_with_args("first_even", vec![Value::Int(3)])
+                .unwrap(),
+            Value::Int(4)
+        );
+        // Char literals still hide their brackets
+        assert_eq!(delimiter_depth("'(' == '\\'' && '}'"), 0);
+    }
+
+    #[test]
+    fn test_trailing_equals_continues() {
+        let mut repl = Repl::new().unwrap();
+        let results = feed_all(&mut repl, &["fn double(x: i64) -> i64 =", "  x * 2;"]);
+        assert_eq!(
+            results,
+            vec![
+                LineInput::Continue,
+                LineInput::Complete("fn double(x: i64) -> i64 =\n  x * 2;".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unfinished_expression_continues() {
+        let mut repl = Repl::new().unwrap();
+        let results = feed_all(&mut repl, &["1 +", "2"]);
+        assert_eq!(
+            results,
+            vec![LineInput::Continue, LineInput::Complete("1 +\n2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_empty_continuation_aborts() {
+        let mut repl = Repl::new().unwrap();
+        let results = feed_all(&mut repl, &["max(1,", "", "3"]);
+        assert_eq!(
+            results,
+            vec![
+                LineInput::Continue,
+                LineInput::Aborted,
+                LineInput::Complete("3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_command_infers_without_running() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval_input("fn half(x: i64) -> f64 = (x as f64) / 2.0;");
+
+        assert_eq!(repl.type_of("1 + 2").unwrap(), "i64");
+        assert_eq!(repl.type_of("half(3)").unwrap(), "f64");
+        assert_eq!(repl.type_of("[half(1), 2.5]").unwrap(), "[f64; 2]");
+        // Nothing is evaluated: this would fail at runtime
+        assert_eq!(repl.type_of("1 / 0").unwrap(), "i64");
+
+        let err = repl.type_of("half(true)").unwrap_err();
+        assert!(err.starts_with("Type error:"), "{err}");
+        assert!(repl.type_of("1 +").unwrap_err().starts_with("Parse error:"));
+        // The session goes on after an error
+        assert!(!repl.handle_command(":type half(true)"));
+        assert_eq!(repl.type_of("half(4) > 1.0").unwrap(), "bool");
+    }
+
+    #[test]
+    fn test_ast_command_prints_sexpr() {
+        assert_eq!(ast_of("1 + 2 * x").unwrap(), "(+ 1 (* 2 x))");
+        assert!(ast_of("1 +").unwrap_err().starts_with("Parse error:"));
+        assert!(!Repl::new().unwrap().handle_command(":ast (1"));
+    }
+
+    #[test]
+    fn test_time_counts_function_calls() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval_input("fn fib(n: i64) -> i64 = if n < 2 { n } else { fib(n - 1) + fib(n - 2) };");
+        repl.eval_input("fn count(n: i64) -> i64 = if n == 0 { 0 } else { count(n - 1) };");
+
+        // fib(5) makes 15 calls; the wrapper around the input is not counted
+        assert_eq!(repl.eval_input("fib(5)").unwrap().calls, 15);
+        assert_eq!(repl.eval_input("1 + 2").unwrap().calls, 0);
+        // Self tail calls run in one frame but still count
+        assert_eq!(repl.eval_input("count(10)").unwrap().calls, 11);
+        assert!(repl.eval_input("undefined_fn(1)").is_none());
+        assert!(!repl.handle_command(":time fib(3)"));
+    }
+
+    #[test]
+    fn test_unknown_command_lists_commands() {
+        assert_eq!(
+            command_names(),
+            [":help", ":quit", ":clear", ":type", ":ast", ":time", ":load", ":reload"]
+        );
+        assert!(!Repl::new().unwrap().handle_command(":frobnicate now"));
+    }
+
+    #[test]
+    fn test_load_and_reload_replace_definitions() {
+        let path = std::env::temp_dir().join(format!("bmb_repl_load_{}.bmb", std::process::id()));
+        std::fs::write(&path, "struct P { x: i64 }\nfn px(p: P) -> i64 = p.x;\nfn main() -> i64 = 0;\n").unwrap();
+
+        let mut repl = Repl::new().unwrap();
+        assert_eq!(repl.load_file(&path).unwrap(), 3);
+        assert_eq!(repl.type_of("px(new P { x: 4 })").unwrap(), "i64");
+
+        // Reloading the changed file replaces its definitions
+        std::fs::write(&path, "fn px(x: i64) -> i64 = x * 10;\n").unwrap();
+        assert!(!repl.handle_command(":reload"));
+        assert_eq!(repl.loaded.len(), 1);
+        assert_eq!(repl.type_of("px(4)").unwrap(), "i64");
+        assert_eq!(
+            repl.interpreter.call_function_with_args("px", vec![Value::Int(4)]).unwrap(),
+            Value::Int(40)
+        );
+
+        // A broken file is reported and the session keeps the last good load
+        std::fs::write(&path, "fn px(x: i64) -> i64 = true;\n").unwrap();
+        let err = repl.load_file(&path).unwrap_err();
+        assert!(err.contains("Type error"), "{err}");
+        assert_eq!(repl.type_of("px(4)").unwrap(), "i64");
+        assert!(repl.load_file(Path::new("/nonexistent/file.bmb")).unwrap_err().starts_with("Cannot read"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_history_persists_without_consecutive_duplicates() {
+        let path = std::env::temp_dir().join(format!("bmb_repl_history_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut repl = Repl::new().unwrap();
+        repl.editor.clear_history().unwrap();
+        repl.history_path = Some(path.clone());
+        for input in ["1 + 2", "1 + 2", ":type 1", "1 + 2"] {
+            repl.record_history(input);
+        }
+
+        // A new session starts with the saved entries, newest last
+        let mut editor = DefaultEditor::new().unwrap();
+        editor.load_history(&path).unwrap();
+        let entries: Vec<&String> = editor.history().iter().collect();
+        assert_eq!(entries, ["1 + 2", ":type 1", "1 + 2"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_single_line_input_and_commands() {
+        let mut repl = Repl::new().unwrap();
+        assert_eq!(repl.feed_line("1 + 2"), LineInput::Complete("1 + 2".to_string()));
+        assert_eq!(repl.feed_line(":help"), LineInput::Complete(":help".to_string()));
+        assert_eq!(repl.feed_line("   "), LineInput::Empty);
+        // Closing delimiters inside strings and comments don't count
+        let line = r#"println_str("{") -- {"#;
+        assert_eq!(repl.feed_line(line), LineInput::Complete(line.to_string()));
+    }
+}
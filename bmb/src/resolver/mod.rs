@@ -3,11 +3,12 @@
 //! Handles multi-file compilation by resolving `use` statements and
 //! loading/parsing modules from the file system.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use crate::ast::{Item, Program, Span, UseStmt, Visibility};
-use crate::error::{CompileError, Result};
+use crate::error::{CompileError, CompileWarning, Result};
+use crate::project::{Project, ProjectError};
 
 // ============================================================================
 // v0.68: Levenshtein Distance for Module/Item Suggestions
@@ -90,15 +91,74 @@ pub enum ExportedItem {
     Enum(String),
 }
 
+impl ExportedItem {
+    /// Name of the item in its defining module
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Function(name) | Self::Struct(name) | Self::Enum(name) => name,
+        }
+    }
+}
+
 /// Module resolver for multi-file compilation
 #[derive(Debug)]
 pub struct Resolver {
     /// Base directory for module resolution
     base_dir: PathBuf,
+    /// Further directories searched for top-level modules, in order
+    include_paths: Vec<PathBuf>,
+    /// Packages by the name they are imported as, mapped to their source root
+    packages: HashMap<String, PathBuf>,
     /// Loaded modules by name
     modules: HashMap<String, Module>,
+    /// Name each module file was first loaded under, by canonical path
+    names_by_path: HashMap<PathBuf, String>,
     /// Module load order (for dependency tracking)
     load_order: Vec<String>,
+    /// Modules currently being loaded, outermost first (for cycle detection)
+    loading: Vec<Loading>,
+}
+
+/// A module whose own `use` statements are being resolved
+#[derive(Debug)]
+struct Loading {
+    name: String,
+    /// File as found, for messages
+    file: PathBuf,
+    canonical: PathBuf,
+    source: String,
+    /// Span in the root file of the `use` that requested the module
+    requested_at: Option<Span>,
+    /// The module's `use` statement being resolved
+    current_use: Option<Span>,
+}
+
+impl Loading {
+    /// `file:line:col: use ...` for the `use` being resolved
+    fn trace_line(&self) -> String {
+        let Some(span) = self.current_use else {
+            return format!("{}", self.file.display());
+        };
+        let (line, col) = crate::error::line_col(&self.source, span.start);
+        let text = self.source.get(span.start..span.end).unwrap_or("").trim();
+        format!("{}:{}:{}: {}", self.file.display(), line, col, text)
+    }
+}
+
+/// What a single `use` path segment names at one directory level
+enum SegmentTarget {
+    /// A module file, plus the directory holding its submodules
+    Module { file: PathBuf, child_dir: PathBuf },
+    /// A plain directory that only groups submodules
+    Directory(PathBuf),
+}
+
+/// Build a resolution error, attaching the span only when it belongs to the root file
+fn resolve_error(message: String, span: Option<Span>) -> CompileError {
+    match span {
+        Some(span) => CompileError::resolve_error_at(message, span),
+        None => CompileError::resolve_error(message),
+    }
 }
 
 impl Resolver {
@@ -106,69 +166,94 @@ impl Resolver {
     pub fn new<P: AsRef<Path>>(base_dir: P) -> Self {
         Self {
             base_dir: base_dir.as_ref().to_path_buf(),
+            include_paths: Vec::new(),
+            packages: HashMap::new(),
             modules: HashMap::new(),
+            names_by_path: HashMap::new(),
             load_order: Vec::new(),
+            loading: Vec::new(),
         }
     }
 
+    /// Create a resolver for `file`, resolving against its directory and,
+    /// when it belongs to a project, the project's source root and
+    /// declared dependencies
+    pub fn for_file(file: &Path) -> std::result::Result<Self, ProjectError> {
+        let base_dir = file.parent().unwrap_or(Path::new("."));
+        let mut resolver = Self::new(base_dir);
+        if let Some(project) = Project::discover(base_dir)? {
+            resolver.add_project(&project)?;
+        }
+        Ok(resolver)
+    }
+
     /// Get the base directory
     pub fn base_dir(&self) -> &Path {
         &self.base_dir
     }
 
-    /// Load a module by name, parsing the corresponding .bmb file
-    pub fn load_module(&mut self, module_name: &str) -> Result<&Module> {
-        // Check if already loaded
-        if self.modules.contains_key(module_name) {
-            return Ok(self.modules.get(module_name).unwrap());
+    /// Search `dir` for top-level modules not found in the base directory
+    pub fn add_include_path<P: AsRef<Path>>(&mut self, dir: P) {
+        let dir = dir.as_ref().to_path_buf();
+        if dir != self.base_dir && !self.include_paths.contains(&dir) {
+            self.include_paths.push(dir);
         }
+    }
 
-        // Resolve file path
-        let file_path = self.resolve_module_path(module_name)?;
-
-        // Read the file
-        let source = std::fs::read_to_string(&file_path).map_err(|e| {
-            CompileError::io_error(format!(
-                "Failed to read module '{}' at {:?}: {}",
-                module_name, file_path, e
-            ))
-        })?;
-
-        // Tokenize
-        let tokens = crate::lexer::tokenize(&source)?;
-
-        // Parse
-        let program = crate::parser::parse(module_name, &source, tokens)?;
-
-        // Extract exports (pub items)
-        let exports = Self::extract_exports(&program);
-
-        // Create and store the module
-        let module = Module {
-            name: module_name.to_string(),
-            path: file_path,
-            program,
-            exports,
-        };
+    /// Make `use name::...` resolve to the package whose sources are in
+    /// `source_root`, starting from its `lib.bmb`
+    pub fn add_package<P: AsRef<Path>>(&mut self, name: &str, source_root: P) {
+        self.packages.insert(name.to_string(), source_root.as_ref().to_path_buf());
+    }
 
-        self.modules.insert(module_name.to_string(), module);
-        self.load_order.push(module_name.to_string());
+    /// Include the source root of `project` and its path dependencies
+    pub fn add_project(&mut self, project: &Project) -> std::result::Result<(), ProjectError> {
+        self.add_include_path(project.source_root());
+        for (name, source_root) in project.dependencies()? {
+            self.add_package(&name, source_root);
+        }
+        Ok(())
+    }
 
+    /// Load a module by name, parsing the corresponding .bmb file
+    pub fn load_module(&mut self, module_name: &str) -> Result<&Module> {
+        let file_path = self.resolve_module_path(module_name, None)?;
+        self.load_module_file(module_name, &file_path, None)?;
         Ok(self.modules.get(module_name).unwrap())
     }
 
     /// v0.70: Load a module with span for error localization
     pub fn load_module_with_span(&mut self, module_name: &str, span: Span) -> Result<&Module> {
+        let file_path = self.resolve_module_path(module_name, Some(span))?;
+        self.load_module_file(module_name, &file_path, Some(span))?;
+        Ok(self.modules.get(module_name).unwrap())
+    }
+
+    /// Load and parse a module file under the given name, then resolve its own
+    /// `use` statements so dependencies load first and cycles are reported.
+    /// A file is loaded once however many names reach it.
+    fn load_module_file(&mut self, module_name: &str, file_path: &Path, span: Option<Span>) -> Result<()> {
         // Check if already loaded
         if self.modules.contains_key(module_name) {
-            return Ok(self.modules.get(module_name).unwrap());
+            return Ok(());
         }
 
-        // Resolve file path (with span for better error messages)
-        let file_path = self.resolve_module_path_with_span(module_name, span)?;
+        let canonical = file_path.canonicalize().unwrap_or_else(|_| file_path.to_path_buf());
+
+        // A module requested while it is still loading closes an import cycle
+        if let Some(pos) = self.loading.iter().position(|m| m.canonical == canonical) {
+            return Err(self.cycle_error(pos, module_name));
+        }
+
+        // The same file under another name shares the module already loaded
+        if let Some(first) = self.names_by_path.get(&canonical) {
+            let module = self.modules[first].clone();
+            self.modules.insert(module_name.to_string(), module);
+            return Ok(());
+        }
 
         // Read the file
-        let source = std::fs::read_to_string(&file_path).map_err(|e| {
+        let source = std::fs::read_to_string(file_path).map_err(|e| {
             CompileError::io_error(format!(
                 "Failed to read module '{}' at {:?}: {}",
                 module_name, file_path, e
@@ -181,79 +266,136 @@ impl Resolver {
         // Parse
         let program = crate::parser::parse(module_name, &source, tokens)?;
 
+        // Load the module's own dependencies (their spans refer to another file)
+        self.loading.push(Loading {
+            name: module_name.to_string(),
+            file: file_path.to_path_buf(),
+            canonical: canonical.clone(),
+            source,
+            requested_at: span,
+            current_use: None,
+        });
+        let mut module_imports = ResolvedImports::new();
+        let deps = program.items.iter().try_for_each(|item| match item {
+            Item::Use(use_stmt) => {
+                if let Some(loading) = self.loading.last_mut() {
+                    loading.current_use = Some(use_stmt.span);
+                }
+                self.resolve_use(use_stmt, &mut module_imports, false)
+            }
+            _ => Ok(()),
+        });
+        self.loading.pop();
+        deps?;
+
         // Extract exports (pub items)
         let exports = Self::extract_exports(&program);
 
         // Create and store the module
         let module = Module {
             name: module_name.to_string(),
-            path: file_path,
+            path: canonical.clone(),
             program,
             exports,
         };
 
         self.modules.insert(module_name.to_string(), module);
+        self.names_by_path.insert(canonical, module_name.to_string());
         self.load_order.push(module_name.to_string());
 
-        Ok(self.modules.get(module_name).unwrap())
+        Ok(())
     }
 
-    /// Resolve a module name to a file path
-    fn resolve_module_path(&self, module_name: &str) -> Result<PathBuf> {
-        // Try module_name.bmb in the base directory
-        let mut path = self.base_dir.join(format!("{}.bmb", module_name));
-        if path.exists() {
-            return Ok(path);
+    /// The error for `module_name` being requested again while the modules
+    /// from `loading[start]` on are loading: the cycle, then the `use`
+    /// statement behind each step of it. It points at the `use` in the root
+    /// file that led into the cycle.
+    fn cycle_error(&self, start: usize, module_name: &str) -> CompileError {
+        let cycle = &self.loading[start..];
+        let names: Vec<&str> = cycle
+            .iter()
+            .map(|m| m.name.as_str())
+            .chain([module_name])
+            .collect();
+        let mut message = format!("Circular import detected: {}", names.join(" -> "));
+        for step in cycle {
+            message.push_str(&format!("\n  {}", step.trace_line()));
         }
+        let span = self.loading.first().and_then(|m| m.requested_at);
+        resolve_error(message, span)
+    }
 
-        // Try module_name/mod.bmb
-        path = self.base_dir.join(module_name).join("mod.bmb");
-        if path.exists() {
-            return Ok(path);
+    /// Resolve a top-level module name to a file path
+    /// v0.70: Include span for error localization when available
+    fn resolve_module_path(&self, module_name: &str, span: Option<Span>) -> Result<PathBuf> {
+        if let Some(SegmentTarget::Module { file, .. }) = self.resolve_top_level(module_name) {
+            return Ok(file);
         }
 
         // v0.68: Suggest similar module names
-        let suggestion = self.suggest_module_name(module_name);
+        let suggestion = Self::suggest_module_name(&self.base_dir, module_name);
         let hint = format_suggestion_hint(suggestion.as_deref());
 
-        Err(CompileError::resolve_error(format!(
-            "Module '{}' not found in {:?}{}",
-            module_name, self.base_dir, hint
-        )))
+        Err(resolve_error(
+            format!("Module '{}' not found in {:?}{}", module_name, self.base_dir, hint),
+            span,
+        ))
+    }
+
+    /// Resolve the first segment of a path: in the base directory, as a
+    /// declared package, then in each include path
+    fn resolve_top_level(&self, segment: &str) -> Option<SegmentTarget> {
+        if let Some(target) = Self::resolve_segment(&self.base_dir, segment, true) {
+            return Some(target);
+        }
+        if let Some(src_dir) = self.packages.get(segment) {
+            let lib_file = src_dir.join("lib.bmb");
+            if lib_file.is_file() {
+                return Some(SegmentTarget::Module { file: lib_file, child_dir: src_dir.clone() });
+            }
+            return Some(SegmentTarget::Directory(src_dir.clone()));
+        }
+        self.include_paths
+            .iter()
+            .find_map(|dir| Self::resolve_segment(dir, segment, true))
     }
 
-    /// v0.70: Resolve module path with span for error localization
-    fn resolve_module_path_with_span(&self, module_name: &str, span: Span) -> Result<PathBuf> {
-        // Try module_name.bmb in the base directory
-        let mut path = self.base_dir.join(format!("{}.bmb", module_name));
-        if path.exists() {
-            return Ok(path);
+    /// Resolve one path segment inside `dir`: `seg.bmb`, `seg/mod.bmb`,
+    /// a package `seg-name/src/lib.bmb` (top level only), or a plain directory.
+    fn resolve_segment(dir: &Path, segment: &str, allow_package: bool) -> Option<SegmentTarget> {
+        let file = dir.join(format!("{}.bmb", segment));
+        if file.is_file() {
+            return Some(SegmentTarget::Module { file, child_dir: dir.join(segment) });
         }
 
-        // Try module_name/mod.bmb
-        path = self.base_dir.join(module_name).join("mod.bmb");
-        if path.exists() {
-            return Ok(path);
+        let sub_dir = dir.join(segment);
+        let mod_file = sub_dir.join("mod.bmb");
+        if mod_file.is_file() {
+            return Some(SegmentTarget::Module { file: mod_file, child_dir: sub_dir });
         }
 
-        // v0.68: Suggest similar module names
-        // v0.70: Include span for error localization
-        let suggestion = self.suggest_module_name(module_name);
-        let hint = format_suggestion_hint(suggestion.as_deref());
+        if allow_package {
+            // Convert underscore to hyphen for package names (bmb_option -> bmb-option)
+            let src_dir = dir.join(segment.replace('_', "-")).join("src");
+            let lib_file = src_dir.join("lib.bmb");
+            if lib_file.is_file() {
+                return Some(SegmentTarget::Module { file: lib_file, child_dir: src_dir });
+            }
+        }
 
-        Err(CompileError::resolve_error_at(
-            format!("Module '{}' not found in {:?}{}",
-                module_name, self.base_dir, hint),
-            span,
-        ))
+        if sub_dir.is_dir() {
+            return Some(SegmentTarget::Directory(sub_dir));
+        }
+
+        None
     }
 
-    /// v0.68: Find similar module names for suggestions
-    fn suggest_module_name(&self, module_name: &str) -> Option<String> {
-        // Collect available module names from the base directory
+    /// v0.68: Find similar module names for suggestions among the entries of `dir`
+    fn suggest_module_name(dir: &Path, module_name: &str) -> Option<String> {
+        // Collect available module names from the directory
         let mut available_modules = Vec::new();
 
-        if let Ok(entries) = std::fs::read_dir(&self.base_dir) {
+        if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
 
@@ -262,18 +404,16 @@ impl Resolver {
                     && let Some(ext) = path.extension()
                         && ext == "bmb"
                             && let Some(stem) = path.file_stem()
-                                && let Some(name) = stem.to_str() {
+                                && let Some(name) = stem.to_str()
+                                    && name != "mod" && name != "lib" {
                                     available_modules.push(name.to_string());
                                 }
 
-                // Check for directories with mod.bmb
-                if path.is_dir() {
-                    let mod_file = path.join("mod.bmb");
-                    if mod_file.exists()
-                        && let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                            available_modules.push(name.to_string());
-                        }
-                }
+                // Directories can hold submodules (mod.bmb, package src/, or nested files)
+                if path.is_dir()
+                    && let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                        available_modules.push(name.replace('-', "_"));
+                    }
             }
         }
 
@@ -313,66 +453,138 @@ impl Resolver {
         exports
     }
 
-    /// Resolve all use statements in a program, loading required modules
+    /// Resolve all use statements in a program, loading required modules.
+    /// Glob imports never hide a local definition of the same name; such
+    /// names are dropped and reported by [`ResolvedImports::shadowed`].
     pub fn resolve_uses(&mut self, program: &Program) -> Result<ResolvedImports> {
         let mut imports = ResolvedImports::new();
 
         for item in &program.items {
             if let Item::Use(use_stmt) = item {
-                self.resolve_use(use_stmt, &mut imports)?;
+                self.resolve_use(use_stmt, &mut imports, true)?;
             }
         }
 
+        let local_names: HashSet<&str> = program.items.iter().filter_map(|item| match item {
+            Item::FnDef(f) => Some(f.name.node.as_str()),
+            Item::StructDef(s) => Some(s.name.node.as_str()),
+            Item::EnumDef(e) => Some(e.name.node.as_str()),
+            Item::ExternFn(e) => Some(e.name.node.as_str()),
+            Item::TraitDef(t) => Some(t.name.node.as_str()),
+            Item::TypeAlias(t) => Some(t.name.node.as_str()),
+            Item::ConstDef(c) => Some(c.name.node.as_str()),
+            Item::Use(_) | Item::ImplBlock(_) => None,
+        }).collect();
+        imports.drop_shadowed_globs(&local_names);
+
         Ok(imports)
     }
 
-    /// Resolve a single use statement
-    fn resolve_use(&mut self, use_stmt: &UseStmt, imports: &mut ResolvedImports) -> Result<()> {
+    /// Resolve a single use statement by walking its segments through nested
+    /// module directories. `a::b::C` imports item `C` from module `a::b`
+    /// (under its alias for `a::b::C as D`); a path that ends at a module,
+    /// or `a::b::*`, imports all of its public items as a glob.
+    /// Spans are only attached for statements in the root file.
+    fn resolve_use(&mut self, use_stmt: &UseStmt, imports: &mut ResolvedImports, in_root: bool) -> Result<()> {
+        let span_if_root = |span: Span| if in_root { Some(span) } else { None };
+
         if use_stmt.path.is_empty() {
             // v0.70: Use statement span for empty path error
-            return Err(CompileError::resolve_error_at("Empty use path", use_stmt.span));
+            return Err(resolve_error("Empty use path".to_string(), span_if_root(use_stmt.span)));
         }
 
-        // The first segment is the module name
-        let module_name = &use_stmt.path[0].node;
-        let module_span = use_stmt.path[0].span;
-
-        // Load the module (v0.70: pass span for error localization)
-        self.load_module_with_span(module_name, module_span)?;
-        let module = self.modules.get(module_name).unwrap();
-
-        // If there's only one segment, import everything (not supported yet)
-        if use_stmt.path.len() == 1 {
-            // Import all public items from the module
-            // v0.74: Use statement span for glob imports
-            for (name, item) in &module.exports {
-                imports.add_import(name.clone(), module_name.clone(), item.clone(), use_stmt.span);
+        let full_path: Vec<&str> = use_stmt.path.iter().map(|s| s.node.as_str()).collect();
+        let mut dir = self.base_dir.clone();
+        // Innermost module reached so far: (name, file)
+        let mut current: Option<(String, PathBuf)> = None;
+
+        for (i, segment) in use_stmt.path.iter().enumerate() {
+            let is_last = i + 1 == use_stmt.path.len();
+
+            // The last segment names an item when the enclosing module exports it
+            // (`a::b::*` always names a module)
+            if is_last && !use_stmt.glob && let Some((module_name, file)) = &current {
+                let module_name = module_name.clone();
+                self.load_module_file(&module_name, file, span_if_root(use_stmt.path[i - 1].span))?;
+                let module = self.modules.get(&module_name).unwrap();
+                if let Some(item) = module.exports.get(&segment.node) {
+                    // v0.74: Use item span for specific imports; an alias is
+                    // what the importing file refers to, so it carries the span
+                    let (name, span) = match &use_stmt.alias {
+                        Some(alias) => (alias.node.clone(), alias.span),
+                        None => (segment.node.clone(), segment.span),
+                    };
+                    imports.add_import(name, module_name, item.clone(), span);
+                    return Ok(());
+                }
             }
-        } else {
-            // Import specific items (e.g., use lexer::Token)
-            // The last segment is the item name
-            let item_segment = use_stmt.path.last().unwrap();
-            let item_name = &item_segment.node;
-            let item_span = item_segment.span;
-
-            if let Some(item) = module.exports.get(item_name) {
-                // v0.74: Use item span for specific imports
-                imports.add_import(item_name.clone(), module_name.clone(), item.clone(), item_span);
+
+            let target = if i == 0 {
+                self.resolve_top_level(&segment.node)
             } else {
-                // v0.68: Suggest similar export names
-                // v0.70: Include span for error localization
-                let export_names: Vec<&str> = module.exports.keys().map(|s| s.as_str()).collect();
-                let suggestion = find_similar_name(item_name, &export_names);
-                let hint = format_suggestion_hint(suggestion);
-
-                return Err(CompileError::resolve_error_at(
-                    format!("Item '{}' not found in module '{}'{}",
-                        item_name, module_name, hint),
-                    item_span,
-                ));
+                Self::resolve_segment(&dir, &segment.node, false)
+            };
+            match target {
+                Some(SegmentTarget::Module { file, child_dir }) => {
+                    current = Some((full_path[..=i].join("::"), file));
+                    dir = child_dir;
+                }
+                Some(SegmentTarget::Directory(sub_dir)) => {
+                    current = None;
+                    dir = sub_dir;
+                }
+                None => {
+                    if is_last && let Some((module_name, _)) = current {
+                        // v0.68: Suggest similar export names
+                        let module = self.modules.get(&module_name).unwrap();
+                        let export_names: Vec<&str> = module.exports.keys().map(|s| s.as_str()).collect();
+                        let suggestion = find_similar_name(&segment.node, &export_names);
+                        let hint = format_suggestion_hint(suggestion);
+
+                        return Err(resolve_error(
+                            format!("Item '{}' not found in module '{}'{}",
+                                segment.node, module_name, hint),
+                            span_if_root(segment.span),
+                        ));
+                    }
+
+                    // Name the failing segment and suggest siblings at that level
+                    let suggestion = Self::suggest_module_name(&dir, &segment.node);
+                    let hint = format_suggestion_hint(suggestion.as_deref());
+                    let message = if use_stmt.path.len() == 1 {
+                        format!("Module '{}' not found in {:?}{}", segment.node, dir, hint)
+                    } else {
+                        format!("Module '{}' not found in {:?} (segment {} of `{}`){}",
+                            segment.node, dir, i + 1, full_path.join("::"), hint)
+                    };
+                    return Err(resolve_error(message, span_if_root(segment.span)));
+                }
             }
         }
 
+        // The whole path names a module: import everything it exports
+        let Some((module_name, file)) = current else {
+            let last = use_stmt.path.last().unwrap();
+            return Err(resolve_error(
+                format!("'{}' is a directory without a module file (add mod.bmb or import a submodule)",
+                    full_path.join("::")),
+                span_if_root(last.span),
+            ));
+        };
+        if let Some(alias) = &use_stmt.alias {
+            return Err(resolve_error(
+                format!("Cannot import module '{}' as '{}'; only items can be aliased",
+                    module_name, alias.node),
+                span_if_root(alias.span),
+            ));
+        }
+        self.load_module_file(&module_name, &file, span_if_root(use_stmt.path.last().unwrap().span))?;
+        let module = self.modules.get(&module_name).unwrap();
+        // v0.74: Use statement span for glob imports
+        for (name, item) in &module.exports {
+            imports.add_glob_import(name.clone(), module_name.clone(), item.clone(), use_stmt.span);
+        }
+
         Ok(())
     }
 
@@ -390,7 +602,25 @@ impl Resolver {
 
     /// Get the number of loaded modules
     pub fn module_count(&self) -> usize {
-        self.modules.len()
+        self.load_order.len()
+    }
+
+    /// `program` with the items of every loaded module prepended, for the
+    /// interpreter and code generators, which see a single namespace.
+    /// Module `main` functions and `use` statements are left out.
+    pub fn link(&self, program: &Program) -> Program {
+        let mut items: Vec<Item> = self
+            .modules_in_order()
+            .flat_map(|module| module.program.items.iter())
+            .filter(|item| match item {
+                Item::Use(_) => false,
+                Item::FnDef(fn_def) => fn_def.name.node != "main",
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        items.extend(program.items.iter().cloned());
+        Program { header: program.header.clone(), items }
     }
 }
 
@@ -405,13 +635,17 @@ pub struct ImportInfo {
     pub span: Span,
     /// Whether this import has been used
     pub used: bool,
+    /// Imported by a glob (`use m::*` or `use m`) rather than by name
+    pub glob: bool,
 }
 
 /// Collection of resolved imports from use statements
 #[derive(Debug, Default)]
 pub struct ResolvedImports {
-    /// Imported items: name -> ImportInfo
+    /// Imported items: name -> ImportInfo (keyed by alias for `use m::f as g`)
     imports: HashMap<String, ImportInfo>,
+    /// Glob-imported names dropped in favor of a local definition
+    shadowed: Vec<(String, ImportInfo)>,
 }
 
 impl ResolvedImports {
@@ -427,9 +661,44 @@ impl ResolvedImports {
             item,
             span,
             used: false,
+            glob: false,
         });
     }
 
+    /// Add an item imported by a glob; a name imported explicitly keeps its import
+    pub fn add_glob_import(&mut self, name: String, module: String, item: ExportedItem, span: Span) {
+        if self.imports.get(&name).is_some_and(|info| !info.glob) {
+            return;
+        }
+        self.imports.insert(name, ImportInfo {
+            module,
+            item,
+            span,
+            used: false,
+            glob: true,
+        });
+    }
+
+    /// Drop glob imports of names that are defined locally
+    fn drop_shadowed_globs(&mut self, local_names: &HashSet<&str>) {
+        let mut names: Vec<String> = self
+            .imports
+            .iter()
+            .filter(|(name, info)| info.glob && local_names.contains(name.as_str()))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        for name in names {
+            let info = self.imports.remove(&name).unwrap();
+            self.shadowed.push((name, info));
+        }
+    }
+
+    /// Glob-imported names that a local definition shadows: (name, import)
+    pub fn shadowed(&self) -> &[(String, ImportInfo)] {
+        &self.shadowed
+    }
+
     /// Check if a name is imported
     pub fn is_imported(&self, name: &str) -> bool {
         self.imports.contains_key(name)
@@ -463,13 +732,45 @@ impl ResolvedImports {
     }
 
     /// v0.74: Get all unused imports (for warning generation)
-    /// Returns: (name, span) pairs for unused imports
+    /// Returns: (name, span) pairs for unused imports. A glob counts as one
+    /// import, `module::*`, unused only when none of its items is used.
     pub fn get_unused(&self) -> Vec<(String, Span)> {
-        self.imports
+        let mut unused: Vec<(String, Span)> = self
+            .imports
             .iter()
-            .filter(|(name, info)| !info.used && !name.starts_with('_'))
+            .filter(|(name, info)| !info.glob && !info.used && !name.starts_with('_'))
             .map(|(name, info)| (name.clone(), info.span))
-            .collect()
+            .collect();
+
+        // Group glob items by their use statement
+        let mut globs: BTreeMap<(usize, usize), (&str, bool)> = BTreeMap::new();
+        for info in self.imports.values().filter(|info| info.glob) {
+            let entry = globs.entry((info.span.start, info.span.end)).or_insert((&info.module, false));
+            entry.1 |= info.used;
+        }
+        unused.extend(
+            globs
+                .into_iter()
+                .filter(|(_, (_, used))| !used)
+                .map(|((start, end), (module, _))| (format!("{}::*", module), Span::new(start, end))),
+        );
+
+        unused.sort_by_key(|(_, span)| span.start);
+        unused
+    }
+
+    /// Lint warnings for the imports: unused imports and glob imports
+    /// shadowed by a local definition
+    pub fn warnings(&self) -> Vec<CompileWarning> {
+        let mut warnings: Vec<_> = self
+            .get_unused()
+            .into_iter()
+            .map(|(name, span)| CompileWarning::unused_import(name, span))
+            .collect();
+        for (name, info) in self.shadowed() {
+            warnings.push(CompileWarning::shadowed_glob_import(name, &info.module, info.span));
+        }
+        warnings
     }
 }
 
@@ -546,4 +847,186 @@ mod tests {
         let unused = imports.get_unused();
         assert!(unused.is_empty());
     }
+
+    /// Create a fresh temporary project directory populated with `files`
+    fn temp_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bmb_resolver_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (rel, content) in files {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn parse_program(source: &str) -> Program {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        crate::parser::parse("main.bmb", source, tokens).unwrap()
+    }
+
+    #[test]
+    fn test_nested_module_path() {
+        let dir = temp_project("nested", &[
+            ("std/collections/hashmap.bmb", "pub fn hm_new() -> i64 = 0;"),
+        ]);
+        let program = parse_program("use std::collections::hashmap::hm_new;\nfn main() -> i64 = hm_new();");
+
+        let mut resolver = Resolver::new(&dir);
+        let imports = resolver.resolve_uses(&program).unwrap();
+        assert_eq!(imports.get_import_module("hm_new"), Some("std::collections::hashmap"));
+        assert!(resolver.get_module("std::collections::hashmap").is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_package_src_submodule() {
+        let dir = temp_project("package", &[
+            ("my-lib/src/lib.bmb", "pub fn root() -> i64 = 1;"),
+            ("my-lib/src/util.bmb", "pub fn helper() -> i64 = 2;"),
+        ]);
+        let program = parse_program("use my_lib::util::helper;\nuse my_lib::root;\nfn main() -> i64 = 0;");
+
+        let mut resolver = Resolver::new(&dir);
+        let imports = resolver.resolve_uses(&program).unwrap();
+        assert_eq!(imports.get_import_module("helper"), Some("my_lib::util"));
+        assert_eq!(imports.get_import_module("root"), Some("my_lib"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_segment_names_failing_level() {
+        let dir = temp_project("missing", &[
+            ("std/collections/hashmap.bmb", "pub fn hm_new() -> i64 = 0;"),
+        ]);
+        let source = "use std::colections::hashmap::hm_new;";
+        let program = parse_program(source);
+
+        let mut resolver = Resolver::new(&dir);
+        let err = resolver.resolve_uses(&program).unwrap_err();
+        assert!(err.message().contains("Module 'colections' not found"));
+        assert!(err.message().contains("segment 2 of `std::colections::hashmap::hm_new`"));
+        assert!(err.message().contains("did you mean `collections`?"));
+        let span = err.span().unwrap();
+        assert_eq!(&source[span.start..span.end], "colections");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_circular_import_detected() {
+        let dir = temp_project("cycle", &[
+            ("a.bmb", "use b::fb;\npub fn fa() -> i64 = 1;"),
+            ("b.bmb", "use a::fa;\npub fn fb() -> i64 = 2;"),
+        ]);
+        let source = "use a::fa;\nfn main() -> i64 = fa();";
+        let program = parse_program(source);
+
+        let mut resolver = Resolver::new(&dir);
+        let err = resolver.resolve_uses(&program).unwrap_err();
+        assert_eq!(err.code(), "E0403");
+        let lines: Vec<&str> = err.message().lines().collect();
+        assert_eq!(lines[0], "Circular import detected: a -> b -> a");
+        // Each step names the `use` that takes it
+        assert!(lines[1].ends_with("a.bmb:1:1: use b::fb;"), "{}", lines[1]);
+        assert!(lines[2].ends_with("b.bmb:1:1: use a::fa;"), "{}", lines[2]);
+        // The error points at the root file's way into the cycle
+        let span = err.span().unwrap();
+        assert_eq!(&source[span.start..span.end], "a");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diamond_import_loads_shared_module_once() {
+        let dir = temp_project("diamond", &[
+            ("b.bmb", "use d::base;\npub fn fb() -> i64 = base() + 1;"),
+            ("c.bmb", "use d::base;\npub fn fc() -> i64 = base() + 2;"),
+            ("d.bmb", "pub fn base() -> i64 = 10;"),
+        ]);
+        let program = parse_program("use b::fb;\nuse c::fc;\nfn main() -> i64 = fb() + fc();");
+
+        let mut resolver = Resolver::new(&dir);
+        resolver.resolve_uses(&program).unwrap();
+        let order: Vec<&str> = resolver.modules_in_order().map(|m| m.name.as_str()).collect();
+        assert_eq!(order, ["d", "b", "c"]);
+        let linked = resolver.link(&program);
+        let bases = linked
+            .items
+            .iter()
+            .filter(|item| matches!(item, Item::FnDef(f) if f.name.node == "base"))
+            .count();
+        assert_eq!(bases, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_glob_and_alias_imports() {
+        let dir = temp_project("glob_alias", &[
+            ("math.bmb", "pub fn gcd(a: i64, b: i64) -> i64 = a;\npub fn lcm(a: i64, b: i64) -> i64 = a;\nfn hidden() -> i64 = 0;"),
+            ("util.bmb", "pub fn helper() -> i64 = 1;"),
+        ]);
+        let source = "use math::*;\nuse math::gcd as divisor;\nuse util::*;\nfn lcm(a: i64, b: i64) -> i64 = b;";
+        let program = parse_program(source);
+
+        let mut resolver = Resolver::new(&dir);
+        let mut imports = resolver.resolve_uses(&program).unwrap();
+        assert!(imports.is_imported("gcd"));
+        assert!(!imports.is_imported("hidden"));
+        let alias = imports.all_imports().find(|(name, _)| *name == "divisor").unwrap().1;
+        assert_eq!(alias.item.name(), "gcd");
+        assert!(!alias.glob);
+
+        // The local `lcm` wins over the glob import
+        assert!(!imports.is_imported("lcm"));
+        let shadowed: Vec<_> = imports.shadowed().iter().map(|(name, info)| (name.as_str(), info.module.as_str())).collect();
+        assert_eq!(shadowed, [("lcm", "math")]);
+
+        // Using the alias leaves the math glob unused; each glob is reported once
+        imports.mark_used("divisor");
+        let unused: Vec<_> = imports.get_unused().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(unused, ["math::*", "util::*"]);
+        imports.mark_used("gcd");
+        let unused: Vec<_> = imports.get_unused().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(unused, ["util::*"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_alias_of_module_is_rejected() {
+        let dir = temp_project("module_alias", &[("math.bmb", "pub fn gcd(a: i64, b: i64) -> i64 = a;")]);
+        let source = "use math as m;";
+        let program = parse_program(source);
+
+        let mut resolver = Resolver::new(&dir);
+        let err = resolver.resolve_uses(&program).unwrap_err();
+        assert!(err.message().contains("only items can be aliased"));
+        let span = err.span().unwrap();
+        assert_eq!(&source[span.start..span.end], "m");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_project_dependencies_and_link() {
+        let dir = temp_project("project", &[
+            ("app/bmb.toml", "[package]\nname = \"app\"\n[dependencies]\nmath-lib = { path = \"../math-lib\" }\n"),
+            ("app/src/main.bmb", "use math_lib::add;\nuse util::twice;\nfn main() -> i64 = twice(add(1, 2));"),
+            ("app/src/util.bmb", "pub fn twice(x: i64) -> i64 = x * 2;\nfn main() -> i64 = 0;"),
+            ("math-lib/src/lib.bmb", "pub fn add(a: i64, b: i64) -> i64 = a + b;"),
+        ]);
+        let main_file = dir.join("app/src/main.bmb");
+        let program = parse_program(&std::fs::read_to_string(&main_file).unwrap());
+
+        let mut resolver = Resolver::for_file(&main_file).unwrap();
+        let imports = resolver.resolve_uses(&program).unwrap();
+        assert_eq!(imports.get_import_module("add"), Some("math_lib"));
+        assert_eq!(imports.get_import_module("twice"), Some("util"));
+
+        // Module items come first; only the root file's `main` is kept
+        let linked = resolver.link(&program);
+        let fns: Vec<_> = linked.items.iter().filter_map(|item| match item {
+            Item::FnDef(f) => Some(f.name.node.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(fns, ["add", "twice", "main"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
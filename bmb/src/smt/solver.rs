@@ -1,31 +1,86 @@
-//! Z3 SMT solver interface via external process
+//! SMT solver interface via external process
 //!
-//! Invokes Z3 on generated SMT-LIB2 files and parses results.
+//! Pipes generated SMT-LIB2 scripts into Z3 or CVC5 and parses results.
+//! The scripts are the same for every backend; only the command line
+//! differs.
 
 use std::collections::HashMap;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+use serde::{Deserialize, Serialize};
+
+/// Solver executable to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SolverBackend {
+    #[default]
+    Z3,
+    Cvc5,
+}
+
+impl SolverBackend {
+    /// Executable looked up on `PATH` when no path is given
+    pub fn default_path(self) -> &'static str {
+        match self {
+            SolverBackend::Z3 => "z3",
+            SolverBackend::Cvc5 => "cvc5",
+        }
+    }
+
+    /// Arguments that read a script from stdin and give up on each
+    /// `check-sat` after `timeout` seconds
+    fn args(self, timeout: u32) -> Vec<String> {
+        match self {
+            SolverBackend::Z3 => vec!["-in".to_string(), format!("-T:{}", timeout)],
+            // The scripts declare QF_LIA, which CVC5 enforces strictly while
+            // Z3 tolerates quantifiers and nonlinear terms
+            SolverBackend::Cvc5 => vec![
+                "--lang=smt2".to_string(),
+                "--produce-models".to_string(),
+                "--force-logic=ALL".to_string(),
+                format!("--tlimit-per={}", u64::from(timeout) * 1000),
+            ],
+        }
+    }
+}
+
+impl std::fmt::Display for SolverBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverBackend::Z3 => write!(f, "Z3"),
+            SolverBackend::Cvc5 => write!(f, "CVC5"),
+        }
+    }
+}
+
 /// SMT solver interface
 pub struct SmtSolver {
-    /// Path to Z3 executable
-    z3_path: String,
+    backend: SolverBackend,
+    /// Path to the executable, if not the backend's default
+    path: Option<String>,
     /// Timeout in seconds
     timeout: u32,
 }
 
 impl SmtSolver {
-    /// Create a new solver with default Z3 path
+    /// Create a new solver running `z3` from `PATH`
     pub fn new() -> Self {
         Self {
-            z3_path: "z3".to_string(),
+            backend: SolverBackend::Z3,
+            path: None,
             timeout: 10,
         }
     }
 
-    /// Set Z3 executable path
+    /// Select the solver to run
+    pub fn with_backend(mut self, backend: SolverBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Set the solver executable path
     pub fn with_path(mut self, path: &str) -> Self {
-        self.z3_path = path.to_string();
+        self.path = Some(path.to_string());
         self
     }
 
@@ -35,9 +90,23 @@ impl SmtSolver {
         self
     }
 
-    /// Check if Z3 is available
+    pub fn backend(&self) -> SolverBackend {
+        self.backend
+    }
+
+    /// Per-query timeout in seconds
+    pub fn timeout(&self) -> u32 {
+        self.timeout
+    }
+
+    /// The executable that is run
+    pub fn executable(&self) -> &str {
+        self.path.as_deref().unwrap_or(self.backend.default_path())
+    }
+
+    /// Check if the solver is available
     pub fn is_available(&self) -> bool {
-        Command::new(&self.z3_path)
+        Command::new(self.executable())
             .arg("--version")
             .stdout(Stdio::null())
             .stderr(Stdio::null())
@@ -46,37 +115,46 @@ impl SmtSolver {
             .unwrap_or(false)
     }
 
-    /// Run Z3 on the given SMT-LIB2 script
+    /// First line of the solver's `--version` output
+    pub fn version(&self) -> Option<String> {
+        let output = Command::new(self.executable()).arg("--version").output().ok()?;
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        stdout.lines().next().map(|line| line.trim().to_string())
+    }
+
+    /// Run the solver on the given SMT-LIB2 script
     pub fn solve(&self, smt_script: &str) -> Result<SolverResult, SolverError> {
-        let mut child = Command::new(&self.z3_path)
-            .arg("-in")
-            .arg(format!("-T:{}", self.timeout))
+        let exe = self.executable();
+        let mut child = Command::new(exe)
+            .args(self.backend.args(self.timeout))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .map_err(|e| SolverError::ProcessError(format!("failed to start z3: {}", e)))?;
+            .map_err(|e| SolverError::ProcessError(format!("failed to start {}: {}", exe, e)))?;
 
         // Write SMT script to stdin
         if let Some(mut stdin) = child.stdin.take() {
             stdin.write_all(smt_script.as_bytes())
-                .map_err(|e| SolverError::ProcessError(format!("failed to write to z3: {}", e)))?;
+                .map_err(|e| SolverError::ProcessError(format!("failed to write to {}: {}", exe, e)))?;
         }
 
         // Get output
         let output = child.wait_with_output()
-            .map_err(|e| SolverError::ProcessError(format!("failed to wait for z3: {}", e)))?;
+            .map_err(|e| SolverError::ProcessError(format!("failed to wait for {}: {}", exe, e)))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
 
         if !stderr.is_empty() && !stderr.contains("warning") {
-            return Err(SolverError::Z3Error(stderr.to_string()));
+            return Err(SolverError::SolverOutput(self.backend, stderr.to_string()));
         }
 
         self.parse_result(&stdout)
     }
 
+    /// Both backends answer `check-sat` on the first line and print the
+    /// model as `define-fun`s
     fn parse_result(&self, output: &str) -> Result<SolverResult, SolverError> {
         let lines: Vec<&str> = output.lines().collect();
 
@@ -98,126 +176,89 @@ impl SmtSolver {
     }
 
     fn parse_model(&self, lines: &[&str]) -> HashMap<String, String> {
-        let mut model = HashMap::new();
-        let full_output = lines.join("\n");
-
-        // Simple parser for Z3 model output format:
-        // (model
-        //   (define-fun x () Int 5)
+        // The model is printed as s-expressions, often spread over lines:
+        // (
+        //   (define-fun x () Int
+        //     (- 5))
         //   (define-fun y () Bool true)
         // )
+        let output = lines.join("\n");
+        let tokens = sexpr_tokens(&output);
+        let mut model = HashMap::new();
 
-        // Find define-fun declarations
-        for line in lines {
-            let line = line.trim();
-            if line.starts_with("(define-fun ")
-                && let Some(parsed) = self.parse_define_fun(line)
-            {
-                model.insert(parsed.0, parsed.1);
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i] == "define-fun" && i + 1 < tokens.len() {
+                // (define-fun name (args) Sort value)
+                let params_end = sexpr_end(&tokens, i + 2);
+                let sort_end = sexpr_end(&tokens, params_end);
+                let value_end = sexpr_end(&tokens, sort_end);
+                if value_end > sort_end {
+                    model.insert(tokens[i + 1].to_string(), join_sexpr(&tokens[sort_end..value_end]));
+                }
+                i = value_end.max(i + 2);
+            } else {
+                i += 1;
             }
         }
 
-        // If simple parsing failed, try to extract from full output
-        if model.is_empty() && full_output.contains("define-fun") {
-            // More robust parsing using simple string parsing
-            self.parse_model_fallback(&full_output, &mut model);
-        }
-
         model
     }
+}
 
-    fn parse_define_fun(&self, line: &str) -> Option<(String, String)> {
-        // Parse: (define-fun name () Type value)
-        let line = line.trim_start_matches("(define-fun ").trim_end_matches(')');
-        let parts: Vec<&str> = line.split_whitespace().collect();
-
-        if parts.len() >= 4 {
-            let name = parts[0].to_string();
-            // Skip "()" and type, get value
-            let value = parts[3..].join(" ").trim_end_matches(')').to_string();
-            Some((name, value))
-        } else {
-            None
+/// Split SMT-LIB output into parentheses and atoms
+fn sexpr_tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if let Some(s) = start.take() {
+                tokens.push(&text[s..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&text[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
         }
     }
+    if let Some(s) = start {
+        tokens.push(&text[s..]);
+    }
+    tokens
+}
 
-    fn parse_model_fallback(&self, output: &str, model: &mut HashMap<String, String>) {
-        // Parse define-fun expressions more carefully
-        // Format: (define-fun name () Type value)
-        let mut chars = output.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '(' {
-                // Check if this is define-fun
-                let mut token = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next.is_whitespace() || next == '(' || next == ')' {
-                        break;
-                    }
-                    token.push(chars.next().unwrap());
-                }
-
-                if token == "define-fun" {
-                    // Skip whitespace
-                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
-                        chars.next();
-                    }
-
-                    // Get variable name
-                    let mut name = String::new();
-                    while let Some(&next) = chars.peek() {
-                        if next.is_whitespace() || next == '(' || next == ')' {
-                            break;
-                        }
-                        name.push(chars.next().unwrap());
-                    }
-
-                    // Skip to value (after "()" and type)
-                    let mut depth = 0;
-                    let mut found_type = false;
-                    while let Some(c) = chars.next() {
-                        if c == '(' {
-                            depth += 1;
-                        } else if c == ')' {
-                            if depth > 0 {
-                                depth -= 1;
-                            } else {
-                                // End of define-fun without value
-                                break;
-                            }
-                        } else if depth == 0 && !c.is_whitespace() && found_type {
-                            // This is the start of the value
-                            let mut value = String::from(c);
-                            let mut val_depth = if c == '(' { 1 } else { 0 };
-
-                            while let Some(&next) = chars.peek() {
-                                if val_depth == 0 && (next == ')' || next.is_whitespace()) {
-                                    break;
-                                }
-                                let next = chars.next().unwrap();
-                                if next == '(' {
-                                    val_depth += 1;
-                                } else if next == ')' {
-                                    val_depth -= 1;
-                                }
-                                value.push(next);
-                            }
-
-                            model.insert(name, value);
-                            break;
-                        } else if depth == 0 && !c.is_whitespace() {
-                            // This is the type
-                            found_type = true;
-                            // Skip rest of type
-                            while chars.peek().is_some_and(|c| !c.is_whitespace()) {
-                                chars.next();
-                            }
-                        }
-                    }
+/// Index just past the s-expression starting at `start`
+fn sexpr_end(tokens: &[&str], start: usize) -> usize {
+    if tokens.get(start) != Some(&"(") {
+        return (start + 1).min(tokens.len());
+    }
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match *token {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
                 }
             }
+            _ => {}
         }
     }
+    tokens.len()
+}
+
+/// Re-join s-expression tokens as `(- 5)`
+fn join_sexpr(tokens: &[&str]) -> String {
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && tokens[i - 1] != "(" && *token != ")" {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+    out
 }
 
 impl Default for SmtSolver {
@@ -244,7 +285,8 @@ pub enum SolverResult {
 #[allow(clippy::enum_variant_names)]
 pub enum SolverError {
     ProcessError(String),
-    Z3Error(String),
+    /// The solver reported an error on stderr
+    SolverOutput(SolverBackend, String),
     ParseError(String),
 }
 
@@ -252,7 +294,7 @@ impl std::fmt::Display for SolverError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SolverError::ProcessError(msg) => write!(f, "process error: {}", msg),
-            SolverError::Z3Error(msg) => write!(f, "Z3 error: {}", msg),
+            SolverError::SolverOutput(backend, msg) => write!(f, "{} error: {}", backend, msg),
             SolverError::ParseError(msg) => write!(f, "parse error: {}", msg),
         }
     }
@@ -261,7 +303,7 @@ impl std::fmt::Display for SolverError {
 impl std::error::Error for SolverError {}
 
 /// Result of contract verification
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VerifyResult {
     /// Contract is verified (negation is unsatisfiable)
     Verified,
@@ -269,12 +311,12 @@ pub enum VerifyResult {
     Failed(Counterexample),
     /// Solver could not determine (timeout/unknown)
     Unknown(String),
-    /// Z3 not available
+    /// Solver not available
     SolverNotAvailable,
 }
 
 /// Counterexample showing why verification failed
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Counterexample {
     /// Variable assignments that violate the contract
     pub assignments: Vec<(String, String)>,
@@ -317,7 +359,51 @@ mod tests {
         let solver = SmtSolver::new()
             .with_path("/usr/bin/z3")
             .with_timeout(30);
-        assert_eq!(solver.z3_path, "/usr/bin/z3");
+        assert_eq!(solver.executable(), "/usr/bin/z3");
         assert_eq!(solver.timeout, 30);
     }
+
+    #[test]
+    fn test_backend_command_lines() {
+        let z3 = SmtSolver::new().with_timeout(5);
+        assert_eq!(z3.executable(), "z3");
+        assert_eq!(z3.backend.args(z3.timeout), ["-in", "-T:5"]);
+
+        let cvc5 = SmtSolver::new().with_backend(SolverBackend::Cvc5).with_timeout(5);
+        assert_eq!(cvc5.executable(), "cvc5");
+        let args = cvc5.backend.args(cvc5.timeout);
+        assert!(args.contains(&"--produce-models".to_string()));
+        assert!(args.contains(&"--tlimit-per=5000".to_string()));
+    }
+
+    #[test]
+    fn test_parse_cvc5_output() {
+        let output = "sat\n(\n(define-fun x () Int (- 3))\n(define-fun ok () Bool false)\n)\n";
+        let solver = SmtSolver::new().with_backend(SolverBackend::Cvc5);
+        let Ok(SolverResult::Sat(model)) = solver.parse_result(output) else {
+            panic!("expected sat");
+        };
+        assert_eq!(model["x"], "(- 3)");
+        assert_eq!(model["ok"], "false");
+
+        // CVC5 reports the failed get-model after unsat on stdout as well
+        let unsat = "unsat\n(error \"cannot get model unless after a SAT or UNKNOWN response.\")\n";
+        assert!(matches!(solver.parse_result(unsat), Ok(SolverResult::Unsat)));
+    }
+
+    #[test]
+    fn test_parse_multiline_model() {
+        let output = "sat\n(\n  (define-fun x () Int\n    (- 5))\n  (define-fun b () Bool true)\n  (define-fun __ret__ () Int\n    3)\n)\n";
+        let Ok(SolverResult::Sat(model)) = SmtSolver::new().parse_result(output) else {
+            panic!("expected sat");
+        };
+        assert_eq!(model["x"], "(- 5)");
+        assert_eq!(model["b"], "true");
+        assert_eq!(model["__ret__"], "3");
+
+        let Ok(SolverResult::Sat(model)) = SmtSolver::new().parse_result("sat\n((define-fun y () Int 7))") else {
+            panic!("expected sat");
+        };
+        assert_eq!(model["y"], "7");
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! Translates BMB expressions into SMT-LIB2 format strings.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 
 use crate::ast::{BinOp, Expr, FnDef, Spanned, Type, UnOp};
@@ -82,21 +82,83 @@ impl SmtLibGenerator {
 }
 
 /// Translator from BMB AST to SMT-LIB2 expressions
+#[derive(Clone)]
 pub struct SmtTranslator {
     /// Variable types
     var_types: HashMap<String, SmtSort>,
+    /// Values of `const` items, translated where no variable shadows them
+    consts: HashMap<String, Expr>,
+    /// SMT terms for the lengths of array and string variables (`xs.len()`)
+    lengths: HashMap<String, String>,
+    /// `f64` variables, whose values are approximated as integers
+    floats: HashSet<String>,
+    /// Parameters whose value on entry is the constant `name.pre`
+    entry_values: HashSet<String>,
+    /// Translating the inside of `old(...)`/`.pre`
+    in_old: bool,
 }
 
 impl SmtTranslator {
     pub fn new() -> Self {
         Self {
             var_types: HashMap::new(),
+            consts: HashMap::new(),
+            lengths: HashMap::new(),
+            floats: HashSet::new(),
+            entry_values: HashSet::new(),
+            in_old: false,
         }
     }
 
+    /// Translate references to these constants as their values
+    pub fn with_consts(mut self, consts: HashMap<String, Expr>) -> Self {
+        self.consts = consts;
+        self
+    }
+
+    /// Make a local variable visible to later translations
+    pub fn bind_local(&mut self, name: &str, sort: SmtSort) {
+        self.var_types.insert(name.to_string(), sort);
+    }
+
+    /// Sort of a declared variable
+    pub fn sort_of(&self, name: &str) -> Option<SmtSort> {
+        self.var_types.get(name).copied()
+    }
+
+    /// Record the length of an array or string variable, or forget it
+    pub fn set_length(&mut self, name: &str, length: Option<String>) {
+        match length {
+            Some(length) => self.lengths.insert(name.to_string(), length),
+            None => self.lengths.remove(name),
+        };
+    }
+
+    /// Length term of an array or string variable, if known
+    pub fn length_of(&self, name: &str) -> Option<&str> {
+        self.lengths.get(name).map(String::as_str)
+    }
+
+    /// Record whether a variable holds an `f64`
+    pub fn set_float(&mut self, name: &str, is_float: bool) {
+        if is_float {
+            self.floats.insert(name.to_string());
+        } else {
+            self.floats.remove(name);
+        }
+    }
+
+    /// Whether a variable holds an `f64`
+    pub fn is_float(&self, name: &str) -> bool {
+        self.floats.contains(name)
+    }
+
     /// Set up the translator for a function definition
     pub fn setup_function(&mut self, func: &FnDef, generator: &mut SmtLibGenerator) {
         self.var_types.clear();
+        self.lengths.clear();
+        self.floats.clear();
+        self.entry_values.clear();
 
         // Declare parameters
         for param in &func.params {
@@ -104,6 +166,7 @@ impl SmtTranslator {
             let name = &param.name.node;
             generator.declare_var(name, sort);
             self.var_types.insert(name.clone(), sort);
+            self.set_float(name, matches!(param.ty.node, Type::F64));
         }
 
         // Declare __ret__ for return value
@@ -118,6 +181,29 @@ impl SmtTranslator {
         }
     }
 
+    /// Declare the constant `name.pre` for the value parameter `name` has on
+    /// entry, which `old(name)` translates to. Assignments in the body only
+    /// rebind `name` inside the queries built from it, so the global `name`
+    /// is the entry value too
+    pub fn declare_entry_value(&mut self, name: &str, generator: &mut SmtLibGenerator) {
+        let Some(&sort) = self.var_types.get(name) else { return };
+        let entry = format!("{}.pre", name);
+        generator.declare_var(&entry, sort);
+        generator.assert(&format!("(= {} {})", entry, name));
+        self.entry_values.insert(name.to_string());
+    }
+
+    /// A translator for the body of a quantifier over `var`, which shadows
+    /// any variable or constant of the same name
+    fn bound(&self, var: &str, ty: &Type) -> Self {
+        let mut inner = self.clone();
+        inner.bind_local(var, Self::type_to_sort(ty));
+        inner.lengths.remove(var);
+        inner.set_float(var, matches!(ty, Type::F64));
+        inner.entry_values.remove(var);
+        inner
+    }
+
     /// Convert BMB Type to SMT Sort
     pub fn type_to_sort(ty: &Type) -> SmtSort {
         match ty {
@@ -137,7 +223,7 @@ impl SmtTranslator {
             // v0.5 Phase 5: References as Int (simplified)
             Type::Ref(_) | Type::RefMut(_) => SmtSort::Int,
             // v0.5 Phase 6: Arrays as Int (simplified)
-            Type::Array(_, _) => SmtSort::Int,
+            Type::Array(_, _) | Type::ArrayConst(_, _) => SmtSort::Int,
             // v0.2: Refined types use base type sort
             Type::Refined { base, .. } => Self::type_to_sort(base),
             // v0.20.0: Fn types as Int (function pointers)
@@ -191,9 +277,12 @@ impl SmtTranslator {
 
             Expr::Unit => Ok("true".to_string()),
 
+            Expr::Var(name) if self.in_old && self.entry_values.contains(name) => Ok(format!("{}.pre", name)),
             Expr::Var(name) => {
                 if self.var_types.contains_key(name) {
                     Ok(name.clone())
+                } else if let Some(value) = self.consts.get(name) {
+                    self.translate_expr(value)
                 } else {
                     Err(TranslateError::UndefinedVariable(name.clone()))
                 }
@@ -237,6 +326,14 @@ impl SmtTranslator {
                 Err(TranslateError::UnsupportedFeature(format!("assignment: {}", name)))
             }
 
+            Expr::IndexAssign { .. } => {
+                Err(TranslateError::UnsupportedFeature("element assignment".to_string()))
+            }
+
+            Expr::FieldAssign { .. } => {
+                Err(TranslateError::UnsupportedFeature("field assignment".to_string()))
+            }
+
             Expr::While { .. } => {
                 // While loops not supported in SMT
                 Err(TranslateError::UnsupportedFeature("while loop".to_string()))
@@ -246,12 +343,30 @@ impl SmtTranslator {
                 Err(TranslateError::UnsupportedFeature(format!("function call: {}", func)))
             }
 
+            // The block's value is its last expression, under the lets before it
             Expr::Block(exprs) => {
-                if let Some(last) = exprs.last() {
-                    self.translate(last)
-                } else {
-                    Ok("true".to_string())
+                let Some((last, init)) = exprs.split_last() else {
+                    return Ok("true".to_string());
+                };
+                let mut result = self.translate(last)?;
+                for stmt in init.iter().rev() {
+                    match &stmt.node {
+                        Expr::LetStmt { name, value, .. } => {
+                            let v = self.translate(value)?;
+                            result = format!("(let (({} {})) {})", name, v, result);
+                        }
+                        // State changes can't be skipped like other statements
+                        Expr::Assign { .. } | Expr::IndexAssign { .. } | Expr::FieldAssign { .. } | Expr::While { .. } | Expr::Loop { .. } | Expr::For { .. } => {
+                            self.translate(stmt)?;
+                        }
+                        _ => {}
+                    }
                 }
+                Ok(result)
+            }
+
+            Expr::LetStmt { name, .. } => {
+                Err(TranslateError::UnsupportedFeature(format!("let statement: {}", name)))
             }
 
             // v0.5: Struct and Enum expressions - not fully supported in SMT
@@ -308,22 +423,27 @@ impl SmtTranslator {
                 Err(TranslateError::UnsupportedFeature("array index".to_string()))
             }
 
-            // v0.5 Phase 8: Method calls - not supported in SMT
-            Expr::MethodCall { .. } => {
-                Err(TranslateError::UnsupportedFeature("method call".to_string()))
-            }
+            // `xs.len()` where the length of `xs` is known; other method calls
+            // are not supported in SMT
+            Expr::MethodCall { receiver, method, args } => match &receiver.node {
+                Expr::Var(name) if method == "len" && args.is_empty() => self
+                    .length_of(name)
+                    .map(str::to_string)
+                    .ok_or_else(|| TranslateError::UnsupportedFeature(format!("length of {}", name))),
+                _ => Err(TranslateError::UnsupportedFeature("method call".to_string())),
+            },
 
             // v0.2: State references for contracts
-            Expr::StateRef { expr, state } => {
-                let base = self.translate_expr(&expr.node)?;
-                // Append state suffix to create unique SMT variable
-                // e.g., x.pre -> x_pre, x.post -> x_post
-                let suffix = match state {
-                    crate::ast::StateKind::Pre => "_pre",
-                    crate::ast::StateKind::Post => "_post",
-                };
-                Ok(format!("{}{}", base, suffix))
-            }
+            // `old(e)`/`e.pre` reads parameters as their entry values; the
+            // post-state is the current one
+            Expr::StateRef { expr, state } => match state {
+                crate::ast::StateKind::Pre => {
+                    let mut entry = self.clone();
+                    entry.in_old = true;
+                    entry.translate_expr(&expr.node)
+                }
+                crate::ast::StateKind::Post => self.translate_expr(&expr.node),
+            },
 
             // v0.2: Refinement self-reference
             Expr::It => Ok("__it__".to_string()),
@@ -346,19 +466,19 @@ impl SmtTranslator {
                 "loops are not supported in contract verification".to_string(),
             )),
             Expr::Break { .. } => Ok("false".to_string()),
-            Expr::Continue => Ok("false".to_string()),
+            Expr::Continue { .. } => Ok("false".to_string()),
             Expr::Return { .. } => Ok("false".to_string()),
 
             // v0.37: Quantifiers - translate directly to SMT-LIB2 forall/exists
             Expr::Forall { var, ty, body } => {
                 let smt_type = self.type_to_smt(&ty.node)?;
-                let body_smt = self.translate(body)?;
+                let body_smt = self.bound(&var.node, &ty.node).translate(body)?;
                 Ok(format!("(forall (({} {})) {})", var.node, smt_type, body_smt))
             }
 
             Expr::Exists { var, ty, body } => {
                 let smt_type = self.type_to_smt(&ty.node)?;
-                let body_smt = self.translate(body)?;
+                let body_smt = self.bound(&var.node, &ty.node).translate(body)?;
                 Ok(format!("(exists (({} {})) {})", var.node, smt_type, body_smt))
             }
 
@@ -428,9 +548,9 @@ impl SmtTranslator {
     fn type_to_smt(&self, ty: &crate::ast::Type) -> Result<String, TranslateError> {
         use crate::ast::Type;
         match ty {
-            // v0.38: Include unsigned types
-            Type::I32 | Type::I64 | Type::U32 | Type::U64 => Ok("Int".to_string()),
-            Type::F64 => Ok("Real".to_string()),
+            // v0.38: Include unsigned types; f64 is approximated as an integer
+            // like everywhere else in the translation
+            Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::Char => Ok("Int".to_string()),
             Type::Bool => Ok("Bool".to_string()),
             Type::String => Err(TranslateError::UnsupportedFeature(
                 "String type in quantifier".to_string()
@@ -505,4 +625,26 @@ mod tests {
         let expr = Spanned::new(Expr::BoolLit(true), crate::ast::Span::new(0, 0));
         assert_eq!(trans.translate(&expr).unwrap(), "true");
     }
+
+    #[test]
+    fn test_const_translated_as_value() {
+        let trans = SmtTranslator::new().with_consts(HashMap::from([("MAX".to_string(), Expr::IntLit(-8))]));
+        let expr = Spanned::new(Expr::Var("MAX".to_string()), crate::ast::Span::new(0, 0));
+        assert_eq!(trans.translate(&expr).unwrap(), "(- 8)");
+    }
+
+    #[test]
+    fn test_quantifier_binds_its_variable() {
+        let source = "fn f(n: i64) -> i64 post forall i: i64, (0 <= i and i < n) implies i < ret = n;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let crate::ast::Item::FnDef(func) = &program.items[0] else { panic!("expected a function") };
+        let mut trans = SmtTranslator::new().with_consts(HashMap::from([("i".to_string(), Expr::IntLit(3))]));
+        trans.setup_function(func, &mut SmtLibGenerator::new());
+
+        // The bound `i` shadows the constant and stays out of scope afterwards
+        let post = trans.translate(func.post.as_ref().unwrap()).unwrap();
+        assert_eq!(post, "(forall ((i Int)) (=> (and (<= 0 i) (< i n)) (< i __ret__)))");
+        assert_eq!(trans.sort_of("i"), None);
+    }
 }
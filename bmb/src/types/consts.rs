@@ -0,0 +1,316 @@
+//! Compile-time evaluation of `const` declarations
+//!
+//! Initializers are restricted to literals, other constants and operators
+//! applied to them, so every constant has a value before type checking of
+//! function bodies starts. The interpreter and MIR lowering substitute these
+//! values at use sites.
+
+use std::collections::HashMap;
+
+use crate::ast::{BinOp, ConstDef, Expr, Item, Program, Spanned, Type, UnOp};
+use crate::error::{CompileError, Result};
+
+/// Value of an evaluated constant
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
+
+impl ConstValue {
+    /// Literal expression with this value
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            ConstValue::Int(n) => Expr::IntLit(*n),
+            ConstValue::Float(f) => Expr::FloatLit(*f),
+            ConstValue::Bool(b) => Expr::BoolLit(*b),
+            ConstValue::Char(c) => Expr::CharLit(*c),
+            ConstValue::Str(s) => Expr::StringLit(s.clone()),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ConstValue::Int(_) => "integer",
+            ConstValue::Float(_) => "f64",
+            ConstValue::Bool(_) => "bool",
+            ConstValue::Char(_) => "char",
+            ConstValue::Str(_) => "String",
+        }
+    }
+
+    /// Whether the value fits a constant declared with type `ty`
+    fn fits(&self, ty: &Type) -> bool {
+        match (self, ty) {
+            (ConstValue::Int(n), Type::I32) => i32::try_from(*n).is_ok(),
+            (ConstValue::Int(n), Type::U32) => u32::try_from(*n).is_ok(),
+            (ConstValue::Int(n), Type::U64) => *n >= 0,
+            (ConstValue::Int(_), Type::I64)
+            | (ConstValue::Float(_), Type::F64)
+            | (ConstValue::Bool(_), Type::Bool)
+            | (ConstValue::Char(_), Type::Char)
+            | (ConstValue::Str(_), Type::String) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Evaluate every `const` item of `program`, in dependency order
+pub fn eval_consts(program: &Program) -> Result<HashMap<String, ConstValue>> {
+    let defs: HashMap<&str, &ConstDef> = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::ConstDef(c) => Some((c.name.node.as_str(), c)),
+            _ => None,
+        })
+        .collect();
+
+    let mut evaluator = Evaluator { defs: &defs, values: HashMap::new(), in_progress: Vec::new() };
+    for item in &program.items {
+        if let Item::ConstDef(c) = item {
+            evaluator.eval_const(c)?;
+        }
+    }
+    Ok(evaluator.values)
+}
+
+struct Evaluator<'a> {
+    defs: &'a HashMap<&'a str, &'a ConstDef>,
+    values: HashMap<String, ConstValue>,
+    /// Constants whose initializers are being evaluated, outermost first
+    in_progress: Vec<&'a str>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn eval_const(&mut self, def: &'a ConstDef) -> Result<ConstValue> {
+        let name = def.name.node.as_str();
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(start) = self.in_progress.iter().position(|n| *n == name) {
+            let cycle: Vec<&str> = self.in_progress[start..].iter().copied().chain([name]).collect();
+            return Err(CompileError::type_error(
+                format!("cyclic constant definition: {}", cycle.join(" -> ")),
+                def.name.span,
+            ));
+        }
+
+        self.in_progress.push(name);
+        let value = self.eval(&def.value, name);
+        self.in_progress.pop();
+        let value = value?;
+
+        if !value.fits(&def.ty.node) {
+            return Err(CompileError::type_error(
+                format!(
+                    "constant `{}` type mismatch: expected {}, found {} value",
+                    name,
+                    def.ty.node,
+                    value.kind()
+                ),
+                def.value.span,
+            ));
+        }
+        self.values.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn eval(&mut self, expr: &Spanned<Expr>, const_name: &str) -> Result<ConstValue> {
+        let non_constant = |what: &str| {
+            CompileError::type_error(
+                format!("non-constant {} in initializer of constant `{}`", what, const_name),
+                expr.span,
+            )
+        };
+        match &expr.node {
+            Expr::IntLit(n) => Ok(ConstValue::Int(*n)),
+            Expr::FloatLit(f) => Ok(ConstValue::Float(*f)),
+            Expr::BoolLit(b) => Ok(ConstValue::Bool(*b)),
+            Expr::CharLit(c) => Ok(ConstValue::Char(*c)),
+            Expr::StringLit(s) => Ok(ConstValue::Str(s.clone())),
+            Expr::Var(name) => match self.defs.get(name.as_str()) {
+                Some(def) => self.eval_const(def),
+                None => Err(non_constant(&format!("value `{}`", name))),
+            },
+            Expr::Unary { op, expr: operand } => {
+                let value = self.eval(operand, const_name)?;
+                match (op, value) {
+                    (UnOp::Neg, ConstValue::Int(n)) => n.checked_neg().map(ConstValue::Int).ok_or_else(|| overflow(expr, const_name)),
+                    (UnOp::Neg, ConstValue::Float(f)) => Ok(ConstValue::Float(-f)),
+                    (UnOp::Not, ConstValue::Bool(b)) => Ok(ConstValue::Bool(!b)),
+                    (UnOp::Bnot, ConstValue::Int(n)) => Ok(ConstValue::Int(!n)),
+                    (op, value) => Err(operand_mismatch(expr, &op.to_string(), &value, const_name)),
+                }
+            }
+            Expr::Binary { left, op, right } => {
+                let l = self.eval(left, const_name)?;
+                let r = self.eval(right, const_name)?;
+                eval_binary(*op, l, r).map_err(|e| match e {
+                    BinaryError::Overflow => overflow(expr, const_name),
+                    BinaryError::DivByZero => CompileError::type_error(
+                        format!("division by zero in initializer of constant `{}`", const_name),
+                        expr.span,
+                    ),
+                    BinaryError::Mismatch(value) => operand_mismatch(expr, &op.to_string(), &value, const_name),
+                })
+            }
+            Expr::Call { .. } | Expr::MethodCall { .. } => Err(non_constant("call")),
+            _ => Err(non_constant("expression")),
+        }
+    }
+}
+
+/// Value of `expr` if it is built from literals and constants alone;
+/// `lookup` resolves names that refer to constants
+pub fn eval_closed(expr: &Spanned<Expr>, lookup: &impl Fn(&str) -> Option<ConstValue>) -> Option<ConstValue> {
+    match &expr.node {
+        Expr::IntLit(n) => Some(ConstValue::Int(*n)),
+        Expr::FloatLit(f) => Some(ConstValue::Float(*f)),
+        Expr::BoolLit(b) => Some(ConstValue::Bool(*b)),
+        Expr::CharLit(c) => Some(ConstValue::Char(*c)),
+        Expr::StringLit(s) => Some(ConstValue::Str(s.clone())),
+        Expr::Var(name) => lookup(name),
+        Expr::Unary { op, expr: operand } => match (op, eval_closed(operand, lookup)?) {
+            (UnOp::Neg, ConstValue::Int(n)) => n.checked_neg().map(ConstValue::Int),
+            (UnOp::Neg, ConstValue::Float(f)) => Some(ConstValue::Float(-f)),
+            (UnOp::Not, ConstValue::Bool(b)) => Some(ConstValue::Bool(!b)),
+            (UnOp::Bnot, ConstValue::Int(n)) => Some(ConstValue::Int(!n)),
+            _ => None,
+        },
+        Expr::Binary { left, op, right } => {
+            eval_binary(*op, eval_closed(left, lookup)?, eval_closed(right, lookup)?).ok()
+        }
+        _ => None,
+    }
+}
+
+enum BinaryError {
+    Overflow,
+    DivByZero,
+    Mismatch(ConstValue),
+}
+
+fn eval_binary(op: BinOp, l: ConstValue, r: ConstValue) -> std::result::Result<ConstValue, BinaryError> {
+    use ConstValue::*;
+    let int = |v: Option<i64>| v.map(Int).ok_or(BinaryError::Overflow);
+    match (l, r) {
+        (Int(a), Int(b)) => match op {
+            BinOp::Add | BinOp::AddChecked => int(a.checked_add(b)),
+            BinOp::Sub | BinOp::SubChecked => int(a.checked_sub(b)),
+            BinOp::Mul | BinOp::MulChecked => int(a.checked_mul(b)),
+            BinOp::AddWrap => Ok(Int(a.wrapping_add(b))),
+            BinOp::SubWrap => Ok(Int(a.wrapping_sub(b))),
+            BinOp::MulWrap => Ok(Int(a.wrapping_mul(b))),
+            BinOp::AddSat => Ok(Int(a.saturating_add(b))),
+            BinOp::SubSat => Ok(Int(a.saturating_sub(b))),
+            BinOp::MulSat => Ok(Int(a.saturating_mul(b))),
+            BinOp::Div | BinOp::Mod if b == 0 => Err(BinaryError::DivByZero),
+            BinOp::Div => int(a.checked_div(b)),
+            BinOp::Mod => int(a.checked_rem(b)),
+            BinOp::Shl => int(u32::try_from(b).ok().and_then(|b| a.checked_shl(b))),
+            BinOp::Shr => int(u32::try_from(b).ok().and_then(|b| a.checked_shr(b))),
+            BinOp::Band => Ok(Int(a & b)),
+            BinOp::Bor => Ok(Int(a | b)),
+            BinOp::Bxor => Ok(Int(a ^ b)),
+            BinOp::Eq => Ok(Bool(a == b)),
+            BinOp::Ne => Ok(Bool(a != b)),
+            BinOp::Lt => Ok(Bool(a < b)),
+            BinOp::Gt => Ok(Bool(a > b)),
+            BinOp::Le => Ok(Bool(a <= b)),
+            BinOp::Ge => Ok(Bool(a >= b)),
+            _ => Err(BinaryError::Mismatch(Int(a))),
+        },
+        (Float(a), Float(b)) => match op {
+            BinOp::Add => Ok(Float(a + b)),
+            BinOp::Sub => Ok(Float(a - b)),
+            BinOp::Mul => Ok(Float(a * b)),
+            BinOp::Div => Ok(Float(a / b)),
+            BinOp::Eq => Ok(Bool(a == b)),
+            BinOp::Ne => Ok(Bool(a != b)),
+            BinOp::Lt => Ok(Bool(a < b)),
+            BinOp::Gt => Ok(Bool(a > b)),
+            BinOp::Le => Ok(Bool(a <= b)),
+            BinOp::Ge => Ok(Bool(a >= b)),
+            _ => Err(BinaryError::Mismatch(Float(a))),
+        },
+        (Bool(a), Bool(b)) => match op {
+            BinOp::And => Ok(Bool(a && b)),
+            BinOp::Or => Ok(Bool(a || b)),
+            BinOp::Implies => Ok(Bool(!a || b)),
+            BinOp::Eq => Ok(Bool(a == b)),
+            BinOp::Ne => Ok(Bool(a != b)),
+            _ => Err(BinaryError::Mismatch(Bool(a))),
+        },
+        (Str(a), Str(b)) => match op {
+            BinOp::Add => Ok(Str(a + &b)),
+            BinOp::Eq => Ok(Bool(a == b)),
+            BinOp::Ne => Ok(Bool(a != b)),
+            _ => Err(BinaryError::Mismatch(Str(a))),
+        },
+        (l, _) => Err(BinaryError::Mismatch(l)),
+    }
+}
+
+fn overflow(expr: &Spanned<Expr>, const_name: &str) -> CompileError {
+    CompileError::type_error(
+        format!("arithmetic overflow in initializer of constant `{}`", const_name),
+        expr.span,
+    )
+}
+
+fn operand_mismatch(expr: &Spanned<Expr>, op: &str, value: &ConstValue, const_name: &str) -> CompileError {
+    CompileError::type_error(
+        format!(
+            "operand type mismatch in initializer of constant `{}`: `{}` does not apply to {} values",
+            const_name,
+            op,
+            value.kind()
+        ),
+        expr.span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn consts(source: &str) -> Result<HashMap<String, ConstValue>> {
+        eval_consts(&parse("test.bmb", source, tokenize(source).unwrap()).unwrap())
+    }
+
+    #[test]
+    fn test_consts_in_any_order() {
+        let values = consts("const TOTAL: i64 = SIZE * 2 + 1;\nconst SIZE: i64 = 1 << 4;\nconst BIG: bool = TOTAL > 32;").unwrap();
+        assert_eq!(values["SIZE"], ConstValue::Int(16));
+        assert_eq!(values["TOTAL"], ConstValue::Int(33));
+        assert_eq!(values["BIG"], ConstValue::Bool(true));
+    }
+
+    #[test]
+    fn test_non_constant_initializer_rejected() {
+        let err = consts("fn f() -> i64 = 1;\nconst N: i64 = f() + 1;").unwrap_err();
+        assert!(err.message().starts_with("non-constant call"), "{}", err.message());
+        let err = consts("const N: i64 = x;").unwrap_err();
+        assert!(err.message().contains("`x`"), "{}", err.message());
+    }
+
+    #[test]
+    fn test_const_cycle_reported() {
+        let err = consts("const A: i64 = B + 1;\nconst B: i64 = A;").unwrap_err();
+        assert_eq!(err.message(), "cyclic constant definition: A -> B -> A");
+    }
+
+    #[test]
+    fn test_const_type_and_overflow_checked() {
+        assert!(consts("const N: i32 = 3000000000;").is_err());
+        assert!(consts("const N: bool = 1;").is_err());
+        assert!(consts("const N: i64 = 9223372036854775807 + 1;").is_err());
+        assert!(consts("const N: i64 = 1 / 0;").is_err());
+    }
+}
@@ -11,7 +11,7 @@
 //! Algorithm based on Rust's exhaustiveness checker:
 //! <https://rustc-dev-guide.rust-lang.org/pat-exhaustive-checking.html>
 
-use crate::ast::{LiteralPattern, Pattern, Spanned, Type};
+use crate::ast::{BinOp, Expr, LiteralPattern, Pattern, Spanned, Type, UnOp};
 use std::collections::{HashMap, HashSet};
 
 /// Represents a constructor in pattern matching
@@ -161,7 +161,7 @@ impl DeconstructedPattern {
             } => {
                 let (s, e) = match (start, end) {
                     (LiteralPattern::Int(s), LiteralPattern::Int(e)) => {
-                        (*s, if *inclusive { *e } else { *e - 1 })
+                        (*s, if *inclusive { *e } else { e.saturating_sub(1) })
                     }
                     _ => (i64::MIN, i64::MAX), // Non-int ranges match everything
                 };
@@ -378,6 +378,10 @@ pub fn check_exhaustiveness(
     arms: &[(Spanned<Pattern>, Option<Spanned<crate::ast::Expr>>)],
     ctx: &ExhaustivenessContext,
 ) -> ExhaustivenessResult {
+    if matches!(match_type, Type::Bool | Type::Tuple(_)) {
+        return check_with_usefulness(match_type, arms, ctx);
+    }
+
     // Convert patterns to deconstructed form
     let mut matrix: Vec<DeconstructedPattern> = vec![];
     let mut unreachable_arms = vec![];
@@ -728,15 +732,24 @@ fn merge_ranges(ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
 /// Find gaps in range coverage between type_min and type_max
 fn find_range_gaps(merged: &[(i64, i64)], (type_min, type_max): (i64, i64)) -> Vec<(i64, i64)> {
     let mut gaps = vec![];
+    if type_min > type_max {
+        return gaps;
+    }
     let mut current_pos = type_min;
 
     for (start, end) in merged {
+        if *start > type_max {
+            break;
+        }
         if current_pos < *start {
             // Gap before this range
             gaps.push((current_pos, *start - 1));
         }
-        // Move past this range (with overflow protection)
-        current_pos = if *end == i64::MAX { i64::MAX } else { *end + 1 };
+        if *end == i64::MAX {
+            return gaps;
+        }
+        // Move past this range; ranges below type_min don't move it back
+        current_pos = current_pos.max(*end + 1);
     }
 
     // Gap after last range
@@ -747,6 +760,388 @@ fn find_range_gaps(merged: &[(i64, i64)], (type_min, type_max): (i64, i64)) -> V
     gaps
 }
 
+/// Values an integer scrutinee of type `ty` can take, or None for
+/// non-integer types. Refinements of the form `it >= 0` or `it < 10`
+/// narrow the range; other constraints are ignored.
+fn integer_bounds(ty: &Type) -> Option<(i64, i64)> {
+    match ty {
+        Type::I64 => Some((i64::MIN, i64::MAX)),
+        Type::I32 => Some((i32::MIN as i64, i32::MAX as i64)),
+        Type::U64 => Some((0, i64::MAX)), // Approximate for u64
+        Type::U32 => Some((0, u32::MAX as i64)),
+        Type::Refined { base, constraints } => {
+            let mut bounds = integer_bounds(base)?;
+            for constraint in constraints {
+                narrow_bounds(&constraint.node, &mut bounds);
+            }
+            Some(bounds)
+        }
+        _ => None,
+    }
+}
+
+/// Intersect `bounds` with a refinement comparing `it` to a literal
+fn narrow_bounds(constraint: &Expr, bounds: &mut (i64, i64)) {
+    let Expr::Binary { left, op, right } = constraint else {
+        return;
+    };
+    if *op == BinOp::And {
+        narrow_bounds(&left.node, bounds);
+        narrow_bounds(&right.node, bounds);
+        return;
+    }
+    let (op, n) = match (&left.node, int_literal(&right.node), int_literal(&left.node)) {
+        (Expr::It, Some(n), _) => (*op, n),
+        (_, _, Some(n)) if matches!(right.node, Expr::It) => match op {
+            BinOp::Lt => (BinOp::Gt, n),
+            BinOp::Gt => (BinOp::Lt, n),
+            BinOp::Le => (BinOp::Ge, n),
+            BinOp::Ge => (BinOp::Le, n),
+            _ => (*op, n),
+        },
+        _ => return,
+    };
+    match op {
+        BinOp::Ge => bounds.0 = bounds.0.max(n),
+        BinOp::Gt => bounds.0 = bounds.0.max(n.saturating_add(1)),
+        BinOp::Le => bounds.1 = bounds.1.min(n),
+        BinOp::Lt => bounds.1 = bounds.1.min(n.saturating_sub(1)),
+        BinOp::Eq => *bounds = (bounds.0.max(n), bounds.1.min(n)),
+        _ => {}
+    }
+}
+
+fn int_literal(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::IntLit(n) => Some(*n),
+        Expr::Unary { op: UnOp::Neg, expr } => match expr.node {
+            Expr::IntLit(n) => n.checked_neg(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Name a range endpoint, using `i64::MIN` and friends for the type's limits
+fn format_int_bound(n: i64, ty: &Type) -> String {
+    let ty = match ty {
+        Type::Refined { base, .. } => base.as_ref(),
+        _ => ty,
+    };
+    match (ty, n) {
+        (Type::I64, i64::MIN) => "i64::MIN".to_string(),
+        (Type::I64, i64::MAX) => "i64::MAX".to_string(),
+        (Type::I32, n) if n == i32::MIN as i64 => "i32::MIN".to_string(),
+        (Type::I32, n) if n == i32::MAX as i64 => "i32::MAX".to_string(),
+        (Type::U32, n) if n == u32::MAX as i64 => "u32::MAX".to_string(),
+        (Type::U64, i64::MAX) => "u64::MAX".to_string(),
+        _ => n.to_string(),
+    }
+}
+
+/// Missing values of an integer match: the gaps left by its literal and
+/// range arms within the type's bounds, up to 3 of them
+fn missing_int_values(matrix: &[DeconstructedPattern], ty: &Type, bounds: (i64, i64)) -> Vec<String> {
+    let covered_ranges: Vec<(i64, i64)> = matrix
+        .iter()
+        .filter_map(|p| match &p.constructor {
+            Constructor::IntLit(n) => Some((*n, *n)),
+            Constructor::IntRange { start, end } if start <= end => Some((*start, *end)),
+            _ => None,
+        })
+        .collect();
+    if covered_ranges.is_empty() && bounds.0 <= bounds.1 {
+        return vec!["_".to_string()];
+    }
+
+    let merged = merge_ranges(&covered_ranges);
+    let mut missing = vec![];
+    for (i, (gap_start, gap_end)) in find_range_gaps(&merged, bounds).into_iter().enumerate() {
+        if i >= 3 {
+            missing.push("...".to_string());
+            break;
+        }
+        if gap_start == gap_end {
+            missing.push(format_int_bound(gap_start, ty));
+        } else if (gap_end as i128) - (gap_start as i128) < 5 {
+            // Small range - list individual values
+            missing.extend((gap_start..=gap_end).map(|v| v.to_string()));
+        } else {
+            missing.push(format!("{}..={}", format_int_bound(gap_start, ty), format_int_bound(gap_end, ty)));
+        }
+    }
+    missing
+}
+
+// ============================================================================
+// Matrix-based usefulness for bool and tuple scrutinees
+// ============================================================================
+
+/// Upper bound on expanded or-pattern rows and reported witnesses, so
+/// pathological matches can't blow up compile time
+const MAX_EXPANSION: usize = 64;
+
+/// Exhaustiveness and reachability for `bool` and tuple matches using the
+/// usefulness algorithm: bool is a two-constructor type, tuples a single
+/// constructor whose fields are checked component-wise.
+fn check_with_usefulness(
+    match_type: &Type,
+    arms: &[(Spanned<Pattern>, Option<Spanned<crate::ast::Expr>>)],
+    ctx: &ExhaustivenessContext,
+) -> ExhaustivenessResult {
+    let tys = [match_type.clone()];
+    let mut rows: Vec<Vec<DeconstructedPattern>> = vec![];
+    let mut unreachable_arms = vec![];
+    let mut has_any_guard = false;
+    let mut has_unconditional_fallback = false;
+
+    for (i, (pattern, guard)) in arms.iter().enumerate() {
+        if guard.is_some() {
+            has_any_guard = true;
+        } else if is_unconditional_pattern(&pattern.node) {
+            has_unconditional_fallback = true;
+        }
+
+        let mut any_useful = false;
+        for alt in expand_alternatives(&pattern.node, match_type, ctx) {
+            let row = vec![alt];
+            if is_useful_row(&rows, &row, &tys, ctx) {
+                any_useful = true;
+            }
+            rows.push(row);
+        }
+        if !any_useful {
+            unreachable_arms.push(i);
+        }
+    }
+
+    let missing_patterns: Vec<String> = missing_witnesses(&rows, &tys, ctx)
+        .into_iter()
+        .map(|mut w| w.remove(0))
+        .collect();
+
+    ExhaustivenessResult {
+        is_exhaustive: missing_patterns.is_empty(),
+        unreachable_arms,
+        missing_patterns,
+        has_guards_without_fallback: has_any_guard && !has_unconditional_fallback,
+    }
+}
+
+/// Deconstruct a pattern, expanding nested or-patterns into one row each
+/// (e.g. `(true | false, x)` becomes `(true, x)` and `(false, x)`)
+fn expand_alternatives(
+    pattern: &Pattern,
+    ty: &Type,
+    ctx: &ExhaustivenessContext,
+) -> Vec<DeconstructedPattern> {
+    match pattern {
+        Pattern::Or(alts) => alts
+            .iter()
+            .flat_map(|alt| expand_alternatives(&alt.node, ty, ctx))
+            .take(MAX_EXPANSION)
+            .collect(),
+        Pattern::Binding { pattern, .. } => expand_alternatives(&pattern.node, ty, ctx),
+        Pattern::Tuple(elems) => {
+            let elem_types = tuple_field_types(ty, elems.len());
+            let mut combos: Vec<Vec<DeconstructedPattern>> = vec![vec![]];
+            for (elem, elem_ty) in elems.iter().zip(elem_types.iter()) {
+                let alts = expand_alternatives(&elem.node, elem_ty, ctx);
+                combos = combos
+                    .iter()
+                    .flat_map(|prefix| {
+                        alts.iter().map(move |alt| {
+                            let mut combo = prefix.clone();
+                            combo.push(alt.clone());
+                            combo
+                        })
+                    })
+                    .take(MAX_EXPANSION)
+                    .collect();
+            }
+            combos
+                .into_iter()
+                .map(|fields| DeconstructedPattern {
+                    constructor: Constructor::Tuple(elems.len()),
+                    fields,
+                })
+                .collect()
+        }
+        _ => vec![DeconstructedPattern::from_pattern(pattern, ty, ctx)],
+    }
+}
+
+/// Element types of a tuple type (i64 placeholders if the type is unknown)
+fn tuple_field_types(ty: &Type, arity: usize) -> Vec<Type> {
+    match ty {
+        Type::Tuple(types) => types.iter().map(|t| (**t).clone()).collect(),
+        _ => vec![Type::I64; arity],
+    }
+}
+
+/// The complete set of constructors for a type with finitely many,
+/// with their field types; None for types that need a wildcard
+fn constructor_signature(
+    ty: &Type,
+    ctx: &ExhaustivenessContext,
+) -> Option<Vec<(Constructor, Vec<Type>)>> {
+    match ty {
+        Type::Bool => Some(vec![
+            (Constructor::BoolLit(true), vec![]),
+            (Constructor::BoolLit(false), vec![]),
+        ]),
+        Type::Tuple(types) => Some(vec![(
+            Constructor::Tuple(types.len()),
+            types.iter().map(|t| (**t).clone()).collect(),
+        )]),
+        Type::Named(name) => ctx.enums.get(name).map(|variants| {
+            variants
+                .iter()
+                .map(|(variant, fields)| {
+                    let ctor = Constructor::EnumVariant {
+                        enum_name: name.clone(),
+                        variant: variant.clone(),
+                    };
+                    (ctor, fields.clone())
+                })
+                .collect()
+        }),
+        Type::Generic { name, type_args } => ctx.enums.get(name).map(|variants| {
+            let params = ctx.generic_enum_params.get(name).cloned().unwrap_or_default();
+            let subst: HashMap<String, Type> = params
+                .into_iter()
+                .zip(type_args.iter().map(|t| (**t).clone()))
+                .collect();
+            variants
+                .iter()
+                .map(|(variant, fields)| {
+                    let ctor = Constructor::EnumVariant {
+                        enum_name: name.clone(),
+                        variant: variant.clone(),
+                    };
+                    (ctor, fields.iter().map(|t| substitute_type(t, &subst)).collect())
+                })
+                .collect()
+        }),
+        _ => None,
+    }
+}
+
+/// Rows whose head matches `ctor`, with the head replaced by its fields
+fn specialize(
+    rows: &[Vec<DeconstructedPattern>],
+    ctor: &Constructor,
+    arity: usize,
+) -> Vec<Vec<DeconstructedPattern>> {
+    rows.iter()
+        .filter_map(|row| {
+            let head = &row[0];
+            let mut fields = if head.is_wildcard() {
+                vec![DeconstructedPattern::wildcard(); arity]
+            } else if head.constructor == *ctor && head.fields.len() == arity {
+                head.fields.clone()
+            } else {
+                return None;
+            };
+            fields.extend(row[1..].iter().cloned());
+            Some(fields)
+        })
+        .collect()
+}
+
+/// Rows with a wildcard head, with the head removed
+fn default_rows(rows: &[Vec<DeconstructedPattern>]) -> Vec<Vec<DeconstructedPattern>> {
+    rows.iter()
+        .filter(|row| row[0].is_wildcard())
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Whether `row` matches some value no row of `rows` matches
+fn is_useful_row(
+    rows: &[Vec<DeconstructedPattern>],
+    row: &[DeconstructedPattern],
+    tys: &[Type],
+    ctx: &ExhaustivenessContext,
+) -> bool {
+    let Some((head, rest)) = row.split_first() else {
+        return rows.is_empty();
+    };
+    let sub_tys = |field_tys: Vec<Type>| -> Vec<Type> {
+        field_tys.into_iter().chain(tys[1..].iter().cloned()).collect()
+    };
+
+    if !head.is_wildcard() {
+        let field_tys = constructor_signature(&tys[0], ctx)
+            .and_then(|sig| sig.into_iter().find(|(c, _)| *c == head.constructor))
+            .map(|(_, f)| f)
+            .unwrap_or_else(|| vec![Type::I64; head.fields.len()]);
+        let arity = head.fields.len();
+        let mut q = head.fields.clone();
+        q.extend(rest.iter().cloned());
+        return is_useful_row(&specialize(rows, &head.constructor, arity), &q, &sub_tys(field_tys), ctx);
+    }
+
+    match constructor_signature(&tys[0], ctx) {
+        Some(sig) => sig.into_iter().any(|(ctor, field_tys)| {
+            let arity = field_tys.len();
+            let mut q = vec![DeconstructedPattern::wildcard(); arity];
+            q.extend(rest.iter().cloned());
+            is_useful_row(&specialize(rows, &ctor, arity), &q, &sub_tys(field_tys), ctx)
+        }),
+        None => is_useful_row(&default_rows(rows), rest, &tys[1..], ctx),
+    }
+}
+
+/// Values (one pattern string per column) matched by no row of `rows`
+fn missing_witnesses(
+    rows: &[Vec<DeconstructedPattern>],
+    tys: &[Type],
+    ctx: &ExhaustivenessContext,
+) -> Vec<Vec<String>> {
+    let Some((ty, rest_tys)) = tys.split_first() else {
+        return if rows.is_empty() { vec![vec![]] } else { vec![] };
+    };
+
+    let Some(sig) = constructor_signature(ty, ctx) else {
+        return missing_witnesses(&default_rows(rows), rest_tys, ctx)
+            .into_iter()
+            .map(|w| std::iter::once("_".to_string()).chain(w).collect())
+            .collect();
+    };
+
+    let mut witnesses = vec![];
+    for (ctor, field_tys) in sig {
+        let arity = field_tys.len();
+        let sub_tys: Vec<Type> = field_tys.into_iter().chain(rest_tys.iter().cloned()).collect();
+        for w in missing_witnesses(&specialize(rows, &ctor, arity), &sub_tys, ctx) {
+            let (fields, rest) = w.split_at(arity);
+            let mut witness = vec![format_constructor(&ctor, fields)];
+            witness.extend(rest.iter().cloned());
+            witnesses.push(witness);
+            if witnesses.len() >= MAX_EXPANSION {
+                return witnesses;
+            }
+        }
+    }
+    witnesses
+}
+
+/// Render a constructor applied to field patterns, e.g. `(true, _)`
+fn format_constructor(ctor: &Constructor, fields: &[String]) -> String {
+    match ctor {
+        Constructor::BoolLit(b) => b.to_string(),
+        Constructor::Tuple(_) => format!("({})", fields.join(", ")),
+        Constructor::EnumVariant { enum_name, variant } if fields.is_empty() => {
+            format!("{}::{}", enum_name, variant)
+        }
+        Constructor::EnumVariant { enum_name, variant } => {
+            format!("{}::{}({})", enum_name, variant, fields.join(", "))
+        }
+        _ => "_".to_string(),
+    }
+}
+
 /// Find patterns that are missing from the matrix
 fn find_missing_patterns(
     matrix: &[DeconstructedPattern],
@@ -758,6 +1153,10 @@ fn find_missing_patterns(
         return vec![];
     }
 
+    if let Some(bounds) = integer_bounds(ty) {
+        return missing_int_values(matrix, ty, bounds);
+    }
+
     match ty {
         Type::Bool => {
             let mut covered = HashSet::new();
@@ -1084,72 +1483,6 @@ fn find_missing_patterns(
             }
         }
 
-        Type::I64 | Type::I32 | Type::U64 | Type::U32 => {
-            // v0.47: Improved integer range exhaustiveness
-            // Collect all covered ranges/literals
-            let mut covered_ranges: Vec<(i64, i64)> = vec![];
-
-            for p in matrix {
-                match &p.constructor {
-                    Constructor::IntLit(n) => {
-                        covered_ranges.push((*n, *n));
-                    }
-                    Constructor::IntRange { start, end } => {
-                        covered_ranges.push((*start, *end));
-                    }
-                    _ => {}
-                }
-            }
-
-            // Sort and merge overlapping ranges
-            if covered_ranges.is_empty() {
-                return vec!["_".to_string()];
-            }
-
-            covered_ranges.sort_by_key(|(s, _)| *s);
-            let merged = merge_ranges(&covered_ranges);
-
-            // Check for gaps and find missing patterns
-            let mut missing = vec![];
-            let type_range = match ty {
-                Type::I64 => (i64::MIN, i64::MAX),
-                Type::I32 => (i32::MIN as i64, i32::MAX as i64),
-                Type::U64 => (0_i64, i64::MAX), // Approximate for u64
-                Type::U32 => (0_i64, u32::MAX as i64),
-                _ => (i64::MIN, i64::MAX),
-            };
-
-            // Find gaps in coverage
-            let gaps = find_range_gaps(&merged, type_range);
-
-            // Report up to 3 specific missing patterns
-            for (i, (gap_start, gap_end)) in gaps.iter().enumerate() {
-                if i >= 3 {
-                    missing.push("...".to_string());
-                    break;
-                }
-                if *gap_start == *gap_end {
-                    missing.push(format!("{}", gap_start));
-                } else if *gap_end - *gap_start <= 5 {
-                    // Small range - list individual values
-                    for v in *gap_start..=(*gap_end).min(*gap_start + 4) {
-                        missing.push(format!("{}", v));
-                    }
-                    if *gap_end > *gap_start + 4 {
-                        missing.push("...".to_string());
-                    }
-                } else {
-                    missing.push(format!("{}..{}", gap_start, gap_end));
-                }
-            }
-
-            if missing.is_empty() {
-                vec![] // Exhaustive!
-            } else {
-                missing
-            }
-        }
-
         Type::F64 | Type::String => {
             // Truly infinite types - always need wildcard
             vec!["_".to_string()]
@@ -1579,4 +1912,125 @@ mod tests {
         let result = check_exhaustiveness(&ty, &arms, &ctx);
         assert!(result.is_exhaustive);
     }
+
+    fn int_range(start: i64, end: i64) -> (Spanned<Pattern>, Option<Spanned<Expr>>) {
+        let pattern = Pattern::Range {
+            start: LiteralPattern::Int(start),
+            end: LiteralPattern::Int(end),
+            inclusive: true,
+        };
+        (Spanned::new(pattern, Span::new(0, 0)), None)
+    }
+
+    fn int_lit(n: i64) -> (Spanned<Pattern>, Option<Spanned<Expr>>) {
+        (Spanned::new(Pattern::Literal(LiteralPattern::Int(n)), Span::new(0, 0)), None)
+    }
+
+    /// `i64{it >= lo, it < hi}`
+    fn bounded_i64(lo: i64, hi: i64) -> Type {
+        let cmp = |op, n| {
+            let it = Box::new(Spanned::new(Expr::It, Span::new(0, 0)));
+            let lit = Box::new(Spanned::new(Expr::IntLit(n), Span::new(0, 0)));
+            Spanned::new(Expr::Binary { left: it, op, right: lit }, Span::new(0, 0))
+        };
+        Type::Refined { base: Box::new(Type::I64), constraints: vec![cmp(BinOp::Ge, lo), cmp(BinOp::Lt, hi)] }
+    }
+
+    #[test]
+    fn test_contiguous_ranges_cover_small_type() {
+        let ctx = ExhaustivenessContext::new();
+        let ty = bounded_i64(0, 100);
+        let arms = vec![int_lit(0), int_range(1, 49), int_range(50, 99)];
+        let result = check_exhaustiveness(&ty, &arms, &ctx);
+        assert!(result.is_exhaustive, "{:?}", result.missing_patterns);
+
+        let arms = vec![int_range(i32::MIN as i64, -1), int_range(0, i32::MAX as i64)];
+        assert!(check_exhaustiveness(&Type::I32, &arms, &ctx).is_exhaustive);
+    }
+
+    #[test]
+    fn test_ranges_with_gap_report_uncovered_values() {
+        let ctx = ExhaustivenessContext::new();
+        // Off by one: 50 is in neither range
+        let arms = vec![int_range(0, 49), int_range(51, 99)];
+        let result = check_exhaustiveness(&bounded_i64(0, 100), &arms, &ctx);
+        assert!(!result.is_exhaustive);
+        assert_eq!(result.missing_patterns, ["50"]);
+
+        let arms = vec![int_range(0, 9), int_range(20, 99)];
+        let result = check_exhaustiveness(&bounded_i64(0, 100), &arms, &ctx);
+        assert_eq!(result.missing_patterns, ["10..=19"]);
+
+        let arms = vec![int_range(1, i32::MAX as i64)];
+        let result = check_exhaustiveness(&Type::I32, &arms, &ctx);
+        assert_eq!(result.missing_patterns, ["i32::MIN..=0"]);
+    }
+
+    #[test]
+    fn test_unbounded_i64_ranges_need_wildcard() {
+        let ctx = ExhaustivenessContext::new();
+        let arms = vec![int_range(1, 10), int_range(12, 99)];
+        let result = check_exhaustiveness(&Type::I64, &arms, &ctx);
+        assert_eq!(result.missing_patterns, ["i64::MIN..=0", "11", "100..=i64::MAX"]);
+
+        let mut arms = arms;
+        arms.push((Spanned::new(Pattern::Wildcard, Span::new(0, 0)), None));
+        assert!(check_exhaustiveness(&Type::I64, &arms, &ctx).is_exhaustive);
+    }
+
+    fn bool_pat(b: bool) -> Spanned<Pattern> {
+        Spanned::new(Pattern::Literal(LiteralPattern::Bool(b)), Span::new(0, 0))
+    }
+
+    fn tuple_arm(
+        elems: Vec<Spanned<Pattern>>,
+    ) -> (Spanned<Pattern>, Option<Spanned<crate::ast::Expr>>) {
+        (Spanned::new(Pattern::Tuple(elems), Span::new(0, 0)), None)
+    }
+
+    fn wild() -> Spanned<Pattern> {
+        Spanned::new(Pattern::Wildcard, Span::new(0, 0))
+    }
+
+    fn bool_pair() -> Type {
+        Type::Tuple(vec![Box::new(Type::Bool), Box::new(Type::Bool)])
+    }
+
+    #[test]
+    fn test_bool_tuple_reports_missing_combinations() {
+        let ctx = ExhaustivenessContext::new();
+        let arms = vec![tuple_arm(vec![bool_pat(true), bool_pat(true)])];
+        let result = check_exhaustiveness(&bool_pair(), &arms, &ctx);
+        assert!(!result.is_exhaustive);
+        assert_eq!(
+            result.missing_patterns,
+            vec!["(true, false)", "(false, true)", "(false, false)"]
+        );
+    }
+
+    #[test]
+    fn test_bool_tuple_unreachable_by_union_of_arms() {
+        let ctx = ExhaustivenessContext::new();
+        // (_, true) is covered by the first two arms together, not by either alone
+        let arms = vec![
+            tuple_arm(vec![bool_pat(true), wild()]),
+            tuple_arm(vec![bool_pat(false), wild()]),
+            tuple_arm(vec![wild(), bool_pat(true)]),
+        ];
+        let result = check_exhaustiveness(&bool_pair(), &arms, &ctx);
+        assert!(result.is_exhaustive);
+        assert_eq!(result.unreachable_arms, vec![2]);
+    }
+
+    #[test]
+    fn test_nested_tuple_with_or_pattern() {
+        let ctx = ExhaustivenessContext::new();
+        let ty = Type::Tuple(vec![Box::new(Type::Bool), Box::new(bool_pair())]);
+        let either = Spanned::new(Pattern::Or(vec![bool_pat(true), bool_pat(false)]), Span::new(0, 0));
+        let inner = Spanned::new(Pattern::Tuple(vec![either, bool_pat(false)]), Span::new(0, 0));
+        let arms = vec![tuple_arm(vec![wild(), inner]), tuple_arm(vec![bool_pat(true), wild()])];
+        let result = check_exhaustiveness(&ty, &arms, &ctx);
+        assert_eq!(result.missing_patterns, vec!["(false, (true, true))", "(false, (false, true))"]);
+        assert!(result.unreachable_arms.is_empty());
+    }
 }
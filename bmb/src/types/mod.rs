@@ -1,12 +1,17 @@
 //! Type checking
 
+pub mod consts;
 pub mod exhaustiveness;
+pub mod refinement;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::ast::*;
+use crate::derive::{derive_attr_span, derived_impls, extract_derive_traits, DeriveTrait};
 use crate::error::{CompileError, CompileWarning, Result};
-use crate::resolver::{Module, ResolvedImports};
+use crate::resolver::{Module, ResolvedImports, Resolver};
+
+use refinement::RefinementObligation;
 
 // ============================================================================
 // v0.60: Levenshtein Distance for Typo Suggestions
@@ -47,7 +52,7 @@ fn levenshtein_distance(a: &str, b: &str) -> usize {
 
 /// Find the most similar name from a list of candidates
 /// Returns Some(suggestion) if a close match is found (distance <= threshold)
-fn find_similar_name<'a>(name: &str, candidates: &[&'a str], threshold: usize) -> Option<&'a str> {
+pub fn find_similar_name<'a>(name: &str, candidates: &[&'a str], threshold: usize) -> Option<&'a str> {
     let mut best_match: Option<&str> = None;
     let mut best_distance = usize::MAX;
 
@@ -62,6 +67,59 @@ fn find_similar_name<'a>(name: &str, candidates: &[&'a str], threshold: usize) -
     best_match
 }
 
+/// Unify two impl targets, binding the type variables of either side
+fn unify_impl_targets(a: &Type, b: &Type, subst: &mut HashMap<String, Type>) -> bool {
+    if let Type::TypeVar(name) = a
+        && let Some(bound) = subst.get(name).cloned()
+    {
+        return unify_impl_targets(&bound, b, subst);
+    }
+    if let Type::TypeVar(name) = b
+        && let Some(bound) = subst.get(name).cloned()
+    {
+        return unify_impl_targets(a, &bound, subst);
+    }
+    match (a, b) {
+        (Type::TypeVar(x), Type::TypeVar(y)) if x == y => true,
+        (Type::TypeVar(x), other) | (other, Type::TypeVar(x)) => {
+            // `T` against `Box<T>` has no finite solution
+            if mentions_type_var(other, x, subst) {
+                return false;
+            }
+            subst.insert(x.clone(), other.clone());
+            true
+        }
+        (Type::Generic { name: n1, type_args: a1 }, Type::Generic { name: n2, type_args: a2 }) => {
+            n1 == n2
+                && a1.len() == a2.len()
+                && a1.iter().zip(a2).all(|(x, y)| unify_impl_targets(x, y, subst))
+        }
+        (Type::Tuple(a1), Type::Tuple(a2)) => {
+            a1.len() == a2.len() && a1.iter().zip(a2).all(|(x, y)| unify_impl_targets(x, y, subst))
+        }
+        (Type::Array(x, n1), Type::Array(y, n2)) => n1 == n2 && unify_impl_targets(x, y, subst),
+        (Type::Ref(x), Type::Ref(y)) | (Type::RefMut(x), Type::RefMut(y)) | (Type::Range(x), Type::Range(y)) => {
+            unify_impl_targets(x, y, subst)
+        }
+        _ => a == b,
+    }
+}
+
+/// Whether `ty` refers to the type variable `var`, looking through bindings
+fn mentions_type_var(ty: &Type, var: &str, subst: &HashMap<String, Type>) -> bool {
+    match ty {
+        Type::TypeVar(name) if name == var => true,
+        Type::TypeVar(name) => subst.get(name).is_some_and(|bound| mentions_type_var(bound, var, subst)),
+        Type::Generic { type_args: args, .. } | Type::Tuple(args) => {
+            args.iter().any(|arg| mentions_type_var(arg, var, subst))
+        }
+        Type::Array(inner, _) | Type::Ref(inner) | Type::RefMut(inner) | Type::Range(inner) => {
+            mentions_type_var(inner, var, subst)
+        }
+        _ => false,
+    }
+}
+
 /// Format a suggestion hint for an unknown name
 fn format_suggestion_hint(suggestion: Option<&str>) -> String {
     match suggestion {
@@ -70,6 +128,26 @@ fn format_suggestion_hint(suggestion: Option<&str>) -> String {
     }
 }
 
+/// Name of an item an imported module makes visible (public structs,
+/// enums, functions and extern functions)
+fn public_item_name(item: &Item) -> Option<&str> {
+    match item {
+        Item::StructDef(s) if s.visibility == Visibility::Public => Some(&s.name.node),
+        Item::EnumDef(e) if e.visibility == Visibility::Public => Some(&e.name.node),
+        Item::FnDef(f) if f.visibility == Visibility::Public => Some(&f.name.node),
+        Item::ExternFn(e) if e.visibility == Visibility::Public => Some(&e.name.node),
+        _ => None,
+    }
+}
+
+/// Names of a struct's fields that declare a default value
+fn defaulted_fields(s: &StructDef) -> std::collections::HashSet<String> {
+    s.fields.iter()
+        .filter(|f| f.default.is_some())
+        .map(|f| f.name.node.clone())
+        .collect()
+}
+
 /// Trait method signature info (v0.20.1)
 #[derive(Debug, Clone)]
 pub struct TraitMethodInfo {
@@ -79,6 +157,8 @@ pub struct TraitMethodInfo {
     pub param_types: Vec<Type>,
     /// Return type
     pub ret_type: Type,
+    /// Default body, used by impls that omit this method
+    pub default_body: Option<Spanned<Expr>>,
 }
 
 /// Trait definition info (v0.20.1)
@@ -96,12 +176,16 @@ pub struct TraitInfo {
 /// Stores the mapping from (type, trait) to implemented methods
 #[derive(Debug, Clone)]
 pub struct ImplInfo {
-    /// Trait being implemented
-    pub trait_name: String,
-    /// Type implementing the trait
+    /// Trait being implemented; `None` for an inherent impl
+    pub trait_name: Option<String>,
+    /// Type parameters of a generic impl: `impl<T> Trait for Wrapper<T>`
+    pub type_params: Vec<TypeParam>,
+    /// Type implementing the trait (type parameters appear as `TypeVar`s)
     pub target_type: Type,
     /// Implemented methods: name -> (param_types, ret_type)
     pub methods: HashMap<String, (Vec<Type>, Type)>,
+    /// Span of the impl's target type, for overlap errors
+    pub span: Span,
 }
 
 // ============================================================================
@@ -222,6 +306,11 @@ impl BindingTracker {
         }
     }
 
+    /// Whether the innermost binding of `name` is `mut`, if it is tracked
+    fn is_mutable(&self, name: &str) -> Option<bool> {
+        self.scopes.iter().rev().find_map(|s| s.get(name)).map(|info| info.is_mutable)
+    }
+
     /// Check if a variable exists in any scope
     #[allow(dead_code)]
     fn is_bound(&self, name: &str) -> bool {
@@ -235,6 +324,9 @@ pub struct TypeChecker {
     env: HashMap<String, Type>,
     /// Function signatures (non-generic)
     functions: HashMap<String, (Vec<Type>, Type)>,
+    /// Parameter names of the program's non-generic functions, which
+    /// refinements on other parameters may mention
+    param_names: HashMap<String, Vec<String>>,
     /// Generic function signatures: name -> (type_params, param_types, return_type)
     /// v0.15: Support for generic functions like `fn identity<T>(x: T) -> T`
     generic_functions: HashMap<String, (Vec<TypeParam>, Vec<Type>, Type)>,
@@ -243,13 +335,31 @@ pub struct TypeChecker {
     generic_structs: HashMap<String, (Vec<TypeParam>, Vec<(String, Type)>)>,
     /// Struct definitions: name -> field types
     structs: HashMap<String, Vec<(String, Type)>>,
+    /// Fields declared with a default value (`x: i64 = 0`), which struct
+    /// literals may omit: struct name -> field names
+    struct_defaults: HashMap<String, std::collections::HashSet<String>>,
+    /// Fields of the struct whose defaults are being checked; defaults may
+    /// not refer to them
+    default_fields: Vec<String>,
     /// Generic enum definitions: name -> (type_params, variants)
     /// v0.16: Support for generic enums like `enum Option<T> { Some(T), None }`
     generic_enums: HashMap<String, (Vec<TypeParam>, Vec<(String, Vec<Type>)>)>,
     /// Enum definitions: name -> variant info (variant_name, field types)
     enums: HashMap<String, Vec<(String, Vec<Type>)>>,
+    /// Traits named in a struct's or enum's `@derive(...)`: `Eq` makes `==`
+    /// and `!=` compare structurally, `Debug` provides `to_debug_string()`,
+    /// `Hash` provides `hash()`. The `eq()` and `show()` methods of `Eq` and
+    /// `Show` are registered as an inherent impl.
+    derives: HashMap<String, Vec<DeriveTrait>>,
     /// Current function return type (for `ret` keyword)
     current_ret_ty: Option<Type>,
+    /// Name of the function whose body is being checked
+    current_fn: Option<String>,
+    /// Names that have a value on entry to the current function (its
+    /// parameters, and quantified variables), which `old(e)` may mention
+    entry_names: HashSet<String>,
+    /// Whether the expression being inferred is inside `old(...)`/`.pre`
+    in_old: bool,
     /// Current type parameter environment (for checking generic function bodies)
     /// v0.15: Maps type parameter names to their bounds
     type_param_env: HashMap<String, Vec<String>>,
@@ -259,6 +369,11 @@ pub struct TypeChecker {
     /// Impl blocks (v0.20.1)
     /// (type_name, trait_name) -> ImplInfo
     impls: HashMap<(String, String), ImplInfo>,
+    /// Inherent impl blocks (`impl Type { ... }`), keyed by type name
+    inherent_impls: Vec<(String, ImplInfo)>,
+    /// `const` items: name -> (declared type, value). The value is missing
+    /// when an initializer failed to evaluate, which is reported once.
+    consts: HashMap<String, (Type, Option<consts::ConstValue>)>,
     /// Collected warnings during type checking (v0.47)
     /// P0 Correctness: Non-fatal diagnostics for potential issues
     warnings: Vec<CompileWarning>,
@@ -299,6 +414,22 @@ pub struct TypeChecker {
     /// v0.50.11: Function definition spans for duplicate detection
     /// name -> span of first definition
     function_spans: HashMap<String, Span>,
+    /// Resolved type of every inferred expression, keyed by span.
+    /// Only populated for checkers built with `with_span_recording` (IDE use)
+    span_types: Option<Vec<(Span, Type)>>,
+    /// Type of each `+`, `-` and `*` on `i32`, `u32` or `u64` operands, keyed
+    /// by span, so the interpreter checks overflow at that width
+    int_op_types: HashMap<Span, Type>,
+    /// Whether errors in one function are collected instead of aborting the check
+    recover_errors: bool,
+    /// Errors collected when `recover_errors` is set
+    errors: Vec<CompileError>,
+    /// Labels of the loops enclosing the expression being checked,
+    /// innermost last (`None` for an unlabeled loop)
+    loop_labels: Vec<Option<String>>,
+    /// Refinements of values passed to refined parameters and `let`
+    /// annotations that could not be decided during checking
+    refinement_obligations: Vec<RefinementObligation>,
 }
 
 impl TypeChecker {
@@ -306,19 +437,21 @@ impl TypeChecker {
         let mut functions = HashMap::new();
 
         // Register built-in functions
-        // print(x) -> Unit
+        // print(x) -> Unit; calls also take any number of printable values
         functions.insert("print".to_string(), (vec![Type::I64], Type::Unit));
-        // println(x) -> Unit
+        // println(x) -> Unit; likewise variadic (see `check_print`)
         functions.insert("println".to_string(), (vec![Type::I64], Type::Unit));
         // v0.31.21: print_str(s: String) -> i64 (for gotgan string output)
         functions.insert("print_str".to_string(), (vec![Type::String], Type::I64));
         // v0.100: println_str(s: String) -> Unit
         functions.insert("println_str".to_string(), (vec![Type::String], Type::Unit));
+        // flush() -> Unit: write out buffered print output
+        functions.insert("flush".to_string(), (vec![], Type::Unit));
         // assert(cond) -> Unit
         functions.insert("assert".to_string(), (vec![Type::Bool], Type::Unit));
         // read_int() -> i64
         functions.insert("read_int".to_string(), (vec![], Type::I64));
-        // abs(n) -> i64
+        // abs(n) -> i64; calls to abs/min/max take any numeric type (see `check_numeric_builtin`)
         functions.insert("abs".to_string(), (vec![Type::I64], Type::I64));
         // min(a, b) -> i64
         functions.insert("min".to_string(), (vec![Type::I64, Type::I64], Type::I64));
@@ -377,13 +510,35 @@ impl TypeChecker {
         functions.insert("char_at".to_string(), (vec![Type::String, Type::I64], Type::Char));
         // char_to_string(c: char) -> String (convert character to single-char string)
         functions.insert("char_to_string".to_string(), (vec![Type::Char], Type::String));
+        // int_to_string(n: i64) -> String (decimal digits, with a leading '-' if negative)
+        functions.insert("int_to_string".to_string(), (vec![Type::I64], Type::String));
         // str_len(s: String) -> i64 (Unicode character count, O(n))
         // Note: s.len() returns byte length (O(1)), str_len returns char count
         functions.insert("str_len".to_string(), (vec![Type::String], Type::I64));
+        // String search and transformation; offsets are byte offsets like s.len()
+        for name in ["str_contains", "str_starts_with", "str_ends_with"] {
+            functions.insert(name.to_string(), (vec![Type::String, Type::String], Type::Bool));
+        }
+        // str_index_of(s, needle) -> i64 (byte offset of first match, -1 when absent)
+        functions.insert("str_index_of".to_string(), (vec![Type::String, Type::String], Type::I64));
+        // str_replace(s, from, to) -> String (replaces every occurrence)
+        functions.insert("str_replace".to_string(), (vec![Type::String, Type::String, Type::String], Type::String));
+        // str_trim(s) -> String (strips leading/trailing ASCII whitespace)
+        functions.insert("str_trim".to_string(), (vec![Type::String], Type::String));
+        // parse_int(s) -> i64 (PARSE_INT_ERROR = i64 minimum when malformed or out of range)
+        functions.insert("parse_int".to_string(), (vec![Type::String], Type::I64));
+        // str_split(s, sep) -> i64 (vec_* handle of string ids, read with str_from_id)
+        functions.insert("str_split".to_string(), (vec![Type::String, Type::String], Type::I64));
+        functions.insert("str_from_id".to_string(), (vec![Type::I64], Type::String));
 
         // v0.34: Math intrinsics for Phase 34.4 Benchmark Gate (n_body, mandelbrot_fp)
         // sqrt(x: f64) -> f64 (square root)
         functions.insert("sqrt".to_string(), (vec![Type::F64], Type::F64));
+        // sin/cos/floor/ceil/round/ln/exp(x: f64) -> f64, pow(base: f64, exp: f64) -> f64
+        for name in ["sin", "cos", "floor", "ceil", "round", "ln", "exp"] {
+            functions.insert(name.to_string(), (vec![Type::F64], Type::F64));
+        }
+        functions.insert("pow".to_string(), (vec![Type::F64, Type::F64], Type::F64));
         // i64_to_f64(x: i64) -> f64 (type conversion)
         functions.insert("i64_to_f64".to_string(), (vec![Type::I64], Type::F64));
         // f64_to_i64(x: f64) -> i64 (type conversion, truncates toward zero)
@@ -433,6 +588,10 @@ impl TypeChecker {
         functions.insert("vec_free".to_string(), (vec![Type::I64], Type::Unit));
         // vec_clear(vec: i64) -> Unit (set length to 0 without deallocating)
         functions.insert("vec_clear".to_string(), (vec![Type::I64], Type::Unit));
+        // vec_push_str(vec: i64, value: String) -> Unit (append a string)
+        functions.insert("vec_push_str".to_string(), (vec![Type::I64, Type::String], Type::Unit));
+        // vec_get_str(vec: i64, index: i64) -> String (read a string pushed with vec_push_str)
+        functions.insert("vec_get_str".to_string(), (vec![Type::I64, Type::I64], Type::String));
 
         // v0.34.24: Hash builtins
         // hash_i64(x: i64) -> i64 (hash function for integers)
@@ -453,6 +612,16 @@ impl TypeChecker {
         functions.insert("hashmap_len".to_string(), (vec![Type::I64], Type::I64));
         // hashmap_free(map: i64) -> Unit (deallocate hashmap)
         functions.insert("hashmap_free".to_string(), (vec![Type::I64], Type::Unit));
+        // hashmap_insert_str(map: i64, key: i64, value: String) -> Unit
+        functions.insert("hashmap_insert_str".to_string(), (vec![Type::I64, Type::I64, Type::String], Type::Unit));
+        // hashmap_get_str(map: i64, key: i64) -> String (returns "" if not found)
+        functions.insert("hashmap_get_str".to_string(), (vec![Type::I64, Type::I64], Type::String));
+        // hashmap_insert_str_key(map: i64, key: String, value: i64) -> i64 (returns old value or 0)
+        functions.insert("hashmap_insert_str_key".to_string(), (vec![Type::I64, Type::String, Type::I64], Type::I64));
+        // hashmap_get_str_key(map: i64, key: String) -> i64 (returns value or i64::MIN if not found)
+        functions.insert("hashmap_get_str_key".to_string(), (vec![Type::I64, Type::String], Type::I64));
+        // hashmap_contains_str_key(map: i64, key: String) -> i64 (returns 1 if exists, 0 otherwise)
+        functions.insert("hashmap_contains_str_key".to_string(), (vec![Type::I64, Type::String], Type::I64));
 
         // v0.34.24: HashSet<i64> builtins (thin wrapper around HashMap)
         // hashset_new() -> i64 (create empty hashset)
@@ -471,15 +640,22 @@ impl TypeChecker {
         Self {
             env: HashMap::new(),
             functions,
+            param_names: HashMap::new(),
             generic_functions: HashMap::new(),
             generic_structs: HashMap::new(),
             structs: HashMap::new(),
             generic_enums: HashMap::new(),
             enums: HashMap::new(),
+            derives: HashMap::new(),
             current_ret_ty: None,
+            current_fn: None,
+            entry_names: HashSet::new(),
+            in_old: false,
             type_param_env: HashMap::new(),
             traits: HashMap::new(),
             impls: HashMap::new(),
+            inherent_impls: Vec::new(),
+            consts: HashMap::new(),
             warnings: Vec::new(), // v0.47: Warning collection
             binding_tracker: BindingTracker::new(), // v0.48: Unused binding detection
             imported_names: std::collections::HashSet::new(), // v0.74: Import tracking
@@ -487,72 +663,185 @@ impl TypeChecker {
             private_functions: HashMap::new(), // v0.76: Private function tracking
             called_functions: std::collections::HashSet::new(), // v0.76: Called function tracking
             private_structs: HashMap::new(), // v0.77: Private struct tracking
+            struct_defaults: HashMap::new(),
+            default_fields: Vec::new(),
             private_enums: HashMap::new(), // v0.78: Private enum tracking
             private_traits: HashMap::new(), // v0.80: Private trait tracking
             implemented_traits: std::collections::HashSet::new(), // v0.80: Implemented trait tracking
             contract_signatures: HashMap::new(), // v0.84: Contract signature tracking
             type_aliases: HashMap::new(), // v0.50.6: Type alias definitions
             function_spans: HashMap::new(), // v0.50.11: Function span tracking for duplicate detection
+            span_types: None,
+            int_op_types: HashMap::new(),
+            recover_errors: false,
+            errors: Vec::new(),
+            loop_labels: Vec::new(),
+            refinement_obligations: Vec::new(),
         }
     }
 
+    /// Parameter and return types of the built-in functions, by name
+    pub fn builtin_signatures() -> Vec<(String, Vec<Type>, Type)> {
+        let mut builtins: Vec<_> = Self::new()
+            .functions
+            .into_iter()
+            .map(|(name, (params, ret))| (name, params, ret))
+            .collect();
+        builtins.sort_by(|a, b| a.0.cmp(&b.0));
+        builtins
+    }
+
+    /// Keep checking after a type error: each function (and trait default
+    /// body) is checked independently and errors accumulate in `errors()`
+    /// instead of `check_program` returning the first one.
+    pub fn recover_errors(mut self) -> Self {
+        self.recover_errors = true;
+        self
+    }
+
+    /// Get errors collected in recovery mode
+    pub fn errors(&self) -> &[CompileError] {
+        &self.errors
+    }
+
+    /// Take all collected errors (clears the internal collection)
+    pub fn take_errors(&mut self) -> Vec<CompileError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Check if any errors were collected
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Refinements left for the contract verifier to prove
+    pub fn refinement_obligations(&self) -> &[RefinementObligation] {
+        &self.refinement_obligations
+    }
+
+    /// In recovery mode, record a failed item check and continue;
+    /// otherwise propagate the error
+    fn recover(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Err(e) if self.recover_errors => {
+                self.errors.push(e);
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    /// Create a checker that records the type of every expression it infers,
+    /// for editor queries like hover. Regular compilation should use `new`.
+    pub fn with_span_recording() -> Self {
+        Self {
+            span_types: Some(Vec::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Recorded expression types in inference order (empty unless recording)
+    pub fn span_types(&self) -> &[(Span, Type)] {
+        self.span_types.as_deref().unwrap_or(&[])
+    }
+
+    /// Type of the innermost recorded expression containing `offset`
+    pub fn type_at(&self, offset: usize) -> Option<&Type> {
+        self.span_types()
+            .iter()
+            .filter(|(span, _)| span.start <= offset && offset < span.end)
+            .min_by_key(|(span, _)| span.end - span.start)
+            .map(|(_, ty)| ty)
+    }
+
     /// v0.17: Register public items from an imported module
     /// This allows the type checker to recognize types/functions from other modules
     pub fn register_module(&mut self, module: &Module) {
         for item in &module.program.items {
-            match item {
-                // Register public struct definitions
-                Item::StructDef(s) if s.visibility == Visibility::Public => {
-                    let fields: Vec<_> = s.fields.iter()
-                        .map(|f| (f.name.node.clone(), f.ty.node.clone()))
-                        .collect();
-                    if s.type_params.is_empty() {
-                        self.structs.insert(s.name.node.clone(), fields);
-                    } else {
-                        self.generic_structs.insert(
-                            s.name.node.clone(),
-                            (s.type_params.clone(), fields)
-                        );
-                    }
+            if let Some(name) = public_item_name(item) {
+                self.register_public_item(item, name);
+            }
+        }
+    }
+
+    /// Register one public item of `module` under the name it is imported as,
+    /// e.g. `gcd` as `g` for `use math::gcd as g`
+    pub fn register_import(&mut self, module: &Module, exported: &str, local: &str) {
+        for item in &module.program.items {
+            if public_item_name(item) == Some(exported) {
+                self.register_public_item(item, local);
+            }
+        }
+    }
+
+    /// Register the modules behind resolved imports. Aliased imports
+    /// (`use m::f as g`) are also registered under the alias.
+    pub fn register_imports(&mut self, resolver: &Resolver, imports: &ResolvedImports) {
+        for (name, info) in imports.all_imports() {
+            if let Some(module) = resolver.get_module(&info.module) {
+                self.register_module(module);
+                if info.item.name() != name {
+                    self.register_import(module, info.item.name(), name);
                 }
-                // Register public enum definitions
-                Item::EnumDef(e) if e.visibility == Visibility::Public => {
-                    let variants: Vec<_> = e.variants.iter()
-                        .map(|v| (v.name.node.clone(), v.fields.iter().map(|f| f.node.clone()).collect()))
-                        .collect();
-                    if e.type_params.is_empty() {
-                        self.enums.insert(e.name.node.clone(), variants);
-                    } else {
-                        self.generic_enums.insert(
-                            e.name.node.clone(),
-                            (e.type_params.clone(), variants)
-                        );
-                    }
+            }
+        }
+    }
+
+    /// Register the signature or definition of a public item under `name`
+    fn register_public_item(&mut self, item: &Item, name: &str) {
+        match item {
+            // Register public struct definitions
+            Item::StructDef(s) if s.visibility == Visibility::Public => {
+                let fields: Vec<_> = s.fields.iter()
+                    .map(|f| (f.name.node.clone(), f.ty.node.clone()))
+                    .collect();
+                self.struct_defaults.insert(name.to_string(), defaulted_fields(s));
+                if s.type_params.is_empty() {
+                    self.structs.insert(name.to_string(), fields);
+                } else {
+                    self.generic_structs.insert(
+                        name.to_string(),
+                        (s.type_params.clone(), fields)
+                    );
                 }
-                // Register public function signatures
-                Item::FnDef(f) if f.visibility == Visibility::Public => {
-                    if f.type_params.is_empty() {
-                        let param_tys: Vec<_> = f.params.iter().map(|p| p.ty.node.clone()).collect();
-                        self.functions.insert(f.name.node.clone(), (param_tys, f.ret_ty.node.clone()));
-                    } else {
-                        let type_param_names: Vec<_> = f.type_params.iter().map(|tp| tp.name.as_str()).collect();
-                        let param_tys: Vec<_> = f.params.iter()
-                            .map(|p| self.resolve_type_vars(&p.ty.node, &type_param_names))
-                            .collect();
-                        let ret_ty = self.resolve_type_vars(&f.ret_ty.node, &type_param_names);
-                        self.generic_functions.insert(
-                            f.name.node.clone(),
-                            (f.type_params.clone(), param_tys, ret_ty)
-                        );
-                    }
+            }
+            // Register public enum definitions
+            Item::EnumDef(e) if e.visibility == Visibility::Public => {
+                let variants: Vec<_> = e.variants.iter()
+                    .map(|v| (v.name.node.clone(), v.fields.iter().map(|f| f.node.clone()).collect()))
+                    .collect();
+                if e.type_params.is_empty() {
+                    self.enums.insert(name.to_string(), variants);
+                } else {
+                    self.generic_enums.insert(
+                        name.to_string(),
+                        (e.type_params.clone(), variants)
+                    );
                 }
-                // Register public extern function signatures
-                Item::ExternFn(e) if e.visibility == Visibility::Public => {
-                    let param_tys: Vec<_> = e.params.iter().map(|p| p.ty.node.clone()).collect();
-                    self.functions.insert(e.name.node.clone(), (param_tys, e.ret_ty.node.clone()));
+            }
+            // Register public function signatures
+            Item::FnDef(f) if f.visibility == Visibility::Public => {
+                if f.type_params.is_empty() {
+                    let param_tys: Vec<_> = f.params.iter().map(|p| p.ty.node.clone()).collect();
+                    self.functions.insert(name.to_string(), (param_tys, f.ret_ty.node.clone()));
+                } else {
+                    let type_param_names: Vec<_> = f.type_params.iter().map(|tp| tp.name.as_str()).collect();
+                    let param_tys: Vec<_> = f.params.iter()
+                        .map(|p| self.resolve_type_vars(&p.ty.node, &type_param_names))
+                        .collect();
+                    let ret_ty = self.resolve_type_vars(&f.ret_ty.node, &type_param_names);
+                    self.generic_functions.insert(
+                        name.to_string(),
+                        (f.type_params.clone(), param_tys, ret_ty)
+                    );
                 }
-                _ => {}
             }
+            // Register public extern function signatures
+            Item::ExternFn(e) if e.visibility == Visibility::Public => {
+                let param_tys: Vec<_> = e.params.iter().map(|p| p.ty.node.clone()).collect();
+                self.functions.insert(name.to_string(), (param_tys, e.ret_ty.node.clone()));
+            }
+            _ => {}
         }
     }
 
@@ -565,6 +854,12 @@ impl TypeChecker {
         self.warnings.push(warning);
     }
 
+    /// Width of every `+`, `-` and `*` not done on `i64` (see
+    /// `Interpreter::set_int_op_types`)
+    pub fn int_op_types(&self) -> &HashMap<Span, Type> {
+        &self.int_op_types
+    }
+
     /// Get collected warnings as a slice (v0.47)
     pub fn warnings(&self) -> &[CompileWarning] {
         &self.warnings
@@ -587,13 +882,18 @@ impl TypeChecker {
 
     /// Check entire program
     pub fn check_program(&mut self, program: &Program) -> Result<()> {
+        // Constants come first: array lengths and function bodies may name them
+        let result = self.register_consts(program);
+        self.recover(result)?;
+
         // First pass: collect type definitions (structs and enums)
         for item in &program.items {
             match item {
                 Item::StructDef(s) => {
-                    let fields: Vec<_> = s.fields.iter()
-                        .map(|f| (f.name.node.clone(), f.ty.node.clone()))
-                        .collect();
+                    let fields = s.fields.iter()
+                        .map(|f| Ok((f.name.node.clone(), self.resolve_const_lengths(&f.ty.node, f.ty.span)?)))
+                        .collect::<Result<Vec<_>>>()?;
+                    self.struct_defaults.insert(s.name.node.clone(), defaulted_fields(s));
                     // v0.15: Handle generic structs
                     if s.type_params.is_empty() {
                         self.structs.insert(s.name.node.clone(), fields);
@@ -603,6 +903,7 @@ impl TypeChecker {
                             (s.type_params.clone(), fields)
                         );
                     }
+                    self.derives.insert(s.name.node.clone(), extract_derive_traits(&s.attributes));
                     // v0.77: Track private structs for unused type detection
                     if s.visibility != Visibility::Public && !s.name.node.starts_with('_') {
                         self.private_structs.insert(s.name.node.clone(), s.name.span);
@@ -621,12 +922,13 @@ impl TypeChecker {
                             (e.type_params.clone(), variants)
                         );
                     }
+                    self.derives.insert(e.name.node.clone(), extract_derive_traits(&e.attributes));
                     // v0.78: Track private enums for unused enum detection
                     if e.visibility != Visibility::Public && !e.name.node.starts_with('_') {
                         self.private_enums.insert(e.name.node.clone(), e.name.span);
                     }
                 }
-                Item::FnDef(_) | Item::ExternFn(_) => {}
+                Item::FnDef(_) | Item::ExternFn(_) | Item::ConstDef(_) => {}
                 // v0.5 Phase 4: Use statements are processed at module resolution time
                 Item::Use(_) => {}
                 // v0.20.1: Register trait definitions
@@ -641,6 +943,7 @@ impl TypeChecker {
                             name: m.name.node.clone(),
                             param_types,
                             ret_type: m.ret_ty.node.clone(),
+                            default_body: m.body.clone(),
                         }
                     }).collect();
 
@@ -661,9 +964,10 @@ impl TypeChecker {
                 Item::TypeAlias(t) => {
                     // Register type alias: name -> (type_params, target_type, refinement, span)
                     let refinement = t.refinement.as_ref().map(|r| r.node.clone());
+                    let target = self.resolve_const_lengths(&t.target.node, t.target.span)?;
                     self.type_aliases.insert(
                         t.name.node.clone(),
-                        (t.type_params.clone(), t.target.node.clone(), refinement, t.name.span)
+                        (t.type_params.clone(), target, refinement, t.name.span)
                     );
                 }
             }
@@ -672,6 +976,12 @@ impl TypeChecker {
         // v0.50.11: Validate type aliases for cycles
         self.validate_type_alias_cycles()?;
 
+        // Derived traits recurse into fields, so every field must support them too
+        for item in &program.items {
+            let result = self.check_derives(item);
+            self.recover(result)?;
+        }
+
         // Second pass: collect function signatures (including extern fn)
         for item in &program.items {
             match item {
@@ -697,17 +1007,23 @@ impl TypeChecker {
                     }
 
                     // v0.15: Handle generic functions separately
+                    let param_tys = f.params.iter()
+                        .map(|p| self.resolve_const_lengths(&p.ty.node, p.ty.span))
+                        .collect::<Result<Vec<_>>>()?;
+                    let ret_ty = self.resolve_const_lengths(&f.ret_ty.node, f.ret_ty.span)?;
                     if f.type_params.is_empty() {
-                        let param_tys: Vec<_> = f.params.iter().map(|p| p.ty.node.clone()).collect();
-                        self.functions
-                            .insert(f.name.node.clone(), (param_tys, f.ret_ty.node.clone()));
+                        self.functions.insert(f.name.node.clone(), (param_tys, ret_ty));
+                        self.param_names.insert(
+                            f.name.node.clone(),
+                            f.params.iter().map(|p| p.name.node.clone()).collect(),
+                        );
                     } else {
                         // Convert Named types that match type params to TypeVar
                         let type_param_names: Vec<_> = f.type_params.iter().map(|tp| tp.name.as_str()).collect();
-                        let param_tys: Vec<_> = f.params.iter()
-                            .map(|p| self.resolve_type_vars(&p.ty.node, &type_param_names))
+                        let param_tys: Vec<_> = param_tys.iter()
+                            .map(|ty| self.resolve_type_vars(ty, &type_param_names))
                             .collect();
-                        let ret_ty = self.resolve_type_vars(&f.ret_ty.node, &type_param_names);
+                        let ret_ty = self.resolve_type_vars(&ret_ty, &type_param_names);
                         self.generic_functions.insert(
                             f.name.node.clone(),
                             (f.type_params.clone(), param_tys, ret_ty)
@@ -727,57 +1043,62 @@ impl TypeChecker {
                         self.function_spans.insert(e.name.node.clone(), e.name.span);
                     }
 
-                    let param_tys: Vec<_> = e.params.iter().map(|p| p.ty.node.clone()).collect();
-                    self.functions
-                        .insert(e.name.node.clone(), (param_tys, e.ret_ty.node.clone()));
+                    let param_tys = e.params.iter()
+                        .map(|p| self.resolve_const_lengths(&p.ty.node, p.ty.span))
+                        .collect::<Result<Vec<_>>>()?;
+                    let ret_ty = self.resolve_const_lengths(&e.ret_ty.node, e.ret_ty.span)?;
+                    self.functions.insert(e.name.node.clone(), (param_tys, ret_ty));
                 }
-                Item::StructDef(_) | Item::EnumDef(_) | Item::Use(_) | Item::TypeAlias(_) => {}
+                Item::StructDef(_) | Item::EnumDef(_) | Item::Use(_) | Item::TypeAlias(_) | Item::ConstDef(_) => {}
                 // v0.20.1: TraitDef already registered in first pass
                 Item::TraitDef(_) => {}
                 // v0.20.1: Register impl blocks
-                Item::ImplBlock(i) => {
-                    let type_name = self.type_to_string(&i.target_type.node);
-                    let trait_name = i.trait_name.node.clone();
-
-                    // Register methods from impl block
-                    let mut methods = HashMap::new();
-                    for method in &i.methods {
-                        // Substitute Self with target type in method signature
-                        let param_types: Vec<Type> = method.params.iter()
-                            .filter(|p| p.name.node != "self")
-                            .map(|p| self.substitute_self(&p.ty.node, &i.target_type.node))
-                            .collect();
-                        let ret_type = self.substitute_self(&method.ret_ty.node, &i.target_type.node);
-                        methods.insert(method.name.node.clone(), (param_types, ret_type));
-                    }
-
-                    // v0.80: Track that this trait is implemented
-                    self.implemented_traits.insert(trait_name.clone());
-
-                    self.impls.insert((type_name, trait_name.clone()), ImplInfo {
-                        trait_name,
-                        target_type: i.target_type.node.clone(),
-                        methods,
-                    });
-                }
+                Item::ImplBlock(i) => self.register_impl(i)?,
             }
         }
+        // `@derive(Eq)` and `@derive(Show)` methods register like a written inherent impl
+        for i in derived_impls(program) {
+            self.register_impl(&i)?;
+        }
+        self.check_shadowed_trait_methods(program);
 
         // Third pass: type check function bodies (extern fn has no body)
+        // Each function resets the checking state, so recovery resumes here
         for item in &program.items {
             match item {
-                Item::FnDef(f) => self.check_fn(f)?,
-                Item::StructDef(_) | Item::EnumDef(_) | Item::Use(_) | Item::ExternFn(_) => {}
-                // v0.20.1: Traits and impls already registered
-                Item::TraitDef(_) | Item::ImplBlock(_) => {}
+                Item::FnDef(f) => {
+                    let result = self.check_fn(f);
+                    self.recover(result)?;
+                }
+                Item::StructDef(s) => {
+                    let result = self.check_struct_defaults(s);
+                    self.recover(result)?;
+                }
+                Item::EnumDef(_) | Item::Use(_) | Item::ExternFn(_) | Item::ConstDef(_) => {}
+                // Default method bodies are checked against the trait's own signatures
+                Item::TraitDef(t) => {
+                    for m in &t.methods {
+                        if let Some(body) = &m.body {
+                            let result = self.check_trait_default(&t.name.node, m, body);
+                            self.recover(result)?;
+                        }
+                    }
+                }
+                // v0.20.1: Impls already registered
+                Item::ImplBlock(_) => {}
                 // v0.50.6: Type aliases already processed
                 Item::TypeAlias(_) => {}
             }
         }
 
+        // A failed body may leave its function's state behind
+        self.current_ret_ty = None;
+        self.type_param_env.clear();
+
         // v0.31: Validate module header exports (RFC-0002)
         if let Some(header) = &program.header {
-            self.validate_module_exports(header, program)?;
+            let result = self.validate_module_exports(header, program);
+            self.recover(result)?;
         }
 
         // v0.76: Generate unused function warnings
@@ -841,6 +1162,11 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Whether `field` of struct `name` declares a default value
+    fn has_default(&self, name: &str, field: &str) -> bool {
+        self.struct_defaults.get(name).is_some_and(|fields| fields.contains(field))
+    }
+
     /// v0.74: Mark a name as used (for import and local type tracking)
     /// v0.77: Also tracks local struct/enum usage for unused type detection
     fn mark_name_used(&mut self, name: &str) {
@@ -863,6 +1189,10 @@ impl TypeChecker {
             Type::Array(inner, _) => {
                 self.mark_type_names_used(inner);
             }
+            Type::ArrayConst(inner, len) => {
+                self.mark_name_used(len);
+                self.mark_type_names_used(inner);
+            }
             Type::Ref(inner) | Type::RefMut(inner) => {
                 self.mark_type_names_used(inner);
             }
@@ -1001,6 +1331,70 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Evaluate `const` items and make them visible to types and expressions
+    fn register_consts(&mut self, program: &Program) -> Result<()> {
+        for item in &program.items {
+            let Item::ConstDef(c) = item else { continue };
+            if self.consts.contains_key(&c.name.node) {
+                return Err(CompileError::type_error(
+                    format!("duplicate constant `{}`", c.name.node),
+                    c.name.span,
+                ));
+            }
+            self.consts.insert(c.name.node.clone(), (c.ty.node.clone(), None));
+        }
+        for (name, value) in consts::eval_consts(program)? {
+            if let Some(entry) = self.consts.get_mut(&name) {
+                entry.1 = Some(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace `[T; NAME]` array lengths with the value of the constant `NAME`
+    fn resolve_const_lengths(&self, ty: &Type, span: Span) -> Result<Type> {
+        let resolve = |t: &Type| self.resolve_const_lengths(t, span).map(Box::new);
+        Ok(match ty {
+            Type::ArrayConst(elem, name) => {
+                let len = match self.consts.get(name) {
+                    Some((_, Some(consts::ConstValue::Int(n)))) => usize::try_from(*n).map_err(|_| {
+                        CompileError::type_error(format!("array length `{}` is negative ({})", name, n), span)
+                    })?,
+                    // The initializer error is already reported; keep the length symbolic
+                    Some((Type::I32 | Type::I64 | Type::U32 | Type::U64, None)) => return Ok(Type::ArrayConst(resolve(elem)?, name.clone())),
+                    Some(_) => {
+                        return Err(CompileError::type_error(
+                            format!("array length `{}` is not an integer constant", name),
+                            span,
+                        ));
+                    }
+                    None => {
+                        return Err(CompileError::type_error(
+                            format!("undefined constant `{}` in array length", name),
+                            span,
+                        ));
+                    }
+                };
+                Type::Array(resolve(elem)?, len)
+            }
+            Type::Array(elem, len) => Type::Array(resolve(elem)?, *len),
+            Type::Ref(inner) => Type::Ref(resolve(inner)?),
+            Type::RefMut(inner) => Type::RefMut(resolve(inner)?),
+            Type::Nullable(inner) => Type::Nullable(resolve(inner)?),
+            Type::Range(inner) => Type::Range(resolve(inner)?),
+            Type::Generic { name, type_args } => Type::Generic {
+                name: name.clone(),
+                type_args: type_args.iter().map(|a| resolve(a)).collect::<Result<_>>()?,
+            },
+            Type::Tuple(elements) => Type::Tuple(elements.iter().map(|e| resolve(e)).collect::<Result<_>>()?),
+            Type::Fn { params, ret } => Type::Fn {
+                params: params.iter().map(|p| resolve(p)).collect::<Result<_>>()?,
+                ret: resolve(ret)?,
+            },
+            _ => ty.clone(),
+        })
+    }
+
     /// v0.50.6: Resolve type alias
     /// If the type is a named type that's a type alias, expand it to the target type.
     /// Non-generic type aliases are expanded recursively.
@@ -1104,6 +1498,8 @@ impl TypeChecker {
         // Clear environment and add parameters
         self.env.clear();
         self.type_param_env.clear();
+        self.current_fn = Some(f.name.node.clone());
+        self.entry_names = f.params.iter().map(|p| p.name.node.clone()).collect();
 
         // v0.49: Reset binding tracker and push function scope
         self.binding_tracker = BindingTracker::new();
@@ -1123,10 +1519,11 @@ impl TypeChecker {
 
         // v0.15: Convert Named types that match type params to TypeVar for env
         for param in &f.params {
+            let param_ty = self.resolve_const_lengths(&param.ty.node, param.ty.span)?;
             let resolved_ty = if f.type_params.is_empty() {
-                param.ty.node.clone()
+                param_ty
             } else {
-                self.resolve_type_vars(&param.ty.node, &type_param_names)
+                self.resolve_type_vars(&param_ty, &type_param_names)
             };
             self.env.insert(param.name.node.clone(), resolved_ty);
             // v0.49: Track parameter binding for unused detection
@@ -1135,10 +1532,11 @@ impl TypeChecker {
 
         // Set current return type for `ret` keyword
         // v0.15: Resolve type vars in return type too
+        let ret_ty = self.resolve_const_lengths(&f.ret_ty.node, f.ret_ty.span)?;
         let resolved_ret_ty = if f.type_params.is_empty() {
-            f.ret_ty.node.clone()
+            ret_ty
         } else {
-            self.resolve_type_vars(&f.ret_ty.node, &type_param_names)
+            self.resolve_type_vars(&ret_ty, &type_param_names)
         };
         self.current_ret_ty = Some(resolved_ret_ty.clone());
 
@@ -1213,6 +1611,73 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Type check a default method body in a trait definition.
+    /// `Self` is treated as a type variable bounded by the trait itself, so the
+    /// body may only rely on the trait's own methods.
+    fn check_trait_default(&mut self, trait_name: &str, m: &TraitMethod, body: &Spanned<Expr>) -> Result<()> {
+        self.env.clear();
+        self.type_param_env.clear();
+        self.type_param_env.insert("Self".to_string(), vec![trait_name.to_string()]);
+
+        self.binding_tracker = BindingTracker::new();
+        self.binding_tracker.push_scope();
+
+        let self_ty = Type::TypeVar("Self".to_string());
+        for param in &m.params {
+            self.mark_type_names_used(&param.ty.node);
+            self.env.insert(param.name.node.clone(), self.substitute_self(&param.ty.node, &self_ty));
+            self.binding_tracker.bind(param.name.node.clone(), param.name.span);
+        }
+        self.mark_type_names_used(&m.ret_ty.node);
+
+        let ret_ty = self.substitute_self(&m.ret_ty.node, &self_ty);
+        self.current_ret_ty = Some(ret_ty.clone());
+
+        let body_ty = self.infer(&body.node, body.span)?;
+        self.unify(&ret_ty, &body_ty, body.span)?;
+
+        let (unused, _unused_mut) = self.binding_tracker.pop_scope();
+        for (unused_name, unused_span) in unused {
+            self.add_warning(CompileWarning::unused_binding(unused_name, unused_span));
+        }
+
+        self.current_ret_ty = None;
+        self.type_param_env.clear();
+        Ok(())
+    }
+
+    /// Check each field default against the field's type. Defaults are
+    /// evaluated on their own at every construction, so they may use
+    /// constants and functions but not the struct's other fields.
+    fn check_struct_defaults(&mut self, s: &StructDef) -> Result<()> {
+        if s.fields.iter().all(|f| f.default.is_none()) {
+            return Ok(());
+        }
+        self.env.clear();
+        self.type_param_env.clear();
+        self.binding_tracker = BindingTracker::new();
+        self.binding_tracker.push_scope();
+        self.default_fields = s.fields.iter().map(|f| f.name.node.clone()).collect();
+
+        let type_param_names: Vec<_> = s.type_params.iter().map(|tp| tp.name.as_str()).collect();
+        let mut result = Ok(());
+        for field in &s.fields {
+            let Some(default) = &field.default else { continue };
+            result = self.resolve_const_lengths(&field.ty.node, field.ty.span).and_then(|ty| {
+                let field_ty = self.resolve_type_vars(&ty, &type_param_names);
+                let default_ty = self.infer(&default.node, default.span)?;
+                self.unify(&field_ty, &default_ty, default.span)
+            });
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.default_fields.clear();
+        self.binding_tracker.pop_scope();
+        result
+    }
+
     /// v0.31: Validate module header exports (RFC-0002)
     /// Ensures all exported symbols are actually defined in the module
     fn validate_module_exports(&self, header: &ModuleHeader, program: &Program) -> Result<()> {
@@ -1241,6 +1706,9 @@ impl TypeChecker {
                 Item::TypeAlias(t) => {
                     defined_symbols.insert(&t.name.node);
                 }
+                Item::ConstDef(c) => {
+                    defined_symbols.insert(&c.name.node);
+                }
             }
         }
 
@@ -1260,8 +1728,159 @@ impl TypeChecker {
         Ok(())
     }
 
-    /// Infer expression type
+    /// Type check a loop body with the loop's label in scope
+    fn infer_loop_body(&mut self, label: &Option<String>, body: &Spanned<Expr>) -> Result<Type> {
+        self.loop_labels.push(label.clone());
+        let result = self.infer(&body.node, body.span);
+        self.loop_labels.pop();
+        result
+    }
+
+    /// A `break 'label` or `continue 'label` must name an enclosing loop
+    fn check_loop_label(&self, label: Option<&str>, span: Span) -> Result<()> {
+        let Some(label) = label else {
+            return Ok(());
+        };
+        let in_scope: Vec<&str> = self.loop_labels.iter().flatten().map(|s| s.as_str()).collect();
+        if in_scope.contains(&label) {
+            return Ok(());
+        }
+        let suggestion = find_similar_name(label, &in_scope, 2);
+        let hint = match suggestion {
+            Some(name) => format!("\n  hint: did you mean `'{}`?", name),
+            None => String::new(),
+        };
+        Err(CompileError::type_error(
+            format!("undeclared loop label: `'{}`{}", label, hint),
+            span,
+        ))
+    }
+
+    /// Type a `let` binding and bring it into scope (in `env` and in a new
+    /// binding-tracker scope, ended by `close_let_scope`)
+    fn open_let_scope(
+        &mut self,
+        name: &str,
+        mutable: bool,
+        ty: Option<&Spanned<Type>>,
+        value: &Spanned<Expr>,
+        span: Span,
+    ) -> Result<()> {
+        let value_ty = self.infer(&value.node, value.span)?;
+
+        let ann_ty = match ty {
+            Some(ann_ty) => {
+                // v0.75: Mark type names in annotation as used
+                self.mark_type_names_used(&ann_ty.node);
+                let resolved = self.resolve_const_lengths(&ann_ty.node, ann_ty.span)?;
+                self.unify(&resolved, &value_ty, value.span)?;
+                self.check_refinement(&resolved, value, &HashMap::new(), &format!("`{}`", name))?;
+                Some(resolved)
+            }
+            None => None,
+        };
+
+        // v0.48: Track binding for unused detection
+        // v0.52: Track mutability for unused-mut detection
+        self.binding_tracker.push_scope();
+
+        // v0.79: Check for shadow binding before adding
+        if let Some(original_span) = self.binding_tracker.find_shadow(name) {
+            self.add_warning(CompileWarning::shadow_binding(name, span, original_span));
+        }
+
+        self.binding_tracker.bind_with_mutability(name.to_string(), span, mutable);
+
+        // An annotation pins down type arguments the value left open
+        // (e.g. `let m: HashMap<String, i64> = HashMap::new();`)
+        let binding_ty = match ann_ty {
+            Some(ann_ty) if self.has_unbound_type_var(&value_ty) => ann_ty,
+            _ => value_ty,
+        };
+        self.env.insert(name.to_string(), binding_ty);
+        Ok(())
+    }
+
+    /// Check a value entering refined type `ty`: a constraint that evaluates
+    /// to `false` is an error, one that cannot be evaluated here becomes an
+    /// obligation for the contract verifier
+    fn check_refinement(
+        &mut self,
+        ty: &Type,
+        value: &Spanned<Expr>,
+        params: &HashMap<&str, &Spanned<Expr>>,
+        target: &str,
+    ) -> Result<()> {
+        let Type::Refined { constraints, .. } = ty else {
+            return Ok(());
+        };
+        for constraint in constraints {
+            let Some(condition) = refinement::instantiate(constraint, value, params) else {
+                continue;
+            };
+            let lookup = |name: &str| match self.env.contains_key(name) {
+                true => None,
+                false => self.consts.get(name).and_then(|(_, value)| value.clone()),
+            };
+            match consts::eval_closed(&condition, &lookup) {
+                Some(consts::ConstValue::Bool(true)) => {}
+                Some(_) => {
+                    return Err(CompileError::type_error(
+                        format!(
+                            "`{}` violates refinement `{}` of {}",
+                            crate::fmt::format_expr(&value.node),
+                            crate::fmt::format_expr(&constraint.node),
+                            target
+                        ),
+                        value.span,
+                    ));
+                }
+                None => {
+                    if let Some(function) = &self.current_fn {
+                        self.refinement_obligations.push(RefinementObligation {
+                            function: function.clone(),
+                            target: target.to_string(),
+                            condition,
+                            span: value.span,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// End the binding scope of a `let`, warning about unused bindings
+    fn close_let_scope(&mut self) {
+        // v0.48: Check for unused bindings and emit warnings
+        // v0.52: Also check for mutable-but-never-mutated
+        let (unused, unused_mut) = self.binding_tracker.pop_scope();
+        for (unused_name, unused_span) in unused {
+            self.add_warning(CompileWarning::unused_binding(unused_name, unused_span));
+        }
+        for (name, span) in unused_mut {
+            self.add_warning(CompileWarning::unused_mut(name, span));
+        }
+    }
+
+    /// Infer the type of an expression outside any function, against the
+    /// items registered by an earlier `check_program` (REPL `:type`)
+    pub fn infer_standalone(&mut self, expr: &Spanned<Expr>) -> Result<Type> {
+        self.env.clear();
+        self.current_ret_ty = None;
+        self.infer(&expr.node, expr.span)
+    }
+
+    /// Infer expression type, recording it by span when enabled
     fn infer(&mut self, expr: &Expr, span: Span) -> Result<Type> {
+        let ty = self.infer_expr(expr, span)?;
+        if let Some(span_types) = &mut self.span_types {
+            span_types.push((span, ty.clone()));
+        }
+        Ok(ty)
+    }
+
+    fn infer_expr(&mut self, expr: &Expr, span: Span) -> Result<Type> {
         match expr {
             Expr::IntLit(_) => Ok(Type::I64),
             Expr::FloatLit(_) => Ok(Type::F64),
@@ -1271,14 +1890,33 @@ impl TypeChecker {
             Expr::CharLit(_) => Ok(Type::Char),
             Expr::Unit => Ok(Type::Unit),
 
+            Expr::Ret if self.in_old => Err(CompileError::type_error(
+                "`old()` refers to `ret`, which does not exist on entry to the function",
+                span,
+            )),
             Expr::Ret => self.current_ret_ty.clone().ok_or_else(|| {
                 CompileError::type_error("'ret' used outside function", span)
             }),
 
+            Expr::Var(name) if self.in_old && self.env.contains_key(name) && !self.entry_names.contains(name) => {
+                Err(CompileError::type_error(
+                    format!("`old()` refers to `{}`, which does not exist on entry to the function", name),
+                    span,
+                ))
+            }
+
             Expr::Var(name) => {
                 // v0.48: Mark variable as used for unused binding detection
                 self.binding_tracker.mark_used(name);
-                self.env.get(name).cloned().ok_or_else(|| {
+                let local_or_const = self.env.get(name)
+                    .or_else(|| self.consts.get(name).map(|(ty, _)| ty));
+                local_or_const.cloned().ok_or_else(|| {
+                    if self.default_fields.contains(name) {
+                        return CompileError::type_error(
+                            format!("field default cannot refer to field `{}`", name),
+                            span,
+                        );
+                    }
                     // v0.62: Suggest similar variable names
                     let var_names: Vec<&str> = self.env.keys().map(|s| s.as_str()).collect();
                     let suggestion = find_similar_name(name, &var_names, 2);
@@ -1292,7 +1930,13 @@ impl TypeChecker {
             Expr::Binary { left, op, right } => {
                 let left_ty = self.infer(&left.node, left.span)?;
                 let right_ty = self.infer(&right.node, right.span)?;
-                self.check_binary_op(*op, &left_ty, &right_ty, span)
+                let ty = self.check_binary_op(*op, &left_ty, &right_ty, span)?;
+                if matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul)
+                    && matches!(ty, Type::I32 | Type::U32 | Type::U64)
+                {
+                    self.int_op_types.insert(span, ty.clone());
+                }
+                Ok(ty)
             }
 
             Expr::Unary { op, expr } => {
@@ -1310,6 +1954,11 @@ impl TypeChecker {
 
                 let then_ty = self.infer(&then_branch.node, then_branch.span)?;
                 let else_ty = self.infer(&else_branch.node, else_branch.span)?;
+
+                // A diverging `then` leaves the `else` to decide the type
+                if matches!(then_ty, Type::Never) {
+                    return Ok(else_ty);
+                }
                 self.unify(&then_ty, &else_ty, else_branch.span)?;
 
                 Ok(then_ty)
@@ -1322,42 +1971,22 @@ impl TypeChecker {
                 value,
                 body,
             } => {
-                let value_ty = self.infer(&value.node, value.span)?;
-
-                if let Some(ann_ty) = ty {
-                    // v0.75: Mark type names in annotation as used
-                    self.mark_type_names_used(&ann_ty.node);
-                    self.unify(&ann_ty.node, &value_ty, value.span)?;
-                }
-
-                // v0.48: Track binding for unused detection
-                // v0.52: Track mutability for unused-mut detection
-                self.binding_tracker.push_scope();
-
-                // v0.79: Check for shadow binding before adding
-                if let Some(original_span) = self.binding_tracker.find_shadow(name) {
-                    self.add_warning(CompileWarning::shadow_binding(name, span, original_span));
-                }
-
-                self.binding_tracker.bind_with_mutability(name.clone(), span, *mutable);
-
-                self.env.insert(name.clone(), value_ty);
+                self.open_let_scope(name, *mutable, ty.as_ref(), value, span)?;
                 let result = self.infer(&body.node, body.span)?;
-
-                // v0.48: Check for unused bindings and emit warnings
-                // v0.52: Also check for mutable-but-never-mutated
-                let (unused, unused_mut) = self.binding_tracker.pop_scope();
-                for (unused_name, unused_span) in unused {
-                    self.add_warning(CompileWarning::unused_binding(unused_name, unused_span));
-                }
-                for (name, span) in unused_mut {
-                    self.add_warning(CompileWarning::unused_mut(name, span));
-                }
-
+                self.close_let_scope();
                 Ok(result)
             }
 
+            // Only reached outside a block; `Expr::Block` scopes these itself
+            Expr::LetStmt { .. } => Err(CompileError::type_error(
+                "`let` statement outside a block",
+                span,
+            )),
+
             Expr::Assign { name, value } => {
+                if !self.env.contains_key(name) && self.consts.contains_key(name) {
+                    return Err(CompileError::type_error(format!("cannot assign to constant `{}`", name), span));
+                }
                 // Check that variable exists
                 let var_ty = self.env.get(name).cloned().ok_or_else(|| {
                     // v0.62: Suggest similar variable names
@@ -1369,19 +1998,83 @@ impl TypeChecker {
                     )
                 })?;
 
-                // Check that value type matches variable type
+                // Check that value type matches variable type
+                let value_ty = self.infer(&value.node, value.span)?;
+                self.unify(&var_ty, &value_ty, value.span)?;
+
+                // v0.52: Mark variable as mutated for unused-mut detection
+                self.binding_tracker.mark_mutated(name);
+
+                // Assignment returns unit
+                Ok(Type::Unit)
+            }
+
+            Expr::IndexAssign { array, index, value } => {
+                // Only elements of a variable's array can be updated in place
+                let Some(root) = array.node.place_root() else {
+                    return Err(CompileError::type_error(
+                        "cannot assign to an element of this expression; index a variable instead",
+                        array.span,
+                    ));
+                };
+                if !self.env.contains_key(root) && self.consts.contains_key(root) {
+                    return Err(CompileError::type_error(format!("cannot assign to constant `{}`", root), array.span));
+                }
+
+                let array_ty = self.infer(&array.node, array.span)?;
+                let elem_ty = match array_ty {
+                    Type::Array(elem_ty, _) => *elem_ty,
+                    other => {
+                        return Err(CompileError::type_error(
+                            format!("cannot assign to an element of type: {}", other),
+                            array.span,
+                        ));
+                    }
+                };
+
+                let index_ty = self.infer(&index.node, index.span)?;
+                match index_ty.base_type() {
+                    Type::I32 | Type::I64 | Type::U32 | Type::U64 => {}
+                    _ => return Err(CompileError::type_error(format!("Array index must be integer, got: {}", index_ty), index.span)),
+                }
+
                 let value_ty = self.infer(&value.node, value.span)?;
-                self.unify(&var_ty, &value_ty, value.span)?;
+                self.unify(&elem_ty, &value_ty, value.span)?;
 
-                // v0.52: Mark variable as mutated for unused-mut detection
-                self.binding_tracker.mark_mutated(name);
+                self.binding_tracker.mark_mutated(root);
+                Ok(Type::Unit)
+            }
 
-                // Assignment returns unit
+            Expr::FieldAssign { object, field, value } => {
+                // Fields are updated in place, so the variable must be `mut`
+                let Some(root) = object.node.place_root() else {
+                    return Err(CompileError::type_error(
+                        "cannot assign to a field of this expression; use a variable instead",
+                        object.span,
+                    ));
+                };
+                if !self.env.contains_key(root) && self.consts.contains_key(root) {
+                    return Err(CompileError::type_error(format!("cannot assign to constant `{}`", root), object.span));
+                }
+                if self.binding_tracker.is_mutable(root) == Some(false) {
+                    return Err(CompileError::type_error(
+                        format!("cannot assign to field `{}`: `{}` is not declared `mut`", field.node, root),
+                        field.span,
+                    ));
+                }
+
+                // Typed like reading the field, with the same unknown-field errors
+                let target = Expr::FieldAccess { expr: object.clone(), field: field.clone() };
+                let field_ty = self.infer(&target, Span::new(object.span.start, field.span.end))?;
+                let value_ty = self.infer(&value.node, value.span)?;
+                self.unify(&field_ty, &value_ty, value.span)?;
+
+                self.binding_tracker.mark_mutated(root);
                 Ok(Type::Unit)
             }
 
             // v0.37: Include invariant type checking
-            Expr::While { cond, invariant, body } => {
+            Expr::While { label, cond, invariant, body } => {
                 // Condition must be bool
                 let cond_ty = self.infer(&cond.node, cond.span)?;
                 self.unify(&Type::Bool, &cond_ty, cond.span)?;
@@ -1393,7 +2086,7 @@ impl TypeChecker {
                 }
 
                 // Type check body (result is discarded)
-                let _ = self.infer(&body.node, body.span)?;
+                let _ = self.infer_loop_body(label, body)?;
 
                 // While returns unit
                 Ok(Type::Unit)
@@ -1417,7 +2110,7 @@ impl TypeChecker {
             }
 
             // v0.5 Phase 3: For loop
-            Expr::For { var, iter, body } => {
+            Expr::For { label, var, iter, body } => {
                 let iter_ty = self.infer(&iter.node, iter.span)?;
 
                 // Iterator must be a Range type
@@ -1435,7 +2128,7 @@ impl TypeChecker {
                 self.env.insert(var.clone(), elem_ty);
 
                 // Type check body (result is discarded)
-                let _ = self.infer(&body.node, body.span)?;
+                let _ = self.infer_loop_body(label, body)?;
 
                 // For returns unit
                 Ok(Type::Unit)
@@ -1472,6 +2165,23 @@ impl TypeChecker {
                     return Ok(*ret_ty);
                 }
 
+                // assert_eq/assert_ne accept any pair of same-typed primitives
+                if matches!(func.as_str(), "assert_eq" | "assert_ne") && !self.functions.contains_key(func) {
+                    return self.check_assert_cmp(func, args, span);
+                }
+
+                // Builtins over several types, unless redefined: print/println
+                // take any printable values, min/max/abs any numeric type
+                if matches!(func.as_str(), "print" | "println" | "min" | "max" | "abs")
+                    && !self.function_spans.contains_key(func)
+                    && !self.imported_names.contains(func)
+                {
+                    return match func.as_str() {
+                        "print" | "println" => self.check_print(func, args),
+                        _ => self.check_numeric_builtin(func, args, span),
+                    };
+                }
+
                 // v0.15: Try non-generic functions
                 if let Some((param_tys, ret_ty)) = self.functions.get(func).cloned() {
                     if args.len() != param_tys.len() {
@@ -1490,6 +2200,15 @@ impl TypeChecker {
                         self.unify(param_ty, &arg_ty, arg.span)?;
                     }
 
+                    if let Some(names) = self.param_names.get(func).cloned() {
+                        let by_name: HashMap<&str, &Spanned<Expr>> =
+                            names.iter().map(String::as_str).zip(args).collect();
+                        for ((name, arg), param_ty) in names.iter().zip(args).zip(&param_tys) {
+                            let target = format!("parameter `{}` in call to `{}`", name, func);
+                            self.check_refinement(param_ty, arg, &by_name, &target)?;
+                        }
+                    }
+
                     return Ok(ret_ty);
                 }
 
@@ -1543,6 +2262,13 @@ impl TypeChecker {
                         return Err(CompileError::type_error(msg, span));
                     }
 
+                    // Check trait bounds against the inferred type arguments
+                    for tp in &type_params {
+                        if let Some(concrete) = type_subst.get(&tp.name) {
+                            self.check_trait_bounds(tp, concrete, span)?;
+                        }
+                    }
+
                     // Substitute type parameters in return type
                     let instantiated_ret_ty = self.substitute_type(&ret_ty, &type_subst);
                     return Ok(instantiated_ret_ty);
@@ -1572,13 +2298,31 @@ impl TypeChecker {
                 let mut last_ty = Type::Unit;
                 let mut diverged = false;
                 let mut diverge_span: Option<Span> = None;
+                // Statement-form lets stay in scope until the end of the block;
+                // the bindings they shadow come back after it
+                let mut shadowed = Vec::new();
 
                 for expr in exprs {
                     // v0.53: Check for unreachable code after divergent expression
                     if diverged {
                         self.add_warning(CompileWarning::unreachable_code(expr.span));
                         // Still type-check for error reporting, but don't update last_ty
-                        let _ = self.infer(&expr.node, expr.span);
+                        if let Expr::LetStmt { name, mutable, ty, value } = &expr.node {
+                            let outer = self.env.get(name).cloned();
+                            if self.open_let_scope(name, *mutable, ty.as_ref(), value, expr.span).is_ok() {
+                                shadowed.push((name, outer));
+                            }
+                        } else {
+                            let _ = self.infer(&expr.node, expr.span);
+                        }
+                        continue;
+                    }
+
+                    if let Expr::LetStmt { name, mutable, ty, value } = &expr.node {
+                        let outer = self.env.get(name).cloned();
+                        self.open_let_scope(name, *mutable, ty.as_ref(), value, expr.span)?;
+                        shadowed.push((name, outer));
+                        last_ty = Type::Unit;
                         continue;
                     }
 
@@ -1591,6 +2335,14 @@ impl TypeChecker {
                     }
                 }
 
+                for (name, outer) in shadowed.into_iter().rev() {
+                    self.close_let_scope();
+                    match outer {
+                        Some(ty) => self.env.insert(name.clone(), ty),
+                        None => self.env.remove(name),
+                    };
+                }
+
                 // If block diverged, the type is Never (unless we want last_ty for partial analysis)
                 if diverged && diverge_span.is_some() {
                     Ok(Type::Never)
@@ -1600,9 +2352,30 @@ impl TypeChecker {
             }
 
             // v0.5: Struct and Enum expressions
-            Expr::StructInit { name, fields } => {
+            Expr::StructInit { name, fields, base } => {
                 // v0.74: Mark imported struct as used
                 self.mark_name_used(name);
+                // `..base` must be a value of the same struct
+                let base_ty = match base {
+                    Some(base) => {
+                        let base_ty = self.infer(&base.node, base.span)?;
+                        let same_struct = match &base_ty {
+                            Type::Named(n) | Type::Struct { name: n, .. } | Type::Generic { name: n, .. } => n == name,
+                            _ => false,
+                        };
+                        if !same_struct {
+                            return Err(CompileError::type_error(
+                                format!(
+                                    "struct update base must be a `{}`, found `{}`",
+                                    name, base_ty
+                                ),
+                                base.span,
+                            ));
+                        }
+                        Some(base_ty)
+                    }
+                    None => None,
+                };
                 // v0.16: First try non-generic structs
                 if let Some(struct_fields) = self.structs.get(name).cloned() {
                     // Check that all required fields are provided
@@ -1613,6 +2386,7 @@ impl TypeChecker {
                                 let expr_ty = self.infer(&expr.node, expr.span)?;
                                 self.unify(field_ty, &expr_ty, expr.span)?;
                             }
+                            None if base.is_some() || self.has_default(name, field_name) => {}
                             None => {
                                 return Err(CompileError::type_error(
                                     format!("missing field: {field_name}"),
@@ -1628,8 +2402,15 @@ impl TypeChecker {
                 if let Some((type_params, struct_fields)) = self.generic_structs.get(name).cloned() {
                     let type_param_names: Vec<_> = type_params.iter().map(|tp| tp.name.as_str()).collect();
 
-                    // Infer type arguments from field values
+                    // Infer type arguments from field values (and the base)
                     let mut type_subst: HashMap<String, Type> = HashMap::new();
+                    if let (Some(base), Some(base_ty)) = (base, &base_ty) {
+                        let generic_ty = Type::Generic {
+                            name: name.clone(),
+                            type_args: type_params.iter().map(|tp| Box::new(Type::TypeVar(tp.name.clone()))).collect(),
+                        };
+                        self.infer_type_args(&generic_ty, base_ty, &mut type_subst, base.span)?;
+                    }
                     for (field_name, field_ty) in &struct_fields {
                         let provided = fields.iter().find(|(n, _)| &n.node == field_name);
                         match provided {
@@ -1638,6 +2419,7 @@ impl TypeChecker {
                                 let resolved_field_ty = self.resolve_type_vars(field_ty, &type_param_names);
                                 self.infer_type_args(&resolved_field_ty, &expr_ty, &mut type_subst, expr.span)?;
                             }
+                            None if base.is_some() || self.has_default(name, field_name) => {}
                             None => {
                                 return Err(CompileError::type_error(
                                     format!("missing field: {field_name}"),
@@ -1758,6 +2540,30 @@ impl TypeChecker {
                 }
             }
 
+            // HashMap<K, V> constructor; K and V come from the annotation or parameter type
+            Expr::EnumVariant { enum_name, variant, args }
+                if enum_name == "HashMap"
+                    && !self.enums.contains_key(enum_name)
+                    && !self.generic_enums.contains_key(enum_name) =>
+            {
+                if variant != "new" {
+                    return Err(CompileError::type_error(
+                        format!("unknown associated function `HashMap::{}`", variant),
+                        span,
+                    ));
+                }
+                if !args.is_empty() {
+                    return Err(CompileError::type_error("HashMap::new() takes no arguments", span));
+                }
+                Ok(Type::Generic {
+                    name: "HashMap".to_string(),
+                    type_args: vec![
+                        Box::new(Type::TypeVar("K".to_string())),
+                        Box::new(Type::TypeVar("V".to_string())),
+                    ],
+                })
+            }
+
             Expr::EnumVariant { enum_name, variant, args } => {
                 // v0.74: Mark imported enum as used
                 self.mark_name_used(enum_name);
@@ -1885,8 +2691,10 @@ impl TypeChecker {
                     }
 
                     match &result_ty {
-                        None => result_ty = Some(body_ty),
-                        Some(expected) => self.unify(expected, &body_ty, arm.body.span)?,
+                        Some(expected) if !matches!(expected, Type::Never) => {
+                            self.unify(expected, &body_ty, arm.body.span)?
+                        }
+                        _ => result_ty = Some(body_ty),
                     }
                 }
 
@@ -2009,9 +2817,13 @@ impl TypeChecker {
             }
 
             // v0.2: State references for contracts
-            Expr::StateRef { expr, .. } => {
+            Expr::StateRef { expr, state } => {
                 // The type of a state reference is the same as the underlying expression
-                self.infer(&expr.node, expr.span)
+                let outer = self.in_old;
+                self.in_old |= matches!(state, StateKind::Pre);
+                let ty = self.infer(&expr.node, expr.span);
+                self.in_old = outer;
+                ty
             }
 
             // v0.2: Refinement self-reference (type depends on context)
@@ -2047,8 +2859,19 @@ impl TypeChecker {
                     self.binding_tracker.bind(param.name.node.clone(), param.name.span);
                 }
 
-                // Infer body type
-                let body_ty = self.infer(&body.node, body.span)?;
+                // Infer body type; loops outside the closure are not
+                // targets for its break/continue
+                let outer_loops = std::mem::take(&mut self.loop_labels);
+                // `return` inside the closure leaves the closure, not the
+                // enclosing function
+                let outer_ret = std::mem::replace(
+                    &mut self.current_ret_ty,
+                    ret_ty.as_ref().map(|t| t.node.clone()),
+                );
+                let body_ty = self.infer(&body.node, body.span);
+                self.loop_labels = outer_loops;
+                self.current_ret_ty = outer_ret;
+                let body_ty = body_ty?;
 
                 // Check against explicit return type if provided
                 if let Some(explicit_ret) = ret_ty {
@@ -2081,14 +2904,15 @@ impl TypeChecker {
 
             // v0.36: Additional control flow
             // Loop returns Never (infinite loop or break)
-            Expr::Loop { body } => {
+            Expr::Loop { label, body } => {
                 // Type check the body but return Never
-                self.infer(&body.node, body.span)?;
+                self.infer_loop_body(label, body)?;
                 Ok(Type::Never)
             }
 
             // Break returns Never (control flow transfer)
-            Expr::Break { value } => {
+            Expr::Break { label, value } => {
+                self.check_loop_label(label.as_deref(), span)?;
                 if let Some(v) = value {
                     self.infer(&v.node, v.span)?;
                 }
@@ -2096,14 +2920,20 @@ impl TypeChecker {
             }
 
             // Continue returns Never (control flow transfer)
-            Expr::Continue => {
+            Expr::Continue { label } => {
+                self.check_loop_label(label.as_deref(), span)?;
                 Ok(Type::Never)
             }
 
-            // Return returns Never (control flow transfer)
+            // Return returns Never (control flow transfer); the value must
+            // match the enclosing function's return type
             Expr::Return { value } => {
-                if let Some(v) = value {
-                    self.infer(&v.node, v.span)?;
+                let (value_ty, value_span) = match value {
+                    Some(v) => (self.infer(&v.node, v.span)?, v.span),
+                    None => (Type::Unit, span),
+                };
+                if let Some(ret_ty) = self.current_ret_ty.clone() {
+                    self.unify(&ret_ty, &value_ty, value_span)?;
                 }
                 Ok(Type::Never)
             }
@@ -2113,9 +2943,14 @@ impl TypeChecker {
             Expr::Forall { var, ty, body } => {
                 // Add bound variable to environment for body type checking
                 self.env.insert(var.node.clone(), ty.node.clone());
-                let body_ty = self.infer(&body.node, body.span)?;
+                let logical = self.entry_names.insert(var.node.clone());
+                let body_ty = self.infer(&body.node, body.span);
                 // Remove bound variable from environment
                 self.env.remove(&var.node);
+                if logical {
+                    self.entry_names.remove(&var.node);
+                }
+                let body_ty = body_ty?;
                 // Body must be a boolean expression
                 self.unify(&Type::Bool, &body_ty, body.span)?;
                 Ok(Type::Bool)
@@ -2125,9 +2960,14 @@ impl TypeChecker {
             Expr::Exists { var, ty, body } => {
                 // Add bound variable to environment for body type checking
                 self.env.insert(var.node.clone(), ty.node.clone());
-                let body_ty = self.infer(&body.node, body.span)?;
+                let logical = self.entry_names.insert(var.node.clone());
+                let body_ty = self.infer(&body.node, body.span);
                 // Remove bound variable from environment
                 self.env.remove(&var.node);
+                if logical {
+                    self.entry_names.remove(&var.node);
+                }
+                let body_ty = body_ty?;
                 // Body must be a boolean expression
                 self.unify(&Type::Bool, &body_ty, body.span)?;
                 Ok(Type::Bool)
@@ -2139,13 +2979,22 @@ impl TypeChecker {
                 let src_ty = self.infer(&expr.node, expr.span)?;
                 let target_ty = ty.node.clone();
 
-                // Validate cast is allowed (numeric types only)
-                let src_numeric = matches!(&src_ty, Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::Bool);
-                let tgt_numeric = matches!(&target_ty, Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::Bool);
+                // Validate cast is allowed: numeric types among themselves,
+                // and `char` to or from an integer type (its codepoint)
+                let numeric = |ty: &Type| matches!(ty, Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::Bool);
+                let integer_or_char = |ty: &Type| matches!(ty, Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::Char);
+                let allowed = if matches!(src_ty, Type::Char) || matches!(target_ty, Type::Char) {
+                    integer_or_char(&src_ty) && integer_or_char(&target_ty)
+                } else {
+                    numeric(&src_ty) && numeric(&target_ty)
+                };
 
-                if !src_numeric || !tgt_numeric {
+                if !allowed {
                     return Err(CompileError::type_error(
-                        format!("cannot cast {:?} to {:?}: only numeric types are supported", src_ty, target_ty),
+                        format!(
+                            "cannot cast {} to {}: only numeric types, and char to or from integers, are supported",
+                            src_ty, target_ty
+                        ),
                         span,
                     ));
                 }
@@ -2247,9 +3096,22 @@ impl TypeChecker {
                 let err_ty = type_args.get(1).map(|t| t.as_ref().clone());
                 self.check_result_method(method, args, ok_ty, err_ty, span)
             }
+            // HashMap<K, V> methods
+            Type::Generic { name, type_args } if name == "HashMap" && type_args.len() == 2 => {
+                let key_ty = type_args[0].as_ref().clone();
+                let value_ty = type_args[1].as_ref().clone();
+                self.check_hashmap_method(method, args, key_ty, value_ty, span)
+            }
             // v0.20.1: For other types, look up trait methods
+            // (a bounded type parameter resolves through its traits' signatures)
             _ => {
-                if let Some((param_types, ret_type)) = self.lookup_trait_method(receiver_ty, method) {
+                let method_sig = match receiver_ty {
+                    Type::TypeVar(name) => self.lookup_bound_method(name, method),
+                    _ => self
+                        .lookup_inherent_method(receiver_ty, method)
+                        .or_else(|| self.lookup_trait_method(receiver_ty, method)),
+                };
+                if let Some((param_types, ret_type)) = method_sig {
                     // Check argument count (excluding self)
                     if args.len() != param_types.len() {
                         return Err(CompileError::type_error(
@@ -2263,9 +3125,26 @@ impl TypeChecker {
                         self.unify(expected_ty, &arg_ty, args[i].span)?;
                     }
                     Ok(ret_type)
+                } else if method == "to_debug_string" && self.supports_derive(receiver_ty, DeriveTrait::Debug, &[]) {
+                    if !args.is_empty() {
+                        return Err(CompileError::type_error("to_debug_string() takes no arguments", span));
+                    }
+                    Ok(Type::String)
+                } else if method == "hash" && self.supports_derive(receiver_ty, DeriveTrait::Hash, &[]) {
+                    if !args.is_empty() {
+                        return Err(CompileError::type_error("hash() takes no arguments", span));
+                    }
+                    Ok(Type::I64)
                 } else {
+                    let hint = match method {
+                        "to_debug_string" => self.missing_derive_hint(receiver_ty, DeriveTrait::Debug),
+                        "hash" => self.missing_derive_hint(receiver_ty, DeriveTrait::Hash),
+                        "eq" => self.missing_derive_hint(receiver_ty, DeriveTrait::Eq),
+                        "show" => self.missing_derive_hint(receiver_ty, DeriveTrait::Show),
+                        _ => String::new(),
+                    };
                     Err(CompileError::type_error(
-                        format!("type {} has no method '{}'", receiver_ty, method),
+                        format!("type {} has no method '{}'{}", receiver_ty, method, hint),
                         span,
                     ))
                 }
@@ -2355,6 +3234,173 @@ impl TypeChecker {
         }
     }
 
+    /// Check `assert_eq(a, b)` / `assert_ne(a, b)`: both operands must share
+    /// one of the primitive types the interpreter can render and compare
+    fn check_assert_cmp(&mut self, func: &str, args: &[Spanned<Expr>], span: Span) -> Result<Type> {
+        if args.len() != 2 {
+            return Err(CompileError::type_error(
+                format!("expected 2 arguments, got {}", args.len()),
+                span,
+            ));
+        }
+        let left_ty = self.infer(&args[0].node, args[0].span)?;
+        let right_ty = self.infer(&args[1].node, args[1].span)?;
+        self.unify(&left_ty, &right_ty, args[1].span)?;
+
+        let base_ty = match self.resolve_type_alias(&left_ty) {
+            Type::Refined { base, .. } => *base,
+            ty => ty,
+        };
+        match base_ty {
+            Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64
+            | Type::Bool | Type::String | Type::Char | Type::TypeVar(_) => Ok(Type::Unit),
+            _ => Err(CompileError::type_error(
+                format!("{}() requires i64, f64, bool, String or char operands, got {}", func, left_ty),
+                args[0].span,
+            )),
+        }
+    }
+
+    /// Check a variadic `print`/`println` call: every argument must be a
+    /// number, bool, String or char
+    fn check_print(&mut self, func: &str, args: &[Spanned<Expr>]) -> Result<Type> {
+        for arg in args {
+            let arg_ty = self.infer(&arg.node, arg.span)?;
+            let base_ty = match self.resolve_type_alias(&arg_ty) {
+                Type::Refined { base, .. } => *base,
+                ty => ty,
+            };
+            if !matches!(
+                base_ty,
+                Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64
+                | Type::Bool | Type::String | Type::Char | Type::TypeVar(_)
+            ) {
+                return Err(CompileError::type_error(
+                    format!("{}() requires i64, f64, bool, String or char arguments, got {}", func, arg_ty),
+                    arg.span,
+                ));
+            }
+        }
+        Ok(Type::Unit)
+    }
+
+    /// Check `min(a, b)`, `max(a, b)` or `abs(x)`: the arguments share one of
+    /// i32, i64, u32, u64 or f64, which is also the result type. An integer
+    /// literal takes the type of the other argument. `abs` of an unsigned
+    /// type is rejected.
+    fn check_numeric_builtin(&mut self, func: &str, args: &[Spanned<Expr>], span: Span) -> Result<Type> {
+        let arity = if func == "abs" { 1 } else { 2 };
+        if args.len() != arity {
+            return Err(CompileError::type_error(
+                format!("expected {} arguments, got {}", arity, args.len()),
+                span,
+            ));
+        }
+
+        let mut result: Option<Type> = None;
+        for arg in args {
+            let arg_ty = self.infer(&arg.node, arg.span)?;
+            let base_ty = match self.resolve_type_alias(&arg_ty) {
+                Type::Refined { base, .. } => *base,
+                ty => ty,
+            };
+            if !matches!(base_ty, Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::TypeVar(_)) {
+                return Err(CompileError::type_error(
+                    format!("{}() requires i32, i64, u32, u64 or f64 arguments, got {}", func, arg_ty),
+                    arg.span,
+                ));
+            }
+            result = Some(match result {
+                None => base_ty,
+                // Whichever side the other coerces to, as `let x: u32 = 1` does
+                Some(prev) if self.unify(&prev, &base_ty, arg.span).is_ok() => prev,
+                Some(prev) if self.unify(&base_ty, &prev, arg.span).is_ok() => base_ty,
+                Some(prev) => {
+                    return Err(CompileError::type_error(
+                        format!("argument type mismatch in {}(): {} and {}", func, prev, base_ty),
+                        arg.span,
+                    ));
+                }
+            });
+        }
+
+        let result = result.expect("checked arity");
+        if func == "abs" && matches!(result, Type::U32 | Type::U64) {
+            return Err(CompileError::type_error(
+                format!("abs() is not defined for unsigned type {}", result),
+                args[0].span,
+            ));
+        }
+        Ok(result)
+    }
+
+    /// Check `HashMap<K, V>` method calls. Lookups return `Option<V>` instead
+    /// of the `i64::MIN` sentinel used by the raw `hashmap_*` builtins.
+    fn check_hashmap_method(&mut self, method: &str, args: &[Spanned<Expr>], key_ty: Type, value_ty: Type, span: Span) -> Result<Type> {
+        // Structs and enums need derived `Hash` for the bucket and `Eq` for the lookup
+        let is_hashable = matches!(
+            key_ty,
+            Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::Bool | Type::Char | Type::String | Type::TypeVar(_)
+        ) || (self.supports_derive(&key_ty, DeriveTrait::Hash, &[]) && self.supports_derive(&key_ty, DeriveTrait::Eq, &[]));
+        if !is_hashable {
+            return Err(CompileError::type_error(
+                format!(
+                    "HashMap key type {} is not hashable (expected an integer, bool, char, String, \
+                     or a type deriving Hash and Eq)",
+                    key_ty
+                ),
+                span,
+            ));
+        }
+
+        let expected_args: &[&Type] = match method {
+            "insert" => &[&key_ty, &value_ty],
+            "get" | "contains" | "remove" => &[&key_ty],
+            "len" => &[],
+            _ => {
+                return Err(CompileError::type_error(
+                    format!("unknown method '{}' for HashMap", method),
+                    span,
+                ));
+            }
+        };
+        if args.len() != expected_args.len() {
+            return Err(CompileError::type_error(
+                format!("{}() takes {} argument(s), got {}", method, expected_args.len(), args.len()),
+                span,
+            ));
+        }
+        for (arg, expected) in args.iter().zip(expected_args) {
+            let arg_ty = self.infer(&arg.node, arg.span)?;
+            self.unify(expected, &arg_ty, arg.span)?;
+        }
+
+        match method {
+            // insert/remove return the previous value, get the current one
+            "insert" | "get" | "remove" => Ok(Type::Generic {
+                name: "Option".to_string(),
+                type_args: vec![Box::new(value_ty)],
+            }),
+            "contains" => Ok(Type::Bool),
+            _ => Ok(Type::I64),
+        }
+    }
+
+    /// Whether a type still contains a type variable that is not a type
+    /// parameter of the function being checked
+    fn has_unbound_type_var(&self, ty: &Type) -> bool {
+        match ty {
+            Type::TypeVar(name) => !self.type_param_env.contains_key(name),
+            Type::Generic { type_args, .. } => type_args.iter().any(|t| self.has_unbound_type_var(t)),
+            Type::Ref(inner) | Type::RefMut(inner) | Type::Nullable(inner) | Type::Range(inner) => {
+                self.has_unbound_type_var(inner)
+            }
+            Type::Array(elem, _) => self.has_unbound_type_var(elem),
+            Type::Tuple(elems) => elems.iter().any(|t| self.has_unbound_type_var(t)),
+            _ => false,
+        }
+    }
+
     /// v0.46: Check match exhaustiveness
     /// Returns exhaustiveness result with missing patterns and unreachable arms
     fn check_match_exhaustiveness(
@@ -2403,7 +3449,7 @@ impl TypeChecker {
     /// v0.53: Check if an expression is divergent (never returns normally)
     /// This is used to detect unreachable code after return, break, continue
     fn is_divergent_expr(&self, expr: &Expr) -> bool {
-        matches!(expr, Expr::Return { .. } | Expr::Break { .. } | Expr::Continue)
+        matches!(expr, Expr::Return { .. } | Expr::Break { .. } | Expr::Continue { .. })
     }
 
     /// Check pattern validity
@@ -2802,6 +3848,106 @@ impl TypeChecker {
         }
     }
 
+    /// Reject `@derive(Eq)`, `@derive(Debug)`, `@derive(Hash)` or
+    /// `@derive(Show)` on a struct or enum with a field whose type does not
+    /// support the trait, and `Show` on a generic type. Errors point at the
+    /// derive attribute.
+    fn check_derives(&self, item: &Item) -> Result<()> {
+        let (name, attrs, type_params, fields): (&Spanned<String>, &[Attribute], &[TypeParam], Vec<(String, &Spanned<Type>)>) = match item {
+            Item::StructDef(s) => (
+                &s.name,
+                &s.attributes,
+                &s.type_params,
+                s.fields.iter().map(|f| (format!("field `{}`", f.name.node), &f.ty)).collect(),
+            ),
+            Item::EnumDef(e) => (
+                &e.name,
+                &e.attributes,
+                &e.type_params,
+                e.variants.iter()
+                    .flat_map(|v| v.fields.iter().map(move |f| (format!("variant `{}`", v.name.node), f)))
+                    .collect(),
+            ),
+            _ => return Ok(()),
+        };
+
+        let params: Vec<&str> = type_params.iter().map(|tp| tp.name.as_str()).collect();
+        for derive in [DeriveTrait::Eq, DeriveTrait::Debug, DeriveTrait::Hash, DeriveTrait::Show] {
+            let Some(attr_span) = derive_attr_span(attrs, derive) else {
+                continue;
+            };
+            if derive == DeriveTrait::Show && !params.is_empty() {
+                return Err(CompileError::type_error(
+                    format!("cannot derive `Show` for generic type `{}`", name.node),
+                    attr_span,
+                ));
+            }
+            for (what, ty) in &fields {
+                if !self.supports_derive(&ty.node, derive, &params) {
+                    let property = match derive {
+                        DeriveTrait::Debug => "debug-printable",
+                        DeriveTrait::Hash => "hashable",
+                        DeriveTrait::Show => "showable",
+                        _ => "comparable",
+                    };
+                    return Err(CompileError::type_error(
+                        format!("cannot derive `{}` for `{}`: {} has type `{}`, which is not {}",
+                            derive.as_str(), name.node, what, ty.node, property),
+                        attr_span,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a struct or enum derives a trait (`PartialEq` also provides `Eq`)
+    fn has_derive(&self, name: &str, derive: DeriveTrait) -> bool {
+        self.derives.get(name).is_some_and(|traits| traits.iter().any(|t| t.provides(derive)))
+    }
+
+    /// Whether values of a type support a derived trait: primitives (for
+    /// `Debug` also unit, arrays and tuples of supported types; for `Hash`
+    /// and `Show` not `f64`), and structs or enums deriving it with supported
+    /// type arguments. Names in `type_params` are accepted; instantiations
+    /// are checked at use.
+    fn supports_derive(&self, ty: &Type, derive: DeriveTrait, type_params: &[&str]) -> bool {
+        match self.resolve_type_alias(ty).base_type() {
+            Type::F64 => !matches!(derive, DeriveTrait::Hash | DeriveTrait::Show),
+            // v0.38: Include unsigned types, v0.64: Include Char type
+            Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::Bool | Type::String | Type::Char => true,
+            Type::Unit => derive == DeriveTrait::Debug,
+            Type::Array(elem, _) => {
+                derive == DeriveTrait::Debug && self.supports_derive(elem, derive, type_params)
+            }
+            Type::Tuple(elems) => {
+                derive == DeriveTrait::Debug
+                    && elems.iter().all(|elem| self.supports_derive(elem, derive, type_params))
+            }
+            Type::Named(name) | Type::TypeVar(name) if type_params.contains(&name.as_str()) => true,
+            Type::Named(name) => self.has_derive(name, derive),
+            Type::Generic { name, type_args } => {
+                self.has_derive(name, derive)
+                    && type_args.iter().all(|arg| self.supports_derive(arg, derive, type_params))
+            }
+            _ => false,
+        }
+    }
+
+    /// Hint for a struct or enum missing a derive, e.g. " (add `@derive(Eq)` to `Point`)"
+    fn missing_derive_hint(&self, ty: &Type, derive: DeriveTrait) -> String {
+        match ty {
+            Type::Named(name) | Type::Generic { name, .. } if self.derives.contains_key(name) => {
+                if self.has_derive(name, derive) {
+                    format!(" (`{}` derives `{}`, but a type argument does not)", name, derive.as_str())
+                } else {
+                    format!(" (add `@derive({})` to `{}`)", derive.as_str(), name)
+                }
+            }
+            _ => String::new(),
+        }
+    }
+
     /// Check binary operation types
     /// v0.2: Uses base_type() to handle refined types correctly
     fn check_binary_op(&self, op: BinOp, left: &Type, right: &Type, span: Span) -> Result<Type> {
@@ -2883,14 +4029,14 @@ impl TypeChecker {
 
             BinOp::Eq | BinOp::Ne => {
                 self.unify(left_base, right_base, span)?;
-                match left_base {
-                    // v0.38: Include unsigned types, v0.64: Include Char type
-                    Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 | Type::Bool | Type::String | Type::Char => Ok(Type::Bool),
-                    _ => Err(CompileError::type_error(
-                        format!("equality operator requires comparable type, got {left}"),
-                        span,
-                    )),
+                if self.supports_derive(left_base, DeriveTrait::Eq, &[]) {
+                    return Ok(Type::Bool);
                 }
+                let hint = self.missing_derive_hint(left_base, DeriveTrait::Eq);
+                Err(CompileError::type_error(
+                    format!("equality operator requires comparable type, got {left}{hint}"),
+                    span,
+                ))
             }
 
             BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge => {
@@ -3002,6 +4148,12 @@ impl TypeChecker {
             return Ok(());
         }
 
+        // A diverging expression (`return`, `break`, ...) never produces a
+        // value, so it fits wherever one is expected
+        if matches!(actual, Type::Never) {
+            return Ok(());
+        }
+
         // Both are TypeVar with same name
         if let (Type::TypeVar(a), Type::TypeVar(b)) = (&expected, &actual)
             && a == b
@@ -3306,6 +4458,7 @@ impl TypeChecker {
             Type::Ref(inner) => format!("&{}", self.type_to_string(inner)),
             Type::RefMut(inner) => format!("&mut {}", self.type_to_string(inner)),
             Type::Array(elem, size) => format!("[{}; {}]", self.type_to_string(elem), size),
+            Type::ArrayConst(elem, len) => format!("[{}; {}]", self.type_to_string(elem), len),
             Type::Range(elem) => format!("Range<{}>", self.type_to_string(elem)),
             Type::Refined { base, .. } => self.type_to_string(base),
             Type::Fn { params, ret } => {
@@ -3372,16 +4525,259 @@ impl TypeChecker {
         }
     }
 
+    /// Look up a method on a type parameter through the traits in its bounds,
+    /// with `Self` standing for the type parameter itself
+    fn lookup_bound_method(&self, type_param: &str, method: &str) -> Option<(Vec<Type>, Type)> {
+        let bounds = self.type_param_env.get(type_param)?;
+        let self_ty = Type::TypeVar(type_param.to_string());
+        bounds.iter()
+            .filter_map(|bound| self.traits.get(bound))
+            .flat_map(|trait_info| trait_info.methods.iter())
+            .find(|m| m.name == method)
+            .map(|m| {
+                let param_types = m.param_types.iter()
+                    .map(|ty| self.substitute_self(ty, &self_ty))
+                    .collect();
+                (param_types, self.substitute_self(&m.ret_type, &self_ty))
+            })
+    }
+
+    /// Check that a type argument satisfies every trait bound of its parameter.
+    /// A type parameter of the enclosing generic function satisfies a bound
+    /// if it declares the same bound; unresolved type variables are accepted.
+    fn check_trait_bounds(&self, type_param: &TypeParam, concrete: &Type, span: Span) -> Result<()> {
+        for bound in &type_param.bounds {
+            let satisfied = match concrete {
+                Type::TypeVar(name) => self
+                    .type_param_env
+                    .get(name)
+                    .is_none_or(|bounds| bounds.contains(bound)),
+                _ => self.implements_trait(concrete, bound),
+            };
+            if !satisfied {
+                return Err(CompileError::type_error(
+                    format!(
+                        "trait bound `{}: {}` is not satisfied: type `{}` does not implement `{}`",
+                        type_param.name, bound, concrete, bound
+                    ),
+                    span,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `ty` implements `trait_name`, directly or through a generic impl
+    fn implements_trait(&self, ty: &Type, trait_name: &str) -> bool {
+        self.impls.iter().any(|((impl_type, impl_trait), impl_info)| {
+            impl_trait == trait_name && self.match_impl(impl_type, impl_info, ty).is_some()
+        })
+    }
+
+    /// Match a receiver type against an impl, returning the instantiation of
+    /// the impl's type parameters. Monomorphic impls match by name only.
+    fn match_impl(&self, impl_type: &str, impl_info: &ImplInfo, ty: &Type) -> Option<HashMap<String, Type>> {
+        if impl_info.type_params.is_empty() {
+            return (impl_type == self.type_to_string(ty)).then(HashMap::new);
+        }
+        let Type::Generic { name: head, .. } = &impl_info.target_type else {
+            return None;
+        };
+        if !matches!(ty, Type::Generic { name, .. } if name == head) {
+            return None;
+        }
+        let mut type_subst = HashMap::new();
+        self.infer_type_args(&impl_info.target_type, ty, &mut type_subst, Span::new(0, 0)).ok()?;
+
+        // Type arguments must satisfy the impl's own bounds
+        for tp in &impl_info.type_params {
+            let Some(arg) = type_subst.get(&tp.name) else { continue };
+            let satisfied = tp.bounds.iter().all(|bound| match arg {
+                Type::TypeVar(name) => self
+                    .type_param_env
+                    .get(name)
+                    .is_none_or(|bounds| bounds.contains(bound)),
+                _ => self.implements_trait(arg, bound),
+            });
+            if !satisfied {
+                return None;
+            }
+        }
+        Some(type_subst)
+    }
+
+    /// Reject an impl whose target overlaps an already registered one: two
+    /// impls of the same trait, or two inherent impls defining the same
+    /// method, that some type would instantiate both of
+    fn check_impl_overlap(&self, info: &ImplInfo) -> Result<()> {
+        let candidates: Vec<&ImplInfo> = match &info.trait_name {
+            Some(trait_name) => self
+                .impls
+                .iter()
+                .filter(|((_, t), _)| t == trait_name)
+                .map(|(_, other)| other)
+                .collect(),
+            None => self.inherent_impls.iter().map(|(_, other)| other).collect(),
+        };
+        for other in candidates {
+            if !self.impl_targets_overlap(&info.target_type, other) {
+                continue;
+            }
+            let target = self.type_to_string(&info.target_type);
+            let message = match &info.trait_name {
+                Some(trait_name) => {
+                    format!("conflicting implementations of trait `{}` for type `{}`", trait_name, target)
+                }
+                None => {
+                    let Some(method) = info.methods.keys().filter(|m| other.methods.contains_key(*m)).min() else {
+                        continue;
+                    };
+                    format!("conflicting implementations of method `{}` for type `{}`", method, target)
+                }
+            };
+            return Err(CompileError::type_error(message, info.span)
+                .with_related(other.span, "first implementation here"));
+        }
+        Ok(())
+    }
+
+    /// Whether some type instantiates both impl targets. The other impl's
+    /// type parameters are renamed apart so both sides unify independently.
+    fn impl_targets_overlap(&self, target: &Type, other: &ImplInfo) -> bool {
+        let renamed: HashMap<String, Type> = other
+            .type_params
+            .iter()
+            .map(|tp| (tp.name.clone(), Type::TypeVar(format!("{}'", tp.name))))
+            .collect();
+        let other_target = self.substitute_type(&other.target_type, &renamed);
+        unify_impl_targets(target, &other_target, &mut HashMap::new())
+    }
+
     /// v0.20.1: Look up trait method for a given receiver type
+    /// Look up a method from an inherent impl (`impl Type { ... }`) of the receiver type
+    fn lookup_inherent_method(&self, receiver_ty: &Type, method: &str) -> Option<(Vec<Type>, Type)> {
+        self.inherent_impls.iter().find_map(|(impl_type, impl_info)| {
+            let type_subst = self.match_impl(impl_type, impl_info, receiver_ty)?;
+            let (param_types, ret_type) = impl_info.methods.get(method)?;
+            let param_types = param_types.iter()
+                .map(|ty| self.substitute_type(ty, &type_subst))
+                .collect();
+            Some((param_types, self.substitute_type(ret_type, &type_subst)))
+        })
+    }
+
+    /// Register the method signatures of an impl block
+    fn register_impl(&mut self, i: &ImplBlock) -> Result<()> {
+        // Generic impls are keyed by their full target (`Pair<T, i64>`)
+        // and instantiated at lookup
+        let type_param_names: Vec<_> = i.type_params.iter().map(|tp| tp.name.as_str()).collect();
+        let target_type = self.resolve_type_vars(&i.target_type.node, &type_param_names);
+        let type_name = self.type_to_string(&target_type);
+
+        // Register methods from impl block
+        let mut methods = HashMap::new();
+        for method in &i.methods {
+            // Substitute Self with target type in method signature
+            let param_types: Vec<Type> = method.params.iter()
+                .filter(|p| p.name.node != "self")
+                .map(|p| self.resolve_type_vars(&self.substitute_self(&p.ty.node, &target_type), &type_param_names))
+                .collect();
+            let ret_type = self.substitute_self(&method.ret_ty.node, &target_type);
+            let ret_type = self.resolve_type_vars(&ret_type, &type_param_names);
+            methods.insert(method.name.node.clone(), (param_types, ret_type));
+        }
+
+        let info = ImplInfo {
+            trait_name: i.trait_name.as_ref().map(|t| t.node.clone()),
+            type_params: i.type_params.clone(),
+            target_type,
+            methods,
+            span: i.target_type.span,
+        };
+        let result = self.check_impl_overlap(&info);
+        self.recover(result)?;
+
+        let Some(trait_name) = &i.trait_name else {
+            self.inherent_impls.push((type_name, info));
+            return Ok(());
+        };
+
+        // v0.80: Track that this trait is implemented
+        self.implemented_traits.insert(trait_name.node.clone());
+
+        self.impls.insert((type_name, trait_name.node.clone()), info);
+        Ok(())
+    }
+
+    /// Warn when an inherent method has the same name as a trait method
+    /// implemented for the same type; method calls resolve to the inherent one
+    fn check_shadowed_trait_methods(&mut self, program: &Program) {
+        let impl_type_name = |i: &crate::ast::ImplBlock| match &i.target_type.node {
+            Type::Generic { name, .. } => name.clone(),
+            ty => crate::ast::output::format_type(ty),
+        };
+        let mut trait_methods: HashMap<(String, &str), (&str, Span)> = HashMap::new();
+        for item in &program.items {
+            if let Item::ImplBlock(i) = item
+                && let Some(trait_name) = &i.trait_name
+            {
+                for m in &i.methods {
+                    trait_methods
+                        .entry((impl_type_name(i), m.name.node.as_str()))
+                        .or_insert((trait_name.node.as_str(), m.name.span));
+                }
+            }
+        }
+        if trait_methods.is_empty() {
+            return;
+        }
+        for item in &program.items {
+            let Item::ImplBlock(i) = item else { continue };
+            if i.trait_name.is_some() {
+                continue;
+            }
+            let type_name = impl_type_name(i);
+            for m in &i.methods {
+                if let Some((trait_name, trait_span)) = trait_methods.get(&(type_name.clone(), m.name.node.as_str())) {
+                    self.add_warning(CompileWarning::shadowed_trait_method(
+                        &type_name,
+                        &m.name.node,
+                        *trait_name,
+                        m.name.span,
+                        *trait_span,
+                    ));
+                }
+            }
+        }
+    }
+
     fn lookup_trait_method(&self, receiver_ty: &Type, method: &str) -> Option<(Vec<Type>, Type)> {
-        let type_name = self.type_to_string(receiver_ty);
+        let matching: Vec<_> = self.impls.iter()
+            .filter_map(|((impl_type, trait_name), impl_info)| {
+                self.match_impl(impl_type, impl_info, receiver_ty)
+                    .map(|type_subst| (trait_name, impl_info, type_subst))
+            })
+            .collect();
 
         // Search all impls for this type to find the method
-        for ((impl_type, _trait_name), impl_info) in &self.impls {
-            if impl_type == &type_name
-                && let Some((param_types, ret_type)) = impl_info.methods.get(method)
-            {
-                return Some((param_types.clone(), ret_type.clone()));
+        for (_, impl_info, type_subst) in &matching {
+            if let Some((param_types, ret_type)) = impl_info.methods.get(method) {
+                let param_types = param_types.iter()
+                    .map(|ty| self.substitute_type(ty, type_subst))
+                    .collect();
+                return Some((param_types, self.substitute_type(ret_type, type_subst)));
+            }
+        }
+
+        // Fall back to a default body of an implemented trait
+        for (trait_name, _, _) in &matching {
+            if let Some(m) = self.traits.get(*trait_name).and_then(|t| {
+                t.methods.iter().find(|m| m.name == method && m.default_body.is_some())
+            }) {
+                let param_types = m.param_types.iter()
+                    .map(|ty| self.substitute_self(ty, receiver_ty))
+                    .collect();
+                return Some((param_types, self.substitute_self(&m.ret_type, receiver_ty)));
             }
         }
         None
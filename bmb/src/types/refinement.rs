@@ -0,0 +1,100 @@
+//! Refinement constraints at the places a value enters a refined type
+//!
+//! A parameter `n: i64{it >= 0}` constrains every argument passed for it,
+//! and `let x: i64{it > 0} = e;` constrains `e`. The constraint is
+//! instantiated with the value for `it` (and, at call sites, the other
+//! arguments for the callee's parameters). The type checker rejects
+//! instantiations that evaluate to `false` outright; the rest become
+//! obligations for the contract verifier.
+
+use std::collections::HashMap;
+
+use crate::ast::{Expr, Span, Spanned};
+
+/// A refinement the type checker could not decide, to be proven by
+/// `verify::ContractVerifier` in the context of `function`
+#[derive(Debug, Clone)]
+pub struct RefinementObligation {
+    /// Function whose body passes the value
+    pub function: String,
+    /// What receives the value, e.g. "argument `n` of `power`"
+    pub target: String,
+    /// The constraint in terms of the caller's expressions
+    pub condition: Spanned<Expr>,
+    /// The value being passed
+    pub span: Span,
+}
+
+/// Copy of `constraint` with `it` replaced by `value` and each name in
+/// `params` by its expression
+///
+/// Returns `None` for constraint forms that have no meaning outside the
+/// refined type (quantifiers, `.pre`/`.post`, blocks).
+pub fn instantiate(
+    constraint: &Spanned<Expr>,
+    value: &Spanned<Expr>,
+    params: &HashMap<&str, &Spanned<Expr>>,
+) -> Option<Spanned<Expr>> {
+    let sub = |e: &Spanned<Expr>| instantiate(e, value, params).map(Box::new);
+    let node = match &constraint.node {
+        Expr::It => return Some(value.clone()),
+        Expr::Var(name) => match params.get(name.as_str()) {
+            Some(arg) => return Some((*arg).clone()),
+            None => constraint.node.clone(),
+        },
+        Expr::IntLit(_)
+        | Expr::FloatLit(_)
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::CharLit(_)
+        | Expr::Unit => constraint.node.clone(),
+        Expr::Binary { left, op, right } => Expr::Binary { left: sub(left)?, op: *op, right: sub(right)? },
+        Expr::Unary { op, expr } => Expr::Unary { op: *op, expr: sub(expr)? },
+        Expr::If { cond, then_branch, else_branch } => Expr::If {
+            cond: sub(cond)?,
+            then_branch: sub(then_branch)?,
+            else_branch: sub(else_branch)?,
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func: func.clone(),
+            args: args.iter().map(|a| instantiate(a, value, params)).collect::<Option<_>>()?,
+        },
+        Expr::Cast { expr, ty } => Expr::Cast { expr: sub(expr)?, ty: ty.clone() },
+        _ => return None,
+    };
+    Some(Spanned::new(node, constraint.span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::BinOp;
+
+    fn spanned(node: Expr) -> Spanned<Expr> {
+        Spanned::new(node, Span::new(0, 0))
+    }
+
+    #[test]
+    fn test_instantiate_replaces_it_and_params() {
+        // it >= lo
+        let constraint = spanned(Expr::Binary {
+            left: Box::new(spanned(Expr::It)),
+            op: BinOp::Ge,
+            right: Box::new(spanned(Expr::Var("lo".to_string()))),
+        });
+        let value = spanned(Expr::Var("x".to_string()));
+        let lo = spanned(Expr::IntLit(3));
+        let params = HashMap::from([("lo", &lo)]);
+
+        let condition = instantiate(&constraint, &value, &params).unwrap();
+        let Expr::Binary { left, right, .. } = condition.node else {
+            panic!("expected a comparison");
+        };
+        assert!(matches!(&left.node, Expr::Var(name) if name == "x"));
+        assert!(matches!(right.node, Expr::IntLit(3)));
+
+        // Quantifiers stay with the type
+        let forall = spanned(Expr::Block(vec![]));
+        assert!(instantiate(&forall, &value, &params).is_none());
+    }
+}
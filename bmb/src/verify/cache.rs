@@ -0,0 +1,274 @@
+//! Verification result cache
+//!
+//! `bmb verify` keeps each function's report in one file per source file
+//! under `.bmb/verify-cache/`, keyed by a hash of everything the function's verification reads, so
+//! unchanged functions are not sent to the solver again. The whole cache is
+//! dropped when the verifier settings (solver, timeout, enabled checks)
+//! change.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Expr, FnDef, Item, Program, Span};
+use crate::smt::VerifyResult;
+use super::contract::FunctionReport;
+
+/// Function reports from earlier `bmb verify` runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerifyCache {
+    /// Verifier settings the reports were produced under
+    settings: String,
+    functions: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    hash: String,
+    /// Report with spans relative to the start of the function
+    report: FunctionReport,
+}
+
+impl VerifyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path of the cache file for `source_file` under `project_root`.
+    /// The file is named after the source file and a hash of its full
+    /// path, so files with the same name in different directories, or
+    /// functions with the same name in different files, do not collide.
+    pub fn path(project_root: &Path, source_file: &Path) -> PathBuf {
+        let full = source_file.canonicalize().unwrap_or_else(|_| source_file.to_path_buf());
+        let mut hasher = DefaultHasher::new();
+        full.hash(&mut hasher);
+        let stem = source_file.file_stem().unwrap_or_default().to_string_lossy();
+        project_root
+            .join(".bmb")
+            .join("verify-cache")
+            .join(format!("{}-{:016x}.json", stem, hasher.finish()))
+    }
+
+    /// Read the cache for `source_file` under `project_root`; a missing or
+    /// unreadable file gives an empty cache
+    pub fn load(project_root: &Path, source_file: &Path) -> Self {
+        std::fs::read_to_string(Self::path(project_root, source_file))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache for `source_file` to `.bmb/verify-cache/` under
+    /// `project_root`
+    pub fn save(&self, project_root: &Path, source_file: &Path) -> std::io::Result<()> {
+        let path = Self::path(project_root, source_file);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Number of cached function reports
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Drop every entry unless they were produced under `settings`
+    pub(crate) fn use_settings(&mut self, settings: &str) {
+        if self.settings != settings {
+            self.settings = settings.to_string();
+            self.functions.clear();
+        }
+    }
+
+    /// The cached report for `func`, if its hash is still `hash`
+    pub(crate) fn get(&self, func: &FnDef, hash: &str) -> Option<FunctionReport> {
+        let entry = self.functions.get(&func.name.node).filter(|entry| entry.hash == hash)?;
+        let mut report = entry.report.clone();
+        shift_spans(&mut report, |offset| offset + func.span.start);
+        report.cached = true;
+        Some(report)
+    }
+
+    /// Remember `report` for `func`. Reports the solver could not decide
+    /// are not kept, so they are retried on the next run.
+    pub(crate) fn insert(&mut self, func: &FnDef, hash: String, report: &FunctionReport) {
+        if !is_decided(report) {
+            self.functions.remove(&func.name.node);
+            return;
+        }
+        let mut report = report.clone();
+        shift_spans(&mut report, |offset| offset.saturating_sub(func.span.start));
+        self.functions.insert(func.name.node.clone(), CacheEntry { hash, report });
+    }
+
+    /// Forget functions that are no longer in the program
+    pub(crate) fn retain(&mut self, names: &HashSet<&str>) {
+        self.functions.retain(|name, _| names.contains(name.as_str()));
+    }
+}
+
+/// Hash of what `func`'s verification reads: its own text, the signatures
+/// and contracts of the functions it calls, and the program's constants
+/// and type definitions
+pub(crate) fn function_hash(program: &Program, source: &str, func: &FnDef) -> String {
+    let text = |start: usize, end: usize| source.get(start..end).unwrap_or_default();
+    let callees = called_functions(func);
+    let mut hasher = DefaultHasher::new();
+    text(func.span.start, func.span.end).hash(&mut hasher);
+    for item in &program.items {
+        match item {
+            // Callers only see the signature and contracts
+            Item::FnDef(callee) if callees.contains(&callee.name.node) => {
+                text(callee.span.start, callee.body.span.start).hash(&mut hasher);
+            }
+            Item::StructDef(_) | Item::EnumDef(_) | Item::TypeAlias(_) | Item::ConstDef(_) => {
+                let span = item.span();
+                text(span.start, span.end).hash(&mut hasher);
+            }
+            _ => {}
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Names of the functions called in `func`'s body and contracts
+fn called_functions(func: &FnDef) -> HashSet<String> {
+    fn visit(expr: &Expr, out: &mut HashSet<String>) {
+        if let Expr::Call { func, .. } = expr {
+            out.insert(func.clone());
+        }
+        expr.for_each_child(&mut |child| visit(&child.node, out));
+    }
+
+    let mut out = HashSet::new();
+    let contracts = func.contracts.iter().map(|c| &c.condition);
+    for expr in func.pre.iter().chain(&func.post).chain(contracts).chain([&func.body]) {
+        visit(&expr.node, &mut out);
+    }
+    out
+}
+
+/// Whether every check in `report` was proved or refuted
+fn is_decided(report: &FunctionReport) -> bool {
+    let decided = |result: &VerifyResult| matches!(result, VerifyResult::Verified | VerifyResult::Failed(_));
+    report.pre_result.iter().chain(&report.post_result).all(decided)
+        && report.contract_results.iter().all(|(_, r)| decided(r))
+        && report.refinement_results.iter().all(|(_, r)| decided(r))
+        && report.bounds_results.iter().all(|(_, _, r)| decided(r))
+        && report.division_results.iter().all(|(_, _, r)| decided(r))
+        && report.loop_results.iter().all(|(_, _, r)| decided(r))
+}
+
+fn shift_spans(report: &mut FunctionReport, shift: impl Fn(usize) -> usize) {
+    let shift_span = |span: &mut Span| *span = Span::new(shift(span.start), shift(span.end));
+    for (span, _, _) in &mut report.bounds_results {
+        shift_span(span);
+    }
+    for (span, _, _) in &mut report.division_results {
+        shift_span(span);
+    }
+    for (span, _, _) in &mut report.loop_results {
+        shift_span(span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::Counterexample;
+
+    const SOURCE: &str = "const LIMIT: i64 = 100;
+fn g(x: i64) -> i64 pre x >= 0 = x + 1;
+fn f(x: i64) -> i64 post ret > 0 = g(x * x);
+fn h() -> i64 = 0;";
+
+    fn parse(source: &str) -> Program {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        crate::parser::parse("test.bmb", source, tokens).unwrap()
+    }
+
+    fn hash_of(source: &str, name: &str) -> String {
+        let program = parse(source);
+        let func = program.items.iter()
+            .find_map(|item| match item {
+                Item::FnDef(func) if func.name.node == name => Some(func),
+                _ => None,
+            })
+            .unwrap();
+        function_hash(&program, source, func)
+    }
+
+    #[test]
+    fn test_function_hash_tracks_what_verification_reads() {
+        let base = hash_of(SOURCE, "f");
+        assert_eq!(hash_of(&format!("\n\n{}", SOURCE), "f"), base);
+        assert_eq!(hash_of(&SOURCE.replace("= 0;", "= 1;"), "f"), base);
+        assert_eq!(hash_of(&SOURCE.replace("= x + 1;", "= x + 2;"), "f"), base);
+
+        assert_ne!(hash_of(&SOURCE.replace("x * x", "x + x"), "f"), base);
+        assert_ne!(hash_of(&SOURCE.replace("pre x >= 0", "pre x > 0"), "f"), base);
+        assert_ne!(hash_of(&SOURCE.replace("100", "50"), "f"), base);
+    }
+
+    #[test]
+    fn test_cache_reuses_reports_under_same_settings() {
+        let program = parse(SOURCE);
+        let Item::FnDef(f) = &program.items[2] else { panic!("expected f") };
+        let mut report = FunctionReport::new("f".to_string());
+        report.pre_result = Some(VerifyResult::Verified);
+        report.post_result = Some(VerifyResult::Failed(Counterexample {
+            assignments: vec![("x".to_string(), "0".to_string())],
+        }));
+        report.division_results.push((Span::new(f.span.start + 4, f.span.start + 9), "a / b".to_string(), VerifyResult::Verified));
+
+        let mut cache = VerifyCache::new();
+        cache.use_settings("z3");
+        cache.insert(f, "1".to_string(), &report);
+        assert!(cache.get(f, "2").is_none());
+
+        // The cached report survives a round trip through the file, and its
+        // spans follow the function when it moves
+        let mut cache: VerifyCache = serde_json::from_str(&serde_json::to_string(&cache).unwrap()).unwrap();
+        let moved = format!("\n\n{}", SOURCE);
+        let program = parse(&moved);
+        let Item::FnDef(moved_f) = &program.items[2] else { panic!("expected f") };
+        let cached = cache.get(moved_f, "1").unwrap();
+        assert!(cached.cached);
+        assert!(matches!(cached.post_result, Some(VerifyResult::Failed(_))));
+        assert_eq!(cached.division_results[0].0, Span::new(moved_f.span.start + 4, moved_f.span.start + 9));
+
+        cache.use_settings("cvc5");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_path_is_per_source_file() {
+        let root = Path::new("/project");
+        let a = VerifyCache::path(root, Path::new("/project/src/lib.bmb"));
+        let b = VerifyCache::path(root, Path::new("/project/tests/lib.bmb"));
+        assert!(a.starts_with("/project/.bmb/verify-cache"));
+        assert!(a.file_name().unwrap().to_string_lossy().starts_with("lib-"));
+        assert_ne!(a, b);
+        assert_eq!(a, VerifyCache::path(root, Path::new("/project/src/lib.bmb")));
+    }
+
+    #[test]
+    fn test_cache_skips_undecided_reports() {
+        let program = parse(SOURCE);
+        let Item::FnDef(f) = &program.items[2] else { panic!("expected f") };
+        let mut report = FunctionReport::new("f".to_string());
+        report.post_result = Some(VerifyResult::Unknown("timeout".to_string()));
+
+        let mut cache = VerifyCache::new();
+        cache.insert(f, "1".to_string(), &report);
+        assert!(cache.is_empty());
+    }
+}
@@ -5,17 +5,35 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::ast::{Expr, FnDef, Item, NamedContract, Program, Spanned, Type};
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Expr, FnDef, Item, NamedContract, Program, Span, Spanned, Type};
+use crate::types::consts::eval_consts;
+use crate::types::refinement::RefinementObligation;
+use super::cache::{function_hash, VerifyCache};
+use super::paths::{
+    entry_params, has_assignment, has_invariant, has_loop, path_obligations, ObligationKind, PathChecks,
+};
 use crate::smt::{
-    SmtLibGenerator, SmtTranslator, SmtSolver, SolverResult,
+    SmtLibGenerator, SmtTranslator, SmtSolver, SolverBackend, SolverResult,
     VerifyResult, Counterexample,
 };
 
 /// Contract verifier for BMB programs
 pub struct ContractVerifier {
     solver: SmtSolver,
+    /// Values of the program's `const` items, as literal expressions
+    consts: HashMap<String, Expr>,
+    /// Also prove every array index in bounds
+    check_bounds: bool,
+    /// Also prove every integer divisor nonzero
+    check_division: bool,
+    /// Refinements at call sites and `let` annotations left by the type checker
+    refinement_obligations: Vec<RefinementObligation>,
+    /// Functions to verify; `None` verifies all of them
+    selected: Option<HashSet<String>>,
 }
 
 impl ContractVerifier {
@@ -23,40 +41,82 @@ impl ContractVerifier {
     pub fn new() -> Self {
         Self {
             solver: SmtSolver::new(),
+            consts: HashMap::new(),
+            check_bounds: false,
+            check_division: false,
+            refinement_obligations: Vec::new(),
+            selected: None,
         }
     }
 
-    /// Set custom Z3 path
-    pub fn with_z3_path(mut self, path: &str) -> Self {
+    /// Select the SMT solver (Z3 by default)
+    pub fn with_solver(mut self, backend: SolverBackend) -> Self {
+        self.solver = self.solver.with_backend(backend);
+        self
+    }
+
+    /// Set the solver executable path
+    pub fn with_solver_path(mut self, path: &str) -> Self {
         self.solver = self.solver.with_path(path);
         self
     }
 
+    /// Set custom Z3 path
+    pub fn with_z3_path(self, path: &str) -> Self {
+        self.with_solver_path(path)
+    }
+
     /// Set timeout in seconds
     pub fn with_timeout(mut self, seconds: u32) -> Self {
         self.solver = self.solver.with_timeout(seconds);
         self
     }
 
-    /// Check if the solver is available
+    /// Prove that every array index expression stays within bounds
+    pub fn with_bounds_checking(mut self, enabled: bool) -> Self {
+        self.check_bounds = enabled;
+        self
+    }
+
+    /// Prove that the divisor of every integer `/` and `%` is nonzero
+    pub fn with_division_checking(mut self, enabled: bool) -> Self {
+        self.check_division = enabled;
+        self
+    }
+
+    /// Also prove the refinements the type checker could not decide
+    /// (`TypeChecker::refinement_obligations`), each in its function's context
+    pub fn with_refinement_obligations(mut self, obligations: Vec<RefinementObligation>) -> Self {
+        self.refinement_obligations = obligations;
+        self
+    }
+
+    /// Only verify the named functions; the others are reported as skipped
+    pub fn with_functions(mut self, names: &[String]) -> Self {
+        self.selected = Some(names.iter().cloned().collect());
+        self
+    }
+
+    /// Check if the selected solver is available
     pub fn is_solver_available(&self) -> bool {
         self.solver.is_available()
     }
 
+    /// Version of the selected solver, if it runs
+    pub fn solver_version(&self) -> Option<String> {
+        self.solver.version()
+    }
+
     /// Verify all functions in a program
-    pub fn verify_program(&self, program: &Program) -> VerificationReport {
+    pub fn verify_program(&mut self, program: &Program) -> VerificationReport {
         let mut report = VerificationReport::new();
-
-        // v0.86: Build function index for contract conflict detection (Phase 83)
-        let mut function_index: HashMap<String, &FnDef> = HashMap::new();
-        for item in &program.items {
-            if let Item::FnDef(func) = item {
-                function_index.insert(func.name.node.clone(), func);
-            }
-        }
+        let function_index = self.prepare(program);
 
         for item in &program.items {
             match item {
+                Item::FnDef(func) if !self.is_selected(func) => {
+                    report.functions.push(FunctionReport::skipped(func.name.node.clone()));
+                }
                 Item::FnDef(func) => {
                     let func_report = self.verify_function_with_index(func, &function_index);
                     report.functions.push(func_report);
@@ -67,12 +127,100 @@ impl ContractVerifier {
                 Item::TraitDef(_) | Item::ImplBlock(_) => {}
                 // v0.50.6: Type aliases don't need verification
                 Item::TypeAlias(_) => {}
+                Item::ConstDef(_) => {}
             }
         }
 
         report
     }
 
+    /// Verify all functions in a program, reusing the reports in `cache` for
+    /// functions whose hash is unchanged and storing the new ones
+    pub fn verify_program_cached(
+        &mut self,
+        program: &Program,
+        source: &str,
+        cache: &mut VerifyCache,
+    ) -> VerificationReport {
+        let mut report = VerificationReport::new();
+        let function_index = self.prepare(program);
+        cache.use_settings(&self.settings());
+
+        for item in &program.items {
+            let Item::FnDef(func) = item else { continue };
+            if !self.is_selected(func) {
+                report.functions.push(FunctionReport::skipped(func.name.node.clone()));
+                continue;
+            }
+            let hash = function_hash(program, source, func);
+            let func_report = cache.get(func, &hash).unwrap_or_else(|| {
+                let func_report = self.verify_function_with_index(func, &function_index);
+                cache.insert(func, hash, &func_report);
+                func_report
+            });
+            report.functions.push(func_report);
+        }
+        cache.retain(&function_index.keys().map(String::as_str).collect());
+
+        report
+    }
+
+    /// Whether `func` is among the functions to verify
+    fn is_selected(&self, func: &FnDef) -> bool {
+        self.selected.as_ref().is_none_or(|names| names.contains(&func.name.node))
+    }
+
+    /// Load the program's constants and index its functions
+    fn prepare<'a>(&mut self, program: &'a Program) -> HashMap<String, &'a FnDef> {
+        // Contracts may mention constants; the type checker has already
+        // rejected invalid initializers
+        self.consts = eval_consts(program)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| (name, value.to_expr()))
+            .collect();
+
+        // v0.86: Build function index for contract conflict detection (Phase 83)
+        let mut function_index: HashMap<String, &FnDef> = HashMap::new();
+        for item in &program.items {
+            if let Item::FnDef(func) = item {
+                function_index.insert(func.name.node.clone(), func);
+            }
+        }
+        function_index
+    }
+
+    /// Everything besides the program that decides a report; cached reports
+    /// are only reused under the same settings
+    fn settings(&self) -> String {
+        format!(
+            "bmb {}; {} {}; timeout {}s; bounds {}; division {}",
+            env!("CARGO_PKG_VERSION"),
+            self.solver.backend(),
+            self.solver_version().unwrap_or_default(),
+            self.solver.timeout(),
+            self.check_bounds,
+            self.check_division,
+        )
+    }
+
+    /// Translator that knows the program's constants
+    fn translator(&self) -> SmtTranslator {
+        SmtTranslator::new().with_consts(self.consts.clone())
+    }
+
+    /// Translator and generator with `func`'s parameters declared, and the
+    /// constants for the entry values `old()` reads
+    fn setup(&self, func: &FnDef) -> (SmtTranslator, SmtLibGenerator) {
+        let mut generator = SmtLibGenerator::new();
+        let mut translator = self.translator();
+        translator.setup_function(func, &mut generator);
+        for name in entry_params(func) {
+            translator.declare_entry_value(&name, &mut generator);
+        }
+        (translator, generator)
+    }
+
     /// Verify a single function (legacy interface without function index)
     pub fn verify_function(&self, func: &FnDef) -> FunctionReport {
         self.verify_function_with_index(func, &HashMap::new())
@@ -99,15 +247,21 @@ impl ContractVerifier {
 
         // Check if function has any contracts (pre/post, named contracts, or refinement types)
         let has_return_refinement = matches!(&func.ret_ty.node, Type::Refined { .. });
+        let obligations: Vec<_> = self.refinement_obligations.iter()
+            .filter(|o| o.function == name)
+            .collect();
         let has_contracts = func.pre.is_some()
             || func.post.is_some()
             || !func.contracts.is_empty()
-            || has_return_refinement;
+            || has_return_refinement
+            || !obligations.is_empty()
+            || has_invariant(&func.body.node);
 
         if !has_contracts {
             report.pre_result = Some(VerifyResult::Verified);
             report.post_result = Some(VerifyResult::Verified);
             report.message = Some("No contracts to verify".to_string());
+            self.verify_paths(func, &mut report);
             return report;
         }
 
@@ -124,9 +278,7 @@ impl ContractVerifier {
         self.detect_contract_conflicts(func, function_index, &mut report);
 
         // Set up translator
-        let mut generator = SmtLibGenerator::new();
-        let mut translator = SmtTranslator::new();
-        translator.setup_function(func, &mut generator);
+        let (translator, generator) = self.setup(func);
 
         // Verify pre-condition if present
         if let Some(pre) = &func.pre {
@@ -159,9 +311,86 @@ impl ContractVerifier {
             }
         }
 
+        // Refinements of values this function passes on, after the return
+        // refinements so counterexamples still line up with those
+        for obligation in obligations {
+            let result = self.verify_refinement_obligation(&translator, &generator, obligation, func);
+            report.refinement_results.push((obligation.target.clone(), result));
+        }
+
+        self.verify_paths(func, &mut report);
+
         report
     }
 
+    /// Prove the obligations that depend on the path through the body:
+    /// loop invariants on entry and preservation, the postcondition of a
+    /// function with loops or assignments, (`--check-bounds`) `0 <= i < len`
+    /// for every index expression `a[i]`, and (`--check-division`) `d != 0`
+    /// for every integer division `n / d` or `n % d`
+    fn verify_paths(&self, func: &FnDef, report: &mut FunctionReport) {
+        let loops = has_loop(&func.body.node);
+        let checks = PathChecks {
+            bounds: self.check_bounds,
+            division: self.check_division,
+            loops,
+            // The body as a whole only translates without state changes
+            post: loops || has_assignment(&func.body.node),
+        };
+        if !checks.bounds && !checks.division && !checks.loops && !checks.post {
+            return;
+        }
+
+        let (translator, generator) = self.setup(func);
+
+        let paths = path_obligations(func, &translator, &generator, checks);
+        let mut exit_results = Vec::new();
+        for obligation in paths.obligations {
+            let result = match obligation.query {
+                Ok(script) => match self.solver.solve(&script) {
+                    Ok(SolverResult::Sat(model)) => VerifyResult::Failed(Counterexample::from_model(model)),
+                    Ok(SolverResult::Unsat) => VerifyResult::Verified,
+                    Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
+                    Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
+                },
+                Err(reason) => VerifyResult::Unknown(reason),
+            };
+            match obligation.kind {
+                ObligationKind::Bounds(text) => report.bounds_results.push((obligation.span, text, result)),
+                ObligationKind::Division(text) => report.division_results.push((obligation.span, text, result)),
+                ObligationKind::InvariantEntry => {
+                    report.loop_results.push((obligation.span, LoopCheck::Entry, result))
+                }
+                ObligationKind::InvariantPreserved => {
+                    report.loop_results.push((obligation.span, LoopCheck::Preserved, result))
+                }
+                ObligationKind::Postcondition => {
+                    // Without an invariant the state after a loop is only
+                    // over-approximated, so a model need not be a real run
+                    let result = match result {
+                        VerifyResult::Failed(_) if paths.imprecise_exit => VerifyResult::Unknown(
+                            "a loop without an invariant (or with `break`) leaves its exit state unknown"
+                                .to_string(),
+                        ),
+                        result => result,
+                    };
+                    if loops {
+                        report.loop_results.push((obligation.span, LoopCheck::Exit, result.clone()));
+                    }
+                    exit_results.push(result);
+                }
+            }
+        }
+
+        // With loops or assignments in the body, the postcondition is proven
+        // at every exit
+        if func.post.is_some() && !exit_results.is_empty() {
+            let failed = exit_results.iter().find(|r| matches!(r, VerifyResult::Failed(_)));
+            let unknown = exit_results.iter().find(|r| !matches!(r, VerifyResult::Verified));
+            report.post_result = Some(failed.or(unknown).unwrap_or(&VerifyResult::Verified).clone());
+        }
+    }
+
     /// v0.31: Detect duplicate contracts by hashing their expressions
     fn detect_duplicate_contracts(&self, func: &FnDef, report: &mut FunctionReport) {
         use std::collections::HashMap;
@@ -201,9 +430,7 @@ impl ContractVerifier {
     /// meaning the contract is always true regardless of inputs
     fn detect_trivial_contracts(&self, func: &FnDef, report: &mut FunctionReport) {
         // Set up translator and generator for contract checking
-        let mut generator = SmtLibGenerator::new();
-        let mut translator = SmtTranslator::new();
-        translator.setup_function(func, &mut generator);
+        let (translator, generator) = self.setup(func);
 
         // Check precondition for tautology
         if let Some(pre) = &func.pre
@@ -272,9 +499,7 @@ impl ContractVerifier {
         let Some(pre) = &func.pre else { return };
 
         // Set up translator and generator
-        let mut generator = SmtLibGenerator::new();
-        let mut translator = SmtTranslator::new();
-        translator.setup_function(func, &mut generator);
+        let (translator, mut generator) = self.setup(func);
 
         // Translate precondition
         let pre_smt = match translator.translate(pre) {
@@ -337,6 +562,9 @@ impl ContractVerifier {
                 self.check_expr_for_conflicts(&value.node, function_index, report);
                 self.check_expr_for_conflicts(&body.node, function_index, report);
             }
+            Expr::LetStmt { value, .. } => {
+                self.check_expr_for_conflicts(&value.node, function_index, report);
+            }
             Expr::Binary { left, right, .. } => {
                 self.check_expr_for_conflicts(&left.node, function_index, report);
                 self.check_expr_for_conflicts(&right.node, function_index, report);
@@ -344,14 +572,14 @@ impl ContractVerifier {
             Expr::Unary { expr: inner, .. } => {
                 self.check_expr_for_conflicts(&inner.node, function_index, report);
             }
-            Expr::While { cond, body, invariant } => {
+            Expr::While { cond, body, invariant, .. } => {
                 self.check_expr_for_conflicts(&cond.node, function_index, report);
                 self.check_expr_for_conflicts(&body.node, function_index, report);
                 if let Some(inv) = invariant {
                     self.check_expr_for_conflicts(&inv.node, function_index, report);
                 }
             }
-            Expr::Loop { body } => {
+            Expr::Loop { body, .. } => {
                 self.check_expr_for_conflicts(&body.node, function_index, report);
             }
             Expr::For { iter, body, .. } => {
@@ -378,10 +606,13 @@ impl ContractVerifier {
                     self.check_expr_for_conflicts(&elem.node, function_index, report);
                 }
             }
-            Expr::StructInit { fields, .. } => {
+            Expr::StructInit { fields, base, .. } => {
                 for (_, value) in fields {
                     self.check_expr_for_conflicts(&value.node, function_index, report);
                 }
+                if let Some(base) = base {
+                    self.check_expr_for_conflicts(&base.node, function_index, report);
+                }
             }
             Expr::Index { expr: array, index } => {
                 self.check_expr_for_conflicts(&array.node, function_index, report);
@@ -399,7 +630,7 @@ impl ContractVerifier {
                     self.check_expr_for_conflicts(&arg.node, function_index, report);
                 }
             }
-            Expr::Break { value } | Expr::Return { value } => {
+            Expr::Break { value, .. } | Expr::Return { value } => {
                 if let Some(v) = value {
                     self.check_expr_for_conflicts(&v.node, function_index, report);
                 }
@@ -416,13 +647,22 @@ impl ContractVerifier {
             Expr::Assign { value, .. } => {
                 self.check_expr_for_conflicts(&value.node, function_index, report);
             }
+            Expr::IndexAssign { array, index, value } => {
+                self.check_expr_for_conflicts(&array.node, function_index, report);
+                self.check_expr_for_conflicts(&index.node, function_index, report);
+                self.check_expr_for_conflicts(&value.node, function_index, report);
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                self.check_expr_for_conflicts(&object.node, function_index, report);
+                self.check_expr_for_conflicts(&value.node, function_index, report);
+            }
             Expr::Forall { body, .. } | Expr::Exists { body, .. } => {
                 self.check_expr_for_conflicts(&body.node, function_index, report);
             }
             // Leaf expressions - no recursion needed
             Expr::IntLit(_) | Expr::FloatLit(_) | Expr::BoolLit(_) | Expr::StringLit(_)
             | Expr::CharLit(_) | Expr::Var(_) | Expr::Ret | Expr::Unit | Expr::It
-            | Expr::Continue | Expr::Todo { .. } => {}
+            | Expr::Continue { .. } | Expr::Todo { .. } => {}
         }
     }
 
@@ -473,7 +713,7 @@ impl ContractVerifier {
     ) -> bool {
         // Set up SMT context
         let mut generator = SmtLibGenerator::new();
-        let mut translator = SmtTranslator::new();
+        let mut translator = self.translator();
 
         // Declare the callee's parameters
         translator.setup_function(callee, &mut generator);
@@ -539,9 +779,7 @@ impl ContractVerifier {
             Ok(SolverResult::Unsat) => VerifyResult::Failed(Counterexample {
                 assignments: vec![("pre".to_string(), "unsatisfiable".to_string())],
             }),
-            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => {
-                VerifyResult::Unknown("solver timeout or unknown".to_string())
-            }
+            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
             Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
         }
     }
@@ -592,9 +830,7 @@ impl ContractVerifier {
             Ok(SolverResult::Sat(model)) => {
                 VerifyResult::Failed(Counterexample::from_model(model))
             }
-            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => {
-                VerifyResult::Unknown("solver timeout or unknown".to_string())
-            }
+            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
             Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
         }
     }
@@ -650,9 +886,7 @@ impl ContractVerifier {
             Ok(SolverResult::Sat(model)) => {
                 VerifyResult::Failed(Counterexample::from_model(model))
             }
-            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => {
-                VerifyResult::Unknown("solver timeout or unknown".to_string())
-            }
+            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
             Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
         }
     }
@@ -714,9 +948,47 @@ impl ContractVerifier {
             Ok(SolverResult::Sat(model)) => {
                 VerifyResult::Failed(Counterexample::from_model(model))
             }
-            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => {
-                VerifyResult::Unknown("solver timeout or unknown".to_string())
+            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
+            Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
+        }
+    }
+
+    /// Verify a refinement obligation: Check that pre → condition at the
+    /// point the value is passed
+    fn verify_refinement_obligation(
+        &self,
+        translator: &SmtTranslator,
+        base_generator: &SmtLibGenerator,
+        obligation: &RefinementObligation,
+        func: &FnDef,
+    ) -> VerifyResult {
+        let mut generator = base_generator.clone();
+
+        // If there's a pre-condition, assert it
+        if let Some(pre) = &func.pre {
+            let pre_smt = match translator.translate(pre) {
+                Ok(s) => s,
+                Err(e) => return VerifyResult::Unknown(format!("pre translation error: {}", e)),
+            };
+            generator.assert(&pre_smt);
+        }
+
+        let condition_smt = match translator.translate(&obligation.condition) {
+            Ok(s) => s,
+            Err(e) => return VerifyResult::Unknown(format!("refinement translation error: {}", e)),
+        };
+
+        // Assert negation of condition (to find counterexample)
+        generator.assert(&format!("(not {})", condition_smt));
+
+        let script = generator.generate();
+
+        match self.solver.solve(&script) {
+            Ok(SolverResult::Unsat) => VerifyResult::Verified,
+            Ok(SolverResult::Sat(model)) => {
+                VerifyResult::Failed(Counterexample::from_model(model))
             }
+            Ok(SolverResult::Unknown) | Ok(SolverResult::Timeout) => undecided(&script),
             Err(e) => VerifyResult::Unknown(format!("solver error: {}", e)),
         }
     }
@@ -728,6 +1000,17 @@ impl Default for ContractVerifier {
     }
 }
 
+/// Result of a query the solver gave up on. Quantified queries are
+/// undecidable in general, so giving up on one says nothing about the
+/// contract
+fn undecided(script: &str) -> VerifyResult {
+    if script.contains("(forall ") || script.contains("(exists ") {
+        VerifyResult::Unknown("inconclusive (quantifier)".to_string())
+    } else {
+        VerifyResult::Unknown("solver timeout or unknown".to_string())
+    }
+}
+
 /// Report for an entire program's verification
 #[derive(Debug)]
 pub struct VerificationReport {
@@ -741,19 +1024,44 @@ impl VerificationReport {
         }
     }
 
-    /// Check if all verifications passed
+    /// Check if all verifications passed; skipped functions are not counted
     pub fn all_verified(&self) -> bool {
-        self.functions.iter().all(|f| f.is_verified())
+        self.checked().all(|f| f.is_verified())
     }
 
-    /// Get number of verified functions
+    /// Get number of functions proved in this run
     pub fn verified_count(&self) -> usize {
-        self.functions.iter().filter(|f| f.is_verified()).count()
+        self.checked().filter(|f| f.is_verified() && !f.cached).count()
     }
 
-    /// Get number of failed functions
+    /// Get number of failed functions, including failures read from the cache
     pub fn failed_count(&self) -> usize {
-        self.functions.iter().filter(|f| f.has_failure()).count()
+        self.checked().filter(|f| f.has_failure()).count()
+    }
+
+    /// Get number of functions whose proof came from the verification cache
+    pub fn cached_count(&self) -> usize {
+        self.checked().filter(|f| f.is_verified() && f.cached).count()
+    }
+
+    /// Get number of functions left out by the function selection
+    pub fn skipped_count(&self) -> usize {
+        self.functions.iter().filter(|f| f.skipped).count()
+    }
+
+    /// Get number of functions the solver could not decide
+    pub fn unknown_count(&self) -> usize {
+        self.checked().filter(|f| !f.is_verified() && !f.has_failure()).count()
+    }
+
+    /// One structured entry per function, for machine-readable reports
+    pub fn entries(&self) -> Vec<FunctionEntry> {
+        self.functions.iter().map(FunctionReport::entry).collect()
+    }
+
+    /// Reports of the functions that were verified or read from the cache
+    fn checked(&self) -> impl Iterator<Item = &FunctionReport> {
+        self.functions.iter().filter(|f| !f.skipped)
     }
 }
 
@@ -771,23 +1079,25 @@ impl std::fmt::Display for VerificationReport {
 
         writeln!(f)?;
         if self.all_verified() {
-            writeln!(f, "All {} function(s) verified successfully.", self.functions.len())?;
-        } else {
-            writeln!(
-                f,
-                "Verified: {}/{}, Failed: {}",
-                self.verified_count(),
-                self.functions.len(),
-                self.failed_count()
-            )?;
+            writeln!(f, "All {} function(s) verified successfully.", self.checked().count())?;
+        }
+        write!(
+            f,
+            "Verified: {}, Cached: {}, Failed: {}, Skipped: {}",
+            self.verified_count(),
+            self.cached_count(),
+            self.failed_count(),
+            self.skipped_count()
+        )?;
+        match self.unknown_count() {
+            0 => writeln!(f),
+            unknown => writeln!(f, ", Unknown: {}", unknown),
         }
-
-        Ok(())
     }
 }
 
 /// Report for a single function's verification
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionReport {
     pub name: String,
     pub pre_result: Option<VerifyResult>,
@@ -801,6 +1111,57 @@ pub struct FunctionReport {
     pub trusted: bool,
     /// v0.31: Warnings (e.g., duplicate contracts)
     pub warnings: Vec<String>,
+    /// Array bounds results (index expression span, index expression, result)
+    pub bounds_results: Vec<(Span, String, VerifyResult)>,
+    /// Nonzero divisor results (division expression span, division expression, result)
+    pub division_results: Vec<(Span, String, VerifyResult)>,
+    /// Loop verification conditions (invariant or exit span, condition, result)
+    pub loop_results: Vec<(Span, LoopCheck, VerifyResult)>,
+    /// Whether this report was read from the verification cache
+    #[serde(skip)]
+    pub cached: bool,
+    /// Whether the function was left out by `with_functions`
+    #[serde(skip)]
+    pub skipped: bool,
+}
+
+/// The Hoare-logic conditions a `while ... invariant` loop is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoopCheck {
+    /// The invariant holds when the loop is entered
+    Entry,
+    /// The body, run where the invariant and the condition hold,
+    /// re-establishes the invariant
+    Preserved,
+    /// The invariant and the negated condition imply the postcondition
+    Exit,
+}
+
+impl LoopCheck {
+    /// Stable name used in reports and JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            LoopCheck::Entry => "entry",
+            LoopCheck::Preserved => "preserved",
+            LoopCheck::Exit => "exit",
+        }
+    }
+
+    fn holds(self) -> &'static str {
+        match self {
+            LoopCheck::Entry => "loop invariant holds on entry",
+            LoopCheck::Preserved => "loop invariant preserved by the body",
+            LoopCheck::Exit => "postcondition holds after the loop",
+        }
+    }
+
+    fn violated(self) -> &'static str {
+        match self {
+            LoopCheck::Entry => "loop invariant may not hold on entry",
+            LoopCheck::Preserved => "loop invariant may not be preserved by the body",
+            LoopCheck::Exit => "postcondition may not hold after the loop",
+        }
+    }
 }
 
 impl FunctionReport {
@@ -814,9 +1175,19 @@ impl FunctionReport {
             message: None,
             trusted: false,
             warnings: Vec::new(),
+            bounds_results: Vec::new(),
+            division_results: Vec::new(),
+            loop_results: Vec::new(),
+            cached: false,
+            skipped: false,
         }
     }
 
+    /// Report for a function left out of verification
+    pub fn skipped(name: String) -> Self {
+        Self { skipped: true, ..Self::new(name) }
+    }
+
     /// Check if function is fully verified
     pub fn is_verified(&self) -> bool {
         let pre_ok = matches!(&self.pre_result, Some(VerifyResult::Verified));
@@ -827,7 +1198,13 @@ impl FunctionReport {
         // v0.2: Check refinement type constraints
         let refinements_ok = self.refinement_results.iter()
             .all(|(_, result)| matches!(result, VerifyResult::Verified));
-        pre_ok && post_ok && contracts_ok && refinements_ok
+        let bounds_ok = self.bounds_results.iter()
+            .all(|(_, _, result)| matches!(result, VerifyResult::Verified));
+        let division_ok = self.division_results.iter()
+            .all(|(_, _, result)| matches!(result, VerifyResult::Verified));
+        let loops_ok = self.loop_results.iter()
+            .all(|(_, _, result)| matches!(result, VerifyResult::Verified));
+        pre_ok && post_ok && contracts_ok && refinements_ok && bounds_ok && division_ok && loops_ok
     }
 
     /// Check if function has any failure
@@ -840,11 +1217,166 @@ impl FunctionReport {
             // v0.2: Check refinement type constraints
             || self.refinement_results.iter()
                 .any(|(_, result)| matches!(result, VerifyResult::Failed(_)))
+            || self.bounds_results.iter()
+                .any(|(_, _, result)| matches!(result, VerifyResult::Failed(_)))
+            || self.division_results.iter()
+                .any(|(_, _, result)| matches!(result, VerifyResult::Failed(_)))
+            || self.loop_results.iter()
+                .any(|(_, _, result)| matches!(result, VerifyResult::Failed(_)))
+    }
+}
+
+/// Overall verdict for one function in a verification report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionStatus {
+    /// Every condition was proved in this run
+    Verified,
+    /// Proved by an earlier run and unchanged since
+    Cached,
+    /// Accepted without proof via `@trust`
+    Trusted,
+    /// At least one condition has a counterexample
+    Failed,
+    /// The solver could not decide some condition
+    Unknown,
+    /// Left out by the function selection
+    Skipped,
+}
+
+/// The kind of condition a verification check proves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionKind {
+    Precondition,
+    Postcondition,
+    /// Named contract from a `where {}` block
+    Contract,
+    /// Refinement type constraint
+    Refinement,
+    /// Array index in bounds (`--check-bounds`)
+    Bounds,
+    /// Nonzero divisor (`--check-division`)
+    Division,
+    /// Loop invariant entry, preservation or exit condition
+    Invariant,
+}
+
+/// Solver verdict for a single condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionStatus {
+    Verified,
+    Failed,
+    Unknown,
+    Unavailable,
+}
+
+/// One checked condition of a function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionEntry {
+    pub kind: ConditionKind,
+    /// What was checked within the function: contract name, refined
+    /// location, index or division expression, or loop check name
+    pub subject: Option<String>,
+    /// Source span of the checked expression, where the check has one
+    pub span: Option<Span>,
+    pub status: ConditionStatus,
+    /// Why the solver gave up, for `unknown`
+    pub reason: Option<String>,
+    /// Model the solver found violating the condition, for `failed`
+    pub counterexample: Option<BTreeMap<String, String>>,
+}
+
+impl ConditionEntry {
+    fn new(kind: ConditionKind, subject: Option<String>, span: Option<Span>, result: &VerifyResult) -> Self {
+        let (status, reason, counterexample) = match result {
+            VerifyResult::Verified => (ConditionStatus::Verified, None, None),
+            VerifyResult::Failed(ce) => {
+                (ConditionStatus::Failed, None, Some(ce.assignments.iter().cloned().collect()))
+            }
+            VerifyResult::Unknown(msg) => (ConditionStatus::Unknown, Some(msg.clone()), None),
+            VerifyResult::SolverNotAvailable => (ConditionStatus::Unavailable, None, None),
+        };
+        ConditionEntry { kind, subject, span, status, reason, counterexample }
+    }
+}
+
+/// Structured per-function entry of a verification report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionEntry {
+    pub name: String,
+    pub status: FunctionStatus,
+    /// Every condition checked, in report order
+    pub conditions: Vec<ConditionEntry>,
+    pub warnings: Vec<String>,
+}
+
+impl FunctionReport {
+    /// Overall verdict, as shown in machine-readable reports
+    pub fn status(&self) -> FunctionStatus {
+        if self.skipped {
+            FunctionStatus::Skipped
+        } else if self.has_failure() {
+            FunctionStatus::Failed
+        } else if !self.is_verified() {
+            FunctionStatus::Unknown
+        } else if self.trusted {
+            FunctionStatus::Trusted
+        } else if self.cached {
+            FunctionStatus::Cached
+        } else {
+            FunctionStatus::Verified
+        }
+    }
+
+    /// Structured form of this report
+    pub fn entry(&self) -> FunctionEntry {
+        use ConditionKind::*;
+        let mut conditions = Vec::new();
+        // A trusted function's pre/post results are placeholders, not proofs
+        if !self.trusted {
+            if let Some(result) = &self.pre_result {
+                conditions.push(ConditionEntry::new(Precondition, None, None, result));
+            }
+            if let Some(result) = &self.post_result {
+                conditions.push(ConditionEntry::new(Postcondition, None, None, result));
+            }
+        }
+        for (name, result) in &self.contract_results {
+            conditions.push(ConditionEntry::new(Contract, name.clone(), None, result));
+        }
+        for (location, result) in &self.refinement_results {
+            conditions.push(ConditionEntry::new(Refinement, Some(location.clone()), None, result));
+        }
+        for (span, index, result) in &self.bounds_results {
+            conditions.push(ConditionEntry::new(Bounds, Some(index.clone()), Some(*span), result));
+        }
+        for (span, division, result) in &self.division_results {
+            conditions.push(ConditionEntry::new(Division, Some(division.clone()), Some(*span), result));
+        }
+        for (span, check, result) in &self.loop_results {
+            conditions.push(ConditionEntry::new(Invariant, Some(check.name().to_string()), Some(*span), result));
+        }
+        FunctionEntry {
+            name: self.name.clone(),
+            status: self.status(),
+            conditions,
+            warnings: self.warnings.clone(),
+        }
     }
 }
 
 impl std::fmt::Display for FunctionReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.skipped {
+            return writeln!(f, "- {}: skipped", self.name);
+        }
+        // Unchanged since a run that proved it; failures are shown in full
+        if self.cached && self.is_verified() {
+            return writeln!(f, "✓ {}: cached: verified", self.name);
+        }
+
         // Pre-condition result
         if let Some(ref result) = self.pre_result {
             match result {
@@ -918,6 +1450,71 @@ impl std::fmt::Display for FunctionReport {
             }
         }
 
+        // Array bounds results (--check-bounds)
+        for (span, index, result) in &self.bounds_results {
+            match result {
+                VerifyResult::Verified => {
+                    writeln!(f, "✓ {}: index `{}` in bounds", self.name, index)?
+                }
+                VerifyResult::Failed(ce) => {
+                    writeln!(
+                        f,
+                        "✗ {}: index `{}` may be out of bounds (at {}..{})",
+                        self.name, index, span.start, span.end
+                    )?;
+                    write!(f, "  {}", ce)?;
+                }
+                VerifyResult::Unknown(msg) => {
+                    writeln!(f, "? {}: index `{}` unknown ({})", self.name, index, msg)?
+                }
+                VerifyResult::SolverNotAvailable => {
+                    writeln!(f, "! {}: solver not available for index `{}`", self.name, index)?
+                }
+            }
+        }
+
+        // Nonzero divisor results (--check-division)
+        for (span, division, result) in &self.division_results {
+            match result {
+                VerifyResult::Verified => {
+                    writeln!(f, "✓ {}: divisor of `{}` nonzero", self.name, division)?
+                }
+                VerifyResult::Failed(ce) => {
+                    writeln!(
+                        f,
+                        "✗ {}: `{}` may divide by zero (at {}..{})",
+                        self.name, division, span.start, span.end
+                    )?;
+                    write!(f, "  {}", ce)?;
+                }
+                VerifyResult::Unknown(msg) => {
+                    writeln!(f, "? {}: divisor of `{}` unknown ({})", self.name, division, msg)?
+                }
+                VerifyResult::SolverNotAvailable => {
+                    writeln!(f, "! {}: solver not available for divisor of `{}`", self.name, division)?
+                }
+            }
+        }
+
+        // Loop invariant verification conditions
+        for (span, check, result) in &self.loop_results {
+            match result {
+                VerifyResult::Verified => {
+                    writeln!(f, "✓ {}: {} (at {}..{})", self.name, check.holds(), span.start, span.end)?
+                }
+                VerifyResult::Failed(ce) => {
+                    writeln!(f, "✗ {}: {} (at {}..{})", self.name, check.violated(), span.start, span.end)?;
+                    write!(f, "  {}", ce)?;
+                }
+                VerifyResult::Unknown(msg) => {
+                    writeln!(f, "? {}: loop {} unknown ({})", self.name, check.name(), msg)?
+                }
+                VerifyResult::SolverNotAvailable => {
+                    writeln!(f, "! {}: solver not available for loop {}", self.name, check.name())?
+                }
+            }
+        }
+
         // Optional message
         if let Some(ref msg) = self.message {
             writeln!(f, "  Note: {}", msg)?;
@@ -1019,6 +1616,53 @@ mod tests {
         assert!(!report.all_verified());
     }
 
+    #[test]
+    fn test_function_selection_skips_the_rest() {
+        let source = "fn a() -> i64 = 1;\n@trust \"by hand\"\nfn b(x: i64) -> i64 post ret > x = x + 1;\nfn c() -> i64 = 3;";
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let mut verifier = ContractVerifier::new().with_functions(&["b".to_string()]);
+        let report = verifier.verify_program(&program);
+
+        let skipped: Vec<_> = report.functions.iter().filter(|f| f.skipped).map(|f| f.name.as_str()).collect();
+        assert_eq!(skipped, ["a", "c"]);
+        assert!(report.all_verified());
+        assert_eq!(report.verified_count(), 1);
+        assert_eq!(report.skipped_count(), 2);
+        assert!(report.to_string().contains("- a: skipped"));
+    }
+
+    #[test]
+    fn test_report_totals_separate_cached_and_skipped() {
+        let verified = |name: &str| {
+            let mut report = FunctionReport::new(name.to_string());
+            report.pre_result = Some(VerifyResult::Verified);
+            report.post_result = Some(VerifyResult::Verified);
+            report
+        };
+        let mut cached = verified("c");
+        cached.cached = true;
+        let mut cached_failure = verified("d");
+        cached_failure.cached = true;
+        cached_failure.post_result = Some(VerifyResult::Failed(Counterexample { assignments: vec![] }));
+        let mut unknown = verified("e");
+        unknown.post_result = Some(VerifyResult::Unknown("timeout".to_string()));
+
+        let report = VerificationReport {
+            functions: vec![verified("a"), FunctionReport::skipped("b".to_string()), cached, cached_failure, unknown],
+        };
+        assert_eq!(report.verified_count(), 1);
+        assert_eq!(report.cached_count(), 1);
+        assert_eq!(report.failed_count(), 1);
+        assert_eq!(report.skipped_count(), 1);
+        assert_eq!(report.unknown_count(), 1);
+
+        let text = report.to_string();
+        assert!(text.contains("✓ c: cached: verified"), "{text}");
+        assert!(text.contains("✗ d: post verification failed"), "{text}");
+        assert!(text.contains("Verified: 1, Cached: 1, Failed: 1, Skipped: 1, Unknown: 1"), "{text}");
+    }
+
     #[test]
     fn test_verify_function_no_contracts() {
         let verifier = ContractVerifier::new();
@@ -1309,4 +1953,376 @@ mod tests {
             "Satisfiable precondition should not be flagged as dead code"
         );
     }
+
+    /// Parse a program and return its function named `name`
+    fn parse_fn(source: &str, name: &str) -> FnDef {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        program.items.into_iter()
+            .find_map(|item| match item {
+                Item::FnDef(func) if func.name.node == name => Some(func),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    fn path_queries(func: &FnDef, checks: PathChecks) -> Vec<(ObligationKind, Result<String, String>)> {
+        let (translator, generator) = ContractVerifier::new().setup(func);
+        path_obligations(func, &translator, &generator, checks)
+            .obligations
+            .into_iter()
+            .map(|o| (o.kind, o.query))
+            .collect()
+    }
+
+    fn bounds_queries(func: &FnDef) -> Vec<(String, Result<String, String>)> {
+        path_queries(func, PathChecks { bounds: true, ..PathChecks::default() })
+            .into_iter()
+            .filter_map(|(kind, query)| match kind {
+                ObligationKind::Bounds(text) => Some((text, query)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bounds_obligations_assume_pre_and_invariant() {
+        let func = parse_fn(
+            "fn sum(xs: [i64; 4], n: i64) -> i64
+               pre n <= xs.len()
+             = {
+                 let mut s: i64 = 0;
+                 let mut i: i64 = 0;
+                 while i < n invariant i >= 0 { { s = s + xs[i]; i = i + 1 } };
+                 s
+             };",
+            "sum",
+        );
+        let queries = bounds_queries(&func);
+        assert_eq!(queries.len(), 1);
+        let (text, query) = &queries[0];
+        assert_eq!(text, "xs[i]");
+        let script = query.as_ref().unwrap();
+        assert!(script.contains("(assert (= xs.len 4))"));
+        assert!(script.contains("(assert (<= n xs.len))"));
+        assert!(script.contains("(=> (>= i 0) (=> (< i n)"));
+        assert!(script.contains("(< i xs.len)"));
+    }
+
+    #[test]
+    fn test_bounds_obligation_unknown_length() {
+        let func = parse_fn(
+            "fn first(v: i64) -> i64 = get_arr(v)[0];
+             fn get_arr(v: i64) -> [i64; 2] = [v, v];",
+            "first",
+        );
+        let queries = bounds_queries(&func);
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].1.as_ref().unwrap_err().contains("unknown length"));
+    }
+
+    #[test]
+    fn test_function_report_bounds_failure() {
+        let mut report = FunctionReport::new("f".to_string());
+        report.pre_result = Some(VerifyResult::Verified);
+        report.post_result = Some(VerifyResult::Verified);
+        assert!(report.is_verified());
+
+        report.bounds_results.push((
+            Span { start: 10, end: 15 },
+            "xs[i]".to_string(),
+            VerifyResult::Failed(Counterexample { assignments: vec![("i".to_string(), "4".to_string())] }),
+        ));
+        assert!(!report.is_verified());
+        assert!(report.has_failure());
+        assert!(report.to_string().contains("index `xs[i]` may be out of bounds (at 10..15)"));
+    }
+
+    #[test]
+    fn test_verify_bounds() {
+        let verifier = ContractVerifier::new().with_bounds_checking(true);
+        if !verifier.is_solver_available() {
+            return;
+        }
+
+        let func = parse_fn(
+            "fn get(xs: [i64; 3], i: i64) -> i64
+               pre i >= 0 and i < 3
+             = if i > 0 { xs[i - 1] } else { xs[i] };",
+            "get",
+        );
+        let report = verifier.verify_function(&func);
+        assert_eq!(report.bounds_results.len(), 2);
+        assert!(report.is_verified(), "{}", report);
+
+        let func = parse_fn("fn get(xs: [i64; 3], i: i64) -> i64 = xs[i];", "get");
+        let report = verifier.verify_function(&func);
+        assert!(matches!(report.bounds_results[0].2, VerifyResult::Failed(_)));
+    }
+
+    #[test]
+    fn test_division_obligations_skip_literals_and_floats() {
+        let func = parse_fn(
+            "fn f(a: i64, b: i64, x: f64, y: f64) -> i64
+               pre b != 0
+             = {
+                 let half = a / 2;
+                 let ratio = x / y;
+                 let c: i64 = b - 1;
+                 half + a % b + a / c
+             };",
+            "f",
+        );
+        let queries: Vec<_> = path_queries(&func, PathChecks { division: true, ..PathChecks::default() })
+            .into_iter()
+            .map(|(kind, query)| (kind, query.unwrap()))
+            .collect();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].0, ObligationKind::Division("a % b".to_string()));
+        assert!(queries[0].1.contains("(assert (not (= b 0)))"));
+        assert!(queries[0].1.contains("(let ((c (- b 1))) (not (= b 0)))"));
+        assert_eq!(queries[1].0, ObligationKind::Division("a / c".to_string()));
+        assert!(queries[1].1.contains("(let ((c (- b 1))) (not (= c 0)))"));
+
+        // A refined parameter is proven nonzero by the callers
+        let func = parse_fn("fn divide(a: i64, b: i64{it != 0}) -> i64 = a / b;", "divide");
+        let queries = path_queries(&func, PathChecks { division: true, ..PathChecks::default() });
+        assert!(queries[0].1.as_ref().unwrap().contains("(assert (not (= b 0)))"));
+    }
+
+    #[test]
+    fn test_verify_division() {
+        let verifier = ContractVerifier::new().with_division_checking(true);
+        if !verifier.is_solver_available() {
+            return;
+        }
+
+        let func = parse_fn("fn divide(a: i64, b: i64) -> i64\n  pre b != 0\n= a / b;", "divide");
+        let report = verifier.verify_function(&func);
+        assert_eq!(report.division_results.len(), 1);
+        assert!(report.is_verified(), "{}", report);
+
+        let func = parse_fn("fn divide(a: i64, b: i64) -> i64 = a / b;", "divide");
+        let report = verifier.verify_function(&func);
+        let (span, text, result) = &report.division_results[0];
+        assert_eq!((span.start, span.end, text.as_str()), (35, 40, "a / b"));
+        let VerifyResult::Failed(ce) = result else { panic!("{}", report) };
+        assert!(ce.assignments.contains(&("b".to_string(), "0".to_string())), "{}", report);
+        assert!(report.to_string().contains("`a / b` may divide by zero (at 35..40)"));
+    }
+
+    const SUM_TO: &str = "fn sum_to(n: i64) -> i64
+           pre n >= 0
+           post ret >= 0
+         = {
+             let mut s: i64 = 0;
+             let mut i: i64 = 0;
+             while i < n invariant i >= 0 and s >= 0 { { s = s + i; i = i + 1 } };
+             s
+         };";
+
+    #[test]
+    fn test_loop_obligations() {
+        let func = parse_fn(SUM_TO, "sum_to");
+        let queries = path_queries(&func, PathChecks { loops: true, post: true, ..PathChecks::default() });
+        let kinds: Vec<_> = queries.iter().map(|(kind, _)| kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![ObligationKind::InvariantEntry, ObligationKind::InvariantPreserved, ObligationKind::Postcondition]
+        );
+        let scripts: Vec<_> = queries.into_iter().map(|(_, query)| query.unwrap()).collect();
+
+        // Entry: the initial values reach the invariant
+        assert!(scripts[0].contains("(assert (>= n 0))"));
+        assert!(scripts[0].contains("(not (let ((s 0)) (let ((i 0)) (and (>= i 0) (>= s 0)))))"));
+
+        // Preservation: from a havocked state satisfying the invariant and
+        // condition, the body's assignments are applied in order
+        assert!(scripts[1].contains("(declare-const i!1 Int)"));
+        assert!(scripts[1].contains("(=> (and (>= i 0) (>= s 0)) (=> (< i n) (let ((s (+ s i))) (let ((i (+ i 1))) (and (>= i 0) (>= s 0))))))"));
+
+        // Exit: the invariant and negated condition establish the postcondition
+        assert!(scripts[2].contains("(=> (not (< i n)) (let ((__ret__ s)) (>= __ret__ 0)))"));
+    }
+
+    #[test]
+    fn test_loop_obligations_track_branches_and_shadowing() {
+        let func = parse_fn(
+            "fn f(x: i64) -> i64
+               post ret >= 1
+             = {
+                 let mut y: i64 = 0;
+                 if x > 0 { { y = x } } else { { y = 1 } };
+                 let y: i64 = y + 1;
+                 while false invariant y >= 1 { () };
+                 y
+             };",
+            "f",
+        );
+        let queries = path_queries(&func, PathChecks { loops: true, ..PathChecks::default() });
+        let entry = queries[0].1.as_ref().unwrap();
+        assert!(entry.contains("(let ((y (ite (> x 0) (let ((y x)) y) (let ((y 1)) y))))"));
+        assert!(entry.contains("(let ((y!1 y)) (let ((y (+ y 1)))"));
+    }
+
+    #[test]
+    fn test_function_report_loop_failure() {
+        let mut report = FunctionReport::new("f".to_string());
+        report.pre_result = Some(VerifyResult::Verified);
+        report.post_result = Some(VerifyResult::Verified);
+        report.loop_results.push((Span { start: 3, end: 9 }, LoopCheck::Entry, VerifyResult::Verified));
+        assert!(report.is_verified());
+
+        report.loop_results.push((
+            Span { start: 3, end: 9 },
+            LoopCheck::Preserved,
+            VerifyResult::Failed(Counterexample { assignments: vec![("i!1".to_string(), "-1".to_string())] }),
+        ));
+        assert!(!report.is_verified());
+        assert!(report.has_failure());
+        let text = report.to_string();
+        assert!(text.contains("✓ f: loop invariant holds on entry (at 3..9)"));
+        assert!(text.contains("✗ f: loop invariant may not be preserved by the body (at 3..9)"));
+    }
+
+    #[test]
+    fn test_verify_loop_invariant() {
+        let verifier = ContractVerifier::new();
+        if !verifier.is_solver_available() {
+            return;
+        }
+
+        let func = parse_fn(SUM_TO, "sum_to");
+        let report = verifier.verify_function(&func);
+        assert_eq!(report.loop_results.len(), 3);
+        assert!(report.is_verified(), "{}", report);
+
+        let weak = SUM_TO.replace("invariant i >= 0 and s >= 0", "invariant i >= 0");
+        let report = verifier.verify_function(&parse_fn(&weak, "sum_to"));
+        assert!(matches!(report.loop_results[2], (_, LoopCheck::Exit, VerifyResult::Failed(_))));
+        assert!(matches!(report.post_result, Some(VerifyResult::Failed(_))));
+
+        let wrong = SUM_TO.replace("invariant i >= 0 and s >= 0", "invariant i >= 0 and s >= i");
+        let report = verifier.verify_function(&parse_fn(&wrong, "sum_to"));
+        assert!(matches!(report.loop_results[1], (_, LoopCheck::Preserved, VerifyResult::Failed(_))));
+    }
+
+    const INCREMENT: &str = "fn increment(x: i64) -> i64
+           post x == old(x) + 1 and ret == x
+         = {
+             x = x + 1;
+             x
+         };";
+
+    #[test]
+    fn test_old_obligations_use_entry_values() {
+        let func = parse_fn(INCREMENT, "increment");
+        let queries = path_queries(&func, PathChecks { post: true, ..PathChecks::default() });
+        let script = queries[0].1.as_ref().unwrap();
+        assert!(script.contains("(declare-const x.pre Int)"));
+        assert!(script.contains("(assert (= x.pre x))"));
+        // The exit value of `x` is compared against its entry snapshot
+        assert!(script.contains("(let ((x (+ x 1))) (let ((__ret__ x)) (and (= x (+ x.pre 1)) (= __ret__ x))))"));
+    }
+
+    #[test]
+    fn test_verify_old_increment() {
+        let verifier = ContractVerifier::new();
+        if !verifier.is_solver_available() {
+            return;
+        }
+
+        let report = verifier.verify_function(&parse_fn(INCREMENT, "increment"));
+        assert!(report.is_verified(), "{}", report);
+
+        let wrong = INCREMENT.replace("old(x) + 1", "old(x) + 2");
+        let report = verifier.verify_function(&parse_fn(&wrong, "increment"));
+        assert!(matches!(report.post_result, Some(VerifyResult::Failed(_))));
+    }
+
+    #[test]
+    fn test_undecided_quantifier_is_inconclusive() {
+        let quantified = undecided("(assert (not (forall ((i Int)) (< i __ret__))))");
+        assert!(matches!(quantified, VerifyResult::Unknown(msg) if msg == "inconclusive (quantifier)"));
+        let plain = undecided("(assert (not (< n __ret__)))");
+        assert!(matches!(plain, VerifyResult::Unknown(msg) if msg == "solver timeout or unknown"));
+    }
+
+    #[test]
+    fn test_verify_quantified_postconditions() {
+        let verifier = ContractVerifier::new().with_timeout(2);
+        if !verifier.is_solver_available() {
+            return;
+        }
+
+        let func = parse_fn(
+            "fn below(n: i64) -> i64
+               pre n >= 0
+               post forall i: i64, (0 <= i and i < n) implies i < ret
+             = n;",
+            "below",
+        );
+        let report = verifier.verify_function(&func);
+        assert!(report.is_verified(), "{}", report);
+
+        let func = parse_fn("fn below(n: i64) -> i64 post forall i: i64, i < n implies i < ret = n - 1;", "below");
+        let report = verifier.verify_function(&func);
+        assert!(matches!(report.post_result, Some(VerifyResult::Failed(_))), "{}", report);
+
+        // Nonlinear arithmetic under quantifiers: the solver gives up or times out
+        let func = parse_fn(
+            "fn cubes(n: i64) -> i64
+               post forall x: i64, (forall y: i64, (forall z: i64,
+                 (x > 0 and y > 0 and z > 0) implies x * x * x + y * y * y != z * z * z))
+             = n;",
+            "cubes",
+        );
+        let report = verifier.verify_function(&func);
+        assert!(
+            matches!(&report.post_result, Some(VerifyResult::Unknown(msg)) if msg == "inconclusive (quantifier)"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_report_entries_json_shape() {
+        let mut passed = FunctionReport::new("passed".to_string());
+        passed.pre_result = Some(VerifyResult::Verified);
+        passed.post_result = Some(VerifyResult::Verified);
+
+        let mut failed = FunctionReport::new("failed".to_string());
+        failed.pre_result = Some(VerifyResult::Verified);
+        failed.post_result = Some(VerifyResult::Failed(Counterexample {
+            assignments: vec![("x".to_string(), "0".to_string())],
+        }));
+        failed.loop_results.push((
+            Span { start: 10, end: 20 },
+            LoopCheck::Preserved,
+            VerifyResult::Unknown("solver timeout or unknown".to_string()),
+        ));
+
+        let mut report = VerificationReport::new();
+        report.functions = vec![passed, failed, FunctionReport::skipped("other".to_string())];
+        let json = serde_json::to_value(report.entries()).unwrap();
+
+        assert_eq!(json[0]["name"], "passed");
+        assert_eq!(json[0]["status"], "verified");
+        assert_eq!(json[0]["conditions"].as_array().unwrap().len(), 2);
+
+        assert_eq!(json[1]["status"], "failed");
+        let post = &json[1]["conditions"][1];
+        assert_eq!(post["kind"], "postcondition");
+        assert_eq!(post["status"], "failed");
+        assert_eq!(post["counterexample"], serde_json::json!({ "x": "0" }));
+        let invariant = &json[1]["conditions"][2];
+        assert_eq!(invariant["kind"], "invariant");
+        assert_eq!(invariant["subject"], "preserved");
+        assert_eq!(invariant["status"], "unknown");
+        assert_eq!(invariant["span"], serde_json::json!({ "start": 10, "end": 20 }));
+        assert!(invariant["counterexample"].is_null());
+
+        assert_eq!(json[2]["status"], "skipped");
+        assert!(json[2]["conditions"].as_array().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,337 @@
+//! Counterexample-driven test generation
+//!
+//! Turns falsifying models from failed verification into `bmb test` files
+//! that call the function with the counterexample arguments and evaluate
+//! the violated condition, so the failure can be reproduced and kept as a
+//! regression test once fixed.
+
+use std::collections::HashMap;
+
+use crate::ast::{Expr, FnDef, Item, Program, Spanned, Type};
+use crate::fmt::{format_expr, format_type};
+use crate::smt::{Counterexample, VerifyResult};
+
+use super::VerificationReport;
+
+/// A generated test file for one function
+#[derive(Debug, Clone)]
+pub struct CounterexampleTest {
+    /// Function under test
+    pub function: String,
+    /// `test_<fn>_counterexample.bmb`
+    pub file_name: String,
+    /// Complete BMB source for the file
+    pub source: String,
+}
+
+/// Tests generated from a verification report
+#[derive(Debug, Default)]
+pub struct CounterexampleTests {
+    pub tests: Vec<CounterexampleTest>,
+    /// Functions with counterexamples that could not be turned into tests (name, reason)
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Generate a test file for every function whose verification produced a model
+///
+/// The generated file carries a copy of the program's items (minus its own
+/// `test_` functions) so it runs standalone under `bmb test`.
+pub fn generate_counterexample_tests(
+    program: &Program,
+    source: &str,
+    report: &VerificationReport,
+) -> CounterexampleTests {
+    let functions: HashMap<&str, &FnDef> = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::FnDef(func) => Some((func.name.node.as_str(), func)),
+            _ => None,
+        })
+        .collect();
+
+    let mut result = CounterexampleTests::default();
+    for func_report in &report.functions {
+        let Some(func) = functions.get(func_report.name.as_str()) else {
+            continue;
+        };
+        let name = &func_report.name;
+
+        // Each failed obligation paired with the condition it checks
+        let mut failures: Vec<(String, &Spanned<Expr>, &Counterexample)> = Vec::new();
+        if let (Some(VerifyResult::Failed(ce)), Some(post)) = (&func_report.post_result, &func.post) {
+            failures.push((format!("test_{}_counterexample", name), post, ce));
+        }
+        for (i, ((contract_name, result), contract)) in
+            func_report.contract_results.iter().zip(&func.contracts).enumerate()
+        {
+            if let VerifyResult::Failed(ce) = result {
+                let label = contract_name.clone().unwrap_or_else(|| format!("contract{}", i + 1));
+                failures.push((format!("test_{}_{}_counterexample", name, label), &contract.condition, ce));
+            }
+        }
+        if let Type::Refined { constraints, .. } = &func.ret_ty.node {
+            for (i, ((_, result), constraint)) in
+                func_report.refinement_results.iter().zip(constraints).enumerate()
+            {
+                if let VerifyResult::Failed(ce) = result {
+                    failures.push((format!("test_{}_refinement{}_counterexample", name, i + 1), constraint, ce));
+                }
+            }
+        }
+        if failures.is_empty() {
+            continue;
+        }
+
+        let mut tests = Vec::new();
+        for (test_name, condition, ce) in failures {
+            match counterexample_test(func, &test_name, condition, ce) {
+                Ok(test) => tests.push(test),
+                Err(reason) => {
+                    result.skipped.push((name.clone(), reason));
+                    tests.clear();
+                    break;
+                }
+            }
+        }
+        if tests.is_empty() {
+            continue;
+        }
+
+        let mut file = format!("// Counterexample tests for `{}` generated by `bmb verify --emit-tests`\n\n", name);
+        for item in &program.items {
+            if matches!(item, Item::FnDef(f) if f.name.node.starts_with("test_")) {
+                continue;
+            }
+            let span = item.span();
+            file.push_str(&source[span.start..span.end]);
+            file.push_str("\n\n");
+        }
+        file.push_str(&tests.join("\n"));
+
+        result.tests.push(CounterexampleTest {
+            function: name.clone(),
+            file_name: format!("test_{}_counterexample.bmb", name),
+            source: file,
+        });
+    }
+    result
+}
+
+/// Render one test function, or the reason it cannot be written
+fn counterexample_test(
+    func: &FnDef,
+    test_name: &str,
+    condition: &Spanned<Expr>,
+    ce: &Counterexample,
+) -> Result<String, String> {
+    if !func.type_params.is_empty() {
+        return Err("generic functions are not supported".to_string());
+    }
+
+    let mut bindings = Vec::new();
+    for param in &func.params {
+        let name = &param.name.node;
+        let value = ce.assignments.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        let literal = literal_for(&param.ty.node, value).map_err(|reason| {
+            format!("parameter `{}`: {}", name, reason)
+        })?;
+        bindings.push((name.clone(), literal));
+    }
+
+    // The return value is bound under the function's own name for it when it has one
+    let result_name = match &func.ret_name {
+        Some(ret_name) => ret_name.node.clone(),
+        None => {
+            let mut result_name = "result".to_string();
+            while func.params.iter().any(|p| p.name.node == result_name) {
+                result_name.push('_');
+            }
+            result_name
+        }
+    };
+    let check = bind_result(condition, &result_name)
+        .ok_or_else(|| "condition cannot be evaluated at runtime".to_string())?;
+
+    let shown: Vec<String> = bindings
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, format_expr(value)))
+        .collect();
+    let mut out = if shown.is_empty() {
+        "// Counterexample: (no arguments)\n".to_string()
+    } else {
+        format!("// Counterexample: {}\n", shown.join(", "))
+    };
+    out.push_str(&format!("fn {}() -> bool = {{\n", test_name));
+    for (name, value) in &bindings {
+        out.push_str(&format!("    let {} = {};\n", name, format_expr(value)));
+    }
+    let args: Vec<&str> = func.params.iter().map(|p| p.name.node.as_str()).collect();
+    out.push_str(&format!("    let {} = {}({});\n", result_name, func.name.node, args.join(", ")));
+    out.push_str(&format!("    {}\n}};\n", format_expr(&check.node)));
+    Ok(out)
+}
+
+/// A literal of type `ty` for the model value (unconstrained parameters get a default)
+fn literal_for(ty: &Type, value: Option<&str>) -> Result<Expr, String> {
+    let expr = match ty {
+        Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F64 => {
+            let n = match value {
+                Some(v) => model_int(v).ok_or_else(|| format!("unsupported model value `{}`", v))?,
+                None => 0,
+            };
+            let (min, max) = match ty {
+                Type::I32 => (i32::MIN as i128, i32::MAX as i128),
+                Type::U32 => (0, u32::MAX as i128),
+                Type::U64 => (0, u64::MAX as i128),
+                _ => (i64::MIN as i128 + 1, i64::MAX as i128),
+            };
+            if n < min || n > max {
+                return Err(format!("model value {} is out of range for {}", n, format_type(ty)));
+            }
+            if matches!(ty, Type::F64) {
+                Expr::FloatLit(n as f64)
+            } else {
+                Expr::IntLit(i64::try_from(n).map_err(|_| format!("model value {} does not fit in a literal", n))?)
+            }
+        }
+        Type::Bool => match value {
+            Some("true") => Expr::BoolLit(true),
+            Some("false") | None => Expr::BoolLit(false),
+            Some(v) => return Err(format!("unsupported model value `{}`", v)),
+        },
+        Type::Refined { base, .. } => return literal_for(base, value),
+        other => return Err(format!("type `{}` cannot be written as a literal", format_type(other))),
+    };
+    Ok(expr)
+}
+
+/// Parse a Z3 integer value: `5` or `(- 5)`
+fn model_int(value: &str) -> Option<i128> {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        let negated = inner.trim().strip_prefix('-')?;
+        return model_int(negated).map(|n| -n);
+    }
+    value.parse().ok()
+}
+
+/// Copy of `expr` with `ret` and `it` replaced by the variable `name`
+///
+/// Returns `None` for contract-only forms (quantifiers, `.pre`/`.post`)
+/// that have no runtime meaning.
+fn bind_result(expr: &Spanned<Expr>, name: &str) -> Option<Spanned<Expr>> {
+    let sub = |e: &Spanned<Expr>| bind_result(e, name).map(Box::new);
+    let all = |es: &[Spanned<Expr>]| es.iter().map(|e| bind_result(e, name)).collect::<Option<Vec<_>>>();
+    let node = match &expr.node {
+        Expr::Ret | Expr::It => Expr::Var(name.to_string()),
+        Expr::IntLit(_)
+        | Expr::FloatLit(_)
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::CharLit(_)
+        | Expr::Unit
+        | Expr::Var(_) => expr.node.clone(),
+        Expr::Binary { left, op, right } => Expr::Binary { left: sub(left)?, op: *op, right: sub(right)? },
+        Expr::Unary { op, expr } => Expr::Unary { op: *op, expr: sub(expr)? },
+        Expr::If { cond, then_branch, else_branch } => Expr::If {
+            cond: sub(cond)?,
+            then_branch: sub(then_branch)?,
+            else_branch: sub(else_branch)?,
+        },
+        Expr::Call { func, args } => Expr::Call { func: func.clone(), args: all(args)? },
+        Expr::MethodCall { receiver, method, args } => Expr::MethodCall {
+            receiver: sub(receiver)?,
+            method: method.clone(),
+            args: all(args)?,
+        },
+        Expr::FieldAccess { expr, field } => Expr::FieldAccess { expr: sub(expr)?, field: field.clone() },
+        Expr::TupleField { expr, index } => Expr::TupleField { expr: sub(expr)?, index: *index },
+        Expr::Index { expr, index } => Expr::Index { expr: sub(expr)?, index: sub(index)? },
+        Expr::Cast { expr, ty } => Expr::Cast { expr: sub(expr)?, ty: ty.clone() },
+        Expr::Tuple(elems) => Expr::Tuple(all(elems)?),
+        Expr::ArrayLit(elems) => Expr::ArrayLit(all(elems)?),
+        Expr::Block(stmts) => Expr::Block(all(stmts)?),
+        Expr::Let { name: var, mutable, ty, value, body } => Expr::Let {
+            name: var.clone(),
+            mutable: *mutable,
+            ty: ty.clone(),
+            value: sub(value)?,
+            body: sub(body)?,
+        },
+        Expr::LetStmt { name: var, mutable, ty, value } => Expr::LetStmt {
+            name: var.clone(),
+            mutable: *mutable,
+            ty: ty.clone(),
+            value: sub(value)?,
+        },
+        _ => return None,
+    };
+    Some(Spanned::new(node, expr.span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+    use crate::verify::FunctionReport;
+
+    fn failed(assignments: &[(&str, &str)]) -> VerifyResult {
+        VerifyResult::Failed(Counterexample {
+            assignments: assignments.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+        })
+    }
+
+    fn generate(source: &str, func_report: FunctionReport) -> CounterexampleTests {
+        let program = parse("test.bmb", source, tokenize(source).unwrap()).unwrap();
+        let report = VerificationReport { functions: vec![func_report] };
+        generate_counterexample_tests(&program, source, &report)
+    }
+
+    #[test]
+    fn test_post_counterexample_test() {
+        let source = "fn bad_abs(x: i64) -> i64\n  post ret >= 0\n= x;\n\nfn test_other() -> bool = true;\n";
+        let mut func_report = FunctionReport::new("bad_abs".to_string());
+        func_report.post_result = Some(failed(&[("__ret__", "(- 3)"), ("x", "(- 3)")]));
+
+        let generated = generate(source, func_report);
+        assert!(generated.skipped.is_empty());
+        let test = &generated.tests[0];
+        assert_eq!(test.file_name, "test_bad_abs_counterexample.bmb");
+        assert!(test.source.contains("fn bad_abs(x: i64) -> i64\n  post ret >= 0\n= x;"));
+        assert!(!test.source.contains("test_other"));
+        assert!(test.source.contains(
+            "fn test_bad_abs_counterexample() -> bool = {\n    let x = -3;\n    let result = bad_abs(x);\n    result >= 0\n};"
+        ));
+
+        // The generated file parses and fails the way verification predicted
+        let program = parse("gen.bmb", &test.source, tokenize(&test.source).unwrap()).unwrap();
+        let mut interp = crate::interp::Interpreter::new();
+        interp.load(&program);
+        assert_eq!(interp.get_test_functions(), ["test_bad_abs_counterexample"]);
+        assert!(matches!(
+            interp.run_function("test_bad_abs_counterexample"),
+            Ok(crate::interp::Value::Bool(false))
+        ));
+    }
+
+    #[test]
+    fn test_unrenderable_parameter_skipped() {
+        let source = "fn first(xs: &[i64; 3]) -> i64\n  post ret > 0\n= xs[0];\n";
+        let mut func_report = FunctionReport::new("first".to_string());
+        func_report.post_result = Some(failed(&[("xs", "0")]));
+
+        let generated = generate(source, func_report);
+        assert!(generated.tests.is_empty());
+        assert_eq!(generated.skipped.len(), 1);
+        assert!(generated.skipped[0].1.contains("parameter `xs`"), "{:?}", generated.skipped);
+    }
+
+    #[test]
+    fn test_model_int() {
+        assert_eq!(model_int("42"), Some(42));
+        assert_eq!(model_int("(- 7)"), Some(-7));
+        assert_eq!(model_int("(/ 1 2)"), None);
+    }
+}
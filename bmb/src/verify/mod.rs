@@ -2,6 +2,14 @@
 //!
 //! Verifies function contracts (pre/post conditions) using SMT solving.
 
+mod cache;
 mod contract;
+mod counterexample;
+mod paths;
 
-pub use contract::{ContractVerifier, VerificationReport, FunctionReport};
+pub use cache::VerifyCache;
+pub use contract::{
+    ConditionEntry, ConditionKind, ConditionStatus, ContractVerifier, FunctionEntry, FunctionReport,
+    FunctionStatus, LoopCheck, VerificationReport,
+};
+pub use counterexample::{generate_counterexample_tests, CounterexampleTest, CounterexampleTests};
@@ -0,0 +1,1053 @@
+//! Path-sensitive verification conditions
+//!
+//! Symbolically executes a function body and builds one SMT query per
+//! obligation found along the way:
+//!
+//! - array bounds: `base[index]` keeps `index` within `0..base.len()`
+//! - division: the divisor of an integer `/` or `%` is nonzero
+//! - loop invariants: a `while ... invariant inv` loop establishes `inv` on
+//!   entry and re-establishes it after every iteration of its body
+//! - the postcondition: it holds for the value the function returns, whether
+//!   by falling off the end of the body or through `return`, with parameters
+//!   at their values on exit and `old(x)` at the value on entry
+//!
+//! Each query is satisfiable iff the obligation can be violated under the
+//! facts known at that point: the precondition, branch and short-circuit
+//! conditions, `let` bindings and assignments. Straight-line code and `if`
+//! branches are tracked exactly. Variables assigned inside a loop are
+//! havocked (given a fresh, unconstrained value) at its head; after the loop
+//! only its invariant and the negated condition are known, so every query
+//! over-approximates the reachable states.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{BinOp, Expr, FnDef, Pattern, RangeKind, Span, Spanned, StateKind, Type, UnOp};
+use crate::smt::{SmtLibGenerator, SmtSort, SmtTranslator};
+use crate::types::refinement::instantiate;
+
+/// Which obligations to collect
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PathChecks {
+    /// Array index bounds
+    pub bounds: bool,
+    /// Nonzero integer divisors
+    pub division: bool,
+    /// Loop invariants
+    pub loops: bool,
+    /// The postcondition, at every point the function returns
+    pub post: bool,
+}
+
+/// What an obligation proves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ObligationKind {
+    /// The index expression (in source syntax) stays in bounds
+    Bounds(String),
+    /// The divisor of the division (in source syntax) is nonzero
+    Division(String),
+    /// The loop invariant holds when the loop is first reached
+    InvariantEntry,
+    /// One iteration of the body, started where the invariant and the loop
+    /// condition hold, re-establishes the invariant
+    InvariantPreserved,
+    /// The postcondition holds for the returned value
+    Postcondition,
+}
+
+/// Verification condition for one point in the body
+pub(crate) struct Obligation {
+    pub kind: ObligationKind,
+    /// Span of the index or division expression, the invariant, or the loop
+    /// or `return` the postcondition is checked after
+    pub span: Span,
+    /// SMT script that is satisfiable iff the obligation can be violated,
+    /// or the reason no script could be built
+    pub query: Result<String, String>,
+}
+
+/// Obligations of one function
+pub(crate) struct PathObligations {
+    pub obligations: Vec<Obligation>,
+    /// A loop leaves its exit state unconstrained (it has no usable
+    /// invariant, or it can be left through `break`), so a satisfiable
+    /// postcondition query may be a spurious counterexample
+    pub imprecise_exit: bool,
+}
+
+/// Build the requested obligations for `func`
+pub(crate) fn path_obligations(
+    func: &FnDef,
+    translator: &SmtTranslator,
+    generator: &SmtLibGenerator,
+    checks: PathChecks,
+) -> PathObligations {
+    let mut collector = Collector {
+        translator: translator.clone(),
+        generator: generator.clone(),
+        checks,
+        frames: Vec::new(),
+        fresh: 0,
+        unusable_pre: None,
+        post: None,
+        loops: Vec::new(),
+        last_loop: None,
+        imprecise_exit: false,
+        obligations: Vec::new(),
+    };
+
+    for param in &func.params {
+        let name = &param.name.node;
+        let length = match &param.ty.node {
+            Type::Array(_, size) => Some(size.to_string()),
+            Type::ArrayConst(_, size) => collector
+                .translator
+                .translate(&Spanned::new(Expr::Var(size.clone()), param.ty.span))
+                .ok(),
+            Type::String => None,
+            _ => continue,
+        };
+        let symbol = format!("{}.len", name);
+        collector.generator.declare_var(&symbol, SmtSort::Int);
+        match length {
+            Some(size) => collector.generator.assert(&format!("(= {} {})", symbol, size)),
+            None => collector.generator.assert(&format!("(>= {} 0)", symbol)),
+        }
+        collector.translator.set_length(name, Some(symbol));
+    }
+
+    if let Some(pre) = &func.pre {
+        match collector.translator.translate(pre) {
+            Ok(pre_smt) => collector.generator.assert(&pre_smt),
+            Err(e) => collector.unusable_pre = Some(format!("precondition: translation error: {}", e)),
+        }
+    }
+
+    // Callers prove the refinements of the arguments they pass, so the body
+    // may assume them
+    for param in &func.params {
+        let Type::Refined { constraints, .. } = &param.ty.node else { continue };
+        let value = Spanned::new(Expr::Var(param.name.node.clone()), param.name.span);
+        for constraint in constraints {
+            if let Some(condition) = instantiate(constraint, &value, &HashMap::new())
+                && let Ok(condition_smt) = collector.translator.translate(&condition)
+            {
+                collector.generator.assert(&condition_smt);
+            }
+        }
+    }
+
+    if checks.post
+        && let Some(post) = &func.post
+    {
+        let mut ret_names = vec!["__ret__".to_string()];
+        ret_names.extend(func.ret_name.as_ref().map(|name| name.node.clone()));
+        collector.post = Some(
+            collector
+                .translator
+                .translate(post)
+                .map(|post_smt| (ret_names, post_smt))
+                .map_err(|e| format!("postcondition: translation error: {}", e)),
+        );
+    }
+
+    let value = collector.eval(&func.body);
+    let span = collector.last_loop.unwrap_or(func.body.span);
+    collector.postcondition(span, value);
+
+    PathObligations {
+        obligations: collector.obligations,
+        imprecise_exit: collector.imprecise_exit,
+    }
+}
+
+/// Whether `expr` contains a `while`, `loop` or `for` loop
+pub(crate) fn has_loop(expr: &Expr) -> bool {
+    let mut found = false;
+    expr.for_each_child(&mut |child| {
+        found = found
+            || matches!(child.node, Expr::While { .. } | Expr::Loop { .. } | Expr::For { .. })
+            || has_loop(&child.node);
+    });
+    found
+}
+
+/// Whether `expr` assigns to any variable
+pub(crate) fn has_assignment(expr: &Expr) -> bool {
+    let mut vars = HashSet::new();
+    assigned_vars(expr, &mut vars);
+    !vars.is_empty()
+}
+
+/// Parameters of `func` whose entry value is read through `old(...)`/`.pre`
+/// in its contracts or loop invariants
+pub(crate) fn entry_params(func: &FnDef) -> Vec<String> {
+    let mut vars = HashSet::new();
+    let contracts = func.pre.iter().chain(&func.post).chain(func.contracts.iter().map(|c| &c.condition));
+    for expr in contracts.chain([&func.body]) {
+        old_vars(&expr.node, false, &mut vars);
+    }
+    func.params
+        .iter()
+        .map(|p| p.name.node.clone())
+        .filter(|name| vars.contains(name))
+        .collect()
+}
+
+fn old_vars(expr: &Expr, in_old: bool, out: &mut HashSet<String>) {
+    match expr {
+        Expr::Var(name) if in_old => {
+            out.insert(name.clone());
+        }
+        Expr::StateRef { expr: inner, state } => {
+            old_vars(&inner.node, in_old || matches!(state, StateKind::Pre), out)
+        }
+        _ => expr.for_each_child(&mut |child| old_vars(&child.node, in_old, out)),
+    }
+}
+
+/// Whether `expr` contains a `while` loop with an invariant
+pub(crate) fn has_invariant(expr: &Expr) -> bool {
+    let mut found = false;
+    expr.for_each_child(&mut |child| {
+        found = found
+            || matches!(child.node, Expr::While { invariant: Some(_), .. })
+            || has_invariant(&child.node);
+    });
+    found
+}
+
+/// A fact scoping over everything evaluated after it
+enum Frame {
+    /// The condition holds
+    Assume(String),
+    /// The variables have these values, all computed before any of them is
+    /// rebound (`(let ((name value) ...) ...)`)
+    Bind(Vec<(String, String)>),
+}
+
+/// What is known about the enclosing loop
+struct LoopFrame {
+    /// Translated invariant, if the loop has one
+    invariant: Option<Result<String, String>>,
+    span: Span,
+}
+
+struct Collector {
+    translator: SmtTranslator,
+    generator: SmtLibGenerator,
+    checks: PathChecks,
+    frames: Vec<Frame>,
+    /// Counter for fresh names
+    fresh: usize,
+    /// Set when the precondition cannot be assumed
+    unusable_pre: Option<String>,
+    /// Names the return value is bound to, and the translated postcondition,
+    /// when postcondition obligations are collected
+    post: Option<Result<(Vec<String>, String), String>>,
+    /// Enclosing loops, innermost last
+    loops: Vec<LoopFrame>,
+    /// The last loop the straight-line path went through
+    last_loop: Option<Span>,
+    imprecise_exit: bool,
+    obligations: Vec<Obligation>,
+}
+
+impl Collector {
+    /// Symbolically evaluate `expr`: push frames describing the state after
+    /// it and return an SMT term for its value in that state, if it has one
+    fn eval(&mut self, expr: &Spanned<Expr>) -> Option<String> {
+        match &expr.node {
+            Expr::Index { expr: base, index } => {
+                self.eval(base);
+                self.eval(index);
+                if self.checks.bounds {
+                    let query = self.bounds_query(base, index);
+                    self.obligations.push(Obligation {
+                        kind: ObligationKind::Bounds(crate::fmt::format_expr(&expr.node)),
+                        span: expr.span,
+                        query,
+                    });
+                }
+                None
+            }
+
+            Expr::If { cond, then_branch, else_branch } => self.eval_if(cond, then_branch, else_branch),
+
+            // `&&` and `||` only evaluate their right operand when the left
+            // one did not decide the result
+            Expr::Binary { left, op: op @ (BinOp::And | BinOp::Or), right } => {
+                let left_smt = self.eval(left);
+                let assumption = match op {
+                    BinOp::And => left_smt,
+                    _ => left_smt.map(|l| format!("(not {})", l)),
+                };
+                self.scoped(|this| {
+                    if let Some(assumption) = assumption {
+                        this.frames.push(Frame::Assume(assumption));
+                    }
+                    this.eval(right);
+                });
+                self.pure(expr)
+            }
+
+            Expr::Let { .. } => {
+                let saved = self.translator.clone();
+                let mut shadowed = Vec::new();
+                let value = self.eval_let(expr, &mut shadowed, false);
+                self.translator = saved;
+                self.end_scope(shadowed, value)
+            }
+
+            Expr::LetStmt { .. } => self.eval_let(expr, &mut Vec::new(), true),
+
+            Expr::Block(stmts) => {
+                let saved = self.translator.clone();
+                let mut shadowed = Vec::new();
+                let mut value = Some("true".to_string());
+                for stmt in stmts {
+                    value = self.eval_let(stmt, &mut shadowed, true);
+                }
+                self.translator = saved;
+                self.end_scope(shadowed, value)
+            }
+
+            Expr::Assign { name, value } => {
+                let value_smt = self.eval(value);
+                match value_smt {
+                    Some(value_smt) if self.translator.sort_of(name).is_some() => {
+                        self.frames.push(Frame::Bind(vec![(name.clone(), value_smt)]));
+                    }
+                    _ => self.havoc(name),
+                }
+                None
+            }
+
+            // The array's new contents are not tracked: only the store's
+            // bounds are checked, then the whole variable is forgotten
+            Expr::IndexAssign { array, index, value } => {
+                self.eval(array);
+                self.eval(index);
+                self.eval(value);
+                if self.checks.bounds {
+                    let query = self.bounds_query(array, index);
+                    let target = Expr::Index { expr: array.clone(), index: index.clone() };
+                    self.obligations.push(Obligation {
+                        kind: ObligationKind::Bounds(crate::fmt::format_expr(&target)),
+                        span: expr.span,
+                        query,
+                    });
+                }
+                if let Some(root) = array.node.place_root() {
+                    self.havoc(root);
+                }
+                None
+            }
+
+            // Likewise the struct's new field is not tracked
+            Expr::FieldAssign { object, value, .. } => {
+                self.eval(object);
+                self.eval(value);
+                if let Some(root) = object.node.place_root() {
+                    self.havoc(root);
+                }
+                None
+            }
+
+            Expr::While { cond, invariant, body, .. } => {
+                let invariant_smt = invariant.as_ref().map(|inv| {
+                    self.translator
+                        .translate(inv)
+                        .map_err(|e| format!("invariant: translation error: {}", e))
+                });
+                let invariant_span = invariant.as_ref().map_or(expr.span, |inv| inv.span);
+                if let Some(inv) = &invariant_smt {
+                    self.invariant_obligation(ObligationKind::InvariantEntry, invariant_span, inv.clone());
+                }
+
+                self.havoc_assigned(&[cond, body]);
+                let head = self.frames.len();
+                if let Some(Ok(inv)) = &invariant_smt {
+                    self.frames.push(Frame::Assume(inv.clone()));
+                }
+                let cond_smt = self.eval(cond);
+                let exit = self.frames.len();
+
+                self.loops.push(LoopFrame { invariant: invariant_smt, span: invariant_span });
+                self.scoped(|this| {
+                    if let Some(cond_smt) = &cond_smt {
+                        this.frames.push(Frame::Assume(cond_smt.clone()));
+                    }
+                    this.eval(body);
+                    this.continue_loop();
+                });
+                let frame = self.loops.pop().expect("loop frame pushed above");
+
+                // Leaving through the condition: the invariant still holds
+                // and the condition is false; `break` may leave anywhere
+                if breaks(&body.node) {
+                    self.frames.truncate(head);
+                    self.imprecise_exit = true;
+                } else {
+                    self.frames.truncate(exit);
+                    match cond_smt {
+                        Some(cond_smt) => self.frames.push(Frame::Assume(format!("(not {})", cond_smt))),
+                        None => self.imprecise_exit = true,
+                    }
+                    if !matches!(frame.invariant, Some(Ok(_))) {
+                        self.imprecise_exit = true;
+                    }
+                }
+                self.last_loop = Some(expr.span);
+                None
+            }
+
+            Expr::Loop { body, .. } => {
+                self.havoc_assigned(&[body]);
+                self.loops.push(LoopFrame { invariant: None, span: expr.span });
+                self.scoped(|this| {
+                    this.eval(body);
+                });
+                self.loops.pop();
+                self.imprecise_exit = true;
+                self.last_loop = Some(expr.span);
+                None
+            }
+
+            Expr::For { var, iter, body, .. } => {
+                self.eval(iter);
+                // Evaluate the range bounds once, before the loop changes anything
+                let bounds = match &iter.node {
+                    Expr::Range { start, end, kind } => self
+                        .translator
+                        .translate(start)
+                        .and_then(|s| self.translator.translate(end).map(|e| (s, e, *kind)))
+                        .ok(),
+                    _ => None,
+                };
+                let bounds = bounds.map(|(start, end, kind)| {
+                    (self.bind_fresh("__lo", start), self.bind_fresh("__hi", end), kind)
+                });
+                self.havoc_assigned(&[body]);
+
+                let saved = self.translator.clone();
+                self.loops.push(LoopFrame { invariant: None, span: expr.span });
+                self.scoped(|this| {
+                    this.translator.bind_local(var, SmtSort::Int);
+                    this.translator.set_length(var, None);
+                    this.translator.set_float(var, false);
+                    this.havoc(var);
+                    if let Some((lo, hi, kind)) = bounds {
+                        let upper = match kind {
+                            RangeKind::Exclusive => "<",
+                            RangeKind::Inclusive => "<=",
+                        };
+                        this.frames.push(Frame::Assume(format!(
+                            "(and (<= {} {}) ({} {} {}))",
+                            lo, var, upper, var, hi
+                        )));
+                    }
+                    this.eval(body);
+                });
+                self.loops.pop();
+                self.translator = saved;
+                self.imprecise_exit = true;
+                self.last_loop = Some(expr.span);
+                None
+            }
+
+            Expr::Match { expr: scrutinee, arms } => {
+                self.eval(scrutinee);
+                for arm in arms {
+                    let saved = self.translator.clone();
+                    self.scoped(|this| {
+                        let mut bindings = Vec::new();
+                        pattern_bindings(&arm.pattern.node, &mut bindings);
+                        for name in &bindings {
+                            this.translator.bind_local(name, SmtSort::Int);
+                            this.translator.set_length(name, None);
+                            this.translator.set_float(name, false);
+                            this.havoc(name);
+                        }
+                        if let Some(guard) = &arm.guard
+                            && let Some(guard_smt) = this.eval(guard)
+                        {
+                            this.frames.push(Frame::Assume(guard_smt));
+                        }
+                        this.eval(&arm.body);
+                    });
+                    self.translator = saved;
+                }
+                self.havoc_assigned(&[expr]);
+                None
+            }
+
+            Expr::Closure { params, body, .. } => {
+                let saved = self.translator.clone();
+                // The body runs whenever the closure is called, not here
+                let loops = std::mem::take(&mut self.loops);
+                self.scoped(|this| {
+                    for param in params {
+                        let sort = param
+                            .ty
+                            .as_ref()
+                            .map_or(SmtSort::Int, |t| SmtTranslator::type_to_sort(&t.node));
+                        this.translator.bind_local(&param.name.node, sort);
+                        this.translator.set_length(&param.name.node, None);
+                        let is_float = param.ty.as_ref().is_some_and(|t| matches!(t.node, Type::F64));
+                        this.translator.set_float(&param.name.node, is_float);
+                        this.havoc(&param.name.node);
+                    }
+                    this.eval(body);
+                });
+                self.loops = loops;
+                self.translator = saved;
+                self.havoc_assigned(&[body]);
+                None
+            }
+
+            Expr::Return { value } => {
+                let value_smt = match value {
+                    Some(value) => self.eval(value),
+                    None => Some("true".to_string()),
+                };
+                self.postcondition(expr.span, value_smt);
+                self.unreachable();
+                None
+            }
+
+            Expr::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.eval(value);
+                }
+                self.unreachable();
+                None
+            }
+
+            Expr::Continue { .. } => {
+                self.continue_loop();
+                self.unreachable();
+                None
+            }
+
+            Expr::Binary { left, op, right } => {
+                self.eval(left);
+                self.eval(right);
+                if self.checks.division && matches!(op, BinOp::Div | BinOp::Mod) && self.needs_divisor_check(expr) {
+                    let query = self.division_query(right);
+                    self.obligations.push(Obligation {
+                        kind: ObligationKind::Division(crate::fmt::format_expr(&expr.node)),
+                        span: expr.span,
+                        query,
+                    });
+                }
+                self.pure(expr)
+            }
+
+            Expr::Call { args, .. } | Expr::EnumVariant { args, .. } => {
+                for arg in args {
+                    self.eval(arg);
+                }
+                self.pure(expr)
+            }
+
+            Expr::MethodCall { receiver, args, .. } => {
+                self.eval(receiver);
+                for arg in args {
+                    self.eval(arg);
+                }
+                self.pure(expr)
+            }
+
+            Expr::ArrayLit(elems) | Expr::Tuple(elems) => {
+                for elem in elems {
+                    self.eval(elem);
+                }
+                self.pure(expr)
+            }
+
+            Expr::StructInit { fields, base, .. } => {
+                for (_, value) in fields {
+                    self.eval(value);
+                }
+                if let Some(base) = base {
+                    self.eval(base);
+                }
+                self.pure(expr)
+            }
+
+            Expr::Range { start, end, .. } => {
+                self.eval(start);
+                self.eval(end);
+                self.pure(expr)
+            }
+
+            Expr::Unary { expr: inner, .. }
+            | Expr::FieldAccess { expr: inner, .. }
+            | Expr::TupleField { expr: inner, .. }
+            | Expr::Deref(inner)
+            | Expr::Ref(inner)
+            | Expr::RefMut(inner)
+            | Expr::Cast { expr: inner, .. } => {
+                self.eval(inner);
+                self.pure(expr)
+            }
+
+            // Contract-only and leaf expressions have no effects
+            Expr::StateRef { .. } | Expr::Forall { .. } | Expr::Exists { .. }
+            | Expr::IntLit(_) | Expr::FloatLit(_) | Expr::BoolLit(_) | Expr::StringLit(_)
+            | Expr::CharLit(_) | Expr::Var(_) | Expr::Ret | Expr::Unit | Expr::It => self.pure(expr),
+
+            Expr::Todo { .. } => None,
+        }
+    }
+
+    /// Evaluate `expr` as a statement of a block. `let` bindings stay in
+    /// scope until the end of the block; the names they shadow are recorded
+    /// in `shadowed` together with the fresh names holding the outer values.
+    fn eval_let(
+        &mut self,
+        expr: &Spanned<Expr>,
+        shadowed: &mut Vec<(String, String)>,
+        in_block: bool,
+    ) -> Option<String> {
+        let (name, ty, value, body) = match &expr.node {
+            Expr::Let { name, ty, value, body, .. } => (name, ty, value, Some(body)),
+            Expr::LetStmt { name, ty, value, .. } => (name, ty, value, None),
+            _ => return self.eval(expr),
+        };
+
+        let value_smt = self.eval(value);
+        if self.translator.sort_of(name).is_some() {
+            let outer = self.bind_fresh(name, name.clone());
+            shadowed.push((name.clone(), outer));
+        }
+        self.bind_let(name, ty.as_ref().map(|t| &t.node), value, value_smt);
+
+        // A statement-form let has no value of its own
+        let body = body?;
+        if in_block || matches!(body.node, Expr::Let { .. }) {
+            self.eval_let(body, shadowed, in_block)
+        } else {
+            self.eval(body)
+        }
+    }
+
+    /// Restore the outer values of shadowed names, keeping `value` (which
+    /// may mention the inner ones) under a fresh name
+    fn end_scope(&mut self, shadowed: Vec<(String, String)>, value: Option<String>) -> Option<String> {
+        if shadowed.is_empty() {
+            return value;
+        }
+        let mut binds = shadowed;
+        let value = value.map(|value| {
+            let name = self.fresh_name("__val");
+            binds.push((name.clone(), value));
+            name
+        });
+        self.frames.push(Frame::Bind(binds));
+        value
+    }
+
+    /// Evaluate both branches and merge the variables they assign with `ite`
+    fn eval_if(
+        &mut self,
+        cond: &Spanned<Expr>,
+        then_branch: &Spanned<Expr>,
+        else_branch: &Spanned<Expr>,
+    ) -> Option<String> {
+        let cond_smt = self.eval(cond);
+        let depth = self.frames.len();
+        let saved = self.translator.clone();
+
+        if let Some(c) = &cond_smt {
+            self.frames.push(Frame::Assume(c.clone()));
+        }
+        let then_value = self.eval(then_branch);
+        let then_frames = self.frames.split_off(depth);
+        self.translator = saved.clone();
+
+        if let Some(c) = &cond_smt {
+            self.frames.push(Frame::Assume(format!("(not {})", c)));
+        }
+        let else_value = self.eval(else_branch);
+        let else_frames = self.frames.split_off(depth);
+        self.translator = saved;
+
+        let mut assigned = HashSet::new();
+        assigned_vars(&then_branch.node, &mut assigned);
+        assigned_vars(&else_branch.node, &mut assigned);
+        let mut assigned: Vec<_> = assigned
+            .into_iter()
+            .filter(|name| self.translator.sort_of(name).is_some())
+            .collect();
+        assigned.sort();
+
+        let Some(c) = cond_smt else {
+            for name in assigned {
+                self.havoc(&name);
+            }
+            return None;
+        };
+
+        let mut binds: Vec<_> = assigned
+            .into_iter()
+            .map(|name| {
+                let merged = format!(
+                    "(ite {} {} {})",
+                    c,
+                    close(&then_frames, &name),
+                    close(&else_frames, &name)
+                );
+                (name, merged)
+            })
+            .collect();
+        let value = match (then_value, else_value) {
+            (Some(t), Some(e)) => {
+                let name = self.fresh_name("__val");
+                let merged = format!("(ite {} {} {})", c, close(&then_frames, &t), close(&else_frames, &e));
+                binds.push((name.clone(), merged));
+                Some(name)
+            }
+            _ => None,
+        };
+        if !binds.is_empty() {
+            self.frames.push(Frame::Bind(binds));
+        }
+        value
+    }
+
+    /// Run `f`, then drop the facts it added
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        let depth = self.frames.len();
+        f(self);
+        self.frames.truncate(depth);
+    }
+
+    /// Translation of an expression without effects of its own
+    fn pure(&self, expr: &Spanned<Expr>) -> Option<String> {
+        let mut assigned = HashSet::new();
+        assigned_vars(&expr.node, &mut assigned);
+        if assigned.is_empty() {
+            self.translator.translate(expr).ok()
+        } else {
+            None
+        }
+    }
+
+    fn bind_let(&mut self, name: &str, ty: Option<&Type>, value: &Spanned<Expr>, value_smt: Option<String>) {
+        let sort = match ty {
+            Some(ty) => SmtTranslator::type_to_sort(ty),
+            None => self.infer_sort(&value.node),
+        };
+        let length = match (ty, &value.node) {
+            (Some(Type::Array(_, size)), _) => Some(size.to_string()),
+            (_, Expr::ArrayLit(elems)) => Some(elems.len().to_string()),
+            (_, Expr::Var(other)) => self.translator.length_of(other).map(str::to_string),
+            _ => None,
+        };
+
+        self.translator.bind_local(name, sort);
+        self.translator.set_length(name, length);
+        let is_float = match ty {
+            Some(ty) => matches!(ty, Type::F64),
+            None => self.is_float(&value.node),
+        };
+        self.translator.set_float(name, is_float);
+        match value_smt {
+            Some(value_smt) => self.frames.push(Frame::Bind(vec![(name.to_string(), value_smt)])),
+            None => self.havoc(name),
+        }
+    }
+
+    /// Best-effort sort of an unannotated `let` initializer
+    fn infer_sort(&self, expr: &Expr) -> SmtSort {
+        match expr {
+            Expr::BoolLit(_) => SmtSort::Bool,
+            Expr::Unary { op: UnOp::Not, .. } => SmtSort::Bool,
+            Expr::Binary {
+                op: BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge
+                    | BinOp::And | BinOp::Or | BinOp::Implies,
+                ..
+            } => SmtSort::Bool,
+            Expr::Var(name) => self.translator.sort_of(name).unwrap_or(SmtSort::Int),
+            _ => SmtSort::Int,
+        }
+    }
+
+    /// Best-effort check for an `f64`-valued expression
+    fn is_float(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::FloatLit(_) | Expr::Cast { ty: Spanned { node: Type::F64, .. }, .. } => true,
+            Expr::Var(name) => self.translator.is_float(name),
+            Expr::Binary { left, op: BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod, right } => {
+                self.is_float(&left.node) || self.is_float(&right.node)
+            }
+            Expr::Unary { op: UnOp::Neg, expr } => self.is_float(&expr.node),
+            _ => false,
+        }
+    }
+
+    /// Whether the division `expr` is on integers with a divisor that is not
+    /// a nonzero literal (`f64` division by zero does not trap)
+    fn needs_divisor_check(&self, expr: &Spanned<Expr>) -> bool {
+        let Expr::Binary { right, .. } = &expr.node else { return false };
+        !self.is_float(&expr.node) && !matches!(right.node, Expr::IntLit(n) if n != 0)
+    }
+
+    /// Havoc every variable assigned anywhere in `exprs`
+    fn havoc_assigned(&mut self, exprs: &[&Spanned<Expr>]) {
+        let mut assigned = HashSet::new();
+        for expr in exprs {
+            assigned_vars(&expr.node, &mut assigned);
+        }
+        let mut assigned: Vec<_> = assigned.into_iter().collect();
+        assigned.sort();
+        for name in assigned {
+            self.havoc(&name);
+        }
+    }
+
+    /// Give a variable a fresh, unconstrained value
+    fn havoc(&mut self, name: &str) {
+        let Some(sort) = self.translator.sort_of(name) else { return };
+        let fresh = self.fresh_name(name);
+        self.generator.declare_var(&fresh, sort);
+        self.frames.push(Frame::Bind(vec![(name.to_string(), fresh)]));
+    }
+
+    /// Bind a value to a fresh name and return the name
+    fn bind_fresh(&mut self, prefix: &str, value: String) -> String {
+        let fresh = self.fresh_name(prefix);
+        self.frames.push(Frame::Bind(vec![(fresh.clone(), value)]));
+        fresh
+    }
+
+    fn fresh_name(&mut self, prefix: &str) -> String {
+        self.fresh += 1;
+        format!("{}!{}", prefix, self.fresh)
+    }
+
+    /// Nothing after this point is reached
+    fn unreachable(&mut self) {
+        self.frames.push(Frame::Assume("false".to_string()));
+    }
+
+    /// The innermost loop's invariant must hold again before its next iteration
+    fn continue_loop(&mut self) {
+        let Some(frame) = self.loops.last() else { return };
+        if let Some(inv) = frame.invariant.clone() {
+            let span = frame.span;
+            self.invariant_obligation(ObligationKind::InvariantPreserved, span, inv);
+        }
+    }
+
+    fn invariant_obligation(&mut self, kind: ObligationKind, span: Span, invariant: Result<String, String>) {
+        if !self.checks.loops {
+            return;
+        }
+        let query = invariant.and_then(|inv| self.query(inv));
+        self.obligations.push(Obligation { kind, span, query });
+    }
+
+    /// The postcondition must hold for `value` returned here
+    fn postcondition(&mut self, span: Span, value: Option<String>) {
+        let Some(post) = &self.post else { return };
+        let query = post.clone().and_then(|(ret_names, post_smt)| {
+            let value = value.ok_or_else(|| "cannot translate the returned value".to_string())?;
+            let binds = ret_names
+                .iter()
+                .map(|name| format!("({} {})", name, value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.query(format!("(let ({}) {})", binds, post_smt))
+        });
+        self.obligations.push(Obligation { kind: ObligationKind::Postcondition, span, query });
+    }
+
+    fn bounds_query(&self, base: &Spanned<Expr>, index: &Spanned<Expr>) -> Result<String, String> {
+        let length = match &base.node {
+            Expr::Var(name) => self.translator.length_of(name),
+            _ => None,
+        }
+        .ok_or_else(|| format!("unknown length of {}", crate::fmt::format_expr(&base.node)))?;
+        let index_smt = self
+            .translator
+            .translate(index)
+            .map_err(|e| format!("translation error: {}", e))?;
+
+        self.query(format!("(and (<= 0 {}) (< {} {}))", index_smt, index_smt, length))
+    }
+
+    fn division_query(&self, divisor: &Spanned<Expr>) -> Result<String, String> {
+        let divisor_smt = self
+            .translator
+            .translate(divisor)
+            .map_err(|e| format!("translation error: {}", e))?;
+
+        self.query(format!("(not (= {} 0))", divisor_smt))
+    }
+
+    /// Script asking whether `goal` can be false at the current point
+    fn query(&self, goal: String) -> Result<String, String> {
+        if let Some(reason) = &self.unusable_pre {
+            return Err(reason.clone());
+        }
+
+        let mut goal = goal;
+        for frame in self.frames.iter().rev() {
+            goal = match frame {
+                Frame::Assume(cond) => format!("(=> {} {})", cond, goal),
+                Frame::Bind(binds) => let_term(binds, &goal),
+            };
+        }
+
+        let mut generator = self.generator.clone();
+        generator.assert(&format!("(not {})", goal));
+        Ok(generator.generate())
+    }
+}
+
+/// `term` evaluated after the bindings in `frames` (assumptions only matter
+/// for obligations inside the branch, not for its resulting values)
+fn close(frames: &[Frame], term: &str) -> String {
+    let mut term = term.to_string();
+    for frame in frames.iter().rev() {
+        if let Frame::Bind(binds) = frame {
+            term = let_term(binds, &term);
+        }
+    }
+    term
+}
+
+fn let_term(binds: &[(String, String)], body: &str) -> String {
+    let binds: Vec<_> = binds
+        .iter()
+        .map(|(name, value)| format!("({} {})", name, value))
+        .collect();
+    format!("(let ({}) {})", binds.join(" "), body)
+}
+
+/// Whether `break` in `body` leaves the loop `body` belongs to
+fn breaks(body: &Expr) -> bool {
+    match body {
+        Expr::Break { .. } => true,
+        Expr::While { .. } | Expr::Loop { .. } | Expr::For { .. } | Expr::Closure { .. } => false,
+        _ => {
+            let mut found = false;
+            body.for_each_child(&mut |child| found = found || breaks(&child.node));
+            found
+        }
+    }
+}
+
+/// Variables assigned anywhere inside `expr`
+fn assigned_vars(expr: &Expr, out: &mut HashSet<String>) {
+    match expr {
+        Expr::Assign { name, .. } => {
+            out.insert(name.clone());
+        }
+        Expr::IndexAssign { array, .. } => {
+            out.extend(array.node.place_root().map(str::to_string));
+        }
+        Expr::FieldAssign { object, .. } => {
+            out.extend(object.node.place_root().map(str::to_string));
+        }
+        _ => {}
+    }
+    expr.for_each_child(&mut |child| assigned_vars(&child.node, out));
+}
+
+/// Variables bound by a match pattern
+fn pattern_bindings(pattern: &Pattern, out: &mut Vec<String>) {
+    match pattern {
+        Pattern::Var(name) => out.push(name.clone()),
+        Pattern::EnumVariant { bindings, .. } => {
+            for binding in bindings {
+                pattern_bindings(&binding.node, out);
+            }
+        }
+        Pattern::Struct { fields, .. } => {
+            for (_, field) in fields {
+                pattern_bindings(&field.node, out);
+            }
+        }
+        Pattern::Binding { name, pattern } => {
+            out.push(name.clone());
+            pattern_bindings(&pattern.node, out);
+        }
+        Pattern::Or(patterns) | Pattern::Tuple(patterns) | Pattern::Array(patterns) => {
+            for pattern in patterns {
+                pattern_bindings(&pattern.node, out);
+            }
+        }
+        Pattern::ArrayRest { prefix, suffix } => {
+            for pattern in prefix.iter().chain(suffix) {
+                pattern_bindings(&pattern.node, out);
+            }
+        }
+        Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Item;
+
+    /// Bounds queries of the function named `name` in `source`
+    fn bounds_queries(source: &str, name: &str) -> Vec<(String, String)> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse("test.bmb", source, tokens).unwrap();
+        let func = program.items.iter()
+            .find_map(|item| match item {
+                Item::FnDef(func) if func.name.node == name => Some(func),
+                _ => None,
+            })
+            .unwrap();
+        let mut generator = SmtLibGenerator::new();
+        let mut translator = SmtTranslator::new();
+        translator.setup_function(func, &mut generator);
+        let checks = PathChecks { bounds: true, ..PathChecks::default() };
+        path_obligations(func, &translator, &generator, checks)
+            .obligations
+            .into_iter()
+            .filter_map(|o| match o.kind {
+                ObligationKind::Bounds(text) => Some((text, o.query.unwrap())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_guarded_index_assumes_branch_condition() {
+        let queries = bounds_queries(
+            "fn get(xs: [i64; 3], i: i64) -> i64 = if i >= 0 and i < 3 { xs[i] } else { 0 };",
+            "get",
+        );
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].0, "xs[i]");
+        assert!(queries[0].1.contains("(assert (= xs.len 3))"));
+        assert!(queries[0].1.contains(
+            "(assert (not (=> (and (>= i 0) (< i 3)) (and (<= 0 i) (< i xs.len)))))"
+        ));
+    }
+
+    #[test]
+    fn test_unguarded_index_assumes_nothing() {
+        let queries = bounds_queries("fn get(xs: [i64; 3], i: i64) -> i64 = xs[i];", "get");
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].1.contains("(assert (not (and (<= 0 i) (< i xs.len))))"));
+    }
+
+    #[test]
+    fn test_else_branch_assumes_negated_condition() {
+        let queries = bounds_queries(
+            "fn get(xs: [i64; 3], i: i64) -> i64 = if i < 0 or i >= 3 { 0 } else { xs[i] };",
+            "get",
+        );
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].1.contains(
+            "(assert (not (=> (not (or (< i 0) (>= i 3))) (and (<= 0 i) (< i xs.len)))))"
+        ));
+    }
+}
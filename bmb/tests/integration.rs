@@ -6,7 +6,7 @@
 //! - Contract verification (verify command)
 //! - Error diagnostics
 
-use bmb::lexer::tokenize;
+use bmb::lexer::{tokenize, Token};
 use bmb::parser::parse;
 use bmb::types::TypeChecker;
 
@@ -29,6 +29,17 @@ fn type_error(source: &str) -> bool {
     check_program(source).is_err()
 }
 
+/// Helper to type-check and run a BMB program, returning main's value
+fn run_program(source: &str) -> bmb::interp::Value {
+    let tokens = tokenize(source).expect("tokenize failed");
+    let ast = parse("test.bmb", source, tokens).expect("parse failed");
+    let mut tc = TypeChecker::new();
+    tc.check_program(&ast).expect("type check failed");
+    let mut interp = bmb::interp::Interpreter::new();
+    interp.load(&ast);
+    interp.run(&ast).expect("runtime error")
+}
+
 /// Helper to check if a program produces a specific warning kind
 fn has_warning_kind(source: &str, warning_kind: &str) -> bool {
     let tokens = match tokenize(source) {
@@ -120,6 +131,34 @@ fn test_contract_with_implies() {
     ));
 }
 
+#[test]
+fn test_old_in_postcondition() {
+    assert!(type_checks(
+        "fn increment(x: i64) -> i64
+           post x == old(x) + 1 and ret == x
+         = { x = x + 1; x };"
+    ));
+    assert_eq!(
+        run_program(
+            "fn increment(x: i64) -> i64
+               post x == old(x) + 1
+             = { x = x + 1; x };
+             fn main() -> i64 = increment(4);"
+        ),
+        bmb::interp::Value::Int(5)
+    );
+}
+
+#[test]
+fn test_old_of_local_rejected() {
+    assert!(type_error(
+        "fn f(x: i64) -> i64
+           post old(y) == 0
+         = { let y: i64 = x; y };"
+    ));
+    assert!(type_error("fn f(x: i64) -> i64 post old(ret) == 0 = x;"));
+}
+
 // ============================================
 // Type Error Tests
 // ============================================
@@ -172,6 +211,115 @@ fn test_struct_field_access() {
     ));
 }
 
+#[test]
+fn test_struct_update_copies_unlisted_fields() {
+    let source = "struct P { x: i64, y: i64, z: i64 }
+                  fn main() -> i64 = {
+                    let base = new P { x: 1, y: 2, z: 3 };
+                    let p = new P { y: 20, ..base };
+                    let q = new P { ..p };
+                    p.x * 100 + p.y + q.z * 1000
+                  };";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(3120));
+
+    assert!(type_checks(
+        "struct Pair<T> { a: T, b: T }
+         fn with_a(p: Pair<i64>, a: i64) -> Pair<i64> = new Pair { a: a, ..p };"
+    ));
+}
+
+#[test]
+fn test_struct_update_from_other_struct_rejected() {
+    let source = "struct P { x: i64, y: i64 }
+                  struct Q { x: i64, y: i64 }
+                  fn f(q: Q) -> P = new P { x: 1, ..q };";
+    let err = check_program(source).unwrap_err();
+    assert!(err.message().contains("struct update base must be a `P`, found `Q`"), "{}", err.message());
+
+    // Without a base every field is still required
+    assert!(type_error(
+        "struct P { x: i64, y: i64 }
+         fn f() -> P = new P { x: 1 };"
+    ));
+}
+
+#[test]
+fn test_struct_field_defaults_fill_omitted_fields() {
+    let source = r#"
+const LIMIT: i64 = 10;
+
+fn bump() -> i64 = 5;
+
+struct Config { depth: i64 = LIMIT * 2, width: i64, tries: i64 = bump() }
+
+fn main() -> i64 = {
+    let LIMIT = 1;
+    let a = new Config { width: 7 };
+    let b = new Config { width: 1, depth: 4 };
+    let c = new Config { tries: 0, ..b };
+    println(a.depth + a.width + a.tries);
+    println(b.depth * 100 + b.tries);
+    println(c.depth * 100 + c.tries + LIMIT);
+    0
+};
+"#;
+    assert!(type_checks(source));
+    assert_eq!(run_interpreted("field_defaults", source), "32\n405\n401\n");
+}
+
+#[test]
+fn test_struct_field_defaults_errors() {
+    // Fields without a default are still required
+    let err = check_program(
+        "struct Config { depth: i64 = 3, width: i64 }
+         fn f() -> Config = new Config { depth: 1 };",
+    )
+    .unwrap_err();
+    assert!(err.message().contains("missing field: width"), "{}", err.message());
+
+    // Defaults are checked against the field type at the definition
+    let err = check_program("struct Config { depth: i64 = true }").unwrap_err();
+    assert!(err.message().contains("expected i64, got bool"), "{}", err.message());
+
+    // Defaults may not refer to other fields
+    let err = check_program("struct Range { lo: i64, hi: i64 = lo + 1 }").unwrap_err();
+    assert!(err.message().contains("field default cannot refer to field `lo`"), "{}", err.message());
+}
+
+#[test]
+fn test_struct_field_assign_updates_in_place() {
+    let source = "struct Point { x: i64, y: i64 }
+                  struct Seg { a: Point, b: Point, tags: [i64; 2] }
+                  fn main() -> i64 = {
+                    let mut p = new Point { x: 1, y: 2 };
+                    let copy = p;
+                    p.x = 10;
+                    let mut s = new Seg { a: p, b: copy, tags: [0, 0] };
+                    s.b.y = s.a.x + 5;
+                    s.tags[1] = 7;
+                    p.x * 10000 + copy.x * 1000 + s.b.y * 10 + s.tags[1]
+                  };";
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(101_157));
+}
+
+#[test]
+fn test_struct_field_assign_errors() {
+    let point = "struct Point { x: i64, y: i64 }";
+
+    // The binding must be `mut`, also for nested fields
+    let err = check_program(&format!("{point} fn f() -> i64 = {{ let p = new Point {{ x: 1, y: 2 }}; p.x = 3; p.x }};")).unwrap_err();
+    assert!(err.message().contains("`p` is not declared `mut`"), "{}", err.message());
+    let err = check_program(&format!("{point} struct Seg {{ a: Point }} fn f(s: Seg) -> () = {{ s.a.y = 0; }};")).unwrap_err();
+    assert!(err.message().contains("`s` is not declared `mut`"), "{}", err.message());
+
+    // The field must exist and the value match its type
+    let err = check_program(&format!("{point} fn f() -> () = {{ let mut p = new Point {{ x: 1, y: 2 }}; p.z = 3; }};")).unwrap_err();
+    assert!(err.message().contains("unknown field `z` on struct `Point`"), "{}", err.message());
+    let err = check_program(&format!("{point} fn f() -> () = {{ let mut p = new Point {{ x: 1, y: 2 }}; p.x = true; }};")).unwrap_err();
+    assert!(err.message().contains("expected i64, got bool"), "{}", err.message());
+}
+
 // ============================================
 // Enum Tests
 // ============================================
@@ -220,6 +368,46 @@ fn test_array_index() {
     ));
 }
 
+#[test]
+fn test_array_index_assign_updates_in_place() {
+    let source = "fn main() -> i64 = {
+                    let mut a = [0, 0, 0, 0];
+                    let mut i = 0;
+                    while i < 4 { { a[i] = i * i; i = i + 1 } };
+                    let copy = a;
+                    a[3] = a[1] + 100;
+                    let mut grid = [[1, 2], [3, 4]];
+                    grid[1][0] = grid[0][1] * 10;
+                    a[2] * 1000 + a[3] + copy[3] * 10000 + grid[1][0] * 100000
+                  };";
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(2_094_101));
+}
+
+#[test]
+fn test_array_index_assign_errors() {
+    // Element type and index type are checked
+    let err = check_program("fn f() -> () = { let mut a = [1, 2]; a[0] = true; };").unwrap_err();
+    assert!(err.message().contains("expected i64, got bool"), "{}", err.message());
+    let err = check_program("fn f() -> () = { let mut a = [1, 2]; a[true] = 1; };").unwrap_err();
+    assert!(err.message().contains("Array index must be integer"), "{}", err.message());
+
+    // Only variables' arrays are assignable
+    let err = check_program("fn mk() -> [i64; 2] = [1, 2]; fn f() -> () = { mk()[0] = 1; };").unwrap_err();
+    assert!(err.message().contains("cannot assign to an element of this expression"), "{}", err.message());
+    let err = check_program("fn f() -> () = { let mut s = \"ab\"; s[0] = 1; };").unwrap_err();
+    assert!(err.message().contains("cannot assign to an element of type: String"), "{}", err.message());
+
+    // Stores are bounds checked like loads
+    let source = "fn main() -> i64 = { let mut a = [1, 2]; a[2] = 5; a[0] };";
+    assert!(type_checks(source));
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let err = bmb::interp::Interpreter::new().run(&ast).expect_err("out of bounds");
+    assert_eq!(err.kind, bmb::interp::ErrorKind::IndexOutOfBounds);
+    assert_eq!(err.span.map(|s| &source[s.start..s.end]), Some("2"));
+}
+
 // ============================================
 // Generic Function Tests
 // ============================================
@@ -445,6 +633,50 @@ fn test_f64_comparison() {
     ));
 }
 
+// ============================================
+// Numeric Builtin Tests (min/max/abs)
+// ============================================
+
+#[test]
+fn test_min_max_abs_f64() {
+    use bmb::interp::Value;
+    assert_eq!(run_program("fn main() -> f64 = max(1.5, 2.5);"), Value::Float(2.5));
+    assert_eq!(run_program("fn main() -> f64 = min(1.5, -2.5);"), Value::Float(-2.5));
+    assert_eq!(run_program("fn main() -> f64 = abs(-0.25);"), Value::Float(0.25));
+}
+
+#[test]
+fn test_min_max_abs_i64_and_i32() {
+    use bmb::interp::Value;
+    assert_eq!(run_program("fn main() -> i64 = max(3, 7) + min(3, 7) + abs(-10);"), Value::Int(20));
+    assert_eq!(
+        run_program("fn f(a: i32, b: i32) -> i32 = max(a, b) - abs(b);\nfn main() -> i32 = f(5, -2);"),
+        Value::Int(3)
+    );
+}
+
+#[test]
+fn test_min_max_unsigned() {
+    use bmb::interp::Value;
+    // A literal takes the unsigned type of the other argument
+    assert_eq!(
+        run_program("fn clamp(x: u64) -> u64 = min(max(x, 10), 20);\nfn main() -> u64 = clamp(25);"),
+        Value::Int(20)
+    );
+    assert!(type_checks("fn lo(a: u32, b: u32) -> u32 = min(a, b);"));
+    // The result has the argument type, not i64
+    assert!(type_error("fn lo(a: u32, b: u32) -> bool = min(a, b);"));
+}
+
+#[test]
+fn test_min_max_abs_errors() {
+    let message = |source: &str| check_program(source).unwrap_err().message().to_string();
+    assert!(message("fn f(x: f64) -> f64 = max(x, 2);").contains("argument type mismatch in max(): f64 and i64"));
+    assert!(message("fn f(x: u64) -> u64 = abs(x);").contains("abs() is not defined for unsigned type u64"));
+    assert!(message("fn f(b: bool) -> bool = min(b, b);").contains("min() requires i32, i64, u32, u64 or f64"));
+    assert!(type_error("fn f(x: i64) -> i64 = max(x);"));
+}
+
 // ============================================
 // String Tests
 // ============================================
@@ -461,6 +693,32 @@ fn test_string_concat() {
     ));
 }
 
+#[test]
+fn test_println_takes_mixed_arguments() {
+    assert!(type_checks(r#"fn show(n: i64) -> () = println(n, "items", n > 2, 1.5, 'x');"#));
+    assert!(type_checks("fn blank() -> () = { print(); println() };"));
+    // Arrays and structs have no printed form
+    assert!(type_error("fn show(a: [i64; 2]) -> () = println(1, a);"));
+
+    let dir = std::env::temp_dir().join(format!("bmb_println_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.bmb");
+    std::fs::write(
+        &file,
+        "fn main() -> i64 = {\n  println(42, \"apples\", true);\n  print(\"no\", 'n');\n  println(\"\", -1);\n  0\n};\n",
+    )
+    .unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("run")
+        .arg(&file)
+        .output()
+        .expect("failed to run bmb");
+    let _ = std::fs::remove_dir_all(&dir);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.starts_with("42 apples true\nno n -1\n"), "{}", stdout);
+}
+
 // ============================================
 // Bitwise Operator Tests (keyword syntax: band, bor, bxor, bnot)
 // ============================================
@@ -524,6 +782,58 @@ fn test_while_loop() {
 //     ));
 // }
 
+// ============================================
+// Refinement Call-Site Tests
+// ============================================
+
+#[test]
+fn test_constant_argument_violating_refinement_rejected() {
+    let source = "fn set_age(age: i64{it >= 0}) -> i64 = age;
+                  fn main() -> i64 = set_age(-1);";
+    let err = check_program(source).unwrap_err();
+    assert!(
+        err.message().contains("`-1` violates refinement `it >= 0` of parameter `age` in call to `set_age`"),
+        "{}",
+        err.message()
+    );
+
+    assert!(type_checks(
+        "fn set_age(age: i64{it >= 0}) -> i64 = age;
+         fn main() -> i64 = set_age(30);"
+    ));
+    // Constants are evaluated, and refinements may name other parameters
+    assert!(type_error(
+        "const MIN: i64 = 0 - 5;
+         fn set_age(age: i64{it >= 0}) -> i64 = age;
+         fn main() -> i64 = set_age(MIN);"
+    ));
+    assert!(type_error(
+        "fn between(lo: i64, hi: i64{it >= lo}) -> i64 = hi - lo;
+         fn main() -> i64 = between(5, 2);"
+    ));
+}
+
+#[test]
+fn test_non_constant_argument_leaves_refinement_obligation() {
+    let source = "fn set_age(age: i64{it >= 0}) -> i64 = age;
+                  fn next_age(n: i64) -> i64 = set_age(n + 1);";
+    let ast = parse("test.bmb", source, tokenize(source).unwrap()).unwrap();
+    let mut tc = TypeChecker::new();
+    tc.check_program(&ast).unwrap();
+
+    let obligations = tc.refinement_obligations();
+    assert_eq!(obligations.len(), 1);
+    assert_eq!(obligations[0].function, "next_age");
+    assert_eq!(obligations[0].target, "parameter `age` in call to `set_age`");
+    assert_eq!(bmb::fmt::format_expr(&obligations[0].condition.node), "n + 1 >= 0");
+}
+
+#[test]
+fn test_refined_let_annotation_checked() {
+    assert!(type_error("fn f() -> i64 = { let x: i64{it > 0} = 0; x };"));
+    assert!(type_checks("fn f() -> i64 = { let x: i64{it > 0} = 3; x };"));
+}
+
 // ============================================
 // @trust Annotation Tests
 // ============================================
@@ -662,3 +972,2400 @@ fn test_modulo() {
 fn test_is_even() {
     assert!(type_checks("fn is_even_mod(n: i64) -> bool = n % 2 == 0;"));
 }
+
+// ============================================
+// Math Builtin Tests
+// ============================================
+
+#[test]
+fn test_math_builtins_type_check() {
+    assert!(type_checks(
+        "fn f(x: f64) -> f64 = sin(x) + cos(x) + floor(x) + ceil(x) + round(x) + ln(x) + exp(x);"
+    ));
+    assert!(type_checks("fn g(x: f64, y: f64) -> f64 = pow(x, y);"));
+    assert!(type_error("fn h(x: i64) -> f64 = sin(x);"));
+    assert!(type_error("fn k(x: f64) -> f64 = pow(x);"));
+}
+
+#[test]
+fn test_math_builtins_match_taylor_series() {
+    let source = include_str!("../../tests/examples/valid/math_builtins.bmb");
+    assert_eq!(run_program(source), bmb::interp::Value::Int(1));
+    if let Some(stdout) = run_native("math_builtins", source) {
+        assert_eq!(stdout, "0\n");
+    }
+}
+
+// ============================================
+// File I/O Builtin Tests
+// ============================================
+
+#[test]
+fn test_append_file_example_writes_under_temp_dir() {
+    let source = include_str!("../../tests/examples/valid/io_append_test.bmb");
+    assert_eq!(run_program(source), bmb::interp::Value::Int(0));
+    assert!(!std::path::Path::new("append_test.txt").exists());
+}
+
+// ============================================
+// Structured Diagnostic Tests
+// ============================================
+
+fn first_error_json(source: &str) -> serde_json::Value {
+    let err = check_program(source).expect_err("expected a compile error");
+    err.to_json("test.bmb", source)
+}
+
+#[test]
+fn test_error_json_undefined_variable() {
+    let source = "fn f(value: i64) -> i64 =\n  valeu + 1;";
+    let json = first_error_json(source);
+    assert_eq!(json["code"], "E0301");
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["message"], "undefined variable: `valeu`");
+    assert_eq!(json["hint"], "did you mean `value`?");
+    assert_eq!(json["span"]["line"], 2);
+    assert_eq!(json["span"]["col"], 3);
+}
+
+#[test]
+fn test_error_json_columns_count_characters() {
+    // "東京" is six bytes but two columns
+    let source = "fn f(value: i64) -> i64 =\n  { let s = \"東京\"; valeu };";
+    let json = first_error_json(source);
+    assert_eq!(json["span"]["line"], 2);
+    assert_eq!(json["span"]["col"], 19);
+    assert_eq!(json["span"]["start"], source.find("valeu").unwrap());
+}
+
+#[test]
+fn test_error_json_undeclared_loop_label() {
+    let source = "fn f() -> () = 'outer: loop { loop { break 'outr } };";
+    let json = first_error_json(source);
+    assert_eq!(json["message"], "undeclared loop label: `'outr`");
+    assert_eq!(json["hint"], "did you mean `'outer`?");
+    // A label is only in scope inside its own loop
+    assert!(type_error("fn f() -> () = { 'a: while true { break 'a }; loop { continue 'a } };"));
+    assert!(!type_error("fn f() -> () = 'a: loop { loop { continue 'a } };"));
+}
+
+#[test]
+fn test_error_json_codes_by_phase() {
+    assert_eq!(first_error_json("fn f() -> i64 = 1 +;")["code"], "E0201");
+    assert_eq!(first_error_json("fn f() -> i64 = g(1);")["code"], "E0302");
+    assert_eq!(first_error_json("fn f(x: i64) -> bool = x;")["code"], "E0308");
+}
+
+#[test]
+fn test_warning_json_has_code_and_related() {
+    let source = "fn f() -> i64 = {\n  let x = 1;\n  let x = 2;\n  x\n};";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let mut tc = TypeChecker::new();
+    tc.check_program(&ast).unwrap();
+    let warning = tc
+        .warnings()
+        .iter()
+        .find(|w| w.kind() == "shadow_binding")
+        .expect("expected shadow warning");
+    let json = warning.to_json("test.bmb", source);
+    assert_eq!(json["severity"], "warning");
+    assert_eq!(json["code"], "W0012");
+    assert_eq!(json["related"][0]["span"]["line"], 2);
+}
+
+#[test]
+fn test_diagnostic_report_escapes_and_round_trips() {
+    let source = "fn f() -> i64 = \"a\\tb\";";
+    let err = check_program(source).expect_err("expected a type error");
+    let report = bmb::error::DiagnosticReport::new(vec![err.to_diagnostic("dir\\\"q\".bmb", source)]);
+    let line = report.to_string();
+    assert!(!line.contains('\n'));
+
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(value["diagnostics"][0]["file"], "dir\\\"q\".bmb");
+    assert!(value["diagnostics"][0].as_object().unwrap().contains_key("hint"));
+    let parsed: bmb::error::DiagnosticReport = serde_json::from_str(&line).unwrap();
+    assert_eq!(parsed, report);
+}
+
+#[test]
+fn test_runtime_error_json_carries_stack_frames() {
+    let source = "fn divide(a: i64, b: i64) -> i64 = a / b;\n\
+                  fn average(t: i64, n: i64) -> i64 =\n  divide(t, n);\n\
+                  fn main() -> i64 = average(10, 0);";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let mut interp = bmb::interp::Interpreter::new();
+    let err = interp.run(&ast).expect_err("division by zero");
+    let json = err.to_json("test.bmb", source);
+
+    let frames = json["frames"].as_array().expect("frames array");
+    let trace: Vec<_> = frames
+        .iter()
+        .map(|f| (f["function"].as_str().unwrap(), f["span"]["line"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(trace, vec![("divide", 1), ("average", 3), ("main", 4)]);
+
+    let rendered = err.to_diagnostic("test.bmb", source).render(source);
+    assert!(rendered.contains("at average (test.bmb:3)"), "{rendered}");
+}
+
+#[test]
+fn test_raw_string_literals() {
+    let source = r####"fn main() -> i64 = r"C:\temp".len() + r#"say "hi""#.len();"####;
+    assert_eq!(run_program(source), bmb::interp::Value::Int(15));
+
+    let tokens = tokenize(r###"r"a\b" r#"say "hi""# r##"x"#y"##"###).unwrap();
+    let tokens: Vec<_> = tokens.into_iter().map(|(t, _)| t).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::StringLit(r"a\b".to_string()),
+            Token::StringLit(r#"say "hi""#.to_string()),
+            Token::StringLit(r##"x"#y"##.to_string()),
+        ]
+    );
+
+    let err = tokenize("let s = r#\"open\" ;").unwrap_err();
+    assert!(err.message().contains("unterminated raw string"), "{}", err.message());
+    assert_eq!(err.span(), Some(bmb::ast::Span::new(8, 11)));
+}
+
+// ============================================
+// Trait Bound Tests
+// ============================================
+
+const SHOW_TRAIT: &str = "
+    trait Show {
+        fn show(self: Self) -> i64;
+    }
+    struct Point { x: i64, y: i64 }
+    impl Show for Point {
+        fn show(self: Self) -> i64 = self.x + self.y;
+    }
+    fn display<T: Show>(value: T) -> i64 = value.show();
+";
+
+#[test]
+fn test_trait_bound_satisfied() {
+    let source = format!("{SHOW_TRAIT}\nfn main() -> i64 = display(new Point {{ x: 1, y: 2 }});");
+    assert!(type_checks(&source));
+}
+
+#[test]
+fn test_trait_bound_unsatisfied() {
+    let source = format!("{SHOW_TRAIT}\nfn main() -> i64 = display(42);");
+    let err = check_program(&source).expect_err("i64 has no Show impl");
+    assert!(err.message().contains("`T: Show` is not satisfied"), "{}", err.message());
+    assert_eq!(err.code(), "E0309");
+}
+
+#[test]
+fn test_trait_bound_method_dispatch_in_body() {
+    // The bound's signature gives `show()` its i64 return type
+    let source = format!("{SHOW_TRAIT}\nfn twice<T: Show>(value: T) -> i64 = value.show() * 2;");
+    assert!(type_checks(&source));
+    assert!(type_error("fn bad<T>(value: T) -> i64 = value.show();"));
+    let wrong_ret = format!("{SHOW_TRAIT}\nfn f<T: Show>(value: T) -> bool = value.show();");
+    assert!(type_error(&wrong_ret));
+}
+
+// ============================================
+// Trait Default Method Tests
+// ============================================
+
+const DESCRIBE_TRAIT: &str = "
+    trait Describe {
+        fn id(self: Self) -> i64;
+        fn summary(self: Self) -> i64 = self.id() * 100;
+    }
+    struct Widget { n: i64 }
+    struct Gadget { n: i64 }
+    impl Describe for Widget {
+        fn id(self: Self) -> i64 = self.n;
+    }
+    impl Describe for Gadget {
+        fn id(self: Self) -> i64 = self.n;
+        fn summary(self: Self) -> i64 = self.n + 1;
+    }
+";
+
+#[test]
+fn test_trait_default_method_inherited() {
+    let source = format!("{DESCRIBE_TRAIT}
+fn main() -> i64 = {{ let w = new Widget {{ n: 7 }}; w.summary() }};");
+    assert!(type_checks(&source));
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(700));
+}
+
+#[test]
+fn test_trait_default_method_overridden() {
+    let source = format!("{DESCRIBE_TRAIT}
+fn main() -> i64 = {{ let g = new Gadget {{ n: 7 }}; g.summary() }};");
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(8));
+}
+
+#[test]
+fn test_trait_default_method_body_checked() {
+    // `Self` is abstract in a default body: only the trait's own methods are usable
+    assert!(type_error(
+        "trait Describe { fn summary(self: Self) -> i64 = self.n; }"
+    ));
+    assert!(type_error(
+        "trait Describe { fn id(self: Self) -> i64; fn summary(self: Self) -> bool = self.id(); }"
+    ));
+}
+
+// ============================================
+// Generic Impl Block Tests
+// ============================================
+
+const GENERIC_IMPL: &str = "
+    trait Holder {
+        fn size(self: Self) -> i64;
+    }
+    struct Wrapper<T> { value: T, size: i64 }
+    impl<T> Holder for Wrapper<T> {
+        fn size(self: Self) -> i64 = self.size;
+        fn get(self: Self) -> T = self.value;
+    }
+";
+
+#[test]
+fn test_generic_impl_two_instantiations() {
+    let source = format!(
+        "{GENERIC_IMPL}
+        fn main() -> i64 = {{
+            let a = new Wrapper {{ value: 5, size: 1 }};
+            let b = new Wrapper {{ value: true, size: 2 }};
+            if b.get() {{ a.get() + a.size() + b.size() }} else {{ 0 }}
+        }};"
+    );
+    assert!(type_checks(&source));
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(8));
+}
+
+#[test]
+fn test_generic_impl_instantiates_method_signature() {
+    // `get` on a Wrapper<i64> returns i64, not bool
+    let source = format!(
+        "{GENERIC_IMPL}
+        fn main() -> bool = {{
+            let a = new Wrapper {{ value: 5, size: 1 }};
+            let flag: bool = a.get();
+            flag
+        }};"
+    );
+    assert!(type_error(&source));
+}
+
+#[test]
+fn test_generic_impl_bounds() {
+    let source = format!(
+        "{SHOW_TRAIT}
+        struct Boxed<T> {{ inner: T }}
+        impl<T: Show> Show for Boxed<T> {{
+            fn show(self: Self) -> i64 = self.inner.show();
+        }}
+        fn ok() -> i64 = display(new Boxed {{ inner: new Point {{ x: 1, y: 2 }} }});"
+    );
+    assert!(type_checks(&source));
+    let bad = format!(
+        "{SHOW_TRAIT}
+        struct Boxed<T> {{ inner: T }}
+        impl<T: Show> Show for Boxed<T> {{
+            fn show(self: Self) -> i64 = self.inner.show();
+        }}
+        fn bad() -> i64 = display(new Boxed {{ inner: 42 }});"
+    );
+    let err = check_program(&bad).expect_err("Boxed<i64> does not implement Show");
+    assert_eq!(err.code(), "E0309");
+}
+
+#[test]
+fn test_generic_inherent_method_at_two_instantiations() {
+    let source = "
+        struct Container<T> { items: [T; 2], len: i64 }
+        impl<T> Container<T> {
+            fn first(self) -> T = self.items[0];
+        }
+        fn main() -> i64 = {
+            let nums = new Container { items: [7, 8], len: 2 };
+            let flags = new Container { items: [true, false], len: 2 };
+            let n: i64 = nums.first();
+            if flags.first() { n + flags.len } else { 0 }
+        };";
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(9));
+}
+
+#[test]
+fn test_generic_impl_with_repeated_type_param() {
+    // `Pair<T, T>` only covers pairs of one type, leaving room for `Pair<i64, bool>`
+    let pairs = "
+        trait Show { fn show(self: Self) -> i64; }
+        struct Pair<A, B> { a: A, b: B }
+        impl<T> Show for Pair<T, T> {
+            fn show(self: Self) -> i64 = 1;
+        }
+        impl Show for Pair<i64, bool> {
+            fn show(self: Self) -> i64 = 2;
+        }
+    ";
+    assert!(type_checks(&format!(
+        "{pairs}
+        fn f() -> i64 = new Pair {{ a: 1, b: 2 }}.show() + new Pair {{ a: 1, b: true }}.show();"
+    )));
+    assert!(type_error(&format!(
+        "{pairs}
+        fn f() -> i64 = new Pair {{ a: true, b: 2 }}.show();"
+    )));
+}
+
+#[test]
+fn test_overlapping_impls_rejected() {
+    let source = "trait Show { fn show(self: Self) -> i64; }
+struct Pair<A, B> { a: A, b: B }
+impl<T> Show for Pair<T, i64> { fn show(self: Self) -> i64 = 1; }
+impl<U> Show for Pair<bool, U> { fn show(self: Self) -> i64 = 2; }
+";
+    let json = first_error_json(source);
+    assert_eq!(json["code"], "E0312");
+    assert_eq!(json["message"], "conflicting implementations of trait `Show` for type `Pair<bool, U>`");
+    assert_eq!(json["span"]["line"], 4);
+    assert_eq!(json["related"][0]["message"], "first implementation here");
+    assert_eq!(json["related"][0]["span"]["line"], 3);
+
+    // Inherent impls only conflict on a shared method name
+    let inherent = "struct Pair<A, B> { a: A, b: B }
+impl<T> Pair<T, i64> { fn left(self) -> T = self.a; }
+impl Pair<bool, i64> { fn right(self) -> i64 = self.b; }
+";
+    assert!(type_checks(inherent));
+    let err = check_program(&format!("{inherent}impl<T> Pair<bool, T> {{ fn left(self) -> bool = self.a; }}"))
+        .expect_err("`left` is defined twice for Pair<bool, i64>");
+    assert_eq!(err.code(), "E0312");
+}
+
+// ============================================
+// Inherent Impl Tests
+// ============================================
+
+const POINT_IMPL: &str = "
+    struct Point { x: i64, y: i64 }
+    impl Point {
+        fn dist2(self, other: Point) -> i64 =
+            (self.x - other.x) * (self.x - other.x) + (self.y - other.y) * (self.y - other.y);
+        fn scaled(self, k: i64) -> Point = new Point { x: self.x * k, y: self.y * k };
+    }
+";
+
+#[test]
+fn test_inherent_method_call() {
+    let source = format!(
+        "{POINT_IMPL}
+        fn main() -> i64 = {{
+            let p = new Point {{ x: 1, y: 2 }};
+            let q = p.scaled(3);
+            p.dist2(q)
+        }};"
+    );
+    assert!(type_checks(&source));
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(20));
+    // Inherent methods are checked against their signatures
+    let bad = format!("{POINT_IMPL}\nfn main() -> bool = new Point {{ x: 1, y: 2 }}.dist2(3);");
+    assert!(type_error(&bad));
+}
+
+#[test]
+fn test_inherent_method_shadows_trait_method() {
+    let source = format!(
+        "{SHOW_TRAIT}
+        impl Point {{
+            fn show(self) -> i64 = self.x * 10 + self.y;
+        }}
+        fn main() -> i64 = new Point {{ x: 1, y: 2 }}.show();"
+    );
+    assert!(has_warning_kind(&source, "shadowed_trait_method"));
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(12));
+    // No warning without a clash
+    assert!(!has_warning_kind(POINT_IMPL, "shadowed_trait_method"));
+}
+
+#[test]
+fn test_inherent_method_lowers_to_function_call() {
+    let source = format!(
+        "{POINT_IMPL}
+        fn main() -> i64 = {{
+            let p = new Point {{ x: 1, y: 2 }};
+            p.dist2(p)
+        }};"
+    );
+    let ast = parse("test.bmb", &source, tokenize(&source).unwrap()).unwrap();
+    let mir = bmb::mir::lower_program(&ast);
+    assert!(mir.functions.iter().any(|f| f.name == "Point.dist2"));
+    let main = mir.functions.iter().find(|f| f.name == "main").unwrap();
+    assert!(main.blocks.iter().flat_map(|b| &b.instructions).any(
+        |inst| matches!(inst, bmb::mir::MirInst::Call { func, .. } if func == "Point.dist2")
+    ));
+}
+
+// ============================================
+// Const Declaration Tests
+// ============================================
+
+const CONST_ARRAY: &str = "
+    const SIZE: i64 = 2 * 2;
+    const LIMIT: i64 = SIZE * 25;
+
+    fn clamp_first(xs: [i64; SIZE]) -> i64
+      post ret <= LIMIT
+    = if xs[0] > LIMIT { LIMIT } else { xs[0] };
+";
+
+#[test]
+fn test_const_array_length_and_value() {
+    let source = format!(
+        "{CONST_ARRAY}
+        fn main() -> i64 = {{
+            let xs: [i64; SIZE] = [500, 2, 3, 4];
+            clamp_first(xs) + SIZE
+        }};"
+    );
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(104));
+    // The resolved length is checked against array literals
+    assert!(type_error(&format!("{CONST_ARRAY}\nfn main() -> i64 = clamp_first([1, 2, 3]);")));
+}
+
+#[test]
+fn test_const_substituted_in_mir() {
+    let source = format!("{CONST_ARRAY}\nfn main() -> i64 = LIMIT;");
+    let ast = parse("test.bmb", &source, tokenize(&source).unwrap()).unwrap();
+    let mir = bmb::mir::lower_program(&ast);
+    let main = mir.functions.iter().find(|f| f.name == "main").unwrap();
+    let ret = main.blocks.iter().find_map(|b| match &b.terminator {
+        bmb::mir::Terminator::Return(Some(op)) => Some(op.clone()),
+        _ => None,
+    });
+    assert!(matches!(ret, Some(bmb::mir::Operand::Constant(bmb::mir::Constant::Int(100)))));
+}
+
+#[test]
+fn test_const_initializer_errors() {
+    let err = check_program("fn f() -> i64 = 1;\nconst N: i64 = f();\nfn main() -> i64 = N;").unwrap_err();
+    assert_eq!(err.code(), "E0310");
+    let err = check_program("const A: i64 = B;\nconst B: i64 = A + 1;\nfn main() -> i64 = A;").unwrap_err();
+    assert_eq!(err.code(), "E0311");
+    assert!(type_error("const N: i64 = 3;\nfn main() -> i64 = { N = 4; N };"));
+    assert!(type_error("fn f(xs: [i64; MISSING]) -> i64 = 0;"));
+}
+
+// ============================================
+// HashMap<K, V> Tests
+// ============================================
+
+#[test]
+fn test_hashmap_string_keys() {
+    let source = r#"
+        fn main() -> i64 = {
+            let m: HashMap<String, i64> = HashMap::new();
+            let a = m.insert("apple", 3);
+            let b = m.insert("pear", 5);
+            let replaced = m.insert("apple", 4).unwrap_or(0);
+            let removed = m.remove("pear").unwrap_or(0);
+            m.get("apple").unwrap_or(0) * 1000 + replaced * 100 + removed * 10 + m.len()
+        };
+    "#;
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(4351));
+}
+
+#[test]
+fn test_hashmap_get_miss_is_none() {
+    let source = r#"
+        enum Option<T> { Some(T), None }
+        fn lookup(m: HashMap<String, i64>, key: String) -> i64 =
+            match m.get(key) {
+                Option::Some(v) => v,
+                Option::None => 0 - 1
+            };
+        fn main() -> i64 = {
+            let m: HashMap<String, i64> = HashMap::new();
+            let a = m.insert("present", 7);
+            if m.contains("missing") or m.get("missing").is_some() { 0 }
+            else { lookup(m, "present") * 10 + lookup(m, "missing") }
+        };
+    "#;
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(69));
+}
+
+#[test]
+fn test_hashmap_method_type_errors() {
+    let wrong_key = r#"fn f() -> i64 = {
+        let m: HashMap<String, i64> = HashMap::new();
+        m.get(1).unwrap_or(0)
+    };"#;
+    assert!(type_error(wrong_key));
+    let wrong_value = r#"fn f() -> bool = {
+        let m: HashMap<i64, bool> = HashMap::new();
+        m.insert(1, 2).is_some()
+    };"#;
+    assert!(type_error(wrong_value));
+    let get_is_option = r#"fn f(m: HashMap<i64, i64>) -> i64 = m.get(1);"#;
+    assert!(type_error(get_is_option));
+    assert!(type_error("fn f(m: HashMap<f64, i64>) -> i64 = m.len();"));
+}
+
+// ============================================
+// assert_eq / assert_ne Tests
+// ============================================
+
+#[test]
+fn test_assert_eq_type_checks_primitives() {
+    assert!(type_checks(
+        r#"fn f() -> bool = {
+            assert_eq(1, 1);
+            assert_eq(1.5, 1.5);
+            assert_ne(true, false);
+            assert_eq("a", "a");
+            assert_ne('x', 'y');
+            true
+        };"#
+    ));
+    assert!(type_error("fn f() -> bool = { assert_eq(1, true); true };"));
+    assert!(type_error("fn f() -> bool = { assert_eq(1); true };"));
+    assert!(type_error(
+        "struct P { x: i64 }
+         fn f(a: P, b: P) -> bool = { assert_eq(a, b); true };"
+    ));
+}
+
+#[test]
+fn test_assert_eq_failure_reports_both_values() {
+    let source = "fn main() -> bool = { assert_eq(6 * 7, 41); true };";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let mut interp = bmb::interp::Interpreter::new();
+    let err = interp.run(&ast).expect_err("42 != 41");
+    assert_eq!(err.message, "assertion failed: left = 42, right = 41");
+    assert_eq!(err.operands, Some(("42".to_string(), "41".to_string())));
+
+    let source = "fn main() -> bool = { assert_ne(\"a\", \"a\"); true };";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let err = interp.run(&ast).expect_err("equal operands");
+    assert!(err.message.contains("left = \"a\", right = \"a\""), "{}", err.message);
+}
+
+// ============================================
+// Let Statement Tests
+// ============================================
+
+#[test]
+fn test_let_statements_scope_to_rest_of_block() {
+    let source = r#"
+        fn step(n: i64) -> i64 = {
+            let mut x = n;
+            x = x + 1;
+            let y = x * 10;
+            x = x + y;
+            x
+        };
+        fn main() -> i64 = step(1);
+    "#;
+    assert_eq!(run_program(source), bmb::interp::Value::Int(22));
+}
+
+#[test]
+fn test_let_statement_ends_with_its_block() {
+    assert!(type_error(
+        "fn main() -> i64 = { let a = { let inner = 1; inner }; inner + a };"
+    ));
+    assert!(has_warning_kind(
+        "fn main() -> i64 = { let x = 1; let unused = 2; x };",
+        "unused_binding"
+    ));
+}
+
+// ============================================
+// Early Return Tests
+// ============================================
+
+#[test]
+fn test_return_in_then_branch_unwinds_function() {
+    let source = "
+        fn clamp(n: i64) -> i64 = {
+            let m = if n > 10 then return 10 else n;
+            m * 2
+        };
+        fn main() -> i64 = clamp(50) * 100 + clamp(3);
+    ";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(1006));
+}
+
+#[test]
+fn test_return_from_inside_loops() {
+    let source = "
+        fn first_multiple(of: i64) -> i64 = {
+            for i in 1..<100 {
+                if i % of == 0 { return i } else { () }
+            };
+            0 - 1
+        };
+        fn countdown(n: i64) -> i64 = {
+            let mut k = n;
+            while true { {
+                k = k - 1;
+                if k < 3 { return k * 10 } else { () }
+            } };
+            0
+        };
+        fn main() -> i64 = first_multiple(7) * 1000 + countdown(8);
+    ";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(7020));
+}
+
+#[test]
+fn test_return_from_nested_blocks_and_closures() {
+    let source = "
+        fn pick(a: i64) -> i64 = {
+            let x = {
+                let y = { if a == 1 { return 100 } else { a + 1 } };
+                y * 2
+            };
+            x + 1
+        };
+        fn twice(a: i64) -> i64 = {
+            let f = fn |v: i64| { if v > 0 { return v } else { 0 } };
+            f(a) + f(a)
+        };
+        fn main() -> i64 = pick(1) + pick(2) + twice(4);
+    ";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(100 + 7 + 8));
+}
+
+#[test]
+fn test_return_type_checks_against_declared_type() {
+    assert!(type_checks("fn f(c: bool) -> i64 = if c then return 1 else 2;"));
+    assert!(type_checks(
+        "fn f(n: i64) -> i64 = match n { 0 => return 1, _ => n * 2 };"
+    ));
+    assert!(type_error("fn f(c: bool) -> i64 = if c then return true else 2;"));
+    assert!(type_error("fn f(c: bool) -> i64 = { if c { return } else { () }; 2 };"));
+}
+
+// ============================================
+// Derived Equality Tests
+// ============================================
+
+#[test]
+fn test_derived_eq_compares_nested_structs() {
+    let source = "@derive(Eq)
+         struct Point { x: i64, y: i64 }
+         @derive(Eq)
+         struct Line { from: Point, to: Point }
+         fn line(y: i64) -> Line = new Line { from: new Point { x: 0, y: 0 }, to: new Point { x: 3, y: y } };
+         fn main() -> i64 = {
+             let same = if line(4) == line(4) { 1 } else { 0 };
+             let differ = if line(4) != line(5) { 10 } else { 0 };
+             same + differ
+         };";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(11));
+}
+
+#[test]
+fn test_derived_eq_compares_enum_payloads() {
+    let source = "@derive(Eq)
+         struct Point { x: i64, y: i64 }
+         @derive(Eq)
+         enum Shape { Dot(Point), Circle(Point, i64), Empty }
+         fn main() -> i64 = {
+             let c = Shape::Circle(new Point { x: 1, y: 1 }, 5);
+             let a = if c == Shape::Circle(new Point { x: 1, y: 1 }, 5) { 1 } else { 0 };
+             let b = if c == Shape::Circle(new Point { x: 1, y: 1 }, 6) { 10 } else { 0 };
+             let d = if Shape::Dot(new Point { x: 1, y: 1 }) == c { 100 } else { 0 };
+             let e = if Shape::Empty == Shape::Empty { 1000 } else { 0 };
+             a + b + d + e
+         };";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(1001));
+}
+
+#[test]
+fn test_equality_requires_derive() {
+    let err = check_program(
+        "struct Point { x: i64, y: i64 }
+         fn same(a: Point, b: Point) -> bool = a == b;",
+    )
+    .unwrap_err();
+    assert!(err.message().contains("add `@derive(Eq)` to `Point`"), "{}", err.message());
+
+    // A derived type may only hold comparable fields
+    let err = check_program(
+        "struct Inner { x: i64 }
+         @derive(Eq)
+         struct Outer { inner: Inner }
+         fn main() -> i64 = 0;",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "cannot derive `Eq` for `Outer`: field `inner` has type `Inner`, which is not comparable"
+    );
+}
+
+// ============================================
+// Derived Debug Tests
+// ============================================
+
+/// The string `main` returns, whether a plain string or a concatenation rope
+fn debug_string_of(source: &str) -> String {
+    let value = run_program(source);
+    value.materialize_string().unwrap_or_else(|| panic!("expected a string, got {value}"))
+}
+
+#[test]
+fn test_derived_debug_formats_nested_structs() {
+    let source = "@derive(Debug)
+         struct Point { x: i64, y: i64 }
+         @derive(Debug)
+         struct Labeled { name: String, at: Point, tags: [i64; 2] }
+         fn main() -> String =
+             new Labeled { name: \"origin\", at: new Point { x: 3, y: 4 }, tags: [1, 2] }.to_debug_string();";
+    assert_eq!(
+        debug_string_of(source),
+        "Labeled { name: \"origin\", at: Point { x: 3, y: 4 }, tags: [1, 2] }"
+    );
+}
+
+#[test]
+fn test_derived_debug_formats_enum_variants() {
+    let prelude = "@derive(Debug)
+         struct Point { x: i64, y: i64 }
+         @derive(Debug)
+         enum Shape { Dot(Point), Circle(Point, i64), Empty }";
+    let with_payload = format!(
+        "{prelude} fn main() -> String = Shape::Circle(new Point {{ x: 1, y: 2 }}, 5).to_debug_string();"
+    );
+    assert_eq!(debug_string_of(&with_payload), "Circle(Point { x: 1, y: 2 }, 5)");
+    let empty = format!("{prelude} fn main() -> String = Shape::Empty.to_debug_string();");
+    assert_eq!(debug_string_of(&empty), "Empty");
+}
+
+#[test]
+fn test_debug_string_requires_derive() {
+    let err = check_program(
+        "struct Point { x: i64, y: i64 }
+         fn show(p: Point) -> String = p.to_debug_string();",
+    )
+    .unwrap_err();
+    assert!(err.message().contains("add `@derive(Debug)` to `Point`"), "{}", err.message());
+
+    assert!(type_error(
+        "struct Inner { x: i64 }
+         @derive(Debug)
+         enum Outer { Wrap(Inner) }
+         fn main() -> i64 = 0;"
+    ));
+}
+
+// ============================================
+// Derived Hash Tests
+// ============================================
+
+#[test]
+fn test_derived_hash_equal_for_equal_structs() {
+    let source = "@derive(Hash)
+         struct Point { x: i64, y: i64 }
+         @derive(Hash)
+         struct Line { from: Point, to: Point, name: String }
+         fn line(y: i64) -> Line =
+             new Line { from: new Point { x: 0, y: 0 }, to: new Point { x: 3, y: y }, name: \"l\" };
+         fn main() -> i64 = {
+             let same = if line(4).hash() == line(4).hash() { 1 } else { 0 };
+             // The nested `to.y` takes part in the hash
+             let differ = if line(4).hash() != line(5).hash() { 10 } else { 0 };
+             let swapped = if new Point { x: 1, y: 2 }.hash() != new Point { x: 2, y: 1 }.hash() { 100 } else { 0 };
+             same + differ + swapped
+         };";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(111));
+
+    // Computed from the value alone, with no per-run seed
+    let point = "@derive(Hash) struct Point { x: i64, y: i64 }
+         fn main() -> i64 = new Point { x: 3, y: 4 }.hash();";
+    assert_eq!(run_program(point), run_program(point));
+}
+
+#[test]
+fn test_derived_hash_enum_variants_differ() {
+    let source = "@derive(Hash)
+         enum Shape { Dot(i64), Square(i64), Empty }
+         fn main() -> i64 = {
+             let a = if Shape::Dot(1).hash() != Shape::Square(1).hash() { 1 } else { 0 };
+             let b = if Shape::Empty.hash() == Shape::Empty.hash() { 10 } else { 0 };
+             a + b
+         };";
+    assert_eq!(run_program(source), bmb::interp::Value::Int(11));
+}
+
+#[test]
+fn test_derived_hash_and_eq_make_hashmap_keys() {
+    let source = r#"
+        @derive(Hash, Eq)
+        struct Point { x: i64, y: i64 }
+        fn main() -> i64 = {
+            let m: HashMap<Point, i64> = HashMap::new();
+            let a = m.insert(new Point { x: 1, y: 2 }, 7);
+            let b = m.insert(new Point { x: 2, y: 1 }, 9);
+            m.get(new Point { x: 1, y: 2 }).unwrap_or(0) * 10 + m.len()
+        };
+    "#;
+    assert!(type_checks(source));
+    assert_eq!(run_program(source), bmb::interp::Value::Int(72));
+
+    // Keys need both derives
+    assert!(type_error(
+        "@derive(Hash) struct Point { x: i64 }
+         fn f(m: HashMap<Point, i64>) -> i64 = m.len();"
+    ));
+}
+
+#[test]
+fn test_hash_requires_derive() {
+    let err = check_program(
+        "struct Point { x: i64, y: i64 }
+         fn h(p: Point) -> i64 = p.hash();",
+    )
+    .unwrap_err();
+    assert!(err.message().contains("add `@derive(Hash)` to `Point`"), "{}", err.message());
+
+    let err = check_program(
+        "@derive(Hash)
+         struct Sample { at: f64 }
+         fn main() -> i64 = 0;",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "cannot derive `Hash` for `Sample`: field `at` has type `f64`, which is not hashable"
+    );
+}
+
+// ============================================
+// Derived Eq and Show Method Tests
+// ============================================
+
+const DERIVED_SHAPES: &str = "
+    @derive(Eq, Show)
+    struct Point { x: i64, y: i64 }
+    @derive(Eq, Show)
+    enum Shape { Dot(Point), Circle(Point, i64), Empty }
+    @derive(Eq, Show)
+    struct Named { name: String, shape: Shape, visible: bool, tag: char }";
+
+#[test]
+fn test_derived_eq_method_compares_fields() {
+    let source = format!(
+        "{DERIVED_SHAPES}
+        fn named(r: i64) -> Named =
+            new Named {{ name: \"c\", shape: Shape::Circle(new Point {{ x: 1, y: 2 }}, r), visible: true, tag: 'a' }};
+        fn main() -> i64 = {{
+            let same = if named(5).eq(named(5)) {{ 1 }} else {{ 0 }};
+            // The nested radius takes part in the comparison
+            let differ = if named(5).eq(named(6)) {{ 0 }} else {{ 10 }};
+            let variants = if Shape::Empty.eq(Shape::Dot(new Point {{ x: 0, y: 0 }})) {{ 0 }} else {{ 100 }};
+            same + differ + variants
+        }};"
+    );
+    assert_eq!(run_program(&source), bmb::interp::Value::Int(111));
+}
+
+#[test]
+fn test_derived_show_method_renders_values() {
+    let point = format!("{DERIVED_SHAPES} fn main() -> String = new Point {{ x: 1, y: -2 }}.show();");
+    assert_eq!(debug_string_of(&point), "Point { x: 1, y: -2 }");
+
+    let named = format!(
+        "{DERIVED_SHAPES}
+        fn main() -> String =
+            new Named {{ name: \"c\", shape: Shape::Circle(new Point {{ x: 1, y: 2 }}, 5), visible: true, tag: 'a' }}.show();"
+    );
+    assert_eq!(
+        debug_string_of(&named),
+        "Named { name: \"c\", shape: Circle(Point { x: 1, y: 2 }, 5), visible: true, tag: a }"
+    );
+
+    let empty = format!("{DERIVED_SHAPES} fn main() -> String = Shape::Empty.show();");
+    assert_eq!(debug_string_of(&empty), "Empty");
+}
+
+#[test]
+fn test_derived_methods_lower_to_functions() {
+    let source = format!("{DERIVED_SHAPES} fn main() -> String = Shape::Dot(new Point {{ x: 1, y: 2 }}).show();");
+    let ast = parse("test.bmb", &source, tokenize(&source).unwrap()).unwrap();
+    let mir = bmb::mir::lower_program(&ast);
+    for name in ["Point.eq", "Point.show", "Shape.eq", "Shape.show", "Named.show"] {
+        assert!(mir.functions.iter().any(|f| f.name == name), "missing {name}");
+    }
+    // The payload is rendered by `Point`'s method, not `Shape`'s
+    let shape_show = mir.functions.iter().find(|f| f.name == "Shape.show").unwrap();
+    assert!(shape_show.blocks.iter().flat_map(|b| &b.instructions).any(
+        |inst| matches!(inst, bmb::mir::MirInst::Call { func, .. } if func == "Point.show")
+    ));
+}
+
+#[test]
+fn test_derived_show_rejects_unsupported_fields() {
+    let source = "struct Inner { x: i64 }
+         @derive(Show)
+         struct Outer { inner: Inner }
+         fn main() -> i64 = 0;";
+    let err = check_program(source).unwrap_err();
+    assert_eq!(
+        err.message(),
+        "cannot derive `Show` for `Outer`: field `inner` has type `Inner`, which is not showable"
+    );
+    // Reported at the attribute, not the field
+    let span = err.span().unwrap();
+    assert_eq!(&source[span.start..span.end], "@derive(Show)");
+
+    let err = check_program(
+        "struct Point { x: i64 }
+         fn s(p: Point) -> String = p.show();",
+    )
+    .unwrap_err();
+    assert!(err.message().contains("add `@derive(Show)` to `Point`"), "{}", err.message());
+
+    assert!(type_error(
+        "@derive(Show)
+         struct Sample { at: f64 }
+         fn main() -> i64 = 0;"
+    ));
+    assert!(type_error(
+        "@derive(Show)
+         struct Boxed<T> { value: T }
+         fn main() -> i64 = 0;"
+    ));
+}
+
+#[test]
+fn test_derived_method_conflicts_with_written_method() {
+    let err = check_program(
+        "@derive(Show)
+         struct Point { x: i64 }
+         impl Point {
+             fn show(self) -> String = \"p\";
+         }
+         fn main() -> i64 = 0;",
+    )
+    .unwrap_err();
+    assert_eq!(err.message(), "conflicting implementations of method `show` for type `Point`");
+}
+
+// ============================================
+// MIR Dead Function Elimination Tests
+// ============================================
+
+#[test]
+fn test_emit_mir_release_drops_unreachable_functions() {
+    let golden_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let out = std::env::temp_dir().join(format!("bmb_dce_{}.mir", std::process::id()));
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("build")
+        .arg(golden_dir.join("dce_unreachable.bmb"))
+        .args(["--emit-mir", "--release", "-o"])
+        .arg(&out)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let emitted = std::fs::read_to_string(&out).unwrap();
+    let _ = std::fs::remove_file(&out);
+    let expected = std::fs::read_to_string(golden_dir.join("dce_unreachable.mir")).unwrap();
+    assert_eq!(emitted, expected);
+    assert_eq!(emitted.lines().filter(|l| l.starts_with("fn ")).count(), 2);
+}
+
+#[test]
+fn test_emit_cfg_writes_dot_next_to_source() {
+    let dir = std::env::temp_dir().join(format!("bmb_cfg_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("clamp.bmb");
+    std::fs::write(
+        &src,
+        "fn clamp(x: i64) -> i64\n  pre x >= 0\n= if x > 10 { 10 } else { x };\n\nfn main() -> i64 = clamp(5);\n",
+    )
+    .unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("build")
+        .arg(&src)
+        .arg("--emit-cfg")
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let dot = std::fs::read_to_string(dir.join("clamp.cfg.dot")).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(dot.starts_with("// fn clamp(x: i64) -> i64\n//   pre x >= 0\ndigraph \"clamp\" {"), "{}", dot);
+    assert_eq!(dot.matches("digraph ").count(), 2);
+    assert!(dot.contains("[label=\"true\"]") && dot.contains("[label=\"false\"]"), "{}", dot);
+}
+
+// ============================================
+// Check Directory and Watch Tests
+// ============================================
+
+#[test]
+fn test_check_directory_checks_each_file() {
+    let dir = std::env::temp_dir().join(format!("bmb_check_dir_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("good.bmb"), "fn main() -> i64 = 0;\n").unwrap();
+    std::fs::write(dir.join("bad.bmb"), "fn main() -> i64 = true;\n").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("check")
+        .arg(&dir)
+        .output()
+        .expect("failed to run bmb");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("good.bmb\",\"type\":\"success\""), "{}", stdout);
+    assert!(stdout.contains("expected i64, got bool"), "{}", stdout);
+}
+
+/// A spawned `bmb` that is killed when dropped, so a failing test cannot
+/// leave a watcher running
+struct KillOnDrop(std::process::Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn test_check_watch_rechecks_after_failure() {
+    use std::io::BufRead;
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("bmb_check_watch_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("main.bmb");
+    std::fs::write(&src, "fn main() -> i64 = 0;\n").unwrap();
+    let mut child = KillOnDrop(
+        std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .args(["check", "--watch", "--debounce", "100"])
+            .arg(&src)
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .expect("failed to run bmb"),
+    );
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let stdout = child.0.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = tx.send(line);
+        }
+    });
+    let wait_for = |needle: &str| loop {
+        match rx.recv_timeout(Duration::from_secs(20)) {
+            Ok(line) if line.contains(needle) => break line,
+            Ok(_) => {}
+            Err(_) => panic!("timed out waiting for {needle}"),
+        }
+    };
+
+    wait_for("\"type\":\"success\"");
+    std::fs::write(&src, "fn main() -> i64 = true;\n").unwrap();
+    assert!(wait_for("\"type\":\"changed\"").contains("main.bmb"));
+    wait_for("expected i64, got bool");
+    // The failed check leaves the watcher running
+    std::fs::write(&src, "fn main() -> i64 = 1;\n").unwrap();
+    wait_for("\"type\":\"changed\"");
+    wait_for("\"type\":\"success\"");
+
+    drop(child);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// Doc Generation Tests
+// ============================================
+
+#[test]
+fn test_doc_json_lists_public_items() {
+    let dir = std::env::temp_dir().join(format!("bmb_doc_json_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("lib.bmb");
+    std::fs::write(
+        &file,
+        "/// Larger of two values
+pub fn max<T: Ord>(a: T, b: T) -> r: T
+  pre true
+  post r >= a
+= if a > b { a } else { b };
+
+/// Not exported
+fn helper() -> i64 = 0;
+
+pub enum Shape { Dot, Box(i64) }
+",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("doc")
+        .arg(&file)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let doc: serde_json::Value = serde_json::from_str(stdout.lines().next().unwrap()).unwrap();
+    assert_eq!(doc["type"], "doc");
+
+    let symbols = doc["symbols"].as_array().unwrap();
+    assert_eq!(symbols.len(), 2, "{}", stdout);
+    assert_eq!(symbols[0]["kind"], "fn");
+    assert_eq!(symbols[0]["signature"], "fn max<T: Ord>(a: T, b: T) -> r: T");
+    assert_eq!(symbols[0]["pre"], "true");
+    assert_eq!(symbols[0]["post"], "r >= a");
+    assert_eq!(symbols[0]["doc"], "Larger of two values");
+    assert_eq!(symbols[0]["line"], 2);
+    assert_eq!(symbols[1]["signature"], "enum Shape { Dot, Box(i64) }");
+    // The private helper's doc comment stays with it
+    assert!(symbols[1]["doc"].is_null());
+    assert!(!stdout.contains("helper"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_doc_markdown_pages_link_types_across_modules() {
+    let dir = std::env::temp_dir().join(format!("bmb_doc_md_{}", std::process::id()));
+    let src = dir.join("src");
+    std::fs::create_dir_all(src.join("geo")).unwrap();
+    std::fs::write(
+        src.join("geo/shapes.bmb"),
+        "/// A rectangle\npub struct Rect { w: i64, h: i64 }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src.join("area.bmb"),
+        "/// Area of `r`\npub fn area(r: Rect) -> i64\n  pre r.w >= 0\n= r.w * r.h;\nfn hidden() -> i64 = 0;\n",
+    )
+    .unwrap();
+
+    let out = dir.join("docs");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["doc", "--markdown"])
+        .arg(&out)
+        .arg(&src)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let area = std::fs::read_to_string(out.join("area.md")).unwrap();
+    assert!(area.contains("## fn `area`"), "{}", area);
+    assert!(area.contains("```bmb\nfn area(r: Rect) -> i64\n```"), "{}", area);
+    assert!(area.contains("Area of `r`"), "{}", area);
+    assert!(area.contains("- **pre** `r.w >= 0`"), "{}", area);
+    assert!(area.contains("Uses: [`Rect`](geo/shapes.md#struct.Rect)"), "{}", area);
+    assert!(!area.contains("hidden"), "{}", area);
+
+    let shapes = std::fs::read_to_string(out.join("geo/shapes.md")).unwrap();
+    assert!(shapes.contains("<a id=\"struct.Rect\"></a>"), "{}", shapes);
+    assert!(shapes.contains("A rectangle"), "{}", shapes);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// AST Dump Tests
+// ============================================
+
+#[test]
+fn test_parse_item_filter_dumps_only_that_item() {
+    let dir = std::env::temp_dir().join(format!("bmb_parse_item_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.bmb");
+    std::fs::write(&file, "struct Point { x: i64 }\n\nfn area(p: Point) -> i64 = p.x;\n\nfn main() -> i64 = 0;\n").unwrap();
+    let parse = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .arg("parse")
+            .arg(&file)
+            .args(args)
+            .output()
+            .expect("failed to run bmb")
+    };
+
+    let output = parse(&["--item", "area", "--format", "sexpr"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(fn area"), "{}", stdout);
+    assert!(!stdout.contains("main") && !stdout.contains("struct"), "{}", stdout);
+
+    let output = parse(&["--item", "Point"]);
+    let mut records = serde_json::Deserializer::from_slice(&output.stdout).into_iter::<serde_json::Value>();
+    let json = records.next().unwrap().unwrap();
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+    assert_eq!(json["items"][0]["StructDef"]["name"]["node"], "Point");
+
+    let output = parse(&["--item", "aera"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("no item named `aera`"), "{}", stdout);
+    assert!(stdout.contains("did you mean `area`?"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// Human Diagnostics Tests
+// ============================================
+
+#[test]
+fn test_human_check_renders_source_snippet() {
+    let golden_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["--human", "check", "type_error.bmb"])
+        .current_dir(&golden_dir)
+        .output()
+        .expect("failed to run bmb");
+    assert!(!output.status.success());
+
+    let expected = std::fs::read_to_string(golden_dir.join("type_error.human")).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+// ============================================
+// WASI File I/O Tests
+// ============================================
+
+#[test]
+fn test_wasi_file_copy_runs_under_wasmtime() {
+    let dir = std::env::temp_dir().join(format!("bmb_wasi_copy_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("copy.bmb");
+    std::fs::write(
+        &source,
+        "fn main() -> i64 = {\n    let content = read_file(\"input.txt\");\n    write_file(\"output.txt\", content)\n};\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("input.txt"), "hello \"wasi\"\nsecond line\n").unwrap();
+
+    let wat = dir.join("copy.wat");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("build")
+        .arg(&source)
+        .args(["--emit-wasm", "--wasm-target", "wasi", "-o"])
+        .arg(&wat)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    let module = std::fs::read_to_string(&wat).unwrap();
+    assert!(module.contains("(import \"wasi_snapshot_preview1\" \"path_open\""));
+    assert!(module.contains("(func $read_file"));
+
+    // Executing the module needs wasmtime on PATH
+    let run = std::process::Command::new("wasmtime")
+        .arg("run")
+        .arg("--dir")
+        .arg(&dir)
+        .arg(&wat)
+        .current_dir(&dir)
+        .output();
+    match run {
+        Ok(run) => {
+            assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+            let copied = std::fs::read_to_string(dir.join("output.txt")).unwrap();
+            assert_eq!(copied, "hello \"wasi\"\nsecond line\n");
+        }
+        Err(_) => eprintln!("wasmtime not found; skipping execution of {}", wat.display()),
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_wasm_saturating_operators_match_interpreter() {
+    let dir = std::env::temp_dir().join(format!("bmb_wasm_sat_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("sat.bmb");
+    let program = "fn main() -> i64 = {\n    let max = 9223372036854775807;\n    let min = 0 - max - 1;\n    \
+                   println(max +% 1);\n    println(max +| 1);\n    println(min -| 1);\n    println(min *| 2);\n    \
+                   println(min *| (0 - 1));\n    println(5 -| 7);\n    0\n};\n";
+    std::fs::write(&source, program).unwrap();
+    let expected = run_interpreted("wasm_sat", program);
+
+    let wat = dir.join("sat.wat");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("build")
+        .arg(&source)
+        .args(["--emit-wasm", "-o"])
+        .arg(&wat)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    match std::process::Command::new("wasmtime").arg("run").arg(&wat).output() {
+        Ok(run) => {
+            assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+            assert_eq!(String::from_utf8_lossy(&run.stdout), expected);
+        }
+        Err(_) => eprintln!("wasmtime not found; skipping execution of {}", wat.display()),
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Build `source` through the text LLVM backend and the C runtime, then run it.
+/// Returns None when no native toolchain (clang, or llc plus cc) is available.
+fn run_native(name: &str, source: &str) -> Option<String> {
+    let dir = std::env::temp_dir().join(format!("bmb_native_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (bmb, ll, obj, exe) = (dir.join("main.bmb"), dir.join("main.ll"), dir.join("main.o"), dir.join("main"));
+    std::fs::write(&bmb, source).unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("build")
+        .arg(&bmb)
+        .arg("--emit-ir")
+        .arg("-o")
+        .arg(&ll)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let runtime = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../runtime/runtime.c");
+    let run = |cmd: &str, args: &[&std::ffi::OsStr]| std::process::Command::new(cmd).args(args).output().ok();
+    let llc = |extra: &[&std::ffi::OsStr]| {
+        let base: [&std::ffi::OsStr; 5] =
+            ["-filetype=obj".as_ref(), "-relocation-model=pic".as_ref(), ll.as_os_str(), "-o".as_ref(), obj.as_os_str()];
+        run("llc", &[extra, &base].concat())
+    };
+    let built = match run("clang", &[ll.as_os_str(), runtime.as_os_str(), "-lm".as_ref(), "-o".as_ref(), exe.as_os_str()]) {
+        Some(out) => Some(out),
+        // LLVM 14 needs opaque pointers enabled explicitly
+        None => llc(&[])
+            .filter(|out| out.status.success())
+            .or_else(|| llc(&["-opaque-pointers".as_ref()]))
+            .and_then(|_| run("cc", &[obj.as_os_str(), runtime.as_os_str(), "-lm".as_ref(), "-o".as_ref(), exe.as_os_str()])),
+    };
+    let Some(built) = built else {
+        eprintln!("no native toolchain found; skipping native run of {}", ll.display());
+        let _ = std::fs::remove_dir_all(&dir);
+        return None;
+    };
+    assert!(built.status.success(), "{}", String::from_utf8_lossy(&built.stderr));
+    let out = std::process::Command::new(&exe).output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    Some(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[test]
+fn test_reassigned_parameter_native_matches_interpreter() {
+    let source = r#"
+fn increment(x: i64) -> i64
+  post x == old(x) + 1
+= { x = x + 1; x };
+
+fn main() -> i64 = {
+    println(increment(4));
+    0
+};
+"#;
+    assert!(type_checks(source));
+    if let Some(stdout) = run_native("reassigned_param", source) {
+        assert_eq!(stdout, "5\n");
+    }
+}
+
+#[test]
+fn test_string_builtins_native_matches_interpreter() {
+    let source = r#"
+fn flag(b: bool) -> i64 = if b { 1 } else { 0 };
+
+fn main() -> i64 = {
+    let t = str_trim("  let answer = 42;  ");
+    println(t.len());
+    println(flag(str_contains(t, "answer")) + flag(str_starts_with(t, "let")) * 10 + flag(str_ends_with(t, "?")) * 100);
+    println(str_index_of(t, "="));
+    print_str(str_replace(t, "answer", "x"));
+    println(parse_int("-1234") + parse_int(t.slice(13, 15)));
+    let parts = str_split("a,bb,ccc", ",");
+    println(vec_len(parts) * 10 + str_from_id(vec_get(parts, 2)).len());
+    0
+};
+"#;
+    assert!(type_checks(source));
+    let expected = "16\n11\n11\nlet x = 42;-1192\n33\n";
+    if let Some(stdout) = run_native("strings", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_char_int_casts_native_matches_interpreter() {
+    let source = r#"
+fn shift(c: char, n: i64) -> char = ((c as i64) + n) as char;
+
+fn main() -> i64 = {
+    let code = 'A' as i64;
+    let back = code as char;
+    println(code);
+    println(flag(back == 'A'));
+    println(shift(back, 25) as u32 as i64);
+    0
+};
+
+fn flag(b: bool) -> i64 = if b { 1 } else { 0 };
+"#;
+    assert!(type_checks(source));
+    let expected = "65\n1\n90\n";
+    assert_eq!(run_interpreted("char_casts", source), expected);
+    if let Some(stdout) = run_native("char_casts", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_unsigned_and_i32_native_matches_interpreter() {
+    let source = r#"
+fn main() -> i64 = {
+    let big: u32 = (0 - 1) as u32;
+    let small: i32 = (0 - 2147483648) as i32;
+    println(big as i64);
+    println((big / 2) as i64);
+    println((big % 10) as i64);
+    println(flag(big > (1 as u32)));
+    println(small as i64);
+    println((small / 2) as i64);
+    println(((1 as i32) - small / 2) as i64);
+    println((3000000000 as i32) as i64);
+    println(((0 - 1) as u64) as i64);
+    println((small as u32) as i64);
+    println((big as i32) as i64);
+    0
+};
+
+fn flag(b: bool) -> i64 = if b { 1 } else { 0 };
+"#;
+    assert!(type_checks(source));
+    let expected = "4294967295\n2147483647\n5\n1\n-2147483648\n-1073741824\n1073741825\n-1294967296\n-1\n2147483648\n-1\n";
+    assert_eq!(run_interpreted("unsigned_i32", source), expected);
+    if let Some(stdout) = run_native("unsigned_i32", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_invalid_char_cast_is_runtime_error() {
+    let source = "fn main() -> i64 = { let c = 55296 as char; c as i64 };";
+    assert!(type_checks(source));
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+    let mut interp = bmb::interp::Interpreter::new();
+    let err = interp.run(&ast).expect_err("surrogate code point");
+    assert_eq!(err.kind, bmb::interp::ErrorKind::InvalidChar);
+    assert!(err.message.contains("55296"), "{}", err.message);
+
+    assert!(type_error("fn f(x: f64) -> char = x as char;"));
+    assert!(type_error("fn f(b: bool) -> char = b as char;"));
+}
+
+/// Run `source` with `bmb run` and return what the program printed.
+fn run_interpreted(name: &str, source: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("bmb_interp_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.bmb");
+    std::fs::write(&file, source).unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("run")
+        .arg(&file)
+        .output()
+        .expect("failed to run bmb");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_buffered_print_keeps_order_around_subprocess() {
+    let source = r#"
+fn main() -> i64 = {
+    let a = print_str("before ");
+    println(1);
+    let code = system("echo child");
+    println_str("after");
+    flush();
+    code
+};
+"#;
+    assert_eq!(run_interpreted("buffered", source), "before 1\nchild\nafter\n");
+}
+
+#[test]
+fn test_overflow_operators_native_matches_interpreter() {
+    let source = r#"
+enum Option<T> { Some(T), None }
+
+fn show(x: Option<i64>) -> i64 = match x { Option::Some(v) => v, Option::None => 0 - 1 };
+
+fn main() -> i64 = {
+    let max = 9223372036854775807;
+    let min = 0 - max - 1;
+    println(max +% 1);
+    println(min -% 1);
+    println(max *% 2);
+    println(max +| 1);
+    println(min -| 1);
+    println(max *| 2);
+    println(min *| (0 - 1));
+    println(3 *| 4);
+    println(show(max +? 1));
+    println(show(min -? 1));
+    println(show(min *? (0 - 1)));
+    let sum = 40 +? 2;
+    println(show(sum));
+    println(show(6 *? 7));
+    0
+};
+"#;
+    let expected = run_interpreted("overflow", source);
+    assert_eq!(
+        expected,
+        "-9223372036854775808\n9223372036854775807\n-2\n9223372036854775807\n-9223372036854775808\n\
+         9223372036854775807\n9223372036854775807\n12\n-1\n-1\n-1\n42\n42\n"
+    );
+    if let Some(stdout) = run_native("overflow", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_string_collections_native_matches_interpreter() {
+    let source = r#"
+fn flag(b: bool) -> i64 = if b { 1 } else { 0 };
+
+fn main() -> i64 = {
+    let names = vec_new();
+    vec_push_str(names, "alpha");
+    vec_push_str(names, "be" + "ta");
+    print_str(vec_get_str(names, 1));
+    println(vec_len(names));
+
+    let by_id = hashmap_new();
+    hashmap_insert_str(by_id, 7, "seven");
+    hashmap_insert_str(by_id, 7, "SEVEN");
+    print_str(hashmap_get_str(by_id, 7));
+    println(hashmap_get_str(by_id, 8).len());
+
+    let counts = hashmap_new();
+    let first = hashmap_insert_str_key(counts, "x", 1);
+    let again = hashmap_insert_str_key(counts, "" + "x", 2);
+    println(first * 10 + again);
+    println(hashmap_get_str_key(counts, "x"));
+    println(hashmap_contains_str_key(counts, "x") * 10 + hashmap_contains_str_key(counts, "y"));
+    println(flag(hashmap_get_str_key(counts, "y") == -9223372036854775807 - 1));
+    println(hashmap_len(counts));
+    0
+};
+"#;
+    let expected = run_interpreted("string_collections", source);
+    assert_eq!(expected, "beta2\nSEVEN0\n1\n2\n10\n1\n1\n");
+    if let Some(stdout) = run_native("string_collections", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_structs_native_matches_interpreter() {
+    let source = r#"
+struct Point { x: i64, y: i64 }
+struct Rect { origin: Point, size: Point, scale: f64, filled: bool }
+struct Item { name: String, at: Point, count: i64 }
+
+impl Point {
+    fn plus(self: Self, o: Point) -> Self = new Point { x: self.x + o.x, y: self.y + o.y };
+}
+
+fn make_point(x: i64, y: i64) -> Point = new Point { x: x, y: y };
+
+fn area(r: Rect) -> i64 = f64_to_i64(i64_to_f64(r.size.x * r.size.y) * r.scale);
+
+fn grow(r: Rect, by: i64) -> Rect = {
+    let mut out = r;
+    out.size.x = r.size.x + by;
+    out.size.y = r.size.y + by;
+    out
+};
+
+fn pick(first: bool, a: Point, b: Point) -> Point = if first { a } else { b };
+
+fn rename(item: Item, name: String) -> Item = new Item { name: name, ..item };
+
+fn flag(b: bool) -> i64 = if b { 1 } else { 0 };
+
+fn main() -> i64 = {
+    let p = make_point(3, 4);
+    let q = p.plus(new Point { x: 10, y: 0 });
+    let r = new Rect { origin: p, size: make_point(5, 6), scale: 1.5, filled: true };
+    let big = grow(r, 2);
+    println(p.x, p.y, q.x, q.y);
+    println(area(r), area(big), big.origin.y, flag(big.filled));
+
+    let mut m = pick(false, p, q);
+    let before = m;
+    m.x = 100;
+    println(m.x, before.x, pick(true, p, q).x);
+
+    let mut i = 0;
+    let mut acc = make_point(0, 0);
+    while i < 5 { { acc = acc.plus(make_point(i, 1)); i = i + 1 } };
+    println(acc.x, acc.y);
+
+    let bolt = new Item { name: "bolt", at: p, count: 3 };
+    let nut = rename(bolt, "nut");
+    println(bolt.name, nut.name, nut.at.y, nut.count);
+    0
+};
+"#;
+    let expected = run_interpreted("structs", source);
+    assert_eq!(expected, "3 4 13 4\n45 84 4 1\n100 13 3\n10 5\nbolt nut 4 3\n");
+    if let Some(stdout) = run_native("structs", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_closures_native_matches_interpreter() {
+    let source = r#"
+fn twice(n: i64, x: i64) -> i64 = {
+    let add = fn |y: i64| { y + n };
+    add(add(x))
+};
+
+fn main() -> i64 = {
+    println(twice(10, 1));
+
+    let mut k = 5;
+    let scale = fn |x: i64| { x * k };
+    k = k + 95;
+    println(scale(2), k);
+
+    let flag = true;
+    let half = 0.5;
+    let pick = fn |a: i64, b: i64| { if flag and half < 1.0 { a } else { b } };
+    println(pick(1, 2));
+
+    let answer = fn || { 42 };
+    let outer = 1;
+    let nest = fn |x: i64| { let inner = fn |y: i64| { y + outer }; inner(x) * 2 };
+    println(answer(), nest(20));
+
+    let mut total = 0;
+    let mut i = 0;
+    while i < 4 { { total = total + scale(i); i = i + 1 } };
+    println(total);
+    0
+};
+"#;
+    let expected = run_interpreted("closures", source);
+    assert_eq!(expected, "21\n10 100\n1\n42 42\n30\n");
+    if let Some(stdout) = run_native("closures", source) {
+        assert_eq!(stdout, expected);
+    }
+}
+
+#[test]
+fn test_run_jit_prints_output() {
+    let dir = std::env::temp_dir().join(format!("bmb_run_jit_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("hello.bmb");
+    std::fs::write(
+        &source,
+        "fn square(x: i64) -> i64 = x * x;\n\nfn main() -> i64 = {\n    println(square(7));\n    0\n};\n",
+    )
+    .unwrap();
+
+    // Without LLVM this exercises the interpreter fallback
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["run", "--jit"])
+        .arg(&source)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "49");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_span_recording_types_expressions() {
+    let source = "fn area(w: i64, h: f64) -> f64 = {\n    let s = i64_to_f64(w);\n    s * h\n};\n";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+
+    let mut tc = TypeChecker::with_span_recording();
+    tc.check_program(&ast).unwrap();
+    let w = source.find("(w)").unwrap() + 1;
+    assert_eq!(tc.type_at(w), Some(&bmb::ast::Type::I64));
+    let h = source.rfind('h').unwrap();
+    assert_eq!(tc.type_at(h), Some(&bmb::ast::Type::F64));
+
+    // Plain checkers don't pay for the side table
+    let mut plain = TypeChecker::new();
+    plain.check_program(&ast).unwrap();
+    assert!(plain.span_types().is_empty());
+}
+
+#[test]
+fn test_recover_errors_collects_one_error_per_function() {
+    let source = "fn a(x: i64) -> i64 = x + true;\nfn b(x: i64) -> bool = x;\nfn c(x: i64) -> i64 = x * 2;\nfn main() -> i64 = a(1) + c(missing);\n";
+    let tokens = tokenize(source).unwrap();
+    let ast = parse("test.bmb", source, tokens).unwrap();
+
+    let mut tc = TypeChecker::new().recover_errors();
+    tc.check_program(&ast).unwrap();
+    let messages: Vec<_> = tc.take_errors().iter().map(|e| e.message().to_string()).collect();
+    assert_eq!(messages.len(), 3, "{:?}", messages);
+    assert!(messages[2].contains("missing"));
+
+    // Without recovery the first error is returned as before
+    let mut strict = TypeChecker::new();
+    assert!(strict.check_program(&ast).is_err());
+    assert!(!strict.has_errors());
+}
+
+#[test]
+fn test_check_reports_all_errors_and_fails() {
+    let dir = std::env::temp_dir().join(format!("bmb_check_errors_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("errors.bmb");
+    std::fs::write(&source, "fn a() -> i64 = true;\nfn b() -> bool = 1;\nfn main() -> i64 = a();\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .arg("check")
+        .arg(&source)
+        .output()
+        .expect("failed to run bmb");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: bmb::error::DiagnosticReport = serde_json::from_str(stdout.lines().next().unwrap()).unwrap();
+    let errors = report
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == bmb::error::Severity::Error)
+        .count();
+    assert_eq!(errors, 2, "{}", stdout);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_lsp_rename_across_two_files() {
+    use bmb::lsp::rename::{rename_symbol, RenameFile};
+
+    let lib = "pub fn helper(x: i64) -> i64 = x + 1;\n";
+    let app = "use lib::helper;\n\nfn main() -> i64 = helper(helper(1));\n";
+    let lib_ast = parse("lib.bmb", lib, tokenize(lib).unwrap()).unwrap();
+    let app_ast = parse("app.bmb", app, tokenize(app).unwrap()).unwrap();
+    let files = [
+        RenameFile { content: app, program: &app_ast },
+        RenameFile { content: lib, program: &lib_ast },
+    ];
+
+    // Rename from a call site in app.bmb
+    let edits = rename_symbol(&files, 0, app.find("helper(1)").unwrap(), "step").unwrap();
+    let edited: Vec<(usize, &str, usize)> = edits
+        .iter()
+        .map(|(file, span)| (*file, &files[*file].content[span.start..span.end], span.start))
+        .collect();
+    assert_eq!(edited, vec![
+        (0, "helper", 9),  // use statement
+        (0, "helper", 37), // outer call
+        (0, "helper", 44), // inner call
+        (1, "helper", 7),  // definition
+    ]);
+
+    // Colliding with an existing definition in a touched file is rejected
+    assert!(rename_symbol(&files, 0, 37, "main").is_err());
+}
+
+#[test]
+fn test_use_glob_and_alias_parse() {
+    let source = "use math::*;\nuse math::gcd as g;\nuse a::b::c;\n";
+    let ast = parse("test.bmb", source, tokenize(source).unwrap()).unwrap();
+    let uses: Vec<String> = ast.items.iter().filter_map(|item| match item {
+        bmb::ast::Item::Use(u) => Some(u.path_text()),
+        _ => None,
+    }).collect();
+    assert_eq!(uses, ["math::*", "math::gcd as g", "a::b::c"]);
+}
+
+#[test]
+fn test_fmt_bootstrap_sources_idempotent() {
+    // The bootstrap sources nest deeply; give the parser and formatter room
+    std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(fmt_bootstrap_sources)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn fmt_bootstrap_sources() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../bootstrap");
+    let mut checked = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "bmb") {
+            continue;
+        }
+        let name = path.display().to_string();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let ast = parse(&name, &source, tokenize(&source).unwrap()).unwrap();
+        let once = bmb::fmt::format_program(&ast, &source, bmb::fmt::DEFAULT_WIDTH);
+        let reparsed = parse(&name, &once, tokenize(&once).unwrap())
+            .unwrap_or_else(|e| panic!("{}: formatted output does not parse: {}", name, e));
+        let twice = bmb::fmt::format_program(&reparsed, &once, bmb::fmt::DEFAULT_WIDTH);
+        assert_eq!(once, twice, "{}: formatting is not idempotent", name);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+/// Run `bmb fmt --stdin` with `args`, feeding it `source`
+fn fmt_stdin(source: &str, args: &[&str]) -> std::process::Output {
+    use std::io::Write;
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["fmt", "--stdin"])
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to run bmb");
+    child.stdin.take().unwrap().write_all(source.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_fmt_stdin_writes_only_formatted_source() {
+    let source = "fn a()->i64=1;\nfn b(x:i64)->i64=x*2;\n";
+    let out = fmt_stdin(source, &[]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "fn a() -> i64 = 1;\n\nfn b(x: i64) -> i64 = x * 2;\n");
+
+    let out = fmt_stdin(source, &["--range", "2:2"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "fn a()->i64=1;\nfn b(x: i64) -> i64 = x * 2;\n");
+
+    let out = fmt_stdin("fn a( = ;\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("E0201"));
+}
+
+#[test]
+fn test_bench_reports_stats_per_function() {
+    let dir = std::env::temp_dir().join(format!("bmb_bench_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("bench_math.bmb");
+    std::fs::write(
+        &source,
+        "fn fib(n: i64) -> i64 = if n < 2 { n } else { fib(n - 1) + fib(n - 2) };\n\
+         fn bench_fib() -> i64 = fib(10);\n\
+         fn bench_square() -> i64 = 7 * 7;\n\
+         fn bench_takes_args(n: i64) -> i64 = n;\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["bench", "--iterations", "5"])
+        .arg(&dir)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let names: Vec<_> = records.iter().filter(|r| r["type"] == "bench").map(|r| r["name"].clone()).collect();
+    assert_eq!(names, ["bench_fib", "bench_square"]);
+    let fib = &records[0];
+    assert_eq!(fib["iterations"], 5);
+    assert!(fib["min_ns"].as_u64().unwrap() <= fib["median_ns"].as_u64().unwrap());
+    assert_eq!(records.last().unwrap()["counts"]["benchmarks"], 2);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["bench", "-n", "1", "--filter", "square"])
+        .arg(&source)
+        .output()
+        .expect("failed to run bmb");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().filter(|l| l.contains(r#""type":"bench""#)).count(), 1, "{}", stdout);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_new_project_checks_and_tests_with_dependencies() {
+    let dir = std::env::temp_dir().join(format!("bmb_new_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("mathlib/src")).unwrap();
+    std::fs::write(dir.join("mathlib/src/lib.bmb"), "pub fn square(x: i64) -> i64 = x * x;\n").unwrap();
+
+    let bmb = |args: &[&str], cwd: &std::path::Path| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .expect("failed to run bmb")
+    };
+
+    let output = bmb(&["new", "app"], &dir);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    let app = dir.join("app");
+    assert!(app.join("bmb.toml").is_file());
+
+    // A path dependency resolves without any -I flags
+    let manifest = std::fs::read_to_string(app.join("bmb.toml")).unwrap();
+    std::fs::write(app.join("bmb.toml"), manifest + "mathlib = { path = \"../mathlib\" }\n").unwrap();
+    std::fs::write(
+        app.join("src/main.bmb"),
+        "use mathlib::square;\n\npub fn greeting() -> String = \"hi\";\n\nfn main() -> i64 = square(3);\n",
+    )
+    .unwrap();
+
+    let output = bmb(&["check", "src/main.bmb"], &app);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    // Without a file, `test` runs the project's tests/ directory
+    let output = bmb(&["test"], &app.join("src"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains(r#""passed":1"#), "{}", stdout);
+
+    let output = bmb(&["check"], &dir);
+    assert!(!output.status.success());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// CLI Summary Record Tests
+// ============================================
+
+/// Run `bmb` in `dir`, returning its exit code and the last line of stdout,
+/// which must be a JSON record
+fn summary_of(dir: &std::path::Path, args: &[&str]) -> (Option<i32>, serde_json::Value) {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run bmb");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last = stdout.lines().last().unwrap_or_default();
+    let summary = serde_json::from_str(last).unwrap_or_else(|e| panic!("{args:?}: last line is not JSON ({e}):\n{stdout}"));
+    (output.status.code(), summary)
+}
+
+#[test]
+fn test_every_command_ends_with_summary_record() {
+    let dir = std::env::temp_dir().join(format!("bmb_summary_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("ok.bmb"), "fn main() -> i64 = 0;\n").unwrap();
+    std::fs::write(dir.join("warn.bmb"), "fn main() -> i64 = {\n    let unused = 1;\n    0\n};\n").unwrap();
+    std::fs::write(dir.join("bad.bmb"), "fn main() -> i64 = true;\n").unwrap();
+    std::fs::write(dir.join("ugly.bmb"), "fn   main()->i64=0;\n").unwrap();
+    std::fs::write(dir.join("test_math.bmb"), "fn test_ok() -> bool = true;\nfn test_wrong() -> bool = 1 == 2;\n").unwrap();
+    std::fs::write(dir.join("bench_square.bmb"), "fn bench_square() -> i64 = 7 * 7;\n").unwrap();
+
+    // Arguments, command, exit code and a count the summary carries
+    type Case<'a> = (&'a [&'a str], &'a str, i32, Option<(&'a str, u64)>);
+    let cases: &[Case] = &[
+        (&["check", "ok.bmb"], "check", 0, Some(("files", 1))),
+        (&["check", "bad.bmb"], "check", 1, Some(("failed", 1))),
+        (&["check", "missing.bmb"], "check", 2, None),
+        (&["lint", "warn.bmb"], "lint", 0, Some(("warnings", 1))),
+        (&["lint", "--strict", "warn.bmb"], "lint", 1, Some(("warnings", 1))),
+        (&["fmt", "--check", "ugly.bmb"], "fmt", 1, Some(("unformatted", 1))),
+        (&["test", "test_math.bmb"], "test", 1, Some(("failed", 1))),
+        (&["bench", "-n", "1", "bench_square.bmb"], "bench", 0, Some(("benchmarks", 1))),
+        (&["parse", "ok.bmb"], "parse", 0, Some(("items", 1))),
+        (&["parse", "ok.bmb", "--item", "nope"], "parse", 2, None),
+        (&["tokens", "ok.bmb"], "tokens", 0, None),
+        (&["doc", "ok.bmb"], "doc", 0, Some(("files", 1))),
+        (&["verify", "ok.bmb"], "verify", 0, None),
+        (&["verify", "ok.bmb", "--function", "mian"], "verify", 2, None),
+        (&["build", "ok.bmb", "--emit-mir", "-o", "ok.mir"], "build", 0, None),
+        (&["build", "ok.bmb", "--emit-ir", "--target", "sparkle-unknown-linux"], "build", 2, None),
+        (&["q", "metrics"], "q", 2, None),
+        (&["index", "."], "index", 0, None),
+        (&["q", "metrics"], "q", 0, None),
+        (&["new", "app"], "new", 0, None),
+        (&["check", "--no-such-flag"], "bmb", 2, None),
+    ];
+    for (args, command, code, count) in cases {
+        let (status, summary) = summary_of(&dir, args);
+        assert_eq!(status, Some(*code), "{args:?}: {summary}");
+        assert_eq!(summary["type"], "summary", "{args:?}");
+        assert_eq!(summary["command"], *command, "{args:?}");
+        assert_eq!(summary["ok"], *code == 0, "{args:?}");
+        assert_eq!(summary["exit_code"], *code, "{args:?}");
+        assert!(summary["counts"].is_object(), "{args:?}: {summary}");
+        if let Some((name, value)) = count {
+            assert_eq!(summary["counts"][name], *value, "{args:?}: {summary}");
+        }
+    }
+
+    // A program's stdout is its own
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["run", "ok.bmb"])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_lint_config_levels_and_allow_attribute() {
+    let dir = std::env::temp_dir().join(format!("bmb_lint_config_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("bmb.toml"),
+        "[package]\nname = \"app\"\n\n[lint]\nshadow_binding = \"deny\"\nunused_binding = \"allow\"\n\
+         missing_postcondition = \"allow\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src/main.bmb"),
+        "fn twice() -> i64 = {\n    let x = 1;\n    let x = x + 1;\n    x\n};\n\n\
+         @allow(shadow_binding)\nfn quiet() -> i64 = {\n    let x = 1;\n    let x = x + 1;\n    let unused = 0;\n    x\n};\n\n\
+         fn main() -> i64 = twice() + quiet();\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("lint.toml"), "[lint]\nshadow_binding = \"allow\"\n").unwrap();
+
+    // bmb.toml denies the shadow in `twice`; `quiet` allows its own
+    let (status, summary) = summary_of(&dir, &["lint", "src/main.bmb"]);
+    assert_eq!(status, Some(1), "{summary}");
+    assert_eq!(summary["counts"]["denied"], 1, "{summary}");
+    assert_eq!(summary["counts"]["warnings"], 0, "{summary}");
+    assert_eq!(summary["counts"]["shadow_binding"], 1, "{summary}");
+    assert!(summary["counts"].get("unused_binding").is_none(), "{summary}");
+    assert!(summary["counts"].get("missing_postcondition").is_none(), "{summary}");
+
+    // --lint-config replaces the project's table
+    let (status, summary) = summary_of(&dir, &["lint", "--lint-config", "lint.toml", "src"]);
+    assert_eq!(status, Some(0), "{summary}");
+    assert_eq!(summary["counts"]["denied"], 0, "{summary}");
+    assert_eq!(summary["counts"]["warnings"], 3, "{summary}");
+    assert_eq!(summary["counts"]["unused_binding"], 1, "{summary}");
+    assert_eq!(summary["counts"]["missing_postcondition"], 2, "{summary}");
+    assert!(summary["counts"].get("shadow_binding").is_none(), "{summary}");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_verify_json_reports_each_function() {
+    let dir = std::env::temp_dir().join(format!("bmb_verify_json_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("mixed.bmb"),
+        "fn inc(x: i64) -> i64\n  pre x > 0\n  post ret > x\n= x + 1;\n\nfn dec(x: i64) -> i64\n  post ret > x\n= x - 1;\n",
+    )
+    .unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["verify", "--json", "--no-cache", "mixed.bmb"])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run bmb");
+    let _ = std::fs::remove_dir_all(&dir);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.contains("verify_skip") {
+        eprintln!("z3 not available, skipping");
+        return;
+    }
+
+    let report: serde_json::Value = stdout.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|record| record["type"] == "verify_report")
+        .unwrap_or_else(|| panic!("no verify_report record:\n{stdout}"));
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["counts"]["verified"], 1);
+    assert_eq!(report["counts"]["failed"], 1);
+
+    let inc = &report["functions"][0];
+    assert_eq!(inc["name"], "inc");
+    assert_eq!(inc["status"], "verified");
+    assert_eq!(inc["line"], 1);
+
+    let dec = &report["functions"][1];
+    assert_eq!(dec["name"], "dec");
+    assert_eq!(dec["status"], "failed");
+    assert_eq!(dec["line"], 6);
+    let post = dec["conditions"].as_array().unwrap().iter()
+        .find(|c| c["kind"] == "postcondition")
+        .unwrap();
+    assert_eq!(post["status"], "failed");
+    assert!(!post["counterexample"].as_object().unwrap().is_empty(), "{post}");
+}
+
+// ============================================
+// Todo Query Tests
+// ============================================
+
+#[test]
+fn test_query_todo_lists_every_placeholder() {
+    let dir = std::env::temp_dir().join(format!("bmb_todo_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("parse.bmb"),
+        "fn parse(x: i64) -> i64 =\n    if x > 0 { todo \"handle positives\" } else { todo };\n\nfn ready() -> i64 = 1;\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("emit.bmb"), "fn emit() -> i64 = todo \"pick a backend\";\n").unwrap();
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .expect("failed to run bmb");
+        assert!(output.status.success(), "{args:?}: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    run(&["index", "."]);
+
+    let json = run(&["q", "todo", "--format", "compact"]);
+    let result: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+    assert_eq!(result["total"], 3);
+    let mut sites: Vec<String> = result["todos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            let file = t["file"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+            format!("{} {}:{} {}", t["function"].as_str().unwrap(), file, t["line"], t["message"])
+        })
+        .collect();
+    sites.sort();
+    assert_eq!(
+        sites,
+        [
+            "emit emit.bmb:1 \"pick a backend\"",
+            "parse parse.bmb:2 \"handle positives\"",
+            "parse parse.bmb:2 null",
+        ]
+    );
+    let summary: serde_json::Value = serde_json::from_str(json.lines().last().unwrap()).unwrap();
+    assert_eq!(summary["counts"]["todos"], 3);
+
+    let checklist = run(&["q", "todo", "--format", "llm"]);
+    assert!(checklist.starts_with("TODO: 3\n"), "{checklist}");
+    assert_eq!(checklist.lines().filter(|l| l.starts_with("- [ ] ")).count(), 3, "{checklist}");
+    assert!(checklist.contains("parse.bmb:2 handle positives"), "{checklist}");
+
+    let metrics = run(&["q", "metrics", "--format", "compact"]);
+    let metrics: serde_json::Value = serde_json::from_str(metrics.lines().next().unwrap()).unwrap();
+    assert_eq!(metrics["body_analysis"]["todo_count"], 3);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// Query Server Tests
+// ============================================
+
+#[test]
+fn test_query_server_handles_concurrent_requests() {
+    use std::io::{BufRead, Read, Write};
+
+    let dir = std::env::temp_dir().join(format!("bmb_serve_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lib.bmb"), "fn double(x: i64) -> i64 = x * 2;
+").unwrap();
+    let index = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["index", "."])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run bmb");
+    assert!(index.status.success(), "{}", String::from_utf8_lossy(&index.stderr));
+
+    let mut server = KillOnDrop(
+        std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .args(["q", "serve", "--port", "0", "--max-connections", "4"])
+            .current_dir(&dir)
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .expect("failed to start server"),
+    );
+    let mut stdout = std::io::BufReader::new(server.0.stdout.take().unwrap());
+    let addr = loop {
+        let mut line = String::new();
+        assert!(stdout.read_line(&mut line).unwrap() > 0, "server exited before listening");
+        if let Some(addr) = line.trim().strip_prefix("Listening on http://") {
+            break addr.to_string();
+        }
+    };
+
+    let clients: Vec<_> = (0..50)
+        .map(|_| {
+            let addr = addr.clone();
+            std::thread::spawn(move || {
+                let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+                stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                response
+            })
+        })
+        .collect();
+    for client in clients {
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        assert!(serde_json::from_str::<serde_json::Value>(body).is_ok(), "{body}");
+    }
+
+    // Ctrl+C drains the pool and reports what was served
+    #[cfg(unix)]
+    {
+        let kill = std::process::Command::new("kill")
+            .args(["-INT", &server.0.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(kill.success());
+        assert!(server.0.wait().unwrap().success());
+        let mut rest = String::new();
+        stdout.read_to_string(&mut rest).unwrap();
+        assert!(rest.contains("Served 50 requests (0 failed)"), "{rest}");
+    }
+
+    drop(server);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// Tokens Command Tests
+// ============================================
+
+#[test]
+fn test_tokens_records_use_kind_names() {
+    let dir = std::env::temp_dir().join(format!("bmb_tokens_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("t.bmb"), "let x = 2.5 + 'a'; // note\nprint_str(\"hi\")").unwrap();
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .expect("failed to run bmb");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run(&["tokens", "t.bmb"]);
+    let records: Vec<serde_json::Value> = stdout.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(records[0]["type"], "token_schema");
+    assert_eq!(records[0]["version"], 1);
+    let kinds = records[0]["kinds"].as_array().unwrap();
+    assert!(kinds.contains(&"IntLit".into()) && kinds.contains(&"Plus".into()));
+
+    let tokens: Vec<String> = records[1..records.len() - 1]
+        .iter()
+        .map(|r| {
+            assert_eq!(r["type"], "token");
+            assert!(kinds.contains(&r["kind"]), "{r}");
+            match r.get("value") {
+                Some(value) => format!("{}={}@{}", r["kind"].as_str().unwrap(), value, r["start"]),
+                None => format!("{}@{}", r["kind"].as_str().unwrap(), r["start"]),
+            }
+        })
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            "Let@0", "Ident=\"x\"@4", "Eq@6", "FloatLit=2.5@8", "Plus@12", "CharLit=\"a\"@14", "Semi@17",
+            "Ident=\"print_str\"@27", "LParen@36", "StringLit=\"hi\"@37", "RParen@41",
+        ]
+    );
+    assert_eq!(records.last().unwrap()["counts"]["tokens"], 11);
+
+    // --human keeps the Debug dump
+    let human = run(&["--human", "tokens", "t.bmb"]);
+    assert!(human.starts_with("Let @ 0..3\nIdent(\"x\") @ 4..5\n"), "{human}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================
+// Explain Command Tests
+// ============================================
+
+#[test]
+fn test_explain_prints_example_and_fix() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["explain", "E0308"])
+        .output()
+        .expect("failed to run bmb");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let record: serde_json::Value = serde_json::from_str(stdout.lines().next().unwrap()).unwrap();
+    assert_eq!(record["type"], "explanation");
+    assert_eq!(record["title"], "mismatched types");
+    assert!(record["erroneous"].as_str().unwrap().contains("fn is_positive"));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+        .args(["--human", "explain", "E9999"])
+        .output()
+        .expect("failed to run bmb");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no explanation for `E9999`"));
+}
+
+// ============================================
+// Integer Overflow Tests
+// ============================================
+
+#[test]
+fn test_run_reports_integer_overflow_unless_wrapping() {
+    let dir = std::env::temp_dir().join(format!("bmb_overflow_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.bmb");
+    std::fs::write(&file, "fn main() -> i64 = {\n    let big = 9223372036854775807;\n    let wrapped = big + 1;\n    0\n};\n").unwrap();
+    let run = |extra: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_bmb"))
+            .arg("run")
+            .args(extra)
+            .arg(&file)
+            .output()
+            .expect("failed to run bmb")
+    };
+
+    let checked = run(&[]);
+    let stderr = String::from_utf8_lossy(&checked.stderr).into_owned()
+        + &String::from_utf8_lossy(&checked.stdout);
+    assert!(!checked.status.success());
+    assert!(stderr.contains("integer overflow in `9223372036854775807 + 1`"), "{stderr}");
+
+    let wrapped = run(&["--overflow=wrap"]);
+    assert!(wrapped.status.success(), "{}", String::from_utf8_lossy(&wrapped.stderr));
+
+    // Unsigned arithmetic overflows at its own bounds, not at i64's
+    std::fs::write(
+        &file,
+        "fn main() -> i64 = {\n    let big: u32 = 4000000000 as u32;\n    println((big + (1000000000 as u32)) as i64);\n    0\n};\n",
+    )
+    .unwrap();
+    let checked = run(&[]);
+    let stderr = String::from_utf8_lossy(&checked.stderr).into_owned()
+        + &String::from_utf8_lossy(&checked.stdout);
+    assert!(!checked.status.success());
+    assert!(stderr.contains("integer overflow in `4000000000 + 1000000000`"), "{stderr}");
+    let wrapped = run(&["--overflow=wrap"]);
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(wrapped.status.success(), "{}", String::from_utf8_lossy(&wrapped.stderr));
+    assert_eq!(String::from_utf8_lossy(&wrapped.stdout).lines().next(), Some("705032704"));
+}
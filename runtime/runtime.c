@@ -240,6 +240,18 @@ void bmb_print_str(BmbString* s) {
 }
 
 // Print string with newline
+// Checked contract violation (build --contracts=check)
+// Reports the function and failing condition, then aborts with exit code 101
+void bmb_contract_fail(BmbString* msg) {
+    fflush(stdout);
+    fputs("contract violation: ", stderr);
+    if (msg && msg->data) {
+        fwrite(msg->data, 1, msg->len, stderr);
+    }
+    fputc('\n', stderr);
+    exit(101);
+}
+
 void bmb_println_str(BmbString* s) {
     if (s && s->data) {
         fwrite(s->data, 1, s->len, stdout);
@@ -507,6 +519,358 @@ void print_str(BmbString* s) {
     bmb_print_str(s);
 }
 
+// Print string with newline wrapper
+void println_str(BmbString* s) {
+    bmb_println_str(s);
+}
+
+// Write out buffered stdout
+void flush(void) {
+    fflush(stdout);
+}
+
+// ===================================================
+// String Search and Transformation
+// Offsets are byte offsets, matching len() and slice()
+// ===================================================
+
+// Byte offset of the first occurrence of needle at or after `from`, or -1
+static int64_t bmb_find(BmbString* s, BmbString* needle, int64_t from) {
+    if (!s || !needle) return -1;
+    if (needle->len == 0) return from <= s->len ? from : -1;
+    for (int64_t i = from; i + needle->len <= s->len; i++) {
+        if (memcmp(s->data + i, needle->data, needle->len) == 0) return i;
+    }
+    return -1;
+}
+
+int str_contains(BmbString* s, BmbString* needle) {
+    return bmb_find(s, needle, 0) >= 0;
+}
+
+int str_starts_with(BmbString* s, BmbString* prefix) {
+    if (!s || !prefix || prefix->len > s->len) return 0;
+    return memcmp(s->data, prefix->data, prefix->len) == 0;
+}
+
+int str_ends_with(BmbString* s, BmbString* suffix) {
+    if (!s || !suffix || suffix->len > s->len) return 0;
+    return memcmp(s->data + s->len - suffix->len, suffix->data, suffix->len) == 0;
+}
+
+int64_t str_index_of(BmbString* s, BmbString* needle) {
+    return bmb_find(s, needle, 0);
+}
+
+// Replace every occurrence of `from`; an empty `from` leaves s unchanged
+BmbString* str_replace(BmbString* s, BmbString* from, BmbString* to) {
+    if (!s) return bmb_string_new("", 0);
+    if (!from || from->len == 0) return bmb_string_new(s->data, s->len);
+    int64_t to_len = to ? to->len : 0;
+    int64_t count = 0;
+    for (int64_t i = bmb_find(s, from, 0); i >= 0; i = bmb_find(s, from, i + from->len)) count++;
+    int64_t newlen = s->len + count * (to_len - from->len);
+    char* data = (char*)malloc(newlen + 1);
+    int64_t out = 0, prev = 0;
+    for (int64_t i = bmb_find(s, from, 0); i >= 0; i = bmb_find(s, from, i + from->len)) {
+        memcpy(data + out, s->data + prev, i - prev);
+        out += i - prev;
+        if (to_len > 0) memcpy(data + out, to->data, to_len);
+        out += to_len;
+        prev = i + from->len;
+    }
+    memcpy(data + out, s->data + prev, s->len - prev);
+    BmbString* result = bmb_string_new(data, newlen);
+    free(data);
+    return result;
+}
+
+static int bmb_is_space(char c) {
+    return c == ' ' || c == '\t' || c == '\n' || c == '\r' || c == '\f' || c == '\v';
+}
+
+// Strip leading and trailing ASCII whitespace
+BmbString* str_trim(BmbString* s) {
+    if (!s) return bmb_string_new("", 0);
+    int64_t start = 0, end = s->len;
+    while (start < end && bmb_is_space(s->data[start])) start++;
+    while (end > start && bmb_is_space(s->data[end - 1])) end--;
+    return bmb_string_new(s->data + start, end - start);
+}
+
+// Decimal integer with optional sign; INT64_MIN when malformed or out of range
+int64_t parse_int(BmbString* s) {
+    if (!s || s->len == 0) return INT64_MIN;
+    int64_t i = 0;
+    int negative = 0;
+    if (s->data[0] == '-' || s->data[0] == '+') {
+        negative = s->data[0] == '-';
+        i = 1;
+    }
+    if (i == s->len) return INT64_MIN;
+    // Accumulate negatively so INT64_MIN itself parses
+    int64_t value = 0;
+    for (; i < s->len; i++) {
+        char c = s->data[i];
+        if (c < '0' || c > '9') return INT64_MIN;
+        int64_t digit = c - '0';
+        if (value < (INT64_MIN + digit) / 10) return INT64_MIN;
+        value = value * 10 - digit;
+    }
+    if (!negative) {
+        if (value == INT64_MIN) return INT64_MIN;
+        value = -value;
+    }
+    return value;
+}
+
+// Split on every occurrence of sep (an empty sep yields s itself).
+// Returns a vec_* handle whose elements are string ids for str_from_id.
+int64_t str_split(BmbString* s, BmbString* sep) {
+    if (!s) s = bmb_string_new("", 0);
+    int64_t count = 1;
+    if (sep && sep->len > 0) {
+        for (int64_t i = bmb_find(s, sep, 0); i >= 0; i = bmb_find(s, sep, i + sep->len)) count++;
+    }
+    // Vec header layout shared with the inlined vec_* builtins: {data, len, cap}
+    int64_t* header = (int64_t*)malloc(3 * sizeof(int64_t));
+    int64_t* items = (int64_t*)malloc(count * sizeof(int64_t));
+    int64_t n = 0, prev = 0;
+    if (sep && sep->len > 0) {
+        for (int64_t i = bmb_find(s, sep, 0); i >= 0; i = bmb_find(s, sep, i + sep->len)) {
+            items[n++] = (int64_t)(intptr_t)bmb_string_new(s->data + prev, i - prev);
+            prev = i + sep->len;
+        }
+    }
+    items[n++] = (int64_t)(intptr_t)bmb_string_new(s->data + prev, s->len - prev);
+    header[0] = (int64_t)(intptr_t)items;
+    header[1] = n;
+    header[2] = count;
+    return (int64_t)(intptr_t)header;
+}
+
+// String for an id returned by str_split
+BmbString* str_from_id(int64_t id) {
+    return id ? (BmbString*)(intptr_t)id : bmb_string_new("", 0);
+}
+
+// ===================================================
+// HashMap<i64, i64> and String-valued vec/hashmap builtins
+// Same layout and probing as the interpreter:
+// header [count, capacity, keys, values, states],
+// states 0=empty, 1=occupied, 2=deleted
+// ===================================================
+
+#define BMB_HASHMAP_DEFAULT_CAPACITY 16
+
+static int64_t bmb_hashmap_find_slot(const int64_t* keys, const int64_t* states, int64_t capacity,
+                                     int64_t key, int* found) {
+    uint64_t h = (uint64_t)key * 0x517cc1b727220a95ULL;
+    int64_t mask = capacity - 1;
+    int64_t idx = (int64_t)(h ^ (h >> 32)) & mask;
+    int64_t first_deleted = -1;
+    for (int64_t i = 0; i < capacity; i++) {
+        if (states[idx] == 0) {
+            *found = 0;
+            return first_deleted >= 0 ? first_deleted : idx;
+        } else if (states[idx] == 2) {
+            if (first_deleted < 0) first_deleted = idx;
+        } else if (keys[idx] == key) {
+            *found = 1;
+            return idx;
+        }
+        idx = (idx + 1) & mask;
+    }
+    *found = 0;
+    return first_deleted >= 0 ? first_deleted : 0;
+}
+
+int64_t hashmap_new(void) {
+    int64_t* header = (int64_t*)malloc(5 * sizeof(int64_t));
+    header[0] = 0;
+    header[1] = BMB_HASHMAP_DEFAULT_CAPACITY;
+    header[2] = (int64_t)(intptr_t)calloc(BMB_HASHMAP_DEFAULT_CAPACITY, sizeof(int64_t));
+    header[3] = (int64_t)(intptr_t)calloc(BMB_HASHMAP_DEFAULT_CAPACITY, sizeof(int64_t));
+    header[4] = (int64_t)(intptr_t)calloc(BMB_HASHMAP_DEFAULT_CAPACITY, sizeof(int64_t));
+    return (int64_t)(intptr_t)header;
+}
+
+// Returns the previous value, or 0 for a new key
+int64_t hashmap_insert(int64_t map, int64_t key, int64_t value) {
+    int64_t* header = (int64_t*)(intptr_t)map;
+    int64_t capacity = header[1];
+    // Double the capacity above 70% load
+    if (header[0] * 10 > capacity * 7) {
+        int64_t* keys = (int64_t*)(intptr_t)header[2];
+        int64_t* values = (int64_t*)(intptr_t)header[3];
+        int64_t* states = (int64_t*)(intptr_t)header[4];
+        int64_t new_capacity = capacity * 2;
+        int64_t* new_keys = (int64_t*)calloc(new_capacity, sizeof(int64_t));
+        int64_t* new_values = (int64_t*)calloc(new_capacity, sizeof(int64_t));
+        int64_t* new_states = (int64_t*)calloc(new_capacity, sizeof(int64_t));
+        for (int64_t i = 0; i < capacity; i++) {
+            if (states[i] != 1) continue;
+            int found;
+            int64_t idx = bmb_hashmap_find_slot(new_keys, new_states, new_capacity, keys[i], &found);
+            new_keys[idx] = keys[i];
+            new_values[idx] = values[i];
+            new_states[idx] = 1;
+        }
+        free(keys);
+        free(values);
+        free(states);
+        header[1] = capacity = new_capacity;
+        header[2] = (int64_t)(intptr_t)new_keys;
+        header[3] = (int64_t)(intptr_t)new_values;
+        header[4] = (int64_t)(intptr_t)new_states;
+    }
+    int64_t* keys = (int64_t*)(intptr_t)header[2];
+    int64_t* values = (int64_t*)(intptr_t)header[3];
+    int64_t* states = (int64_t*)(intptr_t)header[4];
+    int found;
+    int64_t idx = bmb_hashmap_find_slot(keys, states, capacity, key, &found);
+    int64_t old = 0;
+    if (found) {
+        old = values[idx];
+    } else {
+        header[0]++;
+    }
+    keys[idx] = key;
+    values[idx] = value;
+    states[idx] = 1;
+    return old;
+}
+
+// Returns the value, or INT64_MIN when the key is missing
+int64_t hashmap_get(int64_t map, int64_t key) {
+    int64_t* header = (int64_t*)(intptr_t)map;
+    int found;
+    int64_t idx = bmb_hashmap_find_slot((int64_t*)(intptr_t)header[2], (int64_t*)(intptr_t)header[4],
+                                        header[1], key, &found);
+    return found ? ((int64_t*)(intptr_t)header[3])[idx] : INT64_MIN;
+}
+
+int64_t hashmap_contains(int64_t map, int64_t key) {
+    int64_t* header = (int64_t*)(intptr_t)map;
+    int found;
+    bmb_hashmap_find_slot((int64_t*)(intptr_t)header[2], (int64_t*)(intptr_t)header[4], header[1], key, &found);
+    return found;
+}
+
+// Returns the removed value, or INT64_MIN when the key is missing
+int64_t hashmap_remove(int64_t map, int64_t key) {
+    int64_t* header = (int64_t*)(intptr_t)map;
+    int64_t* states = (int64_t*)(intptr_t)header[4];
+    int found;
+    int64_t idx = bmb_hashmap_find_slot((int64_t*)(intptr_t)header[2], states, header[1], key, &found);
+    if (!found) return INT64_MIN;
+    states[idx] = 2;
+    header[0]--;
+    return ((int64_t*)(intptr_t)header[3])[idx];
+}
+
+int64_t hashmap_len(int64_t map) {
+    return map ? ((int64_t*)(intptr_t)map)[0] : 0;
+}
+
+void hashmap_free(int64_t map) {
+    if (!map) return;
+    int64_t* header = (int64_t*)(intptr_t)map;
+    free((void*)(intptr_t)header[2]);
+    free((void*)(intptr_t)header[3]);
+    free((void*)(intptr_t)header[4]);
+    free(header);
+}
+
+// String values are stored as BmbString pointers, read back like str_from_id
+void hashmap_insert_str(int64_t map, int64_t key, BmbString* value) {
+    BmbString* copy = value ? bmb_string_new(value->data, value->len) : bmb_string_new("", 0);
+    hashmap_insert(map, key, (int64_t)(intptr_t)copy);
+}
+
+// Returns "" when the key is missing
+BmbString* hashmap_get_str(int64_t map, int64_t key) {
+    int64_t value = hashmap_get(map, key);
+    return value == INT64_MIN ? bmb_string_new("", 0) : str_from_id(value);
+}
+
+// String keys are interned so equal contents map to the same i64 key
+static BmbString** bmb_key_table = NULL;
+static int64_t bmb_key_count = 0;
+static int64_t bmb_key_capacity = 0;
+
+static uint64_t bmb_key_hash(const BmbString* s) {
+    uint64_t h = 0xcbf29ce484222325ULL;
+    for (int64_t i = 0; i < s->len; i++) h = (h ^ (unsigned char)s->data[i]) * 0x100000001b3ULL;
+    return h;
+}
+
+static int64_t bmb_key_slot(BmbString** table, int64_t capacity, const BmbString* s) {
+    int64_t idx = (int64_t)(bmb_key_hash(s) & (uint64_t)(capacity - 1));
+    while (table[idx] && !(table[idx]->len == s->len && memcmp(table[idx]->data, s->data, s->len) == 0)) {
+        idx = (idx + 1) & (capacity - 1);
+    }
+    return idx;
+}
+
+// Key for an interned string, or 0 if `s` was never used as a key
+static int64_t bmb_existing_key(const BmbString* s) {
+    if (!bmb_key_table) return 0;
+    return (int64_t)(intptr_t)bmb_key_table[bmb_key_slot(bmb_key_table, bmb_key_capacity, s)];
+}
+
+static int64_t bmb_intern_key(BmbString* s) {
+    if ((bmb_key_count + 1) * 10 > bmb_key_capacity * 7) {
+        int64_t new_capacity = bmb_key_capacity ? bmb_key_capacity * 2 : 64;
+        BmbString** table = (BmbString**)calloc(new_capacity, sizeof(BmbString*));
+        for (int64_t i = 0; i < bmb_key_capacity; i++) {
+            if (bmb_key_table[i]) table[bmb_key_slot(table, new_capacity, bmb_key_table[i])] = bmb_key_table[i];
+        }
+        free(bmb_key_table);
+        bmb_key_table = table;
+        bmb_key_capacity = new_capacity;
+    }
+    int64_t idx = bmb_key_slot(bmb_key_table, bmb_key_capacity, s);
+    if (!bmb_key_table[idx]) {
+        bmb_key_table[idx] = bmb_string_new(s->data, s->len);
+        bmb_key_count++;
+    }
+    return (int64_t)(intptr_t)bmb_key_table[idx];
+}
+
+int64_t hashmap_insert_str_key(int64_t map, BmbString* key, int64_t value) {
+    if (!key) key = bmb_string_new("", 0);
+    return hashmap_insert(map, bmb_intern_key(key), value);
+}
+
+// Returns the value, or INT64_MIN when the key is missing
+int64_t hashmap_get_str_key(int64_t map, BmbString* key) {
+    int64_t id = key ? bmb_existing_key(key) : 0;
+    return id ? hashmap_get(map, id) : INT64_MIN;
+}
+
+int64_t hashmap_contains_str_key(int64_t map, BmbString* key) {
+    int64_t id = key ? bmb_existing_key(key) : 0;
+    return id ? hashmap_contains(map, id) : 0;
+}
+
+// Vec header layout shared with the inlined vec_* builtins: {data, len, cap}
+void vec_push_str(int64_t vec, BmbString* value) {
+    int64_t* header = (int64_t*)(intptr_t)vec;
+    if (header[1] >= header[2]) {
+        int64_t cap = header[2] ? header[2] * 2 : 4;
+        header[0] = (int64_t)(intptr_t)realloc((void*)(intptr_t)header[0], cap * sizeof(int64_t));
+        header[2] = cap;
+    }
+    BmbString* copy = value ? bmb_string_new(value->data, value->len) : bmb_string_new("", 0);
+    ((int64_t*)(intptr_t)header[0])[header[1]++] = (int64_t)(intptr_t)copy;
+}
+
+BmbString* vec_get_str(int64_t vec, int64_t index) {
+    int64_t* header = (int64_t*)(intptr_t)vec;
+    return str_from_id(((int64_t*)(intptr_t)header[0])[index]);
+}
+
 // ===================================================
 // Command-line Argument Runtime Functions (v0.31.23)
 // Phase 32.3.G: CLI Independence